@@ -0,0 +1,55 @@
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::hayro_syntax::Pdf;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings};
+use hayro_bench::iterations_or_exit;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ITERATIONS: usize = 50;
+
+fn main() {
+    let iterations = iterations_or_exit(DEFAULT_ITERATIONS);
+
+    let path =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../hayro-tests/pdfs/custom/scatter_plot.pdf");
+    let data = std::fs::read(&path).expect("scatter plot fixture should exist");
+    let pdf = Pdf::new(data).expect("scatter plot fixture should load");
+
+    // All pages draw the same scatter plot, but only the last one avoids setting
+    // the same colors again before every marker. With the color cache, all of
+    // them should take roughly the same time.
+    let labels = [
+        "rg/RG before every marker",
+        "scn/SCN before every marker",
+        "colors set only on change",
+    ];
+
+    for (page_idx, label) in labels.iter().enumerate() {
+        let elapsed = bench(&pdf, page_idx, iterations);
+        println!("{label:<28} {elapsed:?}");
+    }
+}
+
+fn bench(pdf: &Pdf, page_idx: usize, iterations: usize) -> Duration {
+    let page = &pdf.pages()[page_idx];
+    let interpreter_settings = InterpreterSettings::default();
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        let pixmap = hayro::render(
+            page,
+            &RenderCache::new(),
+            &interpreter_settings,
+            &render_settings,
+        );
+        assert!(pixmap.width() > 0);
+    }
+
+    start.elapsed() / iterations as u32
+}
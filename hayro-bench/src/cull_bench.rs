@@ -0,0 +1,94 @@
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::hayro_syntax::Pdf;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings};
+use hayro_bench::{iterations_or_exit, single_page_pdf};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ITERATIONS: usize = 20;
+const NUM_LEFTOVERS: usize = 20_000;
+
+fn main() {
+    let iterations = iterations_or_exit(DEFAULT_ITERATIONS);
+
+    let visible = visible_content();
+    let with_leftovers = format!("{}\n{visible}", leftover_content());
+
+    let baseline = bench(&single_page_pdf(&visible), iterations);
+    let leftovers = bench(&single_page_pdf(&with_leftovers), iterations);
+
+    println!("visible content only: {baseline:?}");
+    println!("with {NUM_LEFTOVERS} objects outside of the crop box: {leftovers:?}");
+    println!(
+        "overhead: {:.1}x",
+        leftovers.as_secs_f64() / baseline.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+fn bench(data: &[u8], iterations: usize) -> Duration {
+    let pdf = Pdf::new(data.to_vec()).expect("synthetic document should load");
+    let page = &pdf.pages()[0];
+    let interpreter_settings = InterpreterSettings::default();
+    let render_settings = RenderSettings {
+        x_scale: 2.0,
+        y_scale: 2.0,
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        let pixmap = hayro::render(
+            page,
+            &RenderCache::new(),
+            &interpreter_settings,
+            &render_settings,
+        );
+        assert!(pixmap.width() > 0);
+    }
+
+    start.elapsed() / iterations as u32
+}
+
+/// A few shapes on the page itself.
+fn visible_content() -> String {
+    let mut content = String::new();
+
+    for i in 0..20 {
+        let offset = i as f32 * 25.0;
+        let _ = writeln!(
+            content,
+            "0.2 0.4 0.8 rg {} {} 20 20 re f",
+            50.0 + offset,
+            100.0 + offset
+        );
+    }
+
+    content
+}
+
+/// Stroked and filled curves that are spread around the page, similar to
+/// the leftovers of other artboards in documents exported from design tools.
+fn leftover_content() -> String {
+    let mut content = String::from("0.8 0.2 0.2 rg 0 0 0 RG 2 w\n");
+
+    for i in 0..NUM_LEFTOVERS {
+        let x = 700.0 + (i % 100) as f32 * 40.0;
+        let y = -2000.0 + (i / 100) as f32 * 30.0;
+        let _ = writeln!(
+            content,
+            "{x} {y} m {} {} {} {} {} {y} c {} {} l h B",
+            x + 10.0,
+            y + 30.0,
+            x + 20.0,
+            y - 30.0,
+            x + 30.0,
+            x + 15.0,
+            y + 20.0,
+        );
+    }
+
+    content
+}
@@ -0,0 +1,112 @@
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::hayro_syntax::Pdf;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings};
+use hayro_bench::{iterations_or_exit, stream, write_pdf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ITERATIONS: usize = 20;
+const NUM_PAGES: usize = 10_000;
+const PAGES_PER_NODE: usize = 100;
+const PAGE_INDEX: usize = 3;
+
+fn main() {
+    let iterations = iterations_or_exit(DEFAULT_ITERATIONS);
+
+    let data = Arc::new(synthetic_pdf());
+    println!(
+        "synthetic document: {NUM_PAGES} pages, {} bytes, rendering page {}",
+        data.len(),
+        PAGE_INDEX + 1
+    );
+
+    let full = bench(&data, iterations, |pdf, settings, render_settings| {
+        let page = &pdf.pages()[PAGE_INDEX];
+        hayro::render(page, &RenderCache::new(), settings, render_settings).width()
+    });
+    let lazy = bench(&data, iterations, |pdf, settings, render_settings| {
+        let page = pdf.page(PAGE_INDEX).expect("page should exist");
+        hayro::render(&page, &RenderCache::new(), settings, render_settings).width()
+    });
+
+    println!("open to first pixmap (full page tree): {full:?}");
+    println!("open to first pixmap (single descent): {lazy:?}");
+    println!(
+        "speedup: {:.1}x",
+        full.as_secs_f64() / lazy.as_secs_f64().max(f64::EPSILON)
+    );
+}
+
+fn bench(
+    data: &Arc<Vec<u8>>,
+    iterations: usize,
+    render: impl Fn(&Pdf, &InterpreterSettings, &RenderSettings<'_>) -> u16,
+) -> Duration {
+    let interpreter_settings = InterpreterSettings::default();
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        let pdf = Pdf::new(Arc::clone(data)).expect("synthetic document should load");
+        assert!(render(&pdf, &interpreter_settings, &render_settings) > 0);
+    }
+
+    start.elapsed() / iterations as u32
+}
+
+/// Create a document with a balanced two-level page tree, where all pages share
+/// the same content stream.
+fn synthetic_pdf() -> Vec<u8> {
+    let num_nodes = NUM_PAGES.div_ceil(PAGES_PER_NODE);
+    let first_node = 4;
+    let first_page = first_node + num_nodes;
+    let content = "0.2 0.4 0.8 rg 10 10 80 80 re f";
+
+    let mut objects = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {NUM_PAGES} /MediaBox [0 0 100 100] >>",
+            refs(first_node..first_page)
+        )
+        .into_bytes(),
+        stream("", content.as_bytes()),
+    ];
+
+    for node in 0..num_nodes {
+        let start = node * PAGES_PER_NODE;
+        let end = (start + PAGES_PER_NODE).min(NUM_PAGES);
+
+        objects.push(
+            format!(
+                "<< /Type /Pages /Parent 2 0 R /Kids [{}] /Count {} >>",
+                refs(first_page + start..first_page + end),
+                end - start
+            )
+            .into_bytes(),
+        );
+    }
+
+    for page in 0..NUM_PAGES {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent {} 0 R /Contents 3 0 R >>",
+                first_node + page / PAGES_PER_NODE
+            )
+            .into_bytes(),
+        );
+    }
+
+    write_pdf(&objects)
+}
+
+fn refs(range: std::ops::Range<usize>) -> String {
+    range
+        .map(|id| format!("{id} 0 R"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
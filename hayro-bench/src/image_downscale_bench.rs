@@ -0,0 +1,116 @@
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::hayro_syntax::Pdf;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{ImageResampling, RenderCache, RenderSettings, ResampleFilter};
+use hayro_bench::{iterations_or_exit, stream, write_pdf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ITERATIONS: usize = 10;
+const IMAGE_WIDTH: usize = 4000;
+const IMAGE_HEIGHT: usize = 3000;
+const THUMBNAIL_WIDTH: usize = 200;
+const THUMBNAIL_HEIGHT: usize = 150;
+
+fn main() {
+    let iterations = iterations_or_exit(DEFAULT_ITERATIONS);
+
+    let data = Arc::new(synthetic_pdf());
+    println!(
+        "{IMAGE_WIDTH}x{IMAGE_HEIGHT} image placed at {THUMBNAIL_WIDTH}x{THUMBNAIL_HEIGHT}, {iterations} iterations"
+    );
+
+    let configs = [
+        ("default", ImageResampling::default()),
+        (
+            "catmull-rom",
+            ImageResampling {
+                large_downscale_filter: ResampleFilter::CatmullRom,
+                ..Default::default()
+            },
+        ),
+        (
+            "lanczos3",
+            ImageResampling {
+                filter: ResampleFilter::Lanczos3,
+                large_downscale_filter: ResampleFilter::Lanczos3,
+                ..Default::default()
+            },
+        ),
+        (
+            "box",
+            ImageResampling {
+                filter: ResampleFilter::Box,
+                large_downscale_filter: ResampleFilter::Box,
+                ..Default::default()
+            },
+        ),
+    ];
+
+    for (name, image_resampling) in configs {
+        let duration = bench(&data, iterations, image_resampling);
+        println!("{name:<12} {duration:?}");
+    }
+}
+
+fn bench(data: &Arc<Vec<u8>>, iterations: usize, image_resampling: ImageResampling) -> Duration {
+    let interpreter_settings = InterpreterSettings::default();
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        image_resampling,
+        ..Default::default()
+    };
+    let pdf = Pdf::new(Arc::clone(data)).expect("synthetic document should load");
+    let page = &pdf.pages()[0];
+
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        // Use a fresh cache so that the image is decoded and resized every time.
+        let pixmap = hayro::render(
+            page,
+            &RenderCache::new(),
+            &interpreter_settings,
+            &render_settings,
+        );
+        assert_eq!(pixmap.width() as usize, THUMBNAIL_WIDTH);
+    }
+
+    start.elapsed() / iterations as u32
+}
+
+/// Create a document with a single page containing a large, photo-like
+/// (i.e. smooth gradients with fine detail) image scaled down to a thumbnail.
+fn synthetic_pdf() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(IMAGE_WIDTH * IMAGE_HEIGHT * 3);
+
+    for y in 0..IMAGE_HEIGHT {
+        for x in 0..IMAGE_WIDTH {
+            let detail = ((x * 7 + y * 13) % 32) as u8;
+            pixels.push((x * 255 / IMAGE_WIDTH) as u8 ^ detail);
+            pixels.push((y * 255 / IMAGE_HEIGHT) as u8 ^ detail);
+            pixels.push(((x + y) % 256) as u8);
+        }
+    }
+
+    let content = format!("q {THUMBNAIL_WIDTH} 0 0 {THUMBNAIL_HEIGHT} 0 0 cm /Im0 Do Q");
+    let objects = [
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {THUMBNAIL_WIDTH} {THUMBNAIL_HEIGHT}] \
+             /Contents 4 0 R /Resources << /XObject << /Im0 5 0 R >> >> >>"
+        )
+        .into_bytes(),
+        stream("", content.as_bytes()),
+        stream(
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {IMAGE_WIDTH} /Height {IMAGE_HEIGHT} \
+                 /ColorSpace /DeviceRGB /BitsPerComponent 8"
+            ),
+            &pixels,
+        ),
+    ];
+
+    write_pdf(&objects)
+}
@@ -0,0 +1,91 @@
+//! Helpers that are shared by the benchmark binaries.
+
+use std::env;
+
+/// Parse the `--iterations` argument, falling back to `default` if it isn't given.
+pub fn parse_iterations(default: usize) -> Result<usize, String> {
+    let mut iterations = default;
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" => {
+                let value = args
+                    .next()
+                    .ok_or("--iterations needs a value".to_string())?;
+                iterations = value
+                    .parse()
+                    .map_err(|_| format!("invalid iteration count: {value}"))?;
+            }
+            _ => return Err(format!("unknown argument: {arg}")),
+        }
+    }
+
+    if iterations == 0 {
+        return Err("--iterations must be at least 1".to_string());
+    }
+
+    Ok(iterations)
+}
+
+/// Like [`parse_iterations`], but print the error and exit if the arguments are invalid.
+pub fn iterations_or_exit(default: usize) -> usize {
+    match parse_iterations(default) {
+        Ok(iterations) => iterations,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Create a document with a single letter-sized page with the given content.
+pub fn single_page_pdf(content: &str) -> Vec<u8> {
+    write_pdf(&[
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>".to_vec(),
+        stream("", content.as_bytes()),
+    ])
+}
+
+/// Write a document consisting of the given objects, numbered starting at 1, with
+/// a cross-reference table. The first object must be the catalog.
+pub fn write_pdf(objects: &[Vec<u8>]) -> Vec<u8> {
+    let mut out = b"%PDF-1.7\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        out.extend_from_slice(object);
+        out.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = out.len();
+    out.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+
+    for offset in offsets {
+        out.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+    }
+
+    out.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .as_bytes(),
+    );
+
+    out
+}
+
+/// Create a stream object with the given additional dictionary entries and data.
+pub fn stream(dict: &str, data: &[u8]) -> Vec<u8> {
+    let mut out = format!("<< {dict} /Length {} >>\nstream\n", data.len()).into_bytes();
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\nendstream");
+    out
+}
@@ -0,0 +1,64 @@
+use hayro::hayro_interpret::util::TransformExt;
+use hayro::hayro_interpret::{
+    Context, DummyDevice, InterpreterCache, InterpreterSettings, interpret_page,
+};
+use hayro::hayro_syntax::Pdf;
+use hayro_bench::{iterations_or_exit, single_page_pdf};
+use kurbo::Rect;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+const DEFAULT_ITERATIONS: usize = 20;
+const NUM_GROUPS: usize = 20_000;
+
+fn main() {
+    let iterations = iterations_or_exit(DEFAULT_ITERATIONS);
+
+    let elapsed = bench(&single_page_pdf(&content()), iterations);
+
+    println!("{NUM_GROUPS} nested q/Q groups: {elapsed:?}");
+}
+
+/// Only interpret the page, without rendering anything, so that the
+/// measurement isn't dominated by rasterization.
+fn bench(data: &[u8], iterations: usize) -> Duration {
+    let pdf = Pdf::new(data.to_vec()).expect("synthetic document should load");
+    let page = &pdf.pages()[0];
+    let (width, height) = page.render_dimensions();
+
+    let start = Instant::now();
+
+    for _ in 0..iterations {
+        let cache = InterpreterCache::new();
+        let mut context = Context::new(
+            page.initial_transform(true).to_kurbo(),
+            Rect::new(0.0, 0.0, width as f64, height as f64),
+            &cache,
+            pdf.xref(),
+            InterpreterSettings::default(),
+        );
+        interpret_page(page, &mut context, &mut DummyDevice);
+    }
+
+    start.elapsed() / iterations as u32
+}
+
+/// Small shapes that are each wrapped in nested `q`/`Q` pairs and modify the
+/// graphics state, similar to the output of many PDF producers.
+fn content() -> String {
+    let mut content = String::new();
+
+    for i in 0..NUM_GROUPS {
+        let x = (i % 100) as f32 * 6.0;
+        let y = (i / 100) as f32 * 4.0;
+
+        let _ = writeln!(
+            content,
+            "q q 1 0 0 1 {x} {y} cm 0 0 50 50 re W n {} 0.4 0.8 rg {} w [2 1] 0 d 0 0 4 3 re B Q Q",
+            (i % 10) as f32 / 10.0,
+            (i % 4) as f32 * 0.5,
+        );
+    }
+
+    content
+}
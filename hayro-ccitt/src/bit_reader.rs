@@ -43,6 +43,11 @@ impl<'a> BitReader<'a> {
         self.clone().read_bits(num_bits)
     }
 
+    #[inline(always)]
+    pub(crate) fn rewind(&mut self, num_bits: usize) {
+        self.bit_offset = self.bit_offset.saturating_sub(num_bits);
+    }
+
     #[inline(always)]
     pub(crate) fn align(&mut self) {
         let bit_pos = self.bit_pos();
@@ -104,6 +104,28 @@ impl BitReader<'_> {
         })
     }
 
+    /// Skip all bits up until the next EOL code (T.4 Section 4.1.2), so that it
+    /// is read next. Returns `false` if there is no further EOL code.
+    ///
+    /// Since no valid code contains more than 10 consecutive zeros, this can be
+    /// used to resynchronize after an invalid code was encountered.
+    pub(crate) fn skip_to_eol(&mut self) -> bool {
+        let mut zeros = 0;
+
+        loop {
+            match self.read_bit() {
+                Ok(0) => zeros += 1,
+                Ok(_) if zeros >= 11 => {
+                    self.rewind(12);
+
+                    return true;
+                }
+                Ok(_) => zeros = 0,
+                Err(_) => return false,
+            }
+        }
+    }
+
     /// Read EOL (End-of-Line) codes if present (T.4 Section 4.1.2).
     ///
     /// EOL is defined as `000000000001` (11 zeros followed by a 1).
@@ -84,7 +84,11 @@ pub enum EncodingMode {
 }
 
 /// Settings to apply during decoding.
+///
+/// The default settings correspond to the defaults of the `CCITTFaxDecode` filter
+/// in PDF, except for `rows`, which always needs to be set.
 #[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
 pub struct DecodeSettings {
     /// How many columns the image has (i.e. its width).
     pub columns: u32,
@@ -108,6 +112,42 @@ pub struct DecodeSettings {
     pub encoding: EncodingMode,
     /// Whether black and white should be inverted.
     pub invert_black: bool,
+    /// How many consecutive damaged rows should be tolerated before decoding
+    /// fails.
+    ///
+    /// This only applies to Group 3 images with end-of-line markers. If a row
+    /// contains an invalid code, decoding is resumed at the next end-of-line
+    /// marker, and the rest of the row is filled according to `damaged_row_fill`.
+    /// If this is set to 0, any damaged row causes decoding to fail.
+    pub damaged_rows_before_error: u32,
+    /// How to fill the pixels of a damaged row that couldn't be decoded.
+    pub damaged_row_fill: DamagedRowFill,
+}
+
+impl Default for DecodeSettings {
+    fn default() -> Self {
+        Self {
+            columns: 1728,
+            rows: 0,
+            end_of_block: true,
+            end_of_line: false,
+            rows_are_byte_aligned: false,
+            encoding: EncodingMode::Group3_1D,
+            invert_black: false,
+            damaged_rows_before_error: 0,
+            damaged_row_fill: DamagedRowFill::PreviousRow,
+        }
+    }
+}
+
+/// How to fill the pixels of a damaged row that couldn't be decoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DamagedRowFill {
+    /// Use the pixels of the previous row (or white for the first row).
+    PreviousRow,
+    /// Use white pixels.
+    White,
 }
 
 /// A decoder for CCITT images.
@@ -125,6 +165,12 @@ pub trait Decoder {
     fn push_pixel_chunk(&mut self, white: bool, chunk_count: u32);
     /// Called when a row has been completed.
     fn next_line(&mut self);
+    /// Called right before [`Decoder::next_line`] if the current row was damaged.
+    ///
+    /// The pixels that were decoded before the error was encountered have already
+    /// been pushed, while the rest of the row has been filled according to
+    /// [`DecodeSettings::damaged_row_fill`].
+    fn row_damaged(&mut self) {}
 }
 
 /// Pixel color in a bi-level (black and white) image.
@@ -191,11 +237,18 @@ fn decode_group3_1d(
 ) -> Result<()> {
     // It seems like PDF producers are a bit sloppy with the `end_of_line` flag,
     // so we just always try to read one.
-    let _ = reader.read_eol_if_available();
+    let _ = group3_read_eol(ctx, reader);
 
     loop {
-        decode_1d_line(ctx, reader, decoder)?;
-        ctx.next_line(reader, decoder)?;
+        let row_start = reader.clone();
+
+        if let Err(e) = decode_1d_line(ctx, reader, decoder) {
+            group3_recover(ctx, reader, row_start, decoder, e)?;
+        } else {
+            ctx.consecutive_damaged_rows = 0;
+        }
+
+        ctx.next_line(decoder)?;
 
         if group3_check_eob(ctx, reader) {
             break;
@@ -213,18 +266,25 @@ fn decode_group3_2d(
 ) -> Result<()> {
     // It seems like PDF producers are a bit sloppy with the `end_of_line` flag,
     // so we just always try to read one.
-    let _ = reader.read_eol_if_available();
+    let _ = group3_read_eol(ctx, reader);
 
     loop {
+        let row_start = reader.clone();
         let tag_bit = reader.read_bit()?;
 
-        if tag_bit == 1 {
-            decode_1d_line(ctx, reader, decoder)?;
+        let result = if tag_bit == 1 {
+            decode_1d_line(ctx, reader, decoder)
         } else {
-            decode_2d_line(ctx, reader, decoder)?;
+            decode_2d_line(ctx, reader, decoder)
+        };
+
+        if let Err(e) = result {
+            group3_recover(ctx, reader, row_start, decoder, e)?;
+        } else {
+            ctx.consecutive_damaged_rows = 0;
         }
 
-        ctx.next_line(reader, decoder)?;
+        ctx.next_line(decoder)?;
 
         if group3_check_eob(ctx, reader) {
             break;
@@ -234,9 +294,62 @@ fn decode_group3_2d(
     Ok(())
 }
 
+/// Read the EOL markers in front of the next row, aligning to the byte boundary
+/// if rows are byte-aligned. Returns the number of EOL markers that were read.
+fn group3_read_eol(ctx: &DecoderContext, reader: &mut BitReader<'_>) -> usize {
+    if !ctx.settings.rows_are_byte_aligned {
+        return reader.read_eol_if_available();
+    }
+
+    if ctx.settings.end_of_line {
+        // If rows are byte-aligned and each of them is preceded by an EOL, the
+        // row itself (and not the EOL) needs to start at a byte boundary. Encoders
+        // either insert fill bits in front of the EOL so that it ends at the byte
+        // boundary, or pad the EOL itself, so we need to align after reading it.
+        let eol_count = reader.read_eol_if_available();
+        reader.align();
+
+        eol_count
+    } else {
+        reader.align();
+        reader.read_eol_if_available()
+    }
+}
+
+/// Recover from an error in the current row by resuming at the next EOL marker,
+/// which starts the next row, and filling the rest of the current row.
+///
+/// Returns the original error if too many consecutive rows were damaged or if
+/// no EOL marker follows.
+fn group3_recover<'a>(
+    ctx: &mut DecoderContext,
+    reader: &mut BitReader<'a>,
+    mut row_start: BitReader<'a>,
+    decoder: &mut impl Decoder,
+    error: DecodeError,
+) -> Result<()> {
+    if ctx.consecutive_damaged_rows >= ctx.settings.damaged_rows_before_error {
+        return Err(error);
+    }
+
+    // Search from the start of the row, since parts of the EOL marker might
+    // already have been consumed while trying to decode the row.
+    if !row_start.skip_to_eol() {
+        return Err(error);
+    }
+
+    *reader = row_start;
+    ctx.consecutive_damaged_rows += 1;
+    ctx.damaged_rows += 1;
+    ctx.fill_damaged_row(decoder);
+    decoder.row_damaged();
+
+    Ok(())
+}
+
 /// Check for end-of-block, including RTC (T.4 Section 4.1.4).
 fn group3_check_eob(ctx: &mut DecoderContext, reader: &mut BitReader<'_>) -> bool {
-    let eol_count = reader.read_eol_if_available();
+    let eol_count = group3_read_eol(ctx, reader);
 
     // T.4 Section 4.1.4: "The end of a document transmission is indicated by
     // sending six consecutive EOLs."
@@ -269,7 +382,11 @@ fn decode_group4(
         }
 
         decode_2d_line(ctx, reader, decoder)?;
-        ctx.next_line(reader, decoder)?;
+        ctx.next_line(decoder)?;
+
+        if ctx.settings.rows_are_byte_aligned {
+            reader.align();
+        }
     }
 
     Ok(())
@@ -364,6 +481,10 @@ pub struct DecoderContext {
     settings: DecodeSettings,
     /// Whether to invert black and white.
     invert_black: bool,
+    /// How many rows were damaged so far.
+    damaged_rows: u32,
+    /// How many rows in a row were damaged, up until the current one.
+    consecutive_damaged_rows: u32,
 }
 
 impl DecoderContext {
@@ -381,9 +502,24 @@ impl DecoderContext {
             decoded_rows: 0,
             settings,
             invert_black: settings.invert_black,
+            damaged_rows: 0,
+            consecutive_damaged_rows: 0,
         }
     }
 
+    /// Returns how many rows were decoded by the last call to [`decode`],
+    /// including damaged rows.
+    pub fn decoded_rows(&self) -> u32 {
+        self.decoded_rows
+    }
+
+    /// Returns how many rows were damaged in the last call to [`decode`].
+    ///
+    /// See [`DecodeSettings::damaged_rows_before_error`].
+    pub fn damaged_rows(&self) -> u32 {
+        self.damaged_rows
+    }
+
     fn reset(&mut self) {
         self.ref_changes.clear();
         self.ref_pos = 0;
@@ -394,6 +530,8 @@ impl DecoderContext {
         self.color = Color::White;
         self.decoded_rows = 0;
         self.invert_black = self.settings.invert_black;
+        self.damaged_rows = 0;
+        self.consecutive_damaged_rows = 0;
     }
 
     /// `a0` refers to the first changing element on the current line.
@@ -498,13 +636,41 @@ impl DecoderContext {
         }
     }
 
+    /// Fill the rest of the current row after it was found to be damaged.
+    fn fill_damaged_row(&mut self, decoder: &mut impl Decoder) {
+        while !self.at_eol() {
+            let start = self.pixels_decoded;
+
+            let (color, end) = match self.settings.damaged_row_fill {
+                DamagedRowFill::White => (Color::White, self.line_width),
+                DamagedRowFill::PreviousRow => {
+                    // Find the run of the reference line that contains the
+                    // current position.
+                    let next = self.ref_changes.partition_point(|c| c.idx <= start);
+                    let color = next
+                        .checked_sub(1)
+                        .map_or(Color::White, |i| self.ref_changes[i].color);
+                    let end = self
+                        .ref_changes
+                        .get(next)
+                        .map_or(self.line_width, |c| c.idx.min(self.line_width));
+
+                    (color, end)
+                }
+            };
+
+            self.color = color;
+            self.push_pixels(decoder, end - start);
+        }
+    }
+
     #[inline(always)]
     fn at_eol(&self) -> bool {
         self.a0().unwrap_or(0) == self.line_width
     }
 
     #[inline(always)]
-    fn next_line(&mut self, reader: &mut BitReader<'_>, decoder: &mut impl Decoder) -> Result<()> {
+    fn next_line(&mut self, decoder: &mut impl Decoder) -> Result<()> {
         if self.pixels_decoded != self.settings.columns {
             return Err(DecodeError::LineLengthMismatch);
         }
@@ -517,11 +683,6 @@ impl DecoderContext {
         self.color = Color::White;
         self.decoded_rows += 1;
         decoder.next_line();
-
-        if self.settings.rows_are_byte_aligned {
-            reader.align();
-        }
-
         self.update_b();
 
         Ok(())
@@ -0,0 +1,307 @@
+//! Parsing of command-line arguments.
+
+use std::path::PathBuf;
+
+pub(crate) const USAGE: &str = "\
+Usage: hayro-cli <COMMAND> <INPUT> [OPTIONS]
+
+Commands:
+  render <INPUT> -o <DIR>         Render pages to images
+  text <INPUT>                    Extract the text of pages
+  info <INPUT>                    Print metadata, page sizes and fonts
+  extract-pages <INPUT> -o <FILE> Copy pages into a new PDF
+
+Options:
+  --pages <RANGES>      Pages to process, e.g. `1-5` or `3,7-9,12-` (default: all)
+  --password <PASSWORD> The password of an encrypted document
+  -o, --output <PATH>   The output directory (render) or file (extract-pages)
+  --scale <SCALE>       The scale factor for rendering (default: 1)
+  --format <FORMAT>     The image format for rendering, `png` or `webp` (default: png)
+  --json                Print extracted text as JSON
+  -h, --help            Print this help";
+
+/// A command to run.
+pub(crate) enum Command {
+    Render(RenderArgs),
+    Text(TextArgs),
+    Info(Input),
+    ExtractPages(ExtractPagesArgs),
+    Help,
+}
+
+/// The document to operate on.
+pub(crate) struct Input {
+    pub(crate) path: PathBuf,
+    pub(crate) password: Option<String>,
+}
+
+pub(crate) struct RenderArgs {
+    pub(crate) input: Input,
+    pub(crate) pages: PageSelection,
+    pub(crate) scale: f32,
+    pub(crate) format: ImageFormat,
+    pub(crate) output: PathBuf,
+}
+
+pub(crate) struct TextArgs {
+    pub(crate) input: Input,
+    pub(crate) pages: PageSelection,
+    pub(crate) json: bool,
+}
+
+pub(crate) struct ExtractPagesArgs {
+    pub(crate) input: Input,
+    pub(crate) pages: PageSelection,
+    pub(crate) output: PathBuf,
+}
+
+/// The format of rendered images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImageFormat {
+    Png,
+    Webp,
+}
+
+impl ImageFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Webp => "webp",
+        }
+    }
+}
+
+/// A selection of pages, as given via `--pages`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct PageSelection {
+    /// The selected ranges with 1-based, inclusive bounds. An empty list
+    /// selects all pages.
+    ranges: Vec<(usize, Option<usize>)>,
+}
+
+impl PageSelection {
+    /// Parse a comma-separated list of page numbers and ranges, like `3,7-9,12-`.
+    pub(crate) fn parse(value: &str) -> Result<Self, String> {
+        let parse_number = |s: &str| match s.trim().parse::<usize>() {
+            Ok(0) => Err("page numbers start at 1".to_string()),
+            Ok(n) => Ok(n),
+            Err(_) => Err(format!("invalid page number: `{}`", s.trim())),
+        };
+
+        let mut ranges = vec![];
+
+        for part in value.split(',') {
+            let range = match part.split_once('-') {
+                Some((start, end)) if end.trim().is_empty() => (parse_number(start)?, None),
+                Some((start, end)) => {
+                    let (start, end) = (parse_number(start)?, parse_number(end)?);
+
+                    if end < start {
+                        return Err(format!("invalid page range: `{}`", part.trim()));
+                    }
+
+                    (start, Some(end))
+                }
+                None => {
+                    let page = parse_number(part)?;
+                    (page, Some(page))
+                }
+            };
+
+            ranges.push(range);
+        }
+
+        Ok(Self { ranges })
+    }
+
+    /// Return the 0-based indices of the selected pages, in the order they
+    /// were specified.
+    pub(crate) fn resolve(&self, page_count: usize) -> Result<Vec<usize>, String> {
+        if self.ranges.is_empty() {
+            return Ok((0..page_count).collect());
+        }
+
+        let mut indices = vec![];
+
+        for &(start, end) in &self.ranges {
+            let end = end.unwrap_or(page_count);
+
+            if start > page_count || end > page_count {
+                return Err(format!(
+                    "page {} is out of range, the document has {page_count} pages",
+                    end.max(start)
+                ));
+            }
+
+            indices.extend(start - 1..end);
+        }
+
+        Ok(indices)
+    }
+}
+
+/// Parse the command-line arguments, excluding the name of the binary.
+pub(crate) fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let command = args.next().ok_or("no command given".to_string())?;
+
+    if matches!(command.as_str(), "-h" | "--help" | "help") {
+        return Ok(Command::Help);
+    }
+
+    let allowed: &[&str] = match command.as_str() {
+        "render" => &["--pages", "--password", "--output", "--scale", "--format"],
+        "text" => &["--pages", "--password", "--json"],
+        "info" => &["--password"],
+        "extract-pages" => &["--pages", "--password", "--output"],
+        _ => return Err(format!("unknown command: `{command}`")),
+    };
+
+    let mut path = None;
+    let mut password = None;
+    let mut pages = PageSelection::default();
+    let mut output = None;
+    let mut scale = 1.0;
+    let mut format = ImageFormat::Png;
+    let mut json = false;
+
+    while let Some(arg) = args.next() {
+        let flag = match arg.as_str() {
+            "-o" => "--output",
+            "-h" | "--help" => return Ok(Command::Help),
+            _ => arg.as_str(),
+        };
+
+        if !flag.starts_with('-') {
+            if path.replace(PathBuf::from(&arg)).is_some() {
+                return Err(format!("unexpected argument: `{arg}`"));
+            }
+
+            continue;
+        }
+
+        if !allowed.contains(&flag) {
+            return Err(format!("unknown option for `{command}`: `{arg}`"));
+        }
+
+        if flag == "--json" {
+            json = true;
+            continue;
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("`{arg}` needs a value"))?;
+
+        match flag {
+            "--pages" => pages = PageSelection::parse(&value)?,
+            "--password" => password = Some(value),
+            "--output" => output = Some(PathBuf::from(value)),
+            "--scale" => {
+                scale = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|s| s.is_finite() && *s > 0.0)
+                    .ok_or_else(|| format!("invalid scale: `{value}`"))?;
+            }
+            "--format" => {
+                format = match value.as_str() {
+                    "png" => ImageFormat::Png,
+                    "webp" => ImageFormat::Webp,
+                    _ => return Err(format!("unsupported format: `{value}`")),
+                };
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let input = Input {
+        path: path.ok_or("no input file given".to_string())?,
+        password,
+    };
+    let mut output = || {
+        output
+            .take()
+            .ok_or("no output given (use `-o`)".to_string())
+    };
+
+    Ok(match command.as_str() {
+        "render" => Command::Render(RenderArgs {
+            input,
+            pages,
+            scale,
+            format,
+            output: output()?,
+        }),
+        "text" => Command::Text(TextArgs { input, pages, json }),
+        "info" => Command::Info(input),
+        "extract-pages" => Command::ExtractPages(ExtractPagesArgs {
+            input,
+            pages,
+            output: output()?,
+        }),
+        _ => unreachable!(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn page_selection() {
+        let selection = PageSelection::parse("3,7-9,12-").unwrap();
+
+        assert_eq!(selection.resolve(13).unwrap(), [2, 6, 7, 8, 11, 12]);
+    }
+
+    #[test]
+    fn page_selection_all() {
+        assert_eq!(PageSelection::default().resolve(3).unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn page_selection_invalid() {
+        assert!(PageSelection::parse("0").is_err());
+        assert!(PageSelection::parse("5-3").is_err());
+        assert!(PageSelection::parse("1,,2").is_err());
+        assert!(PageSelection::parse("a-b").is_err());
+        assert!(PageSelection::parse("-3").is_err());
+    }
+
+    #[test]
+    fn page_selection_out_of_range() {
+        assert!(PageSelection::parse("2-4").unwrap().resolve(3).is_err());
+        assert!(PageSelection::parse("4-").unwrap().resolve(3).is_err());
+    }
+
+    #[test]
+    fn parse_render() {
+        let Command::Render(render) = parse(args(
+            "render in.pdf --pages 1-5 --scale 2 --format webp -o out",
+        ))
+        .unwrap() else {
+            panic!("expected render command");
+        };
+
+        assert_eq!(render.input.path, PathBuf::from("in.pdf"));
+        assert_eq!(render.pages, PageSelection::parse("1-5").unwrap());
+        assert_eq!(render.scale, 2.0);
+        assert_eq!(render.format, ImageFormat::Webp);
+        assert_eq!(render.output, PathBuf::from("out"));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse(args("render in.pdf")).is_err());
+        assert!(parse(args("render in.pdf -o out --json")).is_err());
+        assert!(parse(args("info in.pdf other.pdf")).is_err());
+        assert!(parse(args("text in.pdf --pages")).is_err());
+        assert!(parse(args("text in.pdf --scale 2")).is_err());
+        assert!(parse(args("convert in.pdf")).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! The `extract-pages` command.
+
+use crate::args::ExtractPagesArgs;
+use crate::{CommandResult, load_pdf};
+use hayro_write::{
+    ChunkSettings, ExtractionError, ExtractionOptions, ExtractionQuery, extract_with_options,
+};
+use pdf_writer::Ref;
+
+pub(crate) fn run(args: &ExtractPagesArgs) -> CommandResult {
+    let pdf = load_pdf(&args.input)?;
+    let indices = args.pages.resolve(pdf.pages().len())?;
+    let queries = indices
+        .iter()
+        .map(|i| ExtractionQuery::new_page(*i))
+        .collect::<Vec<_>>();
+
+    let mut next_ref = Ref::new(1);
+    let catalog_id = next_ref.bump();
+
+    // Pages that depend on objects that can't be copied are left out, just like
+    // pages that can't be rendered are skipped by the `render` command.
+    let options = ExtractionOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let extracted = extract_with_options(
+        &pdf,
+        Box::new(|| next_ref.bump()),
+        ChunkSettings::default(),
+        |_| {},
+        &queries,
+        &options,
+    )
+    .map_err(|e| format!("failed to extract pages: {e:?}"))?;
+
+    let mut kids = vec![];
+    let mut failed = 0;
+
+    for (index, result) in indices.iter().zip(&extracted.root_refs) {
+        match result {
+            Ok(root_ref) => kids.push(*root_ref),
+            Err(err) => {
+                let reason = match err {
+                    ExtractionError::Incomplete(warning) => format!(
+                        "object {} {} can't be copied",
+                        warning.obj_ref.obj_number, warning.obj_ref.gen_number
+                    ),
+                    _ => format!("{err:?}"),
+                };
+                eprintln!("error: page {}: {reason}", index + 1);
+                failed += 1;
+            }
+        }
+    }
+
+    let mut out = pdf_writer::Pdf::new();
+    out.catalog(catalog_id)
+        .pages(extracted.page_tree_parent_ref);
+    out.pages(extracted.page_tree_parent_ref)
+        .kids(kids.iter().copied())
+        .count(kids.len() as i32);
+    out.extend(&extracted.chunk);
+
+    std::fs::write(&args.output, out.finish())
+        .map_err(|e| format!("failed to write {}: {e}", args.output.display()))?;
+
+    Ok(failed)
+}
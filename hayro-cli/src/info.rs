@@ -0,0 +1,125 @@
+//! The `info` command.
+
+use crate::args::Input;
+use crate::{CommandResult, load_pdf, process_page};
+use hayro::hayro_syntax::PdfVersion;
+use hayro::hayro_syntax::object::dict::keys::{BASE_FONT, SUBTYPE};
+use hayro::hayro_syntax::object::{DateTime, Name};
+use hayro::hayro_syntax::page::Rotation;
+use std::collections::BTreeSet;
+
+pub(crate) fn run(input: &Input) -> CommandResult {
+    let pdf = load_pdf(input)?;
+    let pages = pdf.pages();
+    let metadata = pdf.metadata();
+
+    println!("File:     {}", input.path.display());
+    println!("Version:  {}", version(pdf.version()));
+    println!("Pages:    {}", pages.len());
+
+    let fields = [
+        ("Title", &metadata.title),
+        ("Author", &metadata.author),
+        ("Subject", &metadata.subject),
+        ("Keywords", &metadata.keywords),
+        ("Creator", &metadata.creator),
+        ("Producer", &metadata.producer),
+    ];
+
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("{:<10}{}", format!("{name}:"), value.to_string_lossy());
+        }
+    }
+
+    if let Some(date) = &metadata.creation_date {
+        println!("Created:  {}", format_date(date));
+    }
+
+    if let Some(date) = &metadata.modification_date {
+        println!("Modified: {}", format_date(date));
+    }
+
+    let mut fonts = BTreeSet::new();
+    let mut failed = 0;
+
+    println!();
+
+    for index in 0..pages.len() {
+        let size = process_page(index, || {
+            let page = pages.page(index).ok_or("failed to read page")?;
+            let resources = page.resources();
+
+            for key in resources.fonts.keys() {
+                if let Some(font) = resources.get_font(&key) {
+                    let base_font = font
+                        .get::<Name<'_>>(BASE_FONT)
+                        .map(|n| n.as_str().to_string())
+                        .unwrap_or_else(|| "(unnamed)".to_string());
+                    let subtype = font
+                        .get::<Name<'_>>(SUBTYPE)
+                        .map(|n| n.as_str().to_string())
+                        .unwrap_or_else(|| "Unknown".to_string());
+
+                    fonts.insert((base_font, subtype));
+                }
+            }
+
+            let (width, height) = page.base_dimensions();
+            let rotation = match page.rotation() {
+                Rotation::None => 0,
+                Rotation::Horizontal => 90,
+                Rotation::Flipped => 180,
+                Rotation::FlippedHorizontal => 270,
+            };
+
+            Ok(format!("{width:.2} x {height:.2} pt, rotated {rotation}°"))
+        });
+
+        match size {
+            Some(size) => println!("Page {}: {size}", index + 1),
+            None => failed += 1,
+        }
+    }
+
+    if !fonts.is_empty() {
+        println!();
+        println!("Fonts:");
+
+        for (base_font, subtype) in fonts {
+            println!("  {base_font} ({subtype})");
+        }
+    }
+
+    Ok(failed)
+}
+
+fn version(version: PdfVersion) -> &'static str {
+    match version {
+        PdfVersion::Pdf10 => "1.0",
+        PdfVersion::Pdf11 => "1.1",
+        PdfVersion::Pdf12 => "1.2",
+        PdfVersion::Pdf13 => "1.3",
+        PdfVersion::Pdf14 => "1.4",
+        PdfVersion::Pdf15 => "1.5",
+        PdfVersion::Pdf16 => "1.6",
+        PdfVersion::Pdf17 => "1.7",
+        PdfVersion::Pdf20 => "2.0",
+    }
+}
+
+fn format_date(date: &DateTime) -> String {
+    let sign = if date.utc_offset_hour < 0 { '-' } else { '+' };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {sign}{:02}:{:02}",
+        date.year,
+        date.month,
+        date.day,
+        date.hour,
+        date.minute,
+        date.second,
+        date.utc_offset_hour.unsigned_abs(),
+        date.utc_offset_minute
+    )
+}
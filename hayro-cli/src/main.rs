@@ -0,0 +1,94 @@
+//! A command-line tool for rendering and inspecting PDF files.
+//!
+//! Run `hayro-cli --help` for a list of the supported commands.
+
+mod args;
+mod extract_pages;
+mod info;
+mod render;
+mod text;
+
+use crate::args::{Command, Input, USAGE};
+use hayro::hayro_syntax::{DecryptionError, LoadPdfError, Pdf};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::process::ExitCode;
+
+/// Some pages could not be processed, but the others were.
+const EXIT_PARTIAL_FAILURE: u8 = 1;
+/// The arguments were invalid, or the document could not be read or written.
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    let command = match args::parse(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    let result = match command {
+        Command::Render(args) => render::run(&args),
+        Command::Text(args) => text::run(&args),
+        Command::Info(input) => info::run(&input),
+        Command::ExtractPages(args) => extract_pages::run(&args),
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(0)
+        }
+    };
+
+    match result {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(failed) => {
+            eprintln!("error: {failed} page(s) could not be processed");
+            ExitCode::from(EXIT_PARTIAL_FAILURE)
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// The result of running a command, i.e. the number of pages that failed, or
+/// an error that prevented the command from running at all.
+type CommandResult = Result<usize, String>;
+
+fn load_pdf(input: &Input) -> Result<Pdf, String> {
+    let data = std::fs::read(&input.path)
+        .map_err(|e| format!("failed to read {}: {e}", input.path.display()))?;
+
+    Pdf::new_with_password(data, input.password.as_deref().unwrap_or("")).map_err(|e| {
+        let reason = match e {
+            LoadPdfError::Decryption(DecryptionError::PasswordProtected) => {
+                "the document is password-protected, use `--password`"
+            }
+            LoadPdfError::Decryption(DecryptionError::UnsupportedAlgorithm) => {
+                "the document uses an unsupported encryption algorithm"
+            }
+            LoadPdfError::Decryption(_) => "the document has invalid encryption",
+            LoadPdfError::Invalid => "the document is invalid",
+        };
+
+        format!("failed to load {}: {reason}", input.path.display())
+    })
+}
+
+/// Process a single page, turning panics as well as errors into a message on
+/// stderr so that the remaining pages can still be processed.
+///
+/// Returns `None` if processing failed.
+fn process_page<T>(index: usize, f: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(result)) => Some(result),
+        Ok(Err(err)) => {
+            eprintln!("error: page {}: {err}", index + 1);
+            None
+        }
+        Err(_) => {
+            eprintln!("error: page {}: processing panicked", index + 1);
+            None
+        }
+    }
+}
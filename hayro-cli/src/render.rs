@@ -0,0 +1,82 @@
+//! The `render` command.
+
+use crate::args::{ImageFormat, RenderArgs};
+use crate::{CommandResult, load_pdf, process_page};
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::vello_cpu::Pixmap;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings, render};
+use image::codecs::webp::WebPEncoder;
+use image::{ExtendedColorType, ImageEncoder};
+
+pub(crate) fn run(args: &RenderArgs) -> CommandResult {
+    let pdf = load_pdf(&args.input)?;
+    let pages = pdf.pages();
+    let indices = args.pages.resolve(pages.len())?;
+
+    std::fs::create_dir_all(&args.output)
+        .map_err(|e| format!("failed to create {}: {e}", args.output.display()))?;
+
+    let interpreter_settings = InterpreterSettings::default();
+    let render_settings = RenderSettings {
+        x_scale: args.scale,
+        y_scale: args.scale,
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    // Pad the page numbers so that the files are sorted correctly.
+    let digits = pages.len().to_string().len();
+    let mut failed = 0;
+
+    for index in indices {
+        let path = args.output.join(format!(
+            "page-{:0digits$}.{}",
+            index + 1,
+            args.format.extension()
+        ));
+
+        let success = process_page(index, || {
+            let page = pages.page(index).ok_or("failed to read page")?;
+            let pixmap = render(
+                &page,
+                &RenderCache::new(),
+                &interpreter_settings,
+                &render_settings,
+            );
+            let data = match args.format {
+                ImageFormat::Png => pixmap
+                    .into_png()
+                    .map_err(|_| "failed to encode image".to_string())?,
+                ImageFormat::Webp => to_webp(pixmap)?,
+            };
+
+            std::fs::write(&path, data)
+                .map_err(|e| format!("failed to write {}: {e}", path.display()))
+        });
+
+        if success.is_some() {
+            println!("{}", path.display());
+        } else {
+            failed += 1;
+        }
+    }
+
+    Ok(failed)
+}
+
+fn to_webp(pixmap: Pixmap) -> Result<Vec<u8>, String> {
+    let (width, height) = (pixmap.width() as u32, pixmap.height() as u32);
+    let rgba = pixmap
+        .take_unpremultiplied()
+        .into_iter()
+        .flat_map(|pixel| [pixel.r, pixel.g, pixel.b, pixel.a])
+        .collect::<Vec<_>>();
+
+    let mut out = vec![];
+    WebPEncoder::new_lossless(&mut out)
+        .write_image(&rgba, width, height, ExtendedColorType::Rgba8)
+        .map_err(|_| "failed to encode image".to_string())?;
+
+    Ok(out)
+}
@@ -0,0 +1,106 @@
+//! The `text` command.
+
+use crate::args::TextArgs;
+use crate::{CommandResult, load_pdf, process_page};
+use hayro_interpret::text::TextExtractor;
+use hayro_interpret::{
+    Context, InterpreterCache, InterpreterSettings, TransformExt, interpret_page,
+};
+use std::io::Write;
+
+pub(crate) fn run(args: &TextArgs) -> CommandResult {
+    let pdf = load_pdf(&args.input)?;
+    let pages = pdf.pages();
+    let indices = args.pages.resolve(pages.len())?;
+    let settings = InterpreterSettings::default();
+
+    // Pages are written as soon as they are extracted, so that the output of
+    // large documents can be processed while extraction is still going on.
+    let mut out = std::io::stdout().lock();
+    let write_err = |e: std::io::Error| format!("failed to write output: {e}");
+    let mut failed = 0;
+    let mut first = true;
+
+    if args.json {
+        write!(out, "[").map_err(write_err)?;
+    }
+
+    for index in indices {
+        let lines = process_page(index, || {
+            let page = pages.page(index).ok_or("failed to read page")?;
+            let (width, height) = page.render_dimensions();
+            let cache = InterpreterCache::new();
+            let mut context = Context::new(
+                page.initial_transform(true).to_kurbo(),
+                kurbo::Rect::new(0.0, 0.0, width as f64, height as f64),
+                &cache,
+                pdf.xref(),
+                settings.clone(),
+            );
+            let mut extractor = TextExtractor::new();
+            interpret_page(&page, &mut context, &mut extractor);
+
+            Ok(extractor
+                .lines()
+                .iter()
+                .map(|l| l.logical_order())
+                .collect::<Vec<_>>())
+        });
+
+        let Some(lines) = lines else {
+            failed += 1;
+            continue;
+        };
+
+        if args.json {
+            let separator = if first { "" } else { "," };
+
+            write!(out, "{separator}\n  {}", page_json(index, &lines)).map_err(write_err)?;
+        } else {
+            // Like `pdftotext`, separate pages with a form feed.
+            if !first {
+                write!(out, "\x0c").map_err(write_err)?;
+            }
+
+            for line in lines {
+                writeln!(out, "{line}").map_err(write_err)?;
+            }
+        }
+
+        out.flush().map_err(write_err)?;
+        first = false;
+    }
+
+    if args.json {
+        writeln!(out, "\n]").map_err(write_err)?;
+    }
+
+    Ok(failed)
+}
+
+/// Encode the extracted lines of the page with the given index as a JSON object.
+fn page_json(index: usize, lines: &[String]) -> String {
+    // Serializing a list of strings can't fail.
+    let lines = serde_json::to_string(lines).unwrap();
+
+    format!("{{\"page\": {}, \"lines\": {lines}}}", index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::page_json;
+
+    #[test]
+    fn json_escape() {
+        let lines = ["a\"b\\c".to_string(), "tab\there\u{1}".to_string()];
+
+        assert_eq!(
+            page_json(0, &lines),
+            r#"{"page": 1, "lines": ["a\"b\\c","tab\there\u0001"]}"#
+        );
+        assert_eq!(
+            page_json(1, &["שלום".to_string()]),
+            "{\"page\": 2, \"lines\": [\"שלום\"]}"
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Tests that invoke the `hayro-cli` binary.
+
+use hayro_syntax::Pdf;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// A document with three pages: "Page 1", an empty page and "Page 3".
+const THREE_PAGES: &str = "../hayro-tests/pdfs/custom/blank_page_without_contents.pdf";
+const ENCRYPTED: &str = "../hayro-tests/pdfs/custom/password_encrypted_aes_256.pdf";
+// A single page whose graphics state references an object that doesn't exist.
+const MISSING_OBJECT: &str = "../hayro-tests/pdfs/custom/write_missing_object.pdf";
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hayro-cli"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+#[test]
+fn render() {
+    let dir = temp_dir("render");
+    let output = run(&[
+        "render",
+        THREE_PAGES,
+        "--pages",
+        "1,3",
+        "--scale",
+        "0.5",
+        "-o",
+        dir.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+    assert!(!dir.join("page-2.png").exists());
+
+    for name in ["page-1.png", "page-3.png"] {
+        let image = image::open(dir.join(name)).unwrap();
+        assert_eq!((image.width(), image.height()), (306, 396));
+    }
+}
+
+#[test]
+fn render_webp() {
+    let dir = temp_dir("render_webp");
+    let output = run(&[
+        "render",
+        THREE_PAGES,
+        "--pages",
+        "2",
+        "--format",
+        "webp",
+        "-o",
+        dir.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+
+    let image = image::open(dir.join("page-2.webp")).unwrap();
+    assert_eq!((image.width(), image.height()), (612, 792));
+}
+
+#[test]
+fn render_partial_failure() {
+    let dir = temp_dir("render_partial_failure");
+    // Writing the first page fails because a directory with the same name exists.
+    std::fs::create_dir(dir.join("page-1.png")).unwrap();
+
+    let output = run(&[
+        "render",
+        THREE_PAGES,
+        "--pages",
+        "1,3",
+        "-o",
+        dir.to_str().unwrap(),
+    ]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("error: page 1:"));
+    assert!(dir.join("page-3.png").is_file());
+}
+
+#[test]
+fn text() {
+    let output = run(&["text", THREE_PAGES]);
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "Page 1\n\x0c\x0cPage 3\n");
+}
+
+#[test]
+fn text_json() {
+    let output = run(&["text", THREE_PAGES, "--pages", "3,1", "--json"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "[\n  {\"page\": 3, \"lines\": [\"Page 3\"]},\n  {\"page\": 1, \"lines\": [\"Page 1\"]}\n]\n"
+    );
+}
+
+#[test]
+fn info() {
+    let output = run(&["info", THREE_PAGES]);
+    let stdout = stdout(&output);
+
+    assert!(output.status.success());
+    assert!(stdout.contains("Version:  1.4\n"));
+    assert!(stdout.contains("Pages:    3\n"));
+    assert!(stdout.contains("Page 2: 612.00 x 792.00 pt, rotated 0°\n"));
+    assert!(stdout.contains("Fonts:\n  Helvetica (Type1)\n"));
+}
+
+#[test]
+fn extract_pages() {
+    let dir = temp_dir("extract_pages");
+    let path = dir.join("out.pdf");
+    let output = run(&[
+        "extract-pages",
+        THREE_PAGES,
+        "--pages",
+        "3,1-2",
+        "-o",
+        path.to_str().unwrap(),
+    ]);
+
+    assert!(output.status.success());
+
+    let pdf = Pdf::new(std::fs::read(path).unwrap()).unwrap();
+    assert_eq!(pdf.pages().len(), 3);
+}
+
+#[test]
+fn extract_pages_partial_failure() {
+    let dir = temp_dir("extract_pages_partial_failure");
+    let path = dir.join("out.pdf");
+    let output = run(&[
+        "extract-pages",
+        MISSING_OBJECT,
+        "-o",
+        path.to_str().unwrap(),
+    ]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("error: page 1:"));
+    assert!(path.is_file());
+}
+
+#[test]
+fn password() {
+    let output = run(&["info", ENCRYPTED]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = run(&["info", ENCRYPTED, "--password", "testpw"]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn invalid_page_range() {
+    let output = run(&["text", THREE_PAGES, "--pages", "2-4"]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn invalid_arguments() {
+    assert_eq!(run(&["render", THREE_PAGES]).status.code(), Some(2));
+    assert_eq!(run(&["convert", THREE_PAGES]).status.code(), Some(2));
+}
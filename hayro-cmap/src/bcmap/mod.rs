@@ -7,7 +7,8 @@ pub(crate) mod embedded;
 pub(crate) mod huffman;
 pub(crate) mod reader;
 
-use alloc::boxed::Box;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 pub use embedded::load_embedded;
@@ -50,7 +51,7 @@ const SEGMENT_BF_RANGE_2U: u8 = 0x16;
 
 pub(crate) fn parse<'a>(
     data: &[u8],
-    get_cmap: impl Fn(CMapName<'_>) -> Option<&'a [u8]> + Clone + 'a,
+    get_cmap: impl Fn(CMapName<'_>) -> Option<Cow<'a, [u8]>> + Clone + 'a,
     depth: u32,
 ) -> Option<CMap> {
     // While in theory we can assume that all binary cmaps are valid, it can
@@ -72,7 +73,7 @@ pub(crate) fn parse<'a>(
     let mut cmap_name = None;
     let mut character_collection = None;
     let mut writing_mode = None;
-    let mut base: Option<Box<CMap>> = None;
+    let mut base: Option<Arc<CMap>> = None;
     let mut _codespace_ranges = Vec::new();
     let mut cid_ranges = PartitionedRanges::new();
     let mut notdef_ranges = PartitionedRanges::new();
@@ -109,8 +110,8 @@ pub(crate) fn parse<'a>(
             SEGMENT_USECMAP => {
                 let base_data = get_cmap(CMapName::from_bytes(payload))?;
 
-                base = Some(Box::new(parse::parse_inner(
-                    base_data,
+                base = Some(Arc::new(parse::parse_inner(
+                    &base_data,
                     get_cmap.clone(),
                     depth,
                 )?));
@@ -195,10 +196,12 @@ pub(crate) fn parse<'a>(
             writing_mode,
         },
         _codespace_ranges,
-        cid_ranges,
-        notdef_ranges,
-        bf_entries,
+        cid_ranges: Arc::new(cid_ranges),
+        notdef_ranges: Arc::new(notdef_ranges),
+        bf_entries: bf_entries.into(),
         base,
+        warnings: Arc::new([]),
+        is_identity: false,
     })
 }
 
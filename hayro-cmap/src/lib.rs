@@ -18,9 +18,15 @@ extern crate alloc;
 #[cfg(feature = "embed-cmaps")]
 mod bcmap;
 mod parse;
+mod provider;
 
 #[cfg(feature = "embed-cmaps")]
 pub use bcmap::load_embedded;
+#[cfg(feature = "std")]
+pub use provider::CMapDirectory;
+#[cfg(feature = "embed-cmaps")]
+pub use provider::EmbeddedCMaps;
+pub use provider::{CMapProvider, NoCMaps};
 
 /// Look up an embedded binary cmap by name.
 ///
@@ -30,8 +36,9 @@ pub fn load_embedded(_name: CMapName<'_>) -> Option<&'static [u8]> {
     None
 }
 
-use alloc::boxed::Box;
+use alloc::borrow::Cow;
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 /// A CID (Character Identifier).
@@ -279,6 +286,26 @@ impl<'a> CMapName<'a> {
         }
     }
 
+    /// Create a `CMapType` from raw bytes, tolerating names that don't exactly
+    /// match the predefined ones.
+    ///
+    /// Surrounding whitespace is ignored and the name is compared
+    /// case-insensitively against the predefined cmaps, so that for example
+    /// `identity-h` resolves to [`CMapName::IdentityH`]. If there is no match,
+    /// the trimmed name is returned as [`CMapName::Custom`].
+    pub fn from_bytes_lenient(name: &'a [u8]) -> Self {
+        let name = name.trim_ascii();
+
+        match Self::from_bytes(name) {
+            Self::Custom(_) => PREDEFINED
+                .iter()
+                .find(|p| p.to_bytes().eq_ignore_ascii_case(name))
+                .copied()
+                .unwrap_or(Self::Custom(name)),
+            predefined => predefined,
+        }
+    }
+
     /// Convert the `CMapType` back to its raw byte representation.
     pub fn to_bytes(&self) -> &[u8] {
         match self {
@@ -352,22 +379,108 @@ impl<'a> CMapName<'a> {
     }
 }
 
+/// All predefined cmaps.
+const PREDEFINED: [CMapName<'static>; 65] = [
+    CMapName::N83pvRksjH,
+    CMapName::N90msRksjH,
+    CMapName::N90msRksjV,
+    CMapName::N90mspRksjH,
+    CMapName::N90mspRksjV,
+    CMapName::N90pvRksjH,
+    CMapName::AddRksjH,
+    CMapName::AddRksjV,
+    CMapName::AdobeCns1Ucs2,
+    CMapName::AdobeGb1Ucs2,
+    CMapName::AdobeJapan1Ucs2,
+    CMapName::AdobeKorea1Ucs2,
+    CMapName::B5pcH,
+    CMapName::B5pcV,
+    CMapName::CnsEucH,
+    CMapName::CnsEucV,
+    CMapName::ETenB5H,
+    CMapName::ETenB5V,
+    CMapName::ETenmsB5H,
+    CMapName::ETenmsB5V,
+    CMapName::EucH,
+    CMapName::EucV,
+    CMapName::ExtRksjH,
+    CMapName::ExtRksjV,
+    CMapName::GbEucH,
+    CMapName::GbEucV,
+    CMapName::GbkEucH,
+    CMapName::GbkEucV,
+    CMapName::Gbk2kH,
+    CMapName::Gbk2kV,
+    CMapName::GbkpEucH,
+    CMapName::GbkpEucV,
+    CMapName::GbpcEucH,
+    CMapName::GbpcEucV,
+    CMapName::H,
+    CMapName::HKscsB5H,
+    CMapName::HKscsB5V,
+    CMapName::IdentityH,
+    CMapName::IdentityV,
+    CMapName::KscEucH,
+    CMapName::KscEucV,
+    CMapName::KscmsUhcH,
+    CMapName::KscmsUhcHwH,
+    CMapName::KscmsUhcHwV,
+    CMapName::KscmsUhcV,
+    CMapName::KscpcEucH,
+    CMapName::UniCnsUcs2H,
+    CMapName::UniCnsUcs2V,
+    CMapName::UniCnsUtf16H,
+    CMapName::UniCnsUtf16V,
+    CMapName::UniGbUcs2H,
+    CMapName::UniGbUcs2V,
+    CMapName::UniGbUtf16H,
+    CMapName::UniGbUtf16V,
+    CMapName::UniJisUcs2H,
+    CMapName::UniJisUcs2HwH,
+    CMapName::UniJisUcs2HwV,
+    CMapName::UniJisUcs2V,
+    CMapName::UniJisUtf16H,
+    CMapName::UniJisUtf16V,
+    CMapName::UniKsUcs2H,
+    CMapName::UniKsUcs2V,
+    CMapName::UniKsUtf16H,
+    CMapName::UniKsUtf16V,
+    CMapName::V,
+];
+
 /// Let's limit the number of nested `usecmap` references to 16.
 const MAX_NESTING_DEPTH: u32 = 16;
 
 /// A parsed cmap.
+///
+/// A cmap is immutable after parsing, and all lookups only require a shared
+/// reference, so it is `Send` and `Sync` and can be shared between threads,
+/// for example by wrapping it in an `Arc`. The mapping tables as well as cmaps
+/// referenced via `usecmap` are reference-counted, so cloning a cmap is cheap
+/// and doesn't copy any of the ranges.
 #[derive(Debug, Clone)]
 pub struct CMap {
     metadata: Metadata,
     // Note that we don't actually use this, because Acrobat _seems_ to ignore
     // it, too.
     _codespace_ranges: Vec<CodespaceRange>,
-    cid_ranges: PartitionedRanges,
-    notdef_ranges: PartitionedRanges,
-    bf_entries: Vec<BfRange>,
-    base: Option<Box<Self>>,
+    cid_ranges: Arc<PartitionedRanges>,
+    notdef_ranges: Arc<PartitionedRanges>,
+    bf_entries: Arc<[BfRange]>,
+    base: Option<Arc<Self>>,
+    warnings: Arc<[CMapWarning]>,
+    // Whether this is an Identity-H or Identity-V cmap created via `identity`,
+    // which map each two-byte code to the CID with the same value.
+    is_identity: bool,
 }
 
+// `CMap`s are cached and shared across threads, so make sure they stay
+// thread-safe.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<CMap>();
+};
+
 impl CMap {
     /// Parse a cmap from raw bytes.
     ///
@@ -377,7 +490,13 @@ impl CMap {
         data: &[u8],
         get_cmap: impl Fn(CMapName<'_>) -> Option<&'a [u8]> + Clone + 'a,
     ) -> Option<Self> {
-        parse::parse_inner(data, get_cmap, 0)
+        parse::parse_inner(data, move |name| get_cmap(name).map(Cow::Borrowed), 0)
+    }
+
+    /// Parse a cmap from raw bytes, using the given provider to recursively
+    /// resolve cmaps that are referenced via `usecmap`.
+    pub fn parse_with_provider(data: &[u8], provider: &dyn CMapProvider) -> Option<Self> {
+        parse::parse_inner(data, |name| provider.load_cmap(name), 0)
     }
 
     /// Create an Identity-H cmap.
@@ -401,7 +520,7 @@ impl CMap {
                 writing_mode: Some(writing_mode),
             },
             _codespace_ranges: Vec::new(),
-            cid_ranges: {
+            cid_ranges: Arc::new({
                 let mut r = PartitionedRanges::new();
                 r.push(
                     2,
@@ -414,10 +533,12 @@ impl CMap {
                     },
                 );
                 r
-            },
-            notdef_ranges: PartitionedRanges::new(),
-            bf_entries: Vec::new(),
+            }),
+            notdef_ranges: Arc::new(PartitionedRanges::new()),
+            bf_entries: Arc::new([]),
             base: None,
+            warnings: Arc::new([]),
+            is_identity: true,
         }
     }
 
@@ -426,6 +547,15 @@ impl CMap {
         &self.metadata
     }
 
+    /// Return the problems that were encountered while parsing this cmap.
+    ///
+    /// Malformed entries don't cause parsing to fail, instead they are
+    /// skipped or repaired and recorded here. Warnings of cmaps referenced
+    /// via `usecmap` are not included.
+    pub fn warnings(&self) -> &[CMapWarning] {
+        &self.warnings
+    }
+
     /// Look up the CID code of a character code.
     ///
     /// Returns `None` if the code does not match any range for the given byte length.
@@ -434,6 +564,12 @@ impl CMap {
         // whether the entry exists in the first place. However, from my experiments
         // Acrobat mostly seems to ignore this, so we do that as well.
 
+        // Fast path for identity cmaps, which are very common, so that we don't
+        // need to search the (single) range.
+        if self.is_identity && byte_len == 2 {
+            return (code <= 0xFFFF).then_some(code);
+        }
+
         let cid_ranges = self.cid_ranges.get(byte_len)?;
         let notdef_ranges = self.notdef_ranges.get(byte_len)?;
 
@@ -596,6 +732,19 @@ pub(crate) struct CodespaceRange {
     pub(crate) high: u32,
 }
 
+/// A problem that was encountered while parsing a cmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CMapWarning {
+    /// An entry of a mapping section couldn't be parsed and was skipped.
+    MalformedEntry,
+    /// A code had an odd number of hex digits and was padded on the left.
+    OddHexDigits,
+    /// The start and end codes of a range had different byte lengths, so the
+    /// range was split into one range per byte length.
+    MixedByteLengths,
+}
+
 /// A Unicode value decoded from a cmap.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BfString {
@@ -874,6 +1023,125 @@ endcidrange
         assert_eq!(cmap.lookup_cid_code(0xFF, 1), Some(200 + 127));
     }
 
+    #[test]
+    fn cid_range_mixed_byte_lengths() {
+        let cmap = parse_with_preamble(
+            br#"
+1 begincidrange
+<00> <FFFF> 0
+endcidrange
+"#,
+        );
+
+        assert_eq!(cmap.lookup_cid_code(0x00, 1), Some(0));
+        assert_eq!(cmap.lookup_cid_code(0xFF, 1), Some(0xFF));
+        assert_eq!(cmap.lookup_cid_code(0x0100, 2), Some(0x0100));
+        assert_eq!(cmap.lookup_cid_code(0xFFFF, 2), Some(0xFFFF));
+        assert_eq!(cmap.lookup_cid_code(0x00FF, 2), None);
+        assert_eq!(cmap.warnings(), &[CMapWarning::MixedByteLengths]);
+    }
+
+    #[test]
+    fn odd_hex_digits() {
+        let cmap = parse_with_preamble(
+            br#"
+1 begincidrange
+<020> <07E> 1
+endcidrange
+1 begincidchar
+<1234> 100
+endcidchar
+"#,
+        );
+
+        assert_eq!(cmap.lookup_cid_code(0x0020, 2), Some(1));
+        assert_eq!(cmap.lookup_cid_code(0x007E, 2), Some(95));
+        assert_eq!(cmap.lookup_cid_code(0x1234, 2), Some(100));
+        assert_eq!(
+            cmap.warnings(),
+            &[CMapWarning::OddHexDigits, CMapWarning::OddHexDigits]
+        );
+    }
+
+    #[test]
+    fn odd_hex_digits_bf_char() {
+        let data = br#"
+/CMapName /Adobe-Identity-UCS def
+/CMapType 2 def
+1 begincodespacerange
+<00> <FF>
+endcodespacerange
+2 beginbfchar
+<3> <0033>
+<41> <0041>
+endbfchar
+"#;
+        let cmap = CMap::parse(data, |_| None).unwrap();
+
+        assert_eq!(cmap.lookup_bf_string(0x03), Some(BfString::Char('3')));
+        assert_eq!(cmap.lookup_bf_string(0x41), Some(BfString::Char('A')));
+    }
+
+    #[test]
+    fn malformed_entries_are_skipped() {
+        let cmap = parse_with_preamble(
+            br#"
+4 begincidrange
+<0000> <00FF> 0
+<0100> 512
+<0300> <02FF> 700
+<0400> <04FF> 1024
+endcidrange
+2 begincidchar
+<0500> (x)
+<0600> 1536
+endcidchar
+"#,
+        );
+
+        assert_eq!(cmap.lookup_cid_code(0x0042, 2), Some(0x42));
+        assert_eq!(cmap.lookup_cid_code(0x0410, 2), Some(1040));
+        assert_eq!(cmap.lookup_cid_code(0x0600, 2), Some(1536));
+        assert_eq!(cmap.lookup_cid_code(0x0500, 2), None);
+        assert_eq!(
+            cmap.warnings()
+                .iter()
+                .filter(|w| **w == CMapWarning::MalformedEntry)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn malformed_bf_entries_are_skipped() {
+        let data = br#"
+/CMapName /Adobe-Identity-UCS def
+/CMapType 2 def
+1 begincodespacerange
+<0000> <FFFF>
+endcodespacerange
+3 beginbfrange
+<0010> <0012> [<0041> <0042>]
+<0020> <0010> <0061>
+<0030> <0031> <0030>
+endbfrange
+2 beginbfchar
+<0040> /space
+<0041> <0020>
+endbfchar
+"#;
+        let cmap = CMap::parse(data, |_| None).unwrap();
+
+        assert_eq!(cmap.lookup_bf_string(0x0010), Some(BfString::Char('A')));
+        assert_eq!(cmap.lookup_bf_string(0x0011), Some(BfString::Char('B')));
+        assert_eq!(cmap.lookup_bf_string(0x0012), None);
+        assert_eq!(cmap.lookup_bf_string(0x0020), None);
+        assert_eq!(cmap.lookup_bf_string(0x0031), Some(BfString::Char('1')));
+        assert_eq!(cmap.lookup_bf_string(0x0040), None);
+        assert_eq!(cmap.lookup_bf_string(0x0041), Some(BfString::Char(' ')));
+        assert_eq!(cmap.warnings(), &[CMapWarning::MalformedEntry; 3]);
+    }
+
     #[test]
     fn dict_style_cidsysteminfo() {
         let data = br#"
@@ -1027,6 +1295,100 @@ endcidrange
         assert_eq!(cmap.lookup_cid_code(0x00FF, 2), Some(0xFF));
     }
 
+    const PROVIDED_H: &[u8] = br#"
+/CIDSystemInfo 3 dict dup begin
+  /Registry (Adobe) def
+  /Ordering (Japan1) def
+  /Supplement 0 def
+end def
+/CMapName /H def
+/WMode 0 def
+1 begincodespacerange
+<2121> <7E7E>
+endcodespacerange
+2 begincidrange
+<2121> <217E> 633
+<3021> <307E> 1125
+endcidrange
+"#;
+
+    const PROVIDED_V: &[u8] = br#"
+/CIDSystemInfo 3 dict dup begin
+  /Registry (Adobe) def
+  /Ordering (Japan1) def
+  /Supplement 0 def
+end def
+/CMapName /V def
+/H usecmap
+/WMode 1 def
+1 begincidchar
+<2122> 7887
+endcidchar
+"#;
+
+    struct TestProvider;
+
+    impl CMapProvider for TestProvider {
+        fn load_cmap(&self, name: CMapName<'_>) -> Option<Cow<'static, [u8]>> {
+            match name {
+                CMapName::H => Some(Cow::Owned(PROVIDED_H.to_vec())),
+                CMapName::V => Some(Cow::Borrowed(PROVIDED_V)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn provider_resolves_usecmap() {
+        let data = TestProvider.load_cmap(CMapName::V).unwrap();
+        let cmap = CMap::parse_with_provider(&data, &TestProvider).unwrap();
+
+        assert_eq!(cmap.metadata().writing_mode, Some(WritingMode::Vertical));
+        // Overridden in V.
+        assert_eq!(cmap.lookup_cid_code(0x2122, 2), Some(7887));
+        // Inherited from H.
+        assert_eq!(cmap.lookup_cid_code(0x2121, 2), Some(633));
+        assert_eq!(cmap.lookup_cid_code(0x2123, 2), Some(635));
+        assert_eq!(cmap.lookup_cid_code(0x307E, 2), Some(1218));
+        assert_eq!(cmap.lookup_cid_code(0x4021, 2), None);
+    }
+
+    #[test]
+    fn provider_missing_usecmap() {
+        assert!(CMap::parse_with_provider(PROVIDED_V, &NoCMaps).is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn directory_provider() {
+        let dir = std::env::temp_dir().join(format!("hayro-cmap-{}", std::process::id()));
+        let cmap_dir = dir.join("Adobe-Japan1-7").join("CMap");
+        std::fs::create_dir_all(&cmap_dir).unwrap();
+        std::fs::write(dir.join("V"), PROVIDED_V).unwrap();
+        std::fs::write(cmap_dir.join("H"), PROVIDED_H).unwrap();
+
+        let provider = CMapDirectory::new(&dir);
+        let data = provider.load_cmap(CMapName::V).unwrap();
+        let cmap = CMap::parse_with_provider(&data, &provider).unwrap();
+
+        assert_eq!(cmap.lookup_cid_code(0x2122, 2), Some(7887));
+        assert_eq!(cmap.lookup_cid_code(0x3021, 2), Some(1125));
+        assert!(provider.load_cmap(CMapName::Custom(b"../V")).is_none());
+
+        // The subdirectories were already listed.
+        let new_dir = dir.join("Adobe-GB1-5").join("CMap");
+        std::fs::create_dir_all(&new_dir).unwrap();
+        std::fs::write(new_dir.join("GB-H"), PROVIDED_H).unwrap();
+        assert!(provider.load_cmap(CMapName::from_bytes(b"GB-H")).is_none());
+        assert!(
+            CMapDirectory::new(&dir)
+                .load_cmap(CMapName::from_bytes(b"GB-H"))
+                .is_some()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn notdef_char_lookup() {
         let cmap = parse_with_preamble(
@@ -1179,6 +1541,25 @@ endbfchar
         assert_eq!(cmap.lookup_cid_code(0x0041, 3), None);
     }
 
+    #[test]
+    fn identity_fast_path() {
+        let mut slow = CMap::identity_h();
+        slow.is_identity = false;
+
+        // The identity path must not need the ranges at all.
+        let mut fast = CMap::identity_h();
+        fast.cid_ranges = Arc::new(PartitionedRanges::new());
+
+        for byte_len in 1..=4 {
+            for code in (0..=0x1_0000).chain([0xFF_FFFF, u32::MAX]) {
+                assert_eq!(
+                    fast.lookup_cid_code(code, byte_len),
+                    slow.lookup_cid_code(code, byte_len)
+                );
+            }
+        }
+    }
+
     #[test]
     fn identity_v() {
         let cmap = CMap::identity_v();
@@ -1271,6 +1652,45 @@ endbfrange
             Some(BfString::Char('\u{007F}'))
         );
     }
+
+    #[test]
+    fn predefined_names() {
+        for name in PREDEFINED {
+            assert_eq!(CMapName::from_bytes(name.to_bytes()), name);
+        }
+    }
+
+    #[test]
+    fn lenient_name_lowercase() {
+        assert_eq!(
+            CMapName::from_bytes_lenient(b"identity-h"),
+            CMapName::IdentityH
+        );
+        assert_eq!(
+            CMapName::from_bytes(b"identity-h"),
+            CMapName::Custom(b"identity-h")
+        );
+    }
+
+    #[test]
+    fn lenient_name_whitespace() {
+        assert_eq!(
+            CMapName::from_bytes_lenient(b" Identity-H "),
+            CMapName::IdentityH
+        );
+        assert_eq!(
+            CMapName::from_bytes_lenient(b"\tunijis-utf16-v\n"),
+            CMapName::UniJisUtf16V
+        );
+    }
+
+    #[test]
+    fn lenient_name_custom() {
+        assert_eq!(
+            CMapName::from_bytes_lenient(b" My-Custom-CMap "),
+            CMapName::Custom(b"My-Custom-CMap")
+        );
+    }
 }
 
 #[cfg(all(test, feature = "embed-cmaps"))]
@@ -1575,4 +1995,56 @@ mod bcmap_tests {
         assert_eq!(cmap.lookup_cid_code(0xD040, 2), Some(7094));
         assert_eq!(cmap.lookup_cid_code(0xF9FE, 2), Some(14056 + 0xFE - 0xD6));
     }
+
+    #[test]
+    fn shared_across_threads() {
+        use std::sync::Barrier;
+
+        const NUM_THREADS: usize = 8;
+
+        let data = load_embedded(CMapName::UniJisUcs2H).unwrap();
+        let cmap = Arc::new(CMap::parse(data, get_embedded_cmap).unwrap());
+        let barrier = Barrier::new(NUM_THREADS + 1);
+
+        std::thread::scope(|s| {
+            for _ in 0..NUM_THREADS {
+                s.spawn(|| {
+                    let local = CMap::clone(&cmap);
+
+                    for code in 0x20..0x3000 {
+                        assert_eq!(
+                            local.lookup_cid_code(code, 2),
+                            cmap.lookup_cid_code(code, 2)
+                        );
+                    }
+
+                    assert_eq!(local.lookup_cid_code(0x0041, 2), Some(34));
+
+                    // Keep the clone alive until the main thread checked the counts.
+                    barrier.wait();
+                    barrier.wait();
+                });
+            }
+
+            barrier.wait();
+            // Each thread holds its own clone, but they all share the same ranges.
+            assert_eq!(Arc::strong_count(&cmap.cid_ranges), NUM_THREADS + 1);
+            assert_eq!(Arc::strong_count(&cmap.notdef_ranges), NUM_THREADS + 1);
+            assert_eq!(Arc::strong_count(&cmap.bf_entries), NUM_THREADS + 1);
+            barrier.wait();
+        });
+
+        assert_eq!(Arc::strong_count(&cmap.cid_ranges), 1);
+    }
+
+    #[test]
+    fn clone_shares_base() {
+        let data = load_embedded(CMapName::V).unwrap();
+        let cmap = CMap::parse(data, get_embedded_cmap).unwrap();
+        let clone = cmap.clone();
+
+        // The V cmap references H via `usecmap`.
+        assert_eq!(Arc::strong_count(cmap.base.as_ref().unwrap()), 2);
+        assert_eq!(clone.lookup_cid_code(0x2121, 2), Some(633));
+    }
 }
@@ -1,4 +1,5 @@
-use alloc::boxed::Box;
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 use hayro_postscript::{Object, Scanner};
@@ -6,18 +7,19 @@ use hayro_postscript::{Object, Scanner};
 #[cfg(feature = "embed-cmaps")]
 use crate::bcmap;
 use crate::{
-    BfRange, CMap, CMapName, CharacterCollection, CidFamily, CidRange, CodespaceRange,
+    BfRange, CMap, CMapName, CMapWarning, CharacterCollection, CidFamily, CidRange, CodespaceRange,
     MAX_NESTING_DEPTH, Metadata, PartitionedRanges, Range, WritingMode,
 };
 
 struct Context<F> {
     buf: Vec<u8>,
     get_cmap: F,
+    warnings: Vec<CMapWarning>,
 }
 
 pub(crate) fn parse_inner<'a>(
     data: &[u8],
-    get_cmap: impl Fn(CMapName<'_>) -> Option<&'a [u8]> + Clone + 'a,
+    get_cmap: impl Fn(CMapName<'_>) -> Option<Cow<'a, [u8]>> + Clone + 'a,
     depth: u32,
 ) -> Option<CMap> {
     // Prevent stack overflow for malicious cmap files or circular references.
@@ -35,6 +37,7 @@ pub(crate) fn parse_inner<'a>(
     let mut ctx = Context {
         buf: Vec::new(),
         get_cmap,
+        warnings: Vec::new(),
     };
     let mut _codespace_ranges = Vec::new();
     let mut ranges = PartitionedRanges::new();
@@ -104,8 +107,8 @@ pub(crate) fn parse_inner<'a>(
                 Some("usecmap") => {
                     let nested_data = (ctx.get_cmap)(CMapName::from_bytes(last_name.as_deref()?))?;
 
-                    base = Some(Box::new(parse_inner(
-                        nested_data,
+                    base = Some(Arc::new(parse_inner(
+                        &nested_data,
                         ctx.get_cmap.clone(),
                         depth + 1,
                     )?));
@@ -142,10 +145,12 @@ pub(crate) fn parse_inner<'a>(
     Some(CMap {
         metadata,
         _codespace_ranges,
-        cid_ranges: ranges,
-        notdef_ranges,
-        bf_entries,
+        cid_ranges: Arc::new(ranges),
+        notdef_ranges: Arc::new(notdef_ranges),
+        bf_entries: bf_entries.into(),
         base,
+        warnings: ctx.warnings.into(),
+        is_identity: false,
     })
 }
 
@@ -165,60 +170,177 @@ fn parse_string_or_name(scanner: &mut Scanner<'_>) -> Option<Vec<u8>> {
     }
 }
 
-fn parse_codespace_range<F>(
-    scanner: &mut Scanner<'_>,
-    ranges: &mut Vec<CodespaceRange>,
+/// The kind of object expected at a position of a section entry.
+#[derive(Clone, Copy)]
+enum Expect {
+    String,
+    Number,
+    StringOrArray,
+}
+
+impl Expect {
+    fn matches(self, obj: &Object<'_>) -> bool {
+        matches!(
+            (self, obj),
+            (Self::String | Self::StringOrArray, Object::String(_))
+                | (Self::Number, Object::Number(_))
+                | (Self::StringOrArray, Object::Array(_))
+        )
+    }
+}
+
+/// Parse the entries of a section until `end_marker` is reached.
+///
+/// Each entry consists of objects of the kinds given by `shape`. If an entry
+/// has missing or superfluous objects, objects are dropped until the shape
+/// lines up again. If `parse_entry` fails for an entry, the entry is skipped.
+/// In both cases, a warning is recorded, so that later valid entries still
+/// load.
+fn parse_entries<'a, F, const N: usize>(
+    scanner: &mut Scanner<'a>,
     ctx: &mut Context<F>,
+    end_marker: &str,
+    shape: [Expect; N],
+    mut parse_entry: impl FnMut(&[Object<'a>; N], &mut Context<F>) -> Option<()>,
 ) -> Option<()> {
+    let mut window = Vec::with_capacity(N);
+    let mut resyncing = false;
+
     loop {
-        let obj = scanner.parse_object().ok()?;
+        while window.len() < N {
+            let obj = scanner.parse_object().ok()?;
 
-        if name_matches(&obj, "endcodespacerange") {
-            return Some(());
+            if name_matches(&obj, end_marker) {
+                if !window.is_empty() && !resyncing {
+                    ctx.warnings.push(CMapWarning::MalformedEntry);
+                }
+
+                return Some(());
+            }
+
+            window.push(obj);
         }
 
-        let low = extract_u32_code(&obj, &mut ctx.buf)?;
-        let n_bytes = u8::try_from(ctx.buf.len()).ok()?;
-        let high = read_u32_code(scanner, &mut ctx.buf)?;
+        if !window.iter().zip(shape).all(|(obj, e)| e.matches(obj)) {
+            if !resyncing {
+                ctx.warnings.push(CMapWarning::MalformedEntry);
+                resyncing = true;
+            }
 
-        if ctx.buf.len() != usize::from(n_bytes) {
-            return None;
+            window.remove(0);
+            continue;
         }
 
-        ranges.push(CodespaceRange {
-            number_bytes: n_bytes,
-            low,
-            high,
-        });
+        resyncing = false;
+
+        if parse_entry(window.as_slice().try_into().ok()?, ctx).is_none() {
+            ctx.warnings.push(CMapWarning::MalformedEntry);
+        }
+
+        window.clear();
     }
 }
 
+fn parse_codespace_range<F>(
+    scanner: &mut Scanner<'_>,
+    ranges: &mut Vec<CodespaceRange>,
+    ctx: &mut Context<F>,
+) -> Option<()> {
+    parse_entries(
+        scanner,
+        ctx,
+        "endcodespacerange",
+        [Expect::String, Expect::String],
+        |[low, high], ctx| {
+            let low = extract_code(low, ctx)?;
+            let high = extract_code(high, ctx)?;
+
+            if low.byte_len != high.byte_len {
+                return None;
+            }
+
+            ranges.push(CodespaceRange {
+                number_bytes: u8::try_from(low.byte_len).ok()?,
+                low: low.value,
+                high: high.value,
+            });
+
+            Some(())
+        },
+    )
+}
+
 fn parse_range<F>(
     scanner: &mut Scanner<'_>,
     ranges: &mut PartitionedRanges,
     ctx: &mut Context<F>,
     end_marker: &str,
 ) -> Option<()> {
-    loop {
-        let obj = scanner.parse_object().ok()?;
+    parse_entries(
+        scanner,
+        ctx,
+        end_marker,
+        [Expect::String, Expect::String, Expect::Number],
+        |[start, end, cid_start], ctx| {
+            let start = extract_code(start, ctx)?;
+            let end = extract_code(end, ctx)?;
+            let cid_start = extract_cid(cid_start)?;
+
+            if start.byte_len > end.byte_len || start.value > end.value {
+                return None;
+            }
 
-        if name_matches(&obj, end_marker) {
-            return Some(());
-        }
+            if start.byte_len == end.byte_len {
+                ranges.push(
+                    start.byte_len,
+                    CidRange {
+                        range: Range {
+                            start: start.value,
+                            end: end.value,
+                        },
+                        cid_start,
+                    },
+                );
 
-        let start = extract_u32_code(&obj, &mut ctx.buf)?;
-        let byte_len = ctx.buf.len();
-        let end = read_u32_code(scanner, &mut ctx.buf)?;
-        let cid_start = u32::try_from(scanner.parse_number().ok()?.as_i32()).ok()?;
-
-        ranges.push(
-            byte_len,
-            CidRange {
-                range: Range { start, end },
-                cid_start,
-            },
-        );
-    }
+                return Some(());
+            }
+
+            // Some cmaps contain ranges like `<00> <FFFF>`, where the start and end
+            // codes have different byte lengths. Split them into one range per byte
+            // length, i.e. `<00> <FF>` and `<0100> <FFFF>`.
+            ctx.warnings.push(CMapWarning::MixedByteLengths);
+
+            for byte_len in start.byte_len..=end.byte_len {
+                let sub_start = if byte_len == start.byte_len {
+                    start.value
+                } else {
+                    1 << (8 * (byte_len - 1))
+                };
+                let sub_end = if byte_len == end.byte_len {
+                    end.value
+                } else {
+                    u32::MAX >> (8 * (4 - byte_len))
+                };
+
+                if sub_start > sub_end {
+                    continue;
+                }
+
+                ranges.push(
+                    byte_len,
+                    CidRange {
+                        range: Range {
+                            start: sub_start,
+                            end: sub_end,
+                        },
+                        cid_start: cid_start.checked_add(sub_start - start.value)?,
+                    },
+                );
+            }
+
+            Some(())
+        },
+    )
 }
 
 fn parse_char<F>(
@@ -227,28 +349,29 @@ fn parse_char<F>(
     ctx: &mut Context<F>,
     end_marker: &str,
 ) -> Option<()> {
-    loop {
-        let obj = scanner.parse_object().ok()?;
-
-        if name_matches(&obj, end_marker) {
-            return Some(());
-        }
-
-        let code = extract_u32_code(&obj, &mut ctx.buf)?;
-        let byte_len = ctx.buf.len();
-        let cid_start = u32::try_from(scanner.parse_number().ok()?.as_i32()).ok()?;
-
-        ranges.push(
-            byte_len,
-            CidRange {
-                range: Range {
-                    start: code,
-                    end: code,
+    parse_entries(
+        scanner,
+        ctx,
+        end_marker,
+        [Expect::String, Expect::Number],
+        |[code, cid_start], ctx| {
+            let code = extract_code(code, ctx)?;
+            let cid_start = extract_cid(cid_start)?;
+
+            ranges.push(
+                code.byte_len,
+                CidRange {
+                    range: Range {
+                        start: code.value,
+                        end: code.value,
+                    },
+                    cid_start,
                 },
-                cid_start,
-            },
-        );
-    }
+            );
+
+            Some(())
+        },
+    )
 }
 
 fn parse_bf_char<F>(
@@ -256,25 +379,29 @@ fn parse_bf_char<F>(
     entries: &mut Vec<BfRange>,
     ctx: &mut Context<F>,
 ) -> Option<()> {
-    loop {
-        let obj = scanner.parse_object().ok()?;
-
-        if name_matches(&obj, "endbfchar") {
-            return Some(());
-        }
+    parse_entries(
+        scanner,
+        ctx,
+        "endbfchar",
+        [Expect::String, Expect::String],
+        |[code, dst], ctx| {
+            let code = extract_code(code, ctx)?.value;
+            let Object::String(dst) = dst else {
+                return None;
+            };
+            dst.decode_into(&mut ctx.buf).ok()?;
+
+            entries.push(BfRange {
+                range: Range {
+                    start: code,
+                    end: code,
+                },
+                dst_base: decode_be(&ctx.buf)?,
+            });
 
-        let code = extract_u32_code(&obj, &mut ctx.buf)?;
-        let dst = scanner.parse_string().ok()?;
-        dst.decode_into(&mut ctx.buf).ok()?;
-
-        entries.push(BfRange {
-            range: Range {
-                start: code,
-                end: code,
-            },
-            dst_base: decode_be(&ctx.buf)?,
-        });
-    }
+            Some(())
+        },
+    )
 }
 
 fn parse_bf_range<F>(
@@ -282,46 +409,50 @@ fn parse_bf_range<F>(
     entries: &mut Vec<BfRange>,
     ctx: &mut Context<F>,
 ) -> Option<()> {
-    loop {
-        let obj = scanner.parse_object().ok()?;
-
-        if name_matches(&obj, "endbfrange") {
-            return Some(());
-        }
-
-        let start = extract_u32_code(&obj, &mut ctx.buf)?;
-        let end = read_u32_code(scanner, &mut ctx.buf)?;
-
-        let next = scanner.parse_object().ok()?;
-
-        match &next {
-            Object::String(s) => {
-                s.decode_into(&mut ctx.buf).ok()?;
-
-                entries.push(BfRange {
-                    range: Range { start, end },
-                    dst_base: decode_be(&ctx.buf)?,
-                });
+    parse_entries(
+        scanner,
+        ctx,
+        "endbfrange",
+        [Expect::String, Expect::String, Expect::StringOrArray],
+        |[start, end, dst], ctx| {
+            let start = extract_code(start, ctx)?.value;
+            let end = extract_code(end, ctx)?.value;
+
+            if start > end {
+                return None;
             }
-            Object::Array(array) => {
-                let mut array_scanner = array.objects();
 
-                for code in start..=end {
-                    let s = array_scanner.parse_string().ok()?;
+            match dst {
+                Object::String(s) => {
                     s.decode_into(&mut ctx.buf).ok()?;
 
                     entries.push(BfRange {
-                        range: Range {
-                            start: code,
-                            end: code,
-                        },
+                        range: Range { start, end },
                         dst_base: decode_be(&ctx.buf)?,
                     });
                 }
+                Object::Array(array) => {
+                    let mut array_scanner = array.objects();
+
+                    for code in start..=end {
+                        let s = array_scanner.parse_string().ok()?;
+                        s.decode_into(&mut ctx.buf).ok()?;
+
+                        entries.push(BfRange {
+                            range: Range {
+                                start: code,
+                                end: code,
+                            },
+                            dst_base: decode_be(&ctx.buf)?,
+                        });
+                    }
+                }
+                _ => return None,
             }
-            _ => return None,
-        }
-    }
+
+            Some(())
+        },
+    )
 }
 
 /// Convert the buffer into native-endian u16, so that we can use `String::from_utf16`.
@@ -349,18 +480,34 @@ fn decode_be(bytes: &[u8]) -> Option<Vec<u16>> {
     Some(out)
 }
 
-#[inline]
-fn read_u32_code(scanner: &mut Scanner<'_>, buf: &mut Vec<u8>) -> Option<u32> {
-    let s = scanner.parse_string().ok()?;
-    s.decode_into(buf).ok()?;
-    bytes_to_u32(buf)
+/// A character code together with the number of bytes it was written with.
+struct Code {
+    value: u32,
+    byte_len: usize,
 }
 
-#[inline]
-fn extract_u32_code(obj: &Object<'_>, buf: &mut Vec<u8>) -> Option<u32> {
+fn extract_code<F>(obj: &Object<'_>, ctx: &mut Context<F>) -> Option<Code> {
     let Object::String(s) = obj else { return None };
-    s.decode_into(buf).ok()?;
-    bytes_to_u32(buf)
+    s.decode_into(&mut ctx.buf).ok()?;
+    let mut value = bytes_to_u32(&ctx.buf)?;
+
+    // Hex strings with an odd number of digits are padded with a zero on the
+    // right when decoding, but Acrobat pads codes on the left instead, i.e.
+    // `<123>` is treated as `<0123>`.
+    if s.hex_digits().is_some_and(|n| n % 2 == 1) {
+        ctx.warnings.push(CMapWarning::OddHexDigits);
+        value >>= 4;
+    }
+
+    Some(Code {
+        value,
+        byte_len: ctx.buf.len(),
+    })
+}
+
+fn extract_cid(obj: &Object<'_>) -> Option<u32> {
+    let Object::Number(n) = obj else { return None };
+    u32::try_from(n.as_i32()).ok()
 }
 
 #[inline]
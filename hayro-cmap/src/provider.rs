@@ -0,0 +1,114 @@
+use alloc::borrow::Cow;
+
+use crate::CMapName;
+
+/// A source for the data of cmaps that are referenced by name.
+///
+/// A provider is used for loading predefined cmaps that are not embedded in
+/// the PDF, as well as for resolving cmaps that are referenced via `usecmap`.
+/// The returned data can either be in the textual cmap format, or, if the
+/// `embed-cmaps` feature is enabled, in the binary format used by
+/// [`EmbeddedCMaps`].
+///
+/// The trait is implemented for closures with the signature
+/// `Fn(CMapName<'_>) -> Option<&'static [u8]>`, so a plain function like
+/// [`load_embedded`](crate::load_embedded) can be used as a provider, too.
+pub trait CMapProvider: Send + Sync {
+    /// Load the data of the cmap with the given name.
+    fn load_cmap(&self, name: CMapName<'_>) -> Option<Cow<'static, [u8]>>;
+}
+
+impl<F> CMapProvider for F
+where
+    F: Fn(CMapName<'_>) -> Option<&'static [u8]> + Send + Sync,
+{
+    fn load_cmap(&self, name: CMapName<'_>) -> Option<Cow<'static, [u8]>> {
+        self(name).map(Cow::Borrowed)
+    }
+}
+
+/// A provider that never returns any cmap.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoCMaps;
+
+impl CMapProvider for NoCMaps {
+    fn load_cmap(&self, _: CMapName<'_>) -> Option<Cow<'static, [u8]>> {
+        None
+    }
+}
+
+/// A provider for the 61 predefined cmaps that are embedded into the binary.
+#[cfg(feature = "embed-cmaps")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EmbeddedCMaps;
+
+#[cfg(feature = "embed-cmaps")]
+impl CMapProvider for EmbeddedCMaps {
+    fn load_cmap(&self, name: CMapName<'_>) -> Option<Cow<'static, [u8]>> {
+        crate::load_embedded(name).map(Cow::Borrowed)
+    }
+}
+
+/// A provider that reads cmap files from a directory.
+///
+/// The files are looked up by their name, either directly in the given
+/// directory or in the `CMap` subdirectory of one of its children. The latter
+/// matches the layout of the [Adobe cmap resources](https://github.com/adobe-type-tools/cmap-resources),
+/// so you can simply pass the path to a checkout of that repository.
+///
+/// The subdirectories are only listed once, so subdirectories that are added
+/// afterwards are not taken into account.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct CMapDirectory {
+    path: std::path::PathBuf,
+    // The `CMap` subdirectories of the children of the directory, which are
+    // only listed once.
+    subdirectories: std::sync::OnceLock<Vec<std::path::PathBuf>>,
+}
+
+#[cfg(feature = "std")]
+impl CMapDirectory {
+    /// Create a new provider that reads cmap files from the given directory.
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            subdirectories: std::sync::OnceLock::new(),
+        }
+    }
+
+    fn subdirectories(&self) -> &[std::path::PathBuf] {
+        self.subdirectories.get_or_init(|| {
+            std::fs::read_dir(&self.path)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|entry| entry.path().join("CMap"))
+                        .filter(|path| path.is_dir())
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl CMapProvider for CMapDirectory {
+    fn load_cmap(&self, name: CMapName<'_>) -> Option<Cow<'static, [u8]>> {
+        let file_name = core::str::from_utf8(name.to_bytes()).ok()?;
+
+        // Don't allow escaping from the directory via custom names.
+        if file_name.is_empty() || file_name.contains(['/', '\\']) || file_name.starts_with('.') {
+            return None;
+        }
+
+        if let Ok(data) = std::fs::read(self.path.join(file_name)) {
+            return Some(Cow::Owned(data));
+        }
+
+        self.subdirectories()
+            .iter()
+            .find_map(|dir| std::fs::read(dir.join(file_name)).ok())
+            .map(Cow::Owned)
+    }
+}
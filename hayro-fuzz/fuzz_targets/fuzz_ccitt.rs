@@ -45,15 +45,14 @@ fuzz_target!(|data: &[u8]| {
     };
     let invert_black = data[9] != 0;
 
-    let settings = hayro_ccitt::DecodeSettings {
-        columns,
-        rows,
-        end_of_block,
-        end_of_line,
-        rows_are_byte_aligned,
-        encoding,
-        invert_black,
-    };
+    let mut settings = hayro_ccitt::DecodeSettings::default();
+    settings.columns = columns;
+    settings.rows = rows;
+    settings.end_of_block = end_of_block;
+    settings.end_of_line = end_of_line;
+    settings.rows_are_byte_aligned = rows_are_byte_aligned;
+    settings.encoding = encoding;
+    settings.invert_black = invert_black;
 
     let mut decoder = Decoder;
     let mut context = hayro_ccitt::DecoderContext::new(settings);
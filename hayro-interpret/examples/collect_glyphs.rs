@@ -0,0 +1,106 @@
+//! This example demonstrates how you can collect the outlines of all glyphs on a page,
+//! for example to rasterize them with your own (GPU-based) renderer.
+//!
+//! Each glyph is recorded as a triple of its outline in glyph space, the transform
+//! from glyph space to device space and the paint it is filled with.
+
+use hayro_interpret::font::Glyph;
+use hayro_interpret::util::TransformExt;
+use hayro_interpret::{
+    BlendMode, CacheKey, ClipPath, Context, Device, DrawMode, DrawProps, Image, ImageDrawProps,
+    InterpreterCache, InterpreterSettings, Paint, SoftMask, interpret_page,
+};
+use hayro_syntax::Pdf;
+use kurbo::{Affine, BezPath, Rect, Shape};
+use rustc_hash::FxHashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+fn main() {
+    let data = std::fs::read(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../hayro-tests/pdfs/custom/text_rendering_1.pdf"),
+    )
+    .unwrap();
+
+    let pdf = Pdf::new(data).unwrap();
+    let page = &pdf.pages()[0];
+
+    // Use the same initial transform as `hayro` does when rendering, so that
+    // the origin is at the top-left corner of the page and one unit corresponds
+    // to one pixel at a scale of 1.0.
+    let (width, height) = page.render_dimensions();
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        page.initial_transform(true).to_kurbo(),
+        Rect::new(0.0, 0.0, width as f64, height as f64),
+        &cache,
+        pdf.xref(),
+        InterpreterSettings::default(),
+    );
+
+    let mut collector = GlyphCollector::default();
+    interpret_page(page, &mut context, &mut collector);
+
+    for (outline, transform, paint) in &collector.glyphs {
+        let bbox = (*transform * outline.as_ref().clone()).bounding_box();
+        let color = match paint {
+            Paint::Color(c) => format!("{:?}", c.to_rgba().to_rgba8()),
+            Paint::Pattern(_) => "pattern".to_string(),
+        };
+
+        println!("glyph at {bbox:?}, painted with {color}");
+    }
+}
+
+#[derive(Default)]
+struct GlyphCollector<'a> {
+    glyphs: Vec<(Rc<BezPath>, Affine, Paint<'a>)>,
+    // The same glyph usually appears many times on a page, so only compute
+    // its outline once.
+    outlines: FxHashMap<u128, Rc<BezPath>>,
+}
+
+impl<'a> Device<'a> for GlyphCollector<'a> {
+    fn draw_path(&mut self, _: &BezPath, _: DrawProps<'a>, _: &DrawMode) {}
+
+    fn push_clip_path(&mut self, _: &ClipPath) {}
+
+    fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'a>>, _: BlendMode) {}
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'a>,
+        glyph_transform: Affine,
+        props: DrawProps<'a>,
+        draw_mode: &DrawMode,
+    ) {
+        // We only collect filled glyphs here. Stroked glyphs would additionally
+        // need the stroke properties that are stored in the draw mode.
+        if !matches!(draw_mode, DrawMode::Fill(_) | DrawMode::FillAndStroke(..)) {
+            return;
+        }
+
+        match glyph {
+            Glyph::Outline(o) => {
+                let outline = self
+                    .outlines
+                    .entry(o.identifier().cache_key())
+                    .or_insert_with(|| Rc::new(o.outline()))
+                    .clone();
+
+                self.glyphs
+                    .push((outline, props.transform * glyph_transform, props.paint));
+            }
+            // Type3 glyphs are defined by PDF drawing instructions, so we would need to
+            // interpret them with another device using `Type3Glyph::interpret`.
+            Glyph::Type3(_) => {}
+        }
+    }
+
+    fn draw_image(&mut self, _: Image<'a, '_>, _: ImageDrawProps<'a>) {}
+
+    fn pop_clip(&mut self) {}
+
+    fn pop_transparency_group(&mut self) {}
+}
@@ -0,0 +1,189 @@
+//! Computing the extent of the visible contents of a page without rendering it.
+
+use crate::color::AlphaColor;
+use crate::font::Glyph;
+use crate::util::TransformExt;
+use crate::{
+    BlendMode, ClipPath, Context, Device, DrawMode, DrawProps, Image, ImageDrawProps,
+    InterpreterCache, InterpreterSettings, Paint, SoftMask, interpret_page,
+};
+use hayro_syntax::page::Page;
+use kurbo::{Affine, BezPath, Rect, Shape};
+
+/// The maximum difference of each color component to the background color for a
+/// paint to be considered invisible.
+const BACKGROUND_TOLERANCE: f32 = 2.0 / 255.0;
+
+/// Compute the bounding box of the visible contents of the given page.
+///
+/// The bounding box is the union of the bounding boxes of all filled and stroked
+/// paths, glyphs and images, each of them intersected with the bounding boxes of
+/// the active clip paths and the crop box of the page. It is approximate in the
+/// sense that strokes are only outset by half of their line width, regardless of
+/// their line caps and joins, and that transparency groups and soft masks are ignored.
+///
+/// If `background` is given, paths, glyphs and image masks painted with a color
+/// that is almost the same as the background color are skipped, so that for example
+/// white rectangles on a white page don't count as content.
+///
+/// The bounding box is returned in the default user space of the page, i.e. the
+/// coordinate space of its `/CropBox` and `/MediaBox` entries, so that it can
+/// directly be used as a new crop box. Returns `None` if nothing is drawn on the page.
+pub fn content_bbox<'a>(
+    page: &Page<'a>,
+    cache: &InterpreterCache<'a>,
+    settings: InterpreterSettings,
+    background: Option<AlphaColor>,
+) -> Option<Rect> {
+    let (width, height) = page.render_dimensions();
+    let page_rect = Rect::new(0.0, 0.0, width as f64, height as f64);
+    let initial_transform = page.initial_transform(true).to_kurbo();
+    let mut context = Context::new(initial_transform, page_rect, cache, page.xref(), settings);
+
+    let mut device = BboxDevice {
+        bbox: None,
+        clips: vec![page_rect],
+        background,
+    };
+    interpret_page(page, &mut context, &mut device);
+
+    device
+        .bbox
+        .map(|bbox| initial_transform.inverse().transform_rect_bbox(bbox))
+}
+
+struct BboxDevice {
+    // The bounding box in device space.
+    bbox: Option<Rect>,
+    // The bounding boxes of the active clip paths, intersected with each other.
+    clips: Vec<Rect>,
+    background: Option<AlphaColor>,
+}
+
+impl BboxDevice {
+    fn add(&mut self, rect: Rect) {
+        let clip = self.clips.last().copied().unwrap_or(Rect::ZERO);
+        let rect = rect.intersect(clip);
+
+        if rect.is_zero_area() {
+            return;
+        }
+
+        self.bbox = Some(self.bbox.map_or(rect, |bbox| bbox.union(rect)));
+    }
+
+    fn is_invisible(&self, paint: &Paint<'_>) -> bool {
+        let Paint::Color(color) = paint else {
+            return false;
+        };
+
+        let color = color.to_rgba().components();
+
+        if color[3] == 0.0 {
+            return true;
+        }
+
+        self.background.is_some_and(|background| {
+            let background = background.components();
+
+            color
+                .iter()
+                .zip(background)
+                .take(3)
+                .all(|(c, b)| (c - b).abs() <= BACKGROUND_TOLERANCE)
+        })
+    }
+}
+
+/// Return the device-space bounding box of a path that is drawn with the given
+/// transform and draw mode.
+fn path_bbox(path: BezPath, transform: Affine, draw_mode: &DrawMode) -> Option<Rect> {
+    let bbox = (transform * path).bounding_box();
+
+    match draw_mode {
+        DrawMode::Fill(_) => Some(bbox),
+        DrawMode::Stroke(stroke) | DrawMode::FillAndStroke(_, stroke) => {
+            // A circle with the radius `r` is transformed into an ellipse that
+            // extends `r * sqrt(a^2 + c^2)` horizontally and `r * sqrt(b^2 + d^2)`
+            // vertically.
+            let [a, b, c, d, _, _] = transform.as_coeffs();
+            let r = stroke.line_width as f64 / 2.0;
+
+            Some(bbox.inflate(r * a.hypot(c), r * b.hypot(d)))
+        }
+        DrawMode::Invisible => None,
+    }
+}
+
+impl<'a> Device<'a> for BboxDevice {
+    fn draw_path(&mut self, path: &BezPath, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        if self.is_invisible(&props.paint) {
+            return;
+        }
+
+        if let Some(bbox) = path_bbox(path.clone(), props.transform, draw_mode) {
+            self.add(bbox);
+        }
+    }
+
+    fn push_clip_path(&mut self, clip_path: &ClipPath) {
+        let clip = self.clips.last().copied().unwrap_or(Rect::ZERO);
+        self.clips
+            .push(clip.intersect(clip_path.path.bounding_box()));
+    }
+
+    fn push_clip_rect(&mut self, rect: &Rect) {
+        let clip = self.clips.last().copied().unwrap_or(Rect::ZERO);
+        self.clips.push(clip.intersect(*rect));
+    }
+
+    fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'a>>, _: BlendMode) {}
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'a>,
+        glyph_transform: Affine,
+        props: DrawProps<'a>,
+        draw_mode: &DrawMode,
+    ) {
+        match glyph {
+            Glyph::Outline(o) => {
+                if self.is_invisible(&props.paint) {
+                    return;
+                }
+
+                // The line width of stroked glyphs is given in user space, not in glyph space.
+                let outline = glyph_transform * o.outline();
+
+                if let Some(bbox) = path_bbox(outline, props.transform, draw_mode) {
+                    self.add(bbox);
+                }
+            }
+            Glyph::Type3(t) => {
+                if !matches!(draw_mode, DrawMode::Invisible) {
+                    t.interpret(self, props.transform, glyph_transform, &props.paint);
+                }
+            }
+        }
+    }
+
+    fn draw_image(&mut self, image: Image<'a, '_>, props: ImageDrawProps<'a>) {
+        if let Image::Stencil(s) = &image
+            && self.is_invisible(&s.paint)
+        {
+            return;
+        }
+
+        let image_rect = Rect::new(0.0, 0.0, image.width() as f64, image.height() as f64);
+        self.add(props.transform.transform_rect_bbox(image_rect));
+    }
+
+    fn pop_clip(&mut self) {
+        // The bounding box of the page itself is never popped.
+        if self.clips.len() > 1 {
+            self.clips.pop();
+        }
+    }
+
+    fn pop_transparency_group(&mut self) {}
+}
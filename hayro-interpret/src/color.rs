@@ -11,7 +11,7 @@ use hayro_syntax::object::Stream;
 use hayro_syntax::object::dict::keys::*;
 use moxcms::{
     ColorProfile, DataColorSpace, Layout, Transform8BitExecutor, TransformF32Executor,
-    TransformOptions, Xyzd,
+    TransformOptions,
 };
 use smallvec::{SmallVec, ToSmallVec, smallvec};
 use std::borrow::Cow;
@@ -22,6 +22,85 @@ use std::sync::{Arc, LazyLock, OnceLock};
 /// A storage for the components of colors.
 pub type ColorComponents = SmallVec<[f32; 4]>;
 
+/// The rendering intent used for converting colors into the output color space.
+///
+/// The intent only matters for conversions that go through a color management
+/// system, i.e. ICC-based color spaces. See section 8.6.5.8 in the PDF
+/// specification for more information.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RenderingIntent {
+    /// Preserve the visual appearance of the color, even if it changes the
+    /// exact colorimetric values.
+    Perceptual,
+    /// Reproduce in-gamut colors exactly, relative to the white point of the
+    /// output medium.
+    #[default]
+    RelativeColorimetric,
+    /// Preserve the saturation of colors, at the expense of accuracy.
+    Saturation,
+    /// Reproduce in-gamut colors exactly, relative to the source white point.
+    AbsoluteColorimetric,
+}
+
+/// A set of process and spot colorants (inks).
+///
+/// Used to describe which colorants of the backdrop are affected by a paint
+/// that overprints, see [`Color::overprint_inks`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct InkMask(u8);
+
+impl InkMask {
+    /// No colorants.
+    pub const NONE: Self = Self(0);
+    /// The cyan process colorant.
+    pub const CYAN: Self = Self(1 << 0);
+    /// The magenta process colorant.
+    pub const MAGENTA: Self = Self(1 << 1);
+    /// The yellow process colorant.
+    pub const YELLOW: Self = Self(1 << 2);
+    /// The black process colorant.
+    pub const BLACK: Self = Self(1 << 3);
+    /// Any colorant that is not a process colorant.
+    pub const SPOT: Self = Self(1 << 4);
+    /// All colorants, as painted by the special `All` colorant of Separation
+    /// color spaces.
+    pub const ALL: Self = Self(0b11111);
+
+    const PROCESS: [Self; 4] = [Self::CYAN, Self::MAGENTA, Self::YELLOW, Self::BLACK];
+
+    /// Return the colorant with the given name, as used by Separation and
+    /// `DeviceN` color spaces.
+    fn from_colorant_name(name: &str) -> Self {
+        match name {
+            "Cyan" => Self::CYAN,
+            "Magenta" => Self::MAGENTA,
+            "Yellow" => Self::YELLOW,
+            "Black" => Self::BLACK,
+            "All" => Self::ALL,
+            "None" => Self::NONE,
+            _ => Self::SPOT,
+        }
+    }
+
+    /// Return whether the mask doesn't contain any colorants.
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Return whether the mask contains all colorants of `other`.
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for InkMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
 /// An RGB color with an alpha channel.
 #[derive(Debug, Copy, Clone)]
 pub struct AlphaColor {
@@ -219,17 +298,23 @@ impl ColorSpace {
 
     /// Return the device gray color space.
     pub(crate) fn device_gray() -> Self {
-        Self(Arc::new(ColorSpaceType::DeviceGray))
+        DEVICE_GRAY_CS.clone()
     }
 
     /// Return the device RGB color space.
     pub(crate) fn device_rgb() -> Self {
-        Self(Arc::new(ColorSpaceType::DeviceRgb))
+        DEVICE_RGB_CS.clone()
     }
 
     /// Return the device CMYK color space.
     pub(crate) fn device_cmyk() -> Self {
-        Self(Arc::new(ColorSpaceType::DeviceCmyk))
+        DEVICE_CMYK_CS.clone()
+    }
+
+    /// Return an identifier for the color space that is stable for as long as the
+    /// color space (or a clone of it) is alive.
+    pub(crate) fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as usize
     }
 
     /// Return the pattern color space.
@@ -305,6 +390,30 @@ impl ColorSpace {
         }
     }
 
+    /// Return the colorants that are painted by the given color with
+    /// overprinting enabled, or `None` if all colorants of the backdrop are
+    /// painted.
+    ///
+    /// Separation and `DeviceN` colors only paint their own colorants, unless
+    /// they use the `All` colorant. With a nonzero overprint mode, zero-valued
+    /// components of `DeviceCMYK` colors don't paint their colorant either. In
+    /// all other cases, all colorants are painted, which is the same as not
+    /// overprinting at all.
+    pub(crate) fn overprint_inks(&self, components: &[f32], overprint_mode: u8) -> Option<InkMask> {
+        match self.0.as_ref() {
+            ColorSpaceType::Separation(s) => (s.inks != InkMask::ALL).then_some(s.inks),
+            ColorSpaceType::DeviceN(d) => (d.inks != InkMask::ALL).then_some(d.inks),
+            ColorSpaceType::DeviceCmyk if overprint_mode == 1 && components.contains(&0.0) => Some(
+                components
+                    .iter()
+                    .zip(InkMask::PROCESS)
+                    .filter(|(c, _)| **c != 0.0)
+                    .fold(InkMask::NONE, |mask, (_, ink)| mask | ink),
+            ),
+            _ => None,
+        }
+    }
+
     pub(crate) fn is_device_gray(&self) -> bool {
         matches!(self.0.as_ref(), ColorSpaceType::DeviceGray)
     }
@@ -333,6 +442,21 @@ impl ColorSpace {
     /// Turn the given component values and opacity into an RGBA color.
     #[inline]
     pub fn to_rgba(&self, c: &[f32], opacity: f32, manual_scale: bool) -> AlphaColor {
+        self.to_rgba_with_intent(c, opacity, manual_scale, RenderingIntent::default())
+    }
+
+    /// Turn the given component values and opacity into an RGBA color, using
+    /// the given rendering intent.
+    ///
+    /// Note that the rendering intent is currently not taken into account yet.
+    #[inline]
+    pub fn to_rgba_with_intent(
+        &self,
+        c: &[f32],
+        opacity: f32,
+        manual_scale: bool,
+        _intent: RenderingIntent,
+    ) -> AlphaColor {
         let alpha = f32_to_u8(opacity);
 
         match self.0.as_ref() {
@@ -472,7 +596,6 @@ pub(crate) struct CalGray {
     gamma: f32,
 }
 
-// See <https://github.com/mozilla/pdf.js/blob/06f44916c8936b92f464d337fe3a0a6b2b78d5b4/src/core/colorspace.js#L752>
 impl CalGray {
     fn new(dict: &Dict<'_>) -> Option<Self> {
         let white_point = dict.get::<[f32; 3]>(WHITE_POINT).unwrap_or([1.0, 1.0, 1.0]);
@@ -490,22 +613,15 @@ impl CalGray {
 impl ToRgb for CalGray {
     fn convert_f32(&self, input: &[f32], output: &mut [u8], _: bool) -> Option<()> {
         for (input, output) in input.iter().copied().zip(output.chunks_exact_mut(3)) {
-            let g = self.gamma;
-            let (_xw, yw, _zw) = {
-                let wp = self.white_point;
-                (wp[0], wp[1], wp[2])
-            };
-            let (_xb, _yb, _zb) = {
-                let bp = self.black_point;
-                (bp[0], bp[1], bp[2])
-            };
-
-            let a = input;
-            let ag = a.powf(g);
-            let l = yw * ag;
-            let val = (0.0_f32.max(295.8 * l.powf(0.333_333_34) - 40.8) + 0.5) as u8;
-
-            output.copy_from_slice(&[val, val, val]);
+            let a = input.clamp(0.0, 1.0);
+            let ag = if a == 1.0 { 1.0 } else { a.powf(self.gamma) };
+            let xyz = self.white_point.map(|w| w * ag);
+
+            output.copy_from_slice(&CalRgb::xyz_to_rgb8(
+                &self.white_point,
+                &self.black_point,
+                &xyz,
+            ));
         }
 
         Some(())
@@ -521,9 +637,8 @@ pub(crate) struct CalRgb {
 }
 
 // See <https://github.com/mozilla/pdf.js/blob/06f44916c8936b92f464d337fe3a0a6b2b78d5b4/src/core/colorspace.js#L846>
-// Completely copied from there without really understanding the logic, but we get the same results as Firefox
-// which should be good enough (and by viewing the `calrgb.pdf` test file in different viewers you will
-// see that in many cases each viewer does whatever it wants, even Acrobat), so this is good enough for us.
+// Based on the approach from there, but with the chromatic adaptation performed on the cone responses
+// of the white points. The resulting XYZ to sRGB pipeline is shared with `CalGray` and `Lab`.
 impl CalRgb {
     fn new(dict: &Dict<'_>) -> Option<Self> {
         let white_point = dict.get::<[f32; 3]>(WHITE_POINT).unwrap_or([1.0, 1.0, 1.0]);
@@ -580,19 +695,27 @@ impl CalRgb {
         ]
     }
 
+    // The von Kries adaptation needs to happen in the cone response domain, so the
+    // white points need to be converted with the Bradford matrix as well.
     fn to_flat(source_white_point: &[f32; 3], lms: &[f32; 3]) -> [f32; 3] {
+        let src = Self::matrix_product(&Self::BRADFORD_SCALE_MATRIX, source_white_point);
+        let flat = Self::matrix_product(&Self::BRADFORD_SCALE_MATRIX, &Self::FLAT_WHITEPOINT);
+
         [
-            lms[0] / source_white_point[0],
-            lms[1] / source_white_point[1],
-            lms[2] / source_white_point[2],
+            lms[0] * flat[0] / src[0],
+            lms[1] * flat[1] / src[1],
+            lms[2] * flat[2] / src[2],
         ]
     }
 
     fn to_d65(source_white_point: &[f32; 3], lms: &[f32; 3]) -> [f32; 3] {
+        let src = Self::matrix_product(&Self::BRADFORD_SCALE_MATRIX, source_white_point);
+        let d65 = Self::matrix_product(&Self::BRADFORD_SCALE_MATRIX, &Self::D65_WHITEPOINT);
+
         [
-            lms[0] * Self::D65_WHITEPOINT[0] / source_white_point[0],
-            lms[1] * Self::D65_WHITEPOINT[1] / source_white_point[1],
-            lms[2] * Self::D65_WHITEPOINT[2] / source_white_point[2],
+            lms[0] * d65[0] / src[0],
+            lms[1] * d65[1] / src[1],
+            lms[2] * d65[2] / src[2],
         ]
     }
 
@@ -624,11 +747,7 @@ impl CalRgb {
         out
     }
 
-    fn normalize_white_point_to_flat(
-        &self,
-        source_white_point: &[f32; 3],
-        xyz: &[f32; 3],
-    ) -> [f32; 3] {
+    fn normalize_white_point_to_flat(source_white_point: &[f32; 3], xyz: &[f32; 3]) -> [f32; 3] {
         if source_white_point[0] == 1.0 && source_white_point[2] == 1.0 {
             return *xyz;
         }
@@ -637,15 +756,22 @@ impl CalRgb {
         Self::matrix_product(&Self::BRADFORD_SCALE_INVERSE_MATRIX, &lms_flat)
     }
 
-    fn normalize_white_point_to_d65(
-        &self,
-        source_white_point: &[f32; 3],
-        xyz: &[f32; 3],
-    ) -> [f32; 3] {
+    fn normalize_white_point_to_d65(source_white_point: &[f32; 3], xyz: &[f32; 3]) -> [f32; 3] {
         let lms = Self::matrix_product(&Self::BRADFORD_SCALE_MATRIX, xyz);
         let lms_d65 = Self::to_d65(source_white_point, &lms);
         Self::matrix_product(&Self::BRADFORD_SCALE_INVERSE_MATRIX, &lms_d65)
     }
+
+    /// Convert a CIE XYZ color relative to the given white and black point
+    /// into an 8-bit sRGB color. Shared by all CIE-based color spaces.
+    fn xyz_to_rgb8(white_point: &[f32; 3], black_point: &[f32; 3], xyz: &[f32; 3]) -> [u8; 3] {
+        let xyz_flat = Self::normalize_white_point_to_flat(white_point, xyz);
+        let xyz_black = Self::compensate_black_point(black_point, &xyz_flat);
+        let xyz_d65 = Self::normalize_white_point_to_d65(&Self::FLAT_WHITEPOINT, &xyz_black);
+        let srgb = Self::matrix_product(&Self::SRGB_D65_XYZ_TO_RGB_MATRIX, &xyz_d65);
+
+        srgb.map(|c| (Self::srgb_transfer_function(c) * 255.0 + 0.5) as u8)
+    }
 }
 
 impl ToRgb for CalRgb {
@@ -669,18 +795,12 @@ impl ToRgb for CalRgb {
             let x = m[0] * agr + m[3] * bgg + m[6] * cgb;
             let y = m[1] * agr + m[4] * bgg + m[7] * cgb;
             let z = m[2] * agr + m[5] * bgg + m[8] * cgb;
-            let xyz = [x, y, z];
-
-            let xyz_flat = self.normalize_white_point_to_flat(&self.white_point, &xyz);
-            let xyz_black = Self::compensate_black_point(&self.black_point, &xyz_flat);
-            let xyz_d65 = self.normalize_white_point_to_d65(&Self::FLAT_WHITEPOINT, &xyz_black);
-            let srgb_xyz = Self::matrix_product(&Self::SRGB_D65_XYZ_TO_RGB_MATRIX, &xyz_d65);
-
-            output.copy_from_slice(&[
-                (Self::srgb_transfer_function(srgb_xyz[0]) * 255.0 + 0.5) as u8,
-                (Self::srgb_transfer_function(srgb_xyz[1]) * 255.0 + 0.5) as u8,
-                (Self::srgb_transfer_function(srgb_xyz[2]) * 255.0 + 0.5) as u8,
-            ]);
+
+            output.copy_from_slice(&Self::xyz_to_rgb8(
+                &self.white_point,
+                &self.black_point,
+                &[x, y, z],
+            ));
         }
 
         Some(())
@@ -689,59 +809,76 @@ impl ToRgb for CalRgb {
 
 #[derive(Debug, Clone)]
 pub(crate) struct Lab {
+    white_point: [f32; 3],
+    black_point: [f32; 3],
     range: [f32; 4],
-    profile: ICCProfile,
 }
 
 impl Lab {
     fn new(dict: &Dict<'_>) -> Option<Self> {
         let white_point = dict.get::<[f32; 3]>(WHITE_POINT).unwrap_or([1.0, 1.0, 1.0]);
-        // Not sure how this should be used.
-        let _black_point = dict.get::<[f32; 3]>(BLACK_POINT).unwrap_or([0.0, 0.0, 0.0]);
+        let black_point = dict.get::<[f32; 3]>(BLACK_POINT).unwrap_or([0.0, 0.0, 0.0]);
         let range = dict
             .get::<[f32; 4]>(RANGE)
             .unwrap_or([-100.0, 100.0, -100.0, 100.0]);
 
-        let mut profile = ColorProfile::new_from_slice(include_bytes!("../assets/LAB.icc")).ok()?;
-        profile.white_point = Xyzd::new(
-            white_point[0] as f64,
-            white_point[1] as f64,
-            white_point[2] as f64,
-        );
+        Some(Self {
+            white_point,
+            black_point,
+            range,
+        })
+    }
 
-        let profile = ICCProfile::new_from_src_profile(
-            profile, false,
-            // This flag is only used to scale the values to [0.0, 1.0], but
-            // we already take care of this in the `convert_f32` method.
-            // Therefore, leave this as false, even though this is a LAB profile.
-            false, 3,
-        )?;
+    // The inverse of the `f` function used for defining L*a*b*, see
+    // section 8.6.5.4 in the PDF specification.
+    fn inverse_f(t: f32) -> f32 {
+        const DELTA: f32 = 6.0 / 29.0;
 
-        Some(Self { range, profile })
+        if t > DELTA {
+            t.powi(3)
+        } else {
+            3.0 * DELTA * DELTA * (t - 4.0 / 29.0)
+        }
     }
 }
 
 impl ToRgb for Lab {
     fn convert_f32(&self, input: &[f32], output: &mut [u8], manual_scale: bool) -> Option<()> {
-        if !manual_scale {
-            // moxcms expects values between 0.0 and 1.0, so we need to undo
-            // the scaling.
+        let [a_min, a_max, b_min, b_max] = self.range;
 
-            let input = input
-                .chunks_exact(3)
-                .flat_map(|i| {
-                    let l = i[0] / 100.0;
-                    let a = (i[1] + 128.0) / 255.0;
-                    let b = (i[2] + 128.0) / 255.0;
+        for (input, output) in input.chunks_exact(3).zip(output.chunks_exact_mut(3)) {
+            let (l, a, b) = if manual_scale {
+                // Values coming from a lookup table of an indexed color space are
+                // in the range [0.0, 1.0] and need to be mapped to the actual range
+                // of each component.
+                (
+                    input[0] * 100.0,
+                    a_min + input[1] * (a_max - a_min),
+                    b_min + input[2] * (b_max - b_min),
+                )
+            } else {
+                (input[0], input[1], input[2])
+            };
 
-                    [l, a, b]
-                })
-                .collect::<Vec<_>>();
+            let l = l.clamp(0.0, 100.0);
+            let a = a.clamp(a_min, a_max);
+            let b = b.clamp(b_min, b_max);
 
-            self.profile.convert_f32(&input, output, manual_scale)
-        } else {
-            self.profile.convert_f32(input, output, manual_scale)
+            let m = (l + 16.0) / 116.0;
+            let xyz = [
+                self.white_point[0] * Self::inverse_f(m + a / 500.0),
+                self.white_point[1] * Self::inverse_f(m),
+                self.white_point[2] * Self::inverse_f(m - b / 200.0),
+            ];
+
+            output.copy_from_slice(&CalRgb::xyz_to_rgb8(
+                &self.white_point,
+                &self.black_point,
+                &xyz,
+            ));
         }
+
+        Some(())
     }
 }
 
@@ -817,6 +954,7 @@ pub(crate) struct Separation {
     alternate_space: ColorSpace,
     tint_transform: Function,
     is_none_separation: bool,
+    inks: InkMask,
 }
 
 impl Separation {
@@ -835,6 +973,7 @@ impl Separation {
             alternate_space,
             tint_transform,
             is_none_separation,
+            inks: InkMask::from_colorant_name(name.as_str()),
         })
     }
 }
@@ -863,6 +1002,7 @@ pub(crate) struct DeviceN {
     num_components: u8,
     tint_transform: Function,
     is_none: bool,
+    inks: InkMask,
 }
 
 impl DeviceN {
@@ -877,6 +1017,9 @@ impl DeviceN {
             .collect::<Vec<_>>();
         let num_components = u8::try_from(names.len()).ok()?;
         let all_none = names.iter().all(|n| n.as_str() == "None");
+        let inks = names.iter().fold(InkMask::NONE, |mask, n| {
+            mask | InkMask::from_colorant_name(n.as_str())
+        });
         let alternate_space = ColorSpace::new(iter.next::<Object<'_>>()?, cache)?;
         let tint_transform = Function::new(&iter.next::<Object<'_>>()?)?;
 
@@ -889,6 +1032,7 @@ impl DeviceN {
             num_components,
             tint_transform,
             is_none: all_none,
+            inks,
         })
     }
 }
@@ -942,15 +1086,6 @@ impl ICCProfile {
             .unwrap_or(false);
         let is_lab = src_profile.color_space == DataColorSpace::Lab;
 
-        Self::new_from_src_profile(src_profile, is_srgb, is_lab, number_components)
-    }
-
-    fn new_from_src_profile(
-        src_profile: ColorProfile,
-        is_srgb: bool,
-        is_lab: bool,
-        number_components: usize,
-    ) -> Option<Self> {
         let src_layout = match number_components {
             1 => Layout::Gray,
             3 => Layout::Rgb,
@@ -1071,6 +1206,10 @@ pub struct Color {
     color_space: ColorSpace,
     components: ColorComponents,
     opacity: f32,
+    rendering_intent: RenderingIntent,
+    overprint_inks: Option<InkMask>,
+    // The converted color, so that cloned colors don't need to be converted again.
+    rgba: OnceLock<AlphaColor>,
 }
 
 impl Color {
@@ -1079,14 +1218,56 @@ impl Color {
             color_space,
             components,
             opacity,
+            rendering_intent: RenderingIntent::default(),
+            overprint_inks: None,
+            rgba: OnceLock::new(),
         }
     }
 
+    pub(crate) fn with_rendering_intent(mut self, rendering_intent: RenderingIntent) -> Self {
+        self.rendering_intent = rendering_intent;
+        self.rgba = OnceLock::new();
+
+        self
+    }
+
     /// Return the color as an RGBA color.
     #[inline]
     pub fn to_rgba(&self) -> AlphaColor {
-        self.color_space
-            .to_rgba(&self.components, self.opacity, false)
+        *self.rgba.get_or_init(|| {
+            self.color_space.to_rgba_with_intent(
+                &self.components,
+                self.opacity,
+                false,
+                self.rendering_intent,
+            )
+        })
+    }
+
+    pub(crate) fn with_overprint_inks(mut self, overprint_inks: Option<InkMask>) -> Self {
+        self.overprint_inks = overprint_inks;
+
+        self
+    }
+
+    /// Return the rendering intent that should be used for converting the color.
+    pub fn rendering_intent(&self) -> RenderingIntent {
+        self.rendering_intent
+    }
+
+    /// Return the colorants painted by the color if it overprints the backdrop.
+    ///
+    /// `None` means that the color paints all colorants, which is always the
+    /// case if overprinting is disabled. An empty mask means that the color
+    /// doesn't change the backdrop at all, for example a `DeviceCMYK` color
+    /// with all components set to zero and an overprint mode of 1.
+    ///
+    /// Renderers that composite in RGB can't keep track of the colorants of the
+    /// backdrop, so `hayro` only uses this to skip colors with an empty mask.
+    /// All other overprinting colors are approximated by multiplying them with
+    /// the backdrop, independently of the colorants they paint.
+    pub fn overprint_inks(&self) -> Option<InkMask> {
+        self.overprint_inks
     }
 
     /// Create a color from RGBA.
@@ -1097,10 +1278,20 @@ impl Color {
             color_space: ColorSpace::device_rgb(),
             components: smallvec![c[0], c[1], c[2]],
             opacity: c[3],
+            rendering_intent: RenderingIntent::default(),
+            overprint_inks: None,
+            rgba: OnceLock::new(),
         }
     }
 }
 
+static DEVICE_GRAY_CS: LazyLock<ColorSpace> =
+    LazyLock::new(|| ColorSpace(Arc::new(ColorSpaceType::DeviceGray)));
+static DEVICE_RGB_CS: LazyLock<ColorSpace> =
+    LazyLock::new(|| ColorSpace(Arc::new(ColorSpaceType::DeviceRgb)));
+static DEVICE_CMYK_CS: LazyLock<ColorSpace> =
+    LazyLock::new(|| ColorSpace(Arc::new(ColorSpaceType::DeviceCmyk)));
+
 static CMYK_TRANSFORM: LazyLock<ICCProfile> = LazyLock::new(|| {
     ICCProfile::new(include_bytes!("../assets/CGATS001Compat-v2-micro.icc"), 4).unwrap()
 });
@@ -1160,3 +1351,137 @@ pub(crate) trait ToRgb {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CalGray, CalRgb, ColorSpace, InkMask, Lab, ToRgb};
+    use crate::cache::Cache;
+
+    use hayro_syntax::object::{Array, Dict, FromBytes, Object};
+
+    fn convert(cs: &impl ToRgb, input: &[f32]) -> [u8; 3] {
+        let mut output = [0; 3];
+        cs.convert_f32(input, &mut output, false).unwrap();
+
+        output
+    }
+
+    fn assert_close(actual: [u8; 3], expected: [u8; 3]) {
+        assert!(
+            actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= 1),
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn lab_d50() {
+        let lab =
+            Lab::new(&Dict::from_bytes(b"<< /WhitePoint [0.9642 1 0.8249] >>").unwrap()).unwrap();
+
+        assert_close(convert(&lab, &[0.0, 0.0, 0.0]), [0, 0, 0]);
+        assert_close(convert(&lab, &[50.0, 0.0, 0.0]), [119, 119, 119]);
+        assert_close(convert(&lab, &[100.0, 0.0, 0.0]), [255, 255, 255]);
+    }
+
+    #[test]
+    fn lab_d65_primaries() {
+        let lab = Lab::new(
+            &Dict::from_bytes(b"<< /WhitePoint [0.9505 1 1.089] /Range [-128 127 -128 127] >>")
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_close(convert(&lab, &[53.24, 80.09, 67.2]), [255, 0, 0]);
+        assert_close(convert(&lab, &[87.73, -86.18, 83.18]), [0, 255, 0]);
+        assert_close(convert(&lab, &[32.3, 79.19, -107.86]), [0, 0, 255]);
+    }
+
+    #[test]
+    fn lab_range() {
+        let lab = Lab::new(
+            &Dict::from_bytes(b"<< /WhitePoint [0.9505 1 1.089] /Range [0 0 0 0] >>").unwrap(),
+        )
+        .unwrap();
+
+        // a* and b* are clamped to the range, so this should end up as neutral gray.
+        assert_eq!(
+            convert(&lab, &[50.0, 60.0, -60.0]),
+            convert(&lab, &[50.0, 0.0, 0.0])
+        );
+    }
+
+    #[test]
+    fn cal_rgb_srgb_primaries() {
+        let cal_rgb = CalRgb::new(
+            &Dict::from_bytes(
+                b"<<
+                  /WhitePoint [0.9505 1 1.089]
+                  /Matrix [0.4124 0.2126 0.0193 0.3576 0.7152 0.1192 0.1805 0.0722 0.9505]
+                >>",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_close(convert(&cal_rgb, &[1.0, 0.0, 0.0]), [255, 0, 0]);
+        assert_close(convert(&cal_rgb, &[0.0, 1.0, 0.0]), [0, 255, 0]);
+        assert_close(convert(&cal_rgb, &[0.0, 0.0, 1.0]), [0, 0, 255]);
+        assert_close(convert(&cal_rgb, &[0.5, 0.5, 0.5]), [188, 188, 188]);
+    }
+
+    #[test]
+    fn cal_gray_gamma() {
+        let cal_gray = CalGray::new(
+            &Dict::from_bytes(b"<< /WhitePoint [0.9505 1 1.089] /Gamma 2.2 >>").unwrap(),
+        )
+        .unwrap();
+
+        assert_close(convert(&cal_gray, &[0.0]), [0, 0, 0]);
+        assert_close(convert(&cal_gray, &[0.5]), [128, 128, 128]);
+        assert_close(convert(&cal_gray, &[1.0]), [255, 255, 255]);
+    }
+
+    #[test]
+    fn cmyk_overprint_inks() {
+        let cmyk = ColorSpace::device_cmyk();
+
+        // With an overprint mode of 0, all colorants are painted.
+        assert_eq!(cmyk.overprint_inks(&[0.0, 1.0, 0.0, 0.0], 0), None);
+        assert_eq!(cmyk.overprint_inks(&[0.5, 1.0, 0.2, 1.0], 1), None);
+        assert_eq!(
+            cmyk.overprint_inks(&[0.5, 1.0, 0.0, 0.0], 1),
+            Some(InkMask::CYAN | InkMask::MAGENTA)
+        );
+        assert_eq!(
+            cmyk.overprint_inks(&[0.0, 0.0, 0.0, 0.0], 1),
+            Some(InkMask::NONE)
+        );
+        assert_eq!(
+            ColorSpace::device_rgb().overprint_inks(&[0.0, 0.0, 0.0], 1),
+            None
+        );
+    }
+
+    #[test]
+    fn separation_overprint_inks() {
+        let separation = |name: &str| {
+            let array = format!(
+                "[/Separation /{name} /DeviceGray << /FunctionType 2 /Domain [0 1] /C0 [1] /C1 [0] /N 1 >>]"
+            );
+            let array = Array::from_bytes(array.as_bytes()).unwrap();
+
+            ColorSpace::new(Object::Array(array), &Cache::new()).unwrap()
+        };
+
+        assert_eq!(
+            separation("Cyan").overprint_inks(&[1.0], 0),
+            Some(InkMask::CYAN)
+        );
+        assert_eq!(
+            separation("PANTONE").overprint_inks(&[1.0], 0),
+            Some(InkMask::SPOT)
+        );
+        // The `All` colorant paints all colorants, so it can't overprint.
+        assert_eq!(separation("All").overprint_inks(&[1.0], 0), None);
+    }
+}
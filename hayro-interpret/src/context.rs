@@ -1,25 +1,77 @@
 use crate::cache::{Cache, CacheKey};
-use crate::color::{Color, ColorSpace};
+use crate::color::{Color, ColorComponents, ColorSpace, InkMask, RenderingIntent};
 use crate::convert::convert_transform;
-use crate::font::{Font, StandardFont};
-use crate::interpret::state::{ClipType, State, TextStateFont};
+use crate::font::fallback::{FallbackFont, is_blank};
+use crate::font::outline::OutlineFont;
+use crate::font::{Font, OutlineGlyph, StandardFont};
+use crate::interpret::SettingsCMapProvider;
+use crate::interpret::state::{ClipType, GraphicsState, State, TextStateFont};
 use crate::ocg::OcgState;
-use crate::util::{BezPathExt, Float64Ext};
-use crate::{ClipPath, Device, DrawProps, FillRule, InterpreterSettings, Paint, StrokeProps};
+use crate::soft_mask::SoftMask;
+use crate::util::{BezPathExt, Float64Ext, hash128};
+use crate::{
+    BlendMode, ClipPath, Device, DrawProps, FillRule, InterpreterSettings, InterpreterWarning,
+    OpContext, Paint, StrokeProps,
+};
+use hayro_cmap::BfString;
+use hayro_syntax::content::SourceLocation;
 use hayro_syntax::content::ops::Transform;
 use hayro_syntax::object::Dict;
 use hayro_syntax::object::Name;
+use hayro_syntax::object::ObjectIdentifier;
 use hayro_syntax::page::Resources;
 use hayro_syntax::xref::XRef;
 use kurbo::{Affine, BezPath, PathEl, Point, Rect, Shape};
 use rustc_hash::FxHashMap;
-use smallvec::smallvec;
+use smallvec::{SmallVec, smallvec};
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::Arc;
 
-/// Maximum nesting depth for interpreting `XObject`'s/patterns/streams.
+/// Default maximum nesting depth for interpreting `XObject`'s/patterns/streams.
 pub(crate) const MAX_NESTED_INTERPRETATION_DEPTH: u32 = 50;
 
+/// Check whether content may still be interpreted at the given nesting depth.
+pub(crate) fn check_nesting_depth(nesting_depth: u32, settings: &InterpreterSettings) -> bool {
+    if nesting_depth > settings.max_nesting_depth {
+        warn!("interpreter nesting depth exceeded");
+
+        return false;
+    }
+
+    true
+}
+
+/// Check whether the content stream with the given identifier may be interpreted,
+/// which isn't the case if it is already being interpreted further up the chain.
+///
+/// Resources are looked up in the stream itself first and then in the invoking
+/// content streams, so re-entering an active stream always interprets the same
+/// content with the same resources again, and the cycle would never end.
+pub(crate) fn check_cycle(active_streams: &[ObjectIdentifier], obj_id: ObjectIdentifier) -> bool {
+    // Identifiers of direct objects are unknown, so we can't detect cycles for them.
+    if obj_id.obj_number != 0 && active_streams.contains(&obj_id) {
+        warn!(
+            "content stream {} {} references itself, skipping it",
+            obj_id.obj_number, obj_id.gen_number
+        );
+
+        return false;
+    }
+
+    true
+}
+
+/// The number of graphics states (and clips) to reserve space for upfront, which
+/// covers the nesting depth of most content streams.
+const INITIAL_STATE_CAPACITY: usize = 16;
+
+/// Maximum number of resolved colors that are cached per context.
+const MAX_COLOR_CACHE_SIZE: usize = 1024;
+
+type FallbackFontMap = FxHashMap<u128, Option<Rc<FallbackFont>>>;
+type FallbackGlyphMap = FxHashMap<(u128, u32), Option<OutlineGlyph>>;
+
 /// A cache used by the interpreter.
 ///
 /// Ideally, such a cache should be constructed once per PDF and then reused across
@@ -27,6 +79,13 @@ pub(crate) const MAX_NESTED_INTERPRETATION_DEPTH: u32 = 50;
 #[derive(Clone)]
 pub struct InterpreterCache<'a> {
     pub(crate) font_cache: Rc<RefCell<FxHashMap<u128, Option<Font<'a>>>>>,
+    // The fallback fonts of fonts with missing glyphs, keyed by the cache key
+    // of the font.
+    pub(crate) fallback_font_cache: Rc<RefCell<FallbackFontMap>>,
+    // The substitutes of glyphs that are missing from their font, keyed by the
+    // cache key of the font and the character code. `MissingGlyph` warnings are
+    // only emitted when a substitute is first looked up.
+    pub(crate) fallback_glyph_cache: Rc<RefCell<FallbackGlyphMap>>,
     pub(crate) object_cache: Cache,
 }
 
@@ -41,6 +100,8 @@ impl<'a> InterpreterCache<'a> {
     pub fn new() -> Self {
         Self {
             font_cache: Rc::new(RefCell::new(FxHashMap::default())),
+            fallback_font_cache: Rc::new(RefCell::new(FxHashMap::default())),
+            fallback_glyph_cache: Rc::new(RefCell::new(FxHashMap::default())),
             object_cache: Cache::new(),
         }
     }
@@ -49,6 +110,8 @@ impl<'a> InterpreterCache<'a> {
 /// A per-page interpretation context that borrows shared data from an [`InterpreterCache`].
 pub struct Context<'a> {
     states: Vec<State<'a>>,
+    // The clips of all states, from the outermost to the innermost one.
+    clips: Vec<ClipType>,
     path: BezPath,
     sub_path_start: Point,
     last_point: Point,
@@ -60,6 +123,25 @@ pub struct Context<'a> {
     pub(crate) xref: &'a XRef,
     pub(crate) ocg_state: OcgState,
     nesting_depth: u32,
+    // The content streams that are currently being interpreted, from the
+    // outermost to the innermost one.
+    active_streams: Vec<ObjectIdentifier>,
+    // Colors that have already been resolved, keyed by the color space, the
+    // components and the opacity.
+    color_cache: RefCell<FxHashMap<u128, Color>>,
+    // The number of operators that have been interpreted with this context.
+    ops_executed: u64,
+    // Only present for the context of a page, so that operators of Type3 glyphs,
+    // patterns and soft masks are attributed to the operator that uses them.
+    pub(crate) op_tracker: Option<OpTracker>,
+}
+
+/// Keeps track of where the operators that are currently being interpreted
+/// are located.
+#[derive(Default)]
+pub(crate) struct OpTracker {
+    pub(crate) xobject_path: Vec<usize>,
+    pub(crate) annotation: Option<usize>,
 }
 
 impl<'a> Context<'a> {
@@ -73,9 +155,22 @@ impl<'a> Context<'a> {
     ) -> Self {
         let state = State::new(initial_transform);
 
-        Self::new_with(initial_transform, bbox, cache, xref, settings, state, 0)
+        let mut context = Self::new_with(
+            initial_transform,
+            bbox,
+            cache,
+            xref,
+            settings,
+            state,
+            0,
+            vec![],
+        );
+        context.op_tracker = Some(OpTracker::default());
+
+        context
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new_with(
         initial_transform: Affine,
         bbox: Rect,
@@ -84,6 +179,7 @@ impl<'a> Context<'a> {
         settings: InterpreterSettings,
         state: State<'a>,
         nesting_depth: u32,
+        active_streams: Vec<ObjectIdentifier>,
     ) -> Self {
         let ocg_state = {
             let root_ref = xref.root_id();
@@ -92,8 +188,15 @@ impl<'a> Context<'a> {
                 .unwrap_or_default()
         };
 
+        let mut states = Vec::with_capacity(INITIAL_STATE_CAPACITY);
+        states.push(State {
+            clip_depth: 0,
+            ..state
+        });
+
         Self {
-            states: vec![state],
+            states,
+            clips: Vec::with_capacity(INITIAL_STATE_CAPACITY),
             settings,
             xref,
             root_transforms: vec![initial_transform],
@@ -105,16 +208,41 @@ impl<'a> Context<'a> {
             interpreter_cache: cache.clone(),
             ocg_state,
             nesting_depth,
+            active_streams,
+            color_cache: RefCell::new(FxHashMap::default()),
+            ops_executed: 0,
+            op_tracker: None,
+        }
+    }
+
+    /// Inform the device about the operator that is about to be interpreted.
+    pub(crate) fn set_op_context(
+        &self,
+        index: usize,
+        location: SourceLocation,
+        device: &mut impl Device<'a>,
+    ) {
+        if let Some(tracker) = &self.op_tracker {
+            device.set_op_context(&OpContext {
+                index,
+                location,
+                xobject_path: &tracker.xobject_path,
+                annotation: tracker.annotation,
+            });
         }
     }
 
     pub(crate) fn save_state(&mut self) {
-        let Some(cur) = self.states.last().cloned() else {
+        let Some(cur) = self.states.last() else {
             warn!("attempted to save state without existing state");
             return;
         };
 
-        self.states.push(cur);
+        let new = State {
+            clip_depth: self.clips.len(),
+            ..cur.clone()
+        };
+        self.states.push(new);
     }
 
     pub(crate) fn bbox(&self) -> Rect {
@@ -149,13 +277,13 @@ impl<'a> Context<'a> {
                 && cur_bbox.max_x().is_nearly_less_or_equal(clip_rect.max_x())
                 && cur_bbox.max_y().is_nearly_less_or_equal(clip_rect.max_y())
             {
-                self.get_mut().clips.push(ClipType::Dummy);
+                self.clips.push(ClipType::Dummy);
                 return;
             }
 
             device.push_clip_rect(&clip_rect);
             self.push_bbox(clip_rect);
-            self.get_mut().clips.push(ClipType::Real);
+            self.clips.push(ClipType::Real);
             return;
         }
 
@@ -165,13 +293,30 @@ impl<'a> Context<'a> {
             fill,
         });
         self.push_bbox(bbox);
-        self.get_mut().clips.push(ClipType::Real);
+        self.clips.push(ClipType::Real);
+    }
+
+    /// Clip the following contents to the given soft mask.
+    ///
+    /// Since devices don't support clipping with masks, this is done with a
+    /// transparency group, which is popped like a clip path.
+    pub(crate) fn push_clip_mask(&mut self, mask: SoftMask<'a>, device: &mut impl Device<'a>) {
+        device.push_transparency_group(1.0, Some(mask), BlendMode::Normal);
+        self.push_bbox(self.bbox());
+        self.clips.push(ClipType::Mask);
     }
 
     pub(crate) fn pop_clip(&mut self, device: &mut impl Device<'a>) {
-        if let Some(ClipType::Real) = self.get_mut().clips.pop() {
-            device.pop_clip();
-            self.pop_bbox();
+        match self.clips.pop() {
+            Some(ClipType::Real) => {
+                device.pop_clip();
+                self.pop_bbox();
+            }
+            Some(ClipType::Mask) => {
+                device.pop_transparency_group();
+                self.pop_bbox();
+            }
+            Some(ClipType::Dummy) | None => {}
         }
     }
 
@@ -195,23 +340,19 @@ impl<'a> Context<'a> {
     }
 
     pub(crate) fn restore_state(&mut self, device: &mut impl Device<'a>) {
-        let Some(target_clips) = self
-            .states
-            .get(self.states.len().saturating_sub(2))
-            .map(|s| s.clips.len())
-        else {
+        // The first state should never be popped.
+        if self.states.len() <= 1 {
             warn!("underflowed graphics state");
             return;
-        };
+        }
 
-        while self.get().clips.len() > target_clips {
+        let target_clips = self.get().clip_depth;
+
+        while self.clips.len() > target_clips {
             self.pop_clip(device);
         }
 
-        // The first state should never be popped.
-        if self.states.len() > 1 {
-            self.states.pop();
-        }
+        self.states.pop();
     }
 
     pub(crate) fn draw_props(&self, is_stroke: bool) -> DrawProps<'a> {
@@ -220,6 +361,28 @@ impl<'a> Context<'a> {
             paint: self.get_paint(is_stroke),
             soft_mask: self.get().graphics_state.soft_mask.clone(),
             blend_mode: self.get().graphics_state.blend_mode,
+            overprint: self.overprint_inks(is_stroke).is_some(),
+            device_params: self.get().graphics_state.device_params.clone(),
+        }
+    }
+
+    fn overprint_inks(&self, is_stroke: bool) -> Option<InkMask> {
+        let gs = &self.get().graphics_state;
+
+        let (enabled, color_space, color) = if is_stroke {
+            (gs.stroke_overprint, &gs.stroke_cs, &gs.stroke_color)
+        } else {
+            (
+                gs.non_stroke_overprint,
+                &gs.none_stroke_cs,
+                &gs.non_stroke_color,
+            )
+        };
+
+        if enabled {
+            color_space.overprint_inks(color, gs.overprint_mode)
+        } else {
+            None
         }
     }
 
@@ -238,20 +401,67 @@ impl<'a> Context<'a> {
 
                 pattern.pre_concat_transform(self.root_transform());
 
-                Paint::Pattern(Box::new(pattern))
+                // Patterns can't be shared between threads yet, since soft masks
+                // are reference-counted with `Rc`.
+                #[allow(clippy::arc_with_non_send_sync)]
+                Paint::Pattern(Arc::new(pattern))
             } else {
                 // Pattern was likely invalid, use transparent paint.
                 Paint::Color(Color::new(ColorSpace::device_gray(), smallvec![0.0], 0.0))
             }
+        } else if let Some(tf) = &data.transfer_function {
+            let color = Color::new(data.color_space, data.color, data.alpha)
+                .with_rendering_intent(data.rendering_intent);
+            let transferred = Color::from_rgba(tf.apply(&color.to_rgba()))
+                .with_rendering_intent(data.rendering_intent)
+                .with_overprint_inks(self.overprint_inks(is_stroke));
+
+            Paint::Color(transferred)
         } else {
-            let color = Color::new(data.color_space, data.color, data.alpha);
+            let color = self.resolve_color(
+                data.color_space,
+                data.color,
+                data.alpha,
+                data.rendering_intent,
+            );
+
+            Paint::Color(color.with_overprint_inks(self.overprint_inks(is_stroke)))
+        }
+    }
 
-            if let Some(tf) = &data.transfer_function {
-                Paint::Color(Color::from_rgba(tf.apply(&color.to_rgba())))
-            } else {
-                Paint::Color(color)
-            }
+    fn resolve_color(
+        &self,
+        color_space: ColorSpace,
+        components: ColorComponents,
+        opacity: f32,
+        rendering_intent: RenderingIntent,
+    ) -> Color {
+        let key = hash128(&(
+            color_space.id(),
+            components
+                .iter()
+                .map(|c| c.to_bits())
+                .collect::<SmallVec<[u32; 4]>>(),
+            opacity.to_bits(),
+            rendering_intent,
+        ));
+        let mut cache = self.color_cache.borrow_mut();
+
+        if let Some(color) = cache.get(&key) {
+            return color.clone();
+        }
+
+        if cache.len() >= MAX_COLOR_CACHE_SIZE {
+            cache.clear();
         }
+
+        let color =
+            Color::new(color_space, components, opacity).with_rendering_intent(rendering_intent);
+        // Convert the color once, so that all clones of it can reuse the result.
+        color.to_rgba();
+        cache.insert(key, color.clone());
+
+        color
     }
 
     pub(crate) fn path(&self) -> &BezPath {
@@ -294,6 +504,12 @@ impl<'a> Context<'a> {
         self.states.last_mut().unwrap()
     }
 
+    /// Return the graphics state of the current state for modification, copying
+    /// it first if it is still shared with a saved state.
+    pub(crate) fn graphics_state_mut(&mut self) -> &mut GraphicsState<'a> {
+        Rc::make_mut(&mut self.get_mut().graphics_state)
+    }
+
     pub(crate) fn pre_concat_transform(&mut self, transform: Transform) {
         self.pre_concat_affine(convert_transform(transform));
     }
@@ -319,6 +535,26 @@ impl<'a> Context<'a> {
         self.get().graphics_state.stroke_props.clone()
     }
 
+    pub(crate) fn deadline_exceeded(&self, check_clock: bool) -> bool {
+        self.settings
+            .deadline
+            .as_ref()
+            .is_some_and(|deadline| deadline.check_impl(check_clock))
+    }
+
+    /// Return the number of content stream operators that have been interpreted
+    /// with this context so far.
+    ///
+    /// This includes the operators of form `XObjects` and annotations, but not the ones
+    /// of patterns, soft masks and Type3 glyphs, which are interpreted separately.
+    pub fn ops_executed(&self) -> u64 {
+        self.ops_executed
+    }
+
+    pub(crate) fn count_op(&mut self) {
+        self.ops_executed += 1;
+    }
+
     pub(crate) fn num_states(&self) -> usize {
         self.states.len()
     }
@@ -327,21 +563,28 @@ impl<'a> Context<'a> {
         self.nesting_depth
     }
 
-    pub(crate) fn begin_nested_interpretation(&mut self) -> bool {
-        if self.nesting_depth >= MAX_NESTED_INTERPRETATION_DEPTH {
-            warn!("interpreter nesting depth exceeded");
+    pub(crate) fn active_streams(&self) -> &[ObjectIdentifier] {
+        &self.active_streams
+    }
 
+    pub(crate) fn begin_nested_interpretation(&mut self, obj_id: ObjectIdentifier) -> bool {
+        if !check_nesting_depth(self.nesting_depth + 1, &self.settings)
+            || !check_cycle(&self.active_streams, obj_id)
+        {
             return false;
         }
 
         self.nesting_depth += 1;
+        self.active_streams.push(obj_id);
 
         true
     }
 
     pub(crate) fn end_nested_interpretation(&mut self) {
         self.nesting_depth = self.nesting_depth.saturating_sub(1);
+        self.active_streams.pop();
     }
+
     pub(crate) fn resolve_font(&mut self, font_dict: &Dict<'a>) -> Option<TextStateFont<'a>> {
         let cache_key = font_dict.cache_key();
 
@@ -353,7 +596,7 @@ impl<'a> Context<'a> {
                     Font::new(
                         font_dict,
                         &self.settings.font_resolver,
-                        &self.settings.cmap_resolver,
+                        &SettingsCMapProvider(&self.settings),
                     )
                 })
                 .clone()
@@ -366,6 +609,90 @@ impl<'a> Context<'a> {
                 .map(TextStateFont::Fallback)
         }
     }
+
+    pub(crate) fn resolve_standard_font(&self, font: StandardFont) -> Option<Font<'a>> {
+        let cache_key = hash128(&("standard font", font));
+
+        self.interpreter_cache
+            .font_cache
+            .borrow_mut()
+            .entry(cache_key)
+            .or_insert_with(|| Font::new_standard(font, &self.settings.font_resolver))
+            .clone()
+    }
+
+    /// Return a glyph of the fallback font of `font` that can be drawn in place
+    /// of `glyph`, if the glyph is missing from the font.
+    ///
+    /// A glyph is considered missing if it has no outline although its character
+    /// is known and is expected to be visible.
+    pub(crate) fn resolve_fallback_glyph(
+        &self,
+        font: &Font<'a>,
+        glyph: &OutlineGlyph,
+    ) -> Option<OutlineGlyph> {
+        let key = (font.cache_key(), glyph.char_code);
+
+        if let Some(cached) = self
+            .interpreter_cache
+            .fallback_glyph_cache
+            .borrow()
+            .get(&key)
+        {
+            return cached.clone();
+        }
+
+        let resolved = self.find_fallback_glyph(font, glyph);
+        self.interpreter_cache
+            .fallback_glyph_cache
+            .borrow_mut()
+            .insert(key, resolved.clone());
+
+        resolved
+    }
+
+    fn find_fallback_glyph(&self, font: &Font<'a>, glyph: &OutlineGlyph) -> Option<OutlineGlyph> {
+        let c = match glyph.as_unicode()? {
+            BfString::Char(c) => c,
+            BfString::String(s) => s.chars().next()?,
+        };
+
+        if is_blank(c) || !glyph.outline().bounding_box().is_zero_area() {
+            return None;
+        }
+
+        let fallback = self
+            .interpreter_cache
+            .fallback_font_cache
+            .borrow_mut()
+            .entry(font.cache_key())
+            .or_insert_with(|| {
+                FallbackFont::new(
+                    font.fallback_query(),
+                    glyph.font.clone(),
+                    &self.settings.font_resolver,
+                )
+                .map(Rc::new)
+            })
+            .clone()?;
+        let id = fallback.map_unicode(c)?;
+
+        warn!(
+            "glyph for {:?} (code {}) is missing from its font, using fallback font",
+            c, glyph.char_code
+        );
+        (self.settings.warning_sink)(InterpreterWarning::MissingGlyph {
+            code: glyph.char_code,
+            unicode: c,
+        });
+
+        Some(OutlineGlyph {
+            id,
+            font: OutlineFont::Fallback(fallback),
+            char_code: glyph.char_code,
+            advance_override: None,
+        })
+    }
 }
 
 pub(crate) fn path_as_rect(path: &BezPath) -> Option<Rect> {
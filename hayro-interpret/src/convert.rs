@@ -1,4 +1,6 @@
 use hayro_syntax::content::ops::{LineCap, LineJoin, Transform};
+use hayro_syntax::object::Array;
+use smallvec::SmallVec;
 
 pub(crate) fn convert_transform(t: Transform) -> kurbo::Affine {
     kurbo::Affine::new([
@@ -28,3 +30,46 @@ pub(crate) fn convert_line_join(lc: LineJoin) -> kurbo::Join {
         _ => kurbo::Join::Miter,
     }
 }
+
+/// The length that zero-length dashes are extended to, since kurbo doesn't emit
+/// anything for them, even though they should be drawn as dots with round and
+/// projecting square line caps.
+const MIN_DASH_LENGTH: f32 = 0.01;
+
+/// Convert a PDF dash array into the one that should be passed to kurbo.
+///
+/// Both PDF and kurbo restart the dash pattern at the beginning of each subpath, so
+/// apart from zero-length dashes, the array can be used as is. An empty array is
+/// returned for arrays that should result in a solid line.
+pub(crate) fn convert_dash_array(array: &Array<'_>) -> SmallVec<[f32; 4]> {
+    let mut dashes = array.iter::<f32>().collect::<SmallVec<[f32; 4]>>();
+
+    if dashes.iter().any(|d| !d.is_finite() || *d < 0.0) {
+        warn!("dash array contains invalid lengths, ignoring it");
+
+        return SmallVec::new();
+    }
+
+    if dashes.iter().all(|d| *d == 0.0) {
+        return SmallVec::new();
+    }
+
+    // Odd-length arrays are repeated, so that dashes and gaps swap roles in the
+    // second half of the period. We make this explicit to be able to tell them apart.
+    if dashes.len() % 2 == 1 {
+        let period = dashes.clone();
+        dashes.extend_from_slice(&period);
+    }
+
+    // Extend zero-length dashes at the expense of the following gap, so that the
+    // period stays the same and the dashes stay in phase over the whole subpath.
+    for i in (0..dashes.len()).step_by(2) {
+        if dashes[i] == 0.0 {
+            let extension = MIN_DASH_LENGTH.min(dashes[i + 1]);
+            dashes[i] += extension;
+            dashes[i + 1] -= extension;
+        }
+    }
+
+    dashes
+}
@@ -2,6 +2,7 @@ use crate::font::Glyph;
 use crate::soft_mask::SoftMask;
 use crate::{BlendMode, ClipPath, FillRule, Image};
 use crate::{DrawMode, DrawProps, ImageDrawProps};
+use hayro_syntax::content::SourceLocation;
 use kurbo::{Affine, BezPath, Rect, Shape};
 
 /// A trait for a device that can be used to process PDF drawing instructions.
@@ -25,6 +26,17 @@ pub trait Device<'a> {
         blend_mode: BlendMode,
     );
     /// Draw a glyph.
+    ///
+    /// For outline glyphs, `glyph_transform` maps from glyph space (see
+    /// [`OutlineGlyph::outline`]) to the coordinate system of `props.transform`,
+    /// i.e. the outline should be drawn with the transform
+    /// `props.transform * glyph_transform`. It includes the font size, the
+    /// horizontal scaling, the text rise and the position of the glyph within
+    /// the text run. Type3 glyphs expect it as an argument to
+    /// [`Type3Glyph::interpret`] instead.
+    ///
+    /// [`OutlineGlyph::outline`]: crate::font::OutlineGlyph::outline
+    /// [`Type3Glyph::interpret`]: crate::font::Type3Glyph::interpret
     fn draw_glyph(
         &mut self,
         glyph: &Glyph<'a>,
@@ -49,6 +61,36 @@ pub trait Device<'a> {
     fn begin_marked_content(&mut self, _tag: &[u8], _mcid: Option<i32>) {}
     /// Called at the end of a marked content sequence (EMC).
     fn end_marked_content(&mut self) {}
+    /// Called before an operator of the page is interpreted.
+    ///
+    /// All drawing calls until the next invocation originate from the operator
+    /// described by `context`. This can be used to map drawing calls back to the
+    /// content stream, for example for error reporting or debugging overlays.
+    fn set_op_context(&mut self, _context: &OpContext<'_>) {}
+}
+
+/// Information about the operator that is currently being interpreted.
+#[derive(Debug, Clone, Copy)]
+pub struct OpContext<'a> {
+    /// The index of the operator among all operators that are interpreted as part
+    /// of the same content.
+    ///
+    /// If the contents of a page are split into multiple streams, the operators
+    /// of all streams are counted as one sequence, so the index doesn't restart
+    /// at the beginning of each stream (see [`SourceLocation::stream`]). The
+    /// operators of a form `XObject` or of the appearance stream of an annotation
+    /// are counted separately, starting at 0.
+    pub index: usize,
+    /// The location of the operator in its content stream.
+    pub location: SourceLocation,
+    /// The indices of the `Do` operators through which the current form
+    /// `XObject` was invoked, starting with the one in the page content stream.
+    ///
+    /// Empty if the operator is part of the page content stream itself.
+    pub xobject_path: &'a [usize],
+    /// The index of the annotation in the `Annots` array of the page, if the
+    /// operator is part of the appearance stream of an annotation.
+    pub annotation: Option<usize>,
 }
 
 /// A device that discards all drawing operations.
@@ -63,3 +105,43 @@ impl Device<'_> for DummyDevice {
     fn pop_clip(&mut self) {}
     fn pop_transparency_group(&mut self) {}
 }
+
+/// A device that records all drawn paths and glyphs and counts the drawn images,
+/// so that tests can check what the interpreter passes to a device.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct Recorder<'a> {
+    pub(crate) paths: Vec<(BezPath, DrawProps<'a>, DrawMode)>,
+    pub(crate) glyphs: Vec<(Glyph<'a>, Affine, DrawProps<'a>, DrawMode)>,
+    pub(crate) images: usize,
+    pub(crate) clips: Vec<ClipPath>,
+    pub(crate) masks: Vec<SoftMask<'a>>,
+}
+
+#[cfg(test)]
+impl<'a> Device<'a> for Recorder<'a> {
+    fn draw_path(&mut self, path: &BezPath, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        self.paths.push((path.clone(), props, draw_mode.clone()));
+    }
+    fn push_clip_path(&mut self, clip_path: &ClipPath) {
+        self.clips.push(clip_path.clone());
+    }
+    fn push_transparency_group(&mut self, _: f32, mask: Option<SoftMask<'a>>, _: BlendMode) {
+        self.masks.extend(mask);
+    }
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'a>,
+        glyph_transform: Affine,
+        props: DrawProps<'a>,
+        draw_mode: &DrawMode,
+    ) {
+        self.glyphs
+            .push((glyph.clone(), glyph_transform, props, draw_mode.clone()));
+    }
+    fn draw_image(&mut self, _: Image<'a, '_>, _: ImageDrawProps<'a>) {
+        self.images += 1;
+    }
+    fn pop_clip(&mut self) {}
+    fn pop_transparency_group(&mut self) {}
+}
@@ -0,0 +1,427 @@
+use crate::font::Glyph;
+use crate::interpret::DEADLINE_CHECK_INTERVAL;
+use crate::pattern::{Pattern, RecordedTiling};
+use crate::soft_mask::SoftMask;
+use crate::util::hash128;
+use crate::x_object::{DecodedMask, DecodedRaster, ImageSource};
+use crate::{
+    BlendMode, CacheKey, ClipPath, Deadline, Device, DrawMode, DrawProps, FillRule, Image,
+    ImageDrawProps, Paint, RasterImage, StencilImage,
+};
+use kurbo::{Affine, BezPath, Rect, Shape};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A device that records all drawing operations so that they can be replayed later on.
+///
+/// This allows you to interpret a page only once and then draw it many times, for example
+/// at different zoom levels. Images are decoded once at full resolution and shared between
+/// all replays, and the contents of soft masks and tiling patterns are recorded into nested
+/// display lists so that replaying does not require interpreting the content stream again.
+/// Type3 glyphs are the one exception: they are kept by reference and interpreted by the
+/// target device, just like during direct rendering.
+#[derive(Clone, Default)]
+pub struct DisplayList<'a> {
+    commands: Vec<Command<'a>>,
+    // Shared between a display list and all of its nested lists, so that the same
+    // image, mask or pattern is only recorded once.
+    memo: Rc<RefCell<Memo<'a>>>,
+}
+
+impl Debug for DisplayList<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DisplayList({} commands)", self.commands.len())
+    }
+}
+
+#[derive(Default)]
+struct Memo<'a> {
+    masks: FxHashMap<u128, Rc<DisplayList<'a>>>,
+    patterns: FxHashMap<u128, Rc<RecordedTiling<'a>>>,
+    stencils: FxHashMap<u128, Option<Arc<DecodedMask>>>,
+    rasters: FxHashMap<u128, Option<Arc<DecodedRaster>>>,
+}
+
+#[derive(Clone)]
+enum Command<'a> {
+    Path(BezPath, DrawProps<'a>, DrawMode),
+    Rect(Rect, DrawProps<'a>, DrawMode),
+    ClipPath(ClipPath),
+    ClipRect(Rect),
+    PushGroup(f32, Option<SoftMask<'a>>, BlendMode),
+    Glyph(Glyph<'a>, Affine, DrawProps<'a>, DrawMode),
+    Stencil(Arc<DecodedMask>, u128, Paint<'a>, ImageDrawProps<'a>),
+    Raster(Arc<DecodedRaster>, u128, ImageDrawProps<'a>),
+    PopClip,
+    PopGroup,
+    BeginMarkedContent(Vec<u8>, Option<i32>),
+    EndMarkedContent,
+}
+
+impl Command<'_> {
+    /// Whether the command draws something, as opposed to changing the clip path,
+    /// the layer stack or the marked content.
+    fn is_drawing(&self) -> bool {
+        matches!(
+            self,
+            Command::Path(..)
+                | Command::Rect(..)
+                | Command::Glyph(..)
+                | Command::Stencil(..)
+                | Command::Raster(..)
+        )
+    }
+}
+
+impl<'a> DisplayList<'a> {
+    /// Create a new, empty display list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the number of recorded drawing operations.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Return whether no drawing operations have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Replay all recorded drawing operations into the given device.
+    pub fn replay(&self, device: &mut impl Device<'a>) {
+        self.replay_with_transform(Affine::IDENTITY, device);
+    }
+
+    /// Replay all recorded drawing operations into the given device, with an additional
+    /// transform prepended to all of them.
+    ///
+    /// This is mainly useful for drawing the same list at different scales.
+    pub fn replay_with_transform(&self, transform: Affine, device: &mut impl Device<'a>) {
+        self.replay_impl(transform, None, device);
+    }
+
+    /// Replay all recorded drawing operations into the given device, with an additional
+    /// transform prepended to all of them, until the deadline is exceeded.
+    ///
+    /// Once the deadline is exceeded, nothing is drawn anymore, but clip paths and
+    /// transparency groups are still pushed and popped, so that they are unwound cleanly.
+    pub fn replay_with_deadline(
+        &self,
+        transform: Affine,
+        deadline: &Deadline,
+        device: &mut impl Device<'a>,
+    ) {
+        self.replay_impl(transform, Some(deadline), device);
+    }
+
+    fn replay_impl(
+        &self,
+        transform: Affine,
+        deadline: Option<&Deadline>,
+        device: &mut impl Device<'a>,
+    ) {
+        let is_axis_aligned = {
+            let [_, b, c, _, _, _] = transform.as_coeffs();
+            b == 0.0 && c == 0.0
+        };
+
+        let mut num_commands = 0_u32;
+
+        for command in &self.commands {
+            // Just like during interpretation, only check the clock every couple of commands.
+            let cancelled = deadline.is_some_and(|deadline| {
+                deadline.check_impl(num_commands.is_multiple_of(DEADLINE_CHECK_INTERVAL))
+            });
+            num_commands = num_commands.wrapping_add(1);
+
+            if cancelled && command.is_drawing() {
+                continue;
+            }
+
+            match command {
+                Command::Path(path, props, mode) => {
+                    device.draw_path(path, transform_props(props, transform), mode);
+                }
+                Command::Rect(rect, props, mode) => {
+                    device.draw_rect(rect, transform_props(props, transform), mode);
+                }
+                Command::ClipPath(clip_path) => device.push_clip_path(&ClipPath {
+                    path: transform * clip_path.path.clone(),
+                    fill: clip_path.fill,
+                }),
+                Command::ClipRect(rect) => {
+                    if is_axis_aligned {
+                        device.push_clip_rect(&transform.transform_rect_bbox(*rect));
+                    } else {
+                        device.push_clip_path(&ClipPath {
+                            path: transform * rect.to_path(0.1),
+                            fill: FillRule::NonZero,
+                        });
+                    }
+                }
+                Command::PushGroup(opacity, mask, blend_mode) => device.push_transparency_group(
+                    *opacity,
+                    mask.as_ref().map(|m| m.pre_concat_transform(transform)),
+                    *blend_mode,
+                ),
+                Command::Glyph(glyph, glyph_transform, props, mode) => device.draw_glyph(
+                    glyph,
+                    *glyph_transform,
+                    transform_props(props, transform),
+                    mode,
+                ),
+                Command::Stencil(mask, key, paint, props) => device.draw_image(
+                    Image::Stencil(StencilImage {
+                        paint: transform_paint(paint, transform),
+                        source: ImageSource::Mask(mask.clone(), *key),
+                    }),
+                    transform_image_props(props, transform),
+                ),
+                Command::Raster(raster, key, props) => device.draw_image(
+                    Image::Raster(RasterImage(ImageSource::Raster(raster.clone(), *key))),
+                    transform_image_props(props, transform),
+                ),
+                Command::PopClip => device.pop_clip(),
+                Command::PopGroup => device.pop_transparency_group(),
+                Command::BeginMarkedContent(tag, mcid) => device.begin_marked_content(tag, *mcid),
+                Command::EndMarkedContent => device.end_marked_content(),
+            }
+        }
+    }
+
+    fn nested(&self) -> Self {
+        Self {
+            commands: vec![],
+            memo: self.memo.clone(),
+        }
+    }
+
+    fn record_mask(&mut self, mask: SoftMask<'a>) -> SoftMask<'a> {
+        if mask.is_recorded() {
+            return mask;
+        }
+
+        let key = mask.cache_key();
+        let cached = self.memo.borrow().masks.get(&key).cloned();
+
+        let recorded = cached.unwrap_or_else(|| {
+            let mut nested = self.nested();
+            mask.interpret(&mut nested);
+            let recorded = Rc::new(nested);
+            self.memo.borrow_mut().masks.insert(key, recorded.clone());
+
+            recorded
+        });
+
+        mask.with_recorded(recorded)
+    }
+
+    fn record_paint(&mut self, paint: Paint<'a>) -> Paint<'a> {
+        let Paint::Pattern(mut pattern) = paint else {
+            return paint;
+        };
+
+        let Pattern::Tiling(tiling) = pattern.as_ref() else {
+            return Paint::Pattern(pattern);
+        };
+
+        if tiling.is_recorded() {
+            return Paint::Pattern(pattern);
+        }
+
+        let key = hash128(&(
+            tiling.cache_key(),
+            tiling.non_stroking_paint.to_rgba().to_rgba8(),
+            tiling.stroke_paint.to_rgba().to_rgba8(),
+            tiling.ctx_bbox().cache_key(),
+        ));
+        let cached = self.memo.borrow().patterns.get(&key).cloned();
+
+        let recorded = cached.unwrap_or_else(|| {
+            let mut fill = self.nested();
+            tiling.interpret(&mut fill, Affine::IDENTITY, false);
+
+            let stroke = (!tiling.is_color()).then(|| {
+                let mut stroke = self.nested();
+                tiling.interpret(&mut stroke, Affine::IDENTITY, true);
+
+                stroke
+            });
+
+            let recorded = Rc::new(RecordedTiling { fill, stroke });
+            self.memo
+                .borrow_mut()
+                .patterns
+                .insert(key, recorded.clone());
+
+            recorded
+        });
+
+        if let Pattern::Tiling(tiling) = Arc::make_mut(&mut pattern) {
+            tiling.set_recorded(recorded);
+        }
+
+        Paint::Pattern(pattern)
+    }
+
+    fn record_props(&mut self, props: DrawProps<'a>) -> DrawProps<'a> {
+        DrawProps {
+            paint: self.record_paint(props.paint),
+            soft_mask: props.soft_mask.map(|m| self.record_mask(m)),
+            ..props
+        }
+    }
+
+    fn record_image_props(&mut self, props: ImageDrawProps<'a>) -> ImageDrawProps<'a> {
+        ImageDrawProps {
+            soft_mask: props.soft_mask.map(|m| self.record_mask(m)),
+            ..props
+        }
+    }
+}
+
+impl<'a> Device<'a> for DisplayList<'a> {
+    fn draw_path(&mut self, path: &BezPath, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        let props = self.record_props(props);
+        self.commands
+            .push(Command::Path(path.clone(), props, draw_mode.clone()));
+    }
+
+    fn push_clip_path(&mut self, clip_path: &ClipPath) {
+        self.commands.push(Command::ClipPath(clip_path.clone()));
+    }
+
+    fn push_clip_rect(&mut self, rect: &Rect) {
+        self.commands.push(Command::ClipRect(*rect));
+    }
+
+    fn push_transparency_group(
+        &mut self,
+        opacity: f32,
+        mask: Option<SoftMask<'a>>,
+        blend_mode: BlendMode,
+    ) {
+        let mask = mask.map(|m| self.record_mask(m));
+        self.commands
+            .push(Command::PushGroup(opacity, mask, blend_mode));
+    }
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'a>,
+        glyph_transform: Affine,
+        props: DrawProps<'a>,
+        draw_mode: &DrawMode,
+    ) {
+        let props = self.record_props(props);
+        self.commands.push(Command::Glyph(
+            glyph.clone(),
+            glyph_transform,
+            props,
+            draw_mode.clone(),
+        ));
+    }
+
+    fn draw_image(&mut self, image: Image<'a, '_>, props: ImageDrawProps<'a>) {
+        let props = self.record_image_props(props);
+
+        match image {
+            Image::Stencil(s) => {
+                let key = s.cache_key();
+                let cached = self.memo.borrow().stencils.get(&key).cloned();
+                let decoded = cached.unwrap_or_else(|| {
+                    let decoded = s.source.decoded_mask(None);
+                    self.memo.borrow_mut().stencils.insert(key, decoded.clone());
+
+                    decoded
+                });
+
+                if let Some(decoded) = decoded {
+                    let paint = self.record_paint(s.paint);
+                    self.commands
+                        .push(Command::Stencil(decoded, key, paint, props));
+                }
+            }
+            Image::Raster(r) => {
+                let key = r.cache_key();
+                let cached = self.memo.borrow().rasters.get(&key).cloned();
+                let decoded = cached.unwrap_or_else(|| {
+                    let decoded = r.0.decoded_raster(None);
+                    self.memo.borrow_mut().rasters.insert(key, decoded.clone());
+
+                    decoded
+                });
+
+                if let Some(decoded) = decoded {
+                    self.commands.push(Command::Raster(decoded, key, props));
+                }
+            }
+        }
+    }
+
+    fn pop_clip(&mut self) {
+        self.commands.push(Command::PopClip);
+    }
+
+    fn pop_transparency_group(&mut self) {
+        self.commands.push(Command::PopGroup);
+    }
+
+    fn draw_rect(&mut self, rect: &Rect, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        let props = self.record_props(props);
+        self.commands
+            .push(Command::Rect(*rect, props, draw_mode.clone()));
+    }
+
+    fn begin_marked_content(&mut self, tag: &[u8], mcid: Option<i32>) {
+        self.commands
+            .push(Command::BeginMarkedContent(tag.to_vec(), mcid));
+    }
+
+    fn end_marked_content(&mut self) {
+        self.commands.push(Command::EndMarkedContent);
+    }
+}
+
+fn transform_paint<'a>(paint: &Paint<'a>, transform: Affine) -> Paint<'a> {
+    match paint {
+        Paint::Color(c) => Paint::Color(c.clone()),
+        Paint::Pattern(p) => {
+            let mut p = p.as_ref().clone();
+            p.pre_concat_device_transform(transform);
+
+            #[allow(clippy::arc_with_non_send_sync)]
+            Paint::Pattern(Arc::new(p))
+        }
+    }
+}
+
+fn transform_props<'a>(props: &DrawProps<'a>, transform: Affine) -> DrawProps<'a> {
+    DrawProps {
+        transform: transform * props.transform,
+        paint: transform_paint(&props.paint, transform),
+        soft_mask: props
+            .soft_mask
+            .as_ref()
+            .map(|m| m.pre_concat_transform(transform)),
+        blend_mode: props.blend_mode,
+        overprint: props.overprint,
+        device_params: props.device_params.clone(),
+    }
+}
+
+fn transform_image_props<'a>(props: &ImageDrawProps<'a>, transform: Affine) -> ImageDrawProps<'a> {
+    ImageDrawProps {
+        transform: transform * props.transform,
+        soft_mask: props
+            .soft_mask
+            .as_ref()
+            .map(|m| m.pre_concat_transform(transform)),
+        blend_mode: props.blend_mode,
+    }
+}
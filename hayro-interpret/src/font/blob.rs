@@ -6,6 +6,8 @@ use skrifa::instance::{LocationRef, Size};
 use skrifa::metrics::GlyphMetrics;
 use skrifa::outline::{DrawSettings, Engine, HintingInstance, HintingOptions, Target};
 use skrifa::raw::TableProvider;
+use skrifa::raw::ps::cff::dict::{self, Entry};
+use skrifa::raw::ps::cff::index::Index;
 use skrifa::raw::ps::cff::{CffFontRef, Subfont, charset::Charset, v1::Cff};
 use skrifa::raw::ps::string::Sid;
 use skrifa::raw::ps::type1::Type1Font;
@@ -117,6 +119,23 @@ impl CffFontBlob {
         path.take()
     }
 
+    /// Return the advance width of the glyph as specified in its charstring,
+    /// assuming an upem value of 1000.
+    pub(crate) fn advance_width(&self, glyph: GlyphId) -> Option<f32> {
+        let subfont = self.subfont(glyph)?;
+
+        self.font()
+            .draw(
+                subfont,
+                glyph,
+                &[],
+                Some(UNITS_PER_EM),
+                &mut OutlinePath::new(),
+            )
+            .ok()
+            .flatten()
+    }
+
     pub(crate) fn glyph_names(&self) -> Vec<(GlyphId, String)> {
         let Some(charset) = self.charset() else {
             return Vec::new();
@@ -158,6 +177,41 @@ impl CffFontBlob {
     pub(crate) fn is_cid(&self) -> bool {
         self.font().is_cid()
     }
+
+    /// Return the dominant width of vertical stems (i.e. the `StdVW` entry of the
+    /// private dictionary), assuming an upem value of 1000.
+    pub(crate) fn std_vw(&self) -> Option<f32> {
+        let data = self.font().data();
+        let top_dict = self.0.as_ref().get().cff.top_dicts().get(0).ok()?;
+        let mut private_dict = None;
+
+        for entry in dict::entries(top_dict, None).filter_map(|e| e.ok()) {
+            match entry {
+                Entry::PrivateDictRange(range) => private_dict = Some(range),
+                // CID-keyed fonts don't have a top-level private dictionary, so
+                // use the one of the first font dictionary instead.
+                Entry::FdArrayOffset(offset) => {
+                    let fd_array = Index::new(data.get(offset..)?, false).ok()?;
+                    private_dict = dict::entries(fd_array.get(0).ok()?, None)
+                        .filter_map(|e| e.ok())
+                        .find_map(|e| match e {
+                            Entry::PrivateDictRange(range) => Some(range),
+                            _ => None,
+                        });
+                }
+                _ => {}
+            }
+        }
+
+        let std_vw = dict::entries(data.get(private_dict?)?, None)
+            .filter_map(|e| e.ok())
+            .find_map(|e| match e {
+                Entry::StdVw(width) => Some(width.to_f32()),
+                _ => None,
+            })?;
+
+        (std_vw > 0.0).then(|| std_vw * UNITS_PER_EM / self.font().upem() as f32)
+    }
 }
 
 /// A font blob for OpenType fonts.
@@ -2,9 +2,11 @@ use crate::font::blob::{CffFontBlob, OpenTypeFontBlob, Type1FontBlob};
 use crate::font::generated::glyph_names;
 use crate::font::standard_font::select_standard_font;
 use crate::font::{
-    FallbackFontQuery, FontFlags, FontQuery, read_to_unicode, stretch_glyph, strip_subset_prefix,
+    FallbackFontQuery, FontFlags, FontQuery, read_stem_v, read_to_unicode, stretch_glyph,
+    strip_subset_prefix,
 };
-use crate::{CMapResolverFn, CacheKey, FontResolverFn};
+use crate::{CacheKey, FontResolverFn};
+use hayro_cmap::CMapProvider;
 use hayro_cmap::{BfString, CMap, CharacterCollection, CidFamily, WritingMode};
 use hayro_syntax::object;
 use hayro_syntax::object::Dict;
@@ -41,15 +43,19 @@ pub(crate) struct Type0Font {
     /// Whether the `to_unicode` map is a UCS2 `CMap` (CID-indexed) rather than
     /// a `ToUnicode` `CMap` (code-indexed).
     to_unicode_is_cid_indexed: bool,
+    /// The `StemV` entry of the font descriptor, if the font is embedded.
+    stem_v: Option<f32>,
+    /// The character collection of the font, if known.
+    character_collection: Option<CharacterCollection>,
 }
 
 impl Type0Font {
     pub(crate) fn new(
         dict: &Dict<'_>,
         font_resolver: &FontResolverFn,
-        cmap_resolver: &CMapResolverFn,
+        cmap_provider: &dyn CMapProvider,
     ) -> Option<Self> {
-        let cmap = read_encoding(&dict.get::<Object<'_>>(ENCODING)?, cmap_resolver)?;
+        let cmap = read_encoding(&dict.get::<Object<'_>>(ENCODING)?, cmap_provider)?;
 
         let horizontal = cmap.metadata().writing_mode != Some(WritingMode::Vertical);
 
@@ -112,7 +118,7 @@ impl Type0Font {
         let cid_to_gid_map = CidToGIdMap::new(&descendant_font).unwrap_or_default();
         let cache_key = dict.cache_key();
 
-        let mut to_unicode = read_to_unicode(dict, cmap_resolver);
+        let mut to_unicode = read_to_unicode(dict, cmap_provider);
         let mut to_unicode_is_cid_indexed = false;
 
         // If there is no ToUnicode map, try to get the UCS2 CMap.
@@ -120,13 +126,11 @@ impl Type0Font {
             && to_unicode.is_none()
             && let Some(cc) = character_collection.as_ref()
             && let Some(ucs2_name) = cc.family.ucs2_cmap()
-            && let Some(data) = (cmap_resolver)(ucs2_name)
+            && let Some(data) = cmap_provider.load_cmap(ucs2_name)
+            && let Some(ucs2_cmap) = CMap::parse_with_provider(&data, cmap_provider)
         {
-            let resolver = cmap_resolver.clone();
-            if let Some(ucs2_cmap) = CMap::parse(data, move |n| (resolver)(n)) {
-                to_unicode = Some(ucs2_cmap);
-                to_unicode_is_cid_indexed = true;
-            }
+            to_unicode = Some(ucs2_cmap);
+            to_unicode_is_cid_indexed = true;
         }
 
         let postscript_name = dict
@@ -153,6 +157,8 @@ impl Type0Font {
             font_flags,
             fallback,
             to_unicode_is_cid_indexed,
+            stem_v: (!fallback).then(|| read_stem_v(&font_descriptor)).flatten(),
+            character_collection,
         })
     }
 
@@ -180,7 +186,7 @@ impl Type0Font {
         // selected font has the right glyph order, and map via that.
 
         match &self.font_type {
-            FontType::OpenType(_) => self.cid_to_gid_map.map(cid as u16),
+            FontType::OpenType(_) => self.cid_to_gid_map.map(cid),
             FontType::Cff(c) => {
                 if c.is_cid() {
                     // Very confusing stuff going on here, see https://github.com/mozilla/pdf.js/pull/15563.
@@ -192,7 +198,7 @@ impl Type0Font {
                         GlyphId::new(self.cid_to_gid_map.inverse_map(GlyphId::new(cid)) as u32)
                     }
                 } else {
-                    self.cid_to_gid_map.map(cid as u16)
+                    self.cid_to_gid_map.map(cid)
                 }
             }
             // Maybe we need similar processing to CFF fonts? But since
@@ -292,6 +298,11 @@ impl Type0Font {
         self.postscript_name.as_deref()
     }
 
+    /// Get the character collection (registry/ordering) of the font.
+    pub(crate) fn character_collection(&self) -> Option<&CharacterCollection> {
+        self.character_collection.as_ref()
+    }
+
     /// Get the font weight (100-900, 400=normal, 700=bold).
     ///
     /// Returns `None` if weight cannot be determined (CFF fonts or invalid weight).
@@ -391,6 +402,13 @@ impl Type0Font {
         }
     }
 
+    pub(crate) fn stem_width(&self) -> Option<f32> {
+        match &self.font_type {
+            FontType::Cff(c) => c.std_vw().or(self.stem_v),
+            FontType::OpenType(_) | FontType::Type1(_) => self.stem_v,
+        }
+    }
+
     pub(crate) fn char_code_to_unicode(&self, code: u32) -> Option<BfString> {
         if let Some(to_unicode) = &self.to_unicode {
             let key = if self.to_unicode_is_cid_indexed {
@@ -461,7 +479,8 @@ enum CidToGIdMap {
     #[default]
     Identity,
     Mapped {
-        forward: FxHashMap<u16, GlyphId>,
+        /// The glyph ID of each CID, indexed by CID.
+        forward: Vec<GlyphId>,
         inverse: FxHashMap<GlyphId, u16>,
     },
 }
@@ -475,16 +494,18 @@ impl CidToGIdMap {
                 None
             }
         } else if let Some(stream) = dict.get::<Stream<'_>>(CID_TO_GID_MAP) {
+            // "The glyph index for a particular CID value c shall be a 2-byte value
+            // stored in bytes 2 × c and 2 × c + 1." (9.7.4.2)
             let decoded = stream.decoded().ok()?;
-            let mut forward = FxHashMap::default();
-            let mut inverse = FxHashMap::default();
-
-            for (cid, gid) in decoded.chunks_exact(2).enumerate() {
-                let gid = GlyphId::new(u16::from_be_bytes([gid[0], gid[1]]) as u32);
-
-                forward.insert(cid as u16, gid);
-                inverse.insert(gid, cid as u16);
-            }
+            let forward = decoded
+                .chunks_exact(2)
+                .map(|gid| GlyphId::new(u16::from_be_bytes([gid[0], gid[1]]) as u32))
+                .collect::<Vec<_>>();
+            let inverse = forward
+                .iter()
+                .enumerate()
+                .map(|(cid, gid)| (*gid, cid as u16))
+                .collect();
 
             Some(Self::Mapped { forward, inverse })
         } else {
@@ -492,10 +513,13 @@ impl CidToGIdMap {
         }
     }
 
-    fn map(&self, code: u16) -> GlyphId {
+    fn map(&self, cid: u32) -> GlyphId {
         match self {
-            Self::Identity => GlyphId::new(code as u32),
-            Self::Mapped { forward, .. } => forward.get(&code).copied().unwrap_or(GlyphId::NOTDEF),
+            Self::Identity => GlyphId::new(cid),
+            Self::Mapped { forward, .. } => forward
+                .get(cid as usize)
+                .copied()
+                .unwrap_or(GlyphId::NOTDEF),
         }
     }
 
@@ -567,7 +591,7 @@ fn read_cid_system_info(descendant_font: &Dict<'_>) -> Option<CharacterCollectio
     Some(CharacterCollection { family, supplement })
 }
 
-fn read_encoding(object: &Object<'_>, cmap_resolver: &CMapResolverFn) -> Option<CMap> {
+fn read_encoding(object: &Object<'_>, cmap_provider: &dyn CMapProvider) -> Option<CMap> {
     // TODO: Support fetching CMaps referenced via `usecmap` in the PDF.
     match object {
         Object::Name(n) => {
@@ -576,16 +600,14 @@ fn read_encoding(object: &Object<'_>, cmap_resolver: &CMapResolverFn) -> Option<
                 hayro_cmap::CMapName::IdentityH => Some(CMap::identity_h()),
                 hayro_cmap::CMapName::IdentityV => Some(CMap::identity_v()),
                 _ => {
-                    let data = (cmap_resolver)(cmap_type)?;
-                    let resolver = cmap_resolver.clone();
-                    CMap::parse(data, move |n| (resolver)(n))
+                    let data = cmap_provider.load_cmap(cmap_type)?;
+                    CMap::parse_with_provider(&data, cmap_provider)
                 }
             }
         }
         Object::Stream(s) => {
             let decoded = s.decoded().ok()?;
-            let resolver = cmap_resolver.clone();
-            CMap::parse(&decoded, move |n| (resolver)(n))
+            CMap::parse_with_provider(&decoded, cmap_provider)
         }
         _ => None,
     }
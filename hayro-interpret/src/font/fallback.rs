@@ -0,0 +1,95 @@
+use crate::font::generated::glyph_names;
+use crate::font::outline::OutlineFont;
+use crate::font::standard_font::StandardFontBlob;
+use crate::font::{FallbackFontQuery, FontQuery, stretch_glyph};
+use crate::util::hash128;
+use crate::{CacheKey, FontResolverFn};
+use hayro_cmap::BfString;
+use kurbo::BezPath;
+use skrifa::GlyphId;
+
+/// A font that is used to draw glyphs that are missing from an embedded font,
+/// for example because the font was subsetted too aggressively.
+///
+/// The font is provided by the font resolver in response to a fallback query
+/// for the original font. Its glyphs are stretched so that they match the
+/// advances of the original font.
+#[derive(Debug)]
+pub(crate) struct FallbackFont {
+    cache_key: u128,
+    original: OutlineFont,
+    blob: StandardFontBlob,
+}
+
+impl FallbackFont {
+    pub(crate) fn new(
+        query: FallbackFontQuery,
+        original: OutlineFont,
+        resolver: &FontResolverFn,
+    ) -> Option<Self> {
+        let (data, index) = resolver(&FontQuery::Fallback(query))?;
+        let blob = StandardFontBlob::from_data(data, index)?;
+
+        Some(Self {
+            cache_key: hash128(&("fallback font", original.cache_key())),
+            original,
+            blob,
+        })
+    }
+
+    /// Map a character to a glyph.
+    pub(crate) fn map_unicode(&self, c: char) -> Option<GlyphId> {
+        glyph_names::get_reverse(c)
+            .and_then(|name| self.blob.name_to_glyph(name))
+            .or_else(|| self.blob.unicode_to_glyph(c as u32))
+            .filter(|g| *g != GlyphId::NOTDEF)
+    }
+
+    /// Return the outline of the glyph, stretched to the given advance width of
+    /// the original font.
+    pub(crate) fn outline_glyph(&self, glyph: GlyphId, expected_width: Option<f32>) -> BezPath {
+        let path = self.blob.outline_glyph(glyph);
+
+        // Only horizontal advances can be matched, the advance of glyphs in
+        // vertical fonts is zero.
+        match (expected_width, self.advance_width(glyph)) {
+            (Some(expected_width), Some(actual_width)) if expected_width > 0.0 => {
+                stretch_glyph(path, expected_width, actual_width)
+            }
+            _ => path,
+        }
+    }
+
+    fn advance_width(&self, glyph: GlyphId) -> Option<f32> {
+        match &self.blob {
+            StandardFontBlob::Cff(blob) => blob.advance_width(glyph),
+            StandardFontBlob::Otf(..) => self.blob.advance_width(glyph),
+        }
+    }
+
+    pub(crate) fn char_code_to_unicode(&self, code: u32) -> Option<BfString> {
+        self.original.char_code_to_unicode(code)
+    }
+
+    pub(crate) fn stem_width(&self) -> Option<f32> {
+        self.blob.std_vw()
+    }
+
+    pub(crate) fn glyph_advance_width(&self, code: u32) -> Option<f32> {
+        self.original.glyph_advance_width(code)
+    }
+}
+
+impl CacheKey for FallbackFont {
+    fn cache_key(&self) -> u128 {
+        self.cache_key
+    }
+}
+
+/// Return whether the character is not expected to have a visible glyph, so
+/// that an empty outline doesn't mean that the glyph is missing.
+pub(crate) fn is_blank(c: char) -> bool {
+    c.is_whitespace()
+        || c.is_control()
+        || matches!(c, '\u{200B}'..='\u{200F}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}')
+}
@@ -11,9 +11,10 @@ use crate::font::true_type::TrueTypeFont;
 use crate::font::type1::Type1Font;
 use crate::font::type3::Type3;
 use crate::interpret::state::State;
-use crate::{CMapResolverFn, CacheKey, FontResolverFn, InterpreterSettings, Paint};
+use crate::{CacheKey, FontResolverFn, InterpreterSettings, Paint};
 use bitflags::bitflags;
 use hayro_syntax::object::Name;
+use hayro_syntax::object::ObjectIdentifier;
 use hayro_syntax::object::dict::keys::SUBTYPE;
 use hayro_syntax::object::dict::keys::*;
 use hayro_syntax::object::{Dict, Stream};
@@ -22,7 +23,6 @@ use hayro_syntax::xref::XRef;
 use kurbo::{Affine, BezPath, Vec2};
 use outline::OutlineFont;
 use skrifa::GlyphId;
-use std::borrow::Cow;
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::rc::Rc;
@@ -30,6 +30,7 @@ use std::sync::Arc;
 
 mod blob;
 mod cid;
+pub(crate) mod fallback;
 mod generated;
 mod glyph_simulator;
 pub(crate) mod outline;
@@ -49,6 +50,25 @@ pub(crate) fn stretch_glyph(path: BezPath, expected_width: f32, actual_width: f3
     }
 }
 
+/// Return whether the font dictionary refers to one of the standard fonts (or a
+/// common alias of them) by name, so that it can be used without a font descriptor.
+pub(crate) fn is_standard_font(dict: &Dict<'_>) -> bool {
+    matches!(
+        standard_font::select_standard_font(dict, &Dict::empty()),
+        Some((_, true))
+    )
+}
+
+/// Return whether the data of an embedded font program can be parsed. As for CID
+/// fonts, all supported formats are tried regardless of the font type.
+pub(crate) fn is_parseable_font_program(data: FontData) -> bool {
+    if skrifa::FontRef::from_index(data.as_ref().as_ref(), 0).is_ok() {
+        blob::OpenTypeFontBlob::new(data, 0).is_some()
+    } else {
+        blob::CffFontBlob::new(data.clone()).is_some() || blob::Type1FontBlob::new(data).is_some()
+    }
+}
+
 /// A container for the bytes of a PDF file.
 pub type FontData = Arc<dyn AsRef<[u8]> + Send + Sync>;
 
@@ -64,12 +84,18 @@ pub(crate) fn strip_subset_prefix(name: &str) -> &str {
     }
 }
 
+/// Read the width of the dominant vertical stems from a font descriptor.
+pub(crate) fn read_stem_v(descriptor: &Dict<'_>) -> Option<f32> {
+    descriptor.get::<f32>(STEM_V).filter(|w| *w > 0.0)
+}
+
 use crate::util::hash128;
-use hayro_cmap::{BfString, CMap, CMapName, CharacterCollection};
+use hayro_cmap::{BfString, CMap, CMapName, CMapProvider, CharacterCollection};
 pub use outline::OutlineFontData;
 pub use standard_font::StandardFont;
 
 /// A glyph that can be drawn.
+#[derive(Clone)]
 pub enum Glyph<'a> {
     /// A glyph defined by an outline.
     Outline(OutlineGlyph),
@@ -121,17 +147,44 @@ impl CacheKey for GlyphIdentifier {
 }
 
 /// A glyph defined by an outline.
+///
+/// Clients that rasterize glyphs themselves can get the outline of the glyph via
+/// [`OutlineGlyph::outline`]. The outline is in glyph space, which is mapped to
+/// device space by `props.transform * glyph_transform`, where both are the
+/// arguments that were passed to [`Device::draw_glyph`].
 #[derive(Clone, Debug)]
 pub struct OutlineGlyph {
     pub(crate) id: GlyphId,
     pub(crate) font: OutlineFont,
     pub(crate) char_code: u32,
+    /// The advance width from [`InterpreterSettings::advance_override`], if
+    /// it overrides the one of the font.
+    ///
+    /// [`InterpreterSettings::advance_override`]: crate::InterpreterSettings::advance_override
+    pub(crate) advance_override: Option<f32>,
 }
 
 impl OutlineGlyph {
-    /// Return the outline of the glyph, assuming an upem value of 1000.
+    /// Return the outline of the glyph in glyph space.
+    ///
+    /// Independently of the units per em of the underlying font, the outline is
+    /// scaled so that an em spans [`OutlineGlyph::units_per_em`] units. The y-axis
+    /// points upwards and the origin of the glyph is at `(0, 0)`. In case the
+    /// PDF overrides the width of the glyph, the outline might already be
+    /// stretched to match it.
     pub fn outline(&self) -> BezPath {
-        self.font.outline_glyph(self.id, self.char_code)
+        match (&self.font, self.advance_override) {
+            (OutlineFont::Fallback(f), Some(width)) => f.outline_glyph(self.id, Some(width)),
+            _ => self.font.outline_glyph(self.id, self.char_code),
+        }
+    }
+
+    /// Return the number of units per em of the coordinate system of
+    /// [`OutlineGlyph::outline`].
+    ///
+    /// This is currently always 1000.
+    pub fn units_per_em(&self) -> u16 {
+        UNITS_PER_EM as u16
     }
 
     /// Return the identifier of the glyph. You can use this to calculate the cache key
@@ -155,7 +208,8 @@ impl OutlineGlyph {
 
     /// Get raw font bytes and metadata for downstream use.
     ///
-    /// Returns `None` for Type1 fonts.
+    /// Returns `None` for Type1 fonts and for glyphs that are missing from their
+    /// font and drawn with a fallback font instead.
     pub fn font_data(&self) -> Option<OutlineFontData> {
         self.font.font_data()
     }
@@ -168,15 +222,30 @@ impl OutlineGlyph {
     /// Get the advance width for this glyph.
     ///
     /// The advance width is how far to move horizontally after drawing
-    /// this glyph before drawing the next one.
+    /// this glyph before drawing the next one. This takes
+    /// [`InterpreterSettings::advance_override`] into account.
+    ///
+    /// [`InterpreterSettings::advance_override`]: crate::InterpreterSettings::advance_override
     pub fn advance_width(&self) -> Option<f32> {
-        self.font.glyph_advance_width(self.char_code)
+        self.advance_override
+            .or_else(|| self.font.glyph_advance_width(self.char_code))
+    }
+
+    /// Return the width of the dominant vertical stems of the font, assuming an
+    /// upem value of 1000, if known.
+    ///
+    /// The width is read from the private dictionary of CFF fonts, or otherwise
+    /// from the `StemV` entry of the font descriptor.
+    pub fn stem_width(&self) -> Option<f32> {
+        self.font.stem_width()
     }
 
     /// Get the cache key for this glyph's font.
     ///
     /// This identifies the font uniquely, even when `font_data()` returns `None`
-    /// (e.g., for Type1 fonts). Useful for grouping glyphs by font.
+    /// (e.g., for Type1 fonts). Useful for grouping glyphs by font. Together with
+    /// [`OutlineGlyph::glyph_id`], it can be used as the key for caching outlines,
+    /// which is what [`OutlineGlyph::identifier`] does.
     pub fn font_cache_key(&self) -> u128 {
         self.font.cache_key()
     }
@@ -193,6 +262,7 @@ pub struct Type3Glyph<'a> {
     pub(crate) xref: &'a XRef,
     pub(crate) settings: InterpreterSettings,
     pub(crate) nesting_depth: u32,
+    pub(crate) active_streams: Vec<ObjectIdentifier>,
     pub(crate) char_code: u32,
 }
 
@@ -225,26 +295,26 @@ impl CacheKey for Type3Glyph<'_> {
 }
 
 #[derive(Clone, Debug)]
-pub(crate) struct Font<'a>(u128, FontType<'a>);
+pub(crate) struct Font<'a>(u128, FontType<'a>, Dict<'a>);
 
 impl<'a> Font<'a> {
     pub(crate) fn new(
         dict: &Dict<'a>,
         font_resolver: &FontResolverFn,
-        cmap_resolver: &CMapResolverFn,
+        cmap_provider: &dyn CMapProvider,
     ) -> Option<Self> {
         let f_type = match dict.get::<Name<'_>>(SUBTYPE)?.deref() {
             TYPE1 | MM_TYPE1 => {
-                FontType::Type1(Rc::new(Type1Font::new(dict, font_resolver, cmap_resolver)?))
+                FontType::Type1(Rc::new(Type1Font::new(dict, font_resolver, cmap_provider)?))
             }
             // PDFBOX-5463: PDF viewers seem to accept OpenType as well.
             TRUE_TYPE | OPEN_TYPE => FontType::TrueType(Rc::new(TrueTypeFont::new(
                 dict,
                 font_resolver,
-                cmap_resolver,
+                cmap_provider,
             )?)),
-            TYPE0 => FontType::Type0(Rc::new(Type0Font::new(dict, font_resolver, cmap_resolver)?)),
-            TYPE3 => FontType::Type3(Rc::new(Type3::new(dict, cmap_resolver)?)),
+            TYPE0 => FontType::Type0(Rc::new(Type0Font::new(dict, font_resolver, cmap_provider)?)),
+            TYPE3 => FontType::Type3(Rc::new(Type3::new(dict, cmap_provider)?)),
             f => {
                 warn!(
                     "unimplemented font type {:?}",
@@ -257,7 +327,7 @@ impl<'a> Font<'a> {
 
         let cache_key = dict.cache_key();
 
-        Some(Self(cache_key, f_type))
+        Some(Self(cache_key, f_type, dict.clone()))
     }
 
     pub(crate) fn new_standard(
@@ -266,7 +336,11 @@ impl<'a> Font<'a> {
     ) -> Option<Self> {
         let font = Type1Font::new_standard(standard_font, font_resolver)?;
 
-        Some(Self(0, FontType::Type1(Rc::new(font))))
+        Some(Self(
+            font.cache_key(),
+            FontType::Type1(Rc::new(font)),
+            Dict::empty(),
+        ))
     }
 
     pub(crate) fn map_code(&self, code: u32) -> GlyphId {
@@ -290,6 +364,29 @@ impl<'a> Font<'a> {
         }
     }
 
+    /// Map a character to a glyph. Only supported for standard fonts.
+    pub(crate) fn map_unicode(&self, c: char) -> Option<GlyphId> {
+        match &self.1 {
+            FontType::Type1(t) => t.map_unicode(c),
+            FontType::TrueType(_) | FontType::Type0(_) | FontType::Type3(_) => None,
+        }
+    }
+
+    /// Return the query for a font that can be used in place of this one.
+    pub(crate) fn fallback_query(&self) -> FallbackFontQuery {
+        let mut query = FallbackFontQuery::new(&self.2);
+
+        if let FontType::Type0(t) = &self.1 {
+            query.character_collection = t.character_collection().cloned();
+        }
+
+        query
+    }
+
+    pub(crate) fn is_type3(&self) -> bool {
+        matches!(self.1, FontType::Type3(_))
+    }
+
     pub(crate) fn get_glyph(
         &self,
         glyph: GlyphId,
@@ -309,6 +406,7 @@ impl<'a> Font<'a> {
                     id: glyph,
                     font,
                     char_code,
+                    advance_override: None,
                 })
             }
             FontType::TrueType(t) => {
@@ -317,6 +415,7 @@ impl<'a> Font<'a> {
                     id: glyph,
                     font,
                     char_code,
+                    advance_override: None,
                 })
             }
             FontType::Type0(t) => {
@@ -325,6 +424,7 @@ impl<'a> Font<'a> {
                     id: glyph,
                     font,
                     char_code,
+                    advance_override: None,
                 })
             }
             FontType::Type3(t) => {
@@ -338,6 +438,7 @@ impl<'a> Font<'a> {
                     xref: ctx.xref,
                     settings: ctx.settings.clone(),
                     nesting_depth,
+                    active_streams: ctx.active_streams().to_vec(),
                     char_code,
                 };
 
@@ -647,20 +748,16 @@ pub(crate) fn unicode_from_name(name: &str) -> Option<char> {
         .flatten()
 }
 
-pub(crate) fn read_to_unicode(dict: &Dict<'_>, cmap_resolver: &CMapResolverFn) -> Option<CMap> {
+pub(crate) fn read_to_unicode(dict: &Dict<'_>, cmap_provider: &dyn CMapProvider) -> Option<CMap> {
     dict.get::<Stream<'_>>(TO_UNICODE)
         .and_then(|s| s.decoded().ok())
         // See PDFJS-11915, where `Identity-H` is used for `ToUnicode`. I don't
         // believe it's valid, but at least mupdf seems to be able to deal with it.
         .or_else(|| {
             dict.get::<Name<'_>>(TO_UNICODE)
-                .and_then(|name| (cmap_resolver)(CMapName::from_bytes(name.as_ref())))
-                .map(|d| Cow::Owned(d.to_vec()))
-        })
-        .and_then(|data| {
-            let cmap_resolver = cmap_resolver.clone();
-            CMap::parse(&data, move |name| (cmap_resolver)(name))
+                .and_then(|name| cmap_provider.load_cmap(CMapName::from_bytes(name.as_ref())))
         })
+        .and_then(|data| CMap::parse_with_provider(&data, cmap_provider))
 }
 
 // When mapping to glyphs, some fonts might only have a glyph for the "normalized"
@@ -1,5 +1,6 @@
 use crate::CacheKey;
 use crate::font::cid::Type0Font;
+use crate::font::fallback::FallbackFont;
 use crate::font::true_type::TrueTypeFont;
 use crate::font::type1::Type1Font;
 use hayro_cmap::BfString;
@@ -79,6 +80,7 @@ pub(crate) enum OutlineFont {
     Type1(Rc<Type1Font>),
     TrueType(Rc<TrueTypeFont>),
     Type0(Rc<Type0Font>),
+    Fallback(Rc<FallbackFont>),
 }
 
 impl CacheKey for OutlineFont {
@@ -87,6 +89,7 @@ impl CacheKey for OutlineFont {
             Self::Type1(f) => f.cache_key(),
             Self::TrueType(t) => t.cache_key(),
             Self::Type0(t0) => t0.cache_key(),
+            Self::Fallback(f) => f.cache_key(),
         }
     }
 }
@@ -97,6 +100,7 @@ impl OutlineFont {
             Self::Type1(t) => t.outline_glyph(glyph),
             Self::TrueType(t) => t.outline_glyph(glyph),
             Self::Type0(t) => t.outline_glyph(glyph, code),
+            Self::Fallback(f) => f.outline_glyph(glyph, f.glyph_advance_width(code)),
         }
     }
 
@@ -105,6 +109,16 @@ impl OutlineFont {
             Self::Type1(t) => t.char_code_to_unicode(char_code),
             Self::TrueType(t) => t.char_code_to_unicode(char_code),
             Self::Type0(t) => t.char_code_to_unicode(char_code),
+            Self::Fallback(f) => f.char_code_to_unicode(char_code),
+        }
+    }
+
+    pub(crate) fn stem_width(&self) -> Option<f32> {
+        match self {
+            Self::Type1(t) => t.stem_width(),
+            Self::TrueType(t) => t.stem_width(),
+            Self::Type0(t) => t.stem_width(),
+            Self::Fallback(f) => f.stem_width(),
         }
     }
 
@@ -114,15 +128,16 @@ impl OutlineFont {
             Self::Type1(t) => t.glyph_width(char_code as u8),
             Self::TrueType(t) => Some(t.glyph_width(char_code as u8)),
             Self::Type0(t) => Some(t.code_advance(char_code).x as f32),
+            Self::Fallback(f) => f.glyph_advance_width(char_code),
         }
     }
 
     /// Get raw font bytes and metadata.
     ///
-    /// Returns None for Type1 fonts and non-embedded TrueType fonts.
+    /// Returns None for Type1 fonts, non-embedded TrueType fonts and fallback fonts.
     pub(crate) fn font_data(&self) -> Option<OutlineFontData> {
         match self {
-            Self::Type1(_) => None,
+            Self::Type1(_) | Self::Fallback(_) => None,
             Self::TrueType(t) => Some(OutlineFontData {
                 data: t.font_data()?,
                 cache_key: t.cache_key(),
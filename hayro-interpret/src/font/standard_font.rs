@@ -16,7 +16,7 @@ use skrifa::raw::TableProvider;
 use std::cell::RefCell;
 
 /// The 14 standard fonts of PDF.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum StandardFont {
     /// Helvetica.
     Helvetica,
@@ -207,6 +207,25 @@ enum StandardFontFamily {
     Times,
 }
 
+impl StandardFontFamily {
+    fn with_style(self, is_bold: bool, is_italic: bool) -> StandardFont {
+        match (self, is_bold, is_italic) {
+            (Self::Helvetica, false, false) => StandardFont::Helvetica,
+            (Self::Helvetica, true, false) => StandardFont::HelveticaBold,
+            (Self::Helvetica, false, true) => StandardFont::HelveticaOblique,
+            (Self::Helvetica, true, true) => StandardFont::HelveticaBoldOblique,
+            (Self::Courier, false, false) => StandardFont::Courier,
+            (Self::Courier, true, false) => StandardFont::CourierBold,
+            (Self::Courier, false, true) => StandardFont::CourierOblique,
+            (Self::Courier, true, true) => StandardFont::CourierBoldOblique,
+            (Self::Times, false, false) => StandardFont::TimesRoman,
+            (Self::Times, true, false) => StandardFont::TimesBold,
+            (Self::Times, false, true) => StandardFont::TimesItalic,
+            (Self::Times, true, true) => StandardFont::TimesBoldItalic,
+        }
+    }
+}
+
 pub(crate) fn select_standard_font(
     dict: &Dict<'_>,
     descriptor: &Dict<'_>,
@@ -233,6 +252,11 @@ pub(crate) fn select_standard_font(
         _ => {}
     }
 
+    // Then, check for common aliases of the standard fonts.
+    if let Some(alias) = standard_font_alias(name) {
+        return Some(alias);
+    }
+
     // Now, we bruteforce, trying to determine a suitable font based on the
     // keywords that appear in the name.
     let lower = name.to_ascii_lowercase();
@@ -264,24 +288,50 @@ pub(crate) fn select_standard_font(
         (None, false)
     };
 
-    let font = match (family?, is_bold, is_italic) {
-        (StandardFontFamily::Helvetica, false, false) => StandardFont::Helvetica,
-        (StandardFontFamily::Helvetica, true, false) => StandardFont::HelveticaBold,
-        (StandardFontFamily::Helvetica, false, true) => StandardFont::HelveticaOblique,
-        (StandardFontFamily::Helvetica, true, true) => StandardFont::HelveticaBoldOblique,
-        (StandardFontFamily::Courier, false, false) => StandardFont::Courier,
-        (StandardFontFamily::Courier, true, false) => StandardFont::CourierBold,
-        (StandardFontFamily::Courier, false, true) => StandardFont::CourierOblique,
-        (StandardFontFamily::Courier, true, true) => StandardFont::CourierBoldOblique,
-        (StandardFontFamily::Times, false, false) => StandardFont::TimesRoman,
-        (StandardFontFamily::Times, true, false) => StandardFont::TimesBold,
-        (StandardFontFamily::Times, false, true) => StandardFont::TimesItalic,
-        (StandardFontFamily::Times, true, true) => StandardFont::TimesBoldItalic,
-    };
+    let font = family?.with_style(is_bold, is_italic);
 
     Some((font, exact))
 }
 
+/// Resolve common aliases of the standard fonts, like `Arial,Bold` or
+/// `TimesNewRomanPS-BoldItalicMT`, whose metrics are compatible with the
+/// corresponding standard font.
+///
+/// The returned flag indicates whether the alias names the standard font itself,
+/// like `Helvetica,Bold`. Fonts like Arial only have similar metrics, so their
+/// `Widths` array should still be respected.
+fn standard_font_alias(name: &str) -> Option<(StandardFont, bool)> {
+    fn strip_mt_suffix(name: &str) -> &str {
+        name.strip_suffix("PSMT")
+            .or_else(|| name.strip_suffix("MT"))
+            .or_else(|| name.strip_suffix("PS"))
+            .unwrap_or(name)
+    }
+
+    let (family, style) = name.split_once([',', '-']).unwrap_or((name, ""));
+
+    let (family, exact) = match strip_mt_suffix(family) {
+        "Arial" => (StandardFontFamily::Helvetica, false),
+        "Helvetica" => (StandardFontFamily::Helvetica, true),
+        "CourierNew" => (StandardFontFamily::Courier, false),
+        "Courier" => (StandardFontFamily::Courier, true),
+        "TimesNewRoman" => (StandardFontFamily::Times, false),
+        "Times" => (StandardFontFamily::Times, true),
+        "Symbol" => return Some((StandardFont::Symbol, true)),
+        _ => return None,
+    };
+
+    let (is_bold, is_italic) = match strip_mt_suffix(style) {
+        "" | "Roman" | "Regular" => (false, false),
+        "Bold" => (true, false),
+        "Italic" | "Oblique" => (false, true),
+        "BoldItalic" | "BoldOblique" => (true, true),
+        _ => return None,
+    };
+
+    Some((family.with_style(is_bold, is_italic), exact))
+}
+
 #[derive(Debug)]
 pub(crate) enum StandardFontBlob {
     Cff(CffFontBlob),
@@ -333,6 +383,13 @@ impl StandardFontBlob {
         }
     }
 
+    pub(crate) fn std_vw(&self) -> Option<f32> {
+        match self {
+            Self::Cff(blob) => blob.std_vw(),
+            Self::Otf(..) => None,
+        }
+    }
+
     pub(crate) fn outline_glyph(&self, glyph: GlyphId) -> BezPath {
         // Standard fonts have empty outlines for these, but in Liberation Sans
         // they are a .notdef rectangle.
@@ -427,6 +484,13 @@ impl StandardKind {
         result
     }
 
+    pub(crate) fn map_unicode(&self, c: char) -> Option<GlyphId> {
+        glyph_names::get_reverse(c)
+            .and_then(|name| self.base_font_blob.name_to_glyph(name))
+            .or_else(|| self.base_font_blob.unicode_to_glyph(c as u32))
+            .filter(|g| *g != GlyphId::NOTDEF)
+    }
+
     pub(crate) fn outline_glyph(&self, glyph: GlyphId) -> BezPath {
         let path = self.base_font_blob.outline_glyph(glyph);
 
@@ -444,7 +508,9 @@ impl StandardKind {
             // different widths are indicated there. So only if it's an unknown
             // font do we check the widths array. Otherwise, we always use the
             // base font metrics.
-            let should_width = if self.fallback {
+            // If there is no widths array at all, the advances are taken from the
+            // standard font metrics as well, so the glyph should match those.
+            let should_width = if self.fallback && !self.widths.is_empty() {
                 if let Some(Width::Value(w)) = self.widths.get(code as usize).copied() {
                     w
                 } else {
@@ -465,6 +531,10 @@ impl StandardKind {
         path
     }
 
+    pub(crate) fn stem_width(&self) -> Option<f32> {
+        self.base_font_blob.std_vw()
+    }
+
     pub(crate) fn glyph_width(&self, code: u8) -> Option<f32> {
         match self.widths.get(code as usize).copied() {
             Some(Width::Value(w)) => Some(w),
@@ -2,11 +2,12 @@ use crate::font::blob::{CffFontBlob, OpenTypeFontBlob};
 use crate::font::generated::{glyph_names, mac_os_roman, mac_roman, standard};
 use crate::font::standard_font::StandardKind;
 use crate::font::{
-    Encoding, FallbackFontQuery, FontFlags, glyph_name_to_unicode, read_to_unicode,
+    Encoding, FallbackFontQuery, FontFlags, glyph_name_to_unicode, read_stem_v, read_to_unicode,
     strip_subset_prefix, unicode_from_name,
 };
 use crate::util::OptionLog;
-use crate::{CMapResolverFn, CacheKey, FontResolverFn};
+use crate::{CacheKey, FontResolverFn};
+use hayro_cmap::CMapProvider;
 use hayro_cmap::{BfString, CMap};
 use hayro_syntax::object::Array;
 use hayro_syntax::object::Dict;
@@ -41,10 +42,10 @@ impl TrueTypeFont {
     pub(crate) fn new(
         dict: &Dict<'_>,
         font_resolver: &FontResolverFn,
-        cmap_resolver: &CMapResolverFn,
+        cmap_provider: &dyn CMapProvider,
     ) -> Option<Self> {
         let cache_key = dict.cache_key();
-        let to_unicode = read_to_unicode(dict, cmap_resolver);
+        let to_unicode = read_to_unicode(dict, cmap_provider);
 
         if let Some(embedded) = EmbeddedKind::new(dict) {
             return Some(Self {
@@ -175,6 +176,13 @@ impl TrueTypeFont {
         }
     }
 
+    pub(crate) fn stem_width(&self) -> Option<f32> {
+        match &self.kind {
+            Kind::Embedded(e) => e.cff_blob.as_ref().and_then(|b| b.std_vw()).or(e.stem_v),
+            Kind::Standard(s) => s.stem_width(),
+        }
+    }
+
     pub(crate) fn glyph_width(&self, code: u8) -> f32 {
         match &self.kind {
             Kind::Embedded(e) => e.glyph_width(code),
@@ -219,6 +227,7 @@ struct EmbeddedKind {
     cached_mappings: RefCell<FxHashMap<u8, GlyphId>>,
     /// PostScript name from the PDF.
     postscript_name: Option<String>,
+    stem_v: Option<f32>,
 }
 
 impl EmbeddedKind {
@@ -257,6 +266,7 @@ impl EmbeddedKind {
             encoding,
             cached_mappings: RefCell::new(FxHashMap::default()),
             postscript_name,
+            stem_v: read_stem_v(&descriptor),
         })
     }
 
@@ -277,6 +287,15 @@ impl EmbeddedKind {
             .or_else(|| standard::get(code))
     }
 
+    /// Like `code_to_name`, but only considers the encoding if it has been
+    /// specified explicitly in the PDF.
+    fn explicit_code_to_name(&self, code: u8) -> Option<&str> {
+        self.differences
+            .get(&code)
+            .map(|s| s.as_str())
+            .or_else(|| self.encoding.map_code(code))
+    }
+
     fn outline_glyph(&self, glyph: GlyphId) -> BezPath {
         self.base_font.outline_glyph(glyph)
     }
@@ -340,25 +359,58 @@ impl EmbeddedKind {
                 }
             }
         } else if let Ok(cmap) = self.base_font.font_ref().cmap() {
-            for record in cmap.encoding_records() {
-                if record.platform_id() == PlatformId::Windows
-                    && matches!(record.encoding_id(), 0 | 1)
-                {
-                    if let Ok(subtable) = record.subtable(cmap.offset_data()) {
-                        for offset in [0x0000_u32, 0xF000, 0xF100, 0xF200] {
-                            glyph = glyph
-                                .or_else(|| subtable.map_codepoint(code as u32 + offset))
-                                .filter(|g| *g != GlyphId::NOTDEF);
-                        }
-                    }
-                } else if matches!(
-                    record.platform_id(),
-                    PlatformId::Macintosh | PlatformId::Unicode
-                ) && record.encoding_id() == 0
-                    && let Ok(subtable) = record.subtable(cmap.offset_data())
-                {
+            // Symbolic fonts should ignore the encoding according to the spec, but Acrobat
+            // (and other viewers) do take it into account in case the code cannot be resolved
+            // via the symbol or Mac subtables. The order is decided for each glyph separately,
+            // since there are fonts that need different subtables for different codes.
+            let subtables = |platform_id: PlatformId, encoding_id: u16| {
+                cmap.encoding_records()
+                    .iter()
+                    .filter(move |r| {
+                        r.platform_id() == platform_id && r.encoding_id() == encoding_id
+                    })
+                    .filter_map(|r| r.subtable(cmap.offset_data()).ok())
+            };
+
+            // 1) The (3,0) symbol subtable, possibly with the characters moved into the
+            // private use area.
+            for subtable in subtables(PlatformId::Windows, 0) {
+                for offset in [0x0000_u32, 0xF000, 0xF100, 0xF200] {
+                    glyph = glyph
+                        .or_else(|| subtable.map_codepoint(code as u32 + offset))
+                        .filter(|g| *g != GlyphId::NOTDEF);
+                }
+            }
+
+            // 2) The (1,0) Mac subtable (or the (0,0) Unicode one).
+            for subtable in
+                subtables(PlatformId::Macintosh, 0).chain(subtables(PlatformId::Unicode, 0))
+            {
+                glyph = glyph
+                    .or_else(|| subtable.map_codepoint(code))
+                    .filter(|g| *g != GlyphId::NOTDEF);
+            }
+
+            // 3) The (3,1) Unicode subtable, using the glyph name from an explicit
+            // encoding or the differences.
+            if glyph.is_none()
+                && let Some(lookup) = self.explicit_code_to_name(code)
+                && let Some(c) = glyph_names::get(lookup)
+                    .and_then(|n| n.chars().next())
+                    .or_else(|| unicode_from_name(lookup))
+            {
+                for subtable in subtables(PlatformId::Windows, 1) {
+                    glyph = glyph
+                        .or_else(|| subtable.map_codepoint(c))
+                        .filter(|g| *g != GlyphId::NOTDEF);
+                }
+            }
+
+            // 4) As a last resort, use the code as is with the (3,1) subtable.
+            for subtable in subtables(PlatformId::Windows, 1) {
+                for offset in [0x0000_u32, 0xF000, 0xF100, 0xF200] {
                     glyph = glyph
-                        .or_else(|| subtable.map_codepoint(code))
+                        .or_else(|| subtable.map_codepoint(code as u32 + offset))
                         .filter(|g| *g != GlyphId::NOTDEF);
                 }
             }
@@ -2,9 +2,12 @@ use crate::font::blob::{CffFontBlob, Type1FontBlob};
 use crate::font::standard_font::{StandardFont, StandardKind, select_standard_font};
 use crate::font::true_type::{Width, read_encoding, read_widths};
 use crate::font::{
-    Encoding, FallbackFontQuery, glyph_name_to_unicode, normalized_glyph_name, read_to_unicode,
+    Encoding, FallbackFontQuery, glyph_name_to_unicode, normalized_glyph_name, read_stem_v,
+    read_to_unicode,
 };
-use crate::{CMapResolverFn, CacheKey, FontResolverFn};
+use crate::util::hash128;
+use crate::{CacheKey, FontResolverFn};
+use hayro_cmap::CMapProvider;
 use hayro_cmap::{BfString, CMap};
 use hayro_syntax::object::Dict;
 use hayro_syntax::object::Stream;
@@ -21,11 +24,11 @@ impl Type1Font {
     pub(crate) fn new(
         dict: &Dict<'_>,
         resolver: &FontResolverFn,
-        cmap_resolver: &CMapResolverFn,
+        cmap_provider: &dyn CMapProvider,
     ) -> Option<Self> {
         let cache_key = dict.cache_key();
 
-        let to_unicode = read_to_unicode(dict, cmap_resolver);
+        let to_unicode = read_to_unicode(dict, cmap_provider);
 
         let fallback = || {
             // TODO: Actually use fallback fonts
@@ -77,7 +80,11 @@ impl Type1Font {
         let dict = Dict::default();
         let standard = StandardKind::new_with_standard(&dict, font, true, resolver)?;
 
-        Some(Self(0, Kind::Standard(standard), None))
+        Some(Self(
+            hash128(&font.postscript_name()),
+            Kind::Standard(standard),
+            None,
+        ))
     }
 
     pub(crate) fn map_code(&self, code: u8) -> GlyphId {
@@ -88,6 +95,14 @@ impl Type1Font {
         }
     }
 
+    /// Map a character to a glyph. Only supported for standard fonts.
+    pub(crate) fn map_unicode(&self, c: char) -> Option<GlyphId> {
+        match &self.1 {
+            Kind::Standard(s) => s.map_unicode(c),
+            Kind::Type1(_) | Kind::Cff(_) => None,
+        }
+    }
+
     pub(crate) fn outline_glyph(&self, glyph: GlyphId) -> BezPath {
         match &self.1 {
             Kind::Standard(s) => s.outline_glyph(glyph),
@@ -96,6 +111,14 @@ impl Type1Font {
         }
     }
 
+    pub(crate) fn stem_width(&self) -> Option<f32> {
+        match &self.1 {
+            Kind::Standard(s) => s.stem_width(),
+            Kind::Cff(c) => c.font.std_vw().or(c.stem_v),
+            Kind::Type1(t) => t.stem_v,
+        }
+    }
+
     pub(crate) fn glyph_width(&self, code: u8) -> Option<f32> {
         match &self.1 {
             Kind::Standard(s) => s.glyph_width(code),
@@ -159,6 +182,7 @@ struct Type1Kind {
     encodings: FxHashMap<u8, String>,
     name_to_gid: FxHashMap<String, GlyphId>,
     standard_font: Option<StandardFont>,
+    stem_v: Option<f32>,
 }
 
 impl Type1Kind {
@@ -170,6 +194,7 @@ impl Type1Kind {
         let (encoding, encodings) = read_encoding(dict);
         let (widths, missing_width) = read_widths(dict, &descriptor)?;
         let standard_font = select_standard_font(dict, &descriptor).map(|(f, _)| f);
+        let stem_v = read_stem_v(&descriptor);
 
         let name_to_gid: FxHashMap<String, GlyphId> = font
             .table()
@@ -185,6 +210,7 @@ impl Type1Kind {
             encodings,
             name_to_gid,
             standard_font,
+            stem_v,
         })
     }
 
@@ -253,6 +279,7 @@ struct CffKind {
     name_to_gid: FxHashMap<String, GlyphId>,
     gid_to_name: Vec<Option<String>>,
     standard_font: Option<StandardFont>,
+    stem_v: Option<f32>,
 }
 
 impl CffKind {
@@ -264,6 +291,7 @@ impl CffKind {
         let (encoding, encodings) = read_encoding(dict);
         let (widths, missing_width) = read_widths(dict, &descriptor)?;
         let standard_font = select_standard_font(dict, &descriptor).map(|(f, _)| f);
+        let stem_v = read_stem_v(&descriptor);
         let mut gid_to_name = vec![None; font.num_glyphs() as usize];
         let name_to_gid: FxHashMap<String, GlyphId> = font
             .glyph_names()
@@ -281,6 +309,7 @@ impl CffKind {
             name_to_gid,
             gid_to_name,
             standard_font,
+            stem_v,
         })
     }
 
@@ -1,5 +1,4 @@
-use crate::CMapResolverFn;
-use crate::context::Context;
+use crate::context::{Context, check_cycle, check_nesting_depth};
 use crate::device::Device;
 use crate::font::glyph_simulator::GlyphSimulator;
 use crate::font::true_type::{Width, read_encoding, read_widths};
@@ -10,6 +9,7 @@ use crate::util::RectExt;
 use crate::{BlendMode, interpret};
 use crate::{CacheKey, ClipPath, DrawMode, DrawProps, ImageDrawProps};
 use crate::{Image, Paint};
+use hayro_cmap::CMapProvider;
 use hayro_cmap::{BfString, CMap};
 use hayro_syntax::content::TypedIter;
 use hayro_syntax::content::ops::TypedInstruction;
@@ -36,7 +36,7 @@ pub(crate) struct Type3<'a> {
 }
 
 impl<'a> Type3<'a> {
-    pub(crate) fn new(dict: &Dict<'a>, cmap_resolver: &CMapResolverFn) -> Option<Self> {
+    pub(crate) fn new(dict: &Dict<'a>, cmap_provider: &dyn CMapProvider) -> Option<Self> {
         let (encoding, encodings) = read_encoding(dict);
         let (widths, missing_width) = read_widths(dict, dict)?;
         let font_bbox = dict
@@ -62,7 +62,7 @@ impl<'a> Type3<'a> {
             procs
         };
 
-        let to_unicode = read_to_unicode(dict, cmap_resolver);
+        let to_unicode = read_to_unicode(dict, cmap_provider);
 
         Some(Self {
             glyph_simulator: GlyphSimulator::new(),
@@ -110,6 +110,10 @@ impl<'a> Type3<'a> {
         paint: &Paint<'a>,
         device: &mut impl Device<'a>,
     ) -> Option<()> {
+        if !check_nesting_depth(glyph.nesting_depth, &glyph.settings) {
+            return None;
+        }
+
         let mut state = glyph.state.clone();
         let root_transform =
             transform * glyph_transform * self.matrix * Affine::scale(UNITS_PER_EM as f64);
@@ -121,6 +125,11 @@ impl<'a> Type3<'a> {
 
         let name = self.glyph_simulator.glyph_to_string(glyph.glyph_id)?;
         let program = self.char_procs.get(&name)?;
+
+        if !check_cycle(&glyph.active_streams, program.obj_id()) {
+            return None;
+        }
+
         let decoded = program.decoded().ok()?;
         let iter = TypedIter::new(decoded.as_ref());
 
@@ -152,6 +161,12 @@ impl<'a> Type3<'a> {
             glyph.settings.clone(),
             state,
             glyph.nesting_depth,
+            glyph
+                .active_streams
+                .iter()
+                .copied()
+                .chain([program.obj_id()])
+                .collect(),
         );
 
         let mut resources = Resources::from_parent(
@@ -18,6 +18,8 @@ use hayro_syntax::object::{Object, dict_or_stream};
 use smallvec::SmallVec;
 use std::sync::Arc;
 
+pub use type0::SampleCache;
+
 /// The input/output type of functions.
 pub(crate) type Values = SmallVec<[f32; 4]>;
 pub(crate) type StitchingBounds = SmallVec<[f32; 3]>;
@@ -54,13 +56,49 @@ impl Function {
     /// Evaluate the function with the given input.
     pub fn eval(&self, input: Values) -> Option<Values> {
         match self.0.as_ref() {
-            FunctionType::Type0(t0) => t0.eval(input),
+            FunctionType::Type0(t0) => match (t0.sample_cache(), input.as_slice()) {
+                (Some(cache), [x]) => Some(cache.eval(*x)),
+                _ => t0.eval(input),
+            },
             FunctionType::Type2(t2) => Some(t2.eval(*input.first()?)),
             FunctionType::Type3(t3) => t3.eval(*input.first()?),
             FunctionType::Type4(t4) => Some(t4.eval(input)?),
         }
     }
 
+    /// Return the domain of the function, i.e. the interval each input is clamped to.
+    pub fn domain(&self) -> &[(f32, f32)] {
+        &self.clamper().domain
+    }
+
+    /// Return the range of the function, i.e. the interval each output is clamped to.
+    ///
+    /// The range is optional for exponential interpolation, stitching and PostScript
+    /// calculator functions.
+    pub fn range(&self) -> Option<&[(f32, f32)]> {
+        self.clamper().range.as_deref()
+    }
+
+    /// Return a lookup table for evaluating the function more quickly.
+    ///
+    /// This is only available for sampled functions with a single input. Note that
+    /// [`Function::eval`] already makes use of it.
+    pub fn sample_cache(&self) -> Option<&SampleCache> {
+        match self.0.as_ref() {
+            FunctionType::Type0(t0) => t0.sample_cache(),
+            _ => None,
+        }
+    }
+
+    fn clamper(&self) -> &Clamper {
+        match self.0.as_ref() {
+            FunctionType::Type0(t0) => &t0.clamper,
+            FunctionType::Type2(t2) => &t2.clamper,
+            FunctionType::Type3(t3) => &t3.clamper,
+            FunctionType::Type4(t4) => &t4.clamper,
+        }
+    }
+
     pub(crate) fn stitching_bounds(&self) -> StitchingBounds {
         match self.0.as_ref() {
             FunctionType::Type3(t3) => t3.stitching_bounds(),
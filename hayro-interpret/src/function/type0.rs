@@ -5,17 +5,19 @@ use hayro_syntax::object::Stream;
 use hayro_syntax::object::dict::keys::{BITS_PER_SAMPLE, DECODE, ENCODE, SIZE};
 use rustc_hash::FxHashMap;
 use smallvec::{SmallVec, ToSmallVec, smallvec};
+use std::sync::Arc;
 
 /// A type 0 function (sampled function).
 #[derive(Debug)]
 pub(crate) struct Type0 {
     sizes: IntVec,
     table: FxHashMap<Key, IntVec>,
-    clamper: Clamper,
+    pub(super) clamper: Clamper,
     range: TupleVec,
     bits_per_sample: u8,
     encode: TupleVec,
     decode: TupleVec,
+    sample_cache: Option<SampleCache>,
 }
 
 impl Type0 {
@@ -71,7 +73,7 @@ impl Type0 {
 
         let table = build_table(&data, &sizes, range.len())?;
 
-        Some(Self {
+        let mut function = Self {
             sizes,
             clamper,
             range,
@@ -79,7 +81,16 @@ impl Type0 {
             table,
             encode,
             decode,
-        })
+            sample_cache: None,
+        };
+        function.sample_cache = SampleCache::new(&function);
+
+        Some(function)
+    }
+
+    /// Return the lookup table of the function, if it only has a single input.
+    pub(crate) fn sample_cache(&self) -> Option<&SampleCache> {
+        self.sample_cache.as_ref()
     }
 
     /// Evaluate a type 0 function with the given input.
@@ -137,6 +148,95 @@ impl Type0 {
     }
 }
 
+/// A lookup table for quickly evaluating a sampled function with a single input.
+///
+/// In general, evaluating a sampled function requires multilinear interpolation
+/// between the samples that surround the input. With a single input, the function
+/// is piecewise linear, so the decoded samples can be stored in a flat table and
+/// interpolated directly. This is much faster when a function is evaluated many
+/// times, for example when rendering a gradient.
+#[derive(Debug, Clone)]
+pub struct SampleCache {
+    domain: (f32, f32),
+    encode: (f32, f32),
+    range: TupleVec,
+    // The decoded samples, with one value per output for each sample.
+    samples: Arc<[f32]>,
+}
+
+impl SampleCache {
+    fn new(function: &Type0) -> Option<Self> {
+        let [size] = function.sizes.as_slice() else {
+            return None;
+        };
+        let num_outputs = function.range.len();
+
+        if *size == 0 || function.decode.len() != num_outputs {
+            return None;
+        }
+
+        let max_sample = ((1_u64 << function.bits_per_sample) - 1) as f32;
+        let mut samples = Vec::with_capacity(*size as usize * num_outputs);
+
+        for i in 0..*size {
+            // In case the stream has fewer samples than expected, the function
+            // can't be evaluated for all inputs anyway.
+            let values = function.table.get(&Key::from_raw(&function.sizes, &[i]))?;
+
+            samples.extend(
+                values
+                    .iter()
+                    .zip(function.decode.iter())
+                    .map(|(v, decode)| interpolate(*v as f32, 0.0, max_sample, decode.0, decode.1)),
+            );
+        }
+
+        Some(Self {
+            domain: *function.clamper.domain.first()?,
+            encode: *function.encode.first()?,
+            range: function.range.clone(),
+            samples: samples.into(),
+        })
+    }
+
+    /// Evaluate the function with the given input.
+    pub fn eval(&self, input: f32) -> Values {
+        let num_outputs = self.range.len();
+        let num_samples = self.samples.len() / num_outputs;
+
+        let x = input.min(self.domain.1).max(self.domain.0);
+        let x = interpolate(
+            x,
+            self.domain.0,
+            self.domain.1,
+            self.encode.0,
+            self.encode.1,
+        )
+        .max(0.0)
+        .min(num_samples as f32 - 1.0);
+
+        let prev = x.floor() as usize;
+        let next = x.ceil() as usize;
+        let prev_samples = &self.samples[prev * num_outputs..][..num_outputs];
+        let next_samples = &self.samples[next * num_outputs..][..num_outputs];
+
+        prev_samples
+            .iter()
+            .zip(next_samples)
+            .zip(self.range.iter())
+            .map(|((v1, v2), (min, max))| {
+                let val = if prev == next {
+                    *v1
+                } else {
+                    interpolate(x, prev as f32, next as f32, *v1, *v2)
+                };
+
+                val.min(*max).max(*min)
+            })
+            .collect()
+    }
+}
+
 type FloatVec = SmallVec<[f32; 4]>;
 type IntVec = SmallVec<[u32; 4]>;
 
@@ -300,3 +400,81 @@ impl Key {
         Some(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::function::{Function, FunctionType};
+    use hayro_syntax::object::{FromBytes, Object};
+    use smallvec::smallvec;
+
+    fn function(dict: &str, samples: &[u8]) -> Function {
+        let mut data = format!("<< {dict} /Length {} >>\nstream\n", samples.len()).into_bytes();
+        data.extend_from_slice(samples);
+        data.extend_from_slice(b"\nendstream");
+
+        Function::new(&Object::from_bytes(&data).unwrap()).unwrap()
+    }
+
+    fn assert_cache_matches(function: &Function) {
+        let FunctionType::Type0(t0) = function.0.as_ref() else {
+            unreachable!()
+        };
+        let cache = function.sample_cache().unwrap();
+
+        for i in -20..=120 {
+            let x = i as f32 / 100.0 * 3.0 - 1.0;
+            let direct = t0.eval(smallvec![x]).unwrap();
+            let cached = cache.eval(x);
+
+            assert_eq!(direct.len(), cached.len());
+
+            for (d, c) in direct.iter().zip(cached.iter()) {
+                assert!((d - c).abs() < 1e-4, "{direct:?} != {cached:?} at {x}");
+            }
+        }
+    }
+
+    #[test]
+    fn sample_cache_rgb() {
+        let function = function(
+            "/FunctionType 0 /Domain [0 1] /Range [0 1 0 1 0 1] /Size [5] /BitsPerSample 8",
+            &[0, 255, 128, 64, 32, 16, 200, 100, 50, 255, 0, 0, 10, 20, 30],
+        );
+
+        assert_eq!(function.domain(), &[(0.0, 1.0)]);
+        assert_eq!(function.range(), Some([(0.0, 1.0); 3].as_slice()));
+        assert_cache_matches(&function);
+
+        // Halfway between the first two samples.
+        let out = function.eval(smallvec![0.125]).unwrap();
+        let expected = [32.0 / 255.0, 143.5 / 255.0, 72.0 / 255.0];
+
+        for (o, e) in out.iter().zip(expected) {
+            assert!((o - e).abs() < 1e-6, "{out:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn sample_cache_encode_decode() {
+        // The samples are traversed in reverse, decoded to a larger interval than the
+        // range and then clamped again.
+        let function = function(
+            "/FunctionType 0 /Domain [-0.5 1.5] /Range [0.2 0.8] /Size [4] /BitsPerSample 16 \
+             /Encode [3 0] /Decode [-1 2]",
+            &[0, 0, 0x40, 0, 0x80, 0, 0xff, 0xff],
+        );
+
+        assert_cache_matches(&function);
+    }
+
+    #[test]
+    fn sample_cache_two_inputs() {
+        let function = function(
+            "/FunctionType 0 /Domain [0 1 0 1] /Range [0 1] /Size [2 2] /BitsPerSample 8",
+            &[0, 255, 255, 0],
+        );
+
+        assert!(function.sample_cache().is_none());
+        assert_eq!(function.domain(), &[(0.0, 1.0), (0.0, 1.0)]);
+    }
+}
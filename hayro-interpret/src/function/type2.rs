@@ -9,7 +9,7 @@ use smallvec::{SmallVec, smallvec};
 pub(crate) struct Type2 {
     c0: Values,
     c1: Values,
-    clamper: Clamper,
+    pub(super) clamper: Clamper,
     n: f32,
 }
 
@@ -11,7 +11,7 @@ pub(crate) struct Type3 {
     functions: Vec<Function>,
     bounds: Vec<f32>,
     encode: TupleVec,
-    clamper: Clamper,
+    pub(super) clamper: Clamper,
 }
 
 impl Type3 {
@@ -23,12 +23,12 @@ impl Type3 {
             .get::<Array<'_>>(FUNCTIONS)
             .and_then(|d| d.iter::<Object<'_>>().map(|o| Function::new(&o)).collect())?;
         let domain = *clamper.domain.first()?;
-        let mut bounds = vec![domain.0 - 0.0001];
+        // The bounds of the subdomains, including the start and end of the domain.
+        let mut bounds = vec![domain.0];
         if let Some(a) = dict.get::<Array<'_>>(BOUNDS) {
             bounds.extend(a.iter::<f32>());
         }
-        // Add a small delta so that the interval is considered to be closed on the right.
-        bounds.push(domain.1 + 0.0001);
+        bounds.push(domain.1);
 
         let encode = dict.get::<TupleVec>(ENCODE)?;
 
@@ -50,10 +50,15 @@ impl Type3 {
         let bounds_i = *self.bounds.get(index + 1)?;
         let bounds_i_minus_1 = *self.bounds.get(index)?;
 
-        // - 1 because we inserted a dummy bound in the constructor.
         let encoding = self.encode.get(index)?;
         let function = self.functions.get(index)?;
-        let encoded = interpolate(input[0], bounds_i_minus_1, bounds_i, encoding.0, encoding.1);
+        let encoded = if bounds_i > bounds_i_minus_1 {
+            interpolate(input[0], bounds_i_minus_1, bounds_i, encoding.0, encoding.1)
+        } else {
+            // The subdomain only consists of a single point, which is mapped
+            // to the start of the encoding.
+            encoding.0
+        };
 
         let mut evaluated = function.eval(smallvec![encoded])?;
 
@@ -107,21 +112,26 @@ impl Type3 {
     }
 }
 
+/// Find the index of the subdomain that contains `x`.
+///
+/// The subdomains are half-open intervals `[bounds[i], bounds[i + 1])`, except for
+/// the last one, which is also closed on the right. In addition, the start of the
+/// domain always belongs to the first subdomain, so that it is closed on both ends
+/// in case its first bound is equal to the start of the domain.
 fn find_interval(bounds: &[f32], x: f32) -> Option<usize> {
-    if x < *bounds.first()? || x >= *bounds.last()? {
+    let (first, last) = (*bounds.first()?, *bounds.last()?);
+
+    if !(x >= first && x <= last) || bounds.len() < 2 {
         return None;
     }
 
-    match bounds.binary_search_by(|val| {
-        if *val <= x {
-            std::cmp::Ordering::Less
-        } else {
-            std::cmp::Ordering::Greater
-        }
-    }) {
-        Ok(i) => Some(i - 1),
-        Err(i) => Some(i - 1),
+    if x == first {
+        return Some(0);
     }
+
+    // The number of inner bounds that are at or before `x`. Subdomains that only
+    // consist of a single point are skipped this way.
+    Some(bounds[1..bounds.len() - 1].partition_point(|b| *b <= x))
 }
 
 #[cfg(test)]
@@ -176,4 +186,78 @@ mod tests {
             &[0.7, 0.7, 0.7]
         );
     }
+
+    fn constant(value: f32) -> String {
+        format!("<< /FunctionType 2 /Domain [0 1] /C0 [{value}] /C1 [{value}] /N 1 >>")
+    }
+
+    fn stitching(domain: &str, functions: &[String], bounds: &str, encode: &str) -> Function {
+        let data = format!(
+            "<< /FunctionType 3 /Domain [{domain}] /Functions [{}] /Bounds [{bounds}] /Encode [{encode}] >>",
+            functions.join(" ")
+        );
+
+        Function::new(&Object::from_bytes(data.as_bytes()).unwrap()).unwrap()
+    }
+
+    fn eval(function: &Function, x: f32) -> f32 {
+        function.eval(smallvec![x]).unwrap()[0]
+    }
+
+    #[test]
+    fn exact_bounds() {
+        let function = stitching(
+            "0 3",
+            &[constant(0.1), constant(0.2), constant(0.3)],
+            "1 2",
+            "0 1 0 1 0 1",
+        );
+
+        assert_eq!(eval(&function, -1.0), 0.1);
+        assert_eq!(eval(&function, 0.0), 0.1);
+        assert_eq!(eval(&function, 0.999), 0.1);
+        assert_eq!(eval(&function, 1.0), 0.2);
+        assert_eq!(eval(&function, 1.999), 0.2);
+        assert_eq!(eval(&function, 2.0), 0.3);
+        assert_eq!(eval(&function, 3.0), 0.3);
+        assert_eq!(eval(&function, 4.0), 0.3);
+    }
+
+    #[test]
+    fn encode_at_bounds() {
+        let identity = "<< /FunctionType 2 /Domain [0 1] /C0 [0] /C1 [1] /N 1 >>".to_string();
+        // The second subdomain is mapped in reverse.
+        let function = stitching("0 2", &[identity.clone(), identity], "0.5", "0 1 1 0");
+
+        assert_eq!(eval(&function, 0.0), 0.0);
+        assert_eq!(eval(&function, 0.25), 0.5);
+        assert_eq!(eval(&function, 0.5), 1.0);
+        assert_eq!(eval(&function, 1.25), 0.5);
+        assert_eq!(eval(&function, 2.0), 0.0);
+    }
+
+    #[test]
+    fn bound_at_domain_start() {
+        // The first subdomain only consists of the start of the domain.
+        let function = stitching("0 1", &[constant(0.1), constant(0.2)], "0", "0 1 0 1");
+
+        assert_eq!(eval(&function, 0.0), 0.1);
+        assert_eq!(eval(&function, 0.001), 0.2);
+        assert_eq!(eval(&function, 1.0), 0.2);
+    }
+
+    #[test]
+    fn single_point_subdomains() {
+        let function = stitching(
+            "0 2",
+            &[constant(0.1), constant(0.2), constant(0.3), constant(0.4)],
+            "1 1 2",
+            "0 1 0 1 0 1 0 1",
+        );
+
+        assert_eq!(eval(&function, 0.999), 0.1);
+        assert_eq!(eval(&function, 1.0), 0.3);
+        // The last subdomain is closed on the right.
+        assert_eq!(eval(&function, 2.0), 0.4);
+    }
 }
@@ -1,13 +1,16 @@
+use crate::BlendMode;
 use crate::FillRule;
-use crate::color::ColorSpace;
-use crate::context::Context;
-use crate::convert::{convert_line_cap, convert_line_join};
+use crate::color::{ColorComponents, ColorSpace};
+use crate::context::{Context, MAX_NESTED_INTERPRETATION_DEPTH};
+use crate::convert::{convert_dash_array, convert_line_cap, convert_line_join};
 use crate::device::Device;
 use crate::font::{Font, FontData, FontQuery, StandardFont};
 use crate::interpret::path::{
     close_path, fill_path, fill_path_impl, fill_stroke_path, stroke_path,
 };
-use crate::interpret::state::{TextStateFont, handle_gs};
+use crate::interpret::state::{
+    TextStateFont, convert_rendering_intent, handle_gs, read_blend_mode,
+};
 use crate::interpret::text::TextRenderingMode;
 use crate::pattern::{Pattern, ShadingPattern};
 use crate::shading::Shading;
@@ -15,15 +18,21 @@ use crate::util::{OptionLog, RectExt};
 use crate::x_object::{
     FormXObject, ImageXObject, XObject, draw_form_xobject, draw_image_xobject, draw_xobject,
 };
+use hayro_syntax::Filter;
 use hayro_syntax::content::TypedIter;
 use hayro_syntax::content::ops::TypedInstruction;
-use hayro_syntax::object::dict::keys::{ANNOTS, AP, F, MCID, N, OC, RECT};
-use hayro_syntax::object::{Array, Dict, Name, Object, Rect, Stream, dict_or_stream};
+use hayro_syntax::object::dict::keys::{
+    ANNOTS, AP, AS, CA, CA_NS, F, HIGHLIGHT, MCID, N, OC, RECT, SUBTYPE,
+};
+use hayro_syntax::object::{Array, Dict, Name, Number, Object, Rect, Stream, dict_or_stream};
 use hayro_syntax::page::{Page, Resources};
 use kurbo::{Affine, Point, Shape};
 use rustc_hash::FxHashMap;
 use smallvec::smallvec;
+use std::borrow::Cow;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 pub(crate) mod path;
 pub(crate) mod state;
@@ -36,6 +45,11 @@ pub use state::ActiveTransferFunction;
 /// The first argument is the raw data, the second argument is the index in case the font
 /// is a TTC, otherwise it should be 0.
 pub type FontResolverFn = Arc<dyn Fn(&FontQuery) -> Option<(FontData, u32)> + Send + Sync>;
+/// A callback function for overriding the advances of glyphs.
+///
+/// The first argument describes the font, the second one is the character code
+/// of the glyph. See [`InterpreterSettings::advance_override`] for details.
+pub type AdvanceOverrideFn = Arc<dyn Fn(&FontQuery, u32) -> Option<f32> + Send + Sync>;
 /// A callback function for resolving cmap names to their files.
 pub type CMapResolverFn =
     Arc<dyn Fn(hayro_cmap::CMapName<'_>) -> Option<&'static [u8]> + Send + Sync>;
@@ -81,18 +95,31 @@ pub struct InterpreterSettings {
     /// If you don't want having to deal with this, you can just enable the `embed-fonts` feature
     /// and use the default implementation of the callback.
     pub font_resolver: FontResolverFn,
-    /// A callback for resolving cmaps that aren't embedded.
+    /// A provider for cmaps that aren't embedded.
     ///
     /// When the PDF requires using a cmap that is not directly embedded in the PDF,
-    /// this callback will be called to attempt fetching the data of the file.
+    /// or an embedded cmap references another one via `usecmap`, this provider will
+    /// be queried to attempt fetching the data of the file.
+    ///
+    /// When the `embed-cmaps` feature is enabled, this uses [`EmbeddedCMaps`] from
+    /// `hayro-cmap` by default, which embeds the cmap files for all 61 predefined
+    /// cmaps that the PDF specification requires to be readily available on a system.
+    /// Otherwise, you can use [`CMapDirectory`] to read them from a checkout of the
+    /// Adobe cmap resources, or implement [`CMapProvider`] with your custom logic for
+    /// lazily fetching the data. If no provider is configured, such PDFs are not
+    /// supported.
     ///
-    /// When the `embed-cmaps` feature is enabled, this uses `load_embedded`
-    /// method from `hayro-cmap` by default, which embeds the cmap files for
-    /// all 61 predefined cmaps
-    /// that the PDF specification requires to be readily available on a system.
-    /// Otherwise, you can implement your custom logic for lazily fetching the
-    /// data. If you are fine not supporting such PDFs, you can simply pass a closure
-    /// that always returns `None`.
+    /// [`EmbeddedCMaps`]: hayro_cmap::EmbeddedCMaps
+    /// [`CMapDirectory`]: hayro_cmap::CMapDirectory
+    /// [`CMapProvider`]: hayro_cmap::CMapProvider
+    pub cmap_provider: Arc<dyn hayro_cmap::CMapProvider>,
+    /// A callback for resolving cmaps that aren't embedded.
+    ///
+    /// It is only called for cmaps that [`InterpreterSettings::cmap_provider`] can't
+    /// provide, and returns `None` by default. Since the provider can be created from
+    /// a callback with the same signature, you can simply pass your callback to
+    /// `Arc::new` and assign the result to `cmap_provider` instead.
+    #[deprecated(note = "use `cmap_provider` instead, which accepts the same callbacks")]
     pub cmap_resolver: CMapResolverFn,
     /// In certain cases, `hayro` will emit a warning in case an issue was encountered while interpreting
     /// the PDF file. Providing a callback allows you to catch those warnings and handle them, if desired.
@@ -102,9 +129,72 @@ pub struct InterpreterSettings {
     /// Note that this feature is currently not fully implemented yet, so some
     /// annotations might be missing.
     pub render_annotations: bool,
+    /// Whether highlight annotations without an explicit blend mode should be
+    /// painted with the `Multiply` blend mode.
+    ///
+    /// This is not required by the PDF specification, but Acrobat does it, and
+    /// many PDF producers rely on this behavior, so that highlights tint the text
+    /// they are placed over instead of covering it.
+    ///
+    /// Disabled by default, so that highlights are painted as specified.
+    pub multiply_highlight_annotations: bool,
+    /// A deadline after which interpretation should be cancelled.
+    ///
+    /// The deadline is checked cooperatively while processing content stream
+    /// operators (including those of nested form `XObjects`, patterns, soft masks
+    /// and Type3 glyphs), so the device will only have received the operations
+    /// that were processed until then, meaning that the output may be incomplete.
+    /// Use [`Deadline::exceeded`] to find out whether this happened.
+    pub deadline: Option<Deadline>,
+    /// The maximum depth to which form `XObjects`, patterns, soft masks and Type3
+    /// glyphs can be nested.
+    ///
+    /// Content that is nested more deeply is skipped and a warning is logged. This
+    /// protects against (possibly malicious) PDFs that reference themselves recursively.
+    pub max_nesting_depth: u32,
+    /// What to do with glyphs that can't be found in their font.
+    ///
+    /// By default, such glyphs are dropped, which leaves gaps in the text. When
+    /// proofreading documents, it can be useful to make them visible instead.
+    pub notdef_behavior: NotdefBehavior,
+    /// A callback for overriding the advances of glyphs, for example to correct
+    /// `Widths` arrays that don't match the embedded font program, which leads to
+    /// overlapping or spread out text.
+    ///
+    /// The callback is called with a [`FontQuery::Fallback`] query describing the
+    /// font and the character code of each glyph that is shown. If it returns an
+    /// advance, it is used for positioning the next glyph instead of the one
+    /// specified by the PDF. Like the `Widths` array, the advance is given in
+    /// thousandths of a unit of text space. For fonts with vertical writing mode,
+    /// it replaces the vertical displacement of the glyph, which is usually negative.
+    ///
+    /// By default, no advances are overridden.
+    pub advance_override: Option<AdvanceOverrideFn>,
+}
+
+/// How to show glyphs that can't be found in their font.
+///
+/// A glyph is considered missing if its character code maps to the `.notdef`
+/// glyph of an outline font, or if no font could be resolved at all and the
+/// code can't be shown with the fallback font.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum NotdefBehavior {
+    /// Draw the `.notdef` glyph of the font, which is usually empty, and
+    /// nothing at all if no font could be resolved.
+    #[default]
+    Drop,
+    /// Draw the `.notdef` glyph of the font if it has an outline, and a box
+    /// with the advance width of the glyph otherwise.
+    Box,
+    /// Draw the glyph of the given standard font for the character of the code.
+    ///
+    /// If the character is unknown or the standard font doesn't have a glyph
+    /// for it, a box is drawn like in [`NotdefBehavior::Box`].
+    Substitute(StandardFont),
 }
 
 impl Default for InterpreterSettings {
+    #[allow(deprecated)]
     fn default() -> Self {
         Self {
             #[cfg(not(feature = "embed-fonts"))]
@@ -115,17 +205,130 @@ impl Default for InterpreterSettings {
                 FontQuery::Fallback(f) => Some(f.pick_standard_font().get_font_data()),
             }),
             #[cfg(feature = "embed-cmaps")]
-            cmap_resolver: Arc::new(hayro_cmap::load_embedded),
+            cmap_provider: Arc::new(hayro_cmap::EmbeddedCMaps),
             #[cfg(not(feature = "embed-cmaps"))]
+            cmap_provider: Arc::new(hayro_cmap::NoCMaps),
             cmap_resolver: Arc::new(|_| None),
             warning_sink: Arc::new(|_| {}),
             render_annotations: true,
+            multiply_highlight_annotations: false,
+            deadline: None,
+            max_nesting_depth: MAX_NESTED_INTERPRETATION_DEPTH,
+            notdef_behavior: NotdefBehavior::default(),
+            advance_override: None,
         }
     }
 }
 
+/// The cmap provider of the settings, which falls back to the deprecated
+/// `cmap_resolver` for cmaps that the provider doesn't have.
+pub(crate) struct SettingsCMapProvider<'a>(pub(crate) &'a InterpreterSettings);
+
+impl hayro_cmap::CMapProvider for SettingsCMapProvider<'_> {
+    fn load_cmap(&self, name: hayro_cmap::CMapName<'_>) -> Option<Cow<'static, [u8]>> {
+        #[allow(deprecated)]
+        self.0
+            .cmap_provider
+            .load_cmap(name)
+            .or_else(|| (self.0.cmap_resolver)(name).map(Cow::Borrowed))
+    }
+}
+
+/// A point in time after which interpretation should be stopped.
+///
+/// Instead of (or in addition to) a point in time, a deadline can also be
+/// bound to a flag, which is useful for callers that manage their own timers
+/// or want to cancel rendering in response to user input.
+///
+/// Clones of a deadline share their state, so once a deadline has been
+/// found to be exceeded during interpretation, this is visible through
+/// all of its clones.
+#[derive(Clone, Debug)]
+pub struct Deadline {
+    instant: Option<Instant>,
+    flag: Option<Arc<AtomicBool>>,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl Deadline {
+    /// Create a new deadline that expires at the given instant.
+    pub fn new(instant: Instant) -> Self {
+        Self {
+            instant: Some(instant),
+            flag: None,
+            exceeded: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Create a new deadline that expires once the given flag is set.
+    pub fn from_flag(flag: Arc<AtomicBool>) -> Self {
+        Self {
+            instant: None,
+            flag: Some(flag),
+            exceeded: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Let the deadline additionally expire once the given flag is set.
+    pub fn with_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.flag = Some(flag);
+
+        self
+    }
+
+    /// The instant at which the deadline expires, if any.
+    pub fn instant(&self) -> Option<Instant> {
+        self.instant
+    }
+
+    /// Whether interpretation was cancelled because the deadline was exceeded.
+    pub fn exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::Relaxed)
+    }
+
+    /// Forget that the deadline was exceeded.
+    ///
+    /// This should be called before starting a new render with a deadline that was
+    /// already used for a previous one, since the deadline otherwise stays exceeded.
+    /// If the deadline itself has passed, it is found to be exceeded again at the
+    /// next check.
+    pub fn reset(&self) {
+        self.exceeded.store(false, Ordering::Relaxed);
+    }
+
+    /// Check whether the deadline has passed and remember the result if so.
+    ///
+    /// This is mostly useful for devices that perform expensive operations and
+    /// want to cancel them early.
+    pub fn check(&self) -> bool {
+        self.check_impl(true)
+    }
+
+    /// Like [`Deadline::check`], but only look at the clock if `check_clock` is set,
+    /// since that is more expensive than checking the flag.
+    pub(crate) fn check_impl(&self, check_clock: bool) -> bool {
+        if self.exceeded() {
+            return true;
+        }
+
+        let flag_set = self
+            .flag
+            .as_ref()
+            .is_some_and(|f| f.load(Ordering::Relaxed));
+
+        if flag_set || (check_clock && self.instant.is_some_and(|i| Instant::now() >= i)) {
+            self.exceeded.store(true, Ordering::Relaxed);
+
+            return true;
+        }
+
+        false
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 /// Warnings that can occur while interpreting a PDF file.
+#[non_exhaustive]
 pub enum InterpreterWarning {
     /// An unsupported font kind was encountered.
     ///
@@ -133,8 +336,51 @@ pub enum InterpreterWarning {
     UnsupportedFont,
     /// An image failed to decode.
     ImageDecodeFailure,
+    /// A feature that isn't supported was encountered and has been skipped,
+    /// meaning that the output may look different than in other viewers.
+    UnsupportedFeature(UnsupportedFeature),
+    /// The glyph for a character code was missing from its font, for example
+    /// because the font was subsetted too aggressively, and has been drawn with
+    /// the fallback font returned by the font resolver instead.
+    ///
+    /// This is reported once per font and character code. Since the substitutes
+    /// are stored in the [`InterpreterCache`](crate::InterpreterCache), the warning
+    /// is only emitted for the first page that uses the code when the cache is
+    /// shared across pages. Use a fresh cache for each page if you need the
+    /// warnings for every page.
+    MissingGlyph {
+        /// The character code of the glyph.
+        code: u32,
+        /// The character the glyph represents.
+        unicode: char,
+    },
 }
 
+/// A feature of PDF files that is currently not supported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnsupportedFeature {
+    /// A knockout transparency group, which is drawn like a normal group.
+    KnockoutGroup,
+    /// A shading pattern with its own graphics state parameters, which are ignored.
+    ShadingPatternExtGState,
+    /// A `PostScript` `XObject`, which is not drawn.
+    PostScriptXObject,
+    /// An image compressed with the given filter, which can't be decoded because
+    /// the `images` feature is disabled. A placeholder box is drawn instead.
+    MissingImageDecoder(Filter),
+    /// An image compressed with the given filter that uses a feature which isn't
+    /// supported by the decoder (for example some JPEG 2000 images), which is not
+    /// drawn.
+    UnsupportedImage(Filter),
+    /// A shading with the given type, which is not drawn because the type is
+    /// unknown.
+    UnknownShadingType(u8),
+}
+
+/// How many operators to process between two checks of the clock of a deadline.
+pub(crate) const DEADLINE_CHECK_INTERVAL: u32 = 32;
+
 /// interpret the contents of the page and render them into the device.
 pub fn interpret_page<'a>(
     page: &Page<'a>,
@@ -147,7 +393,11 @@ pub fn interpret_page<'a>(
     if context.settings.render_annotations
         && let Some(annot_arr) = page.raw().get::<Array<'_>>(ANNOTS)
     {
-        for annot in annot_arr.iter::<Dict<'_>>() {
+        for (annot_index, annot) in annot_arr.iter::<Dict<'_>>().enumerate() {
+            if context.deadline_exceeded(true) {
+                break;
+            }
+
             let flags = annot.get::<u32>(F).unwrap_or(0);
 
             // Annotation should be hidden.
@@ -155,11 +405,7 @@ pub fn interpret_page<'a>(
                 continue;
             }
 
-            if let Some(apx) = annot
-                .get::<Dict<'_>>(AP)
-                .and_then(|ap| ap.get::<Stream<'_>>(N))
-                .and_then(|o| FormXObject::new(&o))
-            {
+            if let Some(apx) = normal_appearance(&annot).and_then(|o| FormXObject::new(&o)) {
                 let Some(rect) = annot.get::<Rect>(RECT) else {
                     continue;
                 };
@@ -208,7 +454,38 @@ pub fn interpret_page<'a>(
                 context.pre_concat_affine(affine);
                 context.push_root_transform();
 
+                let group = annotation_group(&annot, &context.settings);
+
+                if let Some(group) = &group {
+                    // A transparency group only has a single opacity, so if the opacities
+                    // for stroking and non-stroking operations differ, they are applied
+                    // via the graphics state instead.
+                    let opacity = if group.stroke_alpha == group.non_stroke_alpha {
+                        group.non_stroke_alpha
+                    } else {
+                        context.graphics_state_mut().stroke_alpha = group.stroke_alpha;
+                        context.graphics_state_mut().non_stroke_alpha = group.non_stroke_alpha;
+
+                        1.0
+                    };
+
+                    device.push_transparency_group(opacity, None, group.blend_mode);
+                }
+
+                if let Some(tracker) = &mut context.op_tracker {
+                    tracker.annotation = Some(annot_index);
+                }
+
                 draw_form_xobject(resources, &apx, context, device);
+
+                if let Some(tracker) = &mut context.op_tracker {
+                    tracker.annotation = None;
+                }
+
+                if group.is_some() {
+                    device.pop_transparency_group();
+                }
+
                 context.pop_root_transform();
                 context.restore_state(device);
             }
@@ -216,6 +493,60 @@ pub fn interpret_page<'a>(
     }
 }
 
+/// Return the normal appearance stream of the annotation.
+///
+/// Annotations with multiple appearance states, like the `On` and `Off` states
+/// of a checkbox, have a dictionary of appearance streams instead, from which
+/// the stream of the current state (given by the `AS` entry) is selected. If the
+/// current state is unknown, nothing should be drawn.
+fn normal_appearance<'a>(annot: &Dict<'a>) -> Option<Stream<'a>> {
+    let appearance = annot.get::<Dict<'_>>(AP)?;
+
+    if let Some(stream) = appearance.get::<Stream<'_>>(N) {
+        return Some(stream);
+    }
+
+    let state = annot.get::<Name<'_>>(AS)?;
+
+    appearance
+        .get::<Dict<'_>>(N)?
+        .get::<Stream<'_>>(state.as_ref())
+}
+
+/// The opacities and blend mode an annotation's appearance stream should be drawn with.
+struct AnnotationGroup {
+    stroke_alpha: f32,
+    non_stroke_alpha: f32,
+    blend_mode: BlendMode,
+}
+
+/// Return the opacities and blend mode of the transparency group the appearance
+/// stream of the annotation should be wrapped in, if any.
+///
+/// Wrapping the appearance stream in a group ensures that the annotation-level
+/// settings compose with any `ExtGState` settings of the appearance stream itself.
+fn annotation_group(annot: &Dict<'_>, settings: &InterpreterSettings) -> Option<AnnotationGroup> {
+    let stroke_alpha = annot.get::<f32>(CA);
+    // If no separate opacity for non-stroking operations is given, the one for
+    // stroking operations applies to both.
+    let non_stroke_alpha = annot.get::<f32>(CA_NS).or(stroke_alpha);
+    let blend_mode = read_blend_mode(annot).or_else(|| {
+        let is_highlight = annot.get::<Name<'_>>(SUBTYPE).as_deref() == Some(HIGHLIGHT);
+
+        (is_highlight && settings.multiply_highlight_annotations).then_some(BlendMode::Multiply)
+    });
+
+    if stroke_alpha.is_none() && non_stroke_alpha.is_none() && blend_mode.is_none() {
+        return None;
+    }
+
+    Some(AnnotationGroup {
+        stroke_alpha: stroke_alpha.unwrap_or(1.0).clamp(0.0, 1.0),
+        non_stroke_alpha: non_stroke_alpha.unwrap_or(1.0).clamp(0.0, 1.0),
+        blend_mode: blend_mode.unwrap_or_default(),
+    })
+}
+
 /// Interpret the instructions from `ops` and render them into the device.
 pub fn interpret<'a>(
     mut ops: TypedIter<'_>,
@@ -228,37 +559,53 @@ pub fn interpret<'a>(
 
     context.save_state();
 
-    while let Some(op) = ops.next() {
+    let mut num_ops = 0_u32;
+    let mut index = 0_usize;
+
+    while let Some((op, location)) = ops.next_with_location() {
+        // The cancellation flag is checked for every operator, but checking the clock
+        // is not free, so only do it every couple of operators.
+        if context.deadline_exceeded(num_ops.is_multiple_of(DEADLINE_CHECK_INTERVAL)) {
+            break;
+        }
+
+        num_ops = num_ops.wrapping_add(1);
+        context.count_op();
+
+        let op_index = index;
+        index += 1;
+        context.set_op_context(op_index, location, device);
+
         match op {
             TypedInstruction::SaveState(_) => context.save_state(),
             TypedInstruction::StrokeColorDeviceRgb(s) => {
-                context.get_mut().graphics_state.stroke_cs = ColorSpace::device_rgb();
-                context.get_mut().graphics_state.stroke_color =
+                context.graphics_state_mut().stroke_cs = ColorSpace::device_rgb();
+                context.graphics_state_mut().stroke_color =
                     smallvec![s.0.as_f32(), s.1.as_f32(), s.2.as_f32()];
-                context.get_mut().graphics_state.stroke_pattern = None;
+                context.graphics_state_mut().stroke_pattern = None;
             }
             TypedInstruction::StrokeColorDeviceGray(s) => {
-                context.get_mut().graphics_state.stroke_cs = ColorSpace::device_gray();
-                context.get_mut().graphics_state.stroke_color = smallvec![s.0.as_f32()];
-                context.get_mut().graphics_state.stroke_pattern = None;
+                context.graphics_state_mut().stroke_cs = ColorSpace::device_gray();
+                context.graphics_state_mut().stroke_color = smallvec![s.0.as_f32()];
+                context.graphics_state_mut().stroke_pattern = None;
             }
             TypedInstruction::StrokeColorCmyk(s) => {
-                context.get_mut().graphics_state.stroke_cs = ColorSpace::device_cmyk();
-                context.get_mut().graphics_state.stroke_color =
+                context.graphics_state_mut().stroke_cs = ColorSpace::device_cmyk();
+                context.graphics_state_mut().stroke_color =
                     smallvec![s.0.as_f32(), s.1.as_f32(), s.2.as_f32(), s.3.as_f32()];
-                context.get_mut().graphics_state.stroke_pattern = None;
+                context.graphics_state_mut().stroke_pattern = None;
             }
             TypedInstruction::LineWidth(w) => {
-                context.get_mut().graphics_state.stroke_props.line_width = w.0.as_f32();
+                context.graphics_state_mut().stroke_props.line_width = w.0.as_f32();
             }
             TypedInstruction::LineCap(c) => {
-                context.get_mut().graphics_state.stroke_props.line_cap = convert_line_cap(c);
+                context.graphics_state_mut().stroke_props.line_cap = convert_line_cap(c);
             }
             TypedInstruction::LineJoin(j) => {
-                context.get_mut().graphics_state.stroke_props.line_join = convert_line_join(j);
+                context.graphics_state_mut().stroke_props.line_join = convert_line_join(j);
             }
             TypedInstruction::MiterLimit(l) => {
-                context.get_mut().graphics_state.stroke_props.miter_limit = l.0.as_f32();
+                context.graphics_state_mut().stroke_props.miter_limit = l.0.as_f32();
             }
             TypedInstruction::Transform(t) => {
                 context.pre_concat_transform(t);
@@ -307,21 +654,21 @@ pub fn interpret<'a>(
                 fill_stroke_path(context, device, FillRule::NonZero);
             }
             TypedInstruction::NonStrokeColorDeviceGray(s) => {
-                context.get_mut().graphics_state.none_stroke_cs = ColorSpace::device_gray();
-                context.get_mut().graphics_state.non_stroke_color = smallvec![s.0.as_f32()];
-                context.get_mut().graphics_state.non_stroke_pattern = None;
+                context.graphics_state_mut().none_stroke_cs = ColorSpace::device_gray();
+                context.graphics_state_mut().non_stroke_color = smallvec![s.0.as_f32()];
+                context.graphics_state_mut().non_stroke_pattern = None;
             }
             TypedInstruction::NonStrokeColorDeviceRgb(s) => {
-                context.get_mut().graphics_state.none_stroke_cs = ColorSpace::device_rgb();
-                context.get_mut().graphics_state.non_stroke_color =
+                context.graphics_state_mut().none_stroke_cs = ColorSpace::device_rgb();
+                context.graphics_state_mut().non_stroke_color =
                     smallvec![s.0.as_f32(), s.1.as_f32(), s.2.as_f32()];
-                context.get_mut().graphics_state.non_stroke_pattern = None;
+                context.graphics_state_mut().non_stroke_pattern = None;
             }
             TypedInstruction::NonStrokeColorCmyk(s) => {
-                context.get_mut().graphics_state.none_stroke_cs = ColorSpace::device_cmyk();
-                context.get_mut().graphics_state.non_stroke_color =
+                context.graphics_state_mut().none_stroke_cs = ColorSpace::device_cmyk();
+                context.graphics_state_mut().non_stroke_color =
                     smallvec![s.0.as_f32(), s.1.as_f32(), s.2.as_f32(), s.3.as_f32()];
-                context.get_mut().graphics_state.non_stroke_pattern = None;
+                context.graphics_state_mut().non_stroke_pattern = None;
             }
             TypedInstruction::LineTo(m) => {
                 if !context.path().elements().is_empty() {
@@ -395,14 +742,22 @@ pub fn interpret<'a>(
                 context.path_mut().truncate(0);
             }
             TypedInstruction::NonStrokeColor(c) => {
-                let gs = &mut context.get_mut().graphics_state;
-                gs.non_stroke_color = c.0.into_iter().map(|n| n.as_f32()).collect();
-                gs.non_stroke_pattern = None;
+                let gs = &context.get().graphics_state;
+
+                if gs.non_stroke_pattern.is_some() || !is_same_color(&gs.non_stroke_color, &c.0) {
+                    let gs = context.graphics_state_mut();
+                    gs.non_stroke_color = c.0.into_iter().map(|n| n.as_f32()).collect();
+                    gs.non_stroke_pattern = None;
+                }
             }
             TypedInstruction::StrokeColor(c) => {
-                let gs = &mut context.get_mut().graphics_state;
-                gs.stroke_color = c.0.into_iter().map(|n| n.as_f32()).collect();
-                gs.stroke_pattern = None;
+                let gs = &context.get().graphics_state;
+
+                if gs.stroke_pattern.is_some() || !is_same_color(&gs.stroke_color, &c.0) {
+                    let gs = context.graphics_state_mut();
+                    gs.stroke_color = c.0.into_iter().map(|n| n.as_f32()).collect();
+                    gs.stroke_pattern = None;
+                }
             }
             TypedInstruction::ClipNonZero(_) => {
                 *(context.clip_mut()) = Some(FillRule::NonZero);
@@ -411,8 +766,8 @@ pub fn interpret<'a>(
                 *(context.clip_mut()) = Some(FillRule::EvenOdd);
             }
             TypedInstruction::RestoreState(_) => context.restore_state(device),
-            TypedInstruction::FlatnessTolerance(_) => {
-                // Ignore for now.
+            TypedInstruction::FlatnessTolerance(f) => {
+                context.graphics_state_mut().device_params.flatness = f.0.as_f32();
             }
             TypedInstruction::ColorSpaceStroke(c) => {
                 let cs = if let Some(named) = ColorSpace::new_from_name(c.0) {
@@ -424,10 +779,10 @@ pub fn interpret<'a>(
                 };
 
                 if !cs.is_pattern() {
-                    context.get_mut().graphics_state.stroke_pattern = None;
+                    context.graphics_state_mut().stroke_pattern = None;
                 }
-                context.get_mut().graphics_state.stroke_color = cs.initial_color();
-                context.get_mut().graphics_state.stroke_cs = cs;
+                context.graphics_state_mut().stroke_color = cs.initial_color();
+                context.graphics_state_mut().stroke_cs = cs;
             }
             TypedInstruction::ColorSpaceNonStroke(c) => {
                 let cs = if let Some(named) = ColorSpace::new_from_name(c.0) {
@@ -439,35 +794,52 @@ pub fn interpret<'a>(
                 };
 
                 if !cs.is_pattern() {
-                    context.get_mut().graphics_state.non_stroke_pattern = None;
+                    context.graphics_state_mut().non_stroke_pattern = None;
                 }
-                context.get_mut().graphics_state.non_stroke_color = cs.initial_color();
-                context.get_mut().graphics_state.none_stroke_cs = cs;
+                context.graphics_state_mut().non_stroke_color = cs.initial_color();
+                context.graphics_state_mut().none_stroke_cs = cs;
             }
             TypedInstruction::DashPattern(p) => {
-                context.get_mut().graphics_state.stroke_props.dash_offset = p.1.as_f32();
-                // kurbo apparently cannot properly deal with offsets that are exactly 0.
-                context.get_mut().graphics_state.stroke_props.dash_array =
-                    p.0.iter::<f32>()
-                        .map(|n| if n == 0.0 { 0.01 } else { n })
-                        .collect();
+                context.graphics_state_mut().stroke_props.dash_offset = p.1.as_f32();
+                context.graphics_state_mut().stroke_props.dash_array = convert_dash_array(p.0);
             }
-            TypedInstruction::RenderingIntent(_) => {
-                // Ignore for now.
+            TypedInstruction::RenderingIntent(ri) => {
+                context.graphics_state_mut().rendering_intent =
+                    convert_rendering_intent(ri.0.as_str());
             }
             TypedInstruction::NonStrokeColorNamed(n) => {
-                context.get_mut().graphics_state.non_stroke_color =
+                let gs = &context.get().graphics_state;
+
+                // Plotting libraries tend to set the same color before each path,
+                // so skip the work if nothing changes.
+                if n.1.is_none()
+                    && gs.non_stroke_pattern.is_none()
+                    && is_same_color(&gs.non_stroke_color, &n.0)
+                {
+                    continue;
+                }
+
+                context.graphics_state_mut().non_stroke_color =
                     n.0.into_iter().map(|n| n.as_f32()).collect();
-                context.get_mut().graphics_state.non_stroke_pattern = n.1.and_then(|name| {
+                context.graphics_state_mut().non_stroke_pattern = n.1.and_then(|name| {
                     resources
                         .get_pattern(name)
                         .and_then(|d| Pattern::new(d, context, resources))
                 });
             }
             TypedInstruction::StrokeColorNamed(n) => {
-                context.get_mut().graphics_state.stroke_color =
+                let gs = &context.get().graphics_state;
+
+                if n.1.is_none()
+                    && gs.stroke_pattern.is_none()
+                    && is_same_color(&gs.stroke_color, &n.0)
+                {
+                    continue;
+                }
+
+                context.graphics_state_mut().stroke_color =
                     n.0.into_iter().map(|n| n.as_f32()).collect();
-                context.get_mut().graphics_state.stroke_pattern = n.1.and_then(|name| {
+                context.graphics_state_mut().stroke_pattern = n.1.and_then(|name| {
                     resources
                         .get_pattern(name)
                         .and_then(|d| Pattern::new(d, context, resources))
@@ -534,21 +906,7 @@ pub fn interpret<'a>(
                 context.get_mut().text_state.text_matrix = m;
             }
             TypedInstruction::EndText(_) => {
-                let has_outline = context
-                    .get()
-                    .text_state
-                    .clip_paths
-                    .segments()
-                    .next()
-                    .is_some();
-
-                if has_outline {
-                    let clip_path = context.get().ctm * context.get().text_state.clip_paths.clone();
-
-                    context.push_clip_path(clip_path, FillRule::NonZero, device);
-                }
-
-                context.get_mut().text_state.clip_paths.truncate(0);
+                text::push_text_clip(context, device);
             }
             TypedInstruction::TextFont(t) => {
                 let name = t.0;
@@ -667,7 +1025,19 @@ pub fn interpret<'a>(
                         transfer_function.clone(),
                     )
                 }) {
+                    if let Some(tracker) = &mut context.op_tracker {
+                        tracker.xobject_path.push(op_index);
+                    }
+
                     draw_xobject(&x_object, resources, context, device);
+
+                    if let Some(tracker) = &mut context.op_tracker {
+                        tracker.xobject_path.pop();
+                    }
+
+                    // Anything that is drawn after the form XObject has been
+                    // interpreted (e.g. when popping its group) belongs to `Do`.
+                    context.set_op_context(op_index, location, device);
                 }
             }
             TypedInstruction::InlineImage(i) => {
@@ -699,7 +1069,12 @@ pub fn interpret<'a>(
                     .get_shading(s.0)
                     .and_then(|o| {
                         let (dict, stream) = dict_or_stream(&o)?;
-                        Shading::new(dict, stream, &context.interpreter_cache.object_cache)
+                        Shading::new(
+                            dict,
+                            stream,
+                            &context.interpreter_cache.object_cache,
+                            &context.settings.warning_sink,
+                        )
                     })
                     .map(|s| {
                         Pattern::Shading(ShadingPattern {
@@ -712,9 +1087,9 @@ pub fn interpret<'a>(
                 {
                     context.save_state();
                     context.push_root_transform();
-                    let st = context.get_mut();
-                    st.graphics_state.non_stroke_pattern = Some(sp);
-                    st.graphics_state.none_stroke_cs = ColorSpace::pattern();
+                    let gs = context.graphics_state_mut();
+                    gs.non_stroke_pattern = Some(sp);
+                    gs.none_stroke_cs = ColorSpace::pattern();
 
                     let bbox = context.bbox().to_path(0.1);
                     let inverted_bbox = context.get().ctm.inverse() * bbox;
@@ -745,3 +1120,147 @@ pub fn interpret<'a>(
         context.restore_state(device);
     }
 }
+
+/// Return whether the operands of a color operator match the given color components.
+fn is_same_color(color: &ColorComponents, operands: &[Number]) -> bool {
+    color.len() == operands.len() && color.iter().zip(operands).all(|(c, o)| *c == o.as_f32())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::font::Glyph;
+    use crate::soft_mask::SoftMask;
+    use crate::{
+        BlendMode, ClipPath, Context, Deadline, Device, DisplayList, DrawMode, DrawProps, Image,
+        ImageDrawProps, InterpreterCache, InterpreterSettings, interpret_page,
+    };
+    use hayro_syntax::Pdf;
+    use kurbo::{Affine, BezPath, Rect};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // A page that draws five rectangles, three of them inside of nested clip paths.
+    const PDF: &[u8] = b"%PDF-1.7
+1 0 obj
+<< /Type /Catalog /Pages 2 0 R >>
+endobj
+2 0 obj
+<< /Type /Pages /Kids [3 0 R] /Count 1 >>
+endobj
+3 0 obj
+<< /Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Contents 4 0 R >>
+endobj
+4 0 obj
+<< /Length 116 >>
+stream
+q 0 0 80 80 re W n 0 0 10 10 re f q 0 0 50 50 re W n 0 0 10 10 re f 0 0 10 10 re f Q 0 0 10 10 re f Q 0 0 10 10 re f
+endstream
+endobj
+trailer
+<< /Root 1 0 R >>
+%%EOF
+";
+
+    /// A device that sets the cancellation flag once something was drawn, and keeps
+    /// track of how many clip paths and transparency groups are still pushed.
+    #[derive(Default)]
+    struct Canceller {
+        flag: Arc<AtomicBool>,
+        draws: usize,
+        pushes: usize,
+        depth: usize,
+    }
+
+    impl Canceller {
+        fn push(&mut self) {
+            self.pushes += 1;
+            self.depth += 1;
+        }
+
+        fn pop(&mut self) {
+            self.depth = self.depth.checked_sub(1).expect("unbalanced pop");
+        }
+
+        fn draw(&mut self) {
+            self.draws += 1;
+            self.flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    impl<'a> Device<'a> for Canceller {
+        fn draw_path(&mut self, _: &BezPath, _: DrawProps<'a>, _: &DrawMode) {
+            self.draw();
+        }
+        fn push_clip_path(&mut self, _: &ClipPath) {
+            self.push();
+        }
+        fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'a>>, _: BlendMode) {
+            self.push();
+        }
+        fn draw_glyph(&mut self, _: &Glyph<'a>, _: Affine, _: DrawProps<'a>, _: &DrawMode) {
+            self.draw();
+        }
+        fn draw_image(&mut self, _: Image<'a, '_>, _: ImageDrawProps<'a>) {
+            self.draw();
+        }
+        fn pop_clip(&mut self) {
+            self.pop();
+        }
+        fn pop_transparency_group(&mut self) {
+            self.pop();
+        }
+    }
+
+    #[test]
+    fn cancel_mid_stream() {
+        let pdf = Pdf::new(PDF.to_vec()).unwrap();
+        let page = &pdf.pages()[0];
+        let cache = InterpreterCache::new();
+        let mut device = Canceller::default();
+        let deadline = Deadline::from_flag(device.flag.clone());
+        let settings = InterpreterSettings {
+            deadline: Some(deadline.clone()),
+            ..Default::default()
+        };
+        let mut context = Context::new(
+            Affine::IDENTITY,
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            &cache,
+            page.xref(),
+            settings,
+        );
+        interpret_page(page, &mut context, &mut device);
+
+        // The flag is checked before the very next operator.
+        assert!(deadline.exceeded());
+        assert_eq!(device.draws, 1);
+        assert_eq!(device.pushes, 1);
+        assert_eq!(device.depth, 0);
+    }
+
+    #[test]
+    fn cancel_mid_replay() {
+        let pdf = Pdf::new(PDF.to_vec()).unwrap();
+        let page = &pdf.pages()[0];
+        let cache = InterpreterCache::new();
+        let mut context = Context::new(
+            Affine::IDENTITY,
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            &cache,
+            page.xref(),
+            InterpreterSettings::default(),
+        );
+        let mut list = DisplayList::new();
+        interpret_page(page, &mut context, &mut list);
+
+        let mut device = Canceller::default();
+        let deadline = Deadline::from_flag(device.flag.clone());
+        list.replay_with_deadline(Affine::IDENTITY, &deadline, &mut device);
+
+        // Nothing is drawn after cancelling, but all clip paths are still popped.
+        assert!(deadline.exceeded());
+        assert_eq!(device.draws, 1);
+        assert_eq!(device.pushes, 2);
+        assert_eq!(device.depth, 0);
+    }
+}
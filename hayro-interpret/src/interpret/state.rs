@@ -1,29 +1,32 @@
 use crate::StrokeProps;
-use crate::color::{AlphaColor, ColorComponents, ColorSpace};
+use crate::color::{AlphaColor, ColorComponents, ColorSpace, RenderingIntent};
 use crate::context::Context;
-use crate::convert::{convert_line_cap, convert_line_join};
+use crate::convert::{convert_dash_array, convert_line_cap, convert_line_join};
+use crate::display_list::DisplayList;
 use crate::font::{Font, UNITS_PER_EM};
 use crate::function::Function;
 use crate::interpret::text::TextRenderingMode;
 use crate::pattern::Pattern;
 use crate::soft_mask::SoftMask;
-use crate::types::BlendMode;
+use crate::types::{BlendMode, DeviceParams, Halftone};
 use crate::util::OptionLog;
 use hayro_syntax::content::ops::{LineCap, LineJoin};
-use hayro_syntax::object::dict::keys::{FONT, SMASK, TR, TR2};
+use hayro_syntax::object::dict::keys::{BM, FL, FONT, HT, OP, OP_NS, OPM, RI, SM, SMASK, TR, TR2};
 use hayro_syntax::object::{Array, Dict, Name, Number, Object};
 use hayro_syntax::page::Resources;
 use kurbo::{Affine, BezPath, Vec2};
 use smallvec::smallvec;
 use std::ops::Deref;
+use std::rc::Rc;
 
 /// A transfer function.
 #[derive(Clone, Debug)]
 pub enum ActiveTransferFunction {
     /// A single transfer function applied to all components.
     Single(Function),
-    /// Four transfer functions, one for each component.
-    Four([Function; 4]),
+    /// Four transfer functions, one for each component, where `None` stands
+    /// for the identity function.
+    Four([Option<Function>; 4]),
 }
 
 impl ActiveTransferFunction {
@@ -32,52 +35,58 @@ impl ActiveTransferFunction {
     pub fn apply(&self, color: &AlphaColor) -> AlphaColor {
         let mut rgba = color.components();
 
-        match self {
-            Self::Single(f) => {
-                for c in &mut rgba[..3] {
-                    if let Some(out) = f.eval(smallvec![*c]) {
-                        *c = out[0];
-                    }
-                }
-            }
-            Self::Four(functions) => {
-                for (i, f) in functions[..3].iter().enumerate() {
-                    if let Some(out) = f.eval(smallvec![rgba[i]]) {
-                        rgba[i] = out[0];
-                    }
-                }
-            }
+        for (i, c) in rgba[..3].iter_mut().enumerate() {
+            *c = self.apply_component(i, *c);
         }
 
         AlphaColor::new(rgba)
     }
+
+    /// Apply the transfer function of the component with the given index to a value.
+    pub(crate) fn apply_component(&self, index: usize, value: f32) -> f32 {
+        let function = match self {
+            Self::Single(f) => Some(f),
+            Self::Four(functions) => functions[index].as_ref(),
+        };
+
+        function
+            .and_then(|f| f.eval(smallvec![value]))
+            .and_then(|out| out.first().copied())
+            .unwrap_or(value)
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum ClipType {
     Dummy,
     Real,
+    // A clip that is applied as a transparency group with a soft mask.
+    Mask,
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct State<'a> {
     // Note that the text state and ctm are theoretically part of the graphics state,
     // but we keep them separate for simplicity.
-    pub(crate) graphics_state: GraphicsState<'a>,
+    //
+    // The graphics state is shared between a saved state and its copy until one
+    // of them is modified, so that saving the state with `q` is cheap.
+    pub(crate) graphics_state: Rc<GraphicsState<'a>>,
     pub(crate) text_state: TextState<'a>,
     pub(crate) ctm: Affine,
-    // Strictly speaking not part of the graphics state, but we keep it there for
+    // The number of clips that were active when the state was saved. Strictly
+    // speaking not part of the graphics state, but we keep it there for
     // consistency.
-    pub(crate) clips: Vec<ClipType>,
+    pub(crate) clip_depth: usize,
 }
 
 impl Default for State<'_> {
     fn default() -> Self {
         State {
             ctm: Affine::IDENTITY,
-            clips: vec![],
+            clip_depth: 0,
             text_state: TextState::default(),
-            graphics_state: GraphicsState::default(),
+            graphics_state: Rc::new(GraphicsState::default()),
         }
     }
 }
@@ -97,6 +106,7 @@ impl<'a> State<'a> {
             color_space: self.graphics_state.stroke_cs.clone(),
             pattern: self.graphics_state.stroke_pattern.clone(),
             transfer_function: self.graphics_state.transfer_function.clone(),
+            rendering_intent: self.graphics_state.rendering_intent,
         }
     }
 
@@ -107,6 +117,7 @@ impl<'a> State<'a> {
             color_space: self.graphics_state.none_stroke_cs.clone(),
             pattern: self.graphics_state.non_stroke_pattern.clone(),
             transfer_function: self.graphics_state.transfer_function.clone(),
+            rendering_intent: self.graphics_state.rendering_intent,
         }
     }
 }
@@ -149,6 +160,10 @@ pub(crate) struct TextState<'a> {
     // When setting the text rendering mode to `clip`, the glyphs should instead be collected
     // as paths and then applied as 1 single clip path. This field stores those clip paths.
     pub(crate) clip_paths: BezPath,
+    // Type3 glyphs can't be used in a clip path, since the area they cover can't always be
+    // described by a path, so they are recorded in device space instead. If there are any,
+    // the text clip is applied as a mask.
+    pub(crate) clip_mask: Option<DisplayList<'a>>,
 }
 
 impl<'a> TextState<'a> {
@@ -193,12 +208,13 @@ impl<'a> TextState<'a> {
         self.text_matrix *= Affine::new([1.0, 0.0, 0.0, 1.0, tx as f64, ty as f64]);
     }
 
-    pub(crate) fn apply_code_advance(&mut self, char_code: u32, code_len: usize) {
-        let glyph_advance = self
-            .font
-            .as_ref()
-            .map(|f| f.code_advance(char_code))
-            .unwrap_or(Vec2::ZERO);
+    /// Move the text matrix past a glyph with the given advance in glyph space.
+    pub(crate) fn apply_code_advance(
+        &mut self,
+        char_code: u32,
+        code_len: usize,
+        glyph_advance: Vec2,
+    ) {
         let horizontal = self.font_horizontal();
 
         let word_space = if char_code == 32 && code_len == 1 {
@@ -245,6 +261,7 @@ impl Default for TextState<'_> {
             text_line_matrix: Affine::IDENTITY,
             rise: 0.0,
             clip_paths: BezPath::default(),
+            clip_mask: None,
         }
     }
 }
@@ -269,6 +286,13 @@ pub(crate) struct GraphicsState<'a> {
     pub(crate) soft_mask: Option<SoftMask<'a>>,
     pub(crate) transfer_function: Option<ActiveTransferFunction>,
     pub(crate) blend_mode: BlendMode,
+    pub(crate) rendering_intent: RenderingIntent,
+    pub(crate) device_params: DeviceParams<'a>,
+
+    // Overprint parameters.
+    pub(crate) stroke_overprint: bool,
+    pub(crate) non_stroke_overprint: bool,
+    pub(crate) overprint_mode: u8,
 }
 
 impl Default for GraphicsState<'_> {
@@ -286,6 +310,11 @@ impl Default for GraphicsState<'_> {
             soft_mask: None,
             transfer_function: None,
             blend_mode: BlendMode::default(),
+            rendering_intent: RenderingIntent::default(),
+            device_params: DeviceParams::default(),
+            stroke_overprint: false,
+            non_stroke_overprint: false,
+            overprint_mode: 0,
         }
     }
 }
@@ -296,6 +325,7 @@ pub(crate) struct PaintData<'a> {
     pub(crate) color_space: ColorSpace,
     pub(crate) pattern: Option<Pattern<'a>>,
     pub(crate) transfer_function: Option<ActiveTransferFunction>,
+    pub(crate) rendering_intent: RenderingIntent,
 }
 
 pub(crate) fn handle_gs<'a>(
@@ -319,18 +349,18 @@ pub(crate) fn handle_gs_single<'a>(
 ) -> Option<()> {
     // TODO Can we use constants here somehow?
     match key.as_str() {
-        "LW" => context.get_mut().graphics_state.stroke_props.line_width = dict.get::<f32>(key)?,
+        "LW" => context.graphics_state_mut().stroke_props.line_width = dict.get::<f32>(key)?,
         "LC" => {
-            context.get_mut().graphics_state.stroke_props.line_cap =
+            context.graphics_state_mut().stroke_props.line_cap =
                 convert_line_cap(LineCap(dict.get::<Number>(key)?));
         }
         "LJ" => {
-            context.get_mut().graphics_state.stroke_props.line_join =
+            context.graphics_state_mut().stroke_props.line_join =
                 convert_line_join(LineJoin(dict.get::<Number>(key)?));
         }
-        "ML" => context.get_mut().graphics_state.stroke_props.miter_limit = dict.get::<f32>(key)?,
-        "CA" => context.get_mut().graphics_state.stroke_alpha = dict.get::<f32>(key)?,
-        "ca" => context.get_mut().graphics_state.non_stroke_alpha = dict.get::<f32>(key)?,
+        "ML" => context.graphics_state_mut().stroke_props.miter_limit = dict.get::<f32>(key)?,
+        "CA" => context.graphics_state_mut().stroke_alpha = dict.get::<f32>(key)?,
+        "ca" => context.graphics_state_mut().non_stroke_alpha = dict.get::<f32>(key)?,
         "TR" | "TR2" => {
             let function = match dict
                 .get::<Object<'_>>(TR2)
@@ -338,52 +368,73 @@ pub(crate) fn handle_gs_single<'a>(
             {
                 Object::Array(array) => {
                     let mut iter = array.iter::<Object<'_>>();
-                    let functions = [
-                        Function::new(&iter.next()?)?,
-                        Function::new(&iter.next()?)?,
-                        Function::new(&iter.next()?)?,
-                        Function::new(&iter.next()?)?,
-                    ];
-
-                    Some(ActiveTransferFunction::Four(functions))
+                    // Components that should be left unchanged can use `Identity`.
+                    let mut next = || match iter.next()? {
+                        Object::Name(_) => Some(None),
+                        o => Function::new(&o).map(Some),
+                    };
+                    let functions = [next()?, next()?, next()?, next()?];
+
+                    if functions.iter().all(Option::is_none) {
+                        None
+                    } else {
+                        Some(ActiveTransferFunction::Four(functions))
+                    }
                 }
                 // Only `Identity` and `Default` are valid, which both just reset it.
                 Object::Name(_) => None,
                 o => Some(ActiveTransferFunction::Single(Function::new(&o)?)),
             };
 
-            context.get_mut().graphics_state.transfer_function = function;
+            context.graphics_state_mut().transfer_function = function;
         }
         "SMask" => {
             if let Some(name) = dict.get::<Name<'_>>(SMASK) {
                 if name.deref() == b"None" {
-                    context.get_mut().graphics_state.soft_mask = None;
+                    context.graphics_state_mut().soft_mask = None;
                 }
             } else {
-                context.get_mut().graphics_state.soft_mask = dict
+                context.graphics_state_mut().soft_mask = dict
                     .get::<Dict<'_>>(SMASK)
                     .and_then(|d| SoftMask::new(&d, context, parent_resources.clone()));
             }
         }
         "BM" => {
-            if let Some(name) = dict.get::<Name<'_>>(key.as_ref()) {
-                if let Some(bm) = convert_blend_mode(name.as_str()) {
-                    context.get_mut().graphics_state.blend_mode = bm;
+            context.graphics_state_mut().blend_mode = read_blend_mode(dict).unwrap_or_else(|| {
+                warn!("unknown blend mode, defaulting to Normal");
 
-                    return Some(());
-                }
-            } else if let Some(arr) = dict.get::<Array<'_>>(key) {
-                for name in arr.iter::<Name<'_>>() {
-                    if let Some(bm) = convert_blend_mode(name.as_str()) {
-                        context.get_mut().graphics_state.blend_mode = bm;
+                BlendMode::Normal
+            });
+        }
+        "OP" => {
+            let overprint = dict.get::<bool>(OP)?;
+            context.graphics_state_mut().stroke_overprint = overprint;
 
-                        return Some(());
-                    }
-                }
+            // `op` defaults to the value of `OP` if it isn't present.
+            if !dict.contains_key(OP_NS) {
+                context.graphics_state_mut().non_stroke_overprint = overprint;
             }
+        }
+        "op" => {
+            context.graphics_state_mut().non_stroke_overprint = dict.get::<bool>(OP_NS)?;
+        }
+        "OPM" => context.graphics_state_mut().overprint_mode = dict.get::<u8>(OPM)?,
+        "RI" => {
+            context.graphics_state_mut().rendering_intent =
+                convert_rendering_intent(dict.get::<Name<'_>>(RI)?.as_str());
+        }
+        "FL" => context.graphics_state_mut().device_params.flatness = dict.get::<f32>(FL)?,
+        "SM" => context.graphics_state_mut().device_params.smoothness = Some(dict.get::<f32>(SM)?),
+        "HT" => {
+            let halftone = match dict.get::<Object<'_>>(HT)? {
+                Object::Dict(dict) => Halftone::Dict(dict),
+                Object::Stream(stream) => Halftone::Stream(stream),
+                // Only `Default` is valid, but treat all names the same.
+                Object::Name(_) => Halftone::Default,
+                _ => return None,
+            };
 
-            warn!("unknown blend mode, defaulting to Normal");
-            context.get_mut().graphics_state.blend_mode = BlendMode::Normal;
+            context.graphics_state_mut().device_params.halftone = Some(halftone);
         }
         "Font" => {
             let arr = dict.get::<Array<'_>>(FONT)?;
@@ -401,12 +452,8 @@ pub(crate) fn handle_gs_single<'a>(
             let dash_array = iter.next()?.into_array()?;
             let dash_phase = iter.next()?.into_number()?.as_f32();
 
-            context.get_mut().graphics_state.stroke_props.dash_offset = dash_phase;
-            context.get_mut().graphics_state.stroke_props.dash_array = dash_array
-                .iter::<f32>()
-                // kurbo apparently cannot properly deal with offsets that are exactly 0.
-                .map(|n| if n == 0.0 { 0.01 } else { n })
-                .collect();
+            context.graphics_state_mut().stroke_props.dash_offset = dash_phase;
+            context.graphics_state_mut().stroke_props.dash_array = convert_dash_array(&dash_array);
         }
         "Type" => {}
         _ => {}
@@ -415,6 +462,18 @@ pub(crate) fn handle_gs_single<'a>(
     Some(())
 }
 
+/// Read the `BM` entry of the given dictionary, which can either be a single
+/// blend mode or an array of blend modes, of which the first supported one is used.
+pub(crate) fn read_blend_mode(dict: &Dict<'_>) -> Option<BlendMode> {
+    if let Some(name) = dict.get::<Name<'_>>(BM) {
+        convert_blend_mode(name.as_str())
+    } else {
+        dict.get::<Array<'_>>(BM)?
+            .iter::<Name<'_>>()
+            .find_map(|name| convert_blend_mode(name.as_str()))
+    }
+}
+
 fn convert_blend_mode(name: &str) -> Option<BlendMode> {
     let bm = match name {
         "Normal" => BlendMode::Normal,
@@ -438,3 +497,112 @@ fn convert_blend_mode(name: &str) -> Option<BlendMode> {
 
     Some(bm)
 }
+
+/// Convert the name of a rendering intent. Unknown intents fall back to
+/// `RelativeColorimetric`, as mandated by the specification.
+pub(crate) fn convert_rendering_intent(name: &str) -> RenderingIntent {
+    match name {
+        "Perceptual" => RenderingIntent::Perceptual,
+        "RelativeColorimetric" => RenderingIntent::RelativeColorimetric,
+        "Saturation" => RenderingIntent::Saturation,
+        "AbsoluteColorimetric" => RenderingIntent::AbsoluteColorimetric,
+        _ => {
+            warn!("unknown rendering intent {name}, defaulting to RelativeColorimetric");
+
+            RenderingIntent::RelativeColorimetric
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::Recorder;
+    use crate::{Context, Halftone, InterpreterCache, InterpreterSettings, interpret_page};
+    use hayro_syntax::Pdf;
+    use kurbo::{Affine, Rect};
+
+    // A page that fills a rectangle with the default state, after applying an
+    // `ExtGState`, inside and after a nested state and after applying two
+    // `ExtGState`s in a row.
+    const PDF: &[u8] = b"%PDF-1.7
+1 0 obj
+<< /Type /Catalog /Pages 2 0 R >>
+endobj
+2 0 obj
+<< /Type /Pages /Kids [3 0 R] /Count 1 >>
+endobj
+3 0 obj
+<< /Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Resources << /ExtGState << /GS1 5 0 R /GS2 6 0 R /GS3 7 0 R >> >> /Contents 4 0 R >>
+endobj
+4 0 obj
+<< /Length 104 >>
+stream
+0 0 1 1 re f /GS1 gs 0 0 1 1 re f q /GS2 gs 3 i 0 0 1 1 re f Q 0 0 1 1 re f /GS3 gs /GS2 gs 0 0 1 1 re f
+endstream
+endobj
+5 0 obj
+<< /Type /ExtGState /FL 5 /SM 0.2 /HT /Default >>
+endobj
+6 0 obj
+<< /Type /ExtGState /FL 7 /HT << /Type /Halftone /HalftoneType 1 /Frequency 60 /Angle 45 /SpotFunction /Round >> >>
+endobj
+7 0 obj
+<< /Type /ExtGState /FL 8 /SM 0.5 /HT 8 0 R >>
+endobj
+8 0 obj
+<< /Type /Halftone /HalftoneType 6 /Width 1 /Height 1 /Length 1 >>
+stream
+0
+endstream
+endobj
+trailer
+<< /Root 1 0 R >>
+%%EOF
+";
+
+    #[test]
+    fn device_params() {
+        let pdf = Pdf::new(PDF.to_vec()).unwrap();
+        let page = &pdf.pages()[0];
+        let cache = InterpreterCache::new();
+        let mut context = Context::new(
+            Affine::IDENTITY,
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            &cache,
+            page.xref(),
+            InterpreterSettings::default(),
+        );
+        let mut device = Recorder::default();
+        interpret_page(page, &mut context, &mut device);
+
+        // The flatness, smoothness and halftone type of each filled path.
+        let params = device
+            .paths
+            .iter()
+            .map(|(_, props, _)| {
+                let params = &props.device_params;
+                let halftone = params.halftone.as_ref().map(|h| match h {
+                    Halftone::Default => "default",
+                    Halftone::Dict(_) => "dict",
+                    Halftone::Stream(_) => "stream",
+                });
+
+                (params.flatness, params.smoothness, halftone)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            params,
+            [
+                (1.0, None, None),
+                (5.0, Some(0.2), Some("default")),
+                // The `i` operator overrides the flatness of the `ExtGState`.
+                (3.0, Some(0.2), Some("dict")),
+                // Restoring the state restores the parameters as well.
+                (5.0, Some(0.2), Some("default")),
+                // Later `ExtGState`s only override the entries they contain.
+                (7.0, Some(0.5), Some("dict")),
+            ]
+        );
+    }
+}
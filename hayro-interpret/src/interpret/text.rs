@@ -1,11 +1,16 @@
+use crate::color::{Color, ColorSpace};
 use crate::context::Context;
 use crate::device::Device;
-use crate::font::Glyph;
+use crate::font::{FontQuery, Glyph};
 use crate::interpret::state::TextStateFont;
-use crate::{DrawMode, FillRule};
+use crate::soft_mask::SoftMask;
+use crate::{BlendMode, DeviceParams, DrawMode, DrawProps, FillRule, NotdefBehavior, Paint};
+use hayro_cmap::BfString;
 use hayro_syntax::object;
 use hayro_syntax::page::Resources;
-use kurbo::Affine;
+use kurbo::{Affine, Rect, Shape, Vec2};
+use skrifa::GlyphId;
+use smallvec::smallvec;
 
 pub(crate) fn show_text_string<'a>(
     ctx: &mut Context<'a>,
@@ -27,24 +32,187 @@ pub(crate) fn show_text_string<'a>(
     let show_glyphs = matches!(font, TextStateFont::Font(_))
         || (matches!(font, TextStateFont::Fallback(_)) && bytes.is_ascii());
 
+    // Building the query is not free, so only do it once per string.
+    let advance_query = ctx
+        .settings
+        .advance_override
+        .is_some()
+        .then(|| FontQuery::Fallback(font.fallback_query()));
+
     let mut cur_idx = 0;
 
     while cur_idx < bytes.len() {
         let (code, adv) = font.read_code(bytes, cur_idx);
         cur_idx += adv;
 
-        if show_glyphs {
-            let (glyph, glyph_transform) = font.get_glyph(
-                font.map_code(code),
+        let glyph_id = font.map_code(code);
+        let origin_displacement = font.origin_displacement(code);
+        let (mut glyph, glyph_transform) =
+            font.get_glyph(glyph_id, code, ctx, resources, origin_displacement);
+
+        let advance_override = advance_query
+            .as_ref()
+            .and_then(|query| ctx.settings.advance_override.as_ref()?(query, code));
+        let glyph_advance = match advance_override {
+            Some(a) if font.is_horizontal() => Vec2::new(a as f64, 0.0),
+            Some(a) => Vec2::new(0.0, a as f64),
+            None => font.code_advance(code),
+        };
+        // Devices only see horizontal advances.
+        let width_override = advance_override.filter(|_| font.is_horizontal());
+        override_advance(&mut glyph, width_override);
+
+        if !show_glyphs {
+            show_missing_glyph(
+                ctx,
+                device,
+                resources,
+                &font,
                 code,
+                &glyph,
+                false,
+                glyph_transform,
+                glyph_advance.x,
+                width_override,
+            );
+        } else if glyph_id == GlyphId::NOTDEF && !font.is_type3() {
+            show_missing_glyph(
                 ctx,
+                device,
                 resources,
-                font.origin_displacement(code),
+                &font,
+                code,
+                &glyph,
+                true,
+                glyph_transform,
+                glyph_advance.x,
+                width_override,
             );
+        } else if let TextStateFont::Font(font) = &font
+            && let Glyph::Outline(outline) = &glyph
+            && let Some(fallback) = ctx.resolve_fallback_glyph(font, outline)
+        {
+            let mut fallback = Glyph::Outline(fallback);
+            override_advance(&mut fallback, width_override);
+            show_glyph(ctx, device, &fallback, glyph_transform);
+        } else {
             show_glyph(ctx, device, &glyph, glyph_transform);
         }
 
-        ctx.get_mut().text_state.apply_code_advance(code, adv);
+        ctx.get_mut()
+            .text_state
+            .apply_code_advance(code, adv, glyph_advance);
+    }
+}
+
+/// Make an outline glyph report the given advance width instead of the one of
+/// its font, and stretch fallback glyphs to it.
+fn override_advance(glyph: &mut Glyph<'_>, advance: Option<f32>) {
+    if let Glyph::Outline(o) = glyph
+        && advance.is_some()
+    {
+        o.advance_override = advance;
+    }
+}
+
+/// Show a glyph that couldn't be found in its font, according to the
+/// configured [`NotdefBehavior`].
+///
+/// `glyph` is the glyph the code maps to, which is used for determining the
+/// character of the code. If `is_notdef` is set, it is the `.notdef` glyph of
+/// the font, otherwise the code can't be shown with the font at all, for
+/// example because no font could be resolved. `advance` is the horizontal
+/// advance of the code, and `width_override` the one from the settings, if any.
+#[allow(clippy::too_many_arguments)]
+fn show_missing_glyph<'a>(
+    ctx: &mut Context<'a>,
+    device: &mut impl Device<'a>,
+    resources: &Resources<'a>,
+    font: &TextStateFont<'a>,
+    code: u32,
+    glyph: &Glyph<'a>,
+    is_notdef: bool,
+    glyph_transform: Affine,
+    advance: f64,
+    width_override: Option<f32>,
+) {
+    let behavior = ctx.settings.notdef_behavior;
+
+    if let NotdefBehavior::Substitute(standard_font) = behavior
+        && let Some(c) = match glyph.as_unicode() {
+            Some(BfString::Char(c)) => Some(c),
+            Some(BfString::String(s)) => s.chars().next(),
+            None => None,
+        }
+        && let Some(substitute) = ctx.resolve_standard_font(standard_font)
+        && let Some(glyph_id) = substitute.map_unicode(c)
+    {
+        let (mut glyph, glyph_transform) = substitute.get_glyph(
+            glyph_id,
+            code,
+            ctx,
+            resources,
+            font.origin_displacement(code),
+        );
+        override_advance(&mut glyph, width_override);
+        show_glyph(ctx, device, &glyph, glyph_transform);
+
+        return;
+    }
+
+    let notdef = is_notdef.then_some(glyph);
+
+    match (behavior, notdef) {
+        (NotdefBehavior::Drop, Some(notdef)) => show_glyph(ctx, device, notdef, glyph_transform),
+        (NotdefBehavior::Drop, None) => {}
+        (_, Some(notdef @ Glyph::Outline(o))) if o.outline().segments().next().is_some() => {
+            show_glyph(ctx, device, notdef, glyph_transform);
+        }
+        _ => show_notdef_box(ctx, device, advance, glyph_transform),
+    }
+}
+
+/// Draw a box in place of a missing glyph with the given advance width.
+fn show_notdef_box<'a>(
+    ctx: &mut Context<'a>,
+    device: &mut impl Device<'a>,
+    advance: f64,
+    glyph_transform: Affine,
+) {
+    if !ctx.ocg_state.is_visible() {
+        return;
+    }
+
+    let (fill, stroke) = match ctx.get().text_state.render_mode {
+        TextRenderingMode::Invisible | TextRenderingMode::Clip => return,
+        TextRenderingMode::Fill | TextRenderingMode::FillAndClip => (true, false),
+        TextRenderingMode::Stroke | TextRenderingMode::StrokeAndClip => (false, true),
+        TextRenderingMode::FillStroke | TextRenderingMode::FillAndStrokeAndClip => (true, true),
+    };
+
+    // The box is drawn in glyph space, where an em spans 1000 units.
+    let width = if advance > 0.0 { advance } else { 500.0 };
+    let outer = Rect::new(0.1 * width, 0.0, 0.9 * width, 700.0);
+    let inner = outer.inset(-0.1 * width.min(700.0));
+    let mut path = outer.to_path(0.1);
+    path.extend(inner.to_path(0.1));
+
+    let path = glyph_transform * path;
+
+    if fill {
+        device.draw_path(
+            &path,
+            ctx.draw_props(false),
+            &DrawMode::Fill(FillRule::EvenOdd),
+        );
+    }
+
+    if stroke {
+        device.draw_path(
+            &path,
+            ctx.draw_props(true),
+            &DrawMode::Stroke(ctx.stroke_props()),
+        );
     }
 }
 
@@ -150,7 +318,7 @@ pub(crate) fn show_glyph<'a>(
     }
 }
 
-pub(crate) fn clip_glyph(context: &mut Context<'_>, glyph: &Glyph<'_>, transform: Affine) {
+pub(crate) fn clip_glyph<'a>(context: &mut Context<'a>, glyph: &Glyph<'a>, transform: Affine) {
     match glyph {
         Glyph::Outline(o) => {
             let outline = transform * o.outline();
@@ -160,12 +328,70 @@ pub(crate) fn clip_glyph(context: &mut Context<'_>, glyph: &Glyph<'_>, transform
                 context.get_mut().text_state.clip_paths.extend(outline);
             }
         }
-        Glyph::Type3(_) => {
-            warn!("text rendering mode clip not implemented for shape glyphs");
+        Glyph::Type3(t) => {
+            let mut clip_mask = context
+                .get_mut()
+                .text_state
+                .clip_mask
+                .take()
+                .unwrap_or_default();
+            t.interpret(
+                &mut clip_mask,
+                context.get().ctm,
+                transform,
+                &opaque_black(),
+            );
+
+            context.get_mut().text_state.clip_mask = Some(clip_mask);
+        }
+    }
+}
+
+/// Apply the clip collected from the glyphs shown with a clipping text rendering
+/// mode since the start of the text object.
+///
+/// Outline glyphs are combined into a single clip path. If any Type3 glyphs are
+/// part of the clip, the outlines are instead drawn into the mask that the Type3
+/// glyphs were recorded into, and the contents are clipped by that mask. This
+/// keeps images and strokes in Type3 glyphs intact.
+pub(crate) fn push_text_clip<'a>(context: &mut Context<'a>, device: &mut impl Device<'a>) {
+    let ctm = context.get().ctm;
+    let text_state = &mut context.get_mut().text_state;
+    let clip_paths = std::mem::take(&mut text_state.clip_paths);
+    let clip_mask = text_state.clip_mask.take();
+    let has_outline = clip_paths.segments().next().is_some();
+
+    match clip_mask {
+        Some(mut clip_mask) => {
+            if has_outline {
+                let props = DrawProps {
+                    transform: Affine::IDENTITY,
+                    paint: opaque_black(),
+                    soft_mask: None,
+                    blend_mode: BlendMode::Normal,
+                    overprint: false,
+                    device_params: DeviceParams::default(),
+                };
+                clip_mask.draw_path(
+                    &(ctm * clip_paths),
+                    props,
+                    &DrawMode::Fill(FillRule::NonZero),
+                );
+            }
+
+            context.push_clip_mask(SoftMask::from_recorded(clip_mask), device);
+        }
+        None if has_outline => {
+            context.push_clip_path(ctm * clip_paths, FillRule::NonZero, device);
         }
+        None => {}
     }
 }
 
+fn opaque_black<'a>() -> Paint<'a> {
+    Paint::Color(Color::new(ColorSpace::device_gray(), smallvec![0.0], 1.0))
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub(crate) enum TextRenderingMode {
     #[default]
@@ -178,3 +404,153 @@ pub(crate) enum TextRenderingMode {
     FillAndStrokeAndClip,
     Clip,
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::device::Recorder;
+    use crate::font::StandardFont;
+    use crate::{
+        Context, DrawMode, InterpreterCache, InterpreterSettings, NotdefBehavior, interpret_page,
+    };
+    use hayro_syntax::Pdf;
+    use kurbo::{Affine, Rect, Shape};
+
+    // A page that uses two glyphs of a Type3 font as a text clip. The first glyph is
+    // an image mask whose left half is painted and the second one a stroked line.
+    const PDF: &[u8] = b"%PDF-1.7
+1 0 obj
+<< /Type /Catalog /Pages 2 0 R >>
+endobj
+2 0 obj
+<< /Type /Pages /Kids [3 0 R] /Count 1 >>
+endobj
+3 0 obj
+<< /Type /Page /Parent 2 0 R /MediaBox [0 0 300 200] /Resources << /Font << /F1 5 0 R >> >> /Contents 4 0 R >>
+endobj
+4 0 obj
+<< /Length 38 >>
+stream
+BT /F1 100 Tf 7 Tr 10 10 Td (ab) Tj ET
+endstream
+endobj
+5 0 obj
+<< /Type /Font /Subtype /Type3 /FontBBox [0 0 1000 1000] /FontMatrix [0.001 0 0 0.001 0 0] /CharProcs << /a 6 0 R /b 7 0 R >> /Encoding << /Differences [97 /a /b] >> /FirstChar 97 /LastChar 98 /Widths [1000 1000] >>
+endobj
+6 0 obj
+<< /Length 95 >>
+stream
+1000 0 0 0 1000 1000 d1 q 1000 0 0 1000 0 0 cm BI /W 2 /H 1 /IM true /BPC 1 /F /AHx ID 40> EI Q
+endstream
+endobj
+7 0 obj
+<< /Length 50 >>
+stream
+1000 0 0 0 1000 1000 d1 100 w 0 500 m 1000 500 l S
+endstream
+endobj
+trailer
+<< /Root 1 0 R >>
+%%EOF
+";
+
+    #[test]
+    fn type3_text_clip() {
+        let pdf = Pdf::new(PDF.to_vec()).unwrap();
+        let page = &pdf.pages()[0];
+        let cache = InterpreterCache::new();
+        let mut context = Context::new(
+            Affine::IDENTITY,
+            Rect::new(0.0, 0.0, 300.0, 200.0),
+            &cache,
+            page.xref(),
+            InterpreterSettings::default(),
+        );
+        let mut device = Recorder::default();
+        interpret_page(page, &mut context, &mut device);
+
+        // The glyphs are applied as a mask instead of a clip path, so that the
+        // image and the stroke are kept intact.
+        assert!(device.clips.is_empty());
+        assert_eq!(device.masks.len(), 1);
+
+        let mut mask = Recorder::default();
+        device.masks[0].interpret(&mut mask);
+        assert_eq!(mask.images, 1);
+        assert_eq!(mask.paths.len(), 1);
+
+        let (path, props, draw_mode) = &mask.paths[0];
+        assert!(matches!(draw_mode, DrawMode::Stroke(_)));
+        let bbox = (props.transform * path.clone()).bounding_box();
+        let expected = Rect::new(110.0, 60.0, 210.0, 60.0);
+        assert!(
+            (bbox.min_x() - expected.min_x()).abs() < 1e-6
+                && (bbox.min_y() - expected.min_y()).abs() < 1e-6
+                && (bbox.max_x() - expected.max_x()).abs() < 1e-6
+                && (bbox.max_y() - expected.max_y()).abs() < 1e-6,
+            "{bbox:?}"
+        );
+    }
+
+    // A page that strokes a non-ASCII code with a font that isn't in the resources.
+    const MISSING_FONT_PDF: &[u8] = b"%PDF-1.7
+1 0 obj
+<< /Type /Catalog /Pages 2 0 R >>
+endobj
+2 0 obj
+<< /Type /Pages /Kids [3 0 R] /Count 1 >>
+endobj
+3 0 obj
+<< /Type /Page /Parent 2 0 R /MediaBox [0 0 300 200] /Contents 4 0 R >>
+endobj
+4 0 obj
+<< /Length 39 >>
+stream
+BT /F9 50 Tf 1 Tr 10 10 Td (\\351) Tj ET
+endstream
+endobj
+trailer
+<< /Root 1 0 R >>
+%%EOF
+";
+
+    fn check_missing_font(notdef_behavior: NotdefBehavior, check: impl FnOnce(&Recorder<'_>)) {
+        let pdf = Pdf::new(MISSING_FONT_PDF.to_vec()).unwrap();
+        let page = &pdf.pages()[0];
+        let cache = InterpreterCache::new();
+        let settings = InterpreterSettings {
+            notdef_behavior,
+            ..Default::default()
+        };
+        let mut context = Context::new(
+            Affine::IDENTITY,
+            Rect::new(0.0, 0.0, 300.0, 200.0),
+            &cache,
+            page.xref(),
+            settings,
+        );
+        let mut device = Recorder::default();
+        interpret_page(page, &mut context, &mut device);
+
+        check(&device);
+    }
+
+    #[test]
+    fn missing_font_substitute() {
+        check_missing_font(
+            NotdefBehavior::Substitute(StandardFont::Courier),
+            |device| {
+                assert_eq!(device.glyphs.len(), 1);
+                assert!(device.paths.is_empty());
+            },
+        );
+    }
+
+    #[test]
+    fn missing_font_stroked_box() {
+        check_missing_font(NotdefBehavior::Box, |device| {
+            assert!(device.glyphs.is_empty());
+            assert_eq!(device.paths.len(), 1);
+            assert!(matches!(device.paths[0].2, DrawMode::Stroke(_)));
+        });
+    }
+}
@@ -18,11 +18,16 @@ the implementation of `hayro` and `hayro-svg` to get an idea on how to use this
 This crate forbids unsafe code via a crate-level attribute.
 
 # Cargo features
-This crate has one optional feature:
+This crate has the following optional features:
 - `embed-fonts`: PDF processors are required to support 14 predefined fonts that do not need to be
   embedded into a PDF file. If you enable this feature, hayro will embed a (permissively-licensed)
   substitute for each font, so that you don't have to implement your custom font loading logic. This
   will add around ~240KB to your binary.
+- `images` (enabled by default): Decodes images that are compressed with the `DCTDecode`,
+  `CCITTFaxDecode`, `JBIG2Decode` and `JPXDecode` filters. If disabled, such images are replaced by a
+  gray placeholder box and reported as [`UnsupportedFeature::MissingImageDecoder`].
+- `bidi`: Enables `TextItem::logical_order`, which uses the Unicode bidirectional algorithm
+  to reconstruct the reading order of extracted right-to-left and mixed-direction text.
 */
 
 #![forbid(unsafe_code)]
@@ -31,32 +36,43 @@ This crate has one optional feature:
 #[macro_use]
 mod log;
 
+mod bbox;
 mod cache;
 mod context;
 mod convert;
 mod device;
+mod display_list;
 mod function;
 mod interpret;
 mod ocg;
 mod soft_mask;
+mod summary;
 mod types;
+mod validate;
 mod x_object;
 
 pub mod color;
 pub mod encode;
 pub mod font;
 pub mod gradient;
+pub mod path;
 pub mod pattern;
 pub mod shading;
+pub mod text;
 pub mod util;
 
+pub use bbox::content_bbox;
 pub use cache::CacheKey;
 pub use context::*;
 pub use device::*;
-pub use function::Function;
+pub use display_list::DisplayList;
+pub use function::{Function, SampleCache};
 pub use hayro_cmap;
 pub use hayro_syntax;
 pub use interpret::*;
 pub use soft_mask::*;
+pub use summary::{ContentSummary, content_summary};
 pub use types::*;
 pub use util::{RectExt, TransformExt};
+pub use validate::{Finding, FindingCode, Severity, ValidationDepth, ValidationReport, validate};
+pub use x_object::decode_thumbnail;
@@ -0,0 +1,159 @@
+//! Extracting the vector geometry of PDF content streams.
+//!
+//! [`PathExtractor`] is a [`Device`] that records all filled and stroked paths
+//! together with their transform, draw mode, color and the clip paths that were
+//! active when they were drawn. This is useful for converting pages into other
+//! vector formats or for analyzing their geometry.
+//!
+//! Glyphs, images and shadings drawn with the `sh` operator are not recorded,
+//! and neither are transparency groups and soft masks, so the recorded paths
+//! only describe the geometry of a page, not necessarily its exact appearance.
+//!
+//! # Coordinates
+//!
+//! Each path is stored in its own coordinate space, and needs to be transformed
+//! by [`ExtractedPath::transform`] to end up in device space. Clip paths are
+//! always stored in device space. When using [`extract_paths`], device space is
+//! the same space that is used for rendering: The origin is at the top-left
+//! corner of the page, the y-axis points downwards, one unit corresponds to one
+//! point, and the `/Rotate` entry of the page is applied.
+
+use crate::color::AlphaColor;
+use crate::font::Glyph;
+use crate::util::TransformExt;
+use crate::{
+    BlendMode, ClipPath, Context, Device, DrawMode, DrawProps, FillRule, Image, ImageDrawProps,
+    InterpreterCache, InterpreterSettings, Paint, SoftMask, interpret_page,
+};
+use hayro_syntax::page::Page;
+use kurbo::{Affine, BezPath, Rect};
+use std::rc::Rc;
+
+/// A path that was drawn while interpreting a content stream.
+#[derive(Clone, Debug)]
+pub struct ExtractedPath {
+    /// The path, in its own coordinate space.
+    pub path: BezPath,
+    /// The transform from the coordinate space of the path to device space.
+    pub transform: Affine,
+    /// How the path is painted.
+    pub draw_mode: DrawMode,
+    /// The color of the path, or `None` if it is painted with a pattern.
+    pub color: Option<AlphaColor>,
+    /// The clip paths that were active when the path was drawn, in device space,
+    /// starting with the outermost one.
+    ///
+    /// The effective clip is the intersection of all of them. Paths that were
+    /// drawn with the same clip paths share the same allocation.
+    pub clip_paths: Rc<[ClipPath]>,
+}
+
+impl ExtractedPath {
+    /// Return the path in device space.
+    pub fn device_path(&self) -> BezPath {
+        self.transform * self.path.clone()
+    }
+
+    /// Return the fill rule of the path, or `None` if the path is only stroked.
+    pub fn fill_rule(&self) -> Option<FillRule> {
+        match &self.draw_mode {
+            DrawMode::Fill(fill) | DrawMode::FillAndStroke(fill, _) => Some(*fill),
+            DrawMode::Stroke(_) | DrawMode::Invisible => None,
+        }
+    }
+}
+
+/// Extract all paths of the given page.
+///
+/// The paths are reported in the same coordinate space that is used when
+/// rendering the page, see the [module-level documentation](self#coordinates).
+pub fn extract_paths<'a>(
+    page: &Page<'a>,
+    cache: &InterpreterCache<'a>,
+    settings: InterpreterSettings,
+) -> Vec<ExtractedPath> {
+    let (width, height) = page.render_dimensions();
+    let mut context = Context::new(
+        page.initial_transform(true).to_kurbo(),
+        Rect::new(0.0, 0.0, width as f64, height as f64),
+        cache,
+        page.xref(),
+        settings,
+    );
+
+    let mut extractor = PathExtractor::new();
+    interpret_page(page, &mut context, &mut extractor);
+
+    extractor.into_paths()
+}
+
+/// A device that records all paths that are drawn.
+#[derive(Default)]
+pub struct PathExtractor {
+    paths: Vec<ExtractedPath>,
+    clip_stack: Vec<ClipPath>,
+    // The clip stack as it is shared with the recorded paths. Reset whenever
+    // the clip stack changes.
+    shared_clips: Option<Rc<[ClipPath]>>,
+}
+
+impl PathExtractor {
+    /// Create a new path extractor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return all recorded paths, in content stream order.
+    pub fn paths(&self) -> &[ExtractedPath] {
+        &self.paths
+    }
+
+    /// Consume the extractor and return all recorded paths, in content stream order.
+    pub fn into_paths(self) -> Vec<ExtractedPath> {
+        self.paths
+    }
+}
+
+impl Device<'_> for PathExtractor {
+    fn draw_path(&mut self, path: &BezPath, props: DrawProps<'_>, draw_mode: &DrawMode) {
+        if matches!(draw_mode, DrawMode::Invisible) {
+            return;
+        }
+
+        let color = match &props.paint {
+            Paint::Color(c) => Some(c.to_rgba()),
+            Paint::Pattern(_) => None,
+        };
+
+        let clip_paths = self
+            .shared_clips
+            .get_or_insert_with(|| Rc::from(self.clip_stack.as_slice()))
+            .clone();
+
+        self.paths.push(ExtractedPath {
+            path: path.clone(),
+            transform: props.transform,
+            draw_mode: draw_mode.clone(),
+            color,
+            clip_paths,
+        });
+    }
+
+    fn push_clip_path(&mut self, clip_path: &ClipPath) {
+        self.clip_stack.push(clip_path.clone());
+        self.shared_clips = None;
+    }
+
+    fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'_>>, _: BlendMode) {}
+
+    fn draw_glyph(&mut self, _: &Glyph<'_>, _: Affine, _: DrawProps<'_>, _: &DrawMode) {}
+
+    fn draw_image(&mut self, _: Image<'_, '_>, _: ImageDrawProps<'_>) {}
+
+    fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+        self.shared_clips = None;
+    }
+
+    fn pop_transparency_group(&mut self) {}
+}
@@ -2,17 +2,22 @@
 
 use crate::cache::Cache;
 use crate::color::{Color, ColorSpace};
-use crate::context::{Context, InterpreterCache};
+use crate::context::{Context, InterpreterCache, check_cycle, check_nesting_depth};
 use crate::device::Device;
+use crate::display_list::DisplayList;
 use crate::font::Glyph;
 use crate::interpret::state::{ActiveTransferFunction, State};
 use crate::shading::Shading;
 use crate::soft_mask::SoftMask;
 use crate::util::{Float32Ext, RectExt, hash128};
 use crate::{BlendMode, CacheKey, ClipPath, DrawMode, DrawProps, Image, ImageDrawProps};
-use crate::{FillRule, InterpreterSettings, Paint, interpret};
+use crate::{
+    FillRule, InterpreterSettings, InterpreterWarning, Paint, UnsupportedFeature, WarningSinkFn,
+    interpret,
+};
 use hayro_syntax::content::TypedIter;
 use hayro_syntax::object::Dict;
+use hayro_syntax::object::ObjectIdentifier;
 use hayro_syntax::object::Stream;
 use hayro_syntax::object::dict::keys::{
     BBOX, EXT_G_STATE, MATRIX, PAINT_TYPE, RESOURCES, SHADING, X_STEP, Y_STEP,
@@ -22,6 +27,7 @@ use hayro_syntax::page::Resources;
 use hayro_syntax::xref::XRef;
 use kurbo::{Affine, BezPath, Rect, Shape};
 use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
 use std::sync::Arc;
 
 /// A PDF pattern.
@@ -44,6 +50,7 @@ impl<'a> Pattern<'a> {
                 &dict,
                 &ctx.interpreter_cache.object_cache,
                 ctx.get().graphics_state.non_stroke_alpha,
+                &ctx.settings.warning_sink,
             )?)),
             Object::Stream(stream) => Some(Self::Tiling(Box::new(TilingPattern::new(
                 stream, ctx, resources,
@@ -63,6 +70,22 @@ impl<'a> Pattern<'a> {
         }
     }
 
+    // Unlike `pre_concat_transform`, this assumes that the pattern has already been
+    // fully resolved, i.e. that the clip path of a shading is in device space.
+    pub(crate) fn pre_concat_device_transform(&mut self, transform: Affine) {
+        match self {
+            Self::Shading(p) => {
+                p.matrix = transform * p.matrix;
+                let transformed_clip_path = p.shading.clip_path.clone().map(|r| transform * r);
+                Arc::make_mut(&mut p.shading).clip_path = transformed_clip_path;
+            }
+            Self::Tiling(p) => {
+                p.matrix = transform * p.matrix;
+                p.ctx_bbox = (transform * p.ctx_bbox.to_path(0.1)).bounding_box();
+            }
+        }
+    }
+
     pub(crate) fn set_transfer_function(&mut self, tf: ActiveTransferFunction) {
         if let Self::Shading(p) = self {
             p.transfer_function = Some(tf);
@@ -93,11 +116,16 @@ pub struct ShadingPattern {
 }
 
 impl ShadingPattern {
-    pub(crate) fn new(dict: &Dict<'_>, cache: &Cache, opacity: f32) -> Option<Self> {
+    pub(crate) fn new(
+        dict: &Dict<'_>,
+        cache: &Cache,
+        opacity: f32,
+        warning_sink: &WarningSinkFn,
+    ) -> Option<Self> {
         let shading = dict.get::<Object<'_>>(SHADING).and_then(|o| {
             let (dict, stream) = dict_or_stream(&o)?;
 
-            Shading::new(dict, stream, cache)
+            Shading::new(dict, stream, cache, warning_sink)
         })?;
         let matrix = dict
             .get::<[f64; 6]>(MATRIX)
@@ -106,6 +134,9 @@ impl ShadingPattern {
 
         if dict.contains_key(EXT_G_STATE) {
             warn!("shading patterns with ext_g_state are not supported yet");
+            warning_sink(InterpreterWarning::UnsupportedFeature(
+                UnsupportedFeature::ShadingPatternExtGState,
+            ));
         }
 
         Some(Self {
@@ -123,6 +154,13 @@ impl CacheKey for ShadingPattern {
     }
 }
 
+/// The number of pattern cells needed to cover the area a tiling pattern is
+/// painted on from which on the pattern is made coarser, provided that its
+/// cells are smaller than one unit.
+///
+/// See [`TilingPattern::clamp_tile_count`].
+pub const MAX_TILE_COUNT: f64 = (1 << 20) as f64;
+
 /// A tiling pattern.
 #[derive(Clone)]
 pub struct TilingPattern<'a> {
@@ -145,6 +183,16 @@ pub struct TilingPattern<'a> {
     pub(crate) settings: InterpreterSettings,
     pub(crate) xref: &'a XRef,
     nesting_depth: u32,
+    active_streams: Vec<ObjectIdentifier>,
+    recorded: Option<Rc<RecordedTiling<'a>>>,
+}
+
+/// The recorded contents of a tiling pattern that is part of a display list.
+pub(crate) struct RecordedTiling<'a> {
+    pub(crate) fill: DisplayList<'a>,
+    // Only needed for uncolored patterns, where the stroke paint can differ
+    // from the fill paint.
+    pub(crate) stroke: Option<DisplayList<'a>>,
 }
 
 impl Debug for TilingPattern<'_> {
@@ -159,7 +207,9 @@ impl<'a> TilingPattern<'a> {
         ctx: &Context<'a>,
         resources: &Resources<'a>,
     ) -> Option<Self> {
-        let cache_key = stream.cache_key();
+        // The contents of a pattern can differ if parts of it are skipped because
+        // of cycles.
+        let cache_key = hash128(&(stream.cache_key(), ctx.active_streams()));
         let dict = stream.dict();
 
         let bbox = dict.get::<hayro_syntax::object::Rect>(BBOX)?.to_kurbo();
@@ -194,12 +244,14 @@ impl<'a> TilingPattern<'a> {
             fill_cs,
             state.graphics_state.non_stroke_color.clone(),
             state.graphics_state.non_stroke_alpha,
-        );
+        )
+        .with_rendering_intent(state.graphics_state.rendering_intent);
         let stroke_paint = Color::new(
             stroke_cs,
             state.graphics_state.stroke_color.clone(),
             state.graphics_state.stroke_alpha,
-        );
+        )
+        .with_rendering_intent(state.graphics_state.rendering_intent);
         let nesting_depth = ctx.nesting_depth() + 1;
 
         Some(Self {
@@ -218,6 +270,8 @@ impl<'a> TilingPattern<'a> {
             cache: ctx.interpreter_cache.clone(),
             xref: ctx.xref,
             nesting_depth,
+            active_streams: ctx.active_streams().to_vec(),
+            recorded: None,
         })
     }
 
@@ -228,7 +282,27 @@ impl<'a> TilingPattern<'a> {
         initial_transform: Affine,
         is_stroke: bool,
     ) -> Option<()> {
+        if let Some(recorded) = &self.recorded {
+            let list = match &recorded.stroke {
+                Some(stroke) if is_stroke => stroke,
+                _ => &recorded.fill,
+            };
+            list.replay_with_transform(initial_transform, device);
+
+            return Some(());
+        }
+
+        let obj_id = self.stream.obj_id();
+
+        if !check_nesting_depth(self.nesting_depth, &self.settings)
+            || !check_cycle(&self.active_streams, obj_id)
+        {
+            return None;
+        }
+
         let state = State::new(initial_transform);
+        let mut active_streams = self.active_streams.clone();
+        active_streams.push(obj_id);
 
         let mut context = Context::new_with(
             state.ctm,
@@ -239,6 +313,7 @@ impl<'a> TilingPattern<'a> {
             self.settings.clone(),
             state,
             self.nesting_depth,
+            active_streams,
         );
 
         let decoded = self.stream.decoded().ok()?;
@@ -271,6 +346,54 @@ impl<'a> TilingPattern<'a> {
 
         Some(())
     }
+
+    /// Return the number of pattern cells needed to cover `bounds`.
+    ///
+    /// `transform` maps the coordinate system of the pattern matrix to the one
+    /// `bounds` is specified in.
+    pub fn tile_count(&self, transform: Affine, bounds: Rect) -> f64 {
+        let cell_area = (transform * self.matrix).determinant().abs()
+            * (self.x_step as f64 * self.y_step as f64).abs();
+
+        bounds.area() / cell_area
+    }
+
+    /// Make sure that a pathological number of pattern cells isn't needed to
+    /// cover `bounds`.
+    ///
+    /// A pattern with a tiny step can otherwise expand to millions of tiles
+    /// when painted over a large area. The number of cells is only limited if it
+    /// exceeds both [`MAX_TILE_COUNT`] and the area of `bounds`, i.e. if the cells
+    /// are smaller than one unit (usually a pixel) and there are a lot of them.
+    /// In this case, the pattern matrix is scaled up uniformly until either limit
+    /// is reached, so the pattern becomes coarser while the ratio of painted area
+    /// stays the same.
+    pub fn clamp_tile_count(&mut self, transform: Affine, bounds: Rect) {
+        let count = self.tile_count(transform, bounds);
+        let max_count = MAX_TILE_COUNT.max(bounds.area());
+
+        if count.is_finite() && count > max_count {
+            warn!("tiling pattern needs {count:.0} tiles, clamping to {max_count:.0}");
+
+            self.matrix *= Affine::scale((count / max_count).sqrt());
+        }
+    }
+
+    pub(crate) fn is_recorded(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    pub(crate) fn is_color(&self) -> bool {
+        self.is_color
+    }
+
+    pub(crate) fn ctx_bbox(&self) -> Rect {
+        self.ctx_bbox
+    }
+
+    pub(crate) fn set_recorded(&mut self, recorded: Rc<RecordedTiling<'a>>) {
+        self.recorded = Some(recorded);
+    }
 }
 
 impl CacheKey for TilingPattern<'_> {
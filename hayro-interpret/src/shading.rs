@@ -2,11 +2,11 @@
 
 #![allow(clippy::needless_range_loop)]
 
-use crate::CacheKey;
 use crate::cache::Cache;
 use crate::color::{ColorComponents, ColorSpace};
 use crate::function::{Function, StitchingBounds, Values, interpolate};
 use crate::util::{Float32Ext, PointExt, RectExt};
+use crate::{CacheKey, InterpreterWarning, UnsupportedFeature, WarningSinkFn};
 use hayro_syntax::bit_reader::BitReader;
 use hayro_syntax::object::Array;
 use hayro_syntax::object::Dict;
@@ -138,7 +138,12 @@ pub struct Shading {
 }
 
 impl Shading {
-    pub(crate) fn new(dict: &Dict<'_>, stream: Option<&Stream<'_>>, cache: &Cache) -> Option<Self> {
+    pub(crate) fn new(
+        dict: &Dict<'_>,
+        stream: Option<&Stream<'_>>,
+        cache: &Cache,
+        warning_sink: &WarningSinkFn,
+    ) -> Option<Self> {
         let cache_key = dict.cache_key();
 
         let shading_num = dict.get::<u8>(SHADING_TYPE)?;
@@ -289,7 +294,14 @@ impl Shading {
 
                 ShadingType::TensorProductPatchMesh { patches, function }
             }
-            _ => return None,
+            _ => {
+                warn!("unknown shading type {shading_num}");
+                warning_sink(InterpreterWarning::UnsupportedFeature(
+                    UnsupportedFeature::UnknownShadingType(shading_num),
+                ));
+
+                return None;
+            }
         };
 
         let bbox = dict.get::<Rect>(BBOX).map(|r| r.to_kurbo());
@@ -1,6 +1,7 @@
 use crate::color::{Color, ColorComponents, ColorSpace};
-use crate::context::{Context, InterpreterCache};
+use crate::context::{Context, InterpreterCache, check_nesting_depth};
 use crate::device::Device;
+use crate::display_list::DisplayList;
 use crate::function::Function;
 use crate::interpret::state::State;
 use crate::util::hash128;
@@ -19,9 +20,10 @@ use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Type type of mask.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum MaskType {
     /// A luminosity mask.
     Luminosity,
@@ -49,39 +51,70 @@ impl TransferFunction {
 
 struct Repr<'a> {
     obj_id: ObjectIdentifier,
-    group: FormXObject<'a>,
+    // Masks that share the same group can still differ in their type, backdrop
+    // and transfer function.
+    params_key: u128,
     mask_type: MaskType,
+    transfer_function: Option<TransferFunction>,
+    background: Color,
+    // The group that defines the contents of the mask. Masks without a group are
+    // created by the interpreter itself and always have recorded contents.
+    group: Option<MaskGroup<'a>>,
+}
+
+struct MaskGroup<'a> {
+    form: FormXObject<'a>,
     parent_resources: Resources<'a>,
     root_transform: Affine,
     bbox: kurbo::Rect,
     interpreter_cache: InterpreterCache<'a>,
-    transfer_function: Option<TransferFunction>,
     settings: InterpreterSettings,
-    background: Color,
     xref: &'a XRef,
     nesting_depth: u32,
+    active_streams: Vec<ObjectIdentifier>,
+}
+
+/// The number of masks without a group that were created so far, which is used
+/// for telling them apart.
+static NUM_GROUPLESS_MASKS: AtomicU64 = AtomicU64::new(0);
+
+/// A soft mask.
+#[derive(Clone)]
+pub struct SoftMask<'a> {
+    repr: Rc<Repr<'a>>,
+    // The recorded contents of the mask, if the mask is part of a display list.
+    recorded: Option<Rc<DisplayList<'a>>>,
+    // An additional transform to apply when replaying the recorded contents.
+    transform: Affine,
 }
 
-impl Hash for Repr<'_> {
+impl Hash for SoftMask<'_> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.obj_id.hash(state);
-        self.root_transform.cache_key().hash(state);
+        self.repr.obj_id.hash(state);
+        self.repr.params_key.hash(state);
+
+        if let Some(group) = &self.repr.group {
+            // The contents of a mask can differ if parts of it are skipped because
+            // of cycles.
+            group.active_streams.hash(state);
+            (self.transform * group.root_transform)
+                .cache_key()
+                .hash(state);
+        } else {
+            self.transform.cache_key().hash(state);
+        }
     }
 }
 
-/// A soft mask.
-#[derive(Clone, Hash)]
-pub struct SoftMask<'a>(Rc<Repr<'a>>);
-
 impl Debug for SoftMask<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "SoftMask({:?})", self.0.obj_id)
+        write!(f, "SoftMask({:?})", self.repr.obj_id)
     }
 }
 
 impl PartialEq for SoftMask<'_> {
     fn eq(&self, other: &Self) -> bool {
-        self.0.obj_id == other.0.obj_id
+        self.repr.obj_id == other.repr.obj_id && self.repr.params_key == other.repr.params_key
     }
 }
 
@@ -99,8 +132,6 @@ impl<'a> SoftMask<'a> {
         context: &Context<'a>,
         parent_resources: Resources<'a>,
     ) -> Option<Self> {
-        // TODO: With this setup, if there is a luminosity mask and alpha mask pointing to the
-        // same xobject, the ID will be the same.
         let obj_id = dict.get_ref(G)?.into();
         let group_stream = dict.get::<Stream<'_>>(G)?;
         let group = FormXObject::new(&group_stream)?;
@@ -128,58 +159,129 @@ impl<'a> SoftMask<'a> {
             ),
             _ => return None,
         };
+        // Function streams are identified by their reference, since only their
+        // dictionary is taken into account by their cache key.
+        let object_key = |key: &[u8]| {
+            dict.get_ref(key)
+                .map(|r| r.cache_key())
+                .or_else(|| dict.get::<Object<'_>>(key).map(|o| o.cache_key()))
+        };
+        let params_key = hash128(&(mask_type, object_key(BC), object_key(TR)));
         let nesting_depth = context.nesting_depth() + 1;
 
-        Some(Self(Rc::new(Repr {
-            obj_id,
-            group,
-            mask_type,
-            root_transform: context.get().ctm,
-            transfer_function,
-            bbox: context.bbox(),
-            interpreter_cache: context.interpreter_cache.clone(),
-            settings: context.settings.clone(),
-            xref: context.xref,
-            background,
-            parent_resources,
-            nesting_depth,
-        })))
+        Some(Self {
+            repr: Rc::new(Repr {
+                obj_id,
+                params_key,
+                mask_type,
+                transfer_function,
+                background,
+                group: Some(MaskGroup {
+                    form: group,
+                    parent_resources,
+                    root_transform: context.get().ctm,
+                    bbox: context.bbox(),
+                    interpreter_cache: context.interpreter_cache.clone(),
+                    settings: context.settings.clone(),
+                    xref: context.xref,
+                    nesting_depth,
+                    active_streams: context.active_streams().to_vec(),
+                }),
+            }),
+            recorded: None,
+            transform: Affine::IDENTITY,
+        })
+    }
+
+    /// Create an alpha mask from drawing operations that were recorded in device space.
+    pub(crate) fn from_recorded(recorded: DisplayList<'a>) -> Self {
+        let id = NUM_GROUPLESS_MASKS.fetch_add(1, Ordering::Relaxed);
+
+        Self {
+            repr: Rc::new(Repr {
+                obj_id: ObjectIdentifier::new(0, 0),
+                params_key: id as u128,
+                mask_type: MaskType::Alpha,
+                transfer_function: None,
+                background: Color::new(ColorSpace::device_gray(), smallvec![0.0], 1.0),
+                group: None,
+            }),
+            recorded: Some(Rc::new(recorded)),
+            transform: Affine::IDENTITY,
+        }
     }
 
     /// Interpret the contents of the mask into the given device.
     pub fn interpret(&self, device: &mut impl Device<'a>) {
-        let state = State::new(self.0.root_transform);
+        if let Some(recorded) = &self.recorded {
+            recorded.replay_with_transform(self.transform, device);
+
+            return;
+        }
+
+        let Some(group) = &self.repr.group else {
+            return;
+        };
+
+        if !check_nesting_depth(group.nesting_depth, &group.settings) {
+            return;
+        }
+
+        let state = State::new(group.root_transform);
         let mut ctx = Context::new_with(
-            self.0.root_transform,
-            self.0.bbox,
-            &self.0.interpreter_cache,
-            self.0.xref,
-            self.0.settings.clone(),
+            group.root_transform,
+            group.bbox,
+            &group.interpreter_cache,
+            group.xref,
+            group.settings.clone(),
             state,
-            self.0.nesting_depth,
+            group.nesting_depth,
+            // The group itself is added once it is drawn as a form XObject.
+            group.active_streams.clone(),
         );
-        draw_form_xobject(&self.0.parent_resources, &self.0.group, &mut ctx, device);
+        draw_form_xobject(&group.parent_resources, &group.form, &mut ctx, device);
+    }
+
+    pub(crate) fn is_recorded(&self) -> bool {
+        self.recorded.is_some()
+    }
+
+    pub(crate) fn with_recorded(&self, recorded: Rc<DisplayList<'a>>) -> Self {
+        Self {
+            repr: self.repr.clone(),
+            recorded: Some(recorded),
+            transform: Affine::IDENTITY,
+        }
+    }
+
+    pub(crate) fn pre_concat_transform(&self, transform: Affine) -> Self {
+        Self {
+            transform: transform * self.transform,
+            ..self.clone()
+        }
     }
 
     /// Return the object identifier of the mask.
     ///
-    /// This can be used as a unique identifier for caching purposes.
+    /// Masks that don't correspond to an object, like the ones used for clipping
+    /// by text that contains Type3 glyphs, have an object number of 0. Use the
+    /// cache key of the mask for telling those apart.
     pub fn id(&self) -> ObjectIdentifier {
-        self.0.obj_id
+        self.repr.obj_id
     }
 
     /// Return the underlying mask type.
     pub fn mask_type(&self) -> MaskType {
-        self.0.mask_type
+        self.repr.mask_type
     }
 
     /// The background color against which the mask should be composited.
     pub fn background_color(&self) -> Color {
-        self.0.background.clone()
+        self.repr.background.clone()
     }
 
     /// Return the transfer function that should be used for the mask.
     pub fn transfer_function(&self) -> Option<&TransferFunction> {
-        self.0.transfer_function.as_ref()
+        self.repr.transfer_function.as_ref()
     }
 }
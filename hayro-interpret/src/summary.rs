@@ -0,0 +1,171 @@
+//! Summarizing the contents of a page without rendering it.
+
+use crate::font::Glyph;
+use crate::util::TransformExt;
+use crate::{
+    BlendMode, CacheKey, ClipPath, Context, Device, DrawMode, DrawProps, Image, ImageDrawProps,
+    InterpreterCache, InterpreterSettings, OpContext, SoftMask, interpret_page,
+};
+use hayro_syntax::page::Page;
+use kurbo::{Affine, BezPath, Rect};
+
+/// The minimum fraction of the page that needs to be covered by a single image
+/// for the page to be considered scanned.
+const SCANNED_IMAGE_COVERAGE: f64 = 0.9;
+
+/// A summary of the drawing operations of a page.
+///
+/// All areas are in the coordinate space that is used when rendering the page,
+/// i.e. in square points.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContentSummary {
+    /// The number of text-showing operators (`Tj`, `TJ`, `'` and `"`) that
+    /// drew at least one glyph.
+    pub text_shows: usize,
+    /// The number of glyphs that were drawn visibly.
+    pub glyphs: usize,
+    /// The number of glyphs that were drawn with the invisible text rendering
+    /// mode, as used for example by OCR software to overlay scanned pages with text.
+    pub invisible_glyphs: usize,
+    /// The number of images that were drawn, including inline images and
+    /// image masks.
+    pub images: usize,
+    /// The number of paths that were filled or stroked, excluding clip paths.
+    pub paths: usize,
+    /// The sum of the areas of all images that lie within the page.
+    ///
+    /// The area of each image is approximated by its bounding box, and overlapping
+    /// images are counted multiple times.
+    pub image_area: f64,
+    /// The area of the largest image that lies within the page.
+    pub largest_image_area: f64,
+    /// The area of the page.
+    pub page_area: f64,
+}
+
+impl ContentSummary {
+    /// Return the fraction of the page that is covered by its largest image,
+    /// between 0.0 and 1.0.
+    pub fn image_coverage(&self) -> f64 {
+        if self.page_area > 0.0 {
+            (self.largest_image_area / self.page_area).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Return whether the page likely is a scanned page, i.e. whether it mostly
+    /// consists of a single image covering the whole page and doesn't contain
+    /// any visible text.
+    ///
+    /// Invisible text is ignored, so pages that have already been processed by
+    /// OCR software are still considered to be scanned.
+    pub fn is_likely_scanned(&self) -> bool {
+        self.glyphs == 0 && self.image_coverage() >= SCANNED_IMAGE_COVERAGE
+    }
+}
+
+/// Summarize the drawing operations of the given page.
+///
+/// This interprets the page in the same way as rendering it would, but without
+/// decoding images or rasterizing anything.
+pub fn content_summary<'a>(
+    page: &Page<'a>,
+    cache: &InterpreterCache<'a>,
+    settings: InterpreterSettings,
+) -> ContentSummary {
+    let (width, height) = page.render_dimensions();
+    let page_rect = Rect::new(0.0, 0.0, width as f64, height as f64);
+    let mut context = Context::new(
+        page.initial_transform(true).to_kurbo(),
+        page_rect,
+        cache,
+        page.xref(),
+        settings,
+    );
+
+    let mut device = SummaryDevice {
+        summary: ContentSummary {
+            page_area: page_rect.area(),
+            ..Default::default()
+        },
+        page_rect,
+        new_op: false,
+        last_glyph: None,
+    };
+    interpret_page(page, &mut context, &mut device);
+
+    device.summary
+}
+
+struct SummaryDevice {
+    summary: ContentSummary,
+    page_rect: Rect,
+    // Whether no glyph has been drawn since the current operator started.
+    new_op: bool,
+    // The cache key and the transform of the last drawn glyph.
+    last_glyph: Option<(u128, Affine)>,
+}
+
+impl Device<'_> for SummaryDevice {
+    fn draw_path(&mut self, _: &BezPath, _: DrawProps<'_>, _: &DrawMode) {
+        self.summary.paths += 1;
+    }
+
+    fn push_clip_path(&mut self, _: &ClipPath) {}
+
+    fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'_>>, _: BlendMode) {}
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'_>,
+        glyph_transform: Affine,
+        props: DrawProps<'_>,
+        draw_mode: &DrawMode,
+    ) {
+        let key = match glyph {
+            Glyph::Outline(g) => g.identifier().cache_key(),
+            Glyph::Type3(g) => g.cache_key(),
+        };
+        let current = (key, props.transform * glyph_transform);
+
+        // For the fill-and-stroke text rendering mode, each glyph is drawn twice.
+        if matches!(draw_mode, DrawMode::Stroke(_)) && self.last_glyph == Some(current) {
+            return;
+        }
+
+        self.last_glyph = Some(current);
+
+        if self.new_op {
+            self.summary.text_shows += 1;
+            self.new_op = false;
+        }
+
+        if matches!(draw_mode, DrawMode::Invisible) {
+            self.summary.invisible_glyphs += 1;
+        } else {
+            self.summary.glyphs += 1;
+        }
+    }
+
+    fn draw_image(&mut self, image: Image<'_, '_>, props: ImageDrawProps<'_>) {
+        let image_rect = Rect::new(0.0, 0.0, image.width() as f64, image.height() as f64);
+        let area = props
+            .transform
+            .transform_rect_bbox(image_rect)
+            .intersect(self.page_rect)
+            .area();
+
+        self.summary.images += 1;
+        self.summary.image_area += area;
+        self.summary.largest_image_area = self.summary.largest_image_area.max(area);
+    }
+
+    fn pop_clip(&mut self) {}
+
+    fn pop_transparency_group(&mut self) {}
+
+    fn set_op_context(&mut self, _: &OpContext<'_>) {
+        self.new_op = true;
+    }
+}
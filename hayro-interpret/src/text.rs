@@ -0,0 +1,340 @@
+//! Extracting text from PDF content streams.
+//!
+//! The order in which glyphs appear in a content stream does not need to have
+//! anything to do with the order in which they should be read. A single line
+//! might be split across many `BT`/`ET` blocks, and right-to-left scripts are
+//! usually stored in visual order, i.e. exactly the way they are laid out on
+//! the page from left to right.
+//!
+//! [`TextExtractor`] is a [`Device`] that records all glyphs together with their
+//! position. Afterwards, [`TextExtractor::lines`] clusters them by their baseline
+//! into [`TextItem`]s, which can be converted into a string in content order,
+//! visual order, and (with the `bidi` feature) logical order.
+//!
+//! # Coordinates
+//!
+//! All glyph positions are in device space, i.e. they include the initial
+//! transform of the [`Context`] that was used for interpreting the page. The
+//! easiest way to get consistent positions is to use [`extract_text`], which
+//! uses the same initial transform as rendering: The origin is at the top-left
+//! corner of the page, the y-axis points downwards, one unit corresponds to one
+//! point, and the `/Rotate` entry of the page is applied. Therefore, the page
+//! spans from `(0, 0)` to [`Page::render_dimensions`], and text that appears
+//! upright on a rendered page also runs from left to right in this space,
+//! even if the page is rotated.
+
+use crate::font::Glyph;
+use crate::util::TransformExt;
+use crate::{
+    BlendMode, ClipPath, Context, Device, DrawMode, DrawProps, Image, ImageDrawProps,
+    InterpreterCache, InterpreterSettings, SoftMask, interpret_page,
+};
+use hayro_cmap::BfString;
+use hayro_syntax::page::Page;
+use kurbo::{Affine, BezPath, Point, Rect, Vec2};
+
+/// The maximum distance between two baselines (relative to the font size) so
+/// that the glyphs are still considered to be on the same line.
+const BASELINE_TOLERANCE: f64 = 0.5;
+/// The minimum gap between two glyphs (relative to the font size) for which a
+/// space will be inserted in case there isn't already an explicit one.
+const SPACE_THRESHOLD: f64 = 0.25;
+/// The advance (relative to the font size) we assume for glyphs that don't
+/// provide one, like type3 glyphs.
+const FALLBACK_ADVANCE: f64 = 0.5;
+
+/// A glyph with its Unicode text and position.
+#[derive(Clone, Debug)]
+pub struct TextGlyph {
+    /// The Unicode text of the glyph. Usually a single character, but ligatures
+    /// might for example map to multiple characters.
+    pub text: String,
+    /// The origin of the glyph in device space (see the
+    /// [module-level documentation](self#coordinates)).
+    pub origin: Point,
+    /// The horizontal advance of the glyph in device space.
+    pub advance: f64,
+    /// The font size in device space.
+    pub font_size: f64,
+    /// The unit vector pointing in writing direction.
+    right: Vec2,
+    /// The unit vector pointing from the baseline towards the ascender.
+    up: Vec2,
+}
+
+impl TextGlyph {
+    fn new(text: String, transform: Affine, advance: Option<f32>) -> Self {
+        let origin = transform * Point::ZERO;
+        // Glyph space has 1000 units per em.
+        let right = transform * Point::new(1000.0, 0.0) - origin;
+        let up = transform * Point::new(0.0, 1000.0) - origin;
+        let font_size = up.length();
+
+        let advance = advance
+            .map(|a| right.length() * a as f64 / 1000.0)
+            .unwrap_or(font_size * FALLBACK_ADVANCE);
+
+        Self {
+            text,
+            origin,
+            advance,
+            font_size,
+            right: right.normalize(),
+            up: up.normalize(),
+        }
+    }
+
+    /// The position of the glyph along the writing direction.
+    fn position(&self) -> f64 {
+        self.origin.to_vec2().dot(self.right)
+    }
+
+    /// The position of the baseline of the glyph.
+    fn baseline(&self) -> f64 {
+        self.origin.to_vec2().dot(self.up)
+    }
+}
+
+/// Extract all glyphs of the given page.
+///
+/// The positions of the glyphs are reported in the same coordinate space that is
+/// used when rendering the page, see the [module-level documentation](self#coordinates).
+pub fn extract_text<'a>(
+    page: &Page<'a>,
+    cache: &InterpreterCache<'a>,
+    settings: InterpreterSettings,
+) -> TextExtractor {
+    let (width, height) = page.render_dimensions();
+    let mut context = Context::new(
+        page.initial_transform(true).to_kurbo(),
+        Rect::new(0.0, 0.0, width as f64, height as f64),
+        cache,
+        page.xref(),
+        settings,
+    );
+
+    let mut extractor = TextExtractor::new();
+    interpret_page(page, &mut context, &mut extractor);
+
+    extractor
+}
+
+/// A device that records all visible and invisible glyphs that are drawn.
+///
+/// Glyphs without a Unicode mapping, as well as glyphs that are only used for
+/// clipping, are ignored.
+#[derive(Default)]
+pub struct TextExtractor {
+    glyphs: Vec<TextGlyph>,
+    last_transform: Option<Affine>,
+}
+
+impl TextExtractor {
+    /// Create a new text extractor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return all recorded glyphs, in content stream order.
+    pub fn glyphs(&self) -> &[TextGlyph] {
+        &self.glyphs
+    }
+
+    /// Group the recorded glyphs into lines.
+    ///
+    /// Glyphs are put onto the same line if they share the writing direction
+    /// and their baselines are close to each other, regardless of whether
+    /// they were shown in the same text object. The lines are ordered from
+    /// top to bottom.
+    pub fn lines(&self) -> Vec<TextItem> {
+        let mut lines: Vec<TextItem> = vec![];
+
+        for glyph in &self.glyphs {
+            let baseline = glyph.baseline();
+
+            let line = lines.iter_mut().find(|l| {
+                let first = &l.glyphs[0];
+                let tolerance = BASELINE_TOLERANCE * first.font_size.max(glyph.font_size);
+
+                first.right.dot(glyph.right) > 0.99 && (l.baseline - baseline).abs() <= tolerance
+            });
+
+            match line {
+                Some(line) => line.glyphs.push(glyph.clone()),
+                None => lines.push(TextItem {
+                    glyphs: vec![glyph.clone()],
+                    baseline,
+                }),
+            }
+        }
+
+        lines.sort_by(|a, b| b.baseline.total_cmp(&a.baseline));
+
+        lines
+    }
+}
+
+impl Device<'_> for TextExtractor {
+    fn draw_path(&mut self, _: &BezPath, _: DrawProps<'_>, _: &DrawMode) {}
+
+    fn push_clip_path(&mut self, _: &ClipPath) {}
+
+    fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'_>>, _: BlendMode) {}
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'_>,
+        glyph_transform: Affine,
+        props: DrawProps<'_>,
+        draw_mode: &DrawMode,
+    ) {
+        let transform = props.transform * glyph_transform;
+
+        // For the fill-and-stroke text rendering mode, each glyph is drawn twice.
+        if matches!(draw_mode, DrawMode::Stroke(_)) && self.last_transform == Some(transform) {
+            return;
+        }
+
+        self.last_transform = Some(transform);
+
+        let Some(text) = glyph.as_unicode().map(|s| match s {
+            BfString::Char(c) => c.to_string(),
+            BfString::String(s) => s,
+        }) else {
+            return;
+        };
+
+        let advance = match glyph {
+            Glyph::Outline(o) => o.advance_width(),
+            Glyph::Type3(_) => None,
+        };
+
+        self.glyphs.push(TextGlyph::new(text, transform, advance));
+    }
+
+    fn draw_image(&mut self, _: Image<'_, '_>, _: ImageDrawProps<'_>) {}
+
+    fn pop_clip(&mut self) {}
+
+    fn pop_transparency_group(&mut self) {}
+}
+
+/// A line of text.
+#[derive(Clone, Debug)]
+pub struct TextItem {
+    glyphs: Vec<TextGlyph>,
+    baseline: f64,
+}
+
+impl TextItem {
+    /// Return the glyphs of the line, in content stream order.
+    pub fn glyphs(&self) -> &[TextGlyph] {
+        &self.glyphs
+    }
+
+    /// Return the text of the line in the order the glyphs appear in the
+    /// content stream, without any further processing.
+    pub fn content_order(&self) -> String {
+        self.glyphs.iter().map(|g| g.text.as_str()).collect()
+    }
+
+    /// Return the text of the line in visual order, i.e. as it appears on the
+    /// page when read from left to right (or in writing direction, for rotated
+    /// text).
+    ///
+    /// Spaces are inserted for larger gaps between glyphs.
+    pub fn visual_order(&self) -> String {
+        let mut glyphs = self.glyphs.iter().collect::<Vec<_>>();
+        glyphs.sort_by(|a, b| a.position().total_cmp(&b.position()));
+
+        let mut text = String::new();
+        let mut last: Option<&TextGlyph> = None;
+
+        for glyph in glyphs {
+            if let Some(last) = last {
+                let gap = glyph.position() - (last.position() + last.advance);
+                let has_space = last.text.ends_with(char::is_whitespace)
+                    || glyph.text.starts_with(char::is_whitespace);
+
+                if !has_space && gap > SPACE_THRESHOLD * last.font_size.max(glyph.font_size) {
+                    text.push(' ');
+                }
+            }
+
+            text.push_str(&glyph.text);
+            last = Some(glyph);
+        }
+
+        text
+    }
+
+    /// Return the text of the line in logical order, i.e. the order in which it
+    /// should be read.
+    ///
+    /// The visual order is treated as the output of the Unicode bidirectional
+    /// algorithm and reordered back. The paragraph direction is right-to-left
+    /// if the line contains more strong right-to-left than left-to-right
+    /// characters. Characters in right-to-left runs that were mirrored for
+    /// display, like parentheses, are mirrored back.
+    #[cfg(feature = "bidi")]
+    pub fn logical_order(&self) -> String {
+        use unicode_bidi::{BidiClass, BidiInfo, Level, bidi_class};
+
+        let visual = self.visual_order();
+
+        let (mut rtl, mut ltr) = (0, 0);
+
+        for c in visual.chars() {
+            match bidi_class(c) {
+                BidiClass::R | BidiClass::AL => rtl += 1,
+                BidiClass::L => ltr += 1,
+                _ => {}
+            }
+        }
+
+        if rtl == 0 {
+            return visual;
+        }
+
+        let level = if rtl > ltr {
+            Level::rtl()
+        } else {
+            Level::ltr()
+        };
+        let info = BidiInfo::new(&visual, Some(level));
+        let mut text = String::with_capacity(visual.len());
+
+        for para in &info.paragraphs {
+            let (levels, runs) = info.visual_runs(para, para.range.clone());
+
+            for run in runs {
+                if levels[run.start].is_rtl() {
+                    text.extend(visual[run].chars().rev().map(mirror));
+                } else {
+                    text.push_str(&visual[run]);
+                }
+            }
+        }
+
+        text
+    }
+}
+
+#[cfg(feature = "bidi")]
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        _ => c,
+    }
+}
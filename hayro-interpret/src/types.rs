@@ -3,10 +3,11 @@ use crate::color::Color;
 use crate::pattern::Pattern;
 use crate::soft_mask::SoftMask;
 use crate::util::hash128;
-use crate::x_object::ImageXObject;
-use hayro_syntax::object::Stream;
+use crate::x_object::ImageSource;
+use hayro_syntax::object::{Dict, Stream};
 use kurbo::{Affine, BezPath, Cap, Join};
 use smallvec::{SmallVec, smallvec};
+use std::sync::Arc;
 
 /// A clip path.
 #[derive(Debug, Clone)]
@@ -26,7 +27,7 @@ impl CacheKey for ClipPath {
 /// A stencil image.
 pub struct StencilImage<'a, 'b> {
     pub(crate) paint: Paint<'a>,
-    pub(crate) image_xobject: ImageXObject<'b>,
+    pub(crate) source: ImageSource<'b>,
 }
 
 impl<'a, 'b> StencilImage<'a, 'b> {
@@ -37,13 +38,16 @@ impl<'a, 'b> StencilImage<'a, 'b> {
     /// mean that the resulting image will have that dimension. Instead, it allows
     /// the image decoder to extract a lower-resolution version of the image in
     /// certain cases.
+    ///
+    /// The data is only borrowed, so that images replayed from a
+    /// [`DisplayList`](crate::DisplayList) don't need to be copied on every draw.
     pub fn with_stencil(
         &self,
-        func: impl FnOnce(LumaData, &Paint<'a>),
+        func: impl FnOnce(&LumaData, &Paint<'a>),
         target_dimension: Option<(u32, u32)>,
     ) {
-        if let Some(decoded) = self.image_xobject.decoded_mask(target_dimension) {
-            func(decoded.luma, &self.paint);
+        if let Some(decoded) = self.source.decoded_mask(target_dimension) {
+            func(&decoded.luma, &self.paint);
         }
     }
 
@@ -51,23 +55,23 @@ impl<'a, 'b> StencilImage<'a, 'b> {
     // width/height from `LumaData` instead.
     #[doc(hidden)]
     pub fn width(&self) -> u32 {
-        self.image_xobject.width()
+        self.source.width()
     }
 
     #[doc(hidden)]
     pub fn height(&self) -> u32 {
-        self.image_xobject.height()
+        self.source.height()
     }
 }
 
 impl CacheKey for StencilImage<'_, '_> {
     fn cache_key(&self) -> u128 {
-        self.image_xobject.cache_key()
+        self.source.cache_key()
     }
 }
 
 /// A raster image.
-pub struct RasterImage<'a>(pub(crate) ImageXObject<'a>);
+pub struct RasterImage<'a>(pub(crate) ImageSource<'a>);
 
 impl RasterImage<'_> {
     /// Perform some operation with the RGB and alpha channel of the image.
@@ -77,20 +81,36 @@ impl RasterImage<'_> {
     /// mean that the resulting image will have that dimension. Instead, it allows
     /// the image decoder to extract a lower-resolution version of the image in
     /// certain cases.
+    ///
+    /// The data is only borrowed, so that images replayed from a
+    /// [`DisplayList`](crate::DisplayList) don't need to be copied on every draw.
     pub fn with_rgba(
         &self,
-        func: impl FnOnce(ImageData, Option<LumaData>),
+        func: impl FnOnce(&ImageData, Option<&LumaData>),
         target_dimension: Option<(u32, u32)>,
     ) {
         if let Some(decoded) = self.0.decoded_raster(target_dimension) {
-            func(decoded.image, decoded.alpha);
+            func(&decoded.image, decoded.alpha.as_ref());
         }
     }
 
     /// Return the underlying stream object.
     ///
     /// This allows you to get access to the raw encoded image data, without doing any decoding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image has already been decoded, for example because it is replayed
+    /// from a [`DisplayList`](crate::DisplayList). Use [`RasterImage::try_stream`] if
+    /// the image can come from a display list.
     pub fn stream(&self) -> &Stream<'_> {
+        self.try_stream()
+            .expect("the image was replayed from a display list")
+    }
+
+    /// Return the underlying stream object, or `None` if the image has already been
+    /// decoded, for example because it is replayed from a [`DisplayList`](crate::DisplayList).
+    pub fn try_stream(&self) -> Option<&Stream<'_>> {
         self.0.stream()
     }
 
@@ -253,7 +273,9 @@ pub enum Paint<'a> {
     /// A solid RGBA color.
     Color(Color),
     /// A PDF pattern.
-    Pattern(Box<Pattern<'a>>),
+    ///
+    /// The pattern is reference-counted so that cloning a paint is cheap.
+    Pattern(Arc<Pattern<'a>>),
 }
 
 impl CacheKey for Paint<'_> {
@@ -280,6 +302,15 @@ pub struct DrawProps<'a> {
     pub soft_mask: Option<SoftMask<'a>>,
     /// The blend mode.
     pub blend_mode: BlendMode,
+    /// Whether the paint overprints the backdrop.
+    ///
+    /// If this is `true`, the paint only affects some of the colorants of the
+    /// backdrop, while the others remain unchanged. This is only set for
+    /// overprinting `DeviceCMYK`, Separation and `DeviceN` colors that don't paint
+    /// all colorants, and can be used to simulate overprinting.
+    pub overprint: bool,
+    /// The device-dependent graphics state parameters.
+    pub device_params: DeviceParams<'a>,
 }
 
 /// Properties for an image drawing operation.
@@ -336,6 +367,42 @@ impl Default for StrokeProps {
     }
 }
 
+/// A halftone, as specified by the `HT` entry of an `ExtGState` dictionary.
+#[derive(Clone, Debug)]
+pub enum Halftone<'a> {
+    /// The default halftone of the output device.
+    Default,
+    /// A halftone dictionary, i.e. a type 1 or type 5 halftone.
+    Dict(Dict<'a>),
+    /// A halftone stream, i.e. a type 6, 10 or 16 halftone.
+    Stream(Stream<'a>),
+}
+
+/// Device-dependent graphics state parameters.
+///
+/// These parameters don't affect rendering, but they are tracked so that devices
+/// that write the content into another format can preserve them.
+#[derive(Clone, Debug)]
+pub struct DeviceParams<'a> {
+    /// The flatness tolerance, as set by the `i` operator or the `FL` entry.
+    pub flatness: f32,
+    /// The smoothness tolerance, or `None` if it wasn't set and the device
+    /// default applies.
+    pub smoothness: Option<f32>,
+    /// The halftone, or `None` if it wasn't set and the device default applies.
+    pub halftone: Option<Halftone<'a>>,
+}
+
+impl Default for DeviceParams<'_> {
+    fn default() -> Self {
+        Self {
+            flatness: 1.0,
+            smoothness: None,
+            halftone: None,
+        }
+    }
+}
+
 /// A fill rule.
 #[derive(Clone, Debug, Copy, Hash, PartialEq, Eq)]
 pub enum FillRule {
@@ -0,0 +1,477 @@
+//! Checking the well-formedness of a PDF file.
+
+use crate::font::{is_parseable_font_program, is_standard_font};
+use hayro_syntax::Pdf;
+use hayro_syntax::object::dict::keys::*;
+use hayro_syntax::object::{Array, Dict, MaybeRef, Name, Object, ObjectIdentifier, Stream};
+use hayro_syntax::xref::XRef;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// How thoroughly a PDF file should be validated.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ValidationDepth {
+    /// Only check the structure of the file, i.e. whether pages can be loaded
+    /// and whether all references are valid. Streams are not decoded.
+    Structural,
+    /// Additionally decode all streams and parse all embedded font programs.
+    /// This can be expensive for large files.
+    Full,
+}
+
+/// The severity of a finding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// The file violates the specification, but the problem can be recovered from.
+    Warning,
+    /// Some of the contents of the file are broken and will likely not be
+    /// displayed correctly.
+    Error,
+}
+
+/// The kind of problem a finding describes.
+///
+/// Each kind has a stable, machine-readable code that can be retrieved via
+/// [`FindingCode::as_str`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FindingCode {
+    /// The `Count` entry of the page tree doesn't match the number of pages.
+    PageCountMismatch,
+    /// The `Contents` entry of a page doesn't point to a stream or an array of streams.
+    InvalidPageContents,
+    /// An `XObject` resource references an object that doesn't exist.
+    DanglingXObjectRef,
+    /// A font resource references an object that doesn't exist.
+    DanglingFontRef,
+    /// Any other resource references an object that doesn't exist.
+    DanglingResourceRef,
+    /// A font that is not one of the standard fonts has no font descriptor.
+    MissingFontDescriptor,
+    /// A Type0 font has no descendant font.
+    MissingDescendantFont,
+    /// The `Length` entry of a stream doesn't match its actual length.
+    StreamLengthMismatch,
+    /// A stream couldn't be decoded.
+    StreamDecodeFailure,
+    /// An embedded font program couldn't be parsed.
+    UnparseableFont,
+}
+
+impl FindingCode {
+    /// Return the stable code of the finding, for example `dangling-xobject-ref`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PageCountMismatch => "page-count-mismatch",
+            Self::InvalidPageContents => "invalid-page-contents",
+            Self::DanglingXObjectRef => "dangling-xobject-ref",
+            Self::DanglingFontRef => "dangling-font-ref",
+            Self::DanglingResourceRef => "dangling-resource-ref",
+            Self::MissingFontDescriptor => "missing-font-descriptor",
+            Self::MissingDescendantFont => "missing-descendant-font",
+            Self::StreamLengthMismatch => "stream-length-mismatch",
+            Self::StreamDecodeFailure => "stream-decode-failure",
+            Self::UnparseableFont => "unparseable-font",
+        }
+    }
+
+    /// Return the severity of findings of this kind.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::PageCountMismatch | Self::MissingFontDescriptor | Self::StreamLengthMismatch => {
+                Severity::Warning
+            }
+            Self::InvalidPageContents
+            | Self::DanglingXObjectRef
+            | Self::DanglingFontRef
+            | Self::DanglingResourceRef
+            | Self::MissingDescendantFont
+            | Self::StreamDecodeFailure
+            | Self::UnparseableFont => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for FindingCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single problem that was found while validating a PDF file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The severity of the finding.
+    pub severity: Severity,
+    /// The kind of the finding.
+    pub code: FindingCode,
+    /// The object the finding refers to, if any. For dangling references, this
+    /// is the identifier of the object that doesn't exist.
+    pub object: Option<ObjectIdentifier>,
+    /// The index of the page on which the problem was found, if any.
+    pub page: Option<usize>,
+    /// A human-readable description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(f, "{severity}[{}]", self.code)?;
+
+        if let Some(object) = self.object {
+            write!(f, " {} {} R", object.obj_number, object.gen_number)?;
+        }
+
+        if let Some(page) = self.page {
+            write!(f, " (page {page})")?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// The result of validating a PDF file.
+///
+/// The findings are reported in a deterministic order, so that the reports of
+/// the same file can be compared across runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Return all findings.
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// Return whether no errors were found. Warnings are ignored.
+    pub fn is_valid(&self) -> bool {
+        self.findings.iter().all(|f| f.severity != Severity::Error)
+    }
+}
+
+/// Check the well-formedness of a PDF file.
+///
+/// This checks whether all pages can be loaded, whether the resources of pages,
+/// form `XObject`s and Type3 fonts reference existing objects and whether fonts
+/// are specified properly. With [`ValidationDepth::Full`], all streams are
+/// additionally decoded and all embedded font programs parsed.
+pub fn validate(pdf: &Pdf, depth: ValidationDepth) -> ValidationReport {
+    let mut validator = Validator {
+        xref: pdf.xref(),
+        depth,
+        findings: vec![],
+        reported: BTreeSet::new(),
+        visited: BTreeSet::new(),
+    };
+
+    validator.check_page_tree(pdf);
+    validator.check_pages(pdf);
+    validator.check_streams();
+
+    ValidationReport {
+        findings: validator.findings,
+    }
+}
+
+struct Validator<'a> {
+    xref: &'a XRef,
+    depth: ValidationDepth,
+    findings: Vec<Finding>,
+    // Problems with the same object are only reported once, even if the object
+    // is used on multiple pages.
+    reported: BTreeSet<(FindingCode, ObjectIdentifier)>,
+    // Objects whose resources and fonts have already been checked.
+    visited: BTreeSet<ObjectIdentifier>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(
+        &mut self,
+        code: FindingCode,
+        object: Option<ObjectIdentifier>,
+        page: Option<usize>,
+        message: String,
+    ) {
+        if let Some(object) = object
+            && !self.reported.insert((code, object))
+        {
+            return;
+        }
+
+        self.findings.push(Finding {
+            severity: code.severity(),
+            code,
+            object,
+            page,
+            message,
+        });
+    }
+
+    fn check_page_tree(&mut self, pdf: &Pdf) {
+        let Some(pages_dict) = self
+            .xref
+            .get::<Dict<'_>>(self.xref.root_id())
+            .and_then(|catalog| catalog.get::<Dict<'_>>(PAGES))
+        else {
+            return;
+        };
+
+        let num_pages = pdf.pages().iter().count();
+
+        match pages_dict.get::<usize>(COUNT) {
+            Some(count) if count == num_pages => {}
+            count => self.report(
+                FindingCode::PageCountMismatch,
+                pages_dict.obj_id(),
+                None,
+                format!(
+                    "page tree has a count of {}, but contains {num_pages} pages",
+                    count.map_or("none".to_string(), |c| c.to_string())
+                ),
+            ),
+        }
+    }
+
+    fn check_pages(&mut self, pdf: &'a Pdf) {
+        for (index, page) in pdf.pages().iter().enumerate() {
+            let raw = page.raw();
+
+            if raw.contains_key(CONTENTS) {
+                let valid = match raw.get::<Object<'_>>(CONTENTS) {
+                    Some(Object::Stream(_)) => true,
+                    Some(Object::Array(array)) => array
+                        .raw_iter()
+                        .all(|obj| self.resolve(&obj).and_then(Object::into_stream).is_some()),
+                    _ => false,
+                };
+
+                if !valid {
+                    self.report(
+                        FindingCode::InvalidPageContents,
+                        raw.obj_id(),
+                        Some(index),
+                        "page contents are not a stream or an array of streams".to_string(),
+                    );
+                }
+            }
+
+            let mut resources = vec![];
+            let mut current = Some(page.resources());
+
+            while let Some(r) = current {
+                resources.push([
+                    (XOBJECT, r.x_objects.clone()),
+                    (FONT, r.fonts.clone()),
+                    (EXT_G_STATE, r.ext_g_states.clone()),
+                    (COLORSPACE, r.color_spaces.clone()),
+                    (PATTERN, r.patterns.clone()),
+                    (SHADING, r.shadings.clone()),
+                    (PROPERTIES, r.properties.clone()),
+                ]);
+                current = r.parent();
+            }
+
+            for categories in resources {
+                self.check_resources(categories, index);
+            }
+        }
+    }
+
+    /// Check the given resource categories and, iteratively, the resources of
+    /// all form `XObject`s, patterns and Type3 fonts that are referenced by them.
+    fn check_resources(&mut self, categories: [(&'static [u8], Dict<'a>); 7], page: usize) {
+        let mut pending = vec![categories];
+
+        while let Some(categories) = pending.pop() {
+            for (category, dict) in categories {
+                for (name, entry) in dict.entries() {
+                    let (object, id) = match entry {
+                        MaybeRef::Ref(r) => {
+                            let id = r.into();
+
+                            let Some(object) = self.xref.get::<Object<'_>>(id) else {
+                                let code = match category {
+                                    XOBJECT => FindingCode::DanglingXObjectRef,
+                                    FONT => FindingCode::DanglingFontRef,
+                                    _ => FindingCode::DanglingResourceRef,
+                                };
+
+                                self.report(
+                                    code,
+                                    Some(id),
+                                    Some(page),
+                                    format!(
+                                        "resource /{} in /{} references a missing object",
+                                        name.as_str(),
+                                        std::str::from_utf8(category).unwrap_or("unknown")
+                                    ),
+                                );
+
+                                continue;
+                            };
+
+                            if !self.visited.insert(id) {
+                                continue;
+                            }
+
+                            (object, Some(id))
+                        }
+                        MaybeRef::NotRef(object) => (object, None),
+                    };
+
+                    let dict = match object {
+                        Object::Dict(dict) => dict,
+                        Object::Stream(stream) => stream.dict().clone(),
+                        _ => continue,
+                    };
+
+                    if category == FONT {
+                        self.check_font(&dict, id, page);
+                    }
+
+                    if let Some(resources) = dict.get::<Dict<'_>>(RESOURCES)
+                        && [XOBJECT, FONT, PATTERN].contains(&category)
+                    {
+                        pending.push(
+                            [
+                                XOBJECT,
+                                FONT,
+                                EXT_G_STATE,
+                                COLORSPACE,
+                                PATTERN,
+                                SHADING,
+                                PROPERTIES,
+                            ]
+                            .map(|c| (c, resources.get::<Dict<'_>>(c).unwrap_or_default())),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_font(&mut self, dict: &Dict<'a>, id: Option<ObjectIdentifier>, page: usize) {
+        let id = id.or_else(|| dict.obj_id());
+
+        let descriptor_font = match dict.get::<Name<'_>>(SUBTYPE).as_deref() {
+            Some(TYPE3) => return,
+            Some(TYPE0) => {
+                let Some(descendant) = dict
+                    .get::<Array<'_>>(DESCENDANT_FONTS)
+                    .and_then(|a| a.iter::<Dict<'_>>().next())
+                else {
+                    self.report(
+                        FindingCode::MissingDescendantFont,
+                        id,
+                        Some(page),
+                        "Type0 font has no descendant font".to_string(),
+                    );
+
+                    return;
+                };
+
+                descendant
+            }
+            _ if is_standard_font(dict) => {
+                if !dict.contains_key(FONT_DESC) {
+                    return;
+                }
+
+                dict.clone()
+            }
+            _ => dict.clone(),
+        };
+
+        let Some(descriptor) = descriptor_font.get::<Dict<'_>>(FONT_DESC) else {
+            self.report(
+                FindingCode::MissingFontDescriptor,
+                descriptor_font.obj_id().or(id),
+                Some(page),
+                format!(
+                    "font {} has no font descriptor",
+                    dict.get::<Name<'_>>(BASE_FONT)
+                        .as_ref()
+                        .map_or("without name", |n| n.as_str())
+                ),
+            );
+
+            return;
+        };
+
+        if self.depth != ValidationDepth::Full {
+            return;
+        }
+
+        let Some(font_file) = [FONT_FILE, FONT_FILE2, FONT_FILE3]
+            .into_iter()
+            .find_map(|key| descriptor.get::<Stream<'_>>(key))
+        else {
+            return;
+        };
+
+        // Streams that can't be decoded at all are already reported when
+        // checking the streams.
+        let Ok(data) = font_file.decoded() else {
+            return;
+        };
+
+        if !is_parseable_font_program(Arc::new(data.into_owned())) {
+            self.report(
+                FindingCode::UnparseableFont,
+                Some(font_file.obj_id()),
+                Some(page),
+                "embedded font program couldn't be parsed".to_string(),
+            );
+        }
+    }
+
+    fn check_streams(&mut self) {
+        for id in self.xref.object_ids() {
+            let Some(Object::Stream(stream)) = self.xref.get::<Object<'_>>(id) else {
+                continue;
+            };
+
+            if !stream.has_valid_length() {
+                self.report(
+                    FindingCode::StreamLengthMismatch,
+                    Some(id),
+                    None,
+                    format!(
+                        "stream length of {} doesn't match the position of `endstream`",
+                        stream
+                            .dict()
+                            .get::<u32>(LENGTH)
+                            .map_or("none".to_string(), |l| l.to_string())
+                    ),
+                );
+            }
+
+            if self.depth == ValidationDepth::Full && stream.decoded().is_err() {
+                self.report(
+                    FindingCode::StreamDecodeFailure,
+                    Some(id),
+                    None,
+                    format!(
+                        "stream with filters {:?} couldn't be decoded",
+                        stream.filters().deref()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn resolve(&self, object: &MaybeRef<Object<'a>>) -> Option<Object<'a>> {
+        match object {
+            MaybeRef::Ref(r) => self.xref.get::<Object<'_>>((*r).into()),
+            MaybeRef::NotRef(object) => Some(object.clone()),
+        }
+    }
+}
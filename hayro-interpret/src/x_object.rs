@@ -1,11 +1,16 @@
 use crate::cache::Cache;
-use crate::color::{ColorComponents, ColorSpace, ToRgb};
+use crate::color::{Color, ColorComponents, ColorSpace, ToRgb};
 use crate::context::Context;
 use crate::device::Device;
-use crate::function::{Function, interpolate};
+use crate::function::interpolate;
 use crate::interpret::state::ActiveTransferFunction;
-use crate::{BlendMode, CacheKey, ClipPath, Image, ImageDrawProps, RasterImage, StencilImage};
-use crate::{FillRule, InterpreterWarning, WarningSinkFn, interpret};
+use crate::{
+    BlendMode, CacheKey, DeviceParams, DrawMode, DrawProps, Image, ImageDrawProps, Paint,
+    RasterImage, StencilImage, StrokeProps,
+};
+use crate::{
+    FillRule, InterpreterSettings, InterpreterWarning, UnsupportedFeature, WarningSinkFn, interpret,
+};
 use crate::{ImageData, LumaData, RgbData};
 use hayro_syntax::bit_reader::BitReader;
 use hayro_syntax::content::TypedIter;
@@ -13,15 +18,19 @@ use hayro_syntax::object::Array;
 use hayro_syntax::object::Dict;
 use hayro_syntax::object::Name;
 use hayro_syntax::object::Object;
+use hayro_syntax::object::ObjectIdentifier;
 use hayro_syntax::object::Stream;
 use hayro_syntax::object::dict::keys::*;
-use hayro_syntax::object::stream::{FilterResult, ImageColorSpace, ImageDecodeParams};
-use hayro_syntax::page::Resources;
+use hayro_syntax::object::stream::{
+    DecodeFailure, FilterResult, ImageColorSpace, ImageDecodeParams,
+};
+use hayro_syntax::page::{Page, Resources};
 use kurbo::{Affine, Rect, Shape};
 use smallvec::{SmallVec, smallvec};
 use std::borrow::Cow;
 use std::iter;
 use std::ops::Deref;
+use std::sync::Arc;
 
 pub(crate) enum XObject<'a> {
     FormXObject(FormXObject<'a>),
@@ -45,7 +54,26 @@ impl<'a> XObject<'a> {
                 false,
                 transfer_function,
             )?)),
-            FORM => Some(Self::FormXObject(FormXObject::new(stream)?)),
+            FORM => {
+                let form = FormXObject::new(stream)?;
+
+                if form.is_knockout_group {
+                    warn!("knockout groups are not supported");
+                    warning_sink(InterpreterWarning::UnsupportedFeature(
+                        UnsupportedFeature::KnockoutGroup,
+                    ));
+                }
+
+                Some(Self::FormXObject(form))
+            }
+            PS => {
+                warn!("PostScript XObjects are not supported");
+                warning_sink(InterpreterWarning::UnsupportedFeature(
+                    UnsupportedFeature::PostScriptXObject,
+                ));
+
+                None
+            }
             _ => None,
         }
     }
@@ -56,7 +84,9 @@ pub(crate) struct FormXObject<'a> {
     pub(crate) matrix: Affine,
     pub(crate) bbox: [f32; 4],
     is_transparency_group: bool,
+    is_knockout_group: bool,
     pub(crate) dict: Dict<'a>,
+    pub(crate) obj_id: ObjectIdentifier,
     resources: Dict<'a>,
 }
 
@@ -72,14 +102,18 @@ impl<'a> FormXObject<'a> {
                 .unwrap_or([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]),
         );
         let bbox = dict.get::<[f32; 4]>(BBOX)?;
-        let is_transparency_group = dict.get::<Dict<'_>>(GROUP).is_some();
+        let group = dict.get::<Dict<'_>>(GROUP);
+        let is_transparency_group = group.is_some();
+        let is_knockout_group = group.is_some_and(|g| g.get::<bool>(K).unwrap_or(false));
 
         Some(Self {
             decoded,
             matrix,
             is_transparency_group,
+            is_knockout_group,
             bbox,
             dict: dict.clone(),
+            obj_id: stream.obj_id(),
             resources,
         })
     }
@@ -109,7 +143,7 @@ pub(crate) fn draw_form_xobject<'a, 'b>(
         return;
     }
 
-    if !context.begin_nested_interpretation() {
+    if !context.begin_nested_interpretation(x_object.obj_id) {
         return;
     }
 
@@ -132,16 +166,18 @@ pub(crate) fn draw_form_xobject<'a, 'b>(
     if x_object.is_transparency_group {
         device.push_transparency_group(
             context.get().graphics_state.non_stroke_alpha,
-            std::mem::take(&mut context.get_mut().graphics_state.soft_mask),
-            std::mem::take(&mut context.get_mut().graphics_state.blend_mode),
+            std::mem::take(&mut context.graphics_state_mut().soft_mask),
+            std::mem::take(&mut context.graphics_state_mut().blend_mode),
         );
 
-        context.get_mut().graphics_state.non_stroke_alpha = 1.0;
-        context.get_mut().graphics_state.stroke_alpha = 1.0;
+        context.graphics_state_mut().non_stroke_alpha = 1.0;
+        context.graphics_state_mut().stroke_alpha = 1.0;
     }
 
-    device.push_clip_path(&ClipPath {
-        path: context.get().ctm
+    // Push the clip via the context, so that the bbox of the form XObject is also
+    // taken into account when painting shadings or patterns inside of it.
+    context.push_clip_path(
+        context.get().ctm
             * Rect::new(
                 x_object.bbox[0] as f64,
                 x_object.bbox[1] as f64,
@@ -149,8 +185,9 @@ pub(crate) fn draw_form_xobject<'a, 'b>(
                 x_object.bbox[3] as f64,
             )
             .to_path(0.1),
-        fill: FillRule::NonZero,
-    });
+        FillRule::NonZero,
+        device,
+    );
 
     interpret(
         iter,
@@ -159,7 +196,7 @@ pub(crate) fn draw_form_xobject<'a, 'b>(
         device,
     );
 
-    device.pop_clip();
+    context.pop_clip(device);
 
     if x_object.is_transparency_group {
         device.pop_transparency_group();
@@ -208,8 +245,8 @@ pub(crate) fn draw_image_xobject<'a, 'b>(
 
     let has_alpha = x_object.has_mask();
 
-    let mut soft_mask = std::mem::take(&mut context.get_mut().graphics_state.soft_mask);
-    let blend_mode = std::mem::take(&mut context.get_mut().graphics_state.blend_mode);
+    let mut soft_mask = std::mem::take(&mut context.graphics_state_mut().soft_mask);
+    let blend_mode = std::mem::take(&mut context.graphics_state_mut().blend_mode);
 
     // If image has smask, the soft mask from the graphics state should be discarde.
     if has_alpha {
@@ -222,23 +259,36 @@ pub(crate) fn draw_image_xobject<'a, 'b>(
         blend_mode,
     );
 
-    let image = if x_object.is_mask {
-        Image::Stencil(StencilImage {
-            paint: context.get_paint(false),
-            image_xobject: x_object.clone(),
-        })
+    if let Some(filter) = x_object
+        .stream
+        .filters()
+        .into_iter()
+        .find(|f| !f.is_available())
+    {
+        (x_object.warning_sink)(InterpreterWarning::UnsupportedFeature(
+            UnsupportedFeature::MissingImageDecoder(filter),
+        ));
+        draw_image_placeholder(x_object, transform, device);
     } else {
-        Image::Raster(RasterImage(x_object.clone()))
-    };
+        let image = if x_object.is_mask {
+            Image::Stencil(StencilImage {
+                paint: context.get_paint(false),
+                source: ImageSource::XObject(x_object.clone()),
+            })
+        } else {
+            Image::Raster(RasterImage(ImageSource::XObject(x_object.clone())))
+        };
+
+        device.draw_image(
+            image,
+            ImageDrawProps {
+                transform,
+                soft_mask: None,
+                blend_mode: BlendMode::default(),
+            },
+        );
+    }
 
-    device.draw_image(
-        image,
-        ImageDrawProps {
-            transform,
-            soft_mask: None,
-            blend_mode: BlendMode::default(),
-        },
-    );
     device.pop_transparency_group();
 
     context.restore_state(device);
@@ -248,6 +298,42 @@ pub(crate) fn draw_image_xobject<'a, 'b>(
     }
 }
 
+/// Draw a gray box with a cross in place of an image that can't be decoded.
+fn draw_image_placeholder<'a>(
+    x_object: &ImageXObject<'_>,
+    transform: Affine,
+    device: &mut impl Device<'a>,
+) {
+    let rect = Rect::new(0.0, 0.0, x_object.width as f64, x_object.height as f64);
+    let mut outline = rect.to_path(0.1);
+    outline.move_to((rect.x0, rect.y0));
+    outline.line_to((rect.x1, rect.y1));
+    outline.move_to((rect.x1, rect.y0));
+    outline.line_to((rect.x0, rect.y1));
+
+    let props = |gray: f32| DrawProps {
+        // Transform the paths in advance, so that the stroke has a fixed width
+        // in device space.
+        transform: Affine::IDENTITY,
+        paint: Paint::Color(Color::new(ColorSpace::device_gray(), smallvec![gray], 1.0)),
+        soft_mask: None,
+        blend_mode: BlendMode::default(),
+        overprint: false,
+        device_params: DeviceParams::default(),
+    };
+
+    device.draw_path(
+        &(transform * rect.to_path(0.1)),
+        props(0.85),
+        &DrawMode::Fill(FillRule::NonZero),
+    );
+    device.draw_path(
+        &(transform * outline),
+        props(0.5),
+        &DrawMode::Stroke(StrokeProps::default()),
+    );
+}
+
 fn xobject_oc(dict: &Dict<'_>, context: &mut Context<'_>) -> bool {
     let Some(oc_dict) = dict.get::<Dict<'_>>(OC) else {
         return false;
@@ -262,6 +348,24 @@ fn xobject_oc(dict: &Dict<'_>, context: &mut Context<'_>) -> bool {
     true
 }
 
+/// Decode the embedded thumbnail image of the page, if available.
+///
+/// The thumbnail is decoded in the same way as other images, so the result is
+/// either RGB or grayscale data, depending on the color space of the thumbnail.
+pub fn decode_thumbnail(page: &Page<'_>, settings: &InterpreterSettings) -> Option<ImageData> {
+    let stream = page.thumbnail()?;
+    let image = ImageXObject::new(
+        &stream,
+        |_| None,
+        &settings.warning_sink,
+        &Cache::new(),
+        false,
+        None,
+    )?;
+
+    image.decoded_raster(None).map(|r| r.image)
+}
+
 #[derive(Clone)]
 pub(crate) struct ImageXObject<'a> {
     width: u32,
@@ -376,15 +480,93 @@ impl<'a> ImageXObject<'a> {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct DecodedMask {
     pub(crate) luma: LumaData,
 }
 
+#[derive(Clone)]
 pub(crate) struct DecodedRaster {
     pub(crate) image: ImageData,
     pub(crate) alpha: Option<LumaData>,
 }
 
+/// The source of the data of an image that is passed to a device.
+#[derive(Clone)]
+pub(crate) enum ImageSource<'a> {
+    /// An image that is decoded on demand from its `XObject`.
+    XObject(ImageXObject<'a>),
+    /// A stencil mask that has already been decoded (for example because it
+    /// was recorded into a display list), together with the cache key of the
+    /// original image.
+    Mask(Arc<DecodedMask>, u128),
+    /// A raster image that has already been decoded, together with the cache key
+    /// of the original image.
+    Raster(Arc<DecodedRaster>, u128),
+}
+
+impl<'a> ImageSource<'a> {
+    pub(crate) fn decoded_mask(
+        &self,
+        target_dimension: Option<(u32, u32)>,
+    ) -> Option<Arc<DecodedMask>> {
+        match self {
+            Self::XObject(x) => x.decoded_mask(target_dimension).map(Arc::new),
+            Self::Mask(m, _) => Some(m.clone()),
+            Self::Raster(..) => None,
+        }
+    }
+
+    pub(crate) fn decoded_raster(
+        &self,
+        target_dimension: Option<(u32, u32)>,
+    ) -> Option<Arc<DecodedRaster>> {
+        match self {
+            Self::XObject(x) => x.decoded_raster(target_dimension).map(Arc::new),
+            Self::Mask(..) => None,
+            Self::Raster(r, _) => Some(r.clone()),
+        }
+    }
+
+    pub(crate) fn stream(&self) -> Option<&Stream<'a>> {
+        match self {
+            Self::XObject(x) => Some(x.stream()),
+            Self::Mask(..) | Self::Raster(..) => None,
+        }
+    }
+
+    // Decoded images store the width/height of the data, so we need to undo the
+    // scaling factors to get back the dimensions indicated by the image dictionary.
+    pub(crate) fn width(&self) -> u32 {
+        let scaled = |width: u32, scale: f32| (width as f32 * scale).round() as u32;
+
+        match self {
+            Self::XObject(x) => x.width(),
+            Self::Mask(m, _) => scaled(m.luma.width, m.luma.scale_factors.0),
+            Self::Raster(r, _) => scaled(r.image.width(), r.image.scale_factors().0),
+        }
+    }
+
+    pub(crate) fn height(&self) -> u32 {
+        let scaled = |height: u32, scale: f32| (height as f32 * scale).round() as u32;
+
+        match self {
+            Self::XObject(x) => x.height(),
+            Self::Mask(m, _) => scaled(m.luma.height, m.luma.scale_factors.1),
+            Self::Raster(r, _) => scaled(r.image.height(), r.image.scale_factors().1),
+        }
+    }
+}
+
+impl CacheKey for ImageSource<'_> {
+    fn cache_key(&self) -> u128 {
+        match self {
+            Self::XObject(x) => x.cache_key(),
+            Self::Mask(_, key) | Self::Raster(_, key) => *key,
+        }
+    }
+}
+
 struct DecodeContext<'a> {
     decoded: FilterResult<'a>,
     width: u32,
@@ -418,7 +600,18 @@ fn decode_context<'a>(
     let decoded = obj
         .stream
         .decoded_image(&decode_params)
-        .map_err(|_| (obj.warning_sink)(InterpreterWarning::ImageDecodeFailure))
+        .map_err(|failure| {
+            let filter = obj.stream.filters().last().copied();
+
+            match (failure, filter) {
+                (DecodeFailure::UnsupportedImage, Some(filter)) => {
+                    (obj.warning_sink)(InterpreterWarning::UnsupportedFeature(
+                        UnsupportedFeature::UnsupportedImage(filter),
+                    ));
+                }
+                _ => (obj.warning_sink)(InterpreterWarning::ImageDecodeFailure),
+            }
+        })
         .ok()?;
 
     let (mut scale_x, mut scale_y) = (1.0, 1.0);
@@ -610,26 +803,10 @@ fn decode_raster(
         if let Some(transfer_function) = &obj.transfer_function
             && let Some(rgb_data) = &mut rgb_data
         {
-            let apply_single = |data: u8, function: &Function| {
-                function
-                    .eval(smallvec![data as f32 / 255.0])
-                    .and_then(|v| v.first().copied())
-                    .map(|v| (v * 255.0 + 0.5) as u8)
-                    .unwrap_or(data)
-            };
-
-            match transfer_function {
-                ActiveTransferFunction::Single(s) => {
-                    for data in &mut rgb_data.data {
-                        *data = apply_single(*data, s);
-                    }
-                }
-                ActiveTransferFunction::Four(f) => {
-                    for data in rgb_data.data.chunks_exact_mut(3) {
-                        data[0] = apply_single(data[0], &f[0]);
-                        data[1] = apply_single(data[1], &f[1]);
-                        data[2] = apply_single(data[2], &f[2]);
-                    }
+            for pixel in rgb_data.data.chunks_exact_mut(3) {
+                for (i, data) in pixel.iter_mut().enumerate() {
+                    let value = transfer_function.apply_component(i, *data as f32 / 255.0);
+                    *data = (value * 255.0 + 0.5) as u8;
                 }
             }
         }
@@ -977,3 +1154,85 @@ fn apply_decode_array(
 
     Some(decoded_arr)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::device::Recorder;
+    use crate::{
+        Context, InterpreterCache, InterpreterSettings, InterpreterWarning, UnsupportedFeature,
+        interpret_page,
+    };
+    use hayro_syntax::{Filter, Pdf};
+    use kurbo::{Affine, Rect};
+    use std::sync::{Arc, Mutex};
+
+    // A page that draws a `JPXDecode` image. The image data doesn't need to be
+    // valid, since images are never decoded by the device below.
+    const PDF: &[u8] = b"%PDF-1.7
+1 0 obj
+<< /Type /Catalog /Pages 2 0 R >>
+endobj
+2 0 obj
+<< /Type /Pages /Kids [3 0 R] /Count 1 >>
+endobj
+3 0 obj
+<< /Type /Page /Parent 2 0 R /MediaBox [0 0 100 100] /Resources << /XObject << /Im0 5 0 R >> >> /Contents 4 0 R >>
+endobj
+4 0 obj
+<< /Length 28 >>
+stream
+q 50 0 0 50 25 25 cm /Im0 Do Q
+endstream
+endobj
+5 0 obj
+<< /Type /XObject /Subtype /Image /Width 2 /Height 2 /Filter /JPXDecode /Length 4 >>
+stream
+JPX!
+endstream
+endobj
+trailer
+<< /Root 1 0 R >>
+%%EOF
+";
+
+    #[test]
+    fn missing_image_decoder() {
+        let pdf = Pdf::new(PDF.to_vec()).unwrap();
+        let page = &pdf.pages()[0];
+        let features = Arc::new(Mutex::new(vec![]));
+        let settings = InterpreterSettings {
+            warning_sink: Arc::new({
+                let features = features.clone();
+                move |w| {
+                    if let InterpreterWarning::UnsupportedFeature(f) = w {
+                        features.lock().unwrap().push(f);
+                    }
+                }
+            }),
+            ..Default::default()
+        };
+
+        let cache = InterpreterCache::new();
+        let mut context = Context::new(
+            Affine::IDENTITY,
+            Rect::new(0.0, 0.0, 100.0, 100.0),
+            &cache,
+            page.xref(),
+            settings,
+        );
+        let mut device = Recorder::default();
+        interpret_page(page, &mut context, &mut device);
+
+        if cfg!(feature = "images") {
+            assert!(features.lock().unwrap().is_empty());
+            assert_eq!((device.paths.len(), device.images), (0, 1));
+        } else {
+            // The image is replaced by a filled and a stroked path.
+            assert_eq!(
+                *features.lock().unwrap(),
+                [UnsupportedFeature::MissingImageDecoder(Filter::JpxDecode)]
+            );
+            assert_eq!((device.paths.len(), device.images), (2, 0));
+        }
+    }
+}
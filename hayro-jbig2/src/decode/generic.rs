@@ -49,14 +49,24 @@ pub(crate) fn decode_into(
         );
 
         // "6.2.5 Decoding using a template and arithmetic coding"
-        decode_bitmap_arithmetic_coding(
-            bitmap,
-            &mut decoder,
-            &mut ctx.contexts,
-            header.template,
-            header.tpgdon,
-            &header.adaptive_template_pixels,
-        )?;
+        if let Some(at_pixels) = &header.extended_at_pixels {
+            decode_bitmap_extended_template(
+                bitmap,
+                &mut decoder,
+                &mut ctx.contexts,
+                header.tpgdon,
+                at_pixels,
+            )?;
+        } else {
+            decode_bitmap_arithmetic_coding(
+                bitmap,
+                &mut decoder,
+                &mut ctx.contexts,
+                header.template,
+                header.tpgdon,
+                &header.adaptive_template_pixels,
+            )?;
+        }
     }
 
     Ok(())
@@ -70,6 +80,9 @@ pub(crate) struct GenericRegionHeader<'a> {
     pub(crate) template: Template,
     pub(crate) tpgdon: bool,
     pub(crate) adaptive_template_pixels: [AdaptiveTemplatePixel; 4],
+    /// The AT pixels of the extended reference template, if `EXTTEMPLATE` is
+    /// set for template 0.
+    pub(crate) extended_at_pixels: Option<[AdaptiveTemplatePixel; 12]>,
     pub(crate) data: &'a [u8],
 }
 
@@ -83,11 +96,19 @@ pub(crate) fn parse<'a>(
     let mmr = flags & 0x01 != 0;
     let template = Template::from_byte(flags >> 1);
     let tpgdon = flags & 0x08 != 0;
-    let ext_template = flags & 0x10 != 0;
-    let adaptive_template_pixels = if mmr {
-        [AdaptiveTemplatePixel::default(); 4]
+    // The extended reference template only exists for template 0 (7.4.6.2),
+    // so ignore the flag for the other templates.
+    let ext_template = flags & 0x10 != 0 && template == Template::Template0;
+    let (adaptive_template_pixels, extended_at_pixels) = if mmr {
+        ([AdaptiveTemplatePixel::default(); 4], None)
+    } else if ext_template {
+        let pixels = parse_extended_adaptive_template_pixels(reader)?;
+        let mut first = [AdaptiveTemplatePixel::default(); 4];
+        first.copy_from_slice(&pixels[..4]);
+
+        (first, Some(pixels))
     } else {
-        parse_adaptive_template_pixels(reader, template, ext_template)?
+        (parse_adaptive_template_pixels(reader, template)?, None)
     };
     let mut data = reader.tail().ok_or(ParseError::UnexpectedEof)?;
 
@@ -115,6 +136,7 @@ pub(crate) fn parse<'a>(
         template,
         tpgdon,
         adaptive_template_pixels,
+        extended_at_pixels,
         data,
     })
 }
@@ -123,31 +145,49 @@ pub(crate) fn parse<'a>(
 pub(crate) fn parse_adaptive_template_pixels(
     reader: &mut Reader<'_>,
     template: Template,
-    // TODO: Find a test with this flag.
-    _ext_template: bool,
 ) -> Result<[AdaptiveTemplatePixel; 4]> {
     let num_pixels = template.adaptive_template_pixels() as usize;
 
     let mut pixels = [AdaptiveTemplatePixel::default(); 4];
 
     for pixel in pixels.iter_mut().take(num_pixels) {
-        let x = reader.read_byte().ok_or(ParseError::UnexpectedEof)? as i8;
-        let y = reader.read_byte().ok_or(ParseError::UnexpectedEof)? as i8;
-
-        // Validate AT pixel location (6.2.5.4, Figure 7).
-        // AT pixels must reference already-decoded pixels:
-        // - y must be <= 0 (current row or above)
-        // - if y == 0, x must be < 0 (strictly to the left of current pixel)
-        if y > 0 || (y == 0 && x >= 0) {
-            bail!(TemplateError::InvalidAtPixel);
-        }
+        *pixel = parse_adaptive_template_pixel(reader)?;
+    }
+
+    Ok(pixels)
+}
+
+/// Parse the adaptive template pixel positions of the extended reference
+/// template (7.4.6.3).
+///
+/// In this case, the field contains the 12 pixels A1 to A12 instead of 4.
+pub(crate) fn parse_extended_adaptive_template_pixels(
+    reader: &mut Reader<'_>,
+) -> Result<[AdaptiveTemplatePixel; 12]> {
+    let mut pixels = [AdaptiveTemplatePixel::default(); 12];
 
-        *pixel = AdaptiveTemplatePixel { x, y };
+    for pixel in &mut pixels {
+        *pixel = parse_adaptive_template_pixel(reader)?;
     }
 
     Ok(pixels)
 }
 
+fn parse_adaptive_template_pixel(reader: &mut Reader<'_>) -> Result<AdaptiveTemplatePixel> {
+    let x = reader.read_byte().ok_or(ParseError::UnexpectedEof)? as i8;
+    let y = reader.read_byte().ok_or(ParseError::UnexpectedEof)? as i8;
+
+    // Validate AT pixel location (6.2.5.4, Figure 7).
+    // AT pixels must reference already-decoded pixels:
+    // - y must be <= 0 (current row or above)
+    // - if y == 0, x must be < 0 (strictly to the left of current pixel)
+    if y > 0 || (y == 0 && x >= 0) {
+        bail!(TemplateError::InvalidAtPixel);
+    }
+
+    Ok(AdaptiveTemplatePixel { x, y })
+}
+
 /// Whether the adaptive template pixels correspond to the default ones.
 /// See Table 5.
 fn has_default_at_pixels(template: Template, at_pixels: &[AdaptiveTemplatePixel; 4]) -> bool {
@@ -282,26 +322,27 @@ pub(crate) fn decode_bitmap_mmr(bitmap: &mut Bitmap, data: &[u8]) -> Result<usiz
     let height = bitmap.height;
     let mut decoder = BitmapDecoder::new(bitmap);
 
-    let settings = hayro_ccitt::DecodeSettings {
-        columns: width,
-        rows: height,
-        // "If the number of bytes contained in the encoded bitmap is known in
-        // advance, then it is permissible for the data stream not to contain
-        // an EOFB" (6.2.6). But it _can_ contain it, which is what this
-        // flag indicates.
-        end_of_block: true,
-        end_of_line: false,
-        rows_are_byte_aligned: false,
-        encoding: hayro_ccitt::EncodingMode::Group4,
-        // "Pixels decoded by the MMR decoder having the value 'black' shall be
-        // treated as having the value 1. Pixels decoded by the MMR decoder
-        // having the value 'white' shall be treated as having the value 0."
-        // (6.2.6)
-        //
-        // hayro-ccitt uses 1 for white, 0 for black by default, so we need to
-        // invert to match JBIG2 convention.
-        invert_black: true,
-    };
+    let mut settings = hayro_ccitt::DecodeSettings::default();
+    settings.columns = width;
+    settings.rows = height;
+    // "If the number of bytes contained in the encoded bitmap is known in
+    // advance, then it is permissible for the data stream not to contain
+    // an EOFB" (6.2.6). But it _can_ contain it, which is what this
+    // flag indicates.
+    settings.end_of_block = true;
+    settings.end_of_line = false;
+    settings.rows_are_byte_aligned = false;
+    settings.encoding = hayro_ccitt::EncodingMode::Group4;
+    // "Pixels decoded by the MMR decoder having the value 'black' shall be
+    // treated as having the value 1. Pixels decoded by the MMR decoder
+    // having the value 'white' shall be treated as having the value 0."
+    // (6.2.6)
+    //
+    // hayro-ccitt uses 1 for white, 0 for black by default, so we need to
+    // invert to match JBIG2 convention.
+    settings.invert_black = true;
+    // Error recovery only applies to Group 3 images.
+    settings.damaged_rows_before_error = 0;
 
     // "An invocation of the generic region decoding procedure with MMR equal to
     // 1 shall consume an integral number of bytes, beginning and ending on a
@@ -586,6 +627,56 @@ pub(crate) fn decode_bitmap_arithmetic_coding(
     Ok(())
 }
 
+/// Decode a bitmap using arithmetic coding with the extended reference
+/// template, i.e. template 0 with `EXTTEMPLATE` set (6.2.5).
+fn decode_bitmap_extended_template(
+    bitmap: &mut Bitmap,
+    decoder: &mut ArithmeticDecoder<'_>,
+    contexts: &mut [ArithmeticDecoderContext],
+    tpgdon: bool,
+    at_pixels: &[AdaptiveTemplatePixel; 12],
+) -> Result<()> {
+    if bitmap.width == 0 || bitmap.height == 0 {
+        return Ok(());
+    }
+
+    // The AT pixels of the regular template aren't used by the extended one,
+    // we only use the gatherer for buffering the nearby pixels.
+    let unused = [AdaptiveTemplatePixel::default(); 4];
+    let mut ctx_gatherer = ContextGatherer::new(Template::Template0, &unused);
+
+    // The extended template uses the same context for SLTP as template 0.
+    let sltp_context: u16 = 0b1001101100100101;
+
+    decode_loop!(
+        bitmap,
+        decoder,
+        contexts,
+        &mut ctx_gatherer,
+        tpgdon,
+        sltp_context,
+        |gatherer: &mut ContextGatherer<'_>, bitmap: &Bitmap, x: u32| {
+            gatherer.gather_template0_extended(bitmap, x, at_pixels)
+        }
+    );
+
+    Ok(())
+}
+
+/// The context bits of the AT pixels A1 to A12 of the extended reference
+/// template.
+///
+/// The remaining bits are filled with the four nearest pixels, with the same
+/// positions as in the regular template 0. Each AT pixel takes the place of
+/// the pixel at the same bit in the regular template, so its nominal position
+/// is that of the replaced pixel.
+///
+/// Note that this order has not been checked against a T.88 conformance stream
+/// using the extended template, since none is part of the test corpus. The
+/// tests only check that placing all AT pixels at their nominal positions
+/// results in the same contexts as the regular template 0.
+const EXTENDED_TEMPLATE_AT_BITS: [u16; 12] = [1, 13, 9, 14, 12, 5, 2, 3, 11, 4, 15, 10];
+
 pub(crate) struct ContextGatherer<'a> {
     template: Template,
     at_pixels: &'a [AdaptiveTemplatePixel; 4],
@@ -794,6 +885,31 @@ impl<'a> ContextGatherer<'a> {
         self.ctx
     }
 
+    #[inline(always)]
+    fn gather_template0_extended(
+        &mut self,
+        bitmap: &Bitmap,
+        x: u32,
+        at_pixels: &[AdaptiveTemplatePixel; 12],
+    ) -> u16 {
+        let bx = x - self.cur_x;
+        let xi = x as i32;
+        let yi = self.cur_y as i32;
+
+        let mut ctx = Self::get_buf_pixel(self.buf_cur, bx.wrapping_sub(1))
+            | (Self::get_buf_pixel(self.buf_m1, bx + 1) << 6)
+            | (Self::get_buf_pixel(self.buf_m1, bx) << 7)
+            | (Self::get_buf_pixel(self.buf_m1, bx.wrapping_sub(1)) << 8);
+
+        for (pixel, bit) in at_pixels.iter().zip(EXTENDED_TEMPLATE_AT_BITS) {
+            ctx |= (bitmap.get_pixel((xi + pixel.x as i32) as u32, (yi + pixel.y as i32) as u32)
+                as u16)
+                << bit;
+        }
+
+        ctx
+    }
+
     #[inline(always)]
     fn gather_template1_custom(&mut self, bitmap: &Bitmap, x: u32) -> u16 {
         let bx = x - self.cur_x;
@@ -956,3 +1072,392 @@ const DEFAULT_TEMPLATE3_FAST_PARAMS: DefaultTemplateFastParams = DefaultTemplate
     prev2_next_mask: 0x0000,
     prev1_next_mask: 0x0010,
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    // The test vectors below are synthetic and were produced with a separate
+    // implementation of the encoding side of 6.2.5 and Annex E, so they are
+    // regression tests and not T.88 conformance streams. The conformance streams
+    // with TPGDON for all four templates (`bitmap-*tpgdon.jbig2`) are part of the
+    // snapshot tests in `tests/mod.rs`. With TPGDON enabled, each vector encodes
+    // the bitmap returned by `reference_bitmap` for the given seed, which starts
+    // with an empty row and repeats every third row, so that typical prediction is
+    // actually used. The bitmaps are wider than a word, and the non-default AT
+    // pixels use the full signed 8-bit range, pointing both to far away pixels
+    // inside the bitmap and to pixels outside of it.
+
+    const WIDTH: u32 = 75;
+    const HEIGHT: u32 = 24;
+
+    fn reference_bitmap(seed: u32) -> Vec<Vec<u8>> {
+        let mut state = seed;
+        let mut rows: Vec<Vec<u8>> = vec![];
+
+        for y in 0..HEIGHT {
+            let row = if y == 0 {
+                vec![0; WIDTH as usize]
+            } else if y % 3 == 2 {
+                rows[y as usize - 1].clone()
+            } else {
+                (0..WIDTH)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 17;
+                        state ^= state << 5;
+                        u8::from((state >> 24) < 80)
+                    })
+                    .collect()
+            };
+
+            rows.push(row);
+        }
+
+        rows
+    }
+
+    fn check(template: Template, at_pixels: &[(i8, i8)], seed: u32, data: &[u8]) {
+        let mut adaptive_template_pixels = [AdaptiveTemplatePixel::default(); 4];
+
+        for (pixel, &(x, y)) in adaptive_template_pixels.iter_mut().zip(at_pixels) {
+            *pixel = AdaptiveTemplatePixel { x, y };
+        }
+
+        let mut bitmap = Bitmap::new(WIDTH, HEIGHT).unwrap();
+        let mut contexts = vec![ArithmeticDecoderContext::default(); 1 << 16];
+        let mut decoder = ArithmeticDecoder::new(data);
+
+        decode_bitmap_arithmetic_coding(
+            &mut bitmap,
+            &mut decoder,
+            &mut contexts,
+            template,
+            true,
+            &adaptive_template_pixels,
+        )
+        .unwrap();
+
+        assert_reference(&bitmap, seed);
+    }
+
+    fn assert_reference(bitmap: &Bitmap, seed: u32) {
+        let expected = reference_bitmap(seed);
+
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                assert_eq!(
+                    bitmap.get_pixel(x, y),
+                    expected[y as usize][x as usize],
+                    "pixel ({x}, {y})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn extreme_at_pixels() {
+        let mut reader = Reader::new(&[0x80, 0xff, 0x7f, 0xff, 0xff, 0x80, 0x80, 0x00]);
+        let pixels = parse_adaptive_template_pixels(&mut reader, Template::Template0)
+            .unwrap()
+            .map(|p| (p.x, p.y));
+
+        assert_eq!(pixels, [(-128, -1), (127, -1), (-1, -128), (-128, 0)]);
+    }
+
+    #[test]
+    fn extended_template_region() {
+        let at_pixels: [(i8, i8); 12] = [
+            (-2, 0),
+            (0, -2),
+            (-2, -1),
+            (-1, -2),
+            (1, -2),
+            (2, -1),
+            (-3, 0),
+            (-4, 0),
+            (2, -2),
+            (3, -1),
+            (-2, -2),
+            (-3, -1),
+        ];
+
+        let mut data = vec![];
+        data.extend(WIDTH.to_be_bytes());
+        data.extend(HEIGHT.to_be_bytes());
+        data.extend([0; 8]);
+        // Region segment flags.
+        data.push(0);
+        // Generic region segment flags: GBTEMPLATE = 0, TPGDON = 1, EXTTEMPLATE = 1.
+        data.push(0x18);
+        data.extend(at_pixels.iter().flat_map(|&(x, y)| [x as u8, y as u8]));
+        data.extend(TEMPLATE0_EXTENDED);
+
+        let header = parse(&mut Reader::new(&data), false).unwrap();
+        let parsed = header.extended_at_pixels.unwrap().map(|p| (p.x, p.y));
+        assert_eq!(parsed, at_pixels);
+        assert_eq!(header.data, TEMPLATE0_EXTENDED);
+
+        let region = decode(&header, &mut ScratchBuffers::default()).unwrap();
+        assert_reference(&region.bitmap, 10);
+    }
+
+    // With all AT pixels at their nominal positions, the extended template must
+    // form the same contexts as the regular template 0 with its default AT
+    // pixels, whose context order is covered by the conformance streams.
+    #[test]
+    fn tpgdon_template0_extended_nominal_at() {
+        let at_pixels = [
+            (-2, 0),
+            (0, -2),
+            (-2, -1),
+            (-1, -2),
+            (1, -2),
+            (2, -1),
+            (-3, 0),
+            (-4, 0),
+            (2, -2),
+            (3, -1),
+            (-2, -2),
+            (-3, -1),
+        ]
+        .map(|(x, y)| AdaptiveTemplatePixel { x, y });
+
+        let mut bitmap = Bitmap::new(WIDTH, HEIGHT).unwrap();
+        let mut contexts = vec![ArithmeticDecoderContext::default(); 1 << 16];
+        let mut decoder = ArithmeticDecoder::new(TEMPLATE0);
+
+        decode_bitmap_extended_template(&mut bitmap, &mut decoder, &mut contexts, true, &at_pixels)
+            .unwrap();
+
+        assert_reference(&bitmap, 1);
+    }
+
+    #[test]
+    fn tpgdon_template0_extended_far_at() {
+        let at_pixels = [
+            (-128, -1),
+            (70, -1),
+            (-1, -128),
+            (-72, 0),
+            (5, -3),
+            (-5, -3),
+            (0, -4),
+            (-10, 0),
+            (10, -1),
+            (-20, -2),
+            (40, -5),
+            (-6, -6),
+        ]
+        .map(|(x, y)| AdaptiveTemplatePixel { x, y });
+
+        let mut bitmap = Bitmap::new(WIDTH, HEIGHT).unwrap();
+        let mut contexts = vec![ArithmeticDecoderContext::default(); 1 << 16];
+        let mut decoder = ArithmeticDecoder::new(TEMPLATE0_EXTENDED_FAR_AT);
+
+        decode_bitmap_extended_template(&mut bitmap, &mut decoder, &mut contexts, true, &at_pixels)
+            .unwrap();
+
+        assert_reference(&bitmap, 11);
+    }
+
+    #[test]
+    fn tpgdon_template0() {
+        check(
+            Template::Template0,
+            &[(3, -1), (-3, -1), (2, -2), (-2, -2)],
+            1,
+            TEMPLATE0,
+        );
+    }
+
+    #[test]
+    fn tpgdon_template1() {
+        check(Template::Template1, &[(3, -1)], 2, TEMPLATE1);
+    }
+
+    #[test]
+    fn tpgdon_template2() {
+        check(Template::Template2, &[(2, -1)], 3, TEMPLATE2);
+    }
+
+    #[test]
+    fn tpgdon_template3() {
+        check(Template::Template3, &[(2, -1)], 4, TEMPLATE3);
+    }
+
+    #[test]
+    fn tpgdon_template0_extreme_at() {
+        check(
+            Template::Template0,
+            &[(-128, -1), (70, -1), (-1, -128), (-72, 0)],
+            5,
+            TEMPLATE0_EXTREME_AT,
+        );
+    }
+
+    #[test]
+    fn tpgdon_template1_extreme_at() {
+        check(Template::Template1, &[(-128, -1)], 6, TEMPLATE1_EXTREME_AT);
+    }
+
+    #[test]
+    fn tpgdon_template2_extreme_at() {
+        check(Template::Template2, &[(127, -128)], 7, TEMPLATE2_EXTREME_AT);
+    }
+
+    #[test]
+    fn tpgdon_template2_far_at() {
+        check(Template::Template2, &[(-60, -20)], 9, TEMPLATE2_FAR_AT);
+    }
+
+    #[test]
+    fn tpgdon_template3_extreme_at() {
+        check(Template::Template3, &[(-74, 0)], 8, TEMPLATE3_EXTREME_AT);
+    }
+
+    const TEMPLATE0: &[u8] = &[
+        0xcc, 0xe3, 0x9c, 0xe0, 0xbf, 0x72, 0xf9, 0xb2, 0xa1, 0x86, 0xe8, 0xcc, 0xda, 0x4d, 0x2e,
+        0xb1, 0x49, 0x4e, 0x51, 0x9e, 0x6c, 0x24, 0x02, 0xcf, 0x1f, 0x54, 0xfd, 0xbc, 0xf4, 0x71,
+        0x48, 0x26, 0x4d, 0xa1, 0x59, 0xe6, 0x42, 0x3a, 0xac, 0x92, 0x92, 0xd2, 0x30, 0xc1, 0x37,
+        0xdf, 0xb7, 0x8d, 0xa7, 0x9f, 0x6e, 0x7b, 0x60, 0xc5, 0x75, 0x0d, 0x41, 0x8e, 0x01, 0xec,
+        0x19, 0xe2, 0xdb, 0x70, 0xf8, 0xc8, 0xa0, 0x82, 0xf8, 0x0c, 0x5f, 0x0e, 0x4e, 0xbb, 0x7a,
+        0xf5, 0xa9, 0x91, 0x1d, 0xc6, 0x0f, 0x9b, 0x28, 0x50, 0x5c, 0xb3, 0xb8, 0x32, 0x4d, 0x01,
+        0x90, 0x4d, 0x8b, 0x99, 0x48, 0x74, 0x0d, 0x91, 0xa4, 0x97, 0x1e, 0x46, 0xf4, 0xd0, 0xff,
+        0x0a, 0x82, 0x76, 0x2d, 0x64, 0x58, 0xa3, 0xd8, 0x1f, 0xd6, 0xb3, 0xe6, 0xc3, 0x44, 0x0e,
+        0x8d, 0xc6, 0x70, 0x46, 0x73, 0xd7, 0x10, 0x4e, 0x01, 0xf4, 0x37, 0x2e, 0xc9, 0xb9, 0x12,
+        0x4d, 0x0c, 0x9d, 0x83, 0xde, 0x5f, 0x9f, 0xf7, 0x88, 0x2f, 0xff, 0xac,
+    ];
+
+    const TEMPLATE1: &[u8] = &[
+        0xca, 0x28, 0x7f, 0x44, 0x5b, 0xc4, 0x2b, 0x00, 0xdf, 0x79, 0x3f, 0xd0, 0xf9, 0x1f, 0x1e,
+        0x38, 0xea, 0x15, 0x71, 0xd0, 0x10, 0x71, 0x8d, 0xbb, 0xe0, 0x82, 0x5a, 0x22, 0x96, 0x5f,
+        0x2c, 0x98, 0xbb, 0x92, 0x98, 0xb6, 0xd1, 0x36, 0x2e, 0xb7, 0xf0, 0x7e, 0xa9, 0xb0, 0x5c,
+        0xea, 0x1f, 0xe3, 0x55, 0x04, 0x1a, 0xbd, 0xa4, 0xd7, 0x27, 0x22, 0x71, 0x01, 0x25, 0x8c,
+        0x30, 0x11, 0x01, 0xad, 0xed, 0xa1, 0x4e, 0xe1, 0x13, 0x4d, 0x03, 0xa9, 0x6c, 0xeb, 0x85,
+        0x0b, 0x19, 0xab, 0x90, 0x48, 0x94, 0xfd, 0xfd, 0x8c, 0xd5, 0xe1, 0xaa, 0x4f, 0x58, 0x89,
+        0x95, 0xa8, 0x66, 0x30, 0x28, 0xca, 0xb3, 0x5c, 0xfd, 0xba, 0xb6, 0xfd, 0xf9, 0xce, 0x44,
+        0xaa, 0x32, 0xbd, 0xff, 0x4e, 0xdc, 0x28, 0x37, 0x55, 0xd4, 0xf0, 0xd4, 0xc0, 0xa8, 0x36,
+        0x12, 0xb1, 0xbf, 0xd7, 0xb5, 0x5d, 0x2e, 0x03, 0x79, 0x06, 0xb8, 0x4f, 0xd4, 0xa5, 0x3d,
+        0x62, 0xef, 0x63, 0x94, 0x3b, 0x7c, 0x57, 0x7f, 0xff, 0xac,
+    ];
+
+    const TEMPLATE2: &[u8] = &[
+        0xce, 0x6d, 0xda, 0x88, 0xd3, 0xfe, 0x0b, 0x50, 0xed, 0xaa, 0xad, 0xf4, 0x7d, 0x76, 0x47,
+        0x74, 0xc8, 0xc9, 0x35, 0x87, 0x25, 0x7c, 0x4e, 0xa0, 0xe4, 0x8a, 0x67, 0x5b, 0xda, 0xc5,
+        0x48, 0x1b, 0x2d, 0x84, 0x38, 0xfc, 0x19, 0xbd, 0xfb, 0x3f, 0x7f, 0x6e, 0x9f, 0x5f, 0x51,
+        0xd8, 0x06, 0x52, 0x44, 0xb4, 0xcb, 0x4f, 0xc2, 0x91, 0x5a, 0x8c, 0xbd, 0x2b, 0x1f, 0xc2,
+        0xa1, 0x01, 0xa1, 0x05, 0x4b, 0xf4, 0x2f, 0x60, 0x30, 0xc3, 0x71, 0xa1, 0x63, 0xb9, 0xb5,
+        0x32, 0xd5, 0x4f, 0xe7, 0x5c, 0xe6, 0x27, 0x5d, 0x49, 0xde, 0xa6, 0x3b, 0x71, 0x7c, 0xde,
+        0xb0, 0x76, 0x98, 0x1f, 0xd1, 0x27, 0x97, 0x42, 0x24, 0x07, 0x5a, 0x57, 0xd8, 0x13, 0xc9,
+        0x17, 0xe4, 0x53, 0x00, 0x73, 0xef, 0x88, 0xb2, 0x1f, 0xef, 0x08, 0xd6, 0xfb, 0x5d, 0xa3,
+        0x5f, 0x57, 0x73, 0x1b, 0x09, 0x45, 0x58, 0x95, 0x02, 0xd8, 0x37, 0x22, 0x86, 0x9e, 0x82,
+        0xa4, 0x09, 0x69, 0xac, 0x10, 0x0e, 0xef, 0x78, 0x77, 0xff, 0xac,
+    ];
+
+    const TEMPLATE3: &[u8] = &[
+        0xd0, 0x27, 0x12, 0x22, 0xa1, 0x43, 0xa2, 0x57, 0xac, 0xa5, 0x05, 0xf0, 0xb4, 0xac, 0x1f,
+        0xed, 0xf3, 0x40, 0x94, 0x5c, 0x2e, 0x5a, 0xef, 0xa8, 0x76, 0x76, 0x65, 0xc2, 0x3e, 0xc7,
+        0x76, 0x5b, 0x82, 0xca, 0x91, 0x59, 0x9d, 0xe7, 0xa1, 0x69, 0x39, 0x2e, 0x10, 0x22, 0xb3,
+        0x90, 0xb3, 0x55, 0x7c, 0x44, 0xfb, 0x71, 0x6e, 0x2f, 0x94, 0x1e, 0x94, 0x63, 0x81, 0x28,
+        0xfe, 0xd3, 0x11, 0x86, 0x44, 0x02, 0x04, 0xe3, 0x6f, 0x0c, 0x73, 0xd9, 0x92, 0x7f, 0x59,
+        0xa4, 0x39, 0xc2, 0xa8, 0x5b, 0x4e, 0xc2, 0x3c, 0xb9, 0xef, 0x1d, 0xb3, 0x86, 0xb6, 0x25,
+        0x3e, 0x0b, 0x71, 0xa5, 0xc4, 0x70, 0x8b, 0x60, 0xf9, 0x16, 0xd2, 0xf5, 0xaf, 0x84, 0xde,
+        0x54, 0x87, 0xa0, 0xf7, 0x07, 0x69, 0x15, 0xd2, 0xa8, 0x16, 0x1f, 0x15, 0xb6, 0xb5, 0x82,
+        0x54, 0x54, 0x3b, 0x97, 0x5a, 0xad, 0xbb, 0xa6, 0x91, 0xf8, 0x3e, 0x5f, 0xe7, 0x2f, 0x87,
+        0x1e, 0x49, 0x80, 0x6f, 0x2c, 0xfe, 0x45, 0x54, 0xf4, 0xc3, 0x6d, 0x7f, 0xff, 0xac,
+    ];
+
+    const TEMPLATE0_EXTREME_AT: &[u8] = &[
+        0xce, 0xa8, 0x1d, 0xc3, 0x16, 0x33, 0x41, 0x15, 0x58, 0x98, 0xe0, 0xfc, 0x1e, 0x51, 0x50,
+        0x4d, 0xec, 0x86, 0xdf, 0x8a, 0xf0, 0xe5, 0xf3, 0x59, 0x5a, 0xb6, 0xf2, 0x86, 0xdf, 0x38,
+        0x52, 0xc3, 0xfa, 0xd9, 0xc9, 0x5c, 0x78, 0xe8, 0xcf, 0x24, 0x2c, 0xd5, 0x51, 0xc4, 0x53,
+        0x2d, 0xf1, 0x77, 0x8a, 0x9d, 0x71, 0x6c, 0xe3, 0xbf, 0xc5, 0x6d, 0x17, 0x31, 0xc5, 0x4b,
+        0x70, 0x7d, 0x6d, 0x80, 0x0d, 0xf2, 0x02, 0x1c, 0x80, 0xef, 0xdd, 0x44, 0x04, 0xbb, 0xe8,
+        0x09, 0x35, 0x3d, 0xc0, 0xac, 0xd8, 0x79, 0xad, 0x1e, 0xaf, 0x49, 0x33, 0x8f, 0xce, 0xa1,
+        0xcc, 0x47, 0x77, 0x52, 0xef, 0xc2, 0xc3, 0x48, 0x54, 0xd4, 0x92, 0x85, 0x08, 0xdc, 0x85,
+        0xc0, 0xee, 0xe2, 0x4a, 0xe5, 0xd4, 0x9c, 0x00, 0x83, 0xee, 0xbe, 0xc1, 0x7a, 0xeb, 0xa7,
+        0x78, 0xa6, 0xf3, 0x32, 0x1e, 0x5b, 0xee, 0xe0, 0x80, 0x8e, 0x68, 0xe7, 0xac, 0x1f, 0xa4,
+        0x4f, 0xad, 0xb4, 0x92, 0x74, 0xea, 0x0b, 0x57, 0x77, 0xaf, 0xff, 0xac,
+    ];
+
+    const TEMPLATE1_EXTREME_AT: &[u8] = &[
+        0xce, 0x16, 0x26, 0x50, 0xa0, 0x65, 0x86, 0xf0, 0x61, 0x07, 0x50, 0x0c, 0xdf, 0x8e, 0x0d,
+        0xd5, 0x7a, 0x2b, 0x86, 0x4c, 0xd8, 0x38, 0xb6, 0x9a, 0x0d, 0xf0, 0xfe, 0xb2, 0xca, 0x7c,
+        0x68, 0x31, 0x9d, 0x51, 0x5d, 0x9d, 0x15, 0x19, 0x6a, 0x05, 0x83, 0x10, 0x42, 0x61, 0xcd,
+        0xe8, 0x81, 0x88, 0x93, 0xde, 0x86, 0xf9, 0x6b, 0xce, 0xa4, 0x19, 0x85, 0xae, 0xbe, 0x99,
+        0xec, 0x97, 0x74, 0x1b, 0x02, 0xed, 0xf7, 0xad, 0x3e, 0xba, 0xe8, 0xce, 0x90, 0x92, 0x46,
+        0x70, 0xed, 0x1f, 0x40, 0x39, 0x23, 0x0f, 0x1b, 0x36, 0x93, 0x65, 0xee, 0x1a, 0xff, 0x5d,
+        0x2d, 0x94, 0xb8, 0x90, 0xdf, 0xe3, 0xb4, 0x6d, 0x55, 0x69, 0x26, 0xcf, 0x9d, 0x63, 0xa3,
+        0x75, 0x72, 0x93, 0xc6, 0xfb, 0x5d, 0x2b, 0x9c, 0x7b, 0x8f, 0xef, 0xfc, 0x7d, 0x2d, 0x36,
+        0x80, 0xfc, 0x7d, 0x2d, 0x66, 0x10, 0x20, 0x9b, 0xab, 0xb1, 0x05, 0x51, 0xb7, 0x29, 0xa4,
+        0x1e, 0x39, 0x9d, 0x92, 0xdb, 0x35, 0x09, 0x4b, 0x9b, 0xd6, 0x82, 0xa4, 0x7f, 0xff, 0xac,
+    ];
+
+    const TEMPLATE2_EXTREME_AT: &[u8] = &[
+        0xce, 0x26, 0x1d, 0x68, 0x36, 0x0a, 0xf2, 0x48, 0xce, 0xe1, 0xf1, 0x71, 0x08, 0x6c, 0xfb,
+        0x80, 0xcb, 0xee, 0xdd, 0xde, 0x3d, 0x59, 0xa8, 0x05, 0x58, 0x35, 0xe1, 0x0c, 0x12, 0x9b,
+        0xb5, 0x05, 0xd4, 0x75, 0x26, 0x26, 0x63, 0x2b, 0x61, 0x2b, 0x7c, 0xde, 0x2a, 0x93, 0xe9,
+        0x48, 0xca, 0x56, 0x56, 0x41, 0x6f, 0xf3, 0xf7, 0x03, 0xb7, 0x2d, 0xba, 0xc8, 0x56, 0x9d,
+        0x34, 0x75, 0x9e, 0xff, 0x52, 0x71, 0xea, 0x1f, 0x09, 0xfc, 0xc6, 0x52, 0x10, 0x48, 0x6a,
+        0xb0, 0x33, 0xae, 0x63, 0x32, 0x37, 0x7e, 0x72, 0x9f, 0x4a, 0x8b, 0xa6, 0x71, 0x86, 0x1f,
+        0x3f, 0x23, 0xb8, 0x83, 0x85, 0x08, 0x7c, 0x72, 0x19, 0x21, 0xfe, 0xa8, 0xec, 0x9a, 0x6f,
+        0x8b, 0x05, 0xcb, 0x37, 0xff, 0x61, 0x2a, 0x50, 0xb7, 0x4c, 0x98, 0x9e, 0xde, 0x7d, 0xcc,
+        0x4a, 0xb8, 0x35, 0xda, 0x49, 0xc3, 0xa1, 0x6c, 0x1c, 0x33, 0x4e, 0xa1, 0x60, 0x82, 0x19,
+        0x53, 0xd1, 0xbb, 0x14, 0x37, 0x9c, 0x95, 0x33, 0xdf, 0xff, 0xac,
+    ];
+
+    const TEMPLATE2_FAR_AT: &[u8] = &[
+        0xc6, 0xc1, 0xb9, 0xc9, 0xa3, 0xc4, 0x49, 0x6e, 0x07, 0x71, 0x89, 0xee, 0x89, 0xa7, 0x15,
+        0x67, 0x8f, 0xd6, 0x14, 0x9b, 0x32, 0xe9, 0xa7, 0xd7, 0x4d, 0xf6, 0x4c, 0xa9, 0x2e, 0x13,
+        0x72, 0x1a, 0x5c, 0x77, 0x87, 0xb2, 0x25, 0x04, 0x81, 0x4c, 0xd8, 0xba, 0xd8, 0x1c, 0x27,
+        0x03, 0x5b, 0x27, 0xd0, 0x09, 0xc8, 0x00, 0xa5, 0xa9, 0x01, 0xef, 0x0a, 0x26, 0xac, 0x4d,
+        0xc0, 0xb5, 0x91, 0x71, 0x42, 0xba, 0xa7, 0x95, 0xab, 0x5c, 0xe7, 0xe5, 0x26, 0x66, 0x08,
+        0x2d, 0x96, 0xff, 0x2c, 0x94, 0x0a, 0x80, 0x54, 0x6f, 0x54, 0xb4, 0xa8, 0xe8, 0xd5, 0x5c,
+        0x43, 0x73, 0xd5, 0xb4, 0x74, 0x64, 0x1c, 0x41, 0xa4, 0x33, 0x1f, 0x5c, 0x42, 0x00, 0xc3,
+        0xc3, 0xc3, 0x53, 0xc9, 0xb7, 0xad, 0x3e, 0x47, 0xca, 0x27, 0xa0, 0xfa, 0xca, 0x48, 0x8f,
+        0x26, 0x26, 0x9c, 0x6c, 0xc6, 0x76, 0xe9, 0xa3, 0x65, 0xbc, 0x75, 0x9e, 0xe5, 0xce, 0x22,
+        0xe5, 0x41, 0x7a, 0xdd, 0x01, 0x45, 0xcb, 0x9f, 0xff, 0xac,
+    ];
+
+    const TEMPLATE3_EXTREME_AT: &[u8] = &[
+        0xcf, 0x5a, 0x62, 0x41, 0x60, 0xb5, 0x8a, 0x86, 0x03, 0xc6, 0xbd, 0xec, 0x12, 0xad, 0x19,
+        0x74, 0x76, 0x09, 0x55, 0xa7, 0x65, 0x2e, 0xfa, 0xb0, 0xe2, 0x4f, 0xb1, 0x89, 0x5c, 0xa9,
+        0xe3, 0x35, 0x40, 0x14, 0xc1, 0x09, 0xce, 0x8d, 0x2f, 0xe2, 0xa1, 0xcf, 0xf8, 0xc6, 0x27,
+        0x6e, 0xcd, 0x9d, 0xad, 0xfa, 0x49, 0x7b, 0x00, 0x50, 0x7e, 0x00, 0xc3, 0x84, 0x71, 0x56,
+        0xd1, 0xc7, 0xed, 0x76, 0x49, 0xab, 0xc4, 0xb7, 0x22, 0x7a, 0xa9, 0x72, 0x69, 0x36, 0xe5,
+        0x17, 0x96, 0xef, 0xa1, 0x51, 0x1f, 0xd4, 0xcb, 0x49, 0x04, 0x11, 0x54, 0x3e, 0xa1, 0xce,
+        0xf7, 0x34, 0xf5, 0xe5, 0x14, 0x0c, 0x4a, 0x5d, 0xb6, 0xc8, 0x66, 0x4a, 0x88, 0x43, 0xe5,
+        0xaf, 0xe3, 0xbb, 0xa3, 0x86, 0xbf, 0xc1, 0x63, 0x69, 0x41, 0xc4, 0x95, 0xcd, 0x29, 0xfd,
+        0x99, 0x93, 0x32, 0xf3, 0x7d, 0x8f, 0x0b, 0x48, 0x3d, 0x4c, 0xd8, 0x26, 0x7e, 0xce, 0xbd,
+        0x7f, 0x3c, 0x1c, 0xfe, 0x81, 0x53, 0x05, 0xb8, 0x60, 0x2c, 0xae, 0x7f, 0xff, 0xac,
+    ];
+
+    const TEMPLATE0_EXTENDED: &[u8] = &[
+        0xd0, 0x59, 0x02, 0xd7, 0xf5, 0xcb, 0xb5, 0x47, 0xbc, 0x14, 0x03, 0x26, 0x20, 0xe2, 0x06,
+        0x46, 0x69, 0xef, 0x8b, 0x82, 0x55, 0xbf, 0x99, 0x30, 0x2d, 0x99, 0xc5, 0x36, 0x61, 0x90,
+        0x2d, 0xef, 0x52, 0x35, 0xcf, 0x19, 0x7a, 0xaa, 0x2a, 0x44, 0x00, 0x61, 0x49, 0x53, 0x04,
+        0xae, 0x5c, 0xb5, 0xda, 0x9e, 0x18, 0x40, 0x41, 0x6e, 0xc8, 0xab, 0x40, 0x88, 0x1e, 0x8c,
+        0xbd, 0x24, 0xd7, 0xf0, 0xbd, 0x28, 0x69, 0xac, 0xc2, 0xce, 0x52, 0x2f, 0x99, 0xce, 0x98,
+        0xa5, 0xa4, 0x04, 0x2e, 0x74, 0x0d, 0x62, 0xae, 0xe9, 0x17, 0x94, 0x34, 0xa7, 0x69, 0x01,
+        0x61, 0x0c, 0x0c, 0x9d, 0xaf, 0xe4, 0x94, 0x2b, 0xe8, 0xda, 0xa8, 0x71, 0x13, 0x72, 0x6f,
+        0x23, 0xde, 0xa2, 0x2b, 0x9a, 0xa8, 0x98, 0x03, 0x2c, 0x2f, 0xd6, 0x79, 0xb9, 0x47, 0x3f,
+        0x25, 0x00, 0x1e, 0xbe, 0x2b, 0xe2, 0x7a, 0x8b, 0x47, 0x17, 0x3e, 0x7c, 0xd0, 0x5e, 0xe7,
+        0x88, 0xc8, 0x92, 0x32, 0xd3, 0x1d, 0xd2, 0x25, 0xfc, 0x17, 0xff, 0xac,
+    ];
+
+    const TEMPLATE0_EXTENDED_FAR_AT: &[u8] = &[
+        0xc9, 0x60, 0xe9, 0xa1, 0xe4, 0x25, 0x55, 0x7e, 0x6a, 0x9d, 0xad, 0x7d, 0x1c, 0x5b, 0x5b,
+        0x3e, 0x49, 0xfb, 0xf0, 0x0f, 0x3a, 0xae, 0x4b, 0x6d, 0xe6, 0x0d, 0x69, 0x3e, 0x90, 0x98,
+        0x1d, 0x0d, 0x4c, 0x9c, 0xc3, 0x52, 0x3c, 0x11, 0x1d, 0xbf, 0x59, 0xcf, 0x0f, 0x19, 0x6e,
+        0xaa, 0x95, 0xdd, 0xe6, 0xfe, 0x52, 0x41, 0x77, 0xae, 0x12, 0xb0, 0xeb, 0xb5, 0x2d, 0x88,
+        0xfb, 0xc3, 0xcf, 0x8e, 0x41, 0x23, 0x22, 0xd7, 0xf7, 0xc7, 0x06, 0xde, 0x06, 0x4e, 0x20,
+        0x3a, 0x14, 0x37, 0x04, 0x15, 0xdc, 0xb5, 0x9c, 0x6c, 0x8b, 0xe5, 0x04, 0x9d, 0xa8, 0x50,
+        0xb9, 0xe8, 0xe5, 0x27, 0xfb, 0x8e, 0xf2, 0xb4, 0x7f, 0xae, 0xb2, 0xd0, 0xa6, 0x0b, 0x0f,
+        0xe7, 0xc9, 0xbd, 0xdb, 0xd8, 0xa2, 0xde, 0xe8, 0xd2, 0x65, 0x1c, 0x70, 0xb3, 0x72, 0x13,
+        0x07, 0x4f, 0x21, 0x37, 0xe0, 0x83, 0x4e, 0xf9, 0xea, 0x6c, 0x98, 0x4a, 0xbf, 0x46, 0x8b,
+        0x5c, 0x69, 0xae, 0x1c, 0x44, 0x00, 0xda, 0x5f, 0xff, 0xac,
+    ];
+}
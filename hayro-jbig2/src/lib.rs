@@ -134,38 +134,12 @@ impl<'a> Image<'a> {
     }
 
     fn from_segments(segments: Vec<segment::Segment<'a>>) -> Result<Self> {
-        // Pre-scan for stripe height from EndOfStripe segments.
-        let height_from_stripes = segments
-            .iter()
-            .filter(|seg| seg.header.segment_type == SegmentType::EndOfStripe)
-            .filter_map(|seg| u32::from_be_bytes(seg.data.try_into().ok()?).checked_add(1))
-            .max();
-
-        // Find and parse page information to extract dimensions.
-        let page_info_seg = segments
-            .iter()
-            .find(|s| s.header.segment_type == SegmentType::PageInformation)
-            .ok_or(FormatError::MissingPageInfo)?;
-
-        let mut reader = Reader::new(page_info_seg.data);
-        let page_info = parse_page_information(&mut reader)?;
-
-        // "A page's bitmap height may be declared in its page information segment
-        // to be unknown (by specifying a height of 0xFFFFFFFF). In this case, the
-        // page must be striped." (7.4.8.2)
-        let height = if page_info.height == 0xFFFF_FFFF {
-            height_from_stripes.ok_or(FormatError::UnknownPageHeight)?
-        } else {
-            page_info.height
-        };
-
-        if page_info.width == 0 || height == 0 {
-            bail!(FormatError::EmptyPage);
-        }
+        let height_from_stripes = scan_for_stripe_height(&segments);
+        let (width, height) = page_dimensions(&segments, height_from_stripes)?;
 
         Ok(Self {
             segments,
-            width: page_info.width,
+            width,
             height,
             height_from_stripes,
         })
@@ -200,6 +174,57 @@ impl<'a> Image<'a> {
     }
 }
 
+/// Determine the dimensions of a JBIG2 file without decoding it.
+///
+/// Only the segment headers, the page information segment and, for pages with
+/// an unknown height, the end of stripe segments are parsed, so this is much
+/// cheaper than decoding the image. The file is expected to use the same
+/// organization as for [`Image::new`].
+pub fn probe(data: &[u8]) -> Result<(u32, u32)> {
+    let file = parse_file(data)?;
+    let height_from_stripes = scan_for_stripe_height(&file.segments);
+
+    page_dimensions(&file.segments, height_from_stripes)
+}
+
+/// Determine the page height from `EndOfStripe` segments, if any.
+fn scan_for_stripe_height(segments: &[segment::Segment<'_>]) -> Option<u32> {
+    segments
+        .iter()
+        .filter(|seg| seg.header.segment_type == SegmentType::EndOfStripe)
+        .filter_map(|seg| u32::from_be_bytes(seg.data.try_into().ok()?).checked_add(1))
+        .max()
+}
+
+/// Determine the page dimensions from the page information segment.
+fn page_dimensions(
+    segments: &[segment::Segment<'_>],
+    height_from_stripes: Option<u32>,
+) -> Result<(u32, u32)> {
+    let page_info_seg = segments
+        .iter()
+        .find(|s| s.header.segment_type == SegmentType::PageInformation)
+        .ok_or(FormatError::MissingPageInfo)?;
+
+    let mut reader = Reader::new(page_info_seg.data);
+    let page_info = parse_page_information(&mut reader)?;
+
+    // "A page's bitmap height may be declared in its page information segment
+    // to be unknown (by specifying a height of 0xFFFFFFFF). In this case, the
+    // page must be striped." (7.4.8.2)
+    let height = if page_info.height == 0xFFFF_FFFF {
+        height_from_stripes.ok_or(FormatError::UnknownPageHeight)?
+    } else {
+        page_info.height
+    };
+
+    if page_info.width == 0 || height == 0 {
+        bail!(FormatError::EmptyPage);
+    }
+
+    Ok((page_info.width, height))
+}
+
 fn emit_bitmap<D: Decoder>(bitmap: &Bitmap, decoder: &mut D) {
     let width = bitmap.width;
     let bytes_per_row = width.div_ceil(8) as usize;
@@ -690,3 +715,68 @@ fn init_page(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    /// Build a sequential JBIG2 file with a single page.
+    fn build_file(height: u32, stripe_end_rows: &[u32]) -> Vec<u8> {
+        #[rustfmt::skip]
+        let mut data = vec![
+            0x97, 0x4A, 0x42, 0x32, 0x0D, 0x0A, 0x1A, 0x0A, // ID string
+            0x01,                                           // Sequential organization
+            0x00, 0x00, 0x00, 0x01,                         // Number of pages = 1
+        ];
+
+        let mut segment = |number: u32, segment_type: u8, segment_data: &[u8]| {
+            data.extend_from_slice(&number.to_be_bytes());
+            data.push(segment_type);
+            data.push(0x00); // Refers to no segments
+            data.push(0x01); // Page association = 1
+            data.extend_from_slice(&(segment_data.len() as u32).to_be_bytes());
+            data.extend_from_slice(segment_data);
+        };
+
+        let mut page_info = Vec::new();
+        page_info.extend_from_slice(&37_u32.to_be_bytes()); // Width
+        page_info.extend_from_slice(&height.to_be_bytes()); // Height
+        page_info.extend_from_slice(&[0; 8]); // Resolution
+        page_info.push(0x00); // Flags
+        page_info.extend_from_slice(&[0x80, 0x10]); // Striped, max stripe size 16
+        segment(0, 48, &page_info);
+
+        for (i, end_row) in stripe_end_rows.iter().enumerate() {
+            segment(i as u32 + 1, 50, &end_row.to_be_bytes());
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_probe() {
+        let data = build_file(21, &[]);
+
+        assert_eq!(probe(&data).unwrap(), (37, 21));
+    }
+
+    #[test]
+    fn test_probe_unknown_height() {
+        let data = build_file(0xFFFF_FFFF, &[15, 27]);
+
+        assert_eq!(probe(&data).unwrap(), (37, 28));
+    }
+
+    #[test]
+    fn test_probe_unknown_height_without_stripes() {
+        let data = build_file(0xFFFF_FFFF, &[]);
+
+        assert!(matches!(
+            probe(&data),
+            Err(DecodeError::Format(FormatError::UnknownPageHeight))
+        ));
+    }
+}
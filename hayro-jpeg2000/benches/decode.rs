@@ -44,6 +44,8 @@ struct ManifestEntry {
     resolve_palette_indices: Option<bool>,
     #[serde(default)]
     target_resolution: Option<(u32, u32)>,
+    #[serde(default)]
+    unknown_colorspace_passthrough: Option<bool>,
 }
 
 struct BenchAsset {
@@ -67,18 +69,22 @@ impl ManifestItem {
                     return None;
                 }
 
+                let mut decode_settings = default_settings;
+                decode_settings.resolve_palette_indices = entry
+                    .resolve_palette_indices
+                    .unwrap_or(default_settings.resolve_palette_indices);
+                decode_settings.strict = entry.strict.unwrap_or(default_settings.strict);
+                decode_settings.target_resolution = entry
+                    .target_resolution
+                    .or(default_settings.target_resolution);
+                decode_settings.unknown_colorspace_passthrough = entry
+                    .unknown_colorspace_passthrough
+                    .unwrap_or(default_settings.unknown_colorspace_passthrough);
+
                 Some(BenchAsset {
                     name: format!("{namespace}/{}", entry.id),
                     path: Path::new(namespace).join(entry.path),
-                    decode_settings: DecodeSettings {
-                        resolve_palette_indices: entry
-                            .resolve_palette_indices
-                            .unwrap_or(default_settings.resolve_palette_indices),
-                        strict: entry.strict.unwrap_or(default_settings.strict),
-                        target_resolution: entry
-                            .target_resolution
-                            .or(default_settings.target_resolution),
-                    },
+                    decode_settings,
                 })
             }
         }
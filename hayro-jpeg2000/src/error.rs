@@ -104,7 +104,7 @@ pub enum DecodingError {
     InvalidPrecinct,
     /// A progression iterator ver invalid.
     InvalidProgressionIterator,
-    /// Unexpected end of data.
+    /// Unexpected end of data, for example because the codestream was truncated.
     UnexpectedEof,
 }
 
@@ -121,6 +121,24 @@ pub enum ColorError {
     LabConversionFailed,
 }
 
+/// A problem that was recovered from during decoding.
+///
+/// Warnings are only emitted if strict mode is disabled, in strict mode
+/// the corresponding problems result in a [`DecodeError`] instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeWarning {
+    /// The codestream ended before all tile-parts could be read. Tiles whose
+    /// data is missing are decoded as if all of their coefficients were zero.
+    TruncatedCodestream,
+    /// Not all packets of the tile with the given index could be read. The
+    /// contributions of the affected code-blocks were treated as zero.
+    IncompleteTile(u32),
+    /// The image uses an array-based multiple component transformation that
+    /// could not be applied (completely), so the colors of the image might be
+    /// wrong.
+    UnsupportedMultipleComponentTransform,
+}
+
 impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -225,6 +243,18 @@ impl fmt::Display for ColorError {
     }
 }
 
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TruncatedCodestream => write!(f, "codestream is truncated"),
+            Self::IncompleteTile(idx) => write!(f, "tile {idx} was only partially decoded"),
+            Self::UnsupportedMultipleComponentTransform => {
+                write!(f, "multiple component transformation is not supported")
+            }
+        }
+    }
+}
+
 impl core::error::Error for DecodeError {}
 impl core::error::Error for FormatError {}
 impl core::error::Error for MarkerError {}
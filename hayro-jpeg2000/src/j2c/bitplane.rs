@@ -65,10 +65,17 @@ fn decode_inner(
 
     // Build a list so that we can associate coding passes with their segments
     // and data more easily.
-    for layer in &storage.layers[code_block.layers.start..code_block.layers.end] {
+    'layers: for layer in &storage.layers[code_block.layers.start..code_block.layers.end] {
         if let Some(range) = layer.segments.clone() {
             let layer_segments = &storage.segments[range.clone()];
             for segment in layer_segments {
+                // If the data of a segment is incomplete (which can only happen in
+                // non-strict mode for truncated codestreams), we only decode the
+                // coding passes of the segments before it.
+                if segment.data.len() < segment.data_length as usize {
+                    break 'layers;
+                }
+
                 if segment.idx != last_segment_idx {
                     assert_eq!(segment.idx, last_segment_idx + 1);
 
@@ -85,7 +92,7 @@ fn decode_inner(
         }
     }
 
-    assert_eq!(coding_passes, code_block.number_of_coding_passes);
+    assert!(coding_passes <= code_block.number_of_coding_passes);
 
     bp_buffers
         .segment_ranges
@@ -99,9 +106,7 @@ fn decode_inner(
         // Only one termination per code block, so we can just decode the
         // whole range in one single go, processing all coding passes at once.
         let mut decoder = ArithmeticDecoder::new(&bp_buffers.combined_layers);
-        let end = code_block
-            .number_of_coding_passes
-            .min(ctx.max_coding_passes);
+        let end = coding_passes.min(ctx.max_coding_passes);
 
         if ctx.can_use_fast_path() {
             fast_path::handle_coding_passes(0, end, ctx, &mut decoder)?;
@@ -20,6 +20,9 @@ pub(crate) struct Header<'a> {
     pub(crate) component_infos: Vec<ComponentInfo>,
     pub(crate) ppm_packets: Vec<PpmPacket<'a>>,
     pub(crate) skipped_resolution_levels: u8,
+    /// The array-based multiple component transformation, from the MCT, MCC
+    /// and MCO markers.
+    pub(crate) array_mct: ArrayMct,
     /// Whether strict mode is enabled for decoding.
     pub(crate) strict: bool,
 }
@@ -52,6 +55,7 @@ pub(crate) fn read_header<'a>(
     let mut cod_components = vec![None; num_components as usize];
     let mut qcd_components = vec![None; num_components as usize];
     let mut ppm_markers = vec![];
+    let mut array_mct = ArrayMct::default();
 
     loop {
         match reader.peek_marker().ok_or(MarkerError::Invalid)? {
@@ -100,6 +104,26 @@ pub(crate) fn read_header<'a>(
                 reader.read_marker()?;
                 skip_marker_segment(reader);
             }
+            markers::MCT => {
+                reader.read_marker()?;
+                mct_marker(reader, &mut array_mct).ok_or(MarkerError::ParseFailure("MCT"))?;
+            }
+            markers::MCC => {
+                reader.read_marker()?;
+                mcc_marker(reader, &mut array_mct).ok_or(MarkerError::ParseFailure("MCC"))?;
+            }
+            markers::MCO => {
+                reader.read_marker()?;
+                array_mct.stage_order =
+                    mco_marker(reader).ok_or(MarkerError::ParseFailure("MCO"))?;
+            }
+            markers::CBD => {
+                // The bit depths of the components after the inverse multiple
+                // component transformation. We assume that they are the same
+                // as the ones from the SIZ marker.
+                reader.read_marker()?;
+                skip_marker_segment(reader).ok_or(MarkerError::ParseFailure("CBD"))?;
+            }
             (0x30..=0x3F) => {
                 // "All markers with the marker code between 0xFF30 and 0xFF3F
                 // have no marker segment parameters. They shall be skipped by
@@ -178,6 +202,7 @@ pub(crate) fn read_header<'a>(
             .filter_map(|p| if p.data.is_empty() { None } else { Some(p) })
             .collect(),
         skipped_resolution_levels,
+        array_mct,
         strict: settings.strict,
     };
 
@@ -311,6 +336,26 @@ impl ProgressionOrder {
     }
 }
 
+/// Multiple component transformation type (Table A.17).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MultipleComponentTransform {
+    None,
+    /// The transformation of the first three components, as specified in Annex G.
+    Standard,
+    /// The array-based transformation from JPEG2000 Part 2.
+    ArrayBased,
+}
+
+impl MultipleComponentTransform {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Standard,
+            2 => Self::ArrayBased,
+            _ => Self::None,
+        }
+    }
+}
+
 /// Wavelet transformation type (Table A.20).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum WaveletTransform {
@@ -414,7 +459,7 @@ pub(crate) struct QuantizationInfo {
 pub(crate) struct CodingStyleDefault {
     pub(crate) progression_order: ProgressionOrder,
     pub(crate) num_layers: u8,
-    pub(crate) mct: bool,
+    pub(crate) mct: MultipleComponentTransform,
     // This is the default used for all components, if not overridden by COC.
     pub(crate) component_parameters: CodingStyleComponent,
 }
@@ -749,6 +794,209 @@ fn ppm_marker<'a>(reader: &mut BitReader<'a>) -> Option<PpmMarkerData<'a>> {
     })
 }
 
+/// The type of an array from an MCT marker (Part 2, Table A.37).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MctArrayType {
+    Dependency,
+    Decorrelation,
+    Offset,
+}
+
+/// An array from an MCT marker (Part 2, A.3.7).
+#[derive(Debug, Clone)]
+pub(crate) struct MctArray {
+    pub(crate) index: u8,
+    pub(crate) array_type: MctArrayType,
+    pub(crate) values: Vec<f32>,
+}
+
+/// The type of transformation of a component collection (Part 2, Table A.41).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CollectionTransform {
+    Dependency,
+    Decorrelation,
+    Wavelet,
+}
+
+/// A component collection from an MCC marker (Part 2, A.3.8).
+#[derive(Debug, Clone)]
+pub(crate) struct ComponentCollection {
+    pub(crate) transform: CollectionTransform,
+    pub(crate) input_components: Vec<u16>,
+    pub(crate) output_components: Vec<u16>,
+    /// The index of the MCT array with the transformation matrix, or 0 if there
+    /// is none.
+    pub(crate) matrix_index: u8,
+    /// The index of the MCT array with the offsets, or 0 if there is none.
+    pub(crate) offset_index: u8,
+    pub(crate) reversible: bool,
+}
+
+/// A stage of a multiple component transformation, from an MCC marker (Part 2, A.3.8).
+#[derive(Debug, Clone)]
+pub(crate) struct MccStage {
+    pub(crate) index: u8,
+    pub(crate) collections: Vec<ComponentCollection>,
+}
+
+/// The array-based multiple component transformation from JPEG2000 Part 2 (Annex J).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ArrayMct {
+    pub(crate) arrays: Vec<MctArray>,
+    pub(crate) stages: Vec<MccStage>,
+    /// The indices of the stages that should be applied, in order, from the MCO marker.
+    pub(crate) stage_order: Vec<u8>,
+}
+
+/// MCT marker (Part 2, A.3.7).
+fn mct_marker(reader: &mut BitReader<'_>, array_mct: &mut ArrayMct) -> Option<()> {
+    let length = reader.read_u16()?.checked_sub(2)?;
+    let mut reader = BitReader::new(reader.read_bytes(length as usize)?);
+
+    let segment_index = reader.read_u16()?;
+    let parameters = reader.read_u16()?;
+
+    // The number of MCT marker segments that make up the array, only present
+    // in the first one.
+    if segment_index == 0 {
+        let _ = reader.read_u16()?;
+    }
+
+    let index = (parameters & 0xFF) as u8;
+    let array_type = match (parameters >> 8) & 0b11 {
+        0 => MctArrayType::Dependency,
+        1 => MctArrayType::Decorrelation,
+        2 => MctArrayType::Offset,
+        _ => return None,
+    };
+
+    let data = reader.tail()?;
+    let values: Vec<f32> = match (parameters >> 10) & 0b11 {
+        0 => data
+            .chunks_exact(2)
+            .map(|c| i16::from_be_bytes([c[0], c[1]]) as f32)
+            .collect(),
+        1 => data
+            .chunks_exact(4)
+            .map(|c| i32::from_be_bytes([c[0], c[1], c[2], c[3]]) as f32)
+            .collect(),
+        2 => data
+            .chunks_exact(4)
+            .map(|c| f32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+            .collect(),
+        _ => data
+            .chunks_exact(8)
+            .map(|c| f64::from_be_bytes(c.try_into().unwrap()) as f32)
+            .collect(),
+    };
+
+    // Arrays can be split across multiple marker segments with the same index.
+    if let Some(array) = array_mct.arrays.iter_mut().find(|a| a.index == index) {
+        array.values.extend(values);
+    } else {
+        array_mct.arrays.push(MctArray {
+            index,
+            array_type,
+            values,
+        });
+    }
+
+    Some(())
+}
+
+/// MCC marker (Part 2, A.3.8).
+fn mcc_marker(reader: &mut BitReader<'_>, array_mct: &mut ArrayMct) -> Option<()> {
+    let length = reader.read_u16()?.checked_sub(2)?;
+    let mut reader = BitReader::new(reader.read_bytes(length as usize)?);
+
+    let segment_index = reader.read_u16()?;
+    let index = reader.read_byte()?;
+
+    // The number of MCC marker segments that make up the stage, only present
+    // in the first one.
+    if segment_index == 0 {
+        let _ = reader.read_u16()?;
+    }
+
+    let num_collections = reader.read_u16()?;
+    let mut collections = Vec::new();
+
+    let read_components = |reader: &mut BitReader<'_>| {
+        let num_components = reader.read_u16()?;
+        // The most significant bit indicates whether component indices
+        // are stored using two bytes instead of one.
+        let wide = num_components & 0x8000 != 0;
+
+        (0..num_components & 0x7FFF)
+            .map(|_| {
+                if wide {
+                    reader.read_u16()
+                } else {
+                    reader.read_byte().map(u16::from)
+                }
+            })
+            .collect::<Option<Vec<_>>>()
+    };
+
+    for _ in 0..num_collections {
+        let transform = match reader.read_byte()? & 0b11 {
+            0 => CollectionTransform::Dependency,
+            1 => CollectionTransform::Decorrelation,
+            3 => CollectionTransform::Wavelet,
+            _ => return None,
+        };
+
+        let input_components = read_components(&mut reader)?;
+        let output_components = read_components(&mut reader)?;
+
+        if transform == CollectionTransform::Wavelet {
+            // Wavelet-based transformations have differently structured
+            // parameters, and since we don't support them anyway, we don't
+            // bother reading the remaining collections.
+            collections.push(ComponentCollection {
+                transform,
+                input_components,
+                output_components,
+                matrix_index: 0,
+                offset_index: 0,
+                reversible: false,
+            });
+
+            break;
+        }
+
+        let parameters = reader.read_bytes(3)?;
+
+        collections.push(ComponentCollection {
+            transform,
+            input_components,
+            output_components,
+            matrix_index: parameters[2],
+            offset_index: parameters[1],
+            reversible: parameters[0] & 1 != 0,
+        });
+    }
+
+    // Stages can be split across multiple marker segments with the same index.
+    if let Some(stage) = array_mct.stages.iter_mut().find(|s| s.index == index) {
+        stage.collections.extend(collections);
+    } else {
+        array_mct.stages.push(MccStage { index, collections });
+    }
+
+    Some(())
+}
+
+/// MCO marker (Part 2, A.3.9).
+fn mco_marker(reader: &mut BitReader<'_>) -> Option<Vec<u8>> {
+    // Length.
+    let _ = reader.read_u16()?;
+
+    let num_stages = reader.read_byte()?;
+
+    Some(reader.read_bytes(num_stages as usize)?.to_vec())
+}
+
 /// RGN marker (A.6.3).
 fn rgn_marker(reader: &mut BitReader<'_>) -> Option<()> {
     skip_marker_segment(reader)
@@ -776,7 +1024,7 @@ pub(crate) fn cod_marker(reader: &mut BitReader<'_>) -> Option<CodingStyleDefaul
         return None;
     }
 
-    let mct = reader.read_byte()? == 1;
+    let mct = MultipleComponentTransform::from_u8(reader.read_byte()?);
 
     let coding_style_parameters = coding_style_parameters(reader, &coding_style_flags)?;
 
@@ -958,6 +1206,15 @@ pub(crate) mod markers {
     /// Comment - 'COM'.
     pub(crate) const COM: u8 = 0x64;
 
+    /// Multiple component transformation - 'MCT' (Part 2).
+    pub(crate) const MCT: u8 = 0x74;
+    /// Multiple component collection - 'MCC' (Part 2).
+    pub(crate) const MCC: u8 = 0x75;
+    /// Multiple component transformation ordering - 'MCO' (Part 2).
+    pub(crate) const MCO: u8 = 0x77;
+    /// Component bit depth - 'CBD' (Part 2).
+    pub(crate) const CBD: u8 = 0x78;
+
     pub(crate) fn to_string(marker: u8) -> &'static str {
         match marker {
             // Delimiting markers.
@@ -992,6 +1249,12 @@ pub(crate) mod markers {
             CRG => "CRG",
             COM => "COM",
 
+            // Part 2 markers.
+            MCT => "MCT",
+            MCC => "MCC",
+            MCO => "MCO",
+            CBD => "CBD",
+
             _ => "UNKNOWN",
         }
     }
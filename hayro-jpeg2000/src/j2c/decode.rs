@@ -9,7 +9,9 @@ use alloc::vec::Vec;
 
 use super::bitplane::{BitPlaneDecodeBuffers, BitPlaneDecodeContext};
 use super::build::{CodeBlock, Decomposition, Layer, Precinct, Segment, SubBand, SubBandType};
-use super::codestream::{ComponentInfo, Header, ProgressionOrder, QuantizationStyle};
+use super::codestream::{
+    ComponentInfo, Header, MultipleComponentTransform, ProgressionOrder, QuantizationStyle,
+};
 use super::idwt::IDWTOutput;
 use super::progression::{
     IteratorInput, ProgressionData, component_position_resolution_layer_progression,
@@ -21,7 +23,7 @@ use super::progression::{
 use super::tag_tree::TagNode;
 use super::tile::{ComponentTile, ResolutionTile, Tile};
 use super::{ComponentData, bitplane, build, idwt, mct, segment, tile};
-use crate::error::{DecodingError, Result, TileError, bail};
+use crate::error::{DecodeWarning, DecodingError, Result, TileError, bail};
 use crate::j2c::segment::MAX_BITPLANE_COUNT;
 use crate::math::SimdBuffer;
 use crate::reader::BitReader;
@@ -33,7 +35,8 @@ pub(crate) fn decode<'a>(
     ctx: &mut DecoderContext<'a>,
 ) -> Result<()> {
     let mut reader = BitReader::new(data);
-    let tiles = tile::parse(&mut reader, header)?;
+    ctx.warnings.clear();
+    let tiles = tile::parse(&mut reader, header, &mut ctx.warnings)?;
 
     if tiles.is_empty() {
         bail!(TileError::Invalid);
@@ -82,14 +85,21 @@ pub(crate) fn decode<'a>(
             &mut ctx.tile_decode_context,
             &mut ctx.channel_data,
             &mut ctx.storage,
+            &mut ctx.warnings,
         )?;
     }
 
     // Note that this assumes that either all tiles have MCT or none of them.
     // In theory, only some could have it... But hopefully no such cursed
     // images exist!
-    if tiles[0].mct {
-        mct::apply_inverse(&mut ctx.channel_data, &tiles[0].component_infos, header)?;
+    match tiles[0].mct {
+        MultipleComponentTransform::None => {}
+        MultipleComponentTransform::Standard => {
+            mct::apply_inverse(&mut ctx.channel_data, &tiles[0].component_infos, header)?;
+        }
+        MultipleComponentTransform::ArrayBased => {
+            mct::apply_array_inverse(&mut ctx.channel_data, header, &mut ctx.warnings)?;
+        }
     }
 
     apply_sign_shift(&mut ctx.channel_data, &header.component_infos);
@@ -103,6 +113,8 @@ pub struct DecoderContext<'a> {
     tile_decode_context: TileDecodeContext,
     /// The raw, decoded samples for each channel.
     pub(crate) channel_data: Vec<ComponentData>,
+    /// The problems that were recovered from during decoding.
+    pub(crate) warnings: Vec<DecodeWarning>,
     storage: DecompositionStorage<'a>,
 }
 
@@ -132,6 +144,7 @@ fn decode_tile<'a, 'b>(
     tile_ctx: &mut TileDecodeContext,
     channel_data: &mut [ComponentData],
     storage: &mut DecompositionStorage<'a>,
+    warnings: &mut Vec<DecodeWarning>,
 ) -> Result<()> {
     storage.reset();
 
@@ -141,7 +154,7 @@ fn decode_tile<'a, 'b>(
     // and code blocks.
     build::build(tile, storage)?;
     // Next, we parse the layers/segments for each code block.
-    segment::parse(tile, progression_iterator, header, storage)?;
+    segment::parse(tile, progression_iterator, header, storage, warnings)?;
     // We then decode the bitplanes of each code block, yielding the
     // (possibly dequantized) coefficients of each code block.
     decode_component_tile_bit_planes(tile, tile_ctx, storage, header)?;
@@ -534,3 +547,125 @@ fn store<'a>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{DecodeSettings, DecodeWarning, DecoderContext, Image};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    const NUM_TILES: u16 = 4;
+    const TILE_SIZE: usize = 8;
+
+    /// The length of the main header.
+    const HEADER_LEN: usize = 65;
+    /// The length of a single tile-part, including its header.
+    const TILE_PART_LEN: usize = 28;
+
+    /// Create the codestream of an 8x32 grayscale image, which consists of
+    /// four vertically stacked 8x8 tiles with a single code-block each.
+    fn codestream() -> Vec<u8> {
+        let mut data = vec![
+            // SOC
+            0xFF, 0x4F, //
+            // SIZ
+            0xFF, 0x51, 0x00, 0x29, 0x00, 0x00, //
+            0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x20, //
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x08, //
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x00, 0x01, 0x07, 0x01, 0x01, //
+            // COD
+            0xFF, 0x52, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x01, //
+            0x00, 0x00, 0x01, 0x01, 0x00, 0x01, //
+            // QCD
+            0xFF, 0x5C, 0x00, 0x04, 0x40, 0x40,
+        ];
+        assert_eq!(data.len(), HEADER_LEN);
+
+        for tile in 0..NUM_TILES {
+            let [t0, t1] = tile.to_be_bytes();
+            let start = data.len();
+
+            data.extend_from_slice(&[
+                // SOT
+                0xFF,
+                0x90,
+                0x00,
+                0x0A,
+                t0,
+                t1,
+                0x00,
+                0x00, //
+                0x00,
+                TILE_PART_LEN as u8,
+                0x00,
+                0x01, //
+                // SOD
+                0xFF,
+                0x93, //
+                // Packet header: One code-block with three coding passes
+                // and 12 bytes of data.
+                0xF8,
+                0xC0,
+            ]);
+            // The exact data doesn't matter, as long as the tiles are different.
+            data.extend((0..12_u8).map(|i| (i * 17 + tile as u8 * 13) ^ 0x5A));
+            assert_eq!(data.len() - start, TILE_PART_LEN);
+        }
+
+        // EOC
+        data.extend_from_slice(&[0xFF, 0xD9]);
+
+        data
+    }
+
+    fn decode(data: &[u8], strict: bool) -> crate::Result<(Vec<u8>, Vec<DecodeWarning>)> {
+        let settings = DecodeSettings {
+            strict,
+            ..Default::default()
+        };
+        let image = Image::new(data, &settings)?;
+        assert_eq!((image.width(), image.height()), (8, 32));
+
+        let mut ctx = DecoderContext::default();
+        let decoded = image.decode(&mut ctx)?;
+
+        Ok((decoded.data_u8(), decoded.warnings().to_vec()))
+    }
+
+    #[test]
+    fn complete_codestream() {
+        let (_, warnings) = decode(&codestream(), true).unwrap();
+
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn truncated_codestream() {
+        let data = codestream();
+        let (expected, _) = decode(&data, false).unwrap();
+
+        for fraction in [0.5, 0.6, 0.75, 0.9, 0.97] {
+            let len = (data.len() as f32 * fraction) as usize;
+            let (decoded, warnings) = decode(&data[..len], false).unwrap();
+
+            assert_eq!(decoded.len(), expected.len());
+            assert!(!warnings.is_empty());
+
+            // All tiles that appear completely in the truncated data should
+            // be decoded correctly.
+            let intact_tiles = (len - HEADER_LEN) / TILE_PART_LEN;
+            let intact_len = intact_tiles * TILE_SIZE * TILE_SIZE;
+            assert_eq!(decoded[..intact_len], expected[..intact_len]);
+        }
+    }
+
+    #[test]
+    fn truncated_codestream_strict() {
+        let data = codestream();
+        let len = data.len() - TILE_PART_LEN / 2;
+
+        assert!(decode(&data[..len], true).is_err());
+    }
+}
@@ -776,3 +776,55 @@ fn irreversible_filter_97i_simd<S: Simd>(
         |s1, s2, s3| math::mul_add(s2 + s3, NEG_ALPHA, s1),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{filter_row, filter_vertical};
+    use crate::j2c::codestream::WaveletTransform;
+    use crate::j2c::rect::IntRect;
+    use crate::math::SIMD_WIDTH;
+    use alloc::vec::Vec;
+
+    // The last column is handled by the scalar remainder of the vertical filter,
+    // while all others are handled by SIMD lanes.
+    const WIDTH: usize = SIMD_WIDTH + 1;
+    const HEIGHT: usize = 13;
+
+    /// Filter a column of `values` vertically and compare each column of the
+    /// result bit by bit to the result of filtering `values` horizontally, which
+    /// never uses SIMD.
+    fn check_simd_matches_scalar(transform: WaveletTransform, values: &[f32]) {
+        for start in [0, 1] {
+            let mut expected = values.to_vec();
+            filter_row(&mut expected, HEIGHT, start, transform);
+
+            let mut coefficients = values.iter().flat_map(|v| [*v; WIDTH]).collect::<Vec<_>>();
+            let rect = IntRect::from_xywh(0, start as u32, WIDTH as u32, HEIGHT as u32);
+            filter_vertical(&mut coefficients, rect, transform);
+
+            for (row, expected) in coefficients.chunks_exact(WIDTH).zip(&expected) {
+                for value in row {
+                    assert_eq!(value.to_bits(), expected.to_bits());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reversible_simd_matches_scalar() {
+        let values = (0..HEIGHT)
+            .map(|i| ((i * 37 + 11) % 101) as f32 - 50.0)
+            .collect::<Vec<_>>();
+
+        check_simd_matches_scalar(WaveletTransform::Reversible53, &values);
+    }
+
+    #[test]
+    fn irreversible_simd_matches_scalar() {
+        let values = (0..HEIGHT)
+            .map(|i| ((i * 37 + 11) % 101) as f32 * 0.371 - 18.3)
+            .collect::<Vec<_>>();
+
+        check_simd_matches_scalar(WaveletTransform::Irreversible97, &values);
+    }
+}
@@ -1,10 +1,15 @@
 //! The irreversible multi-component transformation, as specified in
-//! Annex G.2 and G.3.
+//! Annex G.2 and G.3, as well as the array-based multiple component
+//! transformation from Annex J of JPEG2000 Part 2.
+
+use alloc::vec::Vec;
 
 use super::ComponentData;
-use super::codestream::{Header, WaveletTransform};
-use crate::error::{ColorError, Result, bail, err};
-use crate::math::{Level, Simd, dispatch, f32x8};
+use super::codestream::{
+    ArrayMct, CollectionTransform, ComponentCollection, Header, MctArrayType, WaveletTransform,
+};
+use crate::error::{ColorError, DecodeWarning, Result, bail, err};
+use crate::math::{self, Level, Simd, dispatch, f32x8};
 
 /// Apply the inverse multi-component transform, as specified in G.2 and G.3.
 pub(crate) fn apply_inverse(
@@ -103,3 +108,267 @@ fn apply_inner_impl<S: Simd>(
         }
     }
 }
+
+/// Apply the inverse of the array-based multiple component transformation, as
+/// specified in Annex J of JPEG2000 Part 2.
+///
+/// Only decorrelation transformations are supported. In non-strict mode,
+/// stages and component collections that can't be applied are skipped.
+pub(crate) fn apply_array_inverse(
+    components: &mut [ComponentData],
+    header: &Header<'_>,
+    warnings: &mut Vec<DecodeWarning>,
+) -> Result<()> {
+    let array_mct = &header.array_mct;
+    let mut unsupported = false;
+
+    for stage_index in &array_mct.stage_order {
+        let Some(stage) = array_mct.stages.iter().find(|s| s.index == *stage_index) else {
+            unsupported = true;
+            continue;
+        };
+
+        for collection in &stage.collections {
+            unsupported |= apply_collection(components, collection, array_mct).is_none();
+        }
+    }
+
+    if unsupported {
+        if header.strict {
+            bail!(ColorError::Mct);
+        }
+
+        warnings.push(DecodeWarning::UnsupportedMultipleComponentTransform);
+    }
+
+    Ok(())
+}
+
+fn apply_collection(
+    components: &mut [ComponentData],
+    collection: &ComponentCollection,
+    array_mct: &ArrayMct,
+) -> Option<()> {
+    if collection.transform != CollectionTransform::Decorrelation {
+        return None;
+    }
+
+    let inputs = &collection.input_components;
+    let outputs = &collection.output_components;
+    let n = inputs.len();
+
+    if n == 0 || outputs.len() != n || outputs.iter().any(|o| *o as usize >= components.len()) {
+        return None;
+    }
+
+    let array = |index: u8, array_type: MctArrayType| {
+        if index == 0 {
+            return Some(None);
+        }
+
+        array_mct
+            .arrays
+            .iter()
+            .find(|a| a.index == index && a.array_type == array_type)
+            .map(|a| Some(a.values.as_slice()))
+    };
+
+    let matrix = array(collection.matrix_index, MctArrayType::Decorrelation)?;
+    let offsets = array(collection.offset_index, MctArrayType::Offset)?;
+
+    if matrix.is_some_and(|m| m.len() != n * n) || offsets.is_some_and(|o| o.len() < n) {
+        return None;
+    }
+
+    let len = components.get(inputs[0] as usize)?.container.len();
+    let input_data = inputs
+        .iter()
+        .map(|i| {
+            components
+                .get(*i as usize)
+                .filter(|c| c.container.len() == len)
+                .map(|c| c.container.to_vec())
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    for (j, output) in outputs.iter().enumerate() {
+        let component = &mut components[*output as usize];
+
+        if component.container.len() != len {
+            return None;
+        }
+
+        // Like OpenJPEG, we treat the offsets as a replacement of the DC level
+        // shift of the output component, which is applied afterwards, so
+        // we need to compensate for it.
+        let level_shift = (1_u32 << (component.bit_depth - 1)) as f32;
+        let offset = offsets.map_or(0.0, |o| o[j] - level_shift);
+
+        for (i, sample) in component.container.iter_mut().enumerate() {
+            let mut value = match matrix {
+                Some(matrix) => (0..n).map(|k| matrix[j * n + k] * input_data[k][i]).sum(),
+                None => input_data[j][i],
+            };
+
+            if collection.reversible {
+                value = math::round_f32(value);
+            }
+
+            *sample = value + offset;
+        }
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DecodeSettings, DecodeWarning, DecoderContext, Image};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// The packets of a 1x1 image with four components that consist of a
+    /// single code-block each. The coefficients of the components are
+    /// 22, 50, 17 and -105.
+    const PACKETS: [u8; 20] = [
+        0xC3, 0xE7, 0x04, 0x03, 0x1F, // Component 0.
+        0xC7, 0xD4, 0x04, 0x01, 0xCF, // Component 1.
+        0xC3, 0xE7, 0x04, 0x04, 0x7F, // Component 2.
+        0xCF, 0xB4, 0x08, 0x08, 0x6F, // Component 3.
+    ];
+
+    fn marker_segment(data: &mut Vec<u8>, marker: u8, body: &[u8]) {
+        data.extend_from_slice(&[0xFF, marker]);
+        data.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+        data.extend_from_slice(body);
+    }
+
+    /// Create a codestream that uses an array-based multiple component
+    /// transformation with a single component collection of the given type.
+    /// The matrix of the transformation adds and subtracts pairs of components.
+    fn codestream(collection_type: u8) -> Vec<u8> {
+        // SOC
+        let mut data = vec![0xFF, 0x4F];
+
+        // SIZ: 1x1 image with four unsigned 8-bit components.
+        let mut siz = vec![0x80, 0x00];
+        for value in [1_u32, 1, 0, 0, 1, 1, 0, 0] {
+            siz.extend_from_slice(&value.to_be_bytes());
+        }
+        siz.extend_from_slice(&[0x00, 0x04]);
+        siz.extend_from_slice(&[0x07, 0x01, 0x01].repeat(4));
+        marker_segment(&mut data, 0x51, &siz);
+
+        // COD: No decomposition levels, reversible transform and an
+        // array-based multiple component transformation.
+        marker_segment(
+            &mut data,
+            0x52,
+            &[0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x04, 0x04, 0x00, 0x01],
+        );
+
+        // QCD: No quantization, two guard bits.
+        marker_segment(&mut data, 0x5C, &[0x40, 0x40]);
+
+        // MCT: Decorrelation matrix with index 1, stored as 16-bit integers.
+        let mut matrix = vec![0x00, 0x00, 0x01, 0x01, 0x00, 0x00];
+        for value in [1_i16, 1, 0, 0, 1, -1, 0, 0, 0, 0, 1, 1, 0, 0, 1, -1] {
+            matrix.extend_from_slice(&value.to_be_bytes());
+        }
+        marker_segment(&mut data, 0x74, &matrix);
+
+        // MCT: Offsets with index 2, stored as 32-bit floats.
+        let mut offsets = vec![0x00, 0x00, 0x0A, 0x02, 0x00, 0x00];
+        for value in [128.0_f32, 128.0, 100.0, 128.0] {
+            offsets.extend_from_slice(&value.to_be_bytes());
+        }
+        marker_segment(&mut data, 0x74, &offsets);
+
+        // MCC: Stage with index 1 that maps the four components onto
+        // themselves, using the matrix and offsets from above.
+        marker_segment(
+            &mut data,
+            0x75,
+            &[
+                0x00,
+                0x00,
+                0x01,
+                0x00,
+                0x00,
+                0x00,
+                0x01, //
+                collection_type,
+                0x00,
+                0x04,
+                0x00,
+                0x01,
+                0x02,
+                0x03, //
+                0x00,
+                0x04,
+                0x00,
+                0x01,
+                0x02,
+                0x03, //
+                0x00,
+                0x02,
+                0x01,
+            ],
+        );
+
+        // MCO: Apply the stage with index 1.
+        marker_segment(&mut data, 0x77, &[0x01, 0x01]);
+
+        // SOT
+        let mut sot = vec![0x00, 0x00];
+        sot.extend_from_slice(&(PACKETS.len() as u32 + 14).to_be_bytes());
+        sot.extend_from_slice(&[0x00, 0x01]);
+        marker_segment(&mut data, 0x90, &sot);
+
+        // SOD
+        data.extend_from_slice(&[0xFF, 0x93]);
+        data.extend_from_slice(&PACKETS);
+
+        // EOC
+        data.extend_from_slice(&[0xFF, 0xD9]);
+
+        data
+    }
+
+    fn decode(data: &[u8], strict: bool) -> crate::Result<(Vec<u8>, Vec<DecodeWarning>)> {
+        let settings = DecodeSettings {
+            strict,
+            ..Default::default()
+        };
+        let image = Image::new(data, &settings)?;
+
+        let mut ctx = DecoderContext::default();
+        let decoded = image.decode(&mut ctx)?;
+
+        Ok((decoded.data_u8(), decoded.warnings().to_vec()))
+    }
+
+    #[test]
+    fn array_mct_decorrelation() {
+        let (decoded, warnings) = decode(&codestream(1), true).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(decoded, [200, 100, 12, 250]);
+    }
+
+    #[test]
+    fn array_mct_unsupported() {
+        // Dependency transformations are not supported.
+        let data = codestream(0);
+        assert!(decode(&data, true).is_err());
+
+        // In non-strict mode, the transformation is skipped, so we only get
+        // the level-shifted coefficients.
+        let (decoded, warnings) = decode(&data, false).unwrap();
+        assert_eq!(
+            warnings,
+            [DecodeWarning::UnsupportedMultipleComponentTransform]
+        );
+        assert_eq!(decoded, [150, 178, 145, 23]);
+    }
+}
@@ -11,6 +11,8 @@ mod segment;
 mod tag_tree;
 mod tile;
 
+use alloc::vec::Vec;
+
 use super::jp2::ImageBoxes;
 use super::jp2::colr::{ColorSpace, ColorSpecificationBox, EnumeratedColorspace};
 use crate::error::{FormatError, MarkerError, Result, bail};
@@ -69,6 +71,7 @@ pub(crate) fn parse<'a>(stream: &'a [u8], settings: &DecodeSettings) -> Result<I
         codestream: parsed_codestream.data,
         header: parsed_codestream.header,
         boxes,
+        raw_boxes: Vec::new(),
         settings: *settings,
         color_space,
         has_alpha,
@@ -1,6 +1,7 @@
 //! Parsing of layers and their segments, as specified in Annex B.
 
 use alloc::boxed::Box;
+use alloc::vec::Vec;
 
 use super::build::Segment;
 use super::codestream::markers::{EPH, SOP};
@@ -8,7 +9,7 @@ use super::codestream::{ComponentInfo, Header};
 use super::decode::DecompositionStorage;
 use super::progression::ProgressionData;
 use super::tile::{Tile, TilePart};
-use crate::error::{Result, TileError, bail};
+use crate::error::{DecodeWarning, Result, TileError, bail};
 use crate::reader::BitReader;
 
 pub(crate) const MAX_BITPLANE_COUNT: u8 = 32;
@@ -18,6 +19,7 @@ pub(crate) fn parse<'a, 'b>(
     mut progression_iterator: Box<dyn Iterator<Item = ProgressionData> + '_>,
     header: &Header<'_>,
     storage: &mut DecompositionStorage<'a>,
+    warnings: &mut Vec<DecodeWarning>,
 ) -> Result<()> {
     for tile_part in &tile.tile_parts {
         if parse_inner(
@@ -27,9 +29,19 @@ pub(crate) fn parse<'a, 'b>(
             storage,
         )
         .is_none()
-            && header.strict
         {
-            bail!(TileError::Invalid);
+            if header.strict {
+                bail!(TileError::Invalid);
+            }
+
+            // Code-blocks whose data couldn't be read completely will be
+            // ignored during bitplane decoding.
+            let warning = DecodeWarning::IncompleteTile(tile.idx);
+
+            if !warnings.contains(&warning) {
+                warn!("tile {} is incomplete, decoding it partially", tile.idx);
+                warnings.push(warning);
+            }
         }
     }
 
@@ -105,7 +117,16 @@ fn parse_inner<'a>(
                         let segments = &mut storage.segments[segments.clone()];
 
                         for segment in segments {
-                            segment.data = body_reader.read_bytes(segment.data_length as usize)?;
+                            let Some(data) = body_reader.read_bytes(segment.data_length as usize)
+                            else {
+                                // Keep the data that is available, the segment will
+                                // be recognized as incomplete based on its length.
+                                segment.data = body_reader.tail().unwrap_or_default();
+
+                                return None;
+                            };
+
+                            segment.data = data;
                         }
                     }
                 }
@@ -4,9 +4,14 @@ use alloc::vec;
 use alloc::vec::Vec;
 
 use super::build::{PrecinctData, SubBandType};
-use super::codestream::{ComponentInfo, Header, ProgressionOrder, markers, skip_marker_segment};
+use super::codestream::{
+    ComponentInfo, Header, MultipleComponentTransform, ProgressionOrder, markers,
+    skip_marker_segment,
+};
 use super::rect::IntRect;
-use crate::error::{MarkerError, Result, TileError, ValidationError, bail, err};
+use crate::error::{
+    DecodeWarning, DecodingError, MarkerError, Result, TileError, ValidationError, bail, err,
+};
 use crate::j2c::codestream;
 use crate::reader::BitReader;
 
@@ -27,7 +32,7 @@ pub(crate) struct Tile<'a> {
     pub(crate) rect: IntRect,
     pub(crate) progression_order: ProgressionOrder,
     pub(crate) num_layers: u8,
-    pub(crate) mct: bool,
+    pub(crate) mct: MultipleComponentTransform,
 }
 
 /// A tile part where packet headers and packet data are interleaved.
@@ -131,6 +136,7 @@ impl<'a> Tile<'a> {
 pub(crate) fn parse<'a>(
     reader: &mut BitReader<'a>,
     main_header: &'a Header<'a>,
+    warnings: &mut Vec<DecodeWarning>,
 ) -> Result<Vec<Tile<'a>>> {
     let mut tiles = (0..main_header.size_data.num_tiles() as usize)
         .map(|idx| Tile::new(idx as u32, main_header))
@@ -138,11 +144,23 @@ pub(crate) fn parse<'a>(
 
     let mut tile_part_idx = 0;
 
-    parse_tile_part(reader, main_header, &mut tiles, tile_part_idx)?;
+    parse_tile_part(reader, main_header, &mut tiles, tile_part_idx, warnings)?;
     tile_part_idx += 1;
 
     while reader.peek_marker() == Some(markers::SOT) {
-        parse_tile_part(reader, main_header, &mut tiles, tile_part_idx)?;
+        if let Err(e) = parse_tile_part(reader, main_header, &mut tiles, tile_part_idx, warnings) {
+            if main_header.strict {
+                return Err(e);
+            }
+
+            // Most likely, the codestream was truncated in the middle of a
+            // tile-part header. Just decode the tile-parts we have so far.
+            warn!("failed to parse tile-part: {e}");
+            truncated(warnings);
+
+            break;
+        }
+
         tile_part_idx += 1;
     }
 
@@ -158,6 +176,7 @@ fn parse_tile_part<'a>(
     main_header: &'a Header<'a>,
     tiles: &mut [Tile<'a>],
     tile_part_idx: usize,
+    warnings: &mut Vec<DecodeWarning>,
 ) -> Result<()> {
     if reader.read_marker()? != markers::SOT {
         bail!(MarkerError::Expected("SOT"));
@@ -191,6 +210,8 @@ fn parse_tile_part<'a>(
             return if main_header.strict {
                 err!(MarkerError::Invalid)
             } else {
+                truncated(warnings);
+
                 Ok(())
             };
         };
@@ -265,6 +286,17 @@ fn parse_tile_part<'a>(
                 reader.read_marker()?;
                 skip_marker_segment(reader).ok_or(MarkerError::ParseFailure("COM"))?;
             }
+            markers::MCT | markers::MCC | markers::MCO | markers::CBD => {
+                // We only support multiple component transformations that
+                // are defined in the main header.
+                if main_header.strict {
+                    bail!(MarkerError::Unsupported);
+                }
+
+                reader.read_marker()?;
+                skip_marker_segment(reader)
+                    .ok_or(MarkerError::ParseFailure(markers::to_string(marker)))?;
+            }
             (0x30..=0x3F) => {
                 // "All markers with the marker code between 0xFF30 and 0xFF3F
                 // have no marker segment parameters. They shall be skipped by
@@ -295,9 +327,19 @@ fn parse_tile_part<'a>(
         headers.push(BitReader::new(ppm_marker.data));
     }
 
-    let data = reader
-        .read_bytes(remaining_bytes)
-        .ok_or(TileError::Invalid)?;
+    let data = if let Some(data) = reader.read_bytes(remaining_bytes) {
+        data
+    } else if main_header.strict {
+        bail!(DecodingError::UnexpectedEof);
+    } else {
+        // The codestream is truncated, so just use the data that is left.
+        // Packets that can't be read completely will be ignored.
+        truncated(warnings);
+        let data = reader.tail().unwrap_or_default();
+        reader.jump_to_end();
+
+        data
+    };
 
     let tile_part = if !headers.is_empty() {
         TilePart::Separated(SeparatedTilePart {
@@ -316,6 +358,12 @@ fn parse_tile_part<'a>(
     Ok(())
 }
 
+fn truncated(warnings: &mut Vec<DecodeWarning>) {
+    if !warnings.contains(&DecodeWarning::TruncatedCodestream) {
+        warnings.push(DecodeWarning::TruncatedCodestream);
+    }
+}
+
 /// A tile, instantiated to a specific component.
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct ComponentTile<'a> {
@@ -727,7 +775,7 @@ fn sot_marker(reader: &mut BitReader<'_>) -> Option<TilePartHeader> {
 mod tests {
     use super::*;
     use crate::j2c::codestream::{
-        CodeBlockStyle, CodingStyleComponent, CodingStyleDefault, CodingStyleFlags,
+        ArrayMct, CodeBlockStyle, CodingStyleComponent, CodingStyleDefault, CodingStyleFlags,
         CodingStyleParameters, ComponentSizeInfo, QuantizationInfo, QuantizationStyle, SizeData,
         WaveletTransform,
     };
@@ -807,7 +855,7 @@ mod tests {
             global_coding_style: CodingStyleDefault {
                 progression_order: ProgressionOrder::LayerResolutionComponentPosition,
                 num_layers: 0,
-                mct: false,
+                mct: MultipleComponentTransform::None,
                 component_parameters: CodingStyleComponent {
                     flags: CodingStyleFlags::default(),
                     parameters: CodingStyleParameters {
@@ -824,6 +872,7 @@ mod tests {
             component_infos: vec![],
             ppm_packets: vec![],
             skipped_resolution_levels: 0,
+            array_mct: ArrayMct::default(),
             strict: false,
         };
 
@@ -48,9 +48,27 @@ pub(crate) const UUID_LIST: u32 = 0x756C7374;
 /// URL box - 'url\x20'.
 pub(crate) const URL: u32 = 0x75726C20;
 
+/// The type of a JP2 box, stored as a four-character code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BoxType(pub [u8; 4]);
+
+impl BoxType {
+    /// The UUID box type, usually used for storing EXIF metadata.
+    pub const UUID: Self = Self(*b"uuid");
+    /// The XML box type, usually used for storing XMP metadata.
+    pub const XML: Self = Self(*b"xml ");
+
+    pub(crate) fn from_tag(tag: u32) -> Self {
+        Self(tag.to_be_bytes())
+    }
+}
+
 pub(crate) struct Jp2Box<'a> {
     pub(crate) data: &'a [u8],
     pub(crate) box_type: u32,
+    /// Whether the declared length of the box exceeded the remaining data, in
+    /// which case the box has been truncated.
+    pub(crate) truncated: bool,
 }
 
 /// Converts a box tag to its string representation.
@@ -70,33 +88,55 @@ pub(crate) fn read<'a>(reader: &mut BitReader<'a>) -> Option<Jp2Box<'a>> {
     let l_box = reader.read_u32()?;
     let t_box = reader.read_u32()?;
 
-    let data = match l_box {
+    let length = match l_box {
         // If the value of this field is 0, then the length of the box
         // was not known when the LBox field was written. In this case, this box contains
         // all bytes up to the end of the file.
         0 => {
             let data = reader.tail()?;
             reader.jump_to_end();
-            data
+
+            return Some(Jp2Box {
+                data,
+                box_type: t_box,
+                truncated: false,
+            });
         }
         // If the value of this field is 1, then the XLBox field shall exist and the value of
         // that field shall be the actual length of the box.
         // The value includes all of the fields of the box, including the LBox, TBox and XLBox
         // fields.
-        1 => {
-            let xl_box = reader.read_u64()?.checked_sub(16)?;
-            reader.read_bytes(xl_box as usize)?
-        }
+        1 => reader.read_u64()?.checked_sub(16)?,
         // This field specifies the length of the box, stored as a 4-byte big-endian unsigned integer.
         // This value includes all of the fields of the box, including the length and type.
-        _ => {
-            let length = l_box.checked_sub(8)?;
-            reader.read_bytes(length as usize)?
-        }
+        _ => l_box.checked_sub(8)? as u64,
     };
 
+    if let Some(data) = usize::try_from(length)
+        .ok()
+        .and_then(|length| reader.read_bytes(length))
+    {
+        return Some(Jp2Box {
+            data,
+            box_type: t_box,
+            truncated: false,
+        });
+    }
+
+    // The box claims to be longer than the remaining data, so just take
+    // what we have.
+    warn!(
+        "box {} has a length of {length}, but only {} bytes are left, truncating it",
+        tag_to_string(t_box),
+        reader.tail()?.len()
+    );
+
+    let data = reader.tail()?;
+    reader.jump_to_end();
+
     Some(Jp2Box {
         data,
         box_type: t_box,
+        truncated: true,
     })
 }
@@ -28,10 +28,7 @@ pub(crate) fn parse(boxes: &mut ImageBoxes, data: &[u8]) -> Result<()> {
     let method = match meth {
         1 => {
             let enumerated = reader.read_u32().ok_or(FormatError::InvalidBox)?;
-            ColorSpace::Enumerated(
-                EnumeratedColorspace::from_raw(enumerated, &mut reader)
-                    .ok_or(FormatError::InvalidBox)?,
-            )
+            ColorSpace::Enumerated(EnumeratedColorspace::from_raw(enumerated, &mut reader))
         }
         2 => {
             let profile_data = reader.tail().ok_or(FormatError::InvalidBox)?.to_vec();
@@ -82,6 +79,8 @@ pub(crate) enum EnumeratedColorspace {
     EsYcc,
     ScRgb,
     ScRgbGray,
+    /// An enumerated color space that isn't defined by the specification.
+    Other(u32),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -95,16 +94,16 @@ pub(crate) struct CieLab {
 }
 
 impl EnumeratedColorspace {
-    fn from_raw(value: u32, reader: &mut BitReader<'_>) -> Option<Self> {
+    fn from_raw(value: u32, reader: &mut BitReader<'_>) -> Self {
         match value {
-            0 => Some(Self::BiLevel1),
-            1 => Some(Self::YCbCr1),
-            3 => Some(Self::YCbCr2),
-            4 => Some(Self::YCbCr3),
-            9 => Some(Self::PhotoYcc),
-            11 => Some(Self::Cmy),
-            12 => Some(Self::Cmyk),
-            13 => Some(Self::Ycck),
+            0 => Self::BiLevel1,
+            1 => Self::YCbCr1,
+            3 => Self::YCbCr2,
+            4 => Self::YCbCr3,
+            9 => Self::PhotoYcc,
+            11 => Self::Cmy,
+            12 => Self::Cmyk,
+            13 => Self::Ycck,
             14 => {
                 // M.11.7.4.1 EP field format for the CIELab colourspace
                 let rl = reader.read_u32();
@@ -116,28 +115,28 @@ impl EnumeratedColorspace {
                 // Not supported for now.
                 let _il = reader.read_u32();
 
-                Some(Self::CieLab(CieLab {
+                Self::CieLab(CieLab {
                     rl,
                     ol,
                     ra,
                     oa,
                     rb,
                     ob,
-                }))
+                })
             }
-            15 => Some(Self::BiLevel2),
-            16 => Some(Self::Srgb),
-            17 => Some(Self::Greyscale),
-            18 => Some(Self::Sycc),
-            19 => Some(Self::CieJab),
-            20 => Some(Self::EsRgb),
-            21 => Some(Self::RommRgb),
-            22 => Some(Self::YPbPr112560),
-            23 => Some(Self::YPbPr125050),
-            24 => Some(Self::EsYcc),
-            25 => Some(Self::ScRgb),
-            26 => Some(Self::ScRgbGray),
-            _ => None,
+            15 => Self::BiLevel2,
+            16 => Self::Srgb,
+            17 => Self::Greyscale,
+            18 => Self::Sycc,
+            19 => Self::CieJab,
+            20 => Self::EsRgb,
+            21 => Self::RommRgb,
+            22 => Self::YPbPr112560,
+            23 => Self::YPbPr125050,
+            24 => Self::EsYcc,
+            25 => Self::ScRgb,
+            26 => Self::ScRgbGray,
+            _ => Self::Other(value),
         }
     }
 }
@@ -2,13 +2,14 @@
 
 use alloc::vec::Vec;
 
-use crate::error::{FormatError, Result, bail};
+use crate::error::{DecodeWarning, FormatError, Result, bail};
 use crate::j2c::ComponentData;
-use crate::jp2::r#box::{FILE_TYPE, JP2_SIGNATURE};
+use crate::jp2::r#box::{BoxType, FILE_TYPE, JP2_SIGNATURE};
 use crate::jp2::cdef::ChannelDefinitionBox;
 use crate::jp2::cmap::{ComponentMappingBox, ComponentMappingEntry, ComponentMappingType};
 use crate::jp2::colr::ColorSpecificationBox;
 use crate::jp2::pclr::PaletteBox;
+use crate::jp2::res::Resolution;
 use crate::reader::BitReader;
 use crate::{DecodeSettings, Image, resolve_alpha_and_color_space};
 
@@ -18,6 +19,10 @@ pub(crate) mod cmap;
 pub(crate) mod colr;
 pub(crate) mod icc;
 pub(crate) mod pclr;
+pub(crate) mod res;
+
+/// The maximum number of bytes of unrecognized boxes that are retained for an image.
+const MAX_RAW_BOXES_SIZE: usize = 16 * 1024 * 1024;
 
 #[derive(Debug, Clone, Default)]
 pub(crate) struct ImageBoxes {
@@ -25,12 +30,16 @@ pub(crate) struct ImageBoxes {
     pub(crate) channel_definition: Option<ChannelDefinitionBox>,
     pub(crate) palette: Option<PaletteBox>,
     pub(crate) component_mapping: Option<ComponentMappingBox>,
+    pub(crate) capture_resolution: Option<Resolution>,
+    pub(crate) display_resolution: Option<Resolution>,
 }
 
 /// A decoded JPEG2000 image.
 pub struct DecodedImage<'a> {
     /// The raw decoded JPEG2000 codestream components.
     pub(crate) decoded_components: &'a mut Vec<ComponentData>,
+    /// The problems that were recovered from during decoding.
+    pub(crate) warnings: &'a [DecodeWarning],
     /// The JP2 boxes of the image. In the case of a raw codestream, we
     /// will synthesize the necessary boxes.
     pub(crate) boxes: ImageBoxes,
@@ -52,6 +61,8 @@ pub(crate) fn parse<'a>(data: &'a [u8], mut settings: DecodeSettings) -> Result<
 
     let mut image_boxes: Option<ImageBoxes> = None;
     let mut parsed_codestream = None;
+    let mut raw_boxes = Vec::new();
+    let mut raw_boxes_size = 0;
 
     // Read boxes until we find the JP2 Header box
     while !reader.at_end() {
@@ -63,6 +74,10 @@ pub(crate) fn parse<'a>(data: &'a [u8], mut settings: DecodeSettings) -> Result<
             break;
         };
 
+        if current_box.truncated && settings.strict {
+            bail!(FormatError::InvalidBox);
+        }
+
         match current_box.box_type {
             r#box::JP2_HEADER => {
                 let mut boxes = ImageBoxes::default();
@@ -73,6 +88,10 @@ pub(crate) fn parse<'a>(data: &'a [u8], mut settings: DecodeSettings) -> Result<
                 while !jp2h_reader.at_end() {
                     let child_box = r#box::read(&mut jp2h_reader).ok_or(FormatError::InvalidBox)?;
 
+                    if child_box.truncated && settings.strict {
+                        bail!(FormatError::InvalidBox);
+                    }
+
                     match child_box.box_type {
                         r#box::CHANNEL_DEFINITION => {
                             if cdef::parse(&mut boxes, child_box.data).is_err() && settings.strict {
@@ -97,6 +116,11 @@ pub(crate) fn parse<'a>(data: &'a [u8], mut settings: DecodeSettings) -> Result<
                         r#box::COMPONENT_MAPPING => {
                             cmap::parse(&mut boxes, child_box.data)?;
                         }
+                        r#box::RESOLUTION => {
+                            if res::parse(&mut boxes, child_box.data).is_err() && settings.strict {
+                                bail!(FormatError::InvalidBox);
+                            }
+                        }
                         _ => {
                             debug!(
                                 "ignoring header box {}",
@@ -111,7 +135,18 @@ pub(crate) fn parse<'a>(data: &'a [u8], mut settings: DecodeSettings) -> Result<
             r#box::CONTIGUOUS_CODESTREAM => {
                 parsed_codestream = Some(crate::j2c::parse_raw(current_box.data, &settings)?);
             }
-            _ => {}
+            _ => {
+                raw_boxes_size += current_box.data.len();
+
+                if raw_boxes_size > MAX_RAW_BOXES_SIZE {
+                    warn!(
+                        "skipping box {} because the size limit was reached",
+                        r#box::tag_to_string(current_box.box_type)
+                    );
+                } else {
+                    raw_boxes.push((BoxType::from_tag(current_box.box_type), current_box.data));
+                }
+            }
         }
     }
 
@@ -141,8 +176,259 @@ pub(crate) fn parse<'a>(data: &'a [u8], mut settings: DecodeSettings) -> Result<
         codestream: parsed_codestream.data,
         header: parsed_codestream.header,
         boxes: image_boxes,
+        raw_boxes,
         settings,
         color_space,
         has_alpha,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{BoxType, ColorSpace, DecodeSettings, DecoderContext, Image, Resolution};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn jp2_box(box_type: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut out = ((data.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(box_type);
+        out.extend_from_slice(data);
+        out
+    }
+
+    /// The codestream of a 1x1 grayscale image.
+    const CODESTREAM: &[u8] = &[
+        // SOC
+        0xFF, 0x4F, //
+        // SIZ
+        0xFF, 0x51, 0x00, 0x29, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, //
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, //
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+        0x00, 0x01, 0x07, 0x01, 0x01, //
+        // COD
+        0xFF, 0x52, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x01, //
+        0x00, 0x00, 0x04, 0x04, 0x00, 0x01, //
+        // QCD
+        0xFF, 0x5C, 0x00, 0x04, 0x40, 0x40, //
+        // SOT
+        0xFF, 0x90, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, //
+        0x00, 0x00, 0x00, 0x01, //
+        // EOC
+        0xFF, 0xD9,
+    ];
+
+    /// Create a JP2 file for a 1x1 grayscale image, followed by the given boxes.
+    fn minimal_jp2(extra_boxes: &[Vec<u8>]) -> Vec<u8> {
+        jp2_with_colr(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x11], extra_boxes)
+    }
+
+    /// Create a JP2 file for a 1x1 image with the given color specification,
+    /// followed by the given boxes.
+    fn jp2_with_colr(colr: &[u8], extra_boxes: &[Vec<u8>]) -> Vec<u8> {
+        jp2_with_header(&[jp2_box(b"colr", colr)], extra_boxes)
+    }
+
+    /// Create a JP2 file for a 1x1 image with the given header boxes,
+    /// followed by the given boxes.
+    fn jp2_with_header(header_boxes: &[Vec<u8>], extra_boxes: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = jp2_box(b"jP  ", &[0x0D, 0x0A, 0x87, 0x0A]);
+        data.extend(jp2_box(b"ftyp", b"jp2 \x00\x00\x00\x00jp2 "));
+        data.extend(jp2_box(b"jp2h", &header_boxes.concat()));
+
+        for extra_box in extra_boxes {
+            data.extend_from_slice(extra_box);
+        }
+
+        data.extend(jp2_box(b"jp2c", CODESTREAM));
+        data
+    }
+
+    #[test]
+    fn raw_boxes() {
+        let data = minimal_jp2(&[
+            jp2_box(b"uuid", b"exif data"),
+            jp2_box(b"xml ", b"<x:xmpmeta/>"),
+        ]);
+        let image = Image::new(&data, &DecodeSettings::default()).unwrap();
+
+        assert_eq!(
+            image.raw_boxes(),
+            [
+                (BoxType::UUID, b"exif data".as_slice()),
+                (BoxType::XML, b"<x:xmpmeta/>".as_slice()),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_boxes_size_limit() {
+        let data = minimal_jp2(&[
+            jp2_box(b"uuid", &vec![0; super::MAX_RAW_BOXES_SIZE]),
+            jp2_box(b"xml ", b"<x:xmpmeta/>"),
+        ]);
+        let image = Image::new(&data, &DecodeSettings::default()).unwrap();
+        let raw_boxes = image.raw_boxes();
+
+        assert_eq!(raw_boxes.len(), 1);
+        assert_eq!(raw_boxes[0].0, BoxType::UUID);
+    }
+
+    /// Create a capture or display resolution box.
+    fn resolution_box(
+        box_type: &[u8; 4],
+        vertical: (u16, u16, i8),
+        horizontal: (u16, u16, i8),
+    ) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend(vertical.0.to_be_bytes());
+        data.extend(vertical.1.to_be_bytes());
+        data.extend(horizontal.0.to_be_bytes());
+        data.extend(horizontal.1.to_be_bytes());
+        data.push(vertical.2 as u8);
+        data.push(horizontal.2 as u8);
+
+        jp2_box(box_type, &data)
+    }
+
+    fn assert_resolution(resolution: Option<Resolution>, horizontal: f64, vertical: f64) {
+        let resolution = resolution.unwrap();
+
+        assert!(
+            (resolution.horizontal - horizontal).abs() < 0.01,
+            "{resolution:?}"
+        );
+        assert!(
+            (resolution.vertical - vertical).abs() < 0.01,
+            "{resolution:?}"
+        );
+    }
+
+    #[test]
+    fn resolution() {
+        let res = [
+            // 150x300 DPI, i.e. 30000/254 * 10^2 grid points per meter.
+            resolution_box(b"resc", (15000, 254, 2), (30000, 254, 2)),
+            // 72 DPI, with a negative exponent.
+            resolution_box(b"resd", (28346, 1, -1), (28346, 1, -1)),
+        ]
+        .concat();
+        let data = jp2_with_header(
+            &[
+                jp2_box(b"colr", &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x11]),
+                jp2_box(b"res ", &res),
+            ],
+            &[],
+        );
+        let image = Image::new(&data, &DecodeSettings::default()).unwrap();
+
+        assert_resolution(image.capture_resolution(), 300.0, 150.0);
+        assert_resolution(image.display_resolution(), 72.0, 72.0);
+    }
+
+    #[test]
+    fn resolution_invalid() {
+        let res = resolution_box(b"resd", (72, 0, 0), (72, 0, 0));
+        let data = jp2_with_header(
+            &[
+                jp2_box(b"colr", &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x11]),
+                jp2_box(b"res ", &res),
+            ],
+            &[],
+        );
+
+        let image = Image::new(&data, &DecodeSettings::default()).unwrap();
+        assert!(image.display_resolution().is_none());
+
+        let strict = DecodeSettings {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(Image::new(&data, &strict).is_err());
+    }
+
+    #[test]
+    fn xml_and_uuid_boxes() {
+        let uuid = *b"JpgTiffExif->JP2";
+        let data = minimal_jp2(&[
+            jp2_box(b"xml ", b"<gml:FeatureCollection/>"),
+            jp2_box(b"uuid", &[uuid.as_slice(), b"II*\x00"].concat()),
+            // Too short to contain a UUID.
+            jp2_box(b"uuid", b"short"),
+        ]);
+        let image = Image::new(&data, &DecodeSettings::default()).unwrap();
+
+        assert_eq!(
+            image.xml_boxes().collect::<Vec<_>>(),
+            vec![b"<gml:FeatureCollection/>".as_slice()]
+        );
+        assert_eq!(
+            image.uuid_boxes().collect::<Vec<_>>(),
+            vec![(uuid, b"II*\x00".as_slice())]
+        );
+        assert!(image.capture_resolution().is_none());
+    }
+
+    #[test]
+    fn truncated_box() {
+        let mut data = minimal_jp2(&[]);
+        // An XML box that claims to be longer than the rest of the file.
+        data.extend(1000_u32.to_be_bytes());
+        data.extend(b"xml <truncated/>");
+
+        let image = Image::new(&data, &DecodeSettings::default()).unwrap();
+        assert_eq!(
+            image.xml_boxes().collect::<Vec<_>>(),
+            vec![b"<truncated/>".as_slice()]
+        );
+
+        let strict = DecodeSettings {
+            strict: true,
+            ..Default::default()
+        };
+        assert!(Image::new(&data, &strict).is_err());
+    }
+
+    #[test]
+    fn raw_boxes_codestream() {
+        let image = Image::new(CODESTREAM, &DecodeSettings::default()).unwrap();
+
+        assert!(image.raw_boxes().is_empty());
+    }
+
+    #[test]
+    fn unknown_colorspace_passthrough() {
+        let passthrough = DecodeSettings {
+            unknown_colorspace_passthrough: true,
+            ..Default::default()
+        };
+
+        // CIEJab, which is not supported, and an enumerated value that isn't
+        // defined by the specification.
+        for enumerated in [0x13, 0x64] {
+            let data = jp2_with_colr(&[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, enumerated], &[]);
+            assert!(Image::new(&data, &DecodeSettings::default()).is_err());
+
+            let image = Image::new(&data, &passthrough).unwrap();
+            assert!(matches!(
+                image.color_space(),
+                ColorSpace::Unknown { num_channels: 1 }
+            ));
+
+            let mut ctx = DecoderContext::default();
+            let decoded = image.decode(&mut ctx).unwrap();
+            assert_eq!(decoded.data_u8().len(), 1);
+        }
+
+        // An ICC profile that cannot be parsed.
+        let data = jp2_with_colr(b"\x02\x00\x00invalid", &[]);
+        let image = Image::new(&data, &DecodeSettings::default()).unwrap();
+        assert!(matches!(image.color_space(), ColorSpace::Gray));
+        let image = Image::new(&data, &passthrough).unwrap();
+        assert!(matches!(
+            image.color_space(),
+            ColorSpace::Unknown { num_channels: 1 }
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+//! The resolution box (res), defined in I.5.3.7.
+
+use crate::error::{FormatError, Result, bail};
+use crate::jp2::ImageBoxes;
+use crate::jp2::r#box::{self, CAPTURE_RESOLUTION, DISPLAY_RESOLUTION};
+use crate::reader::BitReader;
+
+/// The number of inches per meter.
+const INCHES_PER_METER: f64 = 1.0 / 0.0254;
+
+/// The resolution of an image in dots per inch.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Resolution {
+    /// The horizontal resolution.
+    pub horizontal: f64,
+    /// The vertical resolution.
+    pub vertical: f64,
+}
+
+pub(crate) fn parse(boxes: &mut ImageBoxes, data: &[u8]) -> Result<()> {
+    let mut reader = BitReader::new(data);
+
+    // The resolution box is a superbox that contains a capture resolution box,
+    // a default display resolution box, or both.
+    while !reader.at_end() {
+        let child_box = r#box::read(&mut reader).ok_or(FormatError::InvalidBox)?;
+
+        match child_box.box_type {
+            CAPTURE_RESOLUTION => {
+                boxes.capture_resolution = Some(parse_resolution(child_box.data)?);
+            }
+            DISPLAY_RESOLUTION => {
+                boxes.display_resolution = Some(parse_resolution(child_box.data)?);
+            }
+            _ => {
+                debug!(
+                    "ignoring resolution box {}",
+                    r#box::tag_to_string(child_box.box_type)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_resolution(data: &[u8]) -> Result<Resolution> {
+    let mut reader = BitReader::new(data);
+    let vertical_numerator = reader.read_u16().ok_or(FormatError::InvalidBox)?;
+    let vertical_denominator = reader.read_u16().ok_or(FormatError::InvalidBox)?;
+    let horizontal_numerator = reader.read_u16().ok_or(FormatError::InvalidBox)?;
+    let horizontal_denominator = reader.read_u16().ok_or(FormatError::InvalidBox)?;
+    let vertical_exponent = reader.read_byte().ok_or(FormatError::InvalidBox)? as i8;
+    let horizontal_exponent = reader.read_byte().ok_or(FormatError::InvalidBox)? as i8;
+
+    Ok(Resolution {
+        horizontal: to_dpi(
+            horizontal_numerator,
+            horizontal_denominator,
+            horizontal_exponent,
+        )?,
+        vertical: to_dpi(vertical_numerator, vertical_denominator, vertical_exponent)?,
+    })
+}
+
+/// Convert a resolution in grid points per meter, stored as `numerator /
+/// denominator * 10^exponent`, into dots per inch.
+fn to_dpi(numerator: u16, denominator: u16, exponent: i8) -> Result<f64> {
+    if numerator == 0 || denominator == 0 {
+        bail!(FormatError::InvalidBox);
+    }
+
+    let mut value = numerator as f64 / denominator as f64;
+
+    for _ in 0..exponent.unsigned_abs() {
+        if exponent > 0 {
+            value *= 10.0;
+        } else {
+            value /= 10.0;
+        }
+    }
+
+    Ok(value / INCHES_PER_METER)
+}
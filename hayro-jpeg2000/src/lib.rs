@@ -62,6 +62,13 @@ feature, at the cost of worse decoding performance. Unsafe code is forbidden
 via a crate-level attribute.
 
 The crate is `no_std` compatible but requires an allocator to be available.
+
+# Reproducibility
+The output of the decoder is byte-identical regardless of whether the `simd`
+feature is enabled and which SIMD instructions are available at runtime. The SIMD
+and the scalar code paths perform the same floating-point operations in the same
+order, and fused multiply-add instructions are never used, since they round
+differently than a separate multiplication and addition.
 */
 
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -88,11 +95,13 @@ pub(crate) mod math;
 
 use crate::math::{Level, SIMD_WIDTH, Simd, dispatch, f32x8};
 pub use error::{
-    ColorError, DecodeError, DecodingError, FormatError, MarkerError, Result, TileError,
-    ValidationError,
+    ColorError, DecodeError, DecodeWarning, DecodingError, FormatError, MarkerError, Result,
+    TileError, ValidationError,
 };
 pub use j2c::{ComponentData, DecoderContext};
 pub use jp2::DecodedImage;
+pub use jp2::r#box::BoxType;
+pub use jp2::res::Resolution;
 
 #[cfg(feature = "image")]
 pub mod integration;
@@ -107,6 +116,7 @@ pub(crate) const CODESTREAM_MAGIC: &[u8] = b"\xFF\x4F\xFF\x51";
 
 /// Settings to apply during decoding.
 #[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
 pub struct DecodeSettings {
     /// Whether palette indices should be resolved.
     ///
@@ -127,6 +137,15 @@ pub struct DecodeSettings {
     pub strict: bool,
     /// A hint for the target resolution that the image should be decoded at.
     pub target_resolution: Option<(u32, u32)>,
+    /// Whether images with unsupported color spaces should be passed through.
+    ///
+    /// By default, decoding fails for enumerated color spaces that aren't
+    /// supported. If you enable this option, such images (as well as images
+    /// with ICC profiles that cannot be parsed) are instead reported as
+    /// [`ColorSpace::Unknown`], and their components are returned as is
+    /// without any color conversion. This can be useful for extracting the
+    /// raw data of, for example, multispectral images.
+    pub unknown_colorspace_passthrough: bool,
 }
 
 impl Default for DecodeSettings {
@@ -135,6 +154,7 @@ impl Default for DecodeSettings {
             resolve_palette_indices: true,
             strict: false,
             target_resolution: None,
+            unknown_colorspace_passthrough: false,
         }
     }
 }
@@ -148,6 +168,8 @@ pub struct Image<'a> {
     /// The JP2 boxes of the image. In the case of a raw codestream, we
     /// will synthesize the necessary boxes.
     pub(crate) boxes: ImageBoxes,
+    /// The top-level JP2 boxes that aren't needed for decoding.
+    pub(crate) raw_boxes: Vec<(BoxType, &'a [u8])>,
     /// Settings that should be applied during decoding.
     pub(crate) settings: DecodeSettings,
     /// Whether the image has an alpha channel.
@@ -195,6 +217,53 @@ impl<'a> Image<'a> {
         self.header.component_infos[0].size_info.precision
     }
 
+    /// The contents of all top-level JP2 boxes that aren't needed for decoding the
+    /// image, for example `uuid` boxes with EXIF data or `xml ` boxes with XMP data.
+    ///
+    /// This is always empty for raw codestreams.
+    pub fn raw_boxes(&self) -> &[(BoxType, &'a [u8])] {
+        &self.raw_boxes
+    }
+
+    /// The resolution at which the image was captured, for example by a scanner.
+    ///
+    /// This is `None` if the image doesn't specify a capture resolution or is a
+    /// raw codestream.
+    pub fn capture_resolution(&self) -> Option<Resolution> {
+        self.boxes.capture_resolution
+    }
+
+    /// The resolution at which the image should be displayed by default.
+    ///
+    /// This is `None` if the image doesn't specify a default display resolution
+    /// or is a raw codestream.
+    pub fn display_resolution(&self) -> Option<Resolution> {
+        self.boxes.display_resolution
+    }
+
+    /// The payloads of all top-level `xml ` boxes, for example XMP metadata or
+    /// GML georeferencing information.
+    pub fn xml_boxes(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        self.raw_boxes
+            .iter()
+            .filter(|(box_type, _)| *box_type == BoxType::XML)
+            .map(|(_, data)| *data)
+    }
+
+    /// The identifying UUIDs and payloads of all top-level `uuid` boxes.
+    ///
+    /// Boxes that are too short to contain a UUID are skipped.
+    pub fn uuid_boxes(&self) -> impl Iterator<Item = ([u8; 16], &'a [u8])> + '_ {
+        self.raw_boxes
+            .iter()
+            .filter(|(box_type, _)| *box_type == BoxType::UUID)
+            .filter_map(|(_, data)| {
+                let (uuid, payload) = data.split_first_chunk::<16>()?;
+
+                Some((*uuid, payload))
+            })
+    }
+
     /// Decode the image and return its decoded components.
     pub fn decode<'b>(
         &'a self,
@@ -204,6 +273,7 @@ impl<'a> Image<'a> {
         j2c::decode(self.codestream, &self.header, decoder_context)?;
         let mut decoded_image = DecodedImage {
             decoded_components: &mut decoder_context.channel_data,
+            warnings: &decoder_context.warnings,
             boxes: self.boxes.clone(),
         };
 
@@ -264,7 +334,7 @@ pub(crate) fn resolve_alpha_and_color_space(
         has_alpha = last.channel_type == ChannelType::Opacity;
     }
 
-    let mut color_space = get_color_space(boxes, num_components)?;
+    let mut color_space = get_color_space(boxes, num_components, has_alpha, settings)?;
 
     // If we didn't resolve palette indices, we need to assume grayscale image.
     if !settings.resolve_palette_indices && boxes.palette.is_some() {
@@ -376,6 +446,14 @@ impl DecodedImage<'_> {
         self.decoded_components
     }
 
+    /// The problems that were recovered from while decoding the image.
+    ///
+    /// If this is not empty, the image might have only been partially decoded,
+    /// for example because the codestream was truncated.
+    pub fn warnings(&self) -> &[DecodeWarning] {
+        self.warnings
+    }
+
     /// Return the decoded image as interleaved unsigned 8-bit sample data.
     pub fn data_u8(&self) -> Vec<u8> {
         let components = self.components();
@@ -513,7 +591,16 @@ fn convert_color_space(image: &mut DecodedImage<'_>, bit_depth: u8) -> Result<()
     Ok(())
 }
 
-fn get_color_space(boxes: &ImageBoxes, num_components: usize) -> Result<ColorSpace> {
+fn get_color_space(
+    boxes: &ImageBoxes,
+    num_components: usize,
+    has_alpha: bool,
+    settings: &DecodeSettings,
+) -> Result<ColorSpace> {
+    let passthrough = || ColorSpace::Unknown {
+        num_channels: num_components.saturating_sub(usize::from(has_alpha)) as u8,
+    };
+
     let cs = match boxes
         .color_specification
         .as_ref()
@@ -538,6 +625,7 @@ fn get_color_space(boxes: &ImageBoxes, num_components: usize) -> Result<ColorSpa
                     profile: include_bytes!("../assets/LAB.icc").to_vec(),
                     num_channels: 3,
                 },
+                _ if settings.unknown_colorspace_passthrough => passthrough(),
                 _ => bail!(FormatError::Unsupported),
             }
         }
@@ -547,6 +635,8 @@ fn get_color_space(boxes: &ImageBoxes, num_components: usize) -> Result<ColorSpa
                     profile: icc.clone(),
                     num_channels: metadata.color_space.num_components(),
                 }
+            } else if settings.unknown_colorspace_passthrough {
+                passthrough()
             } else {
                 // See OPENJPEG test orb-blue10-lin-jp2.jp2. They seem to
                 // assume RGB in this case (even though the image has 4
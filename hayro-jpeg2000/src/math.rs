@@ -33,11 +33,13 @@ mod inner {
             }
         }
 
+        /// Compute `self * mul + addend`.
+        ///
+        /// This deliberately doesn't use fused multiply-add instructions, see
+        /// [`super::mul_add`].
         #[inline(always)]
         pub(crate) fn mul_add(self, mul: Self, addend: Self) -> Self {
-            Self {
-                inner: self.inner.mul_add(mul.inner, addend.inner),
-            }
+            self * mul + addend
         }
 
         #[inline(always)]
@@ -419,39 +421,24 @@ mod inner {
     pub(crate) use simd_dispatch as dispatch;
 }
 
-// Note that these polyfills can be very imprecise, but hopefully good enough
-// for the vast majority of cases.
+// The scalar functions below need to produce exactly the same results as their
+// SIMD counterparts and as the functions of the standard library, so that the
+// decoded output doesn't depend on the enabled features or on the CPU.
 
+/// Compute `a * b + c`.
+///
+/// This deliberately doesn't use a fused multiply-add, since whether one is
+/// available depends on the target and on the SIMD level that is detected at
+/// runtime, and the result of a fused multiply-add is rounded differently.
 #[inline(always)]
 pub(crate) fn mul_add(a: f32, b: f32, c: f32) -> f32 {
-    #[cfg(all(
-        feature = "std",
-        any(
-            all(
-                any(target_arch = "x86", target_arch = "x86_64"),
-                target_feature = "fma"
-            ),
-            all(target_arch = "aarch64", target_feature = "neon")
-        )
-    ))]
-    {
-        f32::mul_add(a, b, c)
-    }
-    #[cfg(not(all(
-        feature = "std",
-        any(
-            all(
-                any(target_arch = "x86", target_arch = "x86_64"),
-                target_feature = "fma"
-            ),
-            all(target_arch = "aarch64", target_feature = "neon")
-        )
-    )))]
-    {
-        a * b + c
-    }
+    a * b + c
 }
 
+/// All floats with an absolute value greater than or equal to this are integers.
+#[cfg(not(feature = "std"))]
+const MIN_INTEGRAL_F32: f32 = 8_388_608.0;
+
 #[inline(always)]
 pub(crate) fn floor_f32(x: f32) -> f32 {
     #[cfg(feature = "std")]
@@ -460,12 +447,19 @@ pub(crate) fn floor_f32(x: f32) -> f32 {
     }
     #[cfg(not(feature = "std"))]
     {
+        // Also takes care of NaN and infinite values, which would otherwise
+        // saturate when being converted to an integer.
+        if x.is_nan() || x >= MIN_INTEGRAL_F32 || x <= -MIN_INTEGRAL_F32 {
+            return x;
+        }
+
         let xi = x as i32;
         let xf = xi as f32;
         if x < xf { xf - 1.0 } else { xf }
     }
 }
 
+/// Round half-way cases away from zero, like `f32::round`.
 #[inline(always)]
 pub(crate) fn round_f32(x: f32) -> f32 {
     #[cfg(feature = "std")]
@@ -474,10 +468,16 @@ pub(crate) fn round_f32(x: f32) -> f32 {
     }
     #[cfg(not(feature = "std"))]
     {
-        if x >= 0.0 {
-            floor_f32(x + 0.5)
+        // Note that `floor_f32(x + 0.5)` would be wrong for the largest float
+        // below 0.5, since the addition would round up to 1.0. Subtracting the
+        // floor, on the other hand, is always exact.
+        let floor = floor_f32(x);
+        let fraction = x - floor;
+
+        if fraction > 0.5 || (fraction == 0.5 && x > 0.0) {
+            floor + 1.0
         } else {
-            -floor_f32(-x + 0.5)
+            floor
         }
     }
 }
@@ -179,6 +179,8 @@ struct ManifestEntry {
     resolve_palette_indices: Option<bool>,
     #[serde(default)]
     target_resolution: Option<(u32, u32)>,
+    #[serde(default)]
+    unknown_colorspace_passthrough: Option<bool>,
 }
 
 struct AssetEntry {
@@ -216,15 +218,17 @@ impl ManifestItem {
         match self {
             Self::Simple(id) => AssetEntry::new(namespace, id.clone(), id, true, default_settings),
             Self::Detailed(entry) => {
-                let decode_settings = DecodeSettings {
-                    resolve_palette_indices: entry
-                        .resolve_palette_indices
-                        .unwrap_or(default_settings.resolve_palette_indices),
-                    strict: entry.strict.unwrap_or(default_settings.strict),
-                    target_resolution: entry
-                        .target_resolution
-                        .or(default_settings.target_resolution),
-                };
+                let mut decode_settings = default_settings;
+                decode_settings.resolve_palette_indices = entry
+                    .resolve_palette_indices
+                    .unwrap_or(default_settings.resolve_palette_indices);
+                decode_settings.strict = entry.strict.unwrap_or(default_settings.strict);
+                decode_settings.target_resolution = entry
+                    .target_resolution
+                    .or(default_settings.target_resolution);
+                decode_settings.unknown_colorspace_passthrough = entry
+                    .unknown_colorspace_passthrough
+                    .unwrap_or(default_settings.unknown_colorspace_passthrough);
                 AssetEntry::new(
                     namespace,
                     entry.id,
@@ -0,0 +1,50 @@
+//! Check that decoding produces exactly the same output regardless of the
+//! enabled features and the SIMD level of the CPU.
+//!
+//! The expected hashes are the same for all configurations, so running this
+//! test both with and without the `simd` feature compares the two code paths.
+//! The images are taken from `stream_jpx_3.pdf` and `stream_jpx_6.pdf` of the
+//! `hayro-tests` crate.
+
+#![allow(missing_docs)]
+
+use hayro_jpeg2000::{DecodeSettings, DecoderContext, Image};
+
+/// Return the FNV-1a hash of the decoded samples and of the 8-bit output.
+fn decode_hash(data: &[u8]) -> (u64, u64) {
+    fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+        bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    let image = Image::new(data, &DecodeSettings::default()).unwrap();
+    let mut context = DecoderContext::default();
+    let decoded = image.decode(&mut context).unwrap();
+
+    let samples = fnv1a(
+        decoded
+            .components()
+            .iter()
+            .flat_map(|c| c.samples())
+            .flat_map(|s| s.to_bits().to_le_bytes()),
+    );
+    let output = fnv1a(decoded.data_u8());
+
+    (samples, output)
+}
+
+// A 105x54 RGB image that uses the irreversible 9-7 wavelet transform and the
+// irreversible color transform.
+#[test]
+fn irreversible_rgb() {
+    let hash = decode_hash(include_bytes!("fixtures/irreversible_rgb.jp2"));
+    assert_eq!(hash, (15002676521031551478, 13666924924204623038));
+}
+
+// A 341x392 grayscale image that uses the reversible 5-3 wavelet transform.
+#[test]
+fn reversible_gray() {
+    let hash = decode_hash(include_bytes!("fixtures/reversible_gray.jp2"));
+    assert_eq!(hash, (11385088101352450549, 8885959489468252531));
+}
@@ -60,6 +60,15 @@ impl<'a> Scanner<'a> {
         object::read(&mut self.reader)
     }
 
+    /// Parse the next object without advancing the scanner.
+    pub fn peek_object(&mut self) -> Result<Object<'a>> {
+        let offset = self.reader.offset();
+        let object = self.parse_object();
+        self.reader.jump(offset);
+
+        object
+    }
+
     /// Parse the next object as a [`Number`].
     pub fn parse_number(&mut self) -> Result<Number> {
         match self.parse_object()? {
@@ -171,6 +180,34 @@ endcmap"#;
         assert!(scanner.at_end());
     }
 
+    #[test]
+    fn peek_object() {
+        let mut scanner = Scanner::new(b"1 begincidrange <00> <FF> 0 endcidrange");
+
+        assert_eq!(scanner.parse_number().unwrap(), Number::Integer(1));
+        assert_eq!(
+            scanner.peek_object().unwrap(),
+            Object::Name(Name::new(b"begincidrange", false))
+        );
+        assert_eq!(
+            scanner.peek_object().unwrap(),
+            Object::Name(Name::new(b"begincidrange", false))
+        );
+        assert_eq!(
+            scanner.parse_name().unwrap(),
+            Name::new(b"begincidrange", false)
+        );
+        assert_eq!(scanner.parse_string().unwrap(), String::from_hex(b"00"));
+    }
+
+    #[test]
+    fn peek_object_error() {
+        let mut scanner = Scanner::new(b"{ 42");
+
+        assert!(scanner.peek_object().is_err());
+        assert!(!scanner.at_end());
+    }
+
     #[test]
     fn wrong_type_is_error() {
         let mut scanner = Scanner::new(b"42 ");
@@ -5,7 +5,7 @@ mod literal;
 use alloc::vec::Vec;
 
 use crate::error::{Error, Result};
-use crate::reader::Reader;
+use crate::reader::{Reader, is_whitespace};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum StringInner<'a> {
@@ -50,6 +50,17 @@ impl<'a> String<'a> {
         .ok_or(Error::SyntaxError)
     }
 
+    /// Return the number of hex digits if this is a hex string.
+    ///
+    /// This allows callers to detect hex strings with an odd number of digits,
+    /// whose last digit is padded with zero when decoding.
+    pub fn hex_digits(&self) -> Option<usize> {
+        match self.inner {
+            StringInner::Hex(data) => Some(data.iter().filter(|&&b| !is_whitespace(b)).count()),
+            _ => None,
+        }
+    }
+
     /// Decode the string content.
     pub fn decode(&self) -> Result<Vec<u8>> {
         let mut out = Vec::new();
@@ -238,6 +249,14 @@ mod tests {
         assert_eq!(decode_hex(b"<ABC>").unwrap(), &[0xAB, 0xC0]);
     }
 
+    #[test]
+    fn hex_digit_count() {
+        let mut r = Reader::new(b"<AB C>");
+        let s = String::from_hex(parse_hex(&mut r).unwrap());
+        assert_eq!(s.hex_digits(), Some(3));
+        assert_eq!(String::from_literal(b"ABC").hex_digits(), None);
+    }
+
     #[test]
     fn hex_empty() {
         assert_eq!(decode_hex(b"<>").unwrap(), b"");
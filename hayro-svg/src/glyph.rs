@@ -1,6 +1,6 @@
-use crate::SvgRenderer;
 use crate::hash128;
 use crate::path::BezPathExt;
+use crate::{Container, MaskReuse, SvgRenderer};
 use hayro_interpret::font::{Glyph, Type3Glyph};
 use hayro_interpret::{CacheKey, DrawMode, DrawProps, Paint};
 use kurbo::{Affine, BezPath, Shape};
@@ -16,6 +16,7 @@ pub(crate) struct CachedType3Glyph<'a> {
     transform: Affine,
     glyph_transform: Affine,
     paint: Paint<'a>,
+    container: Container,
 }
 
 impl<'a> SvgRenderer<'a> {
@@ -96,13 +97,18 @@ impl<'a> SvgRenderer<'a> {
                 self.xml.end_element();
             }
             Glyph::Type3(t) => {
-                let cache_key = hash128(&(
+                let mut cache_key = hash128(&(
                     t.cache_key(),
                     props.transform.cache_key(),
                     glyph_transform.cache_key(),
                     props.paint.cache_key(),
                 ));
 
+                // Masks in the glyph depend on the coordinate system the glyph is used in.
+                if self.render_settings.mask_reuse == MaskReuse::PerUse {
+                    cache_key = hash128(&(cache_key, self.container.transform.cache_key()));
+                }
+
                 if !self.type3_glyphs.contains(cache_key) {
                     self.with_dummy(|r| {
                         t.interpret(r, props.transform, glyph_transform, &props.paint);
@@ -117,6 +123,7 @@ impl<'a> SvgRenderer<'a> {
                         transform: props.transform,
                         glyph_transform,
                         paint: props.paint.clone(),
+                        container: self.container,
                     });
 
                 self.xml.start_element("use");
@@ -150,9 +157,11 @@ impl<'a> SvgRenderer<'a> {
             for (id, glyph) in type3_glyphs.iter() {
                 self.xml.start_element("g");
                 self.xml.write_attribute("id", &id);
-                glyph
-                    .glyph
-                    .interpret(self, glyph.transform, glyph.glyph_transform, &glyph.paint);
+                self.with_container(glyph.container, |r| {
+                    glyph
+                        .glyph
+                        .interpret(r, glyph.transform, glyph.glyph_transform, &glyph.paint);
+                });
                 self.xml.end_element();
             }
 
@@ -3,7 +3,7 @@ use crate::SvgRenderer;
 use crate::mask::{ImageLuminanceMask, MaskKind};
 use base64::Engine;
 use hayro_interpret::{
-    BlendMode, Device, DrawMode, DrawProps, FillRule, ImageData, LumaData, Paint,
+    BlendMode, Device, DeviceParams, DrawMode, DrawProps, FillRule, ImageData, LumaData, Paint,
 };
 use image::{DynamicImage, ImageBuffer, ImageFormat};
 use kurbo::{Affine, Rect, Shape};
@@ -13,16 +13,16 @@ use std::sync::Arc;
 impl<'a> SvgRenderer<'a> {
     pub(crate) fn draw_rgba_image(
         &mut self,
-        image_data: ImageData,
+        image_data: &ImageData,
         transform: Affine,
-        alpha: Option<LumaData>,
+        alpha: Option<&LumaData>,
     ) {
         let (rgb_data, width, height, interpolate) = match image_data {
             ImageData::Rgb(rgb) => {
                 let w = rgb.width;
                 let h = rgb.height;
                 let i = rgb.interpolate;
-                (rgb.data, w, h, i)
+                (rgb.data.clone(), w, h, i)
             }
             // TODO: Store as gray-scale.
             ImageData::Luma(luma) => {
@@ -42,8 +42,8 @@ impl<'a> SvgRenderer<'a> {
             if alpha.interpolate == interpolate && alpha.width == width && alpha.height == height {
                 let interleaved = rgb_data
                     .chunks(3)
-                    .zip(alpha.data)
-                    .flat_map(|(rgb, a)| [rgb[0], rgb[1], rgb[2], a])
+                    .zip(&alpha.data)
+                    .flat_map(|(rgb, a)| [rgb[0], rgb[1], rgb[2], *a])
                     .collect::<Vec<u8>>();
 
                 let image = DynamicImage::ImageRgba8(
@@ -58,7 +58,8 @@ impl<'a> SvgRenderer<'a> {
 
                 let alpha = {
                     let image = DynamicImage::ImageLuma8(
-                        ImageBuffer::from_raw(alpha.width, alpha.height, alpha.data).unwrap(),
+                        ImageBuffer::from_raw(alpha.width, alpha.height, alpha.data.clone())
+                            .unwrap(),
                     );
 
                     let transform = transform
@@ -93,7 +94,7 @@ impl<'a> SvgRenderer<'a> {
 
     pub(crate) fn draw_stencil_image(
         &mut self,
-        stencil: LumaData,
+        stencil: &LumaData,
         transform: Affine,
         paint: &Paint<'a>,
     ) {
@@ -117,7 +118,8 @@ impl<'a> SvgRenderer<'a> {
             Paint::Pattern(_) => {
                 let mask = {
                     let image = DynamicImage::ImageLuma8(
-                        ImageBuffer::from_raw(stencil.width, stencil.height, stencil.data).unwrap(),
+                        ImageBuffer::from_raw(stencil.width, stencil.height, stencil.data.clone())
+                            .unwrap(),
                     );
 
                     ImageLuminanceMask {
@@ -139,6 +141,8 @@ impl<'a> SvgRenderer<'a> {
                         paint: paint.clone(),
                         soft_mask: None,
                         blend_mode: BlendMode::Normal,
+                        overprint: false,
+                        device_params: DeviceParams::default(),
                     },
                     &DrawMode::Fill(FillRule::NonZero),
                 );
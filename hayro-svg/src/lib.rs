@@ -5,12 +5,19 @@ This is the pendant to [`hayro`](https://crates.io/crates/hayro), but allows you
 SVG instead of bitmap images. See the description of that crate for more information on the
 supported features and limitations.
 
+## Browser compatibility
+Safari resolves a `<mask>` in the coordinate system of the first element that uses it, which
+breaks masks that are shared between differently transformed elements, for example soft masks
+inside of tiling patterns. If the output needs to be rendered correctly in Safari, set
+`SvgRenderSettings::mask_reuse` to `MaskReuse::PerUse`, at the cost of larger files.
+
 ## Safety
 This crate forbids unsafe code via a crate-level attribute.
 
 ## Cargo features
-This crate has one optional feature:
+This crate has the following optional features:
 - `embed-fonts`: See the description of [`hayro-interpret`](https://docs.rs/hayro-interpret/latest/hayro_interpret/#cargo-features) for more information.
+- `images` (enabled by default): See the description of [`hayro-interpret`](https://docs.rs/hayro-interpret/latest/hayro_interpret/#cargo-features) for more information.
 */
 
 #![forbid(unsafe_code)]
@@ -18,12 +25,12 @@ This crate has one optional feature:
 
 use crate::clip::CachedClipPath;
 use crate::glyph::{CachedOutlineGlyph, CachedType3Glyph};
-use crate::mask::MaskKind;
+use crate::mask::{CachedMask, MaskKind};
 use crate::paint::{
     CachedNativeGradient, CachedShading, CachedShadingPattern, CachedTilingPattern,
 };
 use hayro_interpret::font::Glyph;
-use hayro_interpret::hayro_syntax::page::Page;
+use hayro_interpret::hayro_syntax::page::{Page, Rotation};
 use hayro_interpret::util::{Float32Ext, TransformExt};
 use hayro_interpret::{
     BlendMode, CacheKey, ClipPath, Context, Device, DrawMode, DrawProps, Image, ImageDrawProps,
@@ -72,20 +79,18 @@ pub fn convert<'a>(
     interpreter_settings: &InterpreterSettings,
     render_settings: &SvgRenderSettings,
 ) -> String {
+    let extra_rotation = render_settings.extra_rotation;
+    let (width, height) = page.render_dimensions_with_rotation(extra_rotation);
     let mut state = Context::new(
-        page.initial_transform(true).to_kurbo(),
-        Rect::new(
-            0.0,
-            0.0,
-            page.render_dimensions().0 as f64,
-            page.render_dimensions().1 as f64,
-        ),
+        page.initial_transform_with_rotation(true, extra_rotation)
+            .to_kurbo(),
+        Rect::new(0.0, 0.0, width as f64, height as f64),
         &cache.interpreter_cache,
         page.xref(),
         interpreter_settings.clone(),
     );
-    let mut device = SvgRenderer::new(page, render_settings.clone());
-    device.write_header(page.render_dimensions());
+    let mut device = SvgRenderer::new((width, height), render_settings.clone());
+    device.write_header((width, height));
 
     interpret_page(page, &mut state, &mut device);
 
@@ -94,10 +99,19 @@ pub fn convert<'a>(
 
 /// Settings to apply during SVG rendering.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct SvgRenderSettings {
     /// The background color in format [red, green, blue, alpha].
     /// Determines the background color of the generated SVG root element.
     pub bg_color: [u8; 4],
+    /// How `<mask>` elements are shared between the groups that use them.
+    pub mask_reuse: MaskReuse,
+    /// An additional rotation that is applied on top of the `/Rotate` entry of
+    /// the page.
+    ///
+    /// For rotations by 90 or 270 degrees, the width and height of the SVG are
+    /// swapped.
+    pub extra_rotation: Rotation,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -105,23 +119,55 @@ impl Default for SvgRenderSettings {
     fn default() -> Self {
         Self {
             bg_color: [0, 0, 0, 0],
+            mask_reuse: MaskReuse::default(),
+            extra_rotation: Rotation::None,
         }
     }
 }
 
+/// How `<mask>` elements are shared between the groups that use them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaskReuse {
+    /// Emit each distinct mask once, and reference it from all groups using it,
+    /// even if they live in differently transformed coordinate systems (for
+    /// example inside of tiling patterns).
+    ///
+    /// This leads to the smallest files, but Safari resolves a mask in the
+    /// coordinate system of the first group using it, so it is rendered
+    /// incorrectly for all other groups.
+    #[default]
+    Shared,
+    /// Emit a separate mask for each coordinate system the mask is used in, with
+    /// the mask region and content units set explicitly in that coordinate
+    /// system. This trades file size for correct rendering in Safari.
+    PerUse,
+}
+
 pub(crate) struct SvgRenderer<'a> {
     pub(crate) render_settings: SvgRenderSettings,
     pub(crate) xml: XmlWriter,
     pub(crate) outline_glyphs: Deduplicator<CachedOutlineGlyph>,
     pub(crate) type3_glyphs: Deduplicator<CachedType3Glyph<'a>>,
     pub(crate) clip_paths: Deduplicator<CachedClipPath>,
-    pub(crate) masks: Deduplicator<MaskKind<'a>>,
+    pub(crate) masks: Deduplicator<CachedMask<'a>>,
     pub(crate) shadings: Deduplicator<CachedShading>,
     pub(crate) gradients: Deduplicator<CachedNativeGradient>,
     pub(crate) shading_patterns: Deduplicator<CachedShadingPattern>,
     pub(crate) tiling_patterns: Deduplicator<CachedTilingPattern<'a>>,
     active_clips: Vec<Id>,
     pub(crate) dimensions: (f32, f32),
+    pub(crate) container: Container,
+}
+
+/// The coordinate system of the content that is currently being written.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Container {
+    /// The transform to the coordinate system of the SVG root element, which is
+    /// only not the identity inside of tiling patterns.
+    pub(crate) transform: Affine,
+    /// The area in which content is visible, i.e. the viewport or the tile of
+    /// a tiling pattern.
+    pub(crate) region: Rect,
 }
 
 impl<'a> SvgRenderer<'a> {
@@ -364,7 +410,7 @@ impl<'a> Device<'a> for SvgRenderer<'a> {
 }
 
 impl<'a> SvgRenderer<'a> {
-    pub(crate) fn new(page: &'a Page<'a>, render_settings: SvgRenderSettings) -> Self {
+    pub(crate) fn new(dimensions: (f32, f32), render_settings: SvgRenderSettings) -> Self {
         Self {
             render_settings,
             xml: XmlWriter::new(Options::default()),
@@ -377,7 +423,11 @@ impl<'a> SvgRenderer<'a> {
             shading_patterns: Deduplicator::new('v'),
             tiling_patterns: Deduplicator::new('t'),
             active_clips: Vec::new(),
-            dimensions: page.render_dimensions(),
+            dimensions,
+            container: Container {
+                transform: Affine::IDENTITY,
+                region: Rect::new(0.0, 0.0, dimensions.0 as f64, dimensions.1 as f64),
+            },
         }
     }
 
@@ -417,6 +467,14 @@ impl<'a> SvgRenderer<'a> {
         std::mem::swap(&mut self.xml, &mut old_xml);
     }
 
+    /// Run the closure while writing content in the coordinate system of the given
+    /// container.
+    pub(crate) fn with_container(&mut self, container: Container, f: impl FnOnce(&mut Self)) {
+        let old = std::mem::replace(&mut self.container, container);
+        f(self);
+        self.container = old;
+    }
+
     pub(crate) fn finish(mut self) -> String {
         self.write_glyph_defs();
         self.write_mask_defs();
@@ -1,7 +1,8 @@
-use crate::{Id, SvgRenderer, hash128};
+use crate::{Container, Id, MaskReuse, SvgRenderer, hash128};
 use hayro_interpret::color::AlphaColor;
 use hayro_interpret::{
-    BlendMode, CacheKey, DrawMode, DrawProps, FillRule, MaskType, Paint, SoftMask, TransferFunction,
+    BlendMode, CacheKey, DeviceParams, DrawMode, DrawProps, FillRule, MaskType, Paint, SoftMask,
+    TransferFunction,
 };
 use image::DynamicImage;
 use kurbo::{Affine, Rect, Shape};
@@ -19,31 +20,51 @@ pub(crate) enum MaskKind<'a> {
     Image(Arc<ImageLuminanceMask>),
 }
 
+#[derive(Clone)]
+pub(crate) struct CachedMask<'a> {
+    kind: MaskKind<'a>,
+    container: Container,
+    // Whether the mask is only used in the coordinate system of its container.
+    per_use: bool,
+}
+
 impl<'a> SvgRenderer<'a> {
     pub(crate) fn get_mask_id(&mut self, mask: MaskKind<'a>) -> Id {
-        match mask {
-            MaskKind::SoftMask(mask) => {
-                let cache_key = mask.cache_key();
+        let mask_key = match &mask {
+            MaskKind::SoftMask(mask) => mask.cache_key(),
+            MaskKind::Image(mask) => hash128(&(
+                mask.interpolate,
+                mask.transform.cache_key(),
+                mask.image.as_bytes(),
+            )),
+        };
+
+        let per_use = self.render_settings.mask_reuse == MaskReuse::PerUse;
+        let cache_key = if per_use {
+            hash128(&(
+                mask_key,
+                self.container.transform.cache_key(),
+                self.container.region.cache_key(),
+            ))
+        } else {
+            mask_key
+        };
+
+        if !self.masks.contains(cache_key)
+            && let MaskKind::SoftMask(mask) = &mask
+        {
+            self.with_dummy(|r| {
+                mask.interpret(r);
+            });
+        }
 
-                if !self.masks.contains(cache_key) {
-                    self.with_dummy(|r| {
-                        mask.interpret(r);
-                    });
-                }
+        let container = self.container;
 
-                self.masks
-                    .insert_with(cache_key, || MaskKind::SoftMask(mask))
-            }
-            MaskKind::Image(mask) => {
-                let cache_key = hash128(&(
-                    mask.interpolate,
-                    mask.transform.cache_key(),
-                    mask.image.as_bytes(),
-                ));
-
-                self.masks.insert_with(cache_key, || MaskKind::Image(mask))
-            }
-        }
+        self.masks.insert_with(cache_key, || CachedMask {
+            kind: mask,
+            container,
+            per_use,
+        })
     }
 
     pub(crate) fn write_mask_defs(&mut self) {
@@ -56,7 +77,9 @@ impl<'a> SvgRenderer<'a> {
 
         let masks = self.masks.clone();
 
-        for (id, mask) in masks.iter() {
+        for (id, cached) in masks.iter() {
+            let mask = &cached.kind;
+
             if let MaskKind::SoftMask(mask) = mask
                 && let Some(transfer_function) = mask.transfer_function()
             {
@@ -71,64 +94,90 @@ impl<'a> SvgRenderer<'a> {
             self.xml.write_attribute("id", &id);
             self.xml.write_attribute("maskUnits", "userSpaceOnUse");
 
-            match mask {
-                MaskKind::SoftMask(mask) => {
-                    let filter_id = mask.transfer_function().map(|_| format!("f{id}"));
-
-                    if mask.mask_type() != MaskType::Luminosity || filter_id.is_some() {
-                        self.xml.write_attribute("mask-type", "alpha");
-                    }
-
-                    if let Some(filter_id) = &filter_id {
-                        self.xml.start_element("g");
-                        self.xml
-                            .write_attribute("filter", &format!("url(#{filter_id})"));
-                    }
-
-                    let bg_color = mask.background_color();
-                    let use_bg = bg_color.to_rgba().to_rgba8() != AlphaColor::BLACK.to_rgba8();
-
-                    if use_bg {
-                        let paint = Paint::Color(bg_color);
-                        self.draw_path(
-                            &Rect::new(
-                                0.0,
-                                0.0,
-                                self.dimensions.0 as f64,
-                                self.dimensions.1 as f64,
-                            )
-                            .to_path(0.1),
-                            DrawProps {
-                                transform: Affine::IDENTITY,
-                                paint,
-                                soft_mask: None,
-                                blend_mode: BlendMode::Normal,
-                            },
-                            &DrawMode::Fill(FillRule::NonZero),
-                        );
-                        self.xml.start_element("g");
-                        self.xml.write_attribute("style", "isolation:isolate");
-                    }
-
-                    mask.interpret(self);
-
-                    if use_bg {
-                        self.xml.end_element();
-                    }
-
-                    if filter_id.is_some() {
-                        self.xml.end_element();
-                    }
-                }
-                MaskKind::Image(i) => self.write_image(&i.image, i.interpolate, None, i.transform),
+            // Since the mask is only used in the coordinate system of its container,
+            // we can make its region match the visible area of the container instead
+            // of relying on the default region, which is relative to the viewport.
+            let region = cached.per_use.then_some(cached.container.region);
+
+            if let Some(region) = region {
+                self.xml
+                    .write_attribute("maskContentUnits", "userSpaceOnUse");
+                self.xml.write_attribute("x", &(region.x0 as f32));
+                self.xml.write_attribute("y", &(region.y0 as f32));
+                self.xml.write_attribute("width", &(region.width() as f32));
+                self.xml
+                    .write_attribute("height", &(region.height() as f32));
             }
 
+            // The content of the mask needs to be written in the same coordinate
+            // system as when registering the mask, so that nested masks resolve to
+            // the same identifiers.
+            self.with_container(cached.container, |r| {
+                r.write_mask_content(&id, mask, region);
+            });
+
             self.xml.end_element();
         }
 
         self.xml.end_element();
     }
 
+    fn write_mask_content(&mut self, id: &Id, mask: &MaskKind<'a>, region: Option<Rect>) {
+        match mask {
+            MaskKind::SoftMask(mask) => {
+                let filter_id = mask.transfer_function().map(|_| format!("f{id}"));
+
+                if mask.mask_type() != MaskType::Luminosity || filter_id.is_some() {
+                    self.xml.write_attribute("mask-type", "alpha");
+                }
+
+                if let Some(filter_id) = &filter_id {
+                    self.xml.start_element("g");
+                    self.xml
+                        .write_attribute("filter", &format!("url(#{filter_id})"));
+                }
+
+                let bg_color = mask.background_color();
+                let use_bg = bg_color.to_rgba().to_rgba8() != AlphaColor::BLACK.to_rgba8();
+
+                if use_bg {
+                    let paint = Paint::Color(bg_color);
+                    let bg_rect = region.unwrap_or(Rect::new(
+                        0.0,
+                        0.0,
+                        self.dimensions.0 as f64,
+                        self.dimensions.1 as f64,
+                    ));
+                    self.draw_path(
+                        &bg_rect.to_path(0.1),
+                        DrawProps {
+                            transform: Affine::IDENTITY,
+                            paint,
+                            soft_mask: None,
+                            blend_mode: BlendMode::Normal,
+                            overprint: false,
+                            device_params: DeviceParams::default(),
+                        },
+                        &DrawMode::Fill(FillRule::NonZero),
+                    );
+                    self.xml.start_element("g");
+                    self.xml.write_attribute("style", "isolation:isolate");
+                }
+
+                mask.interpret(self);
+
+                if use_bg {
+                    self.xml.end_element();
+                }
+
+                if filter_id.is_some() {
+                    self.xml.end_element();
+                }
+            }
+            MaskKind::Image(i) => self.write_image(&i.image, i.interpolate, None, i.transform),
+        }
+    }
+
     fn write_transfer_function_filter(
         &mut self,
         id: &str,
@@ -1,8 +1,8 @@
 use crate::clip::CachedClipPath;
-use crate::{Id, hash128};
+use crate::{Container, Id, MaskReuse, hash128};
 use crate::{SvgRenderer, convert_transform};
 use hayro_interpret::encode::{EncodedShadingPattern, EncodedShadingType};
-use hayro_interpret::gradient::{SvgGradient, SvgGradientKind};
+use hayro_interpret::gradient::{SvgGradient, SvgGradientKind, SvgGradientStop};
 use hayro_interpret::pattern::{Pattern, ShadingPattern, TilingPattern};
 use hayro_interpret::{CacheKey, FillRule, Paint, StrokeProps};
 use image::{DynamicImage, ImageBuffer};
@@ -12,6 +12,8 @@ use kurbo::{Affine, Point, Rect, Shape, Vec2};
 pub(crate) struct CachedTilingPattern<'a> {
     pub(crate) transform: Affine,
     pub(crate) tiling_pattern: TilingPattern<'a>,
+    // The coordinate system of the pattern content.
+    pub(crate) container: Container,
 }
 
 pub(crate) struct CachedShadingPattern {
@@ -175,16 +177,52 @@ impl<'a> SvgRenderer<'a> {
                     }
                     Pattern::Tiling(t) => {
                         let inverse_transform = path_transform.inverse();
-                        let pattern = *t.clone();
-                        let cache_key = (pattern.clone(), inverse_transform).cache_key();
+                        let mut pattern = *t.clone();
+                        let mut basic_bbox = path_bbox();
+
+                        if let Some(stroke_width) = stroke_props.map(|s| s.line_width) {
+                            basic_bbox =
+                                basic_bbox.inflate(stroke_width as f64, stroke_width as f64);
+                        }
+
+                        pattern.clamp_tile_count(inverse_transform, basic_bbox);
+                        let (pattern_transform, tile_scale) =
+                            tiling_pattern_transform(&pattern, inverse_transform);
+                        let container = Container {
+                            transform: self.container.transform * pattern_transform,
+                            region: Rect::new(
+                                0.0,
+                                0.0,
+                                (pattern.x_step.abs() as f64) * tile_scale,
+                                (pattern.y_step.abs() as f64) * tile_scale,
+                            ),
+                        };
+
+                        let mut cache_key =
+                            ((pattern.clone(), pattern.matrix), inverse_transform).cache_key();
+
+                        // Masks in the pattern content depend on the coordinate system
+                        // the pattern is used in.
+                        if self.render_settings.mask_reuse == MaskReuse::PerUse {
+                            cache_key = hash128(&(cache_key, container.transform.cache_key()));
+                        }
 
                         if !self.tiling_patterns.contains(cache_key) {
-                            self.with_dummy(|r| {
-                                t.interpret(
-                                    r,
-                                    Affine::translate((-pattern.bbox.x0, -pattern.bbox.y0)),
-                                    false,
-                                );
+                            // The content needs to be interpreted with the same transform
+                            // as when writing the pattern, so that the same resources are
+                            // registered.
+                            self.with_container(container, |r| {
+                                r.with_dummy(|r| {
+                                    t.interpret(
+                                        r,
+                                        Affine::scale(tile_scale)
+                                            * Affine::translate((
+                                                -pattern.bbox.x0,
+                                                -pattern.bbox.y0,
+                                            )),
+                                        false,
+                                    );
+                                });
                             });
                         }
 
@@ -192,6 +230,7 @@ impl<'a> SvgRenderer<'a> {
                             .insert_with(cache_key, || CachedTilingPattern {
                                 transform: inverse_transform,
                                 tiling_pattern: pattern,
+                                container,
                             })
                     }
                 };
@@ -286,32 +325,36 @@ impl<'a> SvgRenderer<'a> {
 
         for (id, pattern) in patterns {
             let pattern = pattern.clone();
-            let bbox_origin = Affine::translate((
-                pattern.tiling_pattern.bbox.x0,
-                pattern.tiling_pattern.bbox.y0,
-            ));
-            let transform = pattern.transform * pattern.tiling_pattern.matrix * bbox_origin;
+            let (transform, tile_scale) =
+                tiling_pattern_transform(&pattern.tiling_pattern, pattern.transform);
 
             self.xml.start_element("pattern");
             self.xml.write_attribute("id", &id);
             self.xml.write_attribute("patternUnits", "userSpaceOnUse");
-            self.xml
-                .write_attribute("width", &pattern.tiling_pattern.x_step.abs());
-            self.xml
-                .write_attribute("height", &pattern.tiling_pattern.y_step.abs());
+            self.xml.write_attribute(
+                "width",
+                &(pattern.tiling_pattern.x_step.abs() * tile_scale as f32),
+            );
+            self.xml.write_attribute(
+                "height",
+                &(pattern.tiling_pattern.y_step.abs() * tile_scale as f32),
+            );
             self.xml.write_attribute(
                 "patternTransform",
                 &format!("matrix({})", convert_transform(&transform)),
             );
 
-            pattern.tiling_pattern.interpret(
-                self,
-                Affine::translate((
-                    -pattern.tiling_pattern.bbox.x0,
-                    -pattern.tiling_pattern.bbox.y0,
-                )),
-                false,
-            );
+            self.with_container(pattern.container, |r| {
+                pattern.tiling_pattern.interpret(
+                    r,
+                    Affine::scale(tile_scale)
+                        * Affine::translate((
+                            -pattern.tiling_pattern.bbox.x0,
+                            -pattern.tiling_pattern.bbox.y0,
+                        )),
+                    false,
+                );
+            });
 
             self.xml.end_element();
         }
@@ -392,20 +435,32 @@ fn gradient_key(shading_key: u128, gradient: &SvgGradient) -> u128 {
 }
 
 fn write_gradient(xml: &mut xmlwriter::XmlWriter, id: &str, gradient: &SvgGradient) {
+    // Emitting the gradient geometry as-is can produce coordinates and transforms
+    // with extreme magnitudes, which some SVG renderers fail to handle. Because of
+    // this, the geometry is normalized so that the gradient vector (or the end
+    // circle) has unit length, and the magnitude is folded into the transform.
     match &gradient.kind {
         SvgGradientKind::Linear { start, end } => {
+            let vector = *end - *start;
+            let length = vector.hypot();
+            // A degenerate gradient is painted with a single color anyway.
+            let (normalization, end) = if length > 0.0 && length.is_finite() {
+                (
+                    Affine::translate(start.to_vec2())
+                        * Affine::rotate(vector.atan2())
+                        * Affine::scale(length),
+                    Point::new(1.0, 0.0),
+                )
+            } else {
+                (Affine::translate(start.to_vec2()), Point::ZERO)
+            };
+
             xml.start_element("linearGradient");
             xml.write_attribute("id", id);
             xml.write_attribute("gradientUnits", "userSpaceOnUse");
-            xml.write_attribute(
-                "gradientTransform",
-                &format!(
-                    "matrix({})",
-                    convert_transform(&(Affine::translate((-0.5, -0.5)) * gradient.transform))
-                ),
-            );
-            xml.write_attribute("x1", &start.x);
-            xml.write_attribute("y1", &start.y);
+            write_gradient_transform(xml, gradient.transform * normalization);
+            xml.write_attribute("x1", &0.0);
+            xml.write_attribute("y1", &0.0);
             xml.write_attribute("x2", &end.x);
             xml.write_attribute("y2", &end.y);
         }
@@ -415,28 +470,31 @@ fn write_gradient(xml: &mut xmlwriter::XmlWriter, id: &str, gradient: &SvgGradie
             end_center,
             end_radius,
         } => {
+            let scale = start_radius.abs().max(end_radius.abs()) as f64;
+            let scale = if scale > 0.0 && scale.is_finite() {
+                scale
+            } else {
+                1.0
+            };
+            let normalization = Affine::translate(end_center.to_vec2()) * Affine::scale(scale);
+            let focal = normalization.inverse() * *start_center;
+
             xml.start_element("radialGradient");
             xml.write_attribute("id", id);
             xml.write_attribute("gradientUnits", "userSpaceOnUse");
-            xml.write_attribute(
-                "gradientTransform",
-                &format!(
-                    "matrix({})",
-                    convert_transform(&(Affine::translate((-0.5, -0.5)) * gradient.transform))
-                ),
-            );
-            xml.write_attribute("fx", &start_center.x);
-            xml.write_attribute("fy", &start_center.y);
-            xml.write_attribute("fr", &start_radius);
-            xml.write_attribute("cx", &end_center.x);
-            xml.write_attribute("cy", &end_center.y);
-            xml.write_attribute("r", &end_radius);
+            write_gradient_transform(xml, gradient.transform * normalization);
+            xml.write_attribute("fx", &focal.x);
+            xml.write_attribute("fy", &focal.y);
+            xml.write_attribute("fr", &(*start_radius as f64 / scale));
+            xml.write_attribute("cx", &0.0);
+            xml.write_attribute("cy", &0.0);
+            xml.write_attribute("r", &(*end_radius as f64 / scale));
         }
     }
 
     xml.write_attribute("spreadMethod", "pad");
 
-    for stop in &gradient.stops {
+    for stop in normalize_stops(&gradient.stops) {
         xml.start_element("stop");
         xml.write_attribute("offset", &stop.offset);
         xml.write_attribute("stop-color", &rgba_to_hex(stop.color));
@@ -449,6 +507,90 @@ fn write_gradient(xml: &mut xmlwriter::XmlWriter, id: &str, gradient: &SvgGradie
     xml.end_element();
 }
 
+fn write_gradient_transform(xml: &mut xmlwriter::XmlWriter, transform: Affine) {
+    xml.write_attribute(
+        "gradientTransform",
+        &format!(
+            "matrix({})",
+            convert_transform(&(Affine::translate((-0.5, -0.5)) * transform))
+        ),
+    );
+}
+
+/// The minimum distance between the offsets of two consecutive gradient stops.
+const MIN_STOP_DISTANCE: f32 = 1.0e-6;
+
+/// Clamp the offsets of the stops to [0, 1] and make them strictly increasing.
+///
+/// Stops that are closer than [`MIN_STOP_DISTANCE`] to their predecessor are
+/// dropped if they have the same color, and moved slightly otherwise, so that
+/// renderers don't need to guess how to resolve them.
+fn normalize_stops(stops: &[SvgGradientStop]) -> Vec<SvgGradientStop> {
+    let mut normalized: Vec<SvgGradientStop> = Vec::with_capacity(stops.len());
+
+    for stop in stops {
+        let offset = if stop.offset.is_nan() {
+            0.0
+        } else {
+            stop.offset.clamp(0.0, 1.0)
+        };
+
+        let Some(last) = normalized.last_mut() else {
+            normalized.push(SvgGradientStop {
+                offset,
+                color: stop.color,
+            });
+            continue;
+        };
+
+        if offset - last.offset >= MIN_STOP_DISTANCE {
+            normalized.push(SvgGradientStop {
+                offset,
+                color: stop.color,
+            });
+        } else if last.color != stop.color {
+            let offset = last.offset + MIN_STOP_DISTANCE;
+
+            if offset <= 1.0 {
+                normalized.push(SvgGradientStop {
+                    offset,
+                    color: stop.color,
+                });
+            } else {
+                // There is no room left, so the later stop takes precedence, the
+                // same as when two stops have the same offset.
+                last.color = stop.color;
+            }
+        }
+    }
+
+    normalized
+}
+
+/// The range of scale factors a pattern transform may have before it is
+/// considered pathological.
+const PATTERN_SCALE_RANGE: (f64, f64) = (1.0e-2, 1.0e2);
+
+/// Return the `patternTransform` of a tiling pattern, as well as a uniform scale
+/// that should be applied to the tile and its content.
+///
+/// Some SVG renderers fail to draw patterns whose transform has a very large or
+/// very small scale factor, so in that case the scale is moved into the tile.
+fn tiling_pattern_transform(pattern: &TilingPattern<'_>, transform: Affine) -> (Affine, f64) {
+    let bbox_origin = Affine::translate((pattern.bbox.x0, pattern.bbox.y0));
+    let transform = transform * pattern.matrix * bbox_origin;
+    let scale = transform.determinant().abs().sqrt();
+
+    if !scale.is_finite()
+        || scale == 0.0
+        || (PATTERN_SCALE_RANGE.0..=PATTERN_SCALE_RANGE.1).contains(&scale)
+    {
+        return (transform, 1.0);
+    }
+
+    (transform * Affine::scale(1.0 / scale), scale)
+}
+
 fn render_shading_texture(
     bbox: Rect,
     shading_pattern: &EncodedShadingPattern,
@@ -0,0 +1,105 @@
+//! Inline images.
+
+use crate::image;
+use crate::object::dict::keys::*;
+use crate::object::stream::{DecodeFailure, FilterResult};
+use crate::object::{Dict, Object, Stream};
+use crate::page::Resources;
+use alloc::borrow::Cow;
+
+/// An image that is embedded directly into a content stream, using the
+/// `BI`, `ID` and `EI` operators.
+#[derive(Clone, Debug)]
+pub struct InlineImage<'a> {
+    // The stream with the normalized dictionary.
+    stream: Stream<'a>,
+}
+
+impl<'a> InlineImage<'a> {
+    pub(crate) fn new(stream: &Stream<'a>) -> Self {
+        let dict = stream.dict().rename_keys(full_key_name);
+
+        Self {
+            stream: stream.with_dict(dict),
+        }
+    }
+
+    /// Return the parameter dictionary of the image.
+    ///
+    /// Abbreviated keys are replaced by their full names, so that for example
+    /// the bits per component can be queried using `BitsPerComponent` instead of
+    /// `BPC`. Note that values are returned as is, so they can still contain
+    /// abbreviated filter or color space names. The latter can be resolved using
+    /// [`InlineImage::color_space`].
+    pub fn dict(&self) -> &Dict<'a> {
+        self.stream.dict()
+    }
+
+    /// Return the raw image data, i.e. the bytes between `ID` and `EI` without
+    /// any filters applied.
+    pub fn raw_data(&self) -> Cow<'a, [u8]> {
+        self.stream.raw_data()
+    }
+
+    /// Return the width of the image.
+    pub fn width(&self) -> Option<u32> {
+        self.dict().get::<u32>(WIDTH)
+    }
+
+    /// Return the height of the image.
+    pub fn height(&self) -> Option<u32> {
+        self.dict().get::<u32>(HEIGHT)
+    }
+
+    /// Return the bits per component of the image.
+    pub fn bits_per_component(&self) -> Option<u8> {
+        self.dict().get::<u8>(BITS_PER_COMPONENT)
+    }
+
+    /// Return whether the image is a stencil mask.
+    pub fn is_image_mask(&self) -> bool {
+        self.dict().get::<bool>(IMAGE_MASK).unwrap_or(false)
+    }
+
+    /// Return the color space of the image.
+    ///
+    /// Abbreviated names of device color spaces are replaced by their full
+    /// names, and other names are looked up in the color spaces of the given
+    /// resources. Color space arrays are returned as is.
+    pub fn color_space(&self, resources: &Resources<'a>) -> Option<Object<'a>> {
+        image::color_space(self.dict(), resources)
+    }
+
+    /// Return the decoded data of the image.
+    ///
+    /// This applies the same filters as for image `XObject`s, with the
+    /// parameters (like the dimensions and the number of components) taken from
+    /// the image dictionary. Named color spaces are looked up in the given
+    /// resources, which should be the ones of the content stream containing the
+    /// image.
+    pub fn decoded(&self, resources: &Resources<'a>) -> Result<FilterResult<'a>, DecodeFailure> {
+        image::decode(&self.stream, resources)
+    }
+
+    pub(crate) fn stream(&self) -> &Stream<'a> {
+        &self.stream
+    }
+}
+
+fn full_key_name(key: &[u8]) -> Option<&'static [u8]> {
+    let name = match key {
+        BPC => BITS_PER_COMPONENT,
+        CS => COLORSPACE,
+        D => DECODE,
+        DP => DECODE_PARMS,
+        F => FILTER,
+        H => HEIGHT,
+        IM => IMAGE_MASK,
+        I => INTERPOLATE,
+        L => LENGTH,
+        W => WIDTH,
+        _ => return None,
+    };
+
+    Some(name)
+}
@@ -30,6 +30,7 @@ assert!(matches!(iter.next(), Some(TypedInstruction::FillPathNonZero(_))));
 ```
 */
 
+pub mod inline_image;
 #[allow(missing_docs)]
 pub mod ops;
 
@@ -91,12 +92,31 @@ impl<'a> Readable<'a> for Operator<'a> {
     }
 }
 
+/// The location of an instruction in the content stream it was read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    /// The index of the content stream.
+    ///
+    /// The contents of a page can be split into multiple streams, in which case
+    /// this is the index of the stream in the `Contents` array of the page.
+    /// Otherwise, it is always 0.
+    pub stream: usize,
+    /// The byte offset of the start of the instruction (i.e. its first
+    /// operand, or the operator if there are none) in the decoded stream.
+    pub offset: usize,
+}
+
 /// An iterator over operators in the PDF content streams, providing raw access to the instructions.
 #[derive(Clone)]
 pub struct UntypedIter<'a> {
     reader: Reader<'a>,
     stack: Stack<'a>,
     operator: Option<Operator<'a>>,
+    /// The offset of the start of the last instruction.
+    instruction_start: usize,
+    /// The offsets at which the individual streams start, in case the data
+    /// consists of multiple concatenated streams.
+    stream_starts: &'a [usize],
 }
 
 impl<'a> UntypedIter<'a> {
@@ -106,27 +126,88 @@ impl<'a> UntypedIter<'a> {
             reader: Reader::new(data),
             stack: Stack::new(),
             operator: None,
+            instruction_start: 0,
+            stream_starts: &[],
         }
     }
 
     /// Create a new empty untyped iterator.
     pub fn empty() -> Self {
-        Self {
-            reader: Reader::new(&[]),
-            stack: Stack::new(),
-            operator: None,
+        Self::new(&[])
+    }
+
+    /// Set the offsets at which the concatenated streams in the data start.
+    pub(crate) fn with_stream_starts(mut self, stream_starts: &'a [usize]) -> Self {
+        self.stream_starts = stream_starts;
+        self
+    }
+
+    /// Return the current byte offset in the content stream.
+    ///
+    /// Together with [`UntypedIter::next`], this can be used to determine the
+    /// raw byte range of an instruction.
+    pub fn offset(&self) -> usize {
+        self.reader.offset()
+    }
+
+    /// Return the location of the last instruction that was returned by
+    /// [`UntypedIter::next`].
+    pub fn location(&self) -> SourceLocation {
+        // Only pages with multiple content streams have stream starts, so
+        // there is nothing to look up in the common case.
+        if self.stream_starts.len() <= 1 {
+            return SourceLocation {
+                stream: 0,
+                offset: self.instruction_start,
+            };
+        }
+
+        let stream = self
+            .stream_starts
+            .partition_point(|&start| start <= self.instruction_start)
+            .saturating_sub(1);
+
+        SourceLocation {
+            stream,
+            offset: self.instruction_start - self.stream_starts[stream],
         }
     }
 
     /// Return the next instruction.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<Instruction<'_, 'a>> {
+        self.advance()?;
+
+        Some(self.instruction())
+    }
+
+    /// Return the next instruction together with its location.
+    pub fn next_with_location(&mut self) -> Option<(Instruction<'_, 'a>, SourceLocation)> {
+        self.advance()?;
+        let location = self.location();
+
+        Some((self.instruction(), location))
+    }
+
+    fn instruction(&self) -> Instruction<'_, 'a> {
+        Instruction {
+            operands: &self.stack,
+            operator: self.operator.as_ref().unwrap(),
+        }
+    }
+
+    /// Read the next instruction into `stack` and `operator`.
+    fn advance(&mut self) -> Option<()> {
         self.stack.clear();
         self.operator = None;
 
         self.reader.skip_white_spaces_and_comments();
 
         while !self.reader.at_end() {
+            if self.stack.len() == 0 {
+                self.instruction_start = self.reader.offset();
+            }
+
             // I believe booleans/null never appear as an operator?
             if matches!(
                 self.reader.peek_byte()?,
@@ -295,10 +376,7 @@ impl<'a> UntypedIter<'a> {
                 }
 
                 self.operator = Some(operator);
-                return Some(Instruction {
-                    operands: &self.stack,
-                    operator: self.operator.as_ref().unwrap(),
-                });
+                return Some(());
             }
 
             self.reader.skip_white_spaces_and_comments();
@@ -327,10 +405,28 @@ impl<'a> TypedIter<'a> {
         Self { untyped }
     }
 
+    /// Return the location of the last instruction that was returned by
+    /// [`TypedIter::next`].
+    pub fn location(&self) -> SourceLocation {
+        self.untyped.location()
+    }
+
     /// Return the next typed instruction.
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<TypedInstruction<'_, 'a>> {
         let op = self.untyped.next()?;
+
+        Self::convert(op)
+    }
+
+    /// Return the next typed instruction together with its location.
+    pub fn next_with_location(&mut self) -> Option<(TypedInstruction<'_, 'a>, SourceLocation)> {
+        let (op, location) = self.untyped.next_with_location()?;
+
+        Some((Self::convert(op)?, location))
+    }
+
+    fn convert<'b>(op: Instruction<'b, 'a>) -> Option<TypedInstruction<'b, 'a>> {
         // TODO: Explore whether dispatching can be made more efficient.
         match TypedInstruction::dispatch(&op) {
             Some(op) => Some(op),
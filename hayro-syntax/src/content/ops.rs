@@ -1,5 +1,6 @@
 //! Content stream operators.
 
+use crate::content::inline_image;
 use crate::content::{Instruction, OPERANDS_THRESHOLD, OperatorTrait, Stack};
 use crate::object;
 use crate::object::Array;
@@ -68,6 +69,14 @@ op_impl!(
     }
 );
 
+impl<'a> InlineImage<'_, 'a> {
+    /// Return the inline image, which provides access to its (normalized)
+    /// parameters and its data.
+    pub fn image(&self) -> inline_image::InlineImage<'a> {
+        inline_image::InlineImage::new(self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::content::TypedIter;
@@ -21,7 +21,7 @@ use core::cmp;
 use core::ops::Deref;
 
 mod aes;
-mod md5;
+pub(crate) mod md5;
 mod rc4;
 mod sha256;
 mod sha384;
@@ -1,6 +1,7 @@
 use crate::object::Dict;
 use crate::object::dict::keys::{
-    BLACK_IS_1, COLUMNS, ENCODED_BYTE_ALIGN, END_OF_BLOCK, END_OF_LINE, K, ROWS,
+    BLACK_IS_1, COLUMNS, DAMAGED_ROWS_BEFORE_ERROR, ENCODED_BYTE_ALIGN, END_OF_BLOCK, END_OF_LINE,
+    K, ROWS,
 };
 use crate::object::stream::{FilterResult, ImageColorSpace, ImageData, ImageDecodeParams};
 use alloc::borrow::Cow;
@@ -21,21 +22,21 @@ pub(crate) fn decode(
     let output_len = (columns as usize).checked_mul(rows as usize)?;
     let end_of_block = params.get::<bool>(END_OF_BLOCK).unwrap_or(true);
 
-    let settings = DecodeSettings {
-        columns,
-        rows,
-        end_of_block,
-        end_of_line: params.get::<bool>(END_OF_LINE).unwrap_or(false),
-        rows_are_byte_aligned: params.get::<bool>(ENCODED_BYTE_ALIGN).unwrap_or(false),
-        encoding: if k < 0 {
-            EncodingMode::Group4
-        } else if k == 0 {
-            EncodingMode::Group3_1D
-        } else {
-            EncodingMode::Group3_2D { k: k as u32 }
-        },
-        invert_black: params.get::<bool>(BLACK_IS_1).unwrap_or(false),
+    let mut settings = DecodeSettings::default();
+    settings.columns = columns;
+    settings.rows = rows;
+    settings.end_of_block = end_of_block;
+    settings.end_of_line = params.get::<bool>(END_OF_LINE).unwrap_or(false);
+    settings.rows_are_byte_aligned = params.get::<bool>(ENCODED_BYTE_ALIGN).unwrap_or(false);
+    settings.encoding = if k < 0 {
+        EncodingMode::Group4
+    } else if k == 0 {
+        EncodingMode::Group3_1D
+    } else {
+        EncodingMode::Group3_2D { k: k as u32 }
     };
+    settings.invert_black = params.get::<bool>(BLACK_IS_1).unwrap_or(false);
+    settings.damaged_rows_before_error = params.get::<u32>(DAMAGED_ROWS_BEFORE_ERROR).unwrap_or(0);
 
     // Whenever possible (if we don't have an indexed color space), we convert
     // the data as 8-bit instead of 1-bit, so that it can be easier converted
@@ -173,6 +174,12 @@ mod tests {
     use crate::object::FromBytes;
     use crate::object::dict::Dict;
     use crate::object::stream::ImageDecodeParams;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::iter;
+    use hayro_ccitt::{DamagedRowFill, DecodeSettings, Decoder, DecoderContext, EncodingMode};
 
     #[test]
     fn issue1258() {
@@ -183,4 +190,182 @@ mod tests {
         assert_eq!(decoded.data.as_ref(), &[0; 8]);
         assert_eq!(decoded.image_data.unwrap().height, 1);
     }
+
+    fn decode_aligned_with_eol(data: &[u8]) -> Vec<u8> {
+        let params = Dict::from_bytes(
+            b"<< /K 0 /Columns 8 /Rows 3 /EncodedByteAlign true /EndOfLine true >>",
+        )
+        .unwrap();
+
+        decode(data, &params, &ImageDecodeParams::default())
+            .unwrap()
+            .data
+            .to_vec()
+    }
+
+    fn expected_rows() -> Vec<u8> {
+        [[255; 8], [255, 255, 255, 255, 0, 0, 0, 0], [0; 8]].concat()
+    }
+
+    #[test]
+    fn byte_aligned_padding_after_eol() {
+        // Each row is preceded by an EOL that is padded to the byte boundary.
+        let data = [0x00, 0x10, 0x98, 0x00, 0x80, 0xB6, 0x00, 0x20, 0x35, 0x14];
+
+        assert_eq!(decode_aligned_with_eol(&data), expected_rows());
+    }
+
+    #[test]
+    fn byte_aligned_fill_before_eol() {
+        // Each row is preceded by fill bits and an EOL that ends at the byte boundary.
+        let data = [0x00, 0x01, 0x98, 0x00, 0x01, 0xB6, 0x00, 0x01, 0x35, 0x14];
+
+        assert_eq!(decode_aligned_with_eol(&data), expected_rows());
+    }
+
+    /// Pack a string of `0` and `1` characters into bytes, ignoring whitespace.
+    fn pack_bits(bits: &str) -> Vec<u8> {
+        let bits = bits
+            .bytes()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect::<Vec<_>>();
+
+        bits.chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, b)| byte | ((b - b'0') << (7 - i)))
+            })
+            .collect()
+    }
+
+    const EOL: &str = "000000000001";
+    // 4 white and 4 black pixels.
+    const ROW_HALF: &str = "1011 011";
+    // 8 white pixels.
+    const ROW_WHITE: &str = "10011";
+    // 0 white and 8 black pixels.
+    const ROW_BLACK: &str = "00110101 000101";
+    // 4 white pixels, followed by an invalid code.
+    const ROW_DAMAGED: &str = "1011 0000000000";
+
+    fn damaged_data(rows: &[&str]) -> Vec<u8> {
+        pack_bits(
+            &rows
+                .iter()
+                .map(|row| format!("{EOL} {row} "))
+                .collect::<String>(),
+        )
+    }
+
+    fn decode_damaged(rows: &[&str], damaged_rows_before_error: u32) -> Vec<u8> {
+        let params = format!(
+            "<< /K 0 /Columns 8 /Rows {} /EndOfLine true /DamagedRowsBeforeError {damaged_rows_before_error} >>",
+            rows.len()
+        );
+        let params = Dict::from_bytes(params.as_bytes()).unwrap();
+
+        decode(&damaged_data(rows), &params, &ImageDecodeParams::default())
+            .unwrap()
+            .data
+            .to_vec()
+    }
+
+    const HALF: [u8; 8] = [255, 255, 255, 255, 0, 0, 0, 0];
+
+    #[test]
+    fn damaged_rows_recovered() {
+        let rows = [
+            ROW_HALF,
+            ROW_DAMAGED,
+            ROW_WHITE,
+            ROW_DAMAGED,
+            ROW_DAMAGED,
+            ROW_BLACK,
+        ];
+
+        // The damaged rows are replaced with the previous row.
+        assert_eq!(
+            decode_damaged(&rows, 2),
+            [HALF, HALF, [255; 8], [255; 8], [255; 8], [0; 8]].concat()
+        );
+    }
+
+    #[test]
+    fn damaged_rows_before_error() {
+        let rows = [ROW_HALF, ROW_DAMAGED, ROW_DAMAGED, ROW_BLACK];
+
+        // By default, decoding stops at the first damaged row, keeping only the
+        // pixels in front of the invalid code.
+        assert_eq!(decode_damaged(&rows, 0), [&HALF[..], &[255; 4]].concat());
+        // Decoding stops at the second damaged row in a row.
+        assert_eq!(
+            decode_damaged(&rows, 1),
+            [&HALF[..], &HALF, &[255; 4]].concat()
+        );
+        assert_eq!(
+            decode_damaged(&rows, 2),
+            [HALF, HALF, HALF, [0; 8]].concat()
+        );
+    }
+
+    #[test]
+    fn damaged_rows_reported() {
+        struct RowRecorder {
+            rows: Vec<Vec<bool>>,
+            damaged: Vec<usize>,
+        }
+
+        impl Decoder for RowRecorder {
+            fn push_pixel(&mut self, white: bool) {
+                self.rows.last_mut().unwrap().push(white);
+            }
+
+            fn push_pixel_chunk(&mut self, white: bool, chunk_count: u32) {
+                let row = self.rows.last_mut().unwrap();
+                row.extend(iter::repeat_n(white, chunk_count as usize * 8));
+            }
+
+            fn next_line(&mut self) {
+                self.rows.push(Vec::new());
+            }
+
+            fn row_damaged(&mut self) {
+                self.damaged.push(self.rows.len() - 1);
+            }
+        }
+
+        let rows = [ROW_BLACK, ROW_HALF, ROW_DAMAGED, ROW_WHITE];
+        let mut settings = DecodeSettings::default();
+        settings.columns = 8;
+        settings.rows = rows.len() as u32;
+        settings.end_of_block = true;
+        settings.end_of_line = true;
+        settings.encoding = EncodingMode::Group3_1D;
+        settings.damaged_rows_before_error = 1;
+        settings.damaged_row_fill = DamagedRowFill::White;
+
+        let mut decoder = RowRecorder {
+            rows: vec![Vec::new()],
+            damaged: Vec::new(),
+        };
+        let mut context = DecoderContext::new(settings);
+        hayro_ccitt::decode(&damaged_data(&rows), &mut decoder, &mut context).unwrap();
+
+        assert_eq!(context.decoded_rows(), 4);
+        assert_eq!(context.damaged_rows(), 1);
+        assert_eq!(decoder.damaged, [2]);
+        assert_eq!(decoder.rows[2], [true; 8]);
+
+        // The same applies to 2D-coded images if the row is 1D-coded.
+        settings.encoding = EncodingMode::Group3_2D { k: 2 };
+        let rows = rows.map(|row| format!("1 {row}"));
+        let rows = rows.each_ref().map(|row| row.as_str());
+        let mut context = DecoderContext::new(settings);
+        hayro_ccitt::decode(&damaged_data(&rows), &mut decoder, &mut context).unwrap();
+
+        assert_eq!(context.decoded_rows(), 4);
+        assert_eq!(context.damaged_rows(), 1);
+    }
 }
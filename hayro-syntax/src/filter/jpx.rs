@@ -1,11 +1,11 @@
 use crate::bit_reader::BitWriter;
 use crate::filter::FilterResult;
 use crate::math::round_f32;
-use crate::object::stream::{ImageColorSpace, ImageData, ImageDecodeParams};
+use crate::object::stream::{DecodeFailure, ImageColorSpace, ImageData, ImageDecodeParams};
 use alloc::borrow::Cow;
 use alloc::vec;
 use alloc::vec::Vec;
-use hayro_jpeg2000::{ColorSpace, DecodeSettings};
+use hayro_jpeg2000::{ColorSpace, DecodeError, DecodeSettings, FormatError, MarkerError};
 
 impl ImageColorSpace {
     fn num_components(&self) -> u8 {
@@ -18,16 +18,17 @@ impl ImageColorSpace {
     }
 }
 
-pub(crate) fn decode(data: &[u8], params: &ImageDecodeParams) -> Option<FilterResult<'static>> {
+pub(crate) fn decode(
+    data: &[u8],
+    params: &ImageDecodeParams,
+) -> Result<FilterResult<'static>, DecodeFailure> {
     use crate::object::stream::ImageColorSpace;
 
-    let settings = DecodeSettings {
-        resolve_palette_indices: false,
-        strict: false,
-        target_resolution: params.target_dimension,
-    };
+    let mut settings = DecodeSettings::default();
+    settings.resolve_palette_indices = false;
+    settings.target_resolution = params.target_dimension;
 
-    let image = hayro_jpeg2000::Image::new(data, &settings).ok()?;
+    let image = hayro_jpeg2000::Image::new(data, &settings).map_err(decode_failure)?;
 
     let width = image.width();
     let height = image.height();
@@ -44,12 +45,15 @@ pub(crate) fn decode(data: &[u8], params: &ImageDecodeParams) -> Option<FilterRe
             1 => ImageColorSpace::Gray,
             3 => ImageColorSpace::Rgb,
             4 => ImageColorSpace::Cmyk,
-            _ => return None,
+            _ => return Err(DecodeFailure::UnsupportedImage),
         },
     };
     let has_alpha = image.has_alpha();
     let mut decoder_context = hayro_jpeg2000::DecoderContext::default();
-    let bitmap = image.decode(&mut decoder_context).ok()?.data_u8();
+    let bitmap = image
+        .decode(&mut decoder_context)
+        .map_err(decode_failure)?
+        .data_u8();
 
     let (mut data, mut alpha) = if !has_alpha {
         (bitmap, None)
@@ -62,7 +66,7 @@ pub(crate) fn decode(data: &[u8], params: &ImageDecodeParams) -> Option<FilterRe
         let mut alpha_channel = Vec::with_capacity(bitmap.len() / total_channels as usize);
 
         for sample in bitmap.chunks_exact(total_channels as usize) {
-            let (alpha, color) = sample.split_last()?;
+            let (alpha, color) = sample.split_last().ok_or(DecodeFailure::ImageDecode)?;
             alpha_channel.push(*alpha);
             color_channels.extend_from_slice(color);
         }
@@ -73,11 +77,12 @@ pub(crate) fn decode(data: &[u8], params: &ImageDecodeParams) -> Option<FilterRe
     // The decoded image is always 8-bit, so if necessary we have to rescale
     // ourselves.
     if bpc != 8 {
-        data = scale(&data, bpc, cs.num_components(), width, height)?;
+        data = scale(&data, bpc, cs.num_components(), width, height)
+            .ok_or(DecodeFailure::ImageDecode)?;
         alpha = alpha.and_then(|alpha| scale(&alpha, bpc, cs.num_components(), width, height));
     }
 
-    Some(FilterResult {
+    Ok(FilterResult {
         data: Cow::Owned(data),
         image_data: Some(ImageData {
             alpha,
@@ -89,6 +94,16 @@ pub(crate) fn decode(data: &[u8], params: &ImageDecodeParams) -> Option<FilterRe
     })
 }
 
+/// Report images that fail to decode because they use a feature that the decoder
+/// doesn't support as such.
+fn decode_failure(err: DecodeError) -> DecodeFailure {
+    match err {
+        DecodeError::Format(FormatError::Unsupported)
+        | DecodeError::Marker(MarkerError::Unsupported) => DecodeFailure::UnsupportedImage,
+        _ => DecodeFailure::ImageDecode,
+    }
+}
+
 fn scale(
     data: &[u8],
     bit_per_component: u8,
@@ -756,8 +756,24 @@ fn apply_predictor(data: Vec<u8>, params: &PredictorParams) -> Option<Vec<u8>> {
         i => {
             let is_png_predictor = i >= 10;
 
+            if !is_png_predictor && i != 2 {
+                warn!("unknown predictor {i}");
+
+                return None;
+            }
+
+            if !matches!(params.bits_per_component, 1 | 2 | 4 | 8 | 16) {
+                warn!("invalid bits per component {}", params.bits_per_component);
+
+                return None;
+            }
+
             let row_len = params.row_length_in_bytes();
 
+            if row_len == 0 {
+                return None;
+            }
+
             let total_row_len = if is_png_predictor {
                 // + 1 Because each row must start with the predictor that is used for PNG predictors.
                 row_len + 1
@@ -767,35 +783,23 @@ fn apply_predictor(data: Vec<u8>, params: &PredictorParams) -> Option<Vec<u8>> {
 
             let num_rows = data.len() / total_row_len;
 
-            if !matches!(params.bits_per_component, 1 | 2 | 4 | 8 | 16) {
-                warn!("invalid bits per component {}", params.bits_per_component);
-
-                return None;
+            if !is_png_predictor {
+                return unfilter_rows(data, false, row_len, num_rows, |_, _, row| {
+                    unfilter_tiff_row(row, params);
+                });
             }
 
-            let (bit_size, chunk_len) = if is_png_predictor {
-                (
-                    8,
-                    (params.colors * params.bits_per_component).div_ceil(8) as usize,
-                )
-            } else {
-                (params.bits_per_component, params.colors as usize)
-            };
+            let chunk_len = (params.colors * params.bits_per_component).div_ceil(8) as usize;
 
-            if bit_size == 8
-                && let Some(tbpp) = BytesPerPixel::from_row_len(row_len, chunk_len)
-            {
-                return apply_predictor_8bit_png(
-                    data,
-                    i,
-                    is_png_predictor,
-                    row_len,
-                    total_row_len,
-                    num_rows,
-                    tbpp,
-                );
+            if let Some(tbpp) = BytesPerPixel::from_row_len(row_len, chunk_len) {
+                return unfilter_rows(data, true, row_len, num_rows, |predictor, prev_row, row| {
+                    if let Some(filter) = predictor.and_then(RowFilter::from_u8) {
+                        png::unfilter(filter, tbpp, prev_row, row);
+                    }
+                });
             }
 
+            let bit_size = 8;
             let zero_row = vec![0; row_len];
             let mut prev_row = BitChunks::new(&zero_row, bit_size, chunk_len)?;
             let zero_col = BitChunk::new(0, chunk_len);
@@ -803,71 +807,55 @@ fn apply_predictor(data: Vec<u8>, params: &PredictorParams) -> Option<Vec<u8>> {
             let mut writer = BitWriter::new(&mut out, bit_size)?;
 
             for in_row in data.chunks_exact(total_row_len) {
-                if is_png_predictor {
-                    let predictor = in_row[0];
-                    let in_data = &in_row[1..];
-                    let in_data_chunks = BitChunks::new(in_data, bit_size, chunk_len)?;
-
-                    match predictor {
-                        1 => apply::<Sub>(
-                            prev_row,
-                            zero_col.clone(),
-                            zero_col.clone(),
-                            in_data_chunks,
-                            &mut writer,
-                            chunk_len,
-                            bit_size,
-                        )?,
-                        2 => apply::<Up>(
-                            prev_row,
-                            zero_col.clone(),
-                            zero_col.clone(),
-                            in_data_chunks,
-                            &mut writer,
-                            chunk_len,
-                            bit_size,
-                        )?,
-                        3 => apply::<Avg>(
-                            prev_row,
-                            zero_col.clone(),
-                            zero_col.clone(),
-                            in_data_chunks,
-                            &mut writer,
-                            chunk_len,
-                            bit_size,
-                        )?,
-                        4 => apply::<Paeth>(
-                            prev_row,
-                            zero_col.clone(),
-                            zero_col.clone(),
-                            in_data_chunks,
-                            &mut writer,
-                            chunk_len,
-                            bit_size,
-                        )?,
-                        _ => {
-                            // Just copy the data.
-                            let mut reader = BitReader::new(in_data);
-
-                            while let Some(data) = reader.read(bit_size) {
-                                writer.write(data);
-                            }
-                        }
-                    }
-                } else if i == 2 {
-                    apply::<Sub>(
+                let predictor = in_row[0];
+                let in_data = &in_row[1..];
+                let in_data_chunks = BitChunks::new(in_data, bit_size, chunk_len)?;
+
+                match predictor {
+                    1 => apply::<Sub>(
                         prev_row,
                         zero_col.clone(),
                         zero_col.clone(),
-                        BitChunks::new(in_row, bit_size, chunk_len)?,
+                        in_data_chunks,
                         &mut writer,
                         chunk_len,
                         bit_size,
-                    );
-                } else {
-                    warn!("unknown predictor {i}");
+                    )?,
+                    2 => apply::<Up>(
+                        prev_row,
+                        zero_col.clone(),
+                        zero_col.clone(),
+                        in_data_chunks,
+                        &mut writer,
+                        chunk_len,
+                        bit_size,
+                    )?,
+                    3 => apply::<Avg>(
+                        prev_row,
+                        zero_col.clone(),
+                        zero_col.clone(),
+                        in_data_chunks,
+                        &mut writer,
+                        chunk_len,
+                        bit_size,
+                    )?,
+                    4 => apply::<Paeth>(
+                        prev_row,
+                        zero_col.clone(),
+                        zero_col.clone(),
+                        in_data_chunks,
+                        &mut writer,
+                        chunk_len,
+                        bit_size,
+                    )?,
+                    _ => {
+                        // Just copy the data.
+                        let mut reader = BitReader::new(in_data);
 
-                    return None;
+                        while let Some(data) = reader.read(bit_size) {
+                            writer.write(data);
+                        }
+                    }
                 }
 
                 let (data, new_writer) = writer.split_off();
@@ -880,54 +868,43 @@ fn apply_predictor(data: Vec<u8>, params: &PredictorParams) -> Option<Vec<u8>> {
     }
 }
 
-fn apply_predictor_8bit_png(
+/// Undo the predictor of each row in place.
+///
+/// If `has_predictor_byte` is set, each row starts with a byte indicating the PNG
+/// predictor that was used for it, which is removed. `unfilter` is called with that
+/// byte, the previous (already decoded) row, which is empty for the first row, and
+/// the current row.
+fn unfilter_rows(
     mut data: Vec<u8>,
-    predictor: u8,
-    is_png_predictor: bool,
+    has_predictor_byte: bool,
     row_len: usize,
-    total_row_len: usize,
     num_rows: usize,
-    tbpp: BytesPerPixel,
+    mut unfilter: impl FnMut(Option<u8>, &[u8], &mut [u8]),
 ) -> Option<Vec<u8>> {
-    if row_len == 0 || total_row_len == 0 {
-        return None;
-    }
-
+    let total_row_len = row_len + usize::from(has_predictor_byte);
     let out_len = num_rows * row_len;
 
     for row_idx in 0..num_rows {
         let src_start = row_idx * total_row_len;
         let dst_start = row_idx * row_len;
 
-        if is_png_predictor {
+        let predictor = if has_predictor_byte {
             let predictor = data[src_start];
             data.copy_within(src_start + 1..src_start + 1 + row_len, dst_start);
 
-            let (done, rest) = data.split_at_mut(dst_start);
-            let prev_row = if let Some(prev_start) = done.len().checked_sub(row_len) {
-                &done[prev_start..]
-            } else {
-                &[]
-            };
-            let row = &mut rest[..row_len];
+            Some(predictor)
+        } else {
+            None
+        };
 
-            if let Some(filter) = RowFilter::from_u8(predictor) {
-                png::unfilter(filter, tbpp, prev_row, row);
-            }
-        } else if predictor == 2 {
-            let (done, rest) = data.split_at_mut(dst_start);
-            let prev_row = if let Some(prev_start) = done.len().checked_sub(row_len) {
-                &done[prev_start..]
-            } else {
-                &[]
-            };
-            let row = &mut rest[..row_len];
-            png::unfilter(RowFilter::Sub, tbpp, prev_row, row);
+        let (done, rest) = data.split_at_mut(dst_start);
+        let prev_row = if let Some(prev_start) = done.len().checked_sub(row_len) {
+            &done[prev_start..]
         } else {
-            warn!("unknown predictor {predictor}");
+            &[]
+        };
 
-            return None;
-        }
+        unfilter(predictor, prev_row, &mut rest[..row_len]);
     }
 
     data.truncate(out_len);
@@ -935,6 +912,51 @@ fn apply_predictor_8bit_png(
     Some(data)
 }
 
+/// Undo the TIFF predictor 2 for a single row in place.
+///
+/// Each sample is stored as the difference to the sample of the same color
+/// component in the pixel to its left. Samples with less than 8 bits are packed
+/// into bytes starting with the most significant bit, and 16-bit samples are
+/// stored in big-endian order.
+fn unfilter_tiff_row(row: &mut [u8], params: &PredictorParams) {
+    let colors = params.colors as usize;
+    let num_samples = params.columns * colors;
+
+    match params.bits_per_component {
+        8 => {
+            for i in colors..num_samples {
+                row[i] = row[i].wrapping_add(row[i - colors]);
+            }
+        }
+        16 => {
+            for i in colors..num_samples {
+                let left = u16::from_be_bytes([row[2 * (i - colors)], row[2 * (i - colors) + 1]]);
+                let cur = u16::from_be_bytes([row[2 * i], row[2 * i + 1]]);
+                row[2 * i..2 * i + 2].copy_from_slice(&cur.wrapping_add(left).to_be_bytes());
+            }
+        }
+        bpc => {
+            let bpc = bpc as usize;
+            let mask = (1_u8 << bpc) - 1;
+
+            // Return the byte index and the shift of the sample with the given index.
+            let position = |i: usize| {
+                let bit = i * bpc;
+                (bit / 8, 8 - bpc - bit % 8)
+            };
+
+            for i in colors..num_samples {
+                let (left_idx, left_shift) = position(i - colors);
+                let (idx, shift) = position(i);
+                let left = (row[left_idx] >> left_shift) & mask;
+                let cur = (row[idx] >> shift) & mask;
+                let sum = cur.wrapping_add(left) & mask;
+                row[idx] = (row[idx] & !(mask << shift)) | (sum << shift);
+            }
+        }
+    }
+}
+
 fn apply<'a, T: Predictor>(
     prev_row: BitChunks<'a>,
     mut prev_col: BitChunk,
@@ -1097,6 +1119,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn predictor_tiff() {
+        predictor_test(
+            2,
+            &[
+                // Row 1
+                127, 127, 127, 254, 2, 0, 254, 1, 1, 
+                // Row 2
+                128, 129, 126, 254, 3, 254, 251, 251, 2, 
+                // Row 3
+                131, 130, 122, 2, 255, 6, 250, 227, 254,
+            ],
+        );
+    }
+
     #[test]
     fn predictor_up() {
         predictor_test(
@@ -1141,4 +1178,98 @@ mod tests {
             ],
         );
     }
+
+    fn get_sample(row: &[u8], idx: usize, bpc: usize) -> u32 {
+        (0..bpc).fold(0, |acc, b| {
+            let bit = idx * bpc + b;
+            (acc << 1) | ((row[bit / 8] >> (7 - bit % 8)) & 1) as u32
+        })
+    }
+
+    fn set_sample(row: &mut [u8], idx: usize, bpc: usize, value: u32) {
+        for b in 0..bpc {
+            let bit = idx * bpc + b;
+            let mask = 1 << (7 - bit % 8);
+            
+            if (value >> (bpc - 1 - b)) & 1 == 1 {
+                row[bit / 8] |= mask;
+            } else {
+                row[bit / 8] &= !mask;
+            }
+        }
+    }
+
+    // Encode random rows with the TIFF predictor, i.e. store the difference of each
+    // sample to the same component of the pixel to its left, and check that decoding
+    // yields the original rows again.
+    fn tiff_predictor_roundtrip(bits_per_component: u8) {
+        let bpc = bits_per_component as usize;
+        let mask = ((1_u64 << bpc) - 1) as u32;
+        let mut seed = 0x1234_5678_u32;
+        let num_rows = 3;
+
+        for colors in 1..=5 {
+            for columns in 1..=7 {
+                let num_samples = columns * colors;
+                let row_len = (num_samples * bpc).div_ceil(8);
+                let mut decoded = vec![0; row_len * num_rows];
+                let mut encoded = vec![0; row_len * num_rows];
+
+                for (row, encoded_row) in decoded
+                    .chunks_exact_mut(row_len)
+                    .zip(encoded.chunks_exact_mut(row_len))
+                {
+                    for i in 0..num_samples {
+                        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                        set_sample(row, i, bpc, (seed >> 8) & mask);
+                    }
+
+                    for i in 0..num_samples {
+                        let left = if i >= colors { get_sample(row, i - colors, bpc) } else { 0 };
+                        let delta = get_sample(row, i, bpc).wrapping_sub(left) & mask;
+                        set_sample(encoded_row, i, bpc, delta);
+                    }
+                }
+
+                let params = PredictorParams {
+                    predictor: 2,
+                    colors: colors as u8,
+                    bits_per_component,
+                    columns,
+                    early_change: false,
+                };
+
+                assert_eq!(
+                    apply_predictor(encoded, &params).unwrap(),
+                    decoded,
+                    "colors: {colors}, columns: {columns}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn predictor_tiff_1_bit() {
+        tiff_predictor_roundtrip(1);
+    }
+
+    #[test]
+    fn predictor_tiff_2_bit() {
+        tiff_predictor_roundtrip(2);
+    }
+
+    #[test]
+    fn predictor_tiff_4_bit() {
+        tiff_predictor_roundtrip(4);
+    }
+
+    #[test]
+    fn predictor_tiff_8_bit() {
+        tiff_predictor_roundtrip(8);
+    }
+
+    #[test]
+    fn predictor_tiff_16_bit() {
+        tiff_predictor_roundtrip(16);
+    }
 }
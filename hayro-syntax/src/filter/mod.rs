@@ -46,6 +46,19 @@ pub enum Filter {
 }
 
 impl Filter {
+    /// Whether data encoded with this filter can be decoded.
+    ///
+    /// This is always the case, except for the image filters `DCTDecode`,
+    /// `CCITTFaxDecode`, `JBIG2Decode` and `JPXDecode` if the `images` feature
+    /// is disabled.
+    pub fn is_available(&self) -> bool {
+        cfg!(feature = "images")
+            || !matches!(
+                self,
+                Self::DctDecode | Self::CcittFaxDecode | Self::Jbig2Decode | Self::JpxDecode
+            )
+    }
+
     fn debug_name(&self) -> &'static str {
         match self {
             Self::AsciiHexDecode => "ascii_hex",
@@ -116,7 +129,7 @@ impl Filter {
                 jbig2::decode(data, params, image_params).ok_or(DecodeFailure::ImageDecode)
             }
             #[cfg(feature = "images")]
-            Self::JpxDecode => jpx::decode(data, image_params).ok_or(DecodeFailure::ImageDecode),
+            Self::JpxDecode => jpx::decode(data, image_params),
             #[cfg(not(feature = "images"))]
             Self::DctDecode | Self::CcittFaxDecode | Self::Jbig2Decode | Self::JpxDecode => {
                 warn!("image decoding is not supported (enable the `images` feature)");
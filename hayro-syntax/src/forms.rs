@@ -0,0 +1,512 @@
+//! Reading interactive forms.
+//!
+//! This allows enumerating the fields of the `AcroForm` of a document, together
+//! with their values and widget annotations. Appearances of fields are not
+//! taken into account. XFA forms are not interpreted, but their packets can
+//! be extracted so that they can be parsed by the caller.
+
+use crate::object;
+use crate::object::dict::keys::*;
+use crate::object::{Array, Dict, Name, Object, ObjectIdentifier, Rect, Stream, TextString};
+use crate::pdf::Pdf;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+/// The maximum depth of the field hierarchy, to prevent stack overflows for
+/// cyclic or degenerate forms.
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// The interactive form of a document.
+#[derive(Clone, Debug)]
+pub struct AcroForm<'a> {
+    dict: Dict<'a>,
+    fields: Vec<Field<'a>>,
+}
+
+impl<'a> AcroForm<'a> {
+    pub(crate) fn new(pdf: &'a Pdf) -> Option<Self> {
+        let xref = pdf.xref();
+        let catalog = xref.get::<Dict<'a>>(xref.root_id())?;
+        let dict = catalog.get::<Dict<'a>>(ACRO_FORM)?;
+
+        let lookup = PageLookup::new(pdf);
+        let inherited = Inherited {
+            field_type: None,
+            flags: 0,
+            default_appearance: dict.get::<object::String<'a>>(DA).map(|s| s.to_vec()),
+            value: None,
+            default_value: None,
+        };
+        let mut visited = Vec::new();
+
+        let fields = dict
+            .get::<Array<'a>>(FIELDS)
+            .map(|fields| {
+                fields
+                    .iter::<Dict<'a>>()
+                    .filter_map(|field| {
+                        Field::new(field, None, &inherited, &lookup, 0, &mut visited)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Self { dict, fields })
+    }
+
+    /// Return the top-level fields of the form.
+    pub fn fields(&self) -> &[Field<'a>] {
+        &self.fields
+    }
+
+    /// Return all fields of the form in depth-first order, including fields
+    /// that only group other fields.
+    pub fn all_fields(&self) -> Vec<&Field<'a>> {
+        fn collect<'b, 'a>(fields: &'b [Field<'a>], out: &mut Vec<&'b Field<'a>>) {
+            for field in fields {
+                out.push(field);
+                collect(&field.kids, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        collect(&self.fields, &mut out);
+
+        out
+    }
+
+    /// Return the field with the given fully qualified name.
+    pub fn field(&self, qualified_name: &str) -> Option<&Field<'a>> {
+        self.all_fields()
+            .into_iter()
+            .find(|f| f.qualified_name == qualified_name)
+    }
+
+    /// Return the packets of the XFA form of the document, if it exists.
+    ///
+    /// If the XFA entry consists of a single stream, it is returned as a single
+    /// packet without a name.
+    pub fn xfa_packets(&self) -> Vec<XfaPacket> {
+        match self.dict.get::<Object<'a>>(XFA) {
+            Some(Object::Stream(stream)) => stream
+                .decoded()
+                .ok()
+                .map(|data| XfaPacket {
+                    name: None,
+                    data: data.into_owned(),
+                })
+                .into_iter()
+                .collect(),
+            Some(Object::Array(array)) => {
+                let mut packets = Vec::new();
+                let mut iter = array.flex_iter();
+
+                while let Some(name) = iter.next::<object::String<'a>>() {
+                    let Some(stream) = iter.next::<Stream<'a>>() else {
+                        break;
+                    };
+
+                    if let Ok(data) = stream.decoded() {
+                        packets.push(XfaPacket {
+                            name: Some(TextString::from(name)),
+                            data: data.into_owned(),
+                        });
+                    }
+                }
+
+                packets
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Return the data of the `datasets` packet of the XFA form, which contains
+    /// the values of the fields as XML.
+    ///
+    /// Returns `None` if there is no XFA form or if it isn't split into packets.
+    pub fn xfa_datasets(&self) -> Option<Vec<u8>> {
+        self.xfa_packets()
+            .into_iter()
+            .find(|p| {
+                p.name
+                    .as_ref()
+                    .is_some_and(|n| n.to_string_lossy() == "datasets")
+            })
+            .map(|p| p.data)
+    }
+
+    /// Return the underlying dictionary of the form.
+    pub fn raw(&self) -> &Dict<'a> {
+        &self.dict
+    }
+}
+
+/// A packet of an XFA form.
+#[derive(Clone, Debug)]
+pub struct XfaPacket {
+    /// The name of the packet, for example `template` or `datasets`.
+    pub name: Option<TextString>,
+    /// The decoded data of the packet.
+    pub data: Vec<u8>,
+}
+
+/// The type of a form field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    /// A push button, check box or radio button.
+    Button,
+    /// A text field.
+    Text,
+    /// A list box or combo box.
+    Choice,
+    /// A signature field.
+    Signature,
+}
+
+impl FieldType {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            BTN => Some(Self::Button),
+            TX => Some(Self::Text),
+            CH => Some(Self::Choice),
+            SIG => Some(Self::Signature),
+            _ => None,
+        }
+    }
+}
+
+/// The flags of a form field.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FieldFlags(u32);
+
+impl FieldFlags {
+    /// Return the raw value of the flags.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    fn is_set(&self, bit: u32) -> bool {
+        self.0 & (1 << (bit - 1)) != 0
+    }
+
+    /// Whether the value of the field may not be changed.
+    pub fn read_only(&self) -> bool {
+        self.is_set(1)
+    }
+
+    /// Whether the field must have a value when the form is submitted.
+    pub fn required(&self) -> bool {
+        self.is_set(2)
+    }
+
+    /// Whether the field must not be exported when the form is submitted.
+    pub fn no_export(&self) -> bool {
+        self.is_set(3)
+    }
+
+    /// Whether a text field may contain multiple lines of text.
+    pub fn multiline(&self) -> bool {
+        self.is_set(13)
+    }
+
+    /// Whether a text field is a password field.
+    pub fn password(&self) -> bool {
+        self.is_set(14)
+    }
+
+    /// Whether a button field is a radio button.
+    pub fn radio(&self) -> bool {
+        self.is_set(16)
+    }
+
+    /// Whether a button field is a push button.
+    pub fn push_button(&self) -> bool {
+        self.is_set(17)
+    }
+
+    /// Whether a choice field is a combo box instead of a list box.
+    pub fn combo(&self) -> bool {
+        self.is_set(18)
+    }
+
+    /// Whether multiple items of a choice field may be selected.
+    pub fn multi_select(&self) -> bool {
+        self.is_set(22)
+    }
+}
+
+/// The value of a form field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldValue {
+    /// A text value, for example of a text field.
+    Text(TextString),
+    /// A name, for example the selected state of a check box or radio button.
+    Name(String),
+    /// Multiple text values, for example the selected items of a list box.
+    List(Vec<TextString>),
+}
+
+impl FieldValue {
+    fn new(object: Object<'_>) -> Option<Self> {
+        match object {
+            Object::String(s) => Some(Self::Text(TextString::from(s))),
+            Object::Name(n) => Some(Self::Name(String::from_utf8_lossy(n.deref()).into_owned())),
+            Object::Array(a) => Some(Self::List(
+                a.iter::<object::String<'_>>()
+                    .map(TextString::from)
+                    .collect(),
+            )),
+            // Rich text values are stored in streams.
+            Object::Stream(s) => Some(Self::Text(TextString::new(s.decoded().ok()?))),
+            _ => None,
+        }
+    }
+}
+
+/// A widget annotation of a form field.
+#[derive(Clone, Debug)]
+pub struct Widget<'a> {
+    dict: Dict<'a>,
+    rect: Rect,
+    page_index: Option<usize>,
+}
+
+impl<'a> Widget<'a> {
+    /// Return the rectangle of the widget in the default user space of its page.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Return the index of the page the widget is placed on, if it could be
+    /// determined.
+    pub fn page_index(&self) -> Option<usize> {
+        self.page_index
+    }
+
+    /// Return the appearance state of the widget, for example the export value
+    /// of a check box or radio button if it is selected.
+    pub fn appearance_state(&self) -> Option<Name<'a>> {
+        self.dict.get::<Name<'a>>(AS)
+    }
+
+    /// Return the underlying dictionary of the widget.
+    pub fn raw(&self) -> &Dict<'a> {
+        &self.dict
+    }
+}
+
+/// A field of an interactive form.
+#[derive(Clone, Debug)]
+pub struct Field<'a> {
+    dict: Dict<'a>,
+    partial_name: Option<TextString>,
+    qualified_name: String,
+    field_type: Option<FieldType>,
+    flags: FieldFlags,
+    default_appearance: Option<Vec<u8>>,
+    value: Option<Object<'a>>,
+    default_value: Option<Object<'a>>,
+    kids: Vec<Self>,
+    widgets: Vec<Widget<'a>>,
+}
+
+impl<'a> Field<'a> {
+    fn new(
+        dict: Dict<'a>,
+        parent_name: Option<&str>,
+        parent: &Inherited<'a>,
+        lookup: &PageLookup,
+        depth: usize,
+        visited: &mut Vec<ObjectIdentifier>,
+    ) -> Option<Self> {
+        if depth >= MAX_FIELD_DEPTH {
+            return None;
+        }
+
+        if let Some(id) = dict.obj_id() {
+            if visited.contains(&id) {
+                return None;
+            }
+
+            visited.push(id);
+        }
+
+        let inherited = Inherited {
+            field_type: dict
+                .get::<Name<'_>>(FT)
+                .and_then(|n| FieldType::from_name(n.deref()))
+                .or(parent.field_type),
+            flags: dict.get::<u32>(FF).unwrap_or(parent.flags),
+            default_appearance: dict
+                .get::<object::String<'_>>(DA)
+                .map(|s| s.to_vec())
+                .or_else(|| parent.default_appearance.clone()),
+            value: dict.get::<Object<'a>>(V).or_else(|| parent.value.clone()),
+            default_value: dict
+                .get::<Object<'a>>(DV)
+                .or_else(|| parent.default_value.clone()),
+        };
+
+        let partial_name = dict.get::<object::String<'_>>(T).map(TextString::from);
+        let qualified_name = match (parent_name, &partial_name) {
+            (Some(parent), Some(name)) => alloc::format!("{parent}.{}", name.to_string_lossy()),
+            (Some(parent), None) => String::from(parent),
+            (None, Some(name)) => name.to_string_lossy(),
+            (None, None) => String::new(),
+        };
+
+        let mut kids = Vec::new();
+        let mut widgets = Vec::new();
+
+        match dict.get::<Array<'a>>(KIDS) {
+            Some(kid_dicts) => {
+                for kid in kid_dicts.iter::<Dict<'a>>() {
+                    // Kids without a partial name are the widget annotations of
+                    // this field, all other kids are fields themselves.
+                    if kid.contains_key(T) {
+                        kids.extend(Self::new(
+                            kid,
+                            Some(&qualified_name),
+                            &inherited,
+                            lookup,
+                            depth + 1,
+                            visited,
+                        ));
+                    } else {
+                        widgets.extend(lookup.widget(kid));
+                    }
+                }
+            }
+            // Fields with a single widget annotation can be merged with it.
+            None => {
+                if dict.get::<Name<'_>>(SUBTYPE).as_deref() == Some(WIDGET) {
+                    widgets.extend(lookup.widget(dict.clone()));
+                }
+            }
+        }
+
+        Some(Self {
+            dict,
+            partial_name,
+            qualified_name,
+            field_type: inherited.field_type,
+            flags: FieldFlags(inherited.flags),
+            default_appearance: inherited.default_appearance,
+            value: inherited.value,
+            default_value: inherited.default_value,
+            kids,
+            widgets,
+        })
+    }
+
+    /// Return the partial name of the field, i.e. its name without the names
+    /// of its ancestors.
+    pub fn partial_name(&self) -> Option<&TextString> {
+        self.partial_name.as_ref()
+    }
+
+    /// Return the fully qualified name of the field, i.e. the partial names of
+    /// the field and its ancestors, separated by periods.
+    pub fn qualified_name(&self) -> &str {
+        &self.qualified_name
+    }
+
+    /// Return the type of the field.
+    ///
+    /// Returns `None` for fields that only group other fields.
+    pub fn field_type(&self) -> Option<FieldType> {
+        self.field_type
+    }
+
+    /// Return the flags of the field.
+    pub fn flags(&self) -> FieldFlags {
+        self.flags
+    }
+
+    /// Return the default appearance string of the field, which is used for
+    /// variable text.
+    pub fn default_appearance(&self) -> Option<&[u8]> {
+        self.default_appearance.as_deref()
+    }
+
+    /// Return the value of the field.
+    pub fn value(&self) -> Option<FieldValue> {
+        self.value.clone().and_then(FieldValue::new)
+    }
+
+    /// Return the default value of the field, to which it is reset when the
+    /// form is reset.
+    pub fn default_value(&self) -> Option<FieldValue> {
+        self.default_value.clone().and_then(FieldValue::new)
+    }
+
+    /// Return the child fields of the field.
+    pub fn kids(&self) -> &[Self] {
+        &self.kids
+    }
+
+    /// Return the widget annotations of the field.
+    pub fn widgets(&self) -> &[Widget<'a>] {
+        &self.widgets
+    }
+
+    /// Return the underlying dictionary of the field.
+    pub fn raw(&self) -> &Dict<'a> {
+        &self.dict
+    }
+}
+
+/// The attributes that are inherited down the field hierarchy.
+struct Inherited<'a> {
+    field_type: Option<FieldType>,
+    flags: u32,
+    default_appearance: Option<Vec<u8>>,
+    value: Option<Object<'a>>,
+    default_value: Option<Object<'a>>,
+}
+
+/// A lookup for the page indices of pages and annotations.
+struct PageLookup {
+    pages: BTreeMap<ObjectIdentifier, usize>,
+    annotations: BTreeMap<ObjectIdentifier, usize>,
+}
+
+impl PageLookup {
+    fn new(pdf: &Pdf) -> Self {
+        let mut pages = BTreeMap::new();
+        let mut annotations = BTreeMap::new();
+
+        for (index, page) in pdf.pages().iter().enumerate() {
+            if let Some(id) = page.raw().obj_id() {
+                pages.insert(id, index);
+            }
+
+            if let Some(annots) = page.raw().get::<Array<'_>>(ANNOTS) {
+                for annot in annots.iter::<Dict<'_>>() {
+                    if let Some(id) = annot.obj_id() {
+                        annotations.entry(id).or_insert(index);
+                    }
+                }
+            }
+        }
+
+        Self { pages, annotations }
+    }
+
+    fn widget<'a>(&self, dict: Dict<'a>) -> Option<Widget<'a>> {
+        let rect = dict.get::<Rect>(RECT)?;
+        let page_index = dict
+            .get_ref(P)
+            .and_then(|p| self.pages.get(&p.into()))
+            .or_else(|| self.annotations.get(&dict.obj_id()?))
+            .copied();
+
+        Some(Widget {
+            dict,
+            rect,
+            page_index,
+        })
+    }
+}
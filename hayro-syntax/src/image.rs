@@ -0,0 +1,253 @@
+//! Extracting the images of a page.
+
+use crate::content::inline_image::InlineImage;
+use crate::content::ops::TypedInstruction;
+use crate::filter::Filter;
+use crate::object::dict::keys::*;
+use crate::object::stream::{DecodeFailure, FilterResult, ImageDecodeParams};
+use crate::object::{Array, Dict, Name, Object, ObjectIdentifier, Stream};
+use crate::page::{Page, Resources};
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::Deref;
+use smallvec::SmallVec;
+
+/// The maximum depth of nested form `XObject`s that are searched for images.
+const MAX_FORM_DEPTH: usize = 16;
+
+/// The way an image is embedded into a page.
+#[derive(Clone, Debug)]
+pub enum ImageSource<'a> {
+    /// An image `XObject` with the given name in the resources.
+    XObject(Name<'a>),
+    /// An inline image in the content stream of the page.
+    Inline,
+}
+
+/// An image in the resources or the content stream of a page.
+#[derive(Clone, Debug)]
+pub struct ImageRef<'a> {
+    // For inline images, abbreviated keys in the dictionary have been replaced
+    // by their full names.
+    stream: Stream<'a>,
+    resources: Resources<'a>,
+    source: ImageSource<'a>,
+}
+
+impl<'a> ImageRef<'a> {
+    fn inline(image: InlineImage<'a>, resources: Resources<'a>) -> Self {
+        Self {
+            stream: image.stream().clone(),
+            resources,
+            source: ImageSource::Inline,
+        }
+    }
+
+    /// Return how the image is embedded into the page.
+    pub fn source(&self) -> &ImageSource<'a> {
+        &self.source
+    }
+
+    /// Return the dictionary of the image.
+    ///
+    /// For inline images, abbreviated keys are replaced by their full names.
+    pub fn dict(&self) -> &Dict<'a> {
+        self.stream.dict()
+    }
+
+    /// Return the width of the image.
+    pub fn width(&self) -> Option<u32> {
+        self.dict().get::<u32>(WIDTH)
+    }
+
+    /// Return the height of the image.
+    pub fn height(&self) -> Option<u32> {
+        self.dict().get::<u32>(HEIGHT)
+    }
+
+    /// Return the bits per component of the image.
+    pub fn bits_per_component(&self) -> Option<u8> {
+        self.dict().get::<u8>(BITS_PER_COMPONENT)
+    }
+
+    /// Return whether the image is a stencil mask.
+    pub fn is_image_mask(&self) -> bool {
+        self.dict().get::<bool>(IMAGE_MASK).unwrap_or(false)
+    }
+
+    /// Return the color space of the image.
+    ///
+    /// Abbreviated names of device color spaces are replaced by their full
+    /// names, and named color spaces of inline images are looked up in the
+    /// resources of the page.
+    pub fn color_space(&self) -> Option<Object<'a>> {
+        color_space(self.dict(), &self.resources)
+    }
+
+    /// Return the filters that are applied to the image data.
+    pub fn filters(&self) -> SmallVec<[Filter; 2]> {
+        self.stream.filters()
+    }
+
+    /// Return the raw image data without any filters applied.
+    pub fn raw(&self) -> Cow<'a, [u8]> {
+        self.stream.raw_data()
+    }
+
+    /// Return the decoded data of the image.
+    pub fn decoded(&self) -> Result<FilterResult<'a>, DecodeFailure> {
+        decode(&self.stream, &self.resources)
+    }
+}
+
+/// Collect the images of the page, optionally including the ones in the
+/// resources of form `XObject`s.
+pub(crate) fn collect<'a>(page: &'a Page<'a>, include_forms: bool) -> Vec<ImageRef<'a>> {
+    let mut images = Vec::new();
+    let mut visited = Vec::new();
+    let resources = page.resources();
+
+    collect_x_objects(resources, include_forms, 0, &mut visited, &mut images);
+
+    let mut ops = page.typed_operations();
+
+    while let Some(op) = ops.next() {
+        if let TypedInstruction::InlineImage(i) = op {
+            images.push(ImageRef::inline(i.image(), resources.clone()));
+        }
+    }
+
+    images
+}
+
+fn collect_x_objects<'a>(
+    resources: &Resources<'a>,
+    include_forms: bool,
+    depth: usize,
+    visited: &mut Vec<ObjectIdentifier>,
+    images: &mut Vec<ImageRef<'a>>,
+) {
+    let mut names = resources.x_objects.keys().collect::<Vec<_>>();
+    names.sort_by(|n1, n2| n1.as_ref().cmp(n2.as_ref()));
+
+    let mut forms = Vec::new();
+
+    for name in names {
+        let Some(stream) = resources.x_objects.get::<Stream<'a>>(name.deref()) else {
+            continue;
+        };
+
+        // Images and forms that are referenced multiple times are only visited
+        // once. Direct objects don't have an identifier, so they can't be shared.
+        let id = stream.obj_id();
+        if id != ObjectIdentifier::new(0, 0) {
+            if visited.contains(&id) {
+                continue;
+            }
+
+            visited.push(id);
+        }
+
+        match stream.dict().get::<Name<'_>>(SUBTYPE).as_deref() {
+            Some(IMAGE) => images.push(ImageRef {
+                stream,
+                resources: resources.clone(),
+                source: ImageSource::XObject(name),
+            }),
+            Some(FORM) => forms.push(stream),
+            _ => {}
+        }
+    }
+
+    // Visit forms after the images, so that images that are shared with the
+    // resources of a form are attributed to the outer resources.
+    if include_forms && depth < MAX_FORM_DEPTH {
+        for form in forms {
+            let form_resources = Resources::from_parent(
+                form.dict().get::<Dict<'_>>(RESOURCES).unwrap_or_default(),
+                resources.clone(),
+            );
+
+            collect_x_objects(&form_resources, include_forms, depth + 1, visited, images);
+        }
+    }
+}
+
+/// Resolve the color space in an image dictionary with full key names.
+pub(crate) fn color_space<'a>(dict: &Dict<'a>, resources: &Resources<'a>) -> Option<Object<'a>> {
+    match dict.get::<Object<'a>>(COLORSPACE)? {
+        Object::Name(name) => match device_color_space(&name) {
+            Some(full_name) => Some(Object::Name(Name::new_unescaped(full_name))),
+            None => resources.get_color_space(&name),
+        },
+        cs => Some(cs),
+    }
+}
+
+/// Decode an image stream whose dictionary uses full key names, looking up
+/// named color spaces in the given resources.
+pub(crate) fn decode<'a>(
+    stream: &Stream<'a>,
+    resources: &Resources<'a>,
+) -> Result<FilterResult<'a>, DecodeFailure> {
+    let dict = stream.dict();
+    let is_image_mask = dict.get::<bool>(IMAGE_MASK).unwrap_or(false);
+
+    let (num_components, is_indexed) = if is_image_mask {
+        (Some(1), false)
+    } else {
+        match color_space(dict, resources).and_then(|cs| num_components(&cs)) {
+            Some((num_components, is_indexed)) => (Some(num_components), is_indexed),
+            None => (None, false),
+        }
+    };
+
+    let bpc = if is_image_mask {
+        Some(1)
+    } else {
+        dict.get::<u8>(BITS_PER_COMPONENT)
+    };
+
+    stream.decoded_image(&ImageDecodeParams {
+        is_indexed,
+        bpc,
+        num_components,
+        target_dimension: None,
+        width: dict.get::<u32>(WIDTH).unwrap_or(0),
+        height: dict.get::<u32>(HEIGHT).unwrap_or(0),
+    })
+}
+
+/// Return the number of components of a resolved color space, and whether it
+/// is an indexed color space.
+fn num_components(cs: &Object<'_>) -> Option<(u8, bool)> {
+    let (family, params) = match cs {
+        Object::Name(name) => (name.clone(), None),
+        Object::Array(array) => {
+            let mut iter = array.flex_iter();
+            (iter.next::<Name<'_>>()?, Some(iter))
+        }
+        _ => return None,
+    };
+
+    let num_components = match family.deref() {
+        I | INDEXED => return Some((1, true)),
+        G | DEVICE_GRAY | CALGRAY | SEPARATION => 1,
+        RGB | DEVICE_RGB | CALRGB | LAB => 3,
+        CMYK | DEVICE_CMYK | CALCMYK => 4,
+        ICC_BASED => params?.next::<Stream<'_>>()?.dict().get::<u8>(N)?,
+        DEVICE_N => u8::try_from(params?.next::<Array<'_>>()?.raw_iter().count()).ok()?,
+        _ => return None,
+    };
+
+    Some((num_components, false))
+}
+
+fn device_color_space(name: &[u8]) -> Option<&'static [u8]> {
+    match name {
+        G | DEVICE_GRAY => Some(DEVICE_GRAY),
+        RGB | DEVICE_RGB => Some(DEVICE_RGB),
+        CMYK | DEVICE_CMYK => Some(DEVICE_CMYK),
+        _ => None,
+    }
+}
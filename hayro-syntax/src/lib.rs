@@ -64,6 +64,7 @@ The supported features include:
 - Parsing of all objects types (also in object streams).
 - Parsing and decoding PDF streams.
 - Iterating over pages as well as their content streams in a typed or untyped fashion.
+- Reading name trees and number trees.
 - The crate is very lightweight, especially in comparison to other PDF crates.
 
 # Limitations
@@ -93,10 +94,13 @@ pub(crate) mod util;
 
 pub mod content;
 mod crypto;
+pub mod forms;
+pub mod image;
 pub mod metadata;
 pub mod object;
 pub mod page;
 pub mod transform;
+pub mod tree;
 pub mod xref;
 
 // We only expose them so hayro-interpret can use them, but they are not intended
@@ -1,7 +1,6 @@
 //! Reading document metadata.
 
-use crate::object::DateTime;
-use alloc::vec::Vec;
+use crate::object::{DateTime, TextString};
 
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 /// The metadata of a PDF document.
@@ -11,33 +10,15 @@ pub struct Metadata {
     /// The modification date of the document.
     pub modification_date: Option<DateTime>,
     /// The title of the document.
-    ///
-    /// In the vast majority of cases, this is going to be an ASCII string, but it doesn't have to
-    /// be.
-    pub title: Option<Vec<u8>>,
+    pub title: Option<TextString>,
     /// The author of the document.
-    ///
-    /// In the vast majority of cases, this is going to be an ASCII string, but it doesn't have to
-    /// be.
-    pub author: Option<Vec<u8>>,
+    pub author: Option<TextString>,
     /// The subject of the document.
-    ///
-    /// In the vast majority of cases, this is going to be an ASCII string, but it doesn't have to
-    /// be.
-    pub subject: Option<Vec<u8>>,
+    pub subject: Option<TextString>,
     /// The keywords of the document.
-    ///
-    /// In the vast majority of cases, this is going to be an ASCII string, but it doesn't have to
-    /// be.
-    pub keywords: Option<Vec<u8>>,
+    pub keywords: Option<TextString>,
     /// The creator of the document.
-    ///
-    /// In the vast majority of cases, this is going to be an ASCII string, but it doesn't have to
-    /// be.
-    pub creator: Option<Vec<u8>>,
+    pub creator: Option<TextString>,
     /// The producer of the document.
-    ///
-    /// In the vast majority of cases, this is going to be an ASCII string, but it doesn't have to
-    /// be.
-    pub producer: Option<Vec<u8>>,
+    pub producer: Option<TextString>,
 }
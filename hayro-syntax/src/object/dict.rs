@@ -121,6 +121,32 @@ impl<'a> Dict<'a> {
         Reader::new(&self.data()[offset..]).read_with_context::<MaybeRef<T>>(self.ctx())
     }
 
+    /// Return a copy of the dictionary where the keys for which `rename` returns
+    /// a new name are renamed. If the new key already exists, the existing entry is
+    /// kept.
+    pub(crate) fn rename_keys(&self, rename: impl Fn(&[u8]) -> Option<&'static [u8]>) -> Self {
+        let Inner::Some(repr) = &self.0 else {
+            return Self::empty();
+        };
+
+        let mut offsets = repr.offsets.clone();
+
+        for (name, offset) in &repr.offsets {
+            if let Some(new_name) = rename(name) {
+                offsets.remove(name.deref());
+                offsets
+                    .entry(Name::new_unescaped(new_name))
+                    .or_insert(*offset);
+            }
+        }
+
+        Self(Inner::Some(Arc::new(Repr {
+            data: repr.data,
+            offsets,
+            ctx: repr.ctx.clone(),
+        })))
+    }
+
     pub(crate) fn ctx(&self) -> &ReaderContext<'a> {
         match &self.0 {
             Inner::Empty => ReaderContext::dummy_ref(),
@@ -503,6 +529,7 @@ pub mod keys {
     // D
     key!(D, b"D");
     key!(DA, b"DA");
+    key!(DAMAGED_ROWS_BEFORE_ERROR, b"DamagedRowsBeforeError");
     key!(DARKEN, b"Darken");
     key!(DATE, b"Date");
     key!(DCT_DECODE, b"DCTDecode");
@@ -638,6 +665,8 @@ pub mod keys {
     key!(HIDE_MENUBAR, b"HideMenubar");
     key!(HIDE_TOOLBAR, b"HideToolbar");
     key!(HIDE_WINDOWUI, b"HideWindowUI");
+    key!(HIGHLIGHT, b"Highlight");
+    key!(HT, b"HT");
     key!(HUE, b"Hue");
 
     // I
@@ -10,7 +10,7 @@ pub use crate::object::number::Number;
 pub use crate::object::rect::Rect;
 pub use crate::object::r#ref::{MaybeRef, ObjRef};
 pub use crate::object::stream::Stream;
-pub use crate::object::string::String;
+pub use crate::object::string::{String, TextEncoding, TextString};
 use crate::reader::Reader;
 use crate::reader::{Readable, ReaderContext, ReaderExt, Skippable};
 use core::fmt::Debug;
@@ -21,11 +21,11 @@ mod null;
 mod number;
 mod rect;
 mod r#ref;
-mod string;
 mod tuple;
 
 pub(crate) mod indirect;
 pub(crate) mod name;
+pub(crate) mod string;
 
 pub mod array;
 pub mod dict;
@@ -59,6 +59,11 @@ impl<'a> Stream<'a> {
         Self { dict, data }
     }
 
+    /// Return a stream with the same data, but a different dictionary.
+    pub(crate) fn with_dict(&self, dict: Dict<'a>) -> Self {
+        Self::new(self.data, dict)
+    }
+
     fn filters_and_params(&self) -> FiltersAndParams<'a> {
         let mut collected_filters = SmallVec::new();
         let mut collected_params = SmallVec::new();
@@ -138,6 +143,15 @@ impl<'a> Stream<'a> {
         &self.dict
     }
 
+    /// Return whether the `Length` entry of the stream dictionary matches the
+    /// number of bytes between the `stream` and `endstream` keywords.
+    ///
+    /// If it doesn't, the data of the stream has been recovered by searching for
+    /// the `endstream` keyword instead.
+    pub fn has_valid_length(&self) -> bool {
+        self.dict.get::<u32>(LENGTH).map(|l| l as usize) == Some(self.data.len())
+    }
+
     /// Return the object identifier of the stream.
     pub fn obj_id(&self) -> ObjectIdentifier {
         self.dict.obj_id().unwrap_or_else(|| {
@@ -231,9 +245,12 @@ impl<'a> Readable<'a> for Stream<'a> {
 
 #[derive(Debug, Copy, Clone)]
 /// A failure that can occur during decoding a data stream.
+#[non_exhaustive]
 pub enum DecodeFailure {
     /// An image stream failed to decode.
     ImageDecode,
+    /// An image stream uses a feature that isn't supported by the decoder.
+    UnsupportedImage,
     /// A data stream failed to decode.
     StreamDecode,
     /// A failure occurred while decrypting a file.
@@ -373,6 +390,7 @@ mod tests {
             .unwrap();
 
         assert_eq!(stream.data, b"abcdefghij");
+        assert!(stream.has_valid_length());
     }
 
     #[test]
@@ -384,5 +402,6 @@ mod tests {
             .unwrap();
 
         assert_eq!(stream.data, b"abcdefghij");
+        assert!(!stream.has_valid_length());
     }
 }
@@ -39,6 +39,130 @@ impl<'a> String<'a> {
     }
 }
 
+/// The encoding of a text string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TextEncoding {
+    /// `PDFDocEncoding`, a superset of ISO Latin-1.
+    PdfDocEncoding,
+    /// UTF-16BE, starting with the byte order mark `FE FF`.
+    Utf16Be,
+    /// UTF-8, starting with the byte order mark `EF BB BF` (PDF 2.0).
+    Utf8,
+}
+
+impl TextEncoding {
+    /// Detect the encoding of a text string from its bytes.
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(&[0xFE, 0xFF]) {
+            Self::Utf16Be
+        } else if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            Self::Utf8
+        } else {
+            Self::PdfDocEncoding
+        }
+    }
+}
+
+/// A PDF text string, like the title of a document.
+///
+/// Stores the raw bytes of the string, which can be decoded using
+/// [`TextString::to_string_lossy`].
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct TextString(Vec<u8>);
+
+impl TextString {
+    /// Create a new text string from its raw bytes.
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self(data.into())
+    }
+
+    /// Return the raw bytes of the string, including a potential byte order mark.
+    pub fn raw_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Return the encoding of the string.
+    pub fn encoding(&self) -> TextEncoding {
+        TextEncoding::detect(&self.0)
+    }
+
+    /// Decode the string.
+    ///
+    /// Byte order marks and language identifiers are removed, and invalid characters
+    /// are replaced by the Unicode replacement character.
+    pub fn to_string_lossy(&self) -> alloc::string::String {
+        decode_text(&self.0)
+    }
+}
+
+impl From<String<'_>> for TextString {
+    fn from(value: String<'_>) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl core::fmt::Debug for TextString {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        core::fmt::Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+/// Decode the bytes of a text string.
+fn decode_text(data: &[u8]) -> alloc::string::String {
+    if let Some(data) = data.strip_prefix(&[0xFE, 0xFF]) {
+        let units = data
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]));
+        let mut text = char::decode_utf16(units)
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect::<alloc::string::String>();
+
+        // A trailing odd byte can't be a complete code unit.
+        if data.len() % 2 != 0 {
+            text.push(char::REPLACEMENT_CHARACTER);
+        }
+
+        // Remove language identifiers, which are enclosed in escape characters.
+        while let Some(start) = text.find('\u{1B}') {
+            let end = text[start + 1..]
+                .find('\u{1B}')
+                .map_or(text.len(), |end| start + 1 + end + 1);
+            text.replace_range(start..end, "");
+        }
+
+        text
+    } else if let Some(data) = data.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        alloc::string::String::from_utf8_lossy(data).into_owned()
+    } else {
+        data.iter().map(|b| pdf_doc_char(*b)).collect()
+    }
+}
+
+/// Map a byte in `PDFDocEncoding` to its character.
+fn pdf_doc_char(byte: u8) -> char {
+    const UNDEFINED: char = char::REPLACEMENT_CHARACTER;
+
+    // The ranges that differ from ISO Latin-1.
+    const LOW: [char; 8] = [
+        '\u{02D8}', '\u{02C7}', '\u{02C6}', '\u{02D9}', '\u{02DD}', '\u{02DB}', '\u{02DA}',
+        '\u{02DC}',
+    ];
+    const HIGH: [char; 33] = [
+        '\u{2022}', '\u{2020}', '\u{2021}', '\u{2026}', '\u{2014}', '\u{2013}', '\u{0192}',
+        '\u{2044}', '\u{2039}', '\u{203A}', '\u{2212}', '\u{2030}', '\u{201E}', '\u{201C}',
+        '\u{201D}', '\u{2018}', '\u{2019}', '\u{201A}', '\u{2122}', '\u{FB01}', '\u{FB02}',
+        '\u{0141}', '\u{0152}', '\u{0160}', '\u{0178}', '\u{017D}', '\u{0131}', '\u{0142}',
+        '\u{0153}', '\u{0161}', '\u{017E}', UNDEFINED, '\u{20AC}',
+    ];
+
+    match byte {
+        0x18..=0x1F => LOW[(byte - 0x18) as usize],
+        0x7F | 0xAD => UNDEFINED,
+        0x80..=0xA0 => HIGH[(byte - 0x80) as usize],
+        _ => char::from(byte),
+    }
+}
+
 impl Deref for String<'_> {
     type Target = [u8];
 
@@ -273,10 +397,136 @@ fn is_octal_digit(byte: u8) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use crate::object::String;
+    use crate::object::{String, TextEncoding, TextString};
     use crate::reader::Reader;
     use crate::reader::ReaderExt;
 
+    fn text(data: &[u8]) -> alloc::string::String {
+        Reader::new(data)
+            .read_without_context::<String<'_>>()
+            .map(TextString::from)
+            .unwrap()
+            .to_string_lossy()
+    }
+
+    #[test]
+    fn text_pdf_doc_encoding() {
+        assert_eq!(text(b"(Caf\\351 \\200 \\240)"), "Café • €");
+    }
+
+    #[test]
+    fn text_utf16() {
+        assert_eq!(text(b"<FEFF004600FC00DF>"), "Füß");
+        assert_eq!(text(b"<FEFFD83DDE00>"), "\u{1F600}");
+    }
+
+    #[test]
+    fn text_utf16_language_code() {
+        assert_eq!(text(b"<FEFF001B00640065001B00480069>"), "Hi");
+    }
+
+    #[test]
+    fn text_utf8() {
+        assert_eq!(text(b"<EFBBBFC3A4>"), "ä");
+    }
+
+    #[test]
+    fn text_pdf_doc_encoding_all_bytes() {
+        let mut seen = Vec::new();
+
+        for byte in 0..=u8::MAX {
+            let text = TextString::new([byte]).to_string_lossy();
+            let mut chars = text.chars();
+            let c = chars.next().unwrap();
+            assert_eq!(chars.next(), None, "byte {byte:#04X}");
+
+            let expected = match byte {
+                0x18..=0x1F => None,
+                0x7F | 0x9F | 0xAD => Some(char::REPLACEMENT_CHARACTER),
+                0x80..=0xA0 => None,
+                _ => Some(char::from(byte)),
+            };
+
+            if let Some(expected) = expected {
+                assert_eq!(c, expected, "byte {byte:#04X}");
+            }
+
+            // Apart from the undefined ones, no two bytes map to the same character.
+            if c != char::REPLACEMENT_CHARACTER {
+                assert!(!seen.contains(&c), "byte {byte:#04X}");
+                seen.push(c);
+            }
+        }
+
+        assert_eq!(seen.len(), 253);
+        assert_eq!(
+            TextString::new(*b"\x18\x19\x1A\x1B\x1C\x1D\x1E\x1F").to_string_lossy(),
+            "\u{02D8}\u{02C7}\u{02C6}\u{02D9}\u{02DD}\u{02DB}\u{02DA}\u{02DC}"
+        );
+    }
+
+    #[test]
+    fn text_utf16_surrogate_pairs() {
+        let mut units = [0; 2];
+
+        for c in (0x10000..=0x10FFFF)
+            .step_by(0x3F)
+            .filter_map(char::from_u32)
+        {
+            let mut data = alloc::vec![0xFE, 0xFF];
+
+            for unit in c.encode_utf16(&mut units) {
+                data.extend(unit.to_be_bytes());
+            }
+
+            assert_eq!(data.len(), 6);
+            assert_eq!(
+                TextString::new(data).to_string_lossy(),
+                alloc::string::String::from(c)
+            );
+        }
+
+        // Unpaired surrogates.
+        assert_eq!(text(b"<FEFFD83D0041>"), "\u{FFFD}A");
+        assert_eq!(text(b"<FEFF0041DE00>"), "A\u{FFFD}");
+        assert_eq!(text(b"<FEFFDE00D83D>"), "\u{FFFD}\u{FFFD}");
+        // A trailing odd byte.
+        assert_eq!(text(b"<FEFF004100>"), "A\u{FFFD}");
+    }
+
+    #[test]
+    fn text_encoding() {
+        assert_eq!(
+            TextString::new(*b"Title").encoding(),
+            TextEncoding::PdfDocEncoding
+        );
+        assert_eq!(
+            TextString::new([0xFE, 0xFF, 0x00, 0x41]).encoding(),
+            TextEncoding::Utf16Be
+        );
+        assert_eq!(
+            TextString::new([0xEF, 0xBB, 0xBF, 0x41]).encoding(),
+            TextEncoding::Utf8
+        );
+        // A lone `FE` is `PDFDocEncoding`.
+        assert_eq!(
+            TextString::new([0xFE, 0x41]).encoding(),
+            TextEncoding::PdfDocEncoding
+        );
+    }
+
+    #[test]
+    fn text_string_raw_bytes() {
+        let string = Reader::new(b"<FEFF0041>")
+            .read_without_context::<String<'_>>()
+            .unwrap();
+        let text = TextString::from(string);
+
+        assert_eq!(text.raw_bytes(), [0xFE, 0xFF, 0x00, 0x41]);
+        assert_eq!(text.to_string_lossy(), "A");
+        assert_eq!(alloc::format!("{text:?}"), "\"A\"");
+    }
+
     #[test]
     fn hex_string_empty() {
         assert_eq!(
@@ -1,6 +1,7 @@
 //! Reading the pages of a PDF document.
 
 use crate::content::{TypedIter, UntypedIter};
+use crate::image::{self, ImageRef};
 use crate::object::Array;
 use crate::object::Dict;
 use crate::object::Name;
@@ -23,7 +24,11 @@ use core::ops::Deref;
 struct PagesContext {
     media_box: Option<Rect>,
     crop_box: Option<Rect>,
+    bleed_box: Option<Rect>,
+    trim_box: Option<Rect>,
+    art_box: Option<Rect>,
     rotate: Option<i32>,
+    user_unit: Option<f32>,
 }
 
 impl PagesContext {
@@ -31,52 +36,71 @@ impl PagesContext {
         Self {
             media_box: None,
             crop_box: None,
+            bleed_box: None,
+            trim_box: None,
+            art_box: None,
             rotate: None,
+            user_unit: None,
         }
     }
 }
 
 /// A structure holding the pages of a PDF document.
+///
+/// The page tree is only walked in full once the pages are accessed as a slice
+/// (for example via indexing or iterating). Use [`Pages::page`] and [`Pages::len`]
+/// to access a single page or the number of pages without doing so.
 pub struct Pages<'a> {
-    pages: Vec<Page<'a>>,
+    // The lazily resolved state is stored with a `'static` lifetime, since a cell
+    // would make `Pages` invariant over `'a`, and it is only ever handed out with
+    // the lifetime of `xref` (see `cached::CachedPages`).
+    pages: OnceLock<Vec<Page<'static>>>,
+    count: OnceLock<usize>,
+    root: Option<Dict<'static>>,
+    ctx: ReaderContext<'static>,
     xref: &'a XRef,
 }
 
-impl<'a> Pages<'a> {
+impl Pages<'static> {
     /// Create a new `Pages` object.
     pub(crate) fn new(
-        pages_dict: &Dict<'a>,
-        ctx: &ReaderContext<'a>,
-        xref: &'a XRef,
+        pages_dict: &Dict<'static>,
+        ctx: &ReaderContext<'static>,
+        xref: &'static XRef,
     ) -> Option<Self> {
-        let mut pages = vec![];
-        let pages_ctx = PagesContext::new();
-        resolve_pages(
-            pages_dict,
-            &mut pages,
-            pages_ctx,
-            Resources::new(Dict::empty(), None, ctx),
-        )?;
+        // Make sure that the root actually is a page tree node, so that we can
+        // fall back to brute-forcing otherwise.
+        pages_dict.get::<Array<'_>>(KIDS)?;
 
-        Some(Self { pages, xref })
+        Some(Self {
+            pages: OnceLock::new(),
+            count: OnceLock::new(),
+            root: Some(pages_dict.clone()),
+            ctx: ctx.clone(),
+            xref,
+        })
     }
 
     /// Create a new `Pages` object by bruteforce-searching.
     ///
     /// Of course this could result in the order of pages being messed up, but
     /// this is still better than nothing.
-    pub(crate) fn new_brute_force(ctx: &ReaderContext<'a>, xref: &'a XRef) -> Option<Self> {
+    pub(crate) fn new_brute_force(
+        ctx: &ReaderContext<'static>,
+        xref: &'static XRef,
+    ) -> Option<Self> {
         let mut pages = vec![];
 
         for object in xref.objects() {
             if let Some(dict) = object.into_dict()
-                && let Some(page) = Page::new(
+                && let Some(mut page) = Page::new(
                     &dict,
                     &PagesContext::new(),
                     Resources::new(Dict::empty(), None, ctx),
                     true,
                 )
             {
+                page.index = pages.len();
                 pages.push(page);
             }
         }
@@ -85,29 +109,112 @@ impl<'a> Pages<'a> {
             return None;
         }
 
-        Some(Self { pages, xref })
+        Some(Self {
+            pages: OnceLock::from(pages),
+            count: OnceLock::new(),
+            root: None,
+            ctx: ctx.clone(),
+            xref,
+        })
+    }
+}
+
+impl<'a> Pages<'a> {
+    /// Return the number of pages.
+    ///
+    /// Unless the pages have already been resolved, this uses the `Count` entry
+    /// of the root of the page tree, provided that the `Count` entries of all
+    /// intermediate nodes are consistent with their kids. Otherwise, all pages are
+    /// resolved, so the result always matches the length of the slice of pages.
+    pub fn len(&self) -> usize {
+        if let Some(pages) = self.pages.get() {
+            return pages.len();
+        }
+
+        *self.count.get_or_init(|| {
+            self.root
+                .as_ref()
+                .and_then(|root| validated_count(root, 0))
+                .unwrap_or_else(|| self.resolved().len())
+        })
+    }
+
+    /// Return whether the document has no pages.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the page with the given index.
+    ///
+    /// Unless the pages have already been resolved, this descends directly to the
+    /// page by means of the `Count` entries of the page tree, without resolving any
+    /// of the other pages. In case an inconsistency is detected along the way, all
+    /// pages are resolved instead. Note that the `Count` entries of the subtrees that
+    /// are skipped are trusted.
+    pub fn page(&self, index: usize) -> Option<Page<'a>> {
+        if let Some(pages) = self.pages.get() {
+            return pages.get(index).cloned();
+        }
+
+        if index >= self.len() {
+            return None;
+        }
+
+        self.root
+            .as_ref()
+            .and_then(|root| {
+                find_page(
+                    root,
+                    index,
+                    PagesContext::new(),
+                    Resources::new(Dict::empty(), None, &self.ctx),
+                    0,
+                )
+            })
+            .map(|page| Page { index, ..page })
+            .or_else(|| self.resolved().get(index).cloned())
     }
 
     /// Return the xref table (of the document the pages belong to).   
     pub fn xref(&self) -> &'a XRef {
         self.xref
     }
+
+    fn resolved(&self) -> &[Page<'a>] {
+        self.pages.get_or_init(|| {
+            let mut pages = vec![];
+
+            if let Some(root) = &self.root {
+                resolve_pages(
+                    root,
+                    &mut pages,
+                    PagesContext::new(),
+                    Resources::new(Dict::empty(), None, &self.ctx),
+                );
+            }
+
+            pages
+        })
+    }
 }
 
 impl<'a> Deref for Pages<'a> {
     type Target = [Page<'a>];
 
     fn deref(&self) -> &Self::Target {
-        &self.pages
+        self.resolved()
     }
 }
 
-fn resolve_pages<'a>(
+/// The maximum depth of the page tree when descending to a single page.
+const MAX_PAGE_TREE_DEPTH: usize = 256;
+
+/// Apply the inheritable attributes of a page tree node.
+fn inherit<'a>(
     pages_dict: &Dict<'a>,
-    entries: &mut Vec<Page<'a>>,
-    mut ctx: PagesContext,
+    ctx: &mut PagesContext,
     resources: Resources<'a>,
-) -> Option<()> {
+) -> Resources<'a> {
     if let Some(media_box) = pages_dict.get::<Rect>(MEDIA_BOX) {
         ctx.media_box = Some(media_box);
     }
@@ -120,10 +227,38 @@ fn resolve_pages<'a>(
         ctx.rotate = Some(rotate);
     }
 
-    let resources = Resources::from_parent(
+    // Strictly speaking, `UserUnit` isn't inheritable, but some producers put it
+    // into the page tree nodes anyway.
+    if let Some(user_unit) = pages_dict.get::<f32>(USER_UNIT) {
+        ctx.user_unit = Some(user_unit);
+    }
+
+    // The same applies to the bleed, trim and art boxes.
+    if let Some(bleed_box) = pages_dict.get::<Rect>(BLEED_BOX) {
+        ctx.bleed_box = Some(bleed_box);
+    }
+
+    if let Some(trim_box) = pages_dict.get::<Rect>(TRIM_BOX) {
+        ctx.trim_box = Some(trim_box);
+    }
+
+    if let Some(art_box) = pages_dict.get::<Rect>(ART_BOX) {
+        ctx.art_box = Some(art_box);
+    }
+
+    Resources::from_parent(
         pages_dict.get::<Dict<'_>>(RESOURCES).unwrap_or_default(),
-        resources.clone(),
-    );
+        resources,
+    )
+}
+
+fn resolve_pages<'a>(
+    pages_dict: &Dict<'a>,
+    entries: &mut Vec<Page<'a>>,
+    mut ctx: PagesContext,
+    resources: Resources<'a>,
+) -> Option<()> {
+    let resources = inherit(pages_dict, &mut ctx, resources);
 
     let kids = pages_dict.get::<Array<'a>>(KIDS)?;
 
@@ -135,7 +270,8 @@ fn resolve_pages<'a>(
             // Let's be lenient and assume it's a `Page` in case it's `None` or something else
             // (see corpus test case 0083781).
             _ => {
-                if let Some(page) = Page::new(&dict, &ctx, resources.clone(), false) {
+                if let Some(mut page) = Page::new(&dict, &ctx, resources.clone(), false) {
+                    page.index = entries.len();
                     entries.push(page);
                 }
             }
@@ -145,10 +281,96 @@ fn resolve_pages<'a>(
     Some(())
 }
 
+/// The kids of a page tree node, together with the `Count` entry of those
+/// that are intermediate nodes.
+type Kids<'a> = Vec<(Dict<'a>, Option<usize>)>;
+
+/// Return the `Count` entry of a page tree node together with its kids, provided
+/// that it matches the number of pages of the kids.
+fn checked_count<'a>(pages_dict: &Dict<'a>) -> Option<(usize, Kids<'a>)> {
+    let count = pages_dict.get::<usize>(COUNT)?;
+    let kids = pages_dict
+        .get::<Array<'a>>(KIDS)?
+        .iter::<Dict<'a>>()
+        .map(|dict| match dict.get::<Name<'_>>(TYPE).as_deref() {
+            Some(PAGES) => {
+                let count = dict.get::<usize>(COUNT)?;
+                Some((dict, Some(count)))
+            }
+            _ => Some((dict, None)),
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let sum = kids.iter().try_fold(0_usize, |sum, (_, count)| {
+        sum.checked_add(count.unwrap_or(1))
+    })?;
+
+    (sum == count).then_some((count, kids))
+}
+
+/// Return the number of pages below a page tree node, provided that the `Count`
+/// entries of the node and all of its descendants are consistent with their kids.
+fn validated_count(pages_dict: &Dict<'_>, depth: usize) -> Option<usize> {
+    if depth > MAX_PAGE_TREE_DEPTH {
+        return None;
+    }
+
+    let (count, kids) = checked_count(pages_dict)?;
+
+    for (dict, kid_count) in kids {
+        if let Some(kid_count) = kid_count
+            && validated_count(&dict, depth + 1)? != kid_count
+        {
+            return None;
+        }
+    }
+
+    Some(count)
+}
+
+/// Descend to the page with the given index by means of the `Count` entries
+/// of the page tree, returning `None` if the page tree is inconsistent.
+fn find_page<'a>(
+    pages_dict: &Dict<'a>,
+    mut index: usize,
+    mut ctx: PagesContext,
+    resources: Resources<'a>,
+    depth: usize,
+) -> Option<Page<'a>> {
+    if depth > MAX_PAGE_TREE_DEPTH {
+        return None;
+    }
+
+    let resources = inherit(pages_dict, &mut ctx, resources);
+    let (_, kids) = checked_count(pages_dict)?;
+
+    for (dict, count) in kids {
+        match count {
+            Some(count) => {
+                if index < count {
+                    return find_page(&dict, index, ctx, resources, depth + 1);
+                }
+
+                index -= count;
+            }
+            None => {
+                if index == 0 {
+                    return Page::new(&dict, &ctx, resources, false);
+                }
+
+                index -= 1;
+            }
+        }
+    }
+
+    None
+}
+
 /// The rotation of the page.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub enum Rotation {
     /// No rotation.
+    #[default]
     None,
     /// A rotation of 90 degrees.
     Horizontal,
@@ -158,17 +380,62 @@ pub enum Rotation {
     FlippedHorizontal,
 }
 
+impl Rotation {
+    /// Create a rotation from an angle in degrees (clockwise).
+    ///
+    /// Angles that are not a multiple of 90 degrees result in no rotation.
+    pub fn from_degrees(degrees: i32) -> Self {
+        match degrees.rem_euclid(360) {
+            90 => Self::Horizontal,
+            180 => Self::Flipped,
+            270 => Self::FlippedHorizontal,
+            _ => Self::None,
+        }
+    }
+
+    /// Return the angle of the rotation in degrees (clockwise).
+    pub fn degrees(self) -> i32 {
+        match self {
+            Self::None => 0,
+            Self::Horizontal => 90,
+            Self::Flipped => 180,
+            Self::FlippedHorizontal => 270,
+        }
+    }
+
+    /// Return the rotation that results from applying `other` in addition to
+    /// this rotation.
+    pub fn then(self, other: Self) -> Self {
+        Self::from_degrees(self.degrees() + other.degrees())
+    }
+}
+
 /// A PDF page.
+#[derive(Clone)]
 pub struct Page<'a> {
     inner: Dict<'a>,
     media_box: Rect,
     crop_box: Rect,
+    bleed_box: Rect,
+    trim_box: Rect,
+    art_box: Rect,
     rotation: Rotation,
-    page_streams: OnceLock<Option<Vec<u8>>>,
+    user_unit: f32,
+    index: usize,
+    page_streams: OnceLock<Option<PageStreams>>,
     resources: Resources<'a>,
     ctx: ReaderContext<'a>,
 }
 
+/// The decoded content streams of a page.
+#[derive(Clone)]
+struct PageStreams {
+    data: Vec<u8>,
+    /// The offsets at which the individual streams start in `data`. Empty if
+    /// the contents only consist of a single stream.
+    starts: Vec<usize>,
+}
+
 impl<'a> Page<'a> {
     fn new(
         dict: &Dict<'a>,
@@ -189,18 +456,26 @@ impl<'a> Page<'a> {
             .or(ctx.crop_box)
             .unwrap_or(media_box);
 
-        let rotation = match dict
-            .get::<i32>(ROTATE)
-            .or(ctx.rotate)
-            .unwrap_or(0)
-            .rem_euclid(360)
-        {
-            0 => Rotation::None,
-            90 => Rotation::Horizontal,
-            180 => Rotation::Flipped,
-            270 => Rotation::FlippedHorizontal,
-            _ => Rotation::None,
-        };
+        let bleed_box = dict
+            .get::<Rect>(BLEED_BOX)
+            .or(ctx.bleed_box)
+            .unwrap_or(crop_box);
+        let trim_box = dict
+            .get::<Rect>(TRIM_BOX)
+            .or(ctx.trim_box)
+            .unwrap_or(crop_box);
+        let art_box = dict
+            .get::<Rect>(ART_BOX)
+            .or(ctx.art_box)
+            .unwrap_or(crop_box);
+
+        let rotation = Rotation::from_degrees(dict.get::<i32>(ROTATE).or(ctx.rotate).unwrap_or(0));
+
+        let user_unit = dict
+            .get::<f32>(USER_UNIT)
+            .or(ctx.user_unit)
+            .filter(|u| u.is_finite() && *u > 0.0)
+            .unwrap_or(1.0);
 
         let ctx = resources.ctx.clone();
         let resources = Resources::from_parent(
@@ -212,7 +487,12 @@ impl<'a> Page<'a> {
             inner: dict.clone(),
             media_box,
             crop_box,
+            bleed_box,
+            trim_box,
+            art_box,
             rotation,
+            user_unit,
+            index: 0,
             page_streams: OnceLock::new(),
             resources,
             ctx,
@@ -220,14 +500,23 @@ impl<'a> Page<'a> {
     }
 
     fn operations_impl(&self) -> Option<UntypedIter<'_>> {
-        let stream = self.page_stream()?;
-        let iter = UntypedIter::new(stream);
+        let streams = self.page_streams()?;
+        let iter = UntypedIter::new(&streams.data).with_stream_starts(&streams.starts);
 
         Some(iter)
     }
 
     /// Return the decoded content stream of the page.
+    ///
+    /// If the `Contents` entry of the page is an array of streams, the decoded
+    /// streams are concatenated, separated by a single space, so that tokens
+    /// at the end of one stream and the start of the next one are not merged.
+    /// Operators may be split across stream boundaries.
     pub fn page_stream(&self) -> Option<&[u8]> {
+        self.page_streams().map(|s| s.data.as_slice())
+    }
+
+    fn page_streams(&self) -> Option<&PageStreams> {
         let convert_single = |s: Stream<'_>| {
             let data = s.decoded().ok()?;
             Some(data.to_vec())
@@ -236,19 +525,30 @@ impl<'a> Page<'a> {
         self.page_streams
             .get_or_init(|| {
                 if let Some(stream) = self.inner.get::<Stream<'_>>(CONTENTS) {
-                    convert_single(stream)
+                    convert_single(stream).map(|data| PageStreams {
+                        data,
+                        starts: vec![],
+                    })
                 } else if let Some(array) = self.inner.get::<Array<'_>>(CONTENTS) {
-                    let streams = array.iter::<Stream<'_>>().flat_map(convert_single);
-
                     let mut collected = vec![];
-
-                    for stream in streams {
-                        collected.extend(stream);
-                        // Streams must have at least one whitespace in-between.
-                        collected.push(b' ');
+                    let mut starts = vec![];
+
+                    for stream in array.iter::<Stream<'_>>() {
+                        // Record the start of streams that fail to decode as well,
+                        // so that stream indices match the indices in the array.
+                        starts.push(collected.len());
+
+                        if let Some(data) = convert_single(stream) {
+                            collected.extend(data);
+                            // Streams must have at least one whitespace in-between.
+                            collected.push(b' ');
+                        }
                     }
 
-                    Some(collected)
+                    Some(PageStreams {
+                        data: collected,
+                        starts,
+                    })
                 } else {
                     warn!("contents entry of page was neither stream nor array of streams");
 
@@ -256,7 +556,6 @@ impl<'a> Page<'a> {
                 }
             })
             .as_ref()
-            .map(|d| d.as_slice())
     }
 
     /// Get the resources of the page.
@@ -279,18 +578,69 @@ impl<'a> Page<'a> {
         self.crop_box
     }
 
+    /// Get the bleed box of the page, i.e. the region to which the contents of
+    /// the page should be clipped when output in a production environment.
+    ///
+    /// Defaults to the crop box if the page doesn't define one.
+    pub fn bleed_box(&self) -> Rect {
+        self.bleed_box
+    }
+
+    /// Get the trim box of the page, i.e. the intended dimensions of the finished
+    /// page after trimming.
+    ///
+    /// Defaults to the crop box if the page doesn't define one.
+    pub fn trim_box(&self) -> Rect {
+        self.trim_box
+    }
+
+    /// Get the art box of the page, i.e. the extent of the meaningful content
+    /// of the page as intended by its creator.
+    ///
+    /// Defaults to the crop box if the page doesn't define one.
+    pub fn art_box(&self) -> Rect {
+        self.art_box
+    }
+
+    /// Get the size of a unit in default user space, in multiples of 1/72 inch.
+    ///
+    /// This is used by documents with pages that would otherwise exceed the maximum
+    /// page size of 14400 units, for example large engineering drawings. The media
+    /// box and crop box are expressed in these units, while
+    /// [`Page::render_dimensions`] and [`Page::initial_transform`] already take the
+    /// user unit into account.
+    pub fn user_unit(&self) -> f32 {
+        self.user_unit
+    }
+
+    /// Return the index of the page in the document, starting at 0.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
     /// Return the intersection of crop box and media box.
+    ///
+    /// If the intersection has a zero area (for example because the crop box is
+    /// empty or lies outside of the media box), the media box is returned instead.
     pub fn intersected_crop_box(&self) -> Rect {
-        self.crop_box().intersect(self.media_box())
+        let crop_box = self.crop_box().intersect(self.media_box());
+
+        if is_degenerate(&crop_box) {
+            self.media_box()
+        } else {
+            crop_box
+        }
     }
 
     /// Return the base dimensions of the page (same as `intersected_crop_box`, but with special
     /// handling applied for zero-area pages).
+    ///
+    /// Note that the dimensions are in user space units, i.e. they are not scaled by
+    /// [`Page::user_unit`].
     pub fn base_dimensions(&self) -> (f32, f32) {
         let crop_box = self.intersected_crop_box();
 
-        if (crop_box.width() as f32).is_nearly_zero() || (crop_box.height() as f32).is_nearly_zero()
-        {
+        if is_degenerate(&crop_box) {
             (A4.width() as f32, A4.height() as f32)
         } else {
             (
@@ -303,14 +653,25 @@ impl<'a> Page<'a> {
     /// Return the with and height of the page that should be assumed when rendering the page.
     ///
     /// Depending on the document, it is either based on the media box or the crop box
-    /// of the page. In addition to that, it also takes the rotation of the page into account.
+    /// of the page. In addition to that, it also takes the rotation and the user unit of
+    /// the page into account.
     pub fn render_dimensions(&self) -> (f32, f32) {
+        self.render_dimensions_with_rotation(Rotation::None)
+    }
+
+    /// Like [`Page::render_dimensions`], but with an additional rotation that is
+    /// applied on top of the rotation of the page.
+    pub fn render_dimensions_with_rotation(&self, extra_rotation: Rotation) -> (f32, f32) {
+        let (width, height) = self.rotated_dimensions(self.rotation.then(extra_rotation));
+
+        (width * self.user_unit, height * self.user_unit)
+    }
+
+    /// Return the base dimensions with the given rotation applied.
+    fn rotated_dimensions(&self, rotation: Rotation) -> (f32, f32) {
         let (mut base_width, mut base_height) = self.base_dimensions();
 
-        if matches!(
-            self.rotation(),
-            Rotation::Horizontal | Rotation::FlippedHorizontal
-        ) {
+        if matches!(rotation, Rotation::Horizontal | Rotation::FlippedHorizontal) {
             core::mem::swap(&mut base_width, &mut base_height);
         }
 
@@ -322,6 +683,38 @@ impl<'a> Page<'a> {
         self.operations_impl().unwrap_or(UntypedIter::empty())
     }
 
+    /// Return the embedded thumbnail image of the page, if available.
+    ///
+    /// The thumbnail is an image `XObject` stream, which can be decoded like
+    /// any other image.
+    pub fn thumbnail(&self) -> Option<Stream<'a>> {
+        self.inner.get::<Stream<'a>>(THUMB)
+    }
+
+    /// Return the images of the page.
+    ///
+    /// This includes all image `XObject`s in the resources of the page as well
+    /// as the inline images in its content stream. Note that the content stream
+    /// isn't checked for whether an image `XObject` is actually drawn, so images
+    /// that are listed in the resources but never used are returned as well.
+    /// Images in the resources of form `XObject`s are not included, see
+    /// [`Page::images_with_forms`] for that.
+    pub fn images(&self) -> Vec<ImageRef<'_>> {
+        image::collect(self, false)
+    }
+
+    /// Return the images of the page, including the image `XObject`s in the
+    /// resources of (nested) form `XObject`s.
+    ///
+    /// As with [`Page::images`], this returns all image `XObject`s in the
+    /// resources, whether they are drawn or not.
+    ///
+    /// Note that inline images in the content streams of form `XObject`s are not
+    /// included.
+    pub fn images_with_forms(&self) -> Vec<ImageRef<'_>> {
+        image::collect(self, true)
+    }
+
     /// Get the raw dictionary of the page.
     pub fn raw(&self) -> &Dict<'a> {
         &self.inner
@@ -340,18 +733,30 @@ impl<'a> Page<'a> {
     /// Return the initial transform that should be applied when rendering.
     ///
     /// This accounts for the mismatch between PDF's y-up and most renderers'
-    /// y-down coordinate system, the rotation of the page and the offset of
-    /// the crop box.
+    /// y-down coordinate system, the rotation of the page, the offset of
+    /// the crop box and the user unit.
     pub fn initial_transform(&self, invert_y: bool) -> Transform {
+        self.initial_transform_with_rotation(invert_y, Rotation::None)
+    }
+
+    /// Like [`Page::initial_transform`], but with an additional rotation that is
+    /// applied on top of the rotation of the page, for example to correct pages
+    /// with a wrong `/Rotate` entry.
+    pub fn initial_transform_with_rotation(
+        &self,
+        invert_y: bool,
+        extra_rotation: Rotation,
+    ) -> Transform {
+        let rotation = self.rotation.then(extra_rotation);
         let crop_box = self.intersected_crop_box();
         let (_, base_height) = self.base_dimensions();
-        let (width, height) = self.render_dimensions();
+        let (width, height) = self.rotated_dimensions(rotation);
 
         let horizontal_t = Transform::ROTATE_CW_90 * Transform::translate((0.0, -width as f64));
         let flipped_horizontal_t =
             Transform::translate((0.0, height as f64)) * Transform::ROTATE_CCW_90;
 
-        let rotation_transform = match self.rotation() {
+        let rotation_transform = match rotation {
             Rotation::None => Transform::IDENTITY,
             Rotation::Horizontal => {
                 if invert_y {
@@ -378,7 +783,8 @@ impl<'a> Page<'a> {
             Transform::IDENTITY
         };
 
-        rotation_transform
+        Transform::scale(self.user_unit as f64)
+            * rotation_transform
             * inversion_transform
             * Transform::translate((-crop_box.x0, -crop_box.y0))
     }
@@ -407,6 +813,12 @@ pub struct Resources<'a> {
 
 impl<'a> Resources<'a> {
     /// Create a new `Resources` object from a dictionary with a parent.
+    ///
+    /// Resources that can't be found in the dictionary are looked up in the
+    /// parent. For a form `XObject`, the parent should be the resources of the
+    /// content stream invoking it, which in turn end with the resources of the
+    /// page. This way, form `XObject`s without their own resources can still use
+    /// the ones of their invoking context, as Acrobat does in practice.
     pub fn from_parent(resources: Dict<'a>, parent: Self) -> Self {
         let ctx = parent.ctx.clone();
 
@@ -438,8 +850,17 @@ impl<'a> Resources<'a> {
         }
     }
 
-    fn get_resource<T: ObjectLike<'a>>(&self, name: &Name<'_>, dict: &Dict<'a>) -> Option<T> {
-        dict.get::<T>(name.deref())
+    /// Look up a resource by name, first in these resources and then in the
+    /// parents, from the innermost to the outermost one.
+    fn get_resource<T: ObjectLike<'a>>(
+        &self,
+        name: &Name<'_>,
+        dict: impl Fn(&Self) -> &Dict<'a>,
+    ) -> Option<T> {
+        // Walk the chain iteratively, so that the lookup doesn't recurse when the
+        // chain is very long.
+        core::iter::successors(Some(self), |r| r.parent())
+            .find_map(|r| dict(r).get::<T>(name.deref()))
     }
 
     /// Get the parent in the resource, chain, if available.
@@ -449,38 +870,32 @@ impl<'a> Resources<'a> {
 
     /// Get an external graphics state by name.
     pub fn get_ext_g_state(&self, name: &Name<'_>) -> Option<Dict<'a>> {
-        self.get_resource::<Dict<'_>>(name, &self.ext_g_states)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_ext_g_state(name)))
+        self.get_resource::<Dict<'_>>(name, |r| &r.ext_g_states)
     }
 
     /// Get a color space by name.
     pub fn get_color_space(&self, name: &Name<'_>) -> Option<Object<'a>> {
-        self.get_resource::<Object<'_>>(name, &self.color_spaces)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_color_space(name)))
+        self.get_resource::<Object<'_>>(name, |r| &r.color_spaces)
     }
 
     /// Get a font by name.
     pub fn get_font(&self, name: &Name<'_>) -> Option<Dict<'a>> {
-        self.get_resource::<Dict<'_>>(name, &self.fonts)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_font(name)))
+        self.get_resource::<Dict<'_>>(name, |r| &r.fonts)
     }
 
     /// Get a pattern by name.
     pub fn get_pattern(&self, name: &Name<'_>) -> Option<Object<'a>> {
-        self.get_resource::<Object<'_>>(name, &self.patterns)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_pattern(name)))
+        self.get_resource::<Object<'_>>(name, |r| &r.patterns)
     }
 
     /// Get an x object by name.
     pub fn get_x_object(&self, name: &Name<'_>) -> Option<Stream<'a>> {
-        self.get_resource::<Stream<'_>>(name, &self.x_objects)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_x_object(name)))
+        self.get_resource::<Stream<'_>>(name, |r| &r.x_objects)
     }
 
     /// Get a shading by name.
     pub fn get_shading(&self, name: &Name<'_>) -> Option<Object<'a>> {
-        self.get_resource::<Object<'_>>(name, &self.shadings)
-            .or_else(|| self.parent.as_ref().and_then(|p| p.get_shading(name)))
+        self.get_resource::<Object<'_>>(name, |r| &r.shadings)
     }
 }
 
@@ -496,8 +911,12 @@ pub const A4: Rect = Rect {
     y1: 297.0 * POINTS_PER_MM,
 };
 
+fn is_degenerate(rect: &Rect) -> bool {
+    (rect.width() as f32).is_nearly_zero() || (rect.height() as f32).is_nearly_zero()
+}
+
 pub(crate) mod cached {
-    use crate::page::Pages;
+    use crate::page::{Page, Pages};
     use crate::reader::ReaderContext;
     use crate::xref::XRef;
     use core::ops::Deref;
@@ -540,5 +959,399 @@ pub(crate) mod cached {
         pub(crate) fn get(&self) -> &Pages<'_> {
             &self.pages
         }
+
+        pub(crate) fn page(&self, index: usize) -> Option<Page<'_>> {
+            self.pages.page(index)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::content::TypedIter;
+    use crate::content::ops::{
+        FillPathNonZero, NonStrokeColorDeviceRgb, RectPath, TypedInstruction,
+    };
+    use crate::object::{Number, Rect};
+    use crate::page::{A4, Page, Rotation};
+    use crate::pdf::Pdf;
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn n(num: i32) -> Number {
+        Number::from_i32(num)
+    }
+
+    fn build_pdf(objects: &[String]) -> Vec<u8> {
+        let mut out = String::from("%PDF-1.7\n");
+        let mut offsets = vec![];
+
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.push_str(&format!("{} 0 obj\n{object}\nendobj\n", i + 1));
+        }
+
+        let xref_offset = out.len();
+        out.push_str(&format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            objects.len() + 1
+        ));
+
+        for offset in offsets {
+            out.push_str(&format!("{offset:010} 00000 n \n"));
+        }
+
+        out.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            objects.len() + 1
+        ));
+
+        out.into_bytes()
+    }
+
+    fn pdf_with_contents(contents: &str, streams: &[&str]) -> Vec<u8> {
+        let page =
+            format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 10 10] /Contents {contents} >>");
+        let mut objects = vec![
+            String::from("<< /Type /Catalog /Pages 2 0 R >>"),
+            String::from("<< /Type /Pages /Kids [3 0 R] /Count 1 >>"),
+            page,
+        ];
+
+        for stream in streams {
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{stream}\nendstream",
+                stream.len()
+            ));
+        }
+
+        build_pdf(&objects)
+    }
+
+    /// A page tree with two intermediate nodes and five pages, where each page
+    /// has a different width depending on the attributes it inherits.
+    fn pdf_with_page_tree(root_count: usize, first_count: usize) -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".into(),
+            format!(
+                "<< /Type /Pages /Kids [3 0 R 4 0 R] /Count {root_count} /MediaBox [0 0 100 100] >>"
+            ),
+            format!(
+                "<< /Type /Pages /Parent 2 0 R /Kids [5 0 R 6 0 R] /Count {first_count} /Rotate 90 >>"
+            ),
+            "<< /Type /Pages /Parent 2 0 R /Kids [7 0 R 8 0 R 9 0 R] /Count 3 /MediaBox [0 0 200 100] >>".into(),
+            "<< /Type /Page /Parent 3 0 R >>".into(),
+            "<< /Type /Page /Parent 3 0 R /MediaBox [0 0 150 100] >>".into(),
+            "<< /Type /Page /Parent 4 0 R >>".into(),
+            "<< /Type /Page /Parent 4 0 R /MediaBox [0 0 250 100] >>".into(),
+            "<< /Type /Page /Parent 4 0 R /Rotate 180 >>".into(),
+        ];
+
+        build_pdf(&objects)
+    }
+
+    fn check_page_tree(pdf: &Pdf) {
+        let expected = [
+            (100.0, Rotation::Horizontal),
+            (150.0, Rotation::Horizontal),
+            (200.0, Rotation::None),
+            (250.0, Rotation::None),
+            (200.0, Rotation::Flipped),
+        ];
+
+        assert_eq!(pdf.pages().len(), expected.len());
+
+        for (i, (width, rotation)) in expected.into_iter().enumerate() {
+            let page = pdf.page(i).unwrap();
+
+            assert_eq!(page.media_box().x1, width);
+            assert_eq!(page.rotation() as u8, rotation as u8);
+            assert_eq!(page.index(), i);
+        }
+
+        for (i, page) in pdf.pages().iter().enumerate() {
+            assert_eq!(page.index(), i);
+        }
+
+        assert!(pdf.page(expected.len()).is_none());
+    }
+
+    fn check_ops(data: &[u8]) {
+        let mut iter = TypedIter::new(data);
+
+        assert!(matches!(
+            iter.next(),
+            Some(TypedInstruction::NonStrokeColorDeviceRgb(NonStrokeColorDeviceRgb(r, g, b)))
+                if [r, g, b] == [n(1), n(0), n(0)]
+        ));
+        assert!(matches!(
+            iter.next(),
+            Some(TypedInstruction::RectPath(RectPath(x, y, w, h)))
+                if [x, y, w, h] == [n(0), n(0), n(10), n(10)]
+        ));
+        assert!(matches!(
+            iter.next(),
+            Some(TypedInstruction::FillPathNonZero(FillPathNonZero))
+        ));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn page_stream_single() {
+        let pdf = Pdf::new(pdf_with_contents("4 0 R", &["1 0 0 rg 0 0 10 10 re f"])).unwrap();
+        let page = &pdf.pages()[0];
+
+        check_ops(page.page_stream().unwrap());
+    }
+
+    #[test]
+    fn page_stream_split_mid_operator() {
+        // The operands of `re` are split across the two streams, and neither
+        // stream has whitespace at the boundary.
+        let pdf = Pdf::new(pdf_with_contents(
+            "[4 0 R 5 0 R]",
+            &["1 0 0 rg 0 0 10", "10 re f"],
+        ))
+        .unwrap();
+        let page = &pdf.pages()[0];
+
+        check_ops(page.page_stream().unwrap());
+    }
+
+    fn collect_ops(page: &Page<'_>) -> Vec<(Vec<u8>, Vec<String>)> {
+        let mut iter = page.operations();
+        let mut ops = vec![];
+
+        while let Some(op) = iter.next() {
+            let operands = op.operands().map(|o| format!("{o:?}")).collect();
+            ops.push((op.operator.to_vec(), operands));
+        }
+
+        ops
+    }
+
+    #[test]
+    fn page_stream_split_matches_joined() {
+        // The operands of `cm` end the first stream and the operator starts
+        // the second one.
+        let joined = Pdf::new(pdf_with_contents(
+            "4 0 R",
+            &["q 1 0 0 1 50 50 cm 0 0 10 10 re f Q"],
+        ))
+        .unwrap();
+        let split = Pdf::new(pdf_with_contents(
+            "[4 0 R 5 0 R]",
+            &["q 1 0 0 1 50 50", "cm 0 0 10 10 re f Q"],
+        ))
+        .unwrap();
+
+        let joined_ops = collect_ops(&joined.pages()[0]);
+        assert_eq!(joined_ops.len(), 5);
+        assert_eq!(joined_ops, collect_ops(&split.pages()[0]));
+    }
+
+    #[test]
+    fn page_stream_locations() {
+        let pdf = Pdf::new(pdf_with_contents(
+            "[4 0 R 5 0 R]",
+            &["1 0 0 rg 0 0 10", "10 re  f"],
+        ))
+        .unwrap();
+        let page = &pdf.pages()[0];
+        let mut iter = page.operations();
+        let mut locations = vec![];
+
+        while let Some((_, location)) = iter.next_with_location() {
+            locations.push((location.stream, location.offset));
+        }
+
+        // The `re` operator starts in the first stream, while `f` is located
+        // at offset 7 of the second one.
+        assert_eq!(locations, vec![(0, 0), (0, 9), (1, 7)]);
+    }
+
+    #[test]
+    fn page_tree_lazy() {
+        let pdf = Pdf::new(pdf_with_page_tree(5, 2)).unwrap();
+        check_page_tree(&pdf);
+    }
+
+    #[test]
+    fn page_tree_lazy_after_resolving() {
+        let pdf = Pdf::new(pdf_with_page_tree(5, 2)).unwrap();
+        assert_eq!(pdf.pages().iter().count(), 5);
+        check_page_tree(&pdf);
+    }
+
+    #[test]
+    fn page_tree_invalid_root_count() {
+        let pdf = Pdf::new(pdf_with_page_tree(7, 2)).unwrap();
+        check_page_tree(&pdf);
+    }
+
+    #[test]
+    fn page_tree_invalid_node_count() {
+        let pdf = Pdf::new(pdf_with_page_tree(5, 1)).unwrap();
+        check_page_tree(&pdf);
+    }
+
+    #[test]
+    fn page_tree_invalid_nested_count() {
+        // The root count is consistent with the counts of its kids, but the
+        // count of the first kid is wrong, so all pages are resolved instead.
+        let pdf = Pdf::new(pdf_with_page_tree(4, 1)).unwrap();
+        assert_eq!(pdf.pages().len(), 5);
+        assert_eq!(pdf.page(0).unwrap().media_box().x1, 100.0);
+        check_page_tree(&pdf);
+    }
+
+    #[test]
+    fn page_user_unit() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".into(),
+            String::from(
+                "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /MediaBox [0 0 2000 1000] /UserUnit 10 >>",
+            ),
+            "<< /Type /Page /Parent 2 0 R >>".into(),
+            "<< /Type /Page /Parent 2 0 R /UserUnit 2 /Rotate 90 /CropBox [100 0 2000 1000] >>"
+                .into(),
+            "<< /Type /Page /Parent 2 0 R /UserUnit -1 >>".into(),
+        ];
+        let pdf = Pdf::new(build_pdf(&objects)).unwrap();
+
+        let page = pdf.page(0).unwrap();
+        assert_eq!(page.user_unit(), 10.0);
+        assert_eq!(page.base_dimensions(), (2000.0, 1000.0));
+        assert_eq!(page.render_dimensions(), (20000.0, 10000.0));
+        assert_eq!(
+            page.initial_transform(true).as_coeffs(),
+            [10.0, 0.0, 0.0, -10.0, 0.0, 10000.0]
+        );
+
+        let page = pdf.page(1).unwrap();
+        assert_eq!(page.user_unit(), 2.0);
+        assert_eq!(page.render_dimensions(), (2000.0, 3800.0));
+        assert_eq!(
+            page.initial_transform(false).as_coeffs(),
+            [0.0, -2.0, 2.0, 0.0, 0.0, 4000.0]
+        );
+
+        assert_eq!(pdf.page(2).unwrap().user_unit(), 1.0);
+    }
+
+    #[test]
+    fn page_boundary_boxes() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".into(),
+            String::from(
+                "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /MediaBox [0 0 200 100] >>",
+            ),
+            "<< /Type /Page /Parent 2 0 R /TrimBox [10 10 190 90] /ArtBox [20 20 180 80] >>".into(),
+            "<< /Type /Page /Parent 2 0 R /CropBox [5 5 195 95] >>".into(),
+            "<< /Type /Pages /Parent 2 0 R /Kids [6 0 R] /Count 1 /BleedBox [2 2 198 98] >>".into(),
+            "<< /Type /Page /Parent 5 0 R >>".into(),
+        ];
+        let pdf = Pdf::new(build_pdf(&objects)).unwrap();
+        let coords = |r: Rect| (r.x0, r.y0, r.x1, r.y1);
+
+        // The bleed box defaults to the crop box, which defaults to the media box.
+        let page = pdf.page(0).unwrap();
+        assert_eq!(coords(page.trim_box()), (10.0, 10.0, 190.0, 90.0));
+        assert_eq!(coords(page.art_box()), (20.0, 20.0, 180.0, 80.0));
+        assert_eq!(coords(page.bleed_box()), (0.0, 0.0, 200.0, 100.0));
+        assert_eq!(coords(page.media_box()), (0.0, 0.0, 200.0, 100.0));
+
+        let page = pdf.page(1).unwrap();
+        assert_eq!(coords(page.bleed_box()), (5.0, 5.0, 195.0, 95.0));
+        assert_eq!(coords(page.trim_box()), (5.0, 5.0, 195.0, 95.0));
+        assert_eq!(coords(page.art_box()), (5.0, 5.0, 195.0, 95.0));
+
+        let page = pdf.page(2).unwrap();
+        assert_eq!(coords(page.bleed_box()), (2.0, 2.0, 198.0, 98.0));
+        assert_eq!(coords(page.trim_box()), (0.0, 0.0, 200.0, 100.0));
+    }
+
+    #[test]
+    fn page_degenerate_crop_box() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".into(),
+            String::from(
+                "<< /Type /Pages /Kids [3 0 R 4 0 R 5 0 R] /Count 3 /MediaBox [0 0 200 100] >>",
+            ),
+            "<< /Type /Page /Parent 2 0 R /CropBox [0 0 0 0] >>".into(),
+            "<< /Type /Page /Parent 2 0 R /CropBox [300 0 400 100] /Rotate 90 >>".into(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 0 100] >>".into(),
+        ];
+        let pdf = Pdf::new(build_pdf(&objects)).unwrap();
+
+        let page = pdf.page(0).unwrap();
+        let crop_box = page.intersected_crop_box();
+        assert_eq!(
+            (crop_box.x0, crop_box.y0, crop_box.x1, crop_box.y1),
+            (0.0, 0.0, 200.0, 100.0)
+        );
+        assert_eq!(page.render_dimensions(), (200.0, 100.0));
+
+        let page = pdf.page(1).unwrap();
+        let crop_box = page.intersected_crop_box();
+        assert_eq!(
+            (crop_box.x0, crop_box.y0, crop_box.x1, crop_box.y1),
+            (0.0, 0.0, 200.0, 100.0)
+        );
+        assert_eq!(page.render_dimensions(), (100.0, 200.0));
+
+        let page = pdf.page(2).unwrap();
+        assert_eq!(
+            page.base_dimensions(),
+            (A4.width() as f32, A4.height() as f32)
+        );
+    }
+
+    #[test]
+    fn page_extra_rotation() {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".into(),
+            String::from("<< /Type /Pages /Kids [3 0 R 4 0 R] /Count 2 /MediaBox [0 0 100 200] >>"),
+            "<< /Type /Page /Parent 2 0 R >>".into(),
+            "<< /Type /Page /Parent 2 0 R /Rotate 270 >>".into(),
+        ];
+        let pdf = Pdf::new(build_pdf(&objects)).unwrap();
+
+        let page = pdf.page(0).unwrap();
+        assert_eq!(
+            page.render_dimensions_with_rotation(Rotation::Horizontal),
+            (200.0, 100.0)
+        );
+        assert_eq!(
+            page.initial_transform_with_rotation(true, Rotation::Horizontal)
+                .as_coeffs(),
+            [0.0, 1.0, 1.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            page.initial_transform_with_rotation(true, Rotation::None)
+                .as_coeffs(),
+            page.initial_transform(true).as_coeffs()
+        );
+
+        // The extra rotation is applied on top of the rotation of the page.
+        let page = pdf.page(1).unwrap();
+        assert_eq!(page.render_dimensions(), (200.0, 100.0));
+        assert_eq!(
+            page.render_dimensions_with_rotation(Rotation::Horizontal),
+            (100.0, 200.0)
+        );
+        assert_eq!(
+            page.initial_transform_with_rotation(true, Rotation::Horizontal)
+                .as_coeffs(),
+            pdf.page(0).unwrap().initial_transform(true).as_coeffs()
+        );
+
+        assert_eq!(
+            Rotation::FlippedHorizontal.then(Rotation::Flipped),
+            Rotation::Horizontal
+        );
+        assert_eq!(Rotation::from_degrees(-90), Rotation::FlippedHorizontal);
     }
 }
@@ -1,12 +1,17 @@
 //! The starting point for reading PDF files.
 
 use crate::PdfData;
-use crate::object::Object;
-use crate::page::Pages;
+use crate::crypto::md5;
+use crate::forms::AcroForm;
+use crate::object::dict::keys::ID;
+use crate::object::indirect::IndirectObject;
+use crate::object::{self, Array, ObjRef, Object};
 use crate::page::cached::CachedPages;
-use crate::reader::Reader;
-use crate::sync::Arc;
-use crate::xref::{XRef, XRefError, fallback, root_xref};
+use crate::page::{Page, Pages};
+use crate::reader::{Reader, ReaderContext, ReaderExt};
+use crate::sync::{Arc, OnceLock};
+use crate::util::find_needle;
+use crate::xref::{Trailer, XRef, XRefError, fallback, find_last_xref_pos, root_xref};
 
 pub use crate::crypto::DecryptionError;
 use crate::metadata::Metadata;
@@ -15,8 +20,32 @@ use crate::metadata::Metadata;
 pub struct Pdf {
     xref: Arc<XRef>,
     header_version: PdfVersion,
+    header_offset: usize,
     pages: CachedPages,
     data: PdfData,
+    fingerprint: OnceLock<[u8; 16]>,
+}
+
+/// Settings for loading a PDF file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LoadSettings {
+    /// The maximum number of bytes that may precede the `%PDF-` header.
+    ///
+    /// Files are sometimes prefixed with a byte order mark, an HTML error page or
+    /// mail headers. Anything before the header is treated as garbage, and byte offsets
+    /// in the xref table are interpreted relative to the header.
+    ///
+    /// The default is 1995, so that the header is searched for in the first 2000 bytes
+    /// of the file.
+    pub max_header_offset: usize,
+}
+
+impl Default for LoadSettings {
+    fn default() -> Self {
+        Self {
+            max_header_offset: 1995,
+        }
+    }
 }
 
 /// An error that occurred while loading a PDF file.
@@ -43,11 +72,26 @@ impl Pdf {
     pub fn new_with_password(
         data: impl Into<PdfData>,
         password: &str,
+    ) -> Result<Self, LoadPdfError> {
+        Self::new_with_settings(data, password, LoadSettings::default())
+    }
+
+    /// Try to read the given PDF file with a password and custom settings.
+    ///
+    /// Returns `Err` if it was unable to read it or if the password is incorrect.
+    pub fn new_with_settings(
+        data: impl Into<PdfData>,
+        password: &str,
+        settings: LoadSettings,
     ) -> Result<Self, LoadPdfError> {
         let data = data.into();
         let password = password.as_bytes();
-        let version = find_version(data.as_ref()).unwrap_or(PdfVersion::Pdf10);
-        let xref = match root_xref(data.clone(), password) {
+        let header_offset = find_header(data.as_ref(), settings.max_header_offset);
+        let version = header_offset
+            .and_then(|offset| PdfVersion::from_bytes(&data.as_ref()[offset + 5..]))
+            .unwrap_or(PdfVersion::Pdf10);
+        let header_offset = header_offset.unwrap_or(0);
+        let xref = match root_xref(data.clone(), password, header_offset) {
             Ok(x) => x,
             Err(e) => match e {
                 XRefError::Unknown => {
@@ -63,8 +107,10 @@ impl Pdf {
         Ok(Self {
             xref,
             header_version: version,
+            header_offset,
             pages,
             data,
+            fingerprint: OnceLock::new(),
         })
     }
 
@@ -78,12 +124,35 @@ impl Pdf {
         self.xref.objects()
     }
 
-    /// Return the version of the PDF file.
+    /// Return the effective version of the PDF file.
+    ///
+    /// This is the version from the `/Version` entry of the document catalog if it is
+    /// present and newer than the version in the header, and the header version otherwise.
     pub fn version(&self) -> PdfVersion {
-        self.xref
-            .trailer_data()
-            .version
-            .unwrap_or(self.header_version)
+        match self.catalog_version() {
+            Some(catalog) if catalog > self.header_version => catalog,
+            _ => self.header_version,
+        }
+    }
+
+    /// Return the version from the `%PDF-x.y` header of the PDF file.
+    ///
+    /// If the header is missing or invalid, PDF 1.0 is assumed.
+    pub fn header_version(&self) -> PdfVersion {
+        self.header_version
+    }
+
+    /// Return the number of bytes preceding the `%PDF-` header of the PDF file.
+    ///
+    /// This is 0 for well-formed files, as well as for files whose header could not
+    /// be found.
+    pub fn header_offset(&self) -> usize {
+        self.header_offset
+    }
+
+    /// Return the version from the `/Version` entry of the document catalog, if present.
+    pub fn catalog_version(&self) -> Option<PdfVersion> {
+        self.xref.trailer_data().version
     }
 
     /// Return the underlying data of the PDF file.
@@ -96,6 +165,15 @@ impl Pdf {
         self.pages.get()
     }
 
+    /// Return the page with the given index.
+    ///
+    /// In contrast to indexing into [`Pdf::pages`], this does not require resolving
+    /// all pages of the document, which makes it much cheaper for documents with
+    /// many pages in case only few of them are needed.
+    pub fn page(&self, index: usize) -> Option<Page<'_>> {
+        self.pages.page(index)
+    }
+
     /// Return the xref of the PDF file.
     pub fn xref(&self) -> &XRef {
         &self.xref
@@ -105,17 +183,84 @@ impl Pdf {
     pub fn metadata(&self) -> &Metadata {
         self.xref.metadata()
     }
-}
 
-fn find_version(data: &[u8]) -> Option<PdfVersion> {
-    let data = &data[..data.len().min(2000)];
-    let mut r = Reader::new(data);
+    /// Return the trailer of the PDF file, which for example contains the `ID` of the
+    /// document and the `Encrypt` dictionary of encrypted documents.
+    ///
+    /// See [`Trailer`] for how the trailers of incrementally updated files are merged.
+    pub fn trailer(&self) -> Trailer<'_> {
+        self.xref.trailer()
+    }
 
-    while r.forward_tag(b"%PDF-").is_none() {
-        r.read_byte()?;
+    /// Return a fingerprint that identifies the document, for example for using it
+    /// as the key of a cache.
+    ///
+    /// If the trailer has a non-empty `ID` entry, the fingerprint is the MD5 hash of
+    /// its first element, which is the permanent identifier assigned to the document
+    /// when it was created, followed by its second element and the offset of the last
+    /// xref section. The latter two change whenever the document is updated, so that
+    /// different revisions of the same document have different fingerprints.
+    /// Otherwise, the fingerprint is the MD5 hash of the whole file.
+    ///
+    /// The fingerprint is only computed once and then cached.
+    pub fn fingerprint(&self) -> [u8; 16] {
+        *self.fingerprint.get_or_init(|| {
+            let data = self.data.as_ref();
+            let id = self.trailer().get::<Array<'_>>(ID);
+            let mut ids = id.as_ref().map(|a| a.flex_iter());
+            let permanent = ids
+                .as_mut()
+                .and_then(|i| i.next::<object::String<'_>>())
+                .filter(|id| !id.as_bytes().is_empty());
+
+            let Some(permanent) = permanent else {
+                return md5::calculate(data);
+            };
+
+            let changing = ids.as_mut().and_then(|i| i.next::<object::String<'_>>());
+            // Files whose xref table was rebuilt might not have a `startxref`, in
+            // which case each update still changes the length of the file.
+            let xref_pos = find_last_xref_pos(data).unwrap_or(data.len()) as u64;
+
+            let mut key = permanent.as_bytes().to_vec();
+            key.extend_from_slice(changing.as_ref().map_or(&[], |c| c.as_bytes()));
+            key.extend_from_slice(&xref_pos.to_le_bytes());
+
+            md5::calculate(&key)
+        })
+    }
+
+    /// Return the interactive form of the document, if it has one.
+    ///
+    /// The fields of the form are read eagerly, which requires resolving all
+    /// pages of the document to map widget annotations back to their pages.
+    pub fn acro_form(&self) -> Option<AcroForm<'_>> {
+        AcroForm::new(self)
+    }
+
+    /// Parse the indirect object (`N G obj ... endobj`) that starts at the given
+    /// byte offset of the PDF file, bypassing the xref table.
+    ///
+    /// This is useful for recovering objects from files whose xref table is broken.
+    /// Note that references inside of the returned object are still resolved using
+    /// the xref table. Returns `None` if no valid indirect object starts at the offset.
+    pub fn object_at_offset(&self, offset: usize) -> Option<(ObjRef, Object<'_>)> {
+        let mut r = Reader::new(self.data.as_ref());
+        r.jump(offset);
+
+        let ctx = ReaderContext::new(&self.xref, false);
+        let object = r.read_with_context::<IndirectObject<Object<'_>>>(&ctx)?;
+
+        Some(((*object.id()).into(), object.get()))
     }
+}
+
+/// Find the offset of the `%PDF-` header, which may be preceded by at most
+/// `max_offset` bytes.
+fn find_header(data: &[u8], max_offset: usize) -> Option<usize> {
+    let data = &data[..data.len().min(max_offset.saturating_add(5))];
 
-    PdfVersion::from_bytes(r.tail()?)
+    find_needle(data, b"%PDF-")
 }
 
 /// The version of a PDF document.
@@ -160,7 +305,10 @@ impl PdfVersion {
 
 #[cfg(test)]
 mod tests {
-    use crate::pdf::{Pdf, PdfVersion};
+    use crate::crypto::md5;
+    use crate::object::{Name, ObjRef, Object};
+    use crate::pdf::{LoadSettings, Pdf, PdfVersion};
+    use crate::xref::find_last_xref_pos;
 
     #[test]
     fn issue_49() {
@@ -182,4 +330,224 @@ mod tests {
 
         assert_eq!(pdf.version(), PdfVersion::Pdf14);
     }
+
+    fn minimal_pdf(header: &str, catalog_version: Option<&str>) -> Vec<u8> {
+        let catalog = match catalog_version {
+            Some(v) => format!("<< /Type /Catalog /Pages 2 0 R /Version /{v} >>"),
+            None => "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        };
+        let objects = [
+            catalog.as_str(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 10 10] >>",
+        ];
+
+        let mut out = format!("%PDF-{header}\n");
+        let mut offsets = vec![];
+
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.push_str(&format!("{} 0 obj\n{object}\nendobj\n", i + 1));
+        }
+
+        let xref_offset = out.len();
+        out.push_str(&format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            objects.len() + 1
+        ));
+
+        for offset in offsets {
+            out.push_str(&format!("{offset:010} 00000 n \n"));
+        }
+
+        out.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            objects.len() + 1
+        ));
+
+        out.into_bytes()
+    }
+
+    #[test]
+    fn fingerprint() {
+        let data = minimal_pdf("1.7", None);
+        let pdf = Pdf::new(data.clone()).unwrap();
+        assert_eq!(pdf.fingerprint(), md5::calculate(&data));
+
+        let with_id = |id: &str| {
+            String::from_utf8(data.clone())
+                .unwrap()
+                .replace("/Root 1 0 R", &format!("/Root 1 0 R /ID {id}"))
+                .into_bytes()
+        };
+        let fingerprint = |data: Vec<u8>| Pdf::new(data).unwrap().fingerprint();
+        let xref_pos = find_last_xref_pos(&data).unwrap() as u64;
+        let expected = |permanent: &[u8], changing: &[u8]| {
+            let mut key = [permanent, changing].concat();
+            key.extend_from_slice(&xref_pos.to_le_bytes());
+            md5::calculate(&key)
+        };
+
+        assert_eq!(
+            fingerprint(with_id("[<0102> <0304>]")),
+            expected(&[1, 2], &[3, 4])
+        );
+        // Different revisions of the same document differ in the second element.
+        assert_ne!(
+            fingerprint(with_id("[<0102> <0304>]")),
+            fingerprint(with_id("[<0102> <0506>]"))
+        );
+        assert_eq!(fingerprint(with_id("[(ab) (cd)]")), expected(b"ab", b"cd"));
+        // Fall back to the data for empty IDs.
+        let data = with_id("[<> <>]");
+        assert_eq!(fingerprint(data.clone()), md5::calculate(&data));
+    }
+
+    #[test]
+    fn fingerprint_incremental_update() {
+        let data = minimal_pdf("1.7", None);
+        let base = String::from_utf8(data)
+            .unwrap()
+            .replace("/Root 1 0 R", "/Root 1 0 R /ID [<0102> <0304>]");
+        let xref_pos = find_last_xref_pos(base.as_bytes()).unwrap();
+
+        // An update that keeps both elements of the ID, but adds a new xref section.
+        let mut updated = base.clone();
+        let update_pos = updated.len();
+        updated.push_str(&format!(
+            "xref\n0 0\ntrailer\n<< /Size 4 /Root 1 0 R /Prev {xref_pos} /ID [<0102> <0304>] >>\nstartxref\n{update_pos}\n%%EOF\n"
+        ));
+
+        let base = Pdf::new(base.into_bytes()).unwrap();
+        let updated = Pdf::new(updated.into_bytes()).unwrap();
+        assert_ne!(base.fingerprint(), updated.fingerprint());
+        // The cached value is returned on subsequent calls.
+        assert_eq!(updated.fingerprint(), updated.fingerprint());
+    }
+
+    #[test]
+    fn pdf_version_header_only() {
+        let pdf = Pdf::new(minimal_pdf("1.5", None)).unwrap();
+
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf15);
+        assert_eq!(pdf.catalog_version(), None);
+        assert_eq!(pdf.version(), PdfVersion::Pdf15);
+    }
+
+    #[test]
+    fn pdf_version_catalog_override() {
+        let pdf = Pdf::new(minimal_pdf("1.4", Some("1.7"))).unwrap();
+
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf14);
+        assert_eq!(pdf.catalog_version(), Some(PdfVersion::Pdf17));
+        assert_eq!(pdf.version(), PdfVersion::Pdf17);
+    }
+
+    #[test]
+    fn pdf_version_catalog_older_than_header() {
+        let pdf = Pdf::new(minimal_pdf("1.7", Some("1.4"))).unwrap();
+
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf17);
+        assert_eq!(pdf.catalog_version(), Some(PdfVersion::Pdf14));
+        assert_eq!(pdf.version(), PdfVersion::Pdf17);
+    }
+
+    #[test]
+    fn pdf_version_missing_header() {
+        let data = minimal_pdf("1.6", Some("2.0"));
+        let data = data[b"%PDF-1.6\n".len()..].to_vec();
+        let pdf = Pdf::new(data).unwrap();
+
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf10);
+        assert_eq!(pdf.catalog_version(), Some(PdfVersion::Pdf20));
+        assert_eq!(pdf.version(), PdfVersion::Pdf20);
+    }
+
+    fn with_prefix(prefix: &[u8], data: Vec<u8>) -> Vec<u8> {
+        let mut out = prefix.to_vec();
+        out.extend(data);
+
+        out
+    }
+
+    #[test]
+    fn header_offset_html_prefix() {
+        let prefix = format!("<html><body>{}</body></html>\n", "x".repeat(273));
+        assert_eq!(prefix.len(), 300);
+        let data = with_prefix(prefix.as_bytes(), minimal_pdf("1.5", None));
+        let pdf = Pdf::new(data).unwrap();
+
+        assert_eq!(pdf.header_offset(), 300);
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf15);
+        assert_eq!(pdf.pages().len(), 1);
+    }
+
+    #[test]
+    fn header_offset_bom() {
+        let data = with_prefix(b"\xEF\xBB\xBF", minimal_pdf("1.4", None));
+        let pdf = Pdf::new(data).unwrap();
+
+        assert_eq!(pdf.header_offset(), 3);
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf14);
+        assert_eq!(pdf.pages().len(), 1);
+    }
+
+    #[test]
+    fn header_offset_beyond_limit() {
+        let data = with_prefix(&[b' '; 1996], minimal_pdf("1.5", None));
+        let pdf = Pdf::new(data.clone()).unwrap();
+
+        assert_eq!(pdf.header_offset(), 0);
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf10);
+
+        let settings = LoadSettings {
+            max_header_offset: 4096,
+        };
+        let pdf = Pdf::new_with_settings(data, "", settings).unwrap();
+
+        assert_eq!(pdf.header_offset(), 1996);
+        assert_eq!(pdf.header_version(), PdfVersion::Pdf15);
+    }
+
+    #[test]
+    fn missing_eof_marker() {
+        let mut data = minimal_pdf("1.5", None);
+        data.truncate(data.len() - b"%%EOF\n".len());
+        let pdf = Pdf::new(data).unwrap();
+
+        assert_eq!(pdf.pages().len(), 1);
+    }
+
+    #[test]
+    fn object_at_offset() {
+        let data = minimal_pdf("1.7", None);
+        let offset = data.windows(7).position(|w| w == b"2 0 obj").unwrap();
+        let pdf = Pdf::new(data).unwrap();
+
+        let (obj_ref, object) = pdf.object_at_offset(offset).unwrap();
+        assert_eq!(obj_ref, ObjRef::new(2, 0));
+
+        let Object::Dict(dict) = object else {
+            panic!("expected a dictionary");
+        };
+        assert_eq!(
+            dict.get::<Name<'_>>(b"Type").as_deref(),
+            Some(b"Pages".as_ref())
+        );
+
+        assert!(pdf.object_at_offset(offset + 1).is_none());
+        assert!(pdf.object_at_offset(usize::MAX).is_none());
+    }
+
+    #[test]
+    fn object_at_offset_not_in_xref() {
+        let mut data = minimal_pdf("1.7", None);
+        let offset = data.len();
+        data.extend_from_slice(b"7 1 obj\n<< /Orphaned true >>\nendobj\n");
+        let pdf = Pdf::new(data).unwrap();
+
+        let (obj_ref, object) = pdf.object_at_offset(offset).unwrap();
+        assert_eq!(obj_ref, ObjRef::new(7, 1));
+        assert!(object.into_dict().unwrap().contains_key(b"Orphaned"));
+    }
 }
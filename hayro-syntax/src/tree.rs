@@ -0,0 +1,574 @@
+//! Reading name trees and number trees.
+//!
+//! Both kinds of trees map keys (strings for name trees and integers for number
+//! trees) to arbitrary objects, and are used in various places of a PDF document,
+//! for example for named destinations, embedded files or page labels.
+//!
+//! Since such trees are often malformed in practice, the implementation tries
+//! to be lenient: Missing `Limits` entries are tolerated (in which case all kids
+//! of a node are searched instead of only the one found via binary search),
+//! unsorted leaves are searched linearly and cycles in the tree are ignored.
+
+use crate::object::Array;
+use crate::object::Dict;
+use crate::object::MaybeRef;
+use crate::object::Object;
+use crate::object::ObjectIdentifier;
+use crate::object::ObjectLike;
+use crate::object::String;
+use crate::object::dict::keys::{KIDS, LIMITS, NAMES, NUMS};
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::{Bound, RangeBounds};
+
+/// The maximum depth of a tree, to prevent stack overflows for degenerate trees.
+const MAX_TREE_DEPTH: usize = 64;
+
+/// A name tree.
+#[derive(Debug, Clone)]
+pub struct NameTree<'a> {
+    root: Dict<'a>,
+}
+
+impl<'a> NameTree<'a> {
+    /// Create a new name tree from its root node.
+    pub fn new(root: Dict<'a>) -> Self {
+        Self { root }
+    }
+
+    /// Return the value for the given key, if it exists.
+    pub fn get(&self, key: &[u8]) -> Option<Object<'a>> {
+        get::<String<'a>, _>(&self.root, &key)
+    }
+
+    /// Return an iterator over all entries of the tree, ordered by their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (String<'a>, Object<'a>)> {
+        TreeIter::<_, &[u8]>::new(self.root.clone(), Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Return an iterator over all entries of the tree whose keys lie in the
+    /// given range, ordered by their keys.
+    pub fn range<'q>(
+        &self,
+        range: impl RangeBounds<&'q [u8]>,
+    ) -> impl Iterator<Item = (String<'a>, Object<'a>)> {
+        TreeIter::new(
+            self.root.clone(),
+            range.start_bound().cloned(),
+            range.end_bound().cloned(),
+        )
+    }
+}
+
+/// A number tree.
+#[derive(Debug, Clone)]
+pub struct NumberTree<'a> {
+    root: Dict<'a>,
+}
+
+impl<'a> NumberTree<'a> {
+    /// Create a new number tree from its root node.
+    pub fn new(root: Dict<'a>) -> Self {
+        Self { root }
+    }
+
+    /// Return the value for the given key, if it exists.
+    pub fn get(&self, key: i32) -> Option<Object<'a>> {
+        get::<i32, _>(&self.root, &key)
+    }
+
+    /// Return an iterator over all entries of the tree, ordered by their keys.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, Object<'a>)> {
+        self.range(..)
+    }
+
+    /// Return an iterator over all entries of the tree whose keys lie in the
+    /// given range, ordered by their keys.
+    pub fn range(&self, range: impl RangeBounds<i32>) -> impl Iterator<Item = (i32, Object<'a>)> {
+        TreeIter::new(
+            self.root.clone(),
+            range.start_bound().cloned(),
+            range.end_bound().cloned(),
+        )
+    }
+}
+
+/// The key of a tree.
+trait TreeKey<'a>: ObjectLike<'a> {
+    /// The key of the array containing the entries of a node.
+    const ENTRIES: &'static [u8];
+
+    fn cmp_key(&self, other: &Self) -> Ordering;
+}
+
+/// A key that can be compared to queries of type `Q`.
+trait CmpQuery<Q> {
+    fn cmp_query(&self, query: &Q) -> Ordering;
+}
+
+impl<'a> TreeKey<'a> for String<'a> {
+    const ENTRIES: &'static [u8] = NAMES;
+
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl CmpQuery<&[u8]> for String<'_> {
+    fn cmp_query(&self, query: &&[u8]) -> Ordering {
+        self.as_bytes().cmp(query)
+    }
+}
+
+impl TreeKey<'_> for i32 {
+    const ENTRIES: &'static [u8] = NUMS;
+
+    fn cmp_key(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+impl CmpQuery<Self> for i32 {
+    fn cmp_query(&self, query: &Self) -> Ordering {
+        self.cmp(query)
+    }
+}
+
+fn get<'a, K: TreeKey<'a> + CmpQuery<Q>, Q>(root: &Dict<'a>, key: &Q) -> Option<Object<'a>> {
+    let mut visited = BTreeSet::from_iter(root.obj_id());
+    let mut stack = vec![(root.clone(), 0)];
+
+    // Descend into all kids whose limits contain the key, so that we only
+    // ever look at the leaves that could actually contain it.
+    while let Some((node, depth)) = stack.pop() {
+        let entries = entries::<K>(&node);
+
+        let found = if entries.is_sorted_by(|a, b| a.0.cmp_key(&b.0).is_le()) {
+            entries
+                .binary_search_by(|(k, _)| k.cmp_query(key))
+                .ok()
+                .map(|idx| &entries[idx])
+        } else {
+            entries.iter().find(|(k, _)| k.cmp_query(key).is_eq())
+        };
+
+        if let Some((_, value)) = found {
+            return Some(value.clone());
+        }
+
+        if depth >= MAX_TREE_DEPTH {
+            warn!("exceeded maximum tree depth");
+
+            continue;
+        }
+
+        // Usually, only a single kid needs to be resolved. Only if we can't rely on
+        // the limits, we have to look at all kids.
+        if let Some(kid) = find_kid::<K, Q>(&node, key) {
+            if let Some(kid) = kid {
+                if kid.obj_id().is_none_or(|id| visited.insert(id)) {
+                    stack.push((kid, depth + 1));
+                } else {
+                    warn!("detected cycle in tree");
+                }
+            }
+
+            continue;
+        }
+
+        for kid in kids(&node, &mut visited, depth).into_iter().rev() {
+            let in_limits = limits::<K>(&kid).is_none_or(|(lower, upper)| {
+                lower.cmp_query(key).is_le() && upper.cmp_query(key).is_ge()
+            });
+
+            if in_limits {
+                stack.push((kid, depth + 1));
+            }
+        }
+    }
+
+    None
+}
+
+/// Find the kid of a node whose limits contain the given key via binary search,
+/// resolving only the kids that are visited along the way.
+///
+/// Returns `Some(None)` if no kid contains the key, and `None` if one of the
+/// visited kids is invalid or has no limits.
+fn find_kid<'a, K: TreeKey<'a> + CmpQuery<Q>, Q>(
+    node: &Dict<'a>,
+    key: &Q,
+) -> Option<Option<Dict<'a>>> {
+    let Some(kids) = node.get::<Array<'a>>(KIDS) else {
+        return Some(None);
+    };
+
+    let mut kids = kids.raw_iter().map(Some).collect::<Vec<_>>();
+    let (mut low, mut high) = (0, kids.len());
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let kid = kids[mid]
+            .take()
+            .and_then(|kid: MaybeRef<Object<'a>>| kid.resolve(node.ctx()))?
+            .into_dict()?;
+        let (lower, upper) = limits::<K>(&kid)?;
+
+        if upper.cmp_query(key).is_lt() {
+            low = mid + 1;
+        } else if lower.cmp_query(key).is_gt() {
+            high = mid;
+        } else {
+            return Some(Some(kid));
+        }
+    }
+
+    Some(None)
+}
+
+/// Return the entries of a node, resolving the values.
+fn entries<'a, K: TreeKey<'a>>(node: &Dict<'a>) -> Vec<(K, Object<'a>)> {
+    let Some(array) = node.get::<Array<'a>>(K::ENTRIES) else {
+        return vec![];
+    };
+
+    let mut iter = array.flex_iter();
+    let mut entries = vec![];
+
+    // The entries are alternating keys and values. Stop at the first key that
+    // isn't valid, since we can't tell where the next pair would start.
+    while let Some(key) = iter.next::<K>() {
+        let Some(value) = iter.next::<Object<'a>>() else {
+            break;
+        };
+
+        entries.push((key, value));
+    }
+
+    entries
+}
+
+/// Return the kids of a node that haven't been visited yet.
+fn kids<'a>(
+    node: &Dict<'a>,
+    visited: &mut BTreeSet<ObjectIdentifier>,
+    depth: usize,
+) -> Vec<Dict<'a>> {
+    if depth >= MAX_TREE_DEPTH {
+        warn!("exceeded maximum tree depth");
+
+        return vec![];
+    }
+
+    let Some(kids) = node.get::<Array<'a>>(KIDS) else {
+        return vec![];
+    };
+
+    kids.iter::<Dict<'a>>()
+        .filter(|kid| {
+            let is_new = kid.obj_id().is_none_or(|id| visited.insert(id));
+
+            if !is_new {
+                warn!("detected cycle in tree");
+            }
+
+            is_new
+        })
+        .collect()
+}
+
+/// Return the lower and upper limits of a node, if they exist.
+fn limits<'a, K: ObjectLike<'a>>(node: &Dict<'a>) -> Option<(K, K)> {
+    let limits = node.get::<Array<'a>>(LIMITS)?;
+    let mut iter = limits.iter::<K>();
+
+    Some((iter.next()?, iter.next()?))
+}
+
+/// An item of a tree that still needs to be yielded by [`TreeIter`].
+enum TreeItem<'a, K> {
+    /// An entry of a node.
+    Entry(K, Object<'a>),
+    /// A node whose entries and kids haven't been looked at yet, with its depth.
+    Node(Dict<'a>, usize),
+}
+
+struct TreeIter<'a, K, Q> {
+    start: Bound<Q>,
+    end: Bound<Q>,
+    visited: BTreeSet<ObjectIdentifier>,
+    /// The items that still need to be processed, with the next one at the end.
+    items: Vec<TreeItem<'a, K>>,
+}
+
+impl<'a, K: TreeKey<'a> + CmpQuery<Q>, Q> TreeIter<'a, K, Q> {
+    fn new(root: Dict<'a>, start: Bound<Q>, end: Bound<Q>) -> Self {
+        Self {
+            start,
+            end,
+            visited: BTreeSet::from_iter(root.obj_id()),
+            items: vec![TreeItem::Node(root, 0)],
+        }
+    }
+
+    fn after_start(&self, key: &K) -> bool {
+        match &self.start {
+            Bound::Included(q) => key.cmp_query(q).is_ge(),
+            Bound::Excluded(q) => key.cmp_query(q).is_gt(),
+            Bound::Unbounded => true,
+        }
+    }
+
+    fn before_end(&self, key: &K) -> bool {
+        match &self.end {
+            Bound::Included(q) => key.cmp_query(q).is_le(),
+            Bound::Excluded(q) => key.cmp_query(q).is_lt(),
+            Bound::Unbounded => true,
+        }
+    }
+}
+
+impl<'a, K: TreeKey<'a> + CmpQuery<Q>, Q> Iterator for TreeIter<'a, K, Q> {
+    type Item = (K, Object<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, depth) = match self.items.pop()? {
+                TreeItem::Entry(key, value) => return Some((key, value)),
+                TreeItem::Node(node, depth) => (node, depth),
+            };
+
+            let mut entries = entries::<K>(&node);
+            entries.retain(|(k, _)| self.after_start(k) && self.before_end(k));
+            // Leaves are supposed to be sorted already, but not all of them are.
+            entries.sort_by(|a, b| a.0.cmp_key(&b.0));
+
+            let mut kids = kids(&node, &mut self.visited, depth)
+                .into_iter()
+                .filter_map(|kid| {
+                    let limits = limits::<K>(&kid);
+                    let in_range = limits.as_ref().is_none_or(|(lower, upper)| {
+                        self.after_start(upper) && self.before_end(lower)
+                    });
+
+                    in_range.then(|| (limits.map(|l| l.0), kid))
+                })
+                .peekable();
+
+            // Usually, a node has either entries or kids, but if it has both, they
+            // need to be merged by their keys, where kids are ordered by their lower
+            // limit. Kids without limits are visited as soon as they are reached.
+            let mut items = vec![];
+            let mut entries = entries.into_iter().peekable();
+
+            loop {
+                let take_entry = match (entries.peek(), kids.peek()) {
+                    (None, None) => break,
+                    (Some((key, _)), Some((Some(lower), _))) => key.cmp_key(lower).is_lt(),
+                    (Some(_), None) => true,
+                    _ => false,
+                };
+
+                if take_entry {
+                    let (key, value) = entries.next().unwrap();
+                    items.push(TreeItem::Entry(key, value));
+                } else {
+                    let (_, kid) = kids.next().unwrap();
+                    items.push(TreeItem::Node(kid, depth + 1));
+                }
+            }
+
+            // Push in reverse order, since we pop from the back.
+            self.items.extend(items.into_iter().rev());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::{Dict, Object};
+    use crate::pdf::Pdf;
+    use crate::tree::{NameTree, NumberTree};
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Build a PDF whose catalog contains the tree with the given root under
+    /// the `Tree` key, where the objects are numbered starting at 2.
+    fn build_pdf(root: &str, objects: &[&str]) -> Vec<u8> {
+        let mut out = String::from("%PDF-1.7\n");
+        let mut offsets = vec![out.len()];
+        // The page tree comes after all other objects.
+        let pages = "<< /Type /Pages /Kids [<< /Type /Page >>] /Count 1 >>";
+        out.push_str(&format!(
+            "1 0 obj\n<< /Type /Catalog /Pages {} 0 R /Tree {root} >>\nendobj\n",
+            objects.len() + 2
+        ));
+
+        for (i, object) in objects.iter().chain([&pages]).enumerate() {
+            offsets.push(out.len());
+            out.push_str(&format!("{} 0 obj\n{object}\nendobj\n", i + 2));
+        }
+
+        let xref_offset = out.len();
+        out.push_str(&format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            offsets.len() + 1
+        ));
+
+        for offset in &offsets {
+            out.push_str(&format!("{offset:010} 00000 n \n"));
+        }
+
+        out.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+            offsets.len() + 1
+        ));
+
+        out.into_bytes()
+    }
+
+    fn tree_root(pdf: &Pdf) -> Dict<'_> {
+        let catalog = pdf.xref().get::<Dict<'_>>(pdf.xref().root_id()).unwrap();
+
+        catalog.get(b"Tree").unwrap()
+    }
+
+    fn names<'a>(
+        iter: impl Iterator<Item = (crate::object::String<'a>, Object<'a>)>,
+    ) -> Vec<Vec<u8>> {
+        iter.map(|(k, _)| k.as_bytes().to_vec()).collect()
+    }
+
+    fn value(tree: &NameTree<'_>, key: &[u8]) -> Option<i32> {
+        tree.get(key).and_then(|o| o.into_i32())
+    }
+
+    #[test]
+    fn name_tree_three_levels() {
+        let pdf = Pdf::new(build_pdf(
+            "<< /Kids [2 0 R 3 0 R] >>",
+            &[
+                "<< /Limits [(a) (d)] /Kids [4 0 R 5 0 R] >>",
+                "<< /Limits [(e) (f)] /Names [(e) 5 (f) 6 0 R] >>",
+                "<< /Limits [(a) (b)] /Names [(a) 1 (b) 2] >>",
+                // The limits are missing.
+                "<< /Names [(c) 3 (d) 4] >>",
+                "6",
+            ],
+        ))
+        .unwrap();
+        let tree = NameTree::new(tree_root(&pdf));
+
+        assert_eq!(value(&tree, b"a"), Some(1));
+        assert_eq!(value(&tree, b"d"), Some(4));
+        assert_eq!(value(&tree, b"e"), Some(5));
+        // The value is resolved.
+        assert_eq!(value(&tree, b"f"), Some(6));
+        assert_eq!(value(&tree, b"g"), None);
+        assert_eq!(value(&tree, b"bb"), None);
+
+        assert_eq!(
+            names(tree.iter()),
+            [b"a", b"b", b"c", b"d", b"e", b"f"].map(|k| k.to_vec())
+        );
+        assert_eq!(
+            names(tree.range(&b"b"[..]..&b"e"[..])),
+            [b"b", b"c", b"d"].map(|k| k.to_vec())
+        );
+        assert_eq!(
+            names(tree.range(&b"cc"[..]..)),
+            [b"d", b"e", b"f"].map(|k| k.to_vec())
+        );
+    }
+
+    #[test]
+    fn name_tree_unsorted_leaf() {
+        let pdf = Pdf::new(build_pdf(
+            "<< /Kids [2 0 R] >>",
+            &["<< /Limits [(a) (d)] /Names [(d) 4 (b) 2 (a) 1 (c) 3] >>"],
+        ))
+        .unwrap();
+        let tree = NameTree::new(tree_root(&pdf));
+
+        for (key, expected) in [(b"a", 1), (b"b", 2), (b"c", 3), (b"d", 4)] {
+            assert_eq!(value(&tree, key), Some(expected));
+        }
+
+        assert_eq!(
+            names(tree.iter()),
+            [b"a", b"b", b"c", b"d"].map(|k| k.to_vec())
+        );
+    }
+
+    #[test]
+    fn name_tree_binary_search() {
+        let pdf = Pdf::new(build_pdf(
+            "<< /Kids [2 0 R 3 0 R 4 0 R 5 0 R] >>",
+            &[
+                "<< /Limits [(a) (b)] /Names [(a) 1 (b) 2] >>",
+                "<< /Limits [(c) (d)] /Names [(c) 3 (d) 4] >>",
+                "<< /Limits [(e) (f)] /Names [(e) 5 (f) 6] >>",
+                "<< /Limits [(g) (h)] /Names [(g) 7 (h) 8] >>",
+            ],
+        ))
+        .unwrap();
+        let tree = NameTree::new(tree_root(&pdf));
+
+        for (key, expected) in [(b"a", 1), (b"d", 4), (b"e", 5), (b"h", 8)] {
+            assert_eq!(value(&tree, key), Some(expected));
+        }
+
+        assert_eq!(value(&tree, b"0"), None);
+        assert_eq!(value(&tree, b"dd"), None);
+        assert_eq!(value(&tree, b"i"), None);
+    }
+
+    #[test]
+    fn name_tree_entries_and_kids() {
+        // The root has both entries and kids, which should be merged by their keys.
+        let pdf = Pdf::new(build_pdf(
+            "<< /Names [(c) 3 (f) 6] /Kids [2 0 R 3 0 R] >>",
+            &[
+                "<< /Limits [(a) (b)] /Names [(a) 1 (b) 2] >>",
+                "<< /Limits [(d) (e)] /Names [(d) 4 (e) 5] >>",
+            ],
+        ))
+        .unwrap();
+        let tree = NameTree::new(tree_root(&pdf));
+
+        assert_eq!(value(&tree, b"c"), Some(3));
+        assert_eq!(value(&tree, b"d"), Some(4));
+        assert_eq!(
+            names(tree.iter()),
+            [b"a", b"b", b"c", b"d", b"e", b"f"].map(|k| k.to_vec())
+        );
+    }
+
+    #[test]
+    fn number_tree_cycle() {
+        let pdf = Pdf::new(build_pdf(
+            "2 0 R",
+            &[
+                "<< /Kids [3 0 R 4 0 R] >>",
+                // Refers back to the root.
+                "<< /Kids [2 0 R] /Nums [0 (a) 2 (b)] >>",
+                "<< /Kids [4 0 R 3 0 R] /Nums [5 (c)] >>",
+            ],
+        ))
+        .unwrap();
+        let tree = NumberTree::new(tree_root(&pdf));
+
+        assert!(tree.get(2).is_some());
+        assert!(tree.get(5).is_some());
+        assert!(tree.get(3).is_none());
+        assert_eq!(tree.iter().map(|(k, _)| k).collect::<Vec<_>>(), [0, 2, 5]);
+        assert_eq!(
+            tree.range(1..=5).map(|(k, _)| k).collect::<Vec<_>>(),
+            [2, 5]
+        );
+    }
+}
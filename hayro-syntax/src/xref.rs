@@ -7,13 +7,13 @@ use crate::object::Name;
 use crate::object::ObjectIdentifier;
 use crate::object::Stream;
 use crate::object::dict::keys::{
-    AUTHOR, CREATION_DATE, CREATOR, ENCRYPT, FIRST, ID, INDEX, INFO, KEYWORDS, MOD_DATE, N,
-    OCPROPERTIES, PAGES, PREV, PRODUCER, ROOT, SIZE, SUBJECT, TITLE, TYPE, VERSION, W, XREF_STM,
+    AUTHOR, CREATION_DATE, CREATOR, ENCRYPT, EXTENDS, FIRST, ID, INDEX, INFO, KEYWORDS, MOD_DATE,
+    N, OCPROPERTIES, PAGES, PREV, PRODUCER, ROOT, SIZE, SUBJECT, TITLE, TYPE, VERSION, W, XREF_STM,
 };
 use crate::object::dict::probe_dict;
 use crate::object::indirect::IndirectObject;
-use crate::object::{Array, MaybeRef};
-use crate::object::{DateTime, Dict};
+use crate::object::{Array, MaybeRef, ObjRef};
+use crate::object::{DateTime, Dict, TextString};
 use crate::object::{Object, ObjectLike};
 use crate::pdf::PdfVersion;
 use crate::reader::Reader;
@@ -38,16 +38,52 @@ pub(crate) enum XRefError {
 }
 
 /// Parse the "root" xref from the PDF.
-pub(crate) fn root_xref(data: PdfData, password: &[u8]) -> Result<XRef, XRefError> {
+///
+/// `header_offset` is the number of garbage bytes preceding the `%PDF-` header. Since
+/// the offsets in the xref are usually relative to the header, we first try to interpret
+/// them that way and only fall back to treating them as absolute if that fails.
+pub(crate) fn root_xref(
+    data: PdfData,
+    password: &[u8],
+    header_offset: usize,
+) -> Result<XRef, XRefError> {
     let mut xref_map = FxHashMap::default();
     let xref_pos = find_last_xref_pos(data.as_ref()).ok_or(XRefError::Unknown)?;
-    let trailer =
+
+    let shifted = data
+        .as_ref()
+        .get(header_offset..)
+        .filter(|_| header_offset > 0);
+    let base = if let Some(shifted) = shifted
+        && populate_xref_impl(shifted, xref_pos, &mut xref_map).is_some()
+    {
+        for entry in xref_map.values_mut() {
+            if let EntryType::Normal(offset) = entry {
+                *offset = offset.saturating_add(header_offset);
+            }
+        }
+
+        header_offset
+    } else {
+        xref_map.clear();
         populate_xref_impl(data.as_ref(), xref_pos, &mut xref_map).ok_or(XRefError::Unknown)?;
 
+        0
+    };
+
+    let trailer_offsets = trailer_offsets(&data.as_ref()[base..], xref_pos)
+        .into_iter()
+        .map(|offset| offset + base)
+        .collect::<Vec<_>>();
+
+    if trailer_offsets.is_empty() {
+        return Err(XRefError::Unknown);
+    }
+
     XRef::new(
         data.clone(),
         xref_map,
-        XRefInput::TrailerDictData(trailer),
+        XRefInput::Trailers(trailer_offsets),
         false,
         password,
     )
@@ -69,7 +105,7 @@ pub(crate) fn fallback(data: PdfData, password: &[u8]) -> Option<XRef> {
     }
 }
 
-fn fallback_xref_map<'a>(data: &'a PdfData, password: &[u8]) -> (XrefMap, Option<XRefInput<'a>>) {
+fn fallback_xref_map(data: &PdfData, password: &[u8]) -> (XrefMap, Option<XRefInput>) {
     fallback_xref_map_inner(data, ReaderContext::dummy(), true, password)
 }
 
@@ -78,9 +114,10 @@ fn fallback_xref_map_inner<'a>(
     mut dummy_ctx: ReaderContext<'a>,
     recurse: bool,
     password: &[u8],
-) -> (XrefMap, Option<XRefInput<'a>>) {
+) -> (XrefMap, Option<XRefInput>) {
     let mut xref_map = FxHashMap::default();
     let mut trailer_dicts = vec![];
+    let mut obj_streams = vec![];
     let mut root_ref = None;
 
     let mut r = Reader::new(data.as_ref());
@@ -110,6 +147,7 @@ fn fallback_xref_map_inner<'a>(
         } else {
             // Then, try to check whether we have a dictionary, in particular a trailer
             // dictionary.
+            let dict_offset = r.offset();
             let mut probe_reader = r.clone();
             if r.peek_bytes(2).is_some_and(|b| b == b"<<")
                 && let Some(probe) =
@@ -120,7 +158,7 @@ fn fallback_xref_map_inner<'a>(
                     let mut dict_reader = Reader::new(probe.data);
                     if let Some(dict) = dict_reader.read_with_context::<Dict<'_>>(&dummy_ctx) {
                         if probe.has_root && dict.contains_key(ROOT) {
-                            trailer_dicts.push(dict.clone());
+                            trailer_dicts.push((dict_offset, dict.clone()));
                         }
 
                         if dict
@@ -136,25 +174,11 @@ fn fallback_xref_map_inner<'a>(
                             && let Some(last_obj_num) = last_obj_num
                             && let Some(obj_stream) = ObjectStream::new(stream, &data, &dummy_ctx)
                         {
-                            for (idx, (obj_num, _)) in obj_stream.offsets.iter().enumerate() {
-                                let id = ObjectIdentifier::new(*obj_num as i32, 0);
-                                // If we already found an entry for that object number that was not
-                                // inside an object stream. Somewhat arbitrary and maybe
-                                // we can do better, but that seems to work for the current
-                                // set of tests.
-                                if xref_map
-                                    .get(&id)
-                                    .is_none_or(|e| !matches!(e, &EntryType::Normal(_)))
-                                {
-                                    xref_map.insert(
-                                        id,
-                                        EntryType::ObjStream(
-                                            last_obj_num.obj_number as u32,
-                                            idx as u32,
-                                        ),
-                                    );
-                                }
-                            }
+                            obj_streams.push(ScannedObjectStream {
+                                obj_number: last_obj_num.obj_number as u32,
+                                extends: dict.get_ref(EXTENDS).map(|r| r.into()),
+                                obj_numbers: obj_stream.offsets.iter().map(|o| o.0).collect(),
+                            });
                         }
                     }
                 }
@@ -175,10 +199,12 @@ fn fallback_xref_map_inner<'a>(
         }
     }
 
+    insert_scanned_object_streams(&mut xref_map, obj_streams);
+
     // Try to choose the right trailer dict by doing basic validation.
     let mut trailer_dict = None;
 
-    for dict in trailer_dicts {
+    for (dict_offset, dict) in trailer_dicts {
         if let Some(root_id) = dict.get_raw::<Dict<'_>>(ROOT) {
             let check = |dict: &Dict<'_>| -> bool { dict.contains_key(PAGES) };
 
@@ -194,7 +220,7 @@ fn fallback_xref_map_inner<'a>(
                                 check(&obj)
                             }
                         {
-                            trailer_dict = Some(dict);
+                            trailer_dict = Some((dict_offset, dict));
                         }
                     }
                     Some(EntryType::ObjStream(obj_num, idx)) => {
@@ -218,7 +244,7 @@ fn fallback_xref_map_inner<'a>(
                                     }
                                 }
                             {
-                                trailer_dict = Some(dict);
+                                trailer_dict = Some((dict_offset, dict));
                             }
                         }
                     }
@@ -226,7 +252,7 @@ fn fallback_xref_map_inner<'a>(
                 },
                 MaybeRef::NotRef(d) => {
                     if check(&d) {
-                        trailer_dict = Some(dict);
+                        trailer_dict = Some((dict_offset, dict));
                     }
                 }
             }
@@ -235,7 +261,7 @@ fn fallback_xref_map_inner<'a>(
 
     let has_encryption = trailer_dict
         .as_ref()
-        .is_some_and(|t| t.contains_key(ENCRYPT));
+        .is_some_and(|(_, t)| t.contains_key(ENCRYPT));
 
     if has_encryption && recurse {
         // The problem is that in this case, we have used a dummy reader context which does not have
@@ -245,7 +271,7 @@ fn fallback_xref_map_inner<'a>(
         if let Ok(xref) = XRef::new(
             data.clone(),
             xref_map.clone(),
-            XRefInput::TrailerDictData(trailer_dict.as_ref().map(|d| d.data()).unwrap()),
+            XRefInput::Trailers(vec![trailer_dict.as_ref().map(|(o, _)| *o).unwrap()]),
             true,
             password,
         ) {
@@ -255,11 +281,8 @@ fn fallback_xref_map_inner<'a>(
         }
     }
 
-    if let Some(trailer_dict_data) = trailer_dict.map(|d| d.data()) {
-        (
-            xref_map,
-            Some(XRefInput::TrailerDictData(trailer_dict_data)),
-        )
+    if let Some((offset, _)) = trailer_dict {
+        (xref_map, Some(XRefInput::Trailers(vec![offset])))
     } else if let Some(root_ref) = root_ref {
         (xref_map, Some(XRefInput::RootRef(root_ref)))
     } else {
@@ -267,6 +290,84 @@ fn fallback_xref_map_inner<'a>(
     }
 }
 
+/// An object stream that was found while scanning the file during repair.
+struct ScannedObjectStream {
+    obj_number: u32,
+    extends: Option<ObjectIdentifier>,
+    obj_numbers: Vec<u32>,
+}
+
+/// Maximum number of object streams we follow in an `Extends` chain before we abort.
+const MAX_EXTENDS_CHAIN_DEPTH: usize = 64;
+
+fn insert_scanned_object_streams(xref_map: &mut XrefMap, obj_streams: Vec<ScannedObjectStream>) {
+    let extends: FxHashMap<u32, u32> = obj_streams
+        .iter()
+        .filter_map(|s| Some((s.obj_number, s.extends?.obj_number as u32)))
+        .collect();
+
+    // The bottom of the `Extends` chain of the given object stream and the number of
+    // object streams below it.
+    let chain_base = |mut obj_number: u32| {
+        let mut visited = BTreeSet::new();
+
+        while let Some(next) = extends.get(&obj_number) {
+            if !visited.insert(obj_number) || visited.len() > MAX_EXTENDS_CHAIN_DEPTH {
+                warn!("cycle detected in object stream extends chain");
+
+                // Use a deterministic member of the cycle as the base, so that all
+                // streams of the cycle end up in the same chain.
+                obj_number = visited.first().copied().unwrap_or(obj_number);
+                break;
+            }
+
+            obj_number = *next;
+        }
+
+        (obj_number, visited.len())
+    };
+
+    let chains: Vec<(u32, usize)> = obj_streams
+        .iter()
+        .map(|s| chain_base(s.obj_number))
+        .collect();
+
+    // The position in the file of the last object stream of each chain.
+    let mut chain_ends = FxHashMap::default();
+
+    for (pos, (base, _)) in chains.iter().enumerate() {
+        chain_ends.insert(*base, pos);
+    }
+
+    // Streams that appear later in the file take precedence, so unrelated streams are
+    // inserted in file order. If the same object number appears in several object
+    // streams of the same chain, though, the entry nearest to the top of the chain
+    // should win, so the streams of one chain are inserted together, from the bottom
+    // to the top, at the position of the chain's last stream in the file.
+    let mut obj_streams: Vec<_> = obj_streams
+        .into_iter()
+        .zip(chains)
+        .map(|(stream, (base, depth))| ((chain_ends[&base], depth), stream))
+        .collect();
+    obj_streams.sort_by_key(|(key, _)| *key);
+
+    for (_, obj_stream) in obj_streams {
+        for (idx, obj_num) in obj_stream.obj_numbers.iter().enumerate() {
+            let id = ObjectIdentifier::new(*obj_num as i32, 0);
+            // If we already found an entry for that object number that was not
+            // inside an object stream. Somewhat arbitrary and maybe
+            // we can do better, but that seems to work for the current
+            // set of tests.
+            if xref_map
+                .get(&id)
+                .is_none_or(|e| !matches!(e, &EntryType::Normal(_)))
+            {
+                xref_map.insert(id, EntryType::ObjStream(obj_stream.obj_number, idx as u32));
+            }
+        }
+    }
+}
+
 const DUMMY_XREF: XRef = XRef(Inner::Dummy);
 
 /// An xref table.
@@ -277,7 +378,7 @@ impl XRef {
     fn new(
         data: PdfData,
         xref_map: XrefMap,
-        input: XRefInput<'_>,
+        input: XRefInput,
         repaired: bool,
         password: &[u8],
     ) -> Result<Self, XRefError> {
@@ -285,6 +386,11 @@ impl XRef {
         // before we actually created the xref struct. So we first create it using dummy data
         // and then populate the data.
         let trailer_data = TrailerData::dummy();
+        let bytes = data.clone();
+        let trailer_offsets = match &input {
+            XRefInput::Trailers(offsets) => offsets.clone(),
+            XRefInput::RootRef(_) => vec![],
+        };
 
         let mut xref = Self(Inner::Some(Arc::new(SomeRepr {
             data: Arc::new(Data::new(data)),
@@ -293,6 +399,7 @@ impl XRef {
             has_ocgs: false,
             metadata: Arc::new(Metadata::default()),
             trailer_data,
+            trailer_offsets: trailer_offsets.clone(),
             password: password.to_vec(),
         })));
 
@@ -302,11 +409,8 @@ impl XRef {
 
         let decryptor = {
             match input {
-                XRefInput::TrailerDictData(trailer_dict_data) => {
-                    let mut r = Reader::new(trailer_dict_data);
-
-                    let trailer_dict = r
-                        .read_with_context::<Dict<'_>>(&ReaderContext::new(&xref, false))
+                XRefInput::Trailers(_) => {
+                    let trailer_dict = read_trailer_dict(bytes.as_ref(), &trailer_offsets, &xref)
                         .ok_or(XRefError::Unknown)?;
 
                     get_decryptor(&trailer_dict, password)?
@@ -324,11 +428,8 @@ impl XRef {
         }
 
         let (trailer_data, has_ocgs, metadata) = match input {
-            XRefInput::TrailerDictData(trailer_dict_data) => {
-                let mut r = Reader::new(trailer_dict_data);
-
-                let trailer_dict = r
-                    .read_with_context::<Dict<'_>>(&ReaderContext::new(&xref, false))
+            XRefInput::Trailers(_) => {
+                let trailer_dict = read_trailer_dict(bytes.as_ref(), &trailer_offsets, &xref)
                     .ok_or(XRefError::Unknown)?;
 
                 let root_ref = trailer_dict.get_ref(ROOT).ok_or(XRefError::Unknown)?;
@@ -356,11 +457,14 @@ impl XRef {
             XRefInput::RootRef(root_ref) => {
                 let root = xref.get::<Dict<'_>>(root_ref).ok_or(XRefError::Unknown)?;
                 let pages_ref = root.get_ref(PAGES).ok_or(XRefError::Unknown)?;
+                let version = root
+                    .get::<Name<'_>>(VERSION)
+                    .and_then(|v| PdfVersion::from_bytes(v.deref()));
 
                 let td = TrailerData {
                     pages_ref: pages_ref.into(),
                     root_ref,
-                    version: None,
+                    version,
                 };
 
                 (td, false, Metadata::default())
@@ -402,6 +506,30 @@ impl XRef {
         }
     }
 
+    /// Return the trailer of the PDF file.
+    ///
+    /// If the xref table had to be rebuilt, this only contains the trailer dictionary
+    /// that was chosen while scanning the file, and if none was found, it is empty.
+    pub fn trailer(&self) -> Trailer<'_> {
+        let Inner::Some(repr) = &self.0 else {
+            return Trailer { dicts: vec![] };
+        };
+
+        let ctx = ReaderContext::new(self, false);
+        let dicts = repr
+            .trailer_offsets
+            .iter()
+            .filter_map(|offset| {
+                let mut r = Reader::new(repr.data.get().as_ref());
+                r.jump(*offset);
+
+                r.read_with_context::<Dict<'_>>(&ctx)
+            })
+            .collect();
+
+        Trailer { dicts }
+    }
+
     pub(crate) fn trailer_data(&self) -> &TrailerData {
         match &self.0 {
             Inner::Dummy => unreachable!(),
@@ -429,6 +557,19 @@ impl XRef {
         }
     }
 
+    /// Return the identifiers of all objects in the xref table, in ascending order.
+    pub fn object_ids(&self) -> Vec<ObjectIdentifier> {
+        match &self.0 {
+            Inner::Dummy => vec![],
+            Inner::Some(r) => {
+                let mut ids = r.map.get().xref_map.keys().copied().collect::<Vec<_>>();
+                ids.sort();
+
+                ids
+            }
+        }
+    }
+
     pub(crate) fn objects(&self) -> impl IntoIterator<Item = Object<'_>> + '_ {
         match &self.0 {
             Inner::Dummy => unimplemented!(),
@@ -593,28 +734,146 @@ impl XRef {
                 // Generation number is implicitly 0.
                 let obj_stream_id = ObjectIdentifier::new(obj_stram_gen_num as i32, 0);
 
-                if obj_stream_id == id {
-                    warn!("cycle detected in object stream");
+                self.get_from_object_stream(repr, id, obj_stream_id, index, &ctx)
+            }
+        }
+    }
 
-                    return None;
-                }
+    fn get_from_object_stream<'a, T>(
+        &'a self,
+        repr: &'a SomeRepr,
+        id: ObjectIdentifier,
+        obj_stream_id: ObjectIdentifier,
+        index: u32,
+        ctx: &ReaderContext<'a>,
+    ) -> Option<T>
+    where
+        T: ObjectLike<'a>,
+    {
+        let obj_number = u32::try_from(id.obj_number).ok()?;
+        let mut visited = BTreeSet::new();
+        let mut first = None;
+        let mut cur_id = obj_stream_id;
 
-                let stream = self.get_with::<Stream<'_>>(obj_stream_id, &ctx)?;
-                let data = repr.data.get_with(obj_stream_id, &ctx)?;
-                let object_stream = ObjectStream::new(stream, data, &ctx)?;
-                object_stream.get(index)
+        loop {
+            if cur_id == id {
+                warn!("cycle detected in object stream");
+
+                return None;
+            }
+
+            if !visited.insert(cur_id) || visited.len() > MAX_EXTENDS_CHAIN_DEPTH {
+                warn!("cycle detected in object stream extends chain");
+
+                break;
+            }
+
+            let stream = self.get_with::<Stream<'_>>(cur_id, ctx)?;
+            let extends = stream.dict().get_ref(EXTENDS);
+            let data = repr.data.get_with(cur_id, ctx)?;
+            let object_stream = ObjectStream::new(stream, data, ctx)?;
+
+            // The index from the xref table is only valid for the object stream the table
+            // points to. Make sure that it actually points to the object we are looking for,
+            // and otherwise search for the object.
+            let found = if first.is_none() && object_stream.obj_number(index) == Some(obj_number) {
+                Some(index)
+            } else {
+                object_stream.index_of(obj_number)
+            };
+
+            if let Some(found) = found {
+                return object_stream.get(found);
+            }
+
+            if first.is_none() {
+                first = Some(object_stream);
             }
+
+            // Otherwise, the object might be stored in one of the object streams
+            // that are extended by the current one.
+            let Some(extends) = extends else {
+                break;
+            };
+
+            cur_id = extends.into();
         }
+
+        // We couldn't find the object anywhere, so just trust the index of the xref table.
+        first?.get(index)
+    }
+}
+
+/// The trailer of a PDF file.
+///
+/// A file that has been updated incrementally contains one trailer dictionary per
+/// update, each of which points to the previous one via its `Prev` entry. While the
+/// trailer of an update should repeat all entries of the previous one, this is not
+/// always the case in practice. Therefore, the trailer dictionaries are merged:
+/// An entry is always taken from the newest trailer dictionary that contains it.
+///
+/// For files with cross-reference streams, the dictionaries of the streams serve as
+/// trailer dictionaries, so they contain entries like `Type`, `W` and `Length` as
+/// well. Note that `Prev` and `XRefStm` describe a single section, so they are
+/// only meaningful for [`Trailer::dicts`].
+#[derive(Debug, Clone)]
+pub struct Trailer<'a> {
+    dicts: Vec<Dict<'a>>,
+}
+
+impl<'a> Trailer<'a> {
+    /// Returns the entry of a key as a specific object, or try to resolve it in case it's
+    /// an object reference.
+    #[allow(
+        private_bounds,
+        reason = "users shouldn't be able to implement `ObjectLike` for custom objects."
+    )]
+    pub fn get<T>(&self, key: impl AsRef<[u8]>) -> Option<T>
+    where
+        T: ObjectLike<'a>,
+    {
+        self.dict_with(key.as_ref())?.get::<T>(key)
+    }
+
+    /// Get the object reference linked to a key.
+    pub fn get_ref(&self, key: impl AsRef<[u8]>) -> Option<ObjRef> {
+        self.dict_with(key.as_ref())?.get_ref(key)
+    }
+
+    /// Checks whether the trailer contains an entry with a specific key.
+    pub fn contains_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.dict_with(key.as_ref()).is_some()
+    }
+
+    /// Returns an iterator over all keys in the trailer, sorted by key.
+    pub fn keys(&self) -> impl Iterator<Item = Name<'a>> + '_ {
+        let keys = self
+            .dicts
+            .iter()
+            .flat_map(|d| d.keys())
+            .collect::<BTreeSet<_>>();
+
+        keys.into_iter()
+    }
+
+    /// Returns the trailer dictionaries of all sections, starting with the newest one.
+    pub fn dicts(&self) -> &[Dict<'a>] {
+        &self.dicts
+    }
+
+    fn dict_with(&self, key: &[u8]) -> Option<&Dict<'a>> {
+        self.dicts.iter().find(|d| d.contains_key(key))
     }
 }
 
 /// An input that is passed to the xref constructor so that we can fully resolve
 /// the PDF.
-#[derive(Debug, Copy, Clone)]
-pub(crate) enum XRefInput<'a> {
+#[derive(Debug, Clone)]
+pub(crate) enum XRefInput {
     /// This option is going to be uesd in 99.999% of the case. It contains the
-    /// raw data of the trailer dictionary which is then going to be processed.
-    TrailerDictData(&'a [u8]),
+    /// offsets of the trailer dictionaries of all xref sections, starting with
+    /// the newest one, which is then going to be processed.
+    Trailers(Vec<usize>),
     /// In case the trailer dictionary could not be read (for example because
     /// it is cut-off), we just pass the object ID of the root dictionary
     /// in case we have found one, and try our best to build the PDF just
@@ -681,6 +940,7 @@ struct SomeRepr {
     has_ocgs: bool,
     password: Vec<u8>,
     trailer_data: TrailerData,
+    trailer_offsets: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -792,6 +1052,61 @@ impl Readable<'_> for SubsectionHeader {
     }
 }
 
+/// Insert the entries of all xref sections that the given xref section depends on.
+///
+/// Both xref tables and xref streams go through this function so that they follow
+/// the same precedence rules: Entries are inserted from the oldest to the newest section,
+/// meaning that newer entries override older ones. We first insert the entries of the
+/// sections referenced by `Prev`, and then the ones of the xref stream referenced by `XRefStm`
+/// in hybrid files. The caller needs to insert the entries of the current section
+/// afterward, since they have the highest priority.
+fn populate_from_previous_sections(
+    data: &[u8],
+    dict: &Dict<'_>,
+    insert_map: &mut XrefMap,
+    visited: &mut BTreeSet<usize>,
+) -> Option<()> {
+    if let Some(prev) = dict.get::<i32>(PREV) {
+        populate_xref_impl_inner(data, prev as usize, insert_map, visited)?;
+    }
+
+    if let Some(xref_stm) = dict.get::<i32>(XREF_STM) {
+        populate_from_hybrid_xref_stream(data, xref_stm as usize, insert_map, visited)?;
+    }
+
+    Some(())
+}
+
+/// Insert the entries of the xref stream referenced by `XRefStm` in the trailer of
+/// a hybrid-reference file.
+///
+/// The stream only provides the locations of objects that readers without support
+/// for compressed objects are not supposed to see (usually objects in object streams),
+/// so it is not an xref section of its own. Therefore, we don't follow its `Prev`
+/// entry: The previous sections are already referenced by the trailer of the table.
+fn populate_from_hybrid_xref_stream(
+    data: &[u8],
+    pos: usize,
+    insert_map: &mut XrefMap,
+    visited: &mut BTreeSet<usize>,
+) -> Option<()> {
+    if !visited.insert(pos) {
+        warn!("circular xref stream reference detected at offset {}", pos);
+
+        return None;
+    }
+
+    let mut reader = Reader::new(data);
+    reader.jump(pos);
+    reader.skip_white_spaces_and_comments();
+
+    let stream = reader
+        .read_with_context::<IndirectObject<Stream<'_>>>(&ReaderContext::dummy())?
+        .get();
+
+    populate_from_xref_stream_entries(&stream, insert_map)
+}
+
 /// Populate the xref table, and return the trailer dict.
 fn populate_from_xref_table<'a>(
     data: &'a [u8],
@@ -810,16 +1125,7 @@ fn populate_from_xref_table<'a>(
 
     let mut max_obj = 0;
 
-    if let Some(prev) = trailer.get::<i32>(PREV) {
-        // First insert the entries from any previous xref tables.
-        populate_xref_impl_inner(data, prev as usize, insert_map, visited)?;
-    }
-
-    // In hybrid files, entries in `XRefStm` should have higher priority, therefore we insert them
-    // after looking at `PREV`.
-    if let Some(xref_stm) = trailer.get::<i32>(XREF_STM) {
-        populate_xref_impl_inner(data, xref_stm as usize, insert_map, visited)?;
-    }
+    populate_from_previous_sections(data, &trailer, insert_map, visited)?;
 
     while let Some(header) = reader.read_without_context::<SubsectionHeader>() {
         reader.skip_white_spaces();
@@ -856,11 +1162,14 @@ fn populate_from_xref_stream<'a>(
         .read_with_context::<IndirectObject<Stream<'_>>>(&ReaderContext::dummy())?
         .get();
 
-    if let Some(prev) = stream.dict().get::<i32>(PREV) {
-        // First insert the entries from any previous xref tables.
-        let _ = populate_xref_impl_inner(data, prev as usize, insert_map, visited)?;
-    }
+    populate_from_previous_sections(data, stream.dict(), insert_map, visited)?;
+    populate_from_xref_stream_entries(&stream, insert_map)?;
 
+    Some(stream.dict().data())
+}
+
+/// Insert the entries of the subsections of an xref stream.
+fn populate_from_xref_stream_entries(stream: &Stream<'_>, insert_map: &mut XrefMap) -> Option<()> {
     let size = stream.dict().get::<u32>(SIZE)?;
 
     let [f1_len, f2_len, f3_len] = stream.dict().get::<[u8; 3]>(W)?;
@@ -905,7 +1214,7 @@ fn populate_from_xref_stream<'a>(
         )?;
     }
 
-    Some(stream.dict().data())
+    Some(())
 }
 
 fn xref_stream_num(data: &[u8]) -> Option<u32> {
@@ -1009,6 +1318,13 @@ fn read_xref_table_trailer<'a>(
     reader: &mut Reader<'a>,
     ctx: &ReaderContext<'a>,
 ) -> Option<Dict<'a>> {
+    skip_to_xref_table_trailer(reader)?;
+
+    reader.read_with_context::<Dict<'_>>(ctx)
+}
+
+/// Move the reader from the start of an xref table to the start of its trailer dictionary.
+fn skip_to_xref_table_trailer(reader: &mut Reader<'_>) -> Option<()> {
     reader.skip_white_spaces();
     reader.forward_tag(b"xref")?;
     reader.skip_white_spaces();
@@ -1022,7 +1338,51 @@ fn read_xref_table_trailer<'a>(
     reader.forward_tag(b"trailer")?;
     reader.skip_white_spaces();
 
-    reader.read_with_context::<Dict<'_>>(ctx)
+    Some(())
+}
+
+/// Read the newest trailer dictionary.
+fn read_trailer_dict<'a>(data: &'a [u8], offsets: &[usize], xref: &'a XRef) -> Option<Dict<'a>> {
+    let mut r = Reader::new(data);
+    r.jump(*offsets.first()?);
+
+    r.read_with_context::<Dict<'_>>(&ReaderContext::new(xref, false))
+}
+
+/// Return the offsets of the trailer dictionaries of the xref section at `pos` and
+/// all sections linked to it via `Prev`, starting with the newest one.
+///
+/// For xref streams, the offset of the stream dictionary is returned.
+fn trailer_offsets(data: &[u8], pos: usize) -> Vec<usize> {
+    let mut offsets = vec![];
+    let mut visited = BTreeSet::new();
+    let mut pos = pos;
+
+    while visited.insert(pos) && visited.len() <= MAX_XREF_CHAIN_DEPTH {
+        let mut reader = Reader::new(data);
+        reader.jump(pos);
+        reader.skip_white_spaces_and_comments();
+
+        if reader.skip::<ObjectIdentifier>(false).is_some() {
+            reader.skip_white_spaces_and_comments();
+        } else if skip_to_xref_table_trailer(&mut reader).is_none() {
+            break;
+        }
+
+        let offset = reader.offset();
+        let Some(dict) = reader.read_with_context::<Dict<'_>>(&ReaderContext::dummy()) else {
+            break;
+        };
+
+        offsets.push(offset);
+
+        match dict.get::<i32>(PREV).and_then(|p| usize::try_from(p).ok()) {
+            Some(prev) => pos = prev,
+            None => break,
+        }
+    }
+
+    offsets
 }
 
 fn get_decryptor(trailer_dict: &Dict<'_>, password: &[u8]) -> Result<Decryptor, XRefError> {
@@ -1051,28 +1411,84 @@ struct ObjectStream<'a> {
 
 impl<'a> ObjectStream<'a> {
     fn new(inner: Stream<'_>, data: &'a [u8], ctx: &ReaderContext<'a>) -> Option<Self> {
-        let num_objects = inner.dict().get::<usize>(N)?;
-        let first_offset = inner.dict().get::<usize>(FIRST)?;
+        let num_objects = inner.dict().get::<usize>(N);
+        // `First` can't be trusted if it points outside of the stream. In that
+        // case, we assume that the objects start right after the header.
+        let first_offset = inner
+            .dict()
+            .get::<usize>(FIRST)
+            .filter(|first| *first <= data.len());
+
+        if num_objects.is_none() && first_offset.is_none() {
+            return None;
+        }
 
         let mut r = Reader::new(data);
 
-        let mut offsets = vec![];
+        let mut header = vec![];
 
-        for _ in 0..num_objects {
+        // Read as many pairs of object numbers and offsets as possible, without
+        // going past the start of the first object.
+        while num_objects.is_none_or(|n| header.len() < n) {
             r.skip_white_spaces_and_comments();
-            // Skip object number
-            let obj_num = r.read_without_context::<u32>()?;
+
+            if first_offset.is_some_and(|first| r.offset() >= first) {
+                break;
+            }
+
+            let Some(obj_num) = r.read_without_context::<u32>() else {
+                break;
+            };
             r.skip_white_spaces_and_comments();
-            let relative_offset = r.read_without_context::<usize>()?;
-            offsets.push((obj_num, first_offset + relative_offset));
+            let Some(relative_offset) = r.read_without_context::<usize>() else {
+                break;
+            };
+
+            header.push((obj_num, relative_offset));
+        }
+
+        if num_objects.is_some_and(|n| header.len() != n) || first_offset.is_none() {
+            warn!(
+                "object stream has an invalid N or First entry, recovered {} objects",
+                header.len()
+            );
         }
 
+        let first_offset = first_offset.unwrap_or_else(|| {
+            r.skip_white_spaces_and_comments();
+            r.offset()
+        });
+        // Objects whose offset lies outside of the stream are kept, so that the
+        // indices of the other objects stay the same, but they can't be read.
+        let offsets = header
+            .into_iter()
+            .map(|(obj_num, relative_offset)| {
+                let offset = first_offset
+                    .checked_add(relative_offset)
+                    .filter(|offset| *offset < data.len())
+                    .unwrap_or(data.len());
+
+                (obj_num, offset)
+            })
+            .collect();
+
         let mut ctx = ctx.clone();
         ctx.set_in_object_stream(true);
 
         Some(Self { data, ctx, offsets })
     }
 
+    fn obj_number(&self, index: u32) -> Option<u32> {
+        self.offsets.get(index as usize).map(|o| o.0)
+    }
+
+    fn index_of(&self, obj_number: u32) -> Option<u32> {
+        self.offsets
+            .iter()
+            .position(|o| o.0 == obj_number)
+            .map(|i| i as u32)
+    }
+
     fn get<T>(&self, index: u32) -> Option<T>
     where
         T: ObjectLike<'a>,
@@ -1096,22 +1512,22 @@ fn parse_metadata(info_dict: &Dict<'_>) -> Metadata {
             .and_then(|c| DateTime::from_bytes(&c)),
         title: info_dict
             .get::<object::String<'_>>(TITLE)
-            .map(|t| t.to_vec()),
+            .map(TextString::from),
         author: info_dict
             .get::<object::String<'_>>(AUTHOR)
-            .map(|t| t.to_vec()),
+            .map(TextString::from),
         subject: info_dict
             .get::<object::String<'_>>(SUBJECT)
-            .map(|t| t.to_vec()),
+            .map(TextString::from),
         keywords: info_dict
             .get::<object::String<'_>>(KEYWORDS)
-            .map(|t| t.to_vec()),
+            .map(TextString::from),
         creator: info_dict
             .get::<object::String<'_>>(CREATOR)
-            .map(|t| t.to_vec()),
+            .map(TextString::from),
         producer: info_dict
             .get::<object::String<'_>>(PRODUCER)
-            .map(|t| t.to_vec()),
+            .map(TextString::from),
     }
 }
 
@@ -1139,6 +1555,411 @@ mod tests {
         let _result = populate_xref_impl(pdf.as_ref(), xref_pos, &mut xref_map);
     }
 
+    /// A small helper for assembling PDF files with object streams.
+    #[derive(Default)]
+    struct TestPdf {
+        data: Vec<u8>,
+        offsets: Vec<(u32, usize)>,
+    }
+
+    impl TestPdf {
+        fn new() -> Self {
+            let mut pdf = Self {
+                data: b"%PDF-1.7\n".to_vec(),
+                offsets: vec![],
+            };
+            pdf.object(1, b"<< /Type /Catalog /Pages 2 0 R >>");
+            pdf.object(2, b"<< /Type /Pages /Kids [] /Count 0 >>");
+
+            pdf
+        }
+
+        fn object(&mut self, num: u32, body: &[u8]) {
+            self.offsets.push((num, self.data.len()));
+            self.data
+                .extend_from_slice(format!("{num} 0 obj\n").as_bytes());
+            self.data.extend_from_slice(body);
+            self.data.extend_from_slice(b"\nendobj\n");
+        }
+
+        fn object_stream(&mut self, num: u32, objects: &[(u32, &str)], extends: Option<u32>) {
+            let mut header = String::new();
+            let mut body = String::new();
+
+            for (obj_num, obj) in objects {
+                header.push_str(&format!("{obj_num} {} ", body.len()));
+                body.push_str(obj);
+                body.push(' ');
+            }
+
+            let extends = extends
+                .map(|e| format!(" /Extends {e} 0 R"))
+                .unwrap_or_default();
+            let content = format!("{header}{body}");
+            let stream = format!(
+                "<< /Type /ObjStm /N {} /First {}{extends} /Length {} >>\nstream\n{content}\nendstream",
+                objects.len(),
+                header.len(),
+                content.len()
+            );
+
+            self.object(num, stream.as_bytes());
+        }
+
+        /// Write an object stream with the given content and `N` and `First` entries.
+        fn raw_object_stream(&mut self, num: u32, content: &str, n: usize, first: usize) {
+            let stream = format!(
+                "<< /Type /ObjStm /N {n} /First {first} /Length {} >>\nstream\n{content}\nendstream",
+                content.len()
+            );
+
+            self.object(num, stream.as_bytes());
+        }
+
+        /// Write an xref stream with the given object number and return its offset,
+        /// where `compressed` contains triples of (object number, object stream number, index).
+        ///
+        /// If `only_compressed` is set, the stream doesn't contain the locations of the
+        /// uncompressed objects, like the xref stream of a hybrid-reference file.
+        fn xref_stream(
+            &mut self,
+            xref_num: u32,
+            compressed: &[(u32, u32, u32)],
+            only_compressed: bool,
+            extra: &str,
+        ) -> usize {
+            let mut entries = vec![];
+
+            for num in 0..=xref_num {
+                let offset = self.offsets.iter().find(|o| o.0 == num).map(|o| o.1);
+                let in_stream = compressed.iter().find(|c| c.0 == num);
+
+                let (ty, f2, f3) = match (offset, in_stream) {
+                    (_, Some((_, stream, index))) => (2, *stream, *index),
+                    _ if only_compressed => (0, 0, 0),
+                    (Some(offset), _) => (1, offset as u32, 0),
+                    // The offset of the xref stream itself.
+                    _ if num == xref_num => (1, self.data.len() as u32, 0),
+                    _ => (0, 0, 0),
+                };
+
+                entries.push(ty);
+                entries.extend_from_slice(&f2.to_be_bytes());
+                entries.extend_from_slice(&(f3 as u16).to_be_bytes());
+            }
+
+            let xref_pos = self.data.len();
+            let mut body = format!(
+                "<< /Type /XRef /Size {} /W [1 4 2] /Root 1 0 R{extra} /Length {} >>\nstream\n",
+                xref_num + 1,
+                entries.len()
+            )
+            .into_bytes();
+            body.extend_from_slice(&entries);
+            body.extend_from_slice(b"\nendstream");
+            self.object(xref_num, &body);
+
+            xref_pos
+        }
+
+        /// Write a classic xref table containing all uncompressed objects so far
+        /// and return its offset.
+        fn xref_table(&mut self, extra: &str) -> usize {
+            let size = self.offsets.iter().map(|o| o.0).max().unwrap_or(0) + 1;
+            let xref_pos = self.data.len();
+            let mut table = format!("xref\n0 {size}\n");
+
+            for num in 0..size {
+                match self.offsets.iter().rev().find(|o| o.0 == num) {
+                    Some((_, offset)) => table.push_str(&format!("{offset:010} 00000 n\r\n")),
+                    None => table.push_str("0000000000 65535 f\r\n"),
+                }
+            }
+
+            table.push_str(&format!("trailer\n<< /Size {size} /Root 1 0 R{extra} >>\n"));
+            self.data.extend_from_slice(table.as_bytes());
+
+            xref_pos
+        }
+
+        fn finish(mut self, xref_pos: usize) -> Vec<u8> {
+            self.data
+                .extend_from_slice(format!("startxref\n{xref_pos}\n%%EOF").as_bytes());
+
+            self.data
+        }
+
+        /// Finish the file with an xref stream, where `compressed` contains triples
+        /// of (object number, object stream number, index).
+        fn finish_with_xref_stream(mut self, compressed: &[(u32, u32, u32)]) -> Vec<u8> {
+            let xref_pos = self.xref_stream(20, compressed, false, "");
+
+            self.finish(xref_pos)
+        }
+
+        /// Finish the file without any xref, so that it has to be repaired.
+        fn finish_without_xref(mut self) -> Vec<u8> {
+            self.data
+                .extend_from_slice(b"trailer\n<< /Root 1 0 R >>\n%%EOF");
+
+            self.data
+        }
+    }
+
+    fn get_int(xref: &XRef, num: i32) -> Option<i32> {
+        xref.get::<i32>(ObjectIdentifier::new(num, 0))
+    }
+
+    #[test]
+    fn object_stream_wrong_index() {
+        let mut pdf = TestPdf::new();
+        pdf.object_stream(10, &[(6, "6"), (5, "5")], None);
+        // The indices in the xref stream are swapped.
+        let data = pdf.finish_with_xref_stream(&[(5, 10, 0), (6, 10, 1)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+
+        assert_eq!(get_int(&xref, 5), Some(5));
+        assert_eq!(get_int(&xref, 6), Some(6));
+    }
+
+    #[test]
+    fn object_stream_extends_lookup() {
+        let mut pdf = TestPdf::new();
+        pdf.object_stream(10, &[(6, "6")], Some(11));
+        pdf.object_stream(11, &[(8, "8")], Some(12));
+        pdf.object_stream(12, &[(7, "7")], None);
+        let data = pdf.finish_with_xref_stream(&[(6, 10, 0), (7, 10, 0)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+
+        assert_eq!(get_int(&xref, 6), Some(6));
+        assert_eq!(get_int(&xref, 7), Some(7));
+    }
+
+    #[test]
+    fn object_stream_extends_cycle() {
+        let mut pdf = TestPdf::new();
+        pdf.object_stream(10, &[(6, "6")], Some(11));
+        pdf.object_stream(11, &[(7, "7")], Some(10));
+        let data = pdf.finish_with_xref_stream(&[(6, 10, 0), (7, 11, 0), (8, 10, 3)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+
+        assert_eq!(get_int(&xref, 6), Some(6));
+        assert_eq!(get_int(&xref, 7), Some(7));
+        assert_eq!(get_int(&xref, 8), None);
+    }
+
+    #[test]
+    fn object_stream_truncated() {
+        let mut pdf = TestPdf::new();
+        // The stream claims to contain three objects, but was cut off in the
+        // middle of the header.
+        pdf.raw_object_stream(10, "5 0 6 2 7", 3, 12);
+        let data = pdf.finish_with_xref_stream(&[(5, 10, 0), (6, 10, 1), (7, 10, 2)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+
+        assert_eq!(get_int(&xref, 5), None);
+        assert_eq!(get_int(&xref, 7), None);
+
+        let mut pdf = TestPdf::new();
+        // The stream claims to contain three objects, but was cut off after the
+        // second one.
+        pdf.raw_object_stream(10, "5 0 6 2 7 4 5 6", 3, 12);
+        let data = pdf.finish_with_xref_stream(&[(5, 10, 0), (6, 10, 1), (7, 10, 2)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+
+        assert_eq!(get_int(&xref, 5), Some(5));
+        assert_eq!(get_int(&xref, 6), Some(6));
+        assert_eq!(get_int(&xref, 7), None);
+    }
+
+    #[test]
+    fn object_stream_invalid_first() {
+        let mut pdf = TestPdf::new();
+        pdf.raw_object_stream(10, "5 0 6 2 5 6", 2, 1000);
+        let data = pdf.finish_with_xref_stream(&[(5, 10, 0), (6, 10, 1)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+
+        assert_eq!(get_int(&xref, 5), Some(5));
+        assert_eq!(get_int(&xref, 6), Some(6));
+    }
+
+    #[test]
+    fn object_stream_invalid_n() {
+        let mut pdf = TestPdf::new();
+        pdf.raw_object_stream(10, "5 0 6 2 5 6", 1000, 8);
+        let data = pdf.finish_with_xref_stream(&[(5, 10, 0), (6, 10, 1)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+
+        assert_eq!(get_int(&xref, 5), Some(5));
+        assert_eq!(get_int(&xref, 6), Some(6));
+    }
+
+    #[test]
+    fn hybrid_xref() {
+        let mut pdf = TestPdf::new();
+        pdf.object_stream(10, &[(5, "5"), (6, "6")], None);
+        pdf.object(7, b"7");
+        let xref_stm = pdf.xref_stream(20, &[(5, 10, 0), (6, 10, 1)], true, "");
+        let xref_pos = pdf.xref_table(&format!(" /XRefStm {xref_stm}"));
+        let xref = root_xref(pdf.finish(xref_pos).into(), b"", 0).unwrap();
+
+        assert!(!xref.is_repaired());
+        assert_eq!(get_int(&xref, 5), Some(5));
+        assert_eq!(get_int(&xref, 6), Some(6));
+        assert_eq!(get_int(&xref, 7), Some(7));
+    }
+
+    #[test]
+    fn hybrid_xref_incremental_update() {
+        let mut pdf = TestPdf::new();
+        pdf.object(5, b"1");
+        pdf.object(6, b"2");
+        let first_xref = pdf.xref_table("");
+        // The update adds object 8 in an object stream and overrides object 6.
+        pdf.object_stream(10, &[(8, "3")], None);
+        pdf.object(6, b"4");
+        // Some writers also include `Prev` in the xref stream, which points to the
+        // same section as the table.
+        let xref_stm = pdf.xref_stream(20, &[(8, 10, 0)], true, &format!(" /Prev {first_xref}"));
+        let xref_pos = pdf.xref_table(&format!(" /Prev {first_xref} /XRefStm {xref_stm}"));
+        let xref = root_xref(pdf.finish(xref_pos).into(), b"", 0).unwrap();
+
+        assert!(!xref.is_repaired());
+        assert_eq!(get_int(&xref, 5), Some(1));
+        assert_eq!(get_int(&xref, 6), Some(4));
+        assert_eq!(get_int(&xref, 8), Some(3));
+    }
+
+    #[test]
+    fn repair_object_stream_extends_precedence() {
+        let mut pdf = TestPdf::new();
+        // The stream at the top of the chain comes first in the file, the stale
+        // one at the bottom of the chain last.
+        pdf.object_stream(10, &[(5, "1")], Some(11));
+        pdf.object_stream(11, &[(5, "2"), (6, "3")], Some(12));
+        pdf.object_stream(12, &[(5, "4"), (6, "5"), (7, "6")], None);
+        let data = pdf.finish_without_xref();
+        let xref = fallback(data.into(), b"").unwrap();
+
+        assert_eq!(get_int(&xref, 5), Some(1));
+        assert_eq!(get_int(&xref, 6), Some(3));
+        assert_eq!(get_int(&xref, 7), Some(6));
+    }
+
+    #[test]
+    fn repair_object_stream_file_order() {
+        let mut pdf = TestPdf::new();
+        // An unrelated stream that appears later in the file should still take
+        // precedence over the top of an earlier chain.
+        pdf.object_stream(10, &[(5, "1")], Some(11));
+        pdf.object_stream(11, &[(5, "2"), (6, "3")], None);
+        pdf.object_stream(12, &[(5, "4")], None);
+        let data = pdf.finish_without_xref();
+        let xref = fallback(data.into(), b"").unwrap();
+
+        assert_eq!(get_int(&xref, 5), Some(4));
+        assert_eq!(get_int(&xref, 6), Some(3));
+    }
+
+    #[test]
+    fn repair_object_stream_extends_cycle() {
+        let mut pdf = TestPdf::new();
+        pdf.object_stream(10, &[(5, "1")], Some(11));
+        pdf.object_stream(11, &[(5, "2")], Some(10));
+        let data = pdf.finish_without_xref();
+        let xref = fallback(data.into(), b"").unwrap();
+
+        assert!(get_int(&xref, 5).is_some());
+    }
+
+    #[test]
+    fn xref_relative_to_header() {
+        let mut pdf = TestPdf::new();
+        pdf.object(5, b"5");
+        pdf.object_stream(10, &[(6, "6")], None);
+        let xref_stm = pdf.xref_stream(20, &[(6, 10, 0)], true, "");
+        let xref_pos = pdf.xref_table(&format!(" /XRefStm {xref_stm}"));
+        let mut data = b"<html>Not found</html>\n".to_vec();
+        let header_offset = data.len();
+        data.extend(pdf.finish(xref_pos));
+        let xref = root_xref(data.into(), b"", header_offset).unwrap();
+
+        assert!(!xref.is_repaired());
+        assert_eq!(get_int(&xref, 5), Some(5));
+        assert_eq!(get_int(&xref, 6), Some(6));
+    }
+
+    #[test]
+    fn xref_relative_to_file_start() {
+        // Some writers account for the garbage in front of the header.
+        let mut pdf = TestPdf::new();
+        pdf.data.splice(0..0, *b"\xEF\xBB\xBF");
+        pdf.offsets.iter_mut().for_each(|o| o.1 += 3);
+        pdf.object(5, b"5");
+        let xref_pos = pdf.xref_table("");
+        let xref = root_xref(pdf.finish(xref_pos).into(), b"", 3).unwrap();
+
+        assert!(!xref.is_repaired());
+        assert_eq!(get_int(&xref, 5), Some(5));
+    }
+
+    #[test]
+    fn find_last_xref_without_eof() {
+        let pdf = b"%PDF-1.0\nstartxref\n42\n";
+        assert_eq!(find_last_xref_pos(pdf), Some(42));
+    }
+
+    #[test]
+    fn trailer_incremental_update() {
+        let mut pdf = TestPdf::new();
+        pdf.object(5, b"(Old)");
+        pdf.object(6, b"(New)");
+        let first_xref = pdf.xref_table(" /Info 5 0 R /ID [<01> <01>]");
+        pdf.object(7, b"7");
+        let xref_pos = pdf.xref_table(&format!(" /Prev {first_xref} /Info 6 0 R"));
+        let xref = root_xref(pdf.finish(xref_pos).into(), b"", 0).unwrap();
+        let trailer = xref.trailer();
+
+        assert_eq!(trailer.dicts().len(), 2);
+        assert_eq!(trailer.get::<i32>(SIZE), Some(8));
+        assert_eq!(trailer.get_ref(INFO), Some(ObjRef::new(6, 0)));
+        // The ID is only present in the first trailer.
+        assert!(trailer.contains_key(ID));
+        assert!(!trailer.contains_key(ENCRYPT));
+        assert_eq!(
+            trailer
+                .keys()
+                .map(|k| k.as_str().to_string())
+                .collect::<Vec<_>>(),
+            ["ID", "Info", "Prev", "Root", "Size"]
+        );
+    }
+
+    #[test]
+    fn trailer_xref_stream() {
+        let mut pdf = TestPdf::new();
+        pdf.object_stream(10, &[(5, "5")], None);
+        let data = pdf.finish_with_xref_stream(&[(5, 10, 0)]);
+        let xref = root_xref(data.into(), b"", 0).unwrap();
+        let trailer = xref.trailer();
+
+        assert_eq!(trailer.dicts().len(), 1);
+        assert_eq!(
+            trailer.get::<Name<'_>>(TYPE).as_deref(),
+            Some(b"XRef".as_ref())
+        );
+        assert_eq!(trailer.get_ref(ROOT), Some(ObjRef::new(1, 0)));
+    }
+
+    #[test]
+    fn trailer_repaired() {
+        let mut pdf = TestPdf::new();
+        pdf.object(5, b"5");
+        let xref = fallback(pdf.finish_without_xref().into(), b"").unwrap();
+
+        assert_eq!(xref.trailer().get_ref(ROOT), Some(ObjRef::new(1, 0)));
+    }
+
     #[test]
     fn find_last_xref_uses_last_startxref() {
         let pdf = b"%PDF-1.0\nstartxref\n5\n%%EOF\nstartxref\n42\n%%EOF";
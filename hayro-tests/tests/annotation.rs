@@ -0,0 +1,76 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use image::RgbaImage;
+
+const OPACITY_PATH: &str = "pdfs/custom/annotation_opacity.pdf";
+
+fn render_page(
+    path: &str,
+    page_index: usize,
+    interpreter_settings: &InterpreterSettings,
+) -> RgbaImage {
+    let pdf = load_pdf(path);
+    let page = &pdf.pages()[page_index];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    render_image(page, interpreter_settings, &render_settings)
+}
+
+// The first page contains a black "H" at (10, 40), which is covered by a yellow
+// highlight annotation without an explicit blend mode.
+#[test]
+fn highlight_annotation_multiply() {
+    let settings = InterpreterSettings {
+        multiply_highlight_annotations: true,
+        ..interpreter_settings()
+    };
+    let image = render_page(OPACITY_PATH, 0, &settings);
+
+    // The stem of the "H" is still visible through the highlight.
+    let stem = image.get_pixel(15, 100 - 55);
+    assert!(stem[0] < 50 && stem[1] < 50 && stem[2] < 50);
+
+    let highlight = image.get_pixel(50, 100 - 55);
+    assert_eq!(highlight.0, [255, 255, 0, 255]);
+}
+
+#[test]
+fn highlight_annotation_normal() {
+    let image = render_page(OPACITY_PATH, 0, &interpreter_settings());
+
+    let stem = image.get_pixel(15, 100 - 55);
+    assert_eq!(stem.0, [255, 255, 0, 255]);
+}
+
+// The second page contains a square annotation with an opacity of 0.5, whose
+// appearance stream paints a red square with an opacity of 0.5 as well.
+#[test]
+fn annotation_opacity_composes() {
+    let image = render_page(OPACITY_PATH, 1, &interpreter_settings());
+    let pixel = image.get_pixel(50, 50);
+
+    assert_eq!(pixel[0], 255);
+    // The effective opacity should be 0.25.
+    assert!(pixel[1].abs_diff(191) <= 2);
+    assert!(pixel[2].abs_diff(191) <= 2);
+}
+
+// The page contains three checkboxes with the same `On` (black) and `Off` (red)
+// appearance states. The first one is checked, the second one is unchecked and
+// the third one has no appearance state at all, so it shouldn't be drawn.
+#[test]
+fn annotation_appearance_state() {
+    let image = render_page(
+        "pdfs/custom/annotation_appearance_state.pdf",
+        0,
+        &interpreter_settings(),
+    );
+
+    assert_eq!(image.get_pixel(40, 50).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(100, 50).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(160, 50).0, [255, 255, 255, 255]);
+}
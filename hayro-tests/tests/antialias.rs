@@ -0,0 +1,45 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{AntialiasMode, RenderSettings};
+use image::RgbaImage;
+
+// The page contains a black triangle whose hypotenuse runs diagonally across the page.
+fn render_page(antialias: AntialiasMode) -> RgbaImage {
+    let pdf = load_pdf("pdfs/custom/antialias_diagonal.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        antialias,
+        ..Default::default()
+    };
+    render_image(page, &interpreter_settings(), &render_settings)
+}
+
+fn is_black_or_white(pixel: [u8; 4]) -> bool {
+    pixel == [0, 0, 0, 255] || pixel == [255, 255, 255, 255]
+}
+
+#[test]
+fn antialias_none() {
+    let image = render_page(AntialiasMode::None);
+
+    assert!(image.pixels().all(|p| is_black_or_white(p.0)));
+    assert_eq!(image.get_pixel(10, 80).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(80, 10).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn antialias_fast() {
+    let image = render_page(AntialiasMode::Fast);
+
+    assert!(image.pixels().any(|p| !is_black_or_white(p.0)));
+}
+
+#[test]
+fn antialias_high() {
+    let image = render_page(AntialiasMode::High);
+
+    assert!(image.pixels().any(|p| !is_black_or_white(p.0)));
+    assert_eq!(image.get_pixel(10, 80).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(80, 10).0, [255, 255, 255, 255]);
+}
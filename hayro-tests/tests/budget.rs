@@ -0,0 +1,89 @@
+use crate::{interpreter_settings, load_pdf, pixmap_to_image};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings, RenderStatus, render_with_status};
+use image::RgbaImage;
+
+// The left half of the page is filled with red inside of a group with a soft
+// mask that is white everywhere, the right half with a blue tiling pattern.
+const PATH: &str = "pdfs/custom/pixmap_budget.pdf";
+// Like the above, but the soft mask is applied to the red path directly instead
+// of to a group, and the right half is filled with plain blue.
+const PATH_MASK_PATH: &str = "pdfs/custom/pixmap_budget_path_mask.pdf";
+// The number of bytes of the page pixmap at a scale of 1.
+const PAGE_BYTES: usize = 200 * 100 * 4;
+
+fn render_with_budget(scale: f32, max_pixmap_bytes: Option<usize>) -> (RgbaImage, RenderStatus) {
+    render_file_with_budget(PATH, scale, max_pixmap_bytes)
+}
+
+fn render_file_with_budget(
+    path: &str,
+    scale: f32,
+    max_pixmap_bytes: Option<usize>,
+) -> (RgbaImage, RenderStatus) {
+    let pdf = load_pdf(path);
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        x_scale: scale,
+        y_scale: scale,
+        bg_color: WHITE,
+        max_pixmap_bytes,
+        ..Default::default()
+    };
+    let (pixmap, status) = render_with_status(
+        page,
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings,
+    );
+
+    let image = if pixmap.width() == 0 {
+        RgbaImage::new(0, 0)
+    } else {
+        pixmap_to_image(pixmap)
+    };
+
+    (image, status)
+}
+
+#[test]
+fn budget_sufficient() {
+    let (image, status) = render_with_budget(1.0, Some(PAGE_BYTES * 4));
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!(image.get_pixel(50, 50).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(150, 50).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn budget_exceeded_by_page() {
+    // At this scale, the page would need about 7 GB, even though its dimensions
+    // are still within the limits supported by the renderer.
+    let (image, status) = render_with_budget(300.0, Some(256 * 1024 * 1024));
+    assert_eq!(status, RenderStatus::OverBudget);
+    assert_eq!(image.dimensions(), (0, 0));
+}
+
+#[test]
+fn budget_exceeded_by_intermediates() {
+    // There is only room for 100 more pixels, so the soft mask can't be rendered
+    // and the masked contents are left out, while the pattern tile is rendered at
+    // a lower resolution.
+    let (image, status) = render_with_budget(1.0, Some(PAGE_BYTES + 400));
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!(image.get_pixel(50, 50).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(150, 50).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn budget_exceeded_by_path_soft_mask() {
+    let (image, status) = render_file_with_budget(PATH_MASK_PATH, 1.0, Some(PAGE_BYTES * 2));
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!(image.get_pixel(50, 50).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(150, 50).0, [0, 0, 255, 255]);
+
+    // The soft mask of the path doesn't fit anymore, so the path is left out.
+    let (image, status) = render_file_with_budget(PATH_MASK_PATH, 1.0, Some(PAGE_BYTES + 400));
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!(image.get_pixel(50, 50).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(150, 50).0, [0, 0, 255, 255]);
+}
@@ -0,0 +1,65 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use kurbo::{Rect, Shape};
+
+#[test]
+fn extra_clip() {
+    // The page is filled with black from (10, 10) to (90, 91).
+    let pdf = load_pdf("pdfs/custom/image_mask_strips.pdf");
+    let page = &pdf.pages()[0];
+    let extra_clip = Rect::new(30.0, 40.0, 60.0, 50.0).to_path(0.1);
+    let render_settings = RenderSettings {
+        x_scale: 2.0,
+        y_scale: 2.0,
+        bg_color: WHITE,
+        extra_clip: Some(&extra_clip),
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    // Convert from page coordinates into pixel coordinates.
+    let is_black = |x: u32, y: u32| image.get_pixel(x * 2, (100 - y) * 2)[0] < 128;
+
+    assert!(is_black(45, 45));
+    assert!(is_black(31, 41));
+    assert!(is_black(59, 49));
+    assert!(!is_black(20, 20));
+    assert!(!is_black(45, 55));
+    assert!(!is_black(70, 45));
+    assert!(!is_black(45, 35));
+}
+
+// Both pages draw a form XObject whose content covers the whole page, while its
+// bbox only spans the area from (20, 30) to (60, 60) on the page.
+fn check_xobject_bbox(page_index: usize) {
+    let pdf = load_pdf("pdfs/custom/xobject_bbox_overflow.pdf");
+    let page = &pdf.pages()[page_index];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    let is_white = |x: u32, y: u32| {
+        let pixel = image.get_pixel(x, 100 - y);
+        pixel[0] == 255 && pixel[1] == 255 && pixel[2] == 255
+    };
+
+    assert!(!is_white(25, 35));
+    assert!(!is_white(55, 55));
+    assert!(is_white(15, 45));
+    assert!(is_white(65, 45));
+    assert!(is_white(40, 25));
+    assert!(is_white(40, 65));
+}
+
+#[test]
+fn xobject_bbox_clips_content() {
+    check_xobject_bbox(0);
+}
+
+#[test]
+fn xobject_bbox_clips_shading() {
+    check_xobject_bbox(1);
+}
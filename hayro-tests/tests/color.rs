@@ -0,0 +1,134 @@
+use crate::{Recorder, interpreter_settings, load_pdf, render_image};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{
+    OutputFormat, RenderCache, RenderSettings, RenderedPixmap, render, render_with_format,
+};
+use hayro_interpret::color::RenderingIntent;
+use hayro_interpret::{Context, InterpreterCache, Paint, interpret_page};
+use kurbo::{Affine, Rect};
+
+// All pages draw the same scatter plot. The first page sets the colors before every
+// marker, the second one does the same using `scn`/`SCN` and the last one only sets
+// the colors when they actually change.
+#[test]
+fn repeated_color_changes() {
+    let pdf = load_pdf("pdfs/custom/scatter_plot.pdf");
+    let cache = RenderCache::new();
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    let rendered = pdf
+        .pages()
+        .iter()
+        .map(|page| {
+            render(page, &cache, &interpreter_settings(), &render_settings)
+                .into_png()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(rendered.len(), 3);
+    assert!(rendered[0] == rendered[2]);
+    assert!(rendered[1] == rendered[2]);
+}
+
+// Colors cache their converted value, which shouldn't prevent them from being
+// shared between threads.
+#[test]
+fn color_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<hayro_interpret::color::Color>();
+}
+
+// The page paints a red square with a transfer function that inverts all
+// channels, a red square with one that only inverts the red channel, and a red
+// and a blue square for which the transfer function was reset again. At the
+// bottom, an orange image is drawn with the second transfer function.
+#[test]
+fn transfer_function() {
+    let pdf = load_pdf("pdfs/custom/transfer_function.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    assert_eq!(image.get_pixel(25, 25).0, [0, 255, 255, 255]);
+    assert_eq!(image.get_pixel(75, 25).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(25, 75).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(75, 75).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(50, 125).0, [0, 128, 0, 255]);
+}
+
+// The page shows a red, a green, a blue and a yellow square next to each other,
+// followed by an empty area.
+#[test]
+fn gray_output() {
+    let pdf = load_pdf("pdfs/custom/gray_output.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        output_format: OutputFormat::Gray8,
+        ..Default::default()
+    };
+    let (pixmap, _) = render_with_format(
+        page,
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings,
+    );
+
+    let RenderedPixmap::Gray8(pixmap) = pixmap else {
+        panic!("expected a grayscale pixmap");
+    };
+
+    assert_eq!((pixmap.width(), pixmap.height()), (250, 50));
+    assert_eq!(pixmap.data().len(), 250 * 50);
+
+    let gray = [25, 75, 125, 175, 225].map(|x| pixmap.data()[25 * 250 + x]);
+    assert_eq!(gray, [76, 150, 29, 226, 255]);
+}
+
+// The page fills five rectangles. The first one uses the default intent, the
+// second one sets `Perceptual` inside of `q`/`Q`, the third one sets `Saturation`
+// via `ri`, the fourth one sets `AbsoluteColorimetric` via the `RI` entry of an
+// ExtGState and the last one uses an unknown intent.
+#[test]
+fn rendering_intent() {
+    let pdf = load_pdf("pdfs/custom/rendering_intent.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 200.0, 100.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let mut recorder = Recorder::default();
+    interpret_page(page, &mut context, &mut recorder);
+
+    let intents = recorder
+        .paths
+        .iter()
+        .filter_map(|(_, props, _)| match &props.paint {
+            Paint::Color(color) => Some(color.rendering_intent()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        intents,
+        [
+            RenderingIntent::RelativeColorimetric,
+            RenderingIntent::Perceptual,
+            RenderingIntent::Saturation,
+            RenderingIntent::AbsoluteColorimetric,
+            RenderingIntent::RelativeColorimetric,
+        ]
+    );
+}
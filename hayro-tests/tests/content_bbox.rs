@@ -0,0 +1,92 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::hayro_interpret::color::AlphaColor;
+use hayro::hayro_interpret::{InterpreterCache, content_bbox};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use kurbo::Rect;
+
+// Both pages show the word "Hi" on top of a white rectangle covering the whole
+// page, as well as a black square that is completely clipped away. The second
+// page is rotated by 90 degrees.
+const PATH: &str = "pdfs/custom/content_bbox.pdf";
+
+// Return the bounding box of all non-white pixels of the rendered page, in
+// the default user space of the page.
+fn pixel_bbox() -> Rect {
+    let pdf = load_pdf(PATH);
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    let mut bbox = (u32::MAX, u32::MAX, 0, 0);
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel.0 != [255, 255, 255, 255] {
+            bbox = (
+                bbox.0.min(x),
+                bbox.1.min(y),
+                bbox.2.max(x + 1),
+                bbox.3.max(y + 1),
+            );
+        }
+    }
+
+    let height = image.height() as f64;
+
+    Rect::new(
+        bbox.0 as f64,
+        height - bbox.3 as f64,
+        bbox.2 as f64,
+        height - bbox.1 as f64,
+    )
+}
+
+fn page_content_bbox(index: usize, background: Option<AlphaColor>) -> Option<Rect> {
+    let pdf = load_pdf(PATH);
+    let page = &pdf.pages()[index];
+
+    content_bbox(
+        page,
+        &InterpreterCache::new(),
+        interpreter_settings(),
+        background,
+    )
+}
+
+#[test]
+fn content_bbox_glyphs() {
+    let bbox = page_content_bbox(0, Some(AlphaColor::WHITE)).unwrap();
+    let expected = pixel_bbox();
+
+    // Anti-aliased pixels are only partially covered, so the pixel bounding
+    // box can be up to one pixel larger in each direction.
+    for (a, b) in [
+        (bbox.x0, expected.x0),
+        (bbox.y0, expected.y0),
+        (bbox.x1, expected.x1),
+        (bbox.y1, expected.y1),
+    ] {
+        assert!((a - b).abs() <= 1.0, "{bbox:?} doesn't match {expected:?}");
+    }
+}
+
+#[test]
+fn content_bbox_rotated() {
+    let bbox = page_content_bbox(0, Some(AlphaColor::WHITE)).unwrap();
+    let rotated = page_content_bbox(1, Some(AlphaColor::WHITE)).unwrap();
+
+    assert!((bbox.x0 - rotated.x0).abs() < 1e-6);
+    assert!((bbox.y0 - rotated.y0).abs() < 1e-6);
+    assert!((bbox.x1 - rotated.x1).abs() < 1e-6);
+    assert!((bbox.y1 - rotated.y1).abs() < 1e-6);
+}
+
+#[test]
+fn content_bbox_without_background() {
+    let bbox = page_content_bbox(0, None).unwrap();
+
+    assert_eq!(bbox, Rect::new(0.0, 0.0, 200.0, 100.0));
+}
@@ -0,0 +1,46 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use image::RgbaImage;
+
+// The page contains many rectangles outside of the crop box, as well as
+// objects whose path lies outside of it but that are still partially visible.
+fn render_page() -> RgbaImage {
+    let pdf = load_pdf("pdfs/custom/cull_offscreen.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    render_image(page, &interpreter_settings(), &render_settings)
+}
+
+#[test]
+fn cull_keeps_wide_stroke_outside_of_crop_box() {
+    let image = render_page();
+
+    // The line lies above the page, but half of the line width reaches into it.
+    assert_eq!(image.get_pixel(10, 2).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(10, 8).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn cull_keeps_miter_join_outside_of_crop_box() {
+    let image = render_page();
+
+    // The tip of the miter join of a path below the page reaches into it.
+    let pixel = image.get_pixel(50, 95).0;
+    assert!(pixel[0] > 200 && pixel[1] < 50, "{pixel:?}");
+}
+
+#[test]
+fn cull_respects_nested_clips() {
+    let image = render_page();
+
+    // Drawn inside of the clip.
+    assert_eq!(image.get_pixel(20, 80).0, [0, 255, 255, 255]);
+    // Drawn outside of the clip.
+    assert_eq!(image.get_pixel(65, 35).0, [255, 255, 255, 255]);
+    // Drawn after the clip has been popped again.
+    assert_eq!(image.get_pixel(80, 20).0, [255, 0, 255, 255]);
+}
@@ -0,0 +1,113 @@
+use crate::{interpreter_settings, load_pdf, pixmap_to_image};
+use hayro::hayro_interpret::{Deadline, InterpreterSettings};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings, RenderStatus, render_with_outcome, render_with_status};
+use image::RgbaImage;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// The page is filled with black from (10, 10) to (90, 91).
+const PATH: &str = "pdfs/custom/image_mask_strips.pdf";
+
+fn render_page(deadline: Option<Instant>) -> (RgbaImage, RenderStatus) {
+    let pdf = load_pdf(PATH);
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        deadline,
+        ..Default::default()
+    };
+    let (pixmap, status) = render_with_status(
+        page,
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings,
+    );
+
+    (pixmap_to_image(pixmap), status)
+}
+
+#[test]
+fn deadline_not_exceeded() {
+    let (image, status) = render_page(Some(Instant::now() + Duration::from_secs(3600)));
+
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!(image.get_pixel(50, 50).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn deadline_exceeded() {
+    let (image, status) = render_page(Some(Instant::now()));
+
+    // Nothing was interpreted, so only the background is visible.
+    assert_eq!(status, RenderStatus::TimedOut);
+    assert!(image.pixels().all(|p| p.0 == [255, 255, 255, 255]));
+}
+
+fn render_cancellable(cancel: bool) -> (RgbaImage, bool, u64) {
+    let pdf = load_pdf(PATH);
+    let page = &pdf.pages()[0];
+    let cancel = Arc::new(AtomicBool::new(cancel));
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        cancel: Some(&cancel),
+        ..Default::default()
+    };
+    let outcome = render_with_outcome(
+        page,
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings,
+    );
+
+    (
+        pixmap_to_image(outcome.pixmap),
+        outcome.completed,
+        outcome.ops_executed,
+    )
+}
+
+#[test]
+fn cancel_flag_not_set() {
+    let (image, completed, ops_executed) = render_cancellable(false);
+
+    assert!(completed);
+    assert!(ops_executed > 0);
+    assert_eq!(image.get_pixel(50, 50).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn cancel_flag_set() {
+    let (image, completed, ops_executed) = render_cancellable(true);
+
+    assert!(!completed);
+    assert_eq!(ops_executed, 0);
+    assert!(image.pixels().all(|p| p.0 == [255, 255, 255, 255]));
+}
+
+#[test]
+fn deadline_reset_between_renders() {
+    let pdf = load_pdf(PATH);
+    let page = &pdf.pages()[0];
+    let cache = RenderCache::new();
+    let cancel = Arc::new(AtomicBool::new(true));
+    let interpreter_settings = InterpreterSettings {
+        deadline: Some(Deadline::from_flag(cancel.clone())),
+        ..interpreter_settings()
+    };
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    let (_, status) = render_with_status(page, &cache, &interpreter_settings, &render_settings);
+    assert_eq!(status, RenderStatus::TimedOut);
+
+    // The deadline of the first render shouldn't carry over to the second one.
+    cancel.store(false, Ordering::Relaxed);
+    let (pixmap, status) =
+        render_with_status(page, &cache, &interpreter_settings, &render_settings);
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!(pixmap_to_image(pixmap).get_pixel(50, 50).0, [0, 0, 0, 255]);
+}
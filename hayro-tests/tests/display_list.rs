@@ -0,0 +1,57 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings, record, render, render_display_list};
+
+fn check_replay(file_path: &str) {
+    let pdf = load_pdf(file_path);
+    let settings = interpreter_settings();
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    for page in pdf.pages().iter() {
+        let cache = RenderCache::new();
+        let expected = render(page, &cache, &settings, &render_settings);
+
+        let cache = RenderCache::new();
+        let list = record(page, &cache, &settings);
+        let actual = render_display_list(page, &list, &cache, &render_settings);
+
+        assert_eq!(
+            expected.into_png().unwrap(),
+            actual.into_png().unwrap(),
+            "replayed display list of {file_path} differs from direct rendering"
+        );
+    }
+}
+
+#[test]
+fn display_list_mask_luminosity() {
+    check_replay("pdfs/custom/mask_luminosity.pdf");
+}
+
+#[test]
+fn display_list_pattern_tiling_nested() {
+    check_replay("pdfs/custom/pattern_tiling_nested.pdf");
+}
+
+#[test]
+fn display_list_pattern_tiling_stencil() {
+    check_replay("pdfs/custom/pattern_tiling_stencil.pdf");
+}
+
+#[test]
+fn display_list_image_rgba8() {
+    check_replay("pdfs/custom/image_rgba8.pdf");
+}
+
+#[test]
+fn display_list_image_mask() {
+    check_replay("pdfs/custom/image_mask.pdf");
+}
+
+#[test]
+fn display_list_font_type3_filled_glyphs() {
+    check_replay("pdfs/custom/font_type3_filled_glyphs.pdf");
+}
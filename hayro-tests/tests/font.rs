@@ -0,0 +1,319 @@
+use crate::{Recorder, interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::hayro_syntax::page::Page;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro_interpret::font::{FontQuery, Glyph, StandardFont};
+use hayro_interpret::text::TextExtractor;
+use hayro_interpret::{
+    Context, InterpreterCache, InterpreterSettings, InterpreterWarning, NotdefBehavior,
+    interpret_page,
+};
+use kurbo::{Affine, Rect, Shape};
+use std::sync::{Arc, Mutex};
+
+// The embedded TrueType font has a glyph covering the left half of the em square
+// (GID 1) and one covering the bottom half (GID 2). The `CIDToGIDMap` swaps them,
+// so CID 1 should show the bottom half and CID 2 the left half.
+#[test]
+fn font_cid_to_gid_map() {
+    let pdf = load_pdf("pdfs/custom/font_cid_to_gid_map.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    // CID 1
+    assert_eq!(image.get_pixel(75, 75).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(25, 25).0, [255, 255, 255, 255]);
+
+    // CID 2
+    assert_eq!(image.get_pixel(125, 25).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(175, 75).0, [255, 255, 255, 255]);
+}
+
+// Each line shows the same text with a non-embedded standard font (or one of its
+// aliases) that has no `Widths` array, so the advances must be taken from the AFM
+// metrics of the standard fonts, regardless of the substitute font used for rendering.
+// The expected line extents are the ones computed by pdfium.
+#[test]
+fn font_standard_metrics() {
+    let pdf = load_pdf("pdfs/custom/font_standard_metrics.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 600.0, 200.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let mut extractor = TextExtractor::new();
+    interpret_page(page, &mut context, &mut extractor);
+
+    let extents = extractor
+        .lines()
+        .iter()
+        .map(|line| {
+            let first = line.glyphs().first().unwrap();
+            let last = line.glyphs().last().unwrap();
+
+            last.origin.x + last.advance - first.origin.x
+        })
+        .collect::<Vec<_>>();
+
+    // Arial, Helvetica, TimesNewRoman,Bold, CourierNewPSMT and Arial-BoldItalicMT.
+    let expected = [312.37, 312.37, 301.82, 414.0, 335.85];
+
+    assert_eq!(extents.len(), expected.len());
+
+    for (extent, expected) in extents.iter().zip(expected) {
+        assert!(
+            (extent - expected).abs() < 0.1,
+            "expected {expected}, got {extent}"
+        );
+    }
+}
+
+// The same page as above, but the advances of all glyphs of the second line (set
+// in Helvetica) are overridden to 500 units, so that they are evenly spaced.
+#[test]
+fn font_advance_override() {
+    let pdf = load_pdf("pdfs/custom/font_standard_metrics.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let settings = InterpreterSettings {
+        advance_override: Some(Arc::new(|query, _| match query {
+            FontQuery::Fallback(f) if f.post_script_name.as_deref() == Some("Helvetica") => {
+                Some(500.0)
+            }
+            _ => None,
+        })),
+        ..interpreter_settings()
+    };
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 600.0, 200.0),
+        &cache,
+        pdf.xref(),
+        settings,
+    );
+    let mut extractor = TextExtractor::new();
+    interpret_page(page, &mut context, &mut extractor);
+
+    let lines = extractor.lines();
+    let first = lines[0].glyphs();
+    let second = lines[1].glyphs();
+    assert_eq!(first.len(), second.len());
+
+    // At a font size of 10, each glyph advances by 5 units. Only look at the
+    // ASCII part of the line, some of the other characters can't be extracted.
+    for (i, pair) in second[..40].windows(2).enumerate() {
+        let step = pair[1].origin.x - pair[0].origin.x;
+        assert!((step - 5.0).abs() < 1e-6, "glyph {i} advances by {step}");
+        // Devices see the overridden advance as well.
+        let advance = pair[0].advance;
+        assert!((advance - 5.0).abs() < 1e-6, "glyph {i} reports {advance}");
+    }
+
+    // The first line is unaffected.
+    let extent = first.last().unwrap().origin.x + first.last().unwrap().advance - first[0].origin.x;
+    assert!((extent - 312.37).abs() < 0.1, "{extent}");
+}
+
+// Both fonts are non-embedded and have a `Widths` array giving "l" an advance of
+// 1000 units. Arial only has metrics similar to Helvetica, so its glyph should be
+// stretched to the width from the array, while Helvetica ignores it.
+#[test]
+fn font_alias_widths() {
+    let pdf = load_pdf("pdfs/custom/font_alias_widths.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 200.0, 100.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let recorder = record_glyphs(page, &mut context);
+
+    let [arial, helvetica] = recorder.glyph_bboxes[..] else {
+        panic!("expected two glyphs");
+    };
+    assert!(arial.width() > 30.0, "{arial:?}");
+    assert!(helvetica.width() < 10.0, "{helvetica:?}");
+}
+
+/// The IDs and the bounding boxes of all drawn outline glyphs, as well as the
+/// bounding boxes of all drawn paths.
+struct RecordedGlyphs {
+    glyph_ids: Vec<u32>,
+    glyph_bboxes: Vec<Rect>,
+    paths: Vec<Rect>,
+}
+
+fn record_glyphs<'a>(page: &Page<'a>, context: &mut Context<'a>) -> RecordedGlyphs {
+    let mut recorder = Recorder::default();
+    interpret_page(page, context, &mut recorder);
+
+    let outlines = recorder
+        .glyphs
+        .iter()
+        .filter_map(|(glyph, transform, _, _)| match glyph {
+            Glyph::Outline(glyph) => Some((glyph, *transform)),
+            _ => None,
+        });
+
+    RecordedGlyphs {
+        glyph_ids: outlines
+            .clone()
+            .map(|(glyph, _)| glyph.glyph_id().to_u32())
+            .collect(),
+        glyph_bboxes: outlines
+            .map(|(glyph, transform)| (transform * glyph.outline()).bounding_box())
+            .collect(),
+        paths: recorder
+            .paths
+            .iter()
+            .map(|(path, _, _)| path.bounding_box())
+            .collect(),
+    }
+}
+
+// The embedded CFF font is a subset of TeX Gyre Pagella Math. The `Differences`
+// array maps the codes of "ABC" to `three` and `one`, whose names are standard
+// strings, and `aleph`, whose name is stored in the String INDEX of the font.
+#[test]
+fn font_type1_cff_differences() {
+    let pdf = load_pdf("pdfs/custom/font_type1_cff_differences.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 200.0, 100.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let recorder = record_glyphs(page, &mut context);
+
+    assert_eq!(recorder.glyph_ids, [3, 18, 1]);
+}
+
+// The embedded TrueType font is symbolic and has a (3,0) subtable mapping 0xF041 to
+// GID 1, a (1,0) subtable mapping 0x42 to GID 2 and a (3,1) subtable. The font uses
+// `WinAnsiEncoding` with a `Differences` array mapping 0x43 and 0x44 to `X` and `Y`.
+// The codes 0x43, 0x44 and 0x93 can't be resolved via the first two subtables, so
+// their glyph names (`X`, `Y` and `quotedblleft`) should be looked up in the (3,1)
+// subtable instead of the raw codes, which map to different glyphs.
+#[test]
+fn font_symbolic_truetype_encoding() {
+    let pdf = load_pdf("pdfs/custom/font_symbolic_truetype_encoding.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 200.0, 100.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let recorder = record_glyphs(page, &mut context);
+
+    assert_eq!(recorder.glyph_ids, [1, 2, 3, 4, 3]);
+}
+
+fn record_notdef(notdef_behavior: NotdefBehavior) -> RecordedGlyphs {
+    let pdf = load_pdf("pdfs/custom/font_notdef.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 200.0, 100.0),
+        &cache,
+        pdf.xref(),
+        InterpreterSettings {
+            notdef_behavior,
+            ..interpreter_settings()
+        },
+    );
+    record_glyphs(page, &mut context)
+}
+
+// The font is a non-embedded Helvetica whose `Differences` array maps the codes
+// of "AB" to `bogus`, which doesn't exist at all, and `uni0042`, which isn't in
+// the substitute font but can be mapped to "B".
+#[test]
+fn font_notdef_behavior() {
+    // By default, the (empty) .notdef glyph is drawn for both codes.
+    let recorder = record_notdef(NotdefBehavior::Drop);
+    assert_eq!(recorder.glyph_ids, [0, 0]);
+    assert!(recorder.paths.is_empty());
+
+    // A box with the advance of 600 units is drawn for each glyph instead.
+    let recorder = record_notdef(NotdefBehavior::Box);
+    assert!(recorder.glyph_ids.is_empty());
+    assert_eq!(recorder.paths.len(), 2);
+    assert!(recorder.paths[0].width() > 20.0 && recorder.paths[0].height() > 30.0);
+    assert!(recorder.paths[1].x0 > recorder.paths[0].x1);
+
+    // The second code can be substituted, the first one still falls back to a box.
+    let recorder = record_notdef(NotdefBehavior::Substitute(StandardFont::Courier));
+    assert_eq!(recorder.paths.len(), 1);
+    assert_eq!(recorder.glyph_ids.len(), 1);
+    assert_ne!(recorder.glyph_ids[0], 0);
+}
+
+// The embedded TrueType font was subsetted too aggressively, so that the glyphs of
+// "B" (GID 2) and the space (GID 3) are empty. Both "B"s should be drawn with the
+// fallback font instead, stretched to the advance of 700 units of the original
+// glyph, and be reported once. The space is blank anyway and stays as it is.
+#[test]
+fn font_subset_fallback() {
+    let pdf = load_pdf("pdfs/custom/font_subset_fallback.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let warnings = Arc::new(Mutex::new(vec![]));
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 200.0, 100.0),
+        &cache,
+        pdf.xref(),
+        InterpreterSettings {
+            warning_sink: Arc::new({
+                let warnings = warnings.clone();
+                move |w| warnings.lock().unwrap().push(w)
+            }),
+            ..interpreter_settings()
+        },
+    );
+    let recorder = record_glyphs(page, &mut context);
+
+    let ids = &recorder.glyph_ids;
+    assert_eq!(ids.len(), 4);
+    assert_eq!((ids[0], ids[2]), (1, 3));
+    assert_ne!(ids[1], 2);
+    assert_eq!(ids[1], ids[3]);
+
+    // At a font size of 50, the advance of the glyphs is 35 units.
+    for bbox in [recorder.glyph_bboxes[1], recorder.glyph_bboxes[3]] {
+        assert!(bbox.width() > 20.0 && bbox.width() < 35.0, "{bbox:?}");
+        assert!(bbox.height() > 30.0, "{bbox:?}");
+    }
+    assert!(recorder.glyph_bboxes[3].x0 - recorder.glyph_bboxes[1].x0 > 35.0);
+    assert!(recorder.glyph_bboxes[2].is_zero_area());
+
+    let warnings = warnings.lock().unwrap();
+    assert!(
+        matches!(
+            warnings[..],
+            [InterpreterWarning::MissingGlyph {
+                code: 0x42,
+                unicode: 'B'
+            }]
+        ),
+        "{warnings:?}"
+    );
+}
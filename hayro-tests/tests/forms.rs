@@ -0,0 +1,117 @@
+use crate::load_pdf;
+use hayro::hayro_syntax::forms::{FieldType, FieldValue};
+use hayro::hayro_syntax::object::TextString;
+
+/// Return the decoded text of a text value.
+fn text(value: Option<FieldValue>) -> Option<String> {
+    match value? {
+        FieldValue::Text(text) => Some(text.to_string_lossy()),
+        _ => None,
+    }
+}
+
+// The form contains a text field group `person` with the kids `name` and
+// `address`, as well as a radio button group `choice` with widgets on both pages.
+#[test]
+fn forms_qualified_names() {
+    let pdf = load_pdf("pdfs/custom/acro_form.pdf");
+    let form = pdf.acro_form().unwrap();
+
+    let names = form
+        .all_fields()
+        .iter()
+        .map(|f| f.qualified_name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["person", "person.name", "person.address", "choice"]);
+
+    let name = form.field("person.name").unwrap();
+    assert_eq!(
+        name.partial_name().map(TextString::to_string_lossy),
+        Some("name".to_string())
+    );
+    assert_eq!(form.fields().len(), 2);
+    assert_eq!(form.fields()[0].kids().len(), 2);
+}
+
+#[test]
+fn forms_text_fields() {
+    let pdf = load_pdf("pdfs/custom/acro_form.pdf");
+    let form = pdf.acro_form().unwrap();
+
+    let name = form.field("person.name").unwrap();
+    // The field type is inherited from the parent, the default appearance
+    // from the form.
+    assert_eq!(name.field_type(), Some(FieldType::Text));
+    assert_eq!(name.default_appearance(), Some(&b"/Helv 0 Tf 0 g"[..]));
+    assert_eq!(text(name.value()).as_deref(), Some("Jérôme"));
+    assert!(name.flags().required());
+    assert!(!name.flags().multiline());
+
+    let widgets = name.widgets();
+    assert_eq!(widgets.len(), 1);
+    assert_eq!(widgets[0].page_index(), Some(0));
+    assert_eq!(widgets[0].rect().y1, 190.0);
+
+    let address = form.field("person.address").unwrap();
+    assert_eq!(address.field_type(), Some(FieldType::Text));
+    assert_eq!(text(address.value()).as_deref(), Some("Straße 1"));
+    assert!(address.flags().multiline());
+    // The field is merged with its widget, which is found via the annotations
+    // of the page.
+    assert_eq!(address.widgets().len(), 1);
+    assert_eq!(address.widgets()[0].page_index(), Some(0));
+}
+
+#[test]
+fn forms_radio_buttons() {
+    let pdf = load_pdf("pdfs/custom/acro_form.pdf");
+    let form = pdf.acro_form().unwrap();
+
+    let choice = form.field("choice").unwrap();
+    assert_eq!(choice.field_type(), Some(FieldType::Button));
+    assert!(choice.flags().radio());
+    assert!(!choice.flags().push_button());
+    assert_eq!(choice.value(), Some(FieldValue::Name("B".to_string())));
+    assert_eq!(
+        choice.default_value(),
+        Some(FieldValue::Name("A".to_string()))
+    );
+
+    let widgets = choice
+        .widgets()
+        .iter()
+        .map(|w| {
+            (
+                w.page_index(),
+                w.appearance_state().unwrap().as_str().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        widgets,
+        [(Some(0), "Off".to_string()), (Some(1), "B".to_string())]
+    );
+}
+
+#[test]
+fn forms_xfa_datasets() {
+    let pdf = load_pdf("pdfs/custom/acro_form.pdf");
+    let form = pdf.acro_form().unwrap();
+
+    let packets = form.xfa_packets();
+    assert_eq!(packets.len(), 2);
+    assert_eq!(
+        packets[0].name.as_ref().map(TextString::to_string_lossy),
+        Some("template".to_string())
+    );
+
+    let datasets = form.xfa_datasets().unwrap();
+    assert!(datasets.starts_with(b"<xfa:datasets"));
+    assert!(datasets.ends_with(b"</xfa:datasets>"));
+}
+
+#[test]
+fn forms_missing() {
+    let pdf = load_pdf("pdfs/custom/page_images.pdf");
+    assert!(pdf.acro_form().is_none());
+}
@@ -0,0 +1,66 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings, render};
+
+// The page contains eight lines of 30 times the same character in 10pt
+// Helvetica, i.e. 240 glyphs that only differ in their position.
+fn render_page(cache: &RenderCache<'_>, scale: f32) {
+    render_page_with(cache, scale, true);
+}
+
+fn render_page_with(cache: &RenderCache<'_>, scale: f32, glyph_cache: bool) {
+    let pdf = load_pdf("pdfs/custom/glyph_cache_text.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        x_scale: scale,
+        y_scale: scale,
+        bg_color: WHITE,
+        glyph_cache,
+        ..Default::default()
+    };
+    render(page, cache, &interpreter_settings(), &render_settings);
+}
+
+#[test]
+fn glyph_cache_disabled() {
+    assert!(!RenderSettings::default().glyph_cache);
+
+    let cache = RenderCache::new();
+    render_page_with(&cache, 1.0, false);
+
+    assert_eq!(cache.rasterized_glyphs(), 0);
+}
+
+#[test]
+fn glyph_cache_repeated_text() {
+    let cache = RenderCache::new();
+    render_page(&cache, 1.0);
+
+    // The glyph is rasterized at most once per sub-pixel position.
+    let rasterized = cache.rasterized_glyphs();
+    assert!(rasterized > 0 && rasterized <= 16, "{rasterized}");
+
+    // Rendering the page again doesn't rasterize anything.
+    render_page(&cache, 1.0);
+    assert_eq!(cache.rasterized_glyphs(), rasterized);
+}
+
+#[test]
+fn glyph_cache_different_size() {
+    let cache = RenderCache::new();
+    render_page(&cache, 1.0);
+    let rasterized = cache.rasterized_glyphs();
+
+    render_page(&cache, 2.0);
+    assert!(cache.rasterized_glyphs() > rasterized);
+}
+
+#[test]
+fn glyph_cache_large_text() {
+    // At this scale, the text has a size of 160 pixels per em, so it's filled
+    // as a path instead.
+    let cache = RenderCache::new();
+    render_page(&cache, 16.0);
+
+    assert_eq!(cache.rasterized_glyphs(), 0);
+}
@@ -0,0 +1,91 @@
+use crate::{Recorder, interpreter_settings, load_pdf};
+use hayro::vello_cpu::color::palette::css::BLACK;
+use hayro::vello_cpu::{Pixmap, RenderContext, Resources};
+use hayro::{RenderCache, RenderSettings, render};
+use hayro_interpret::font::{Glyph, OutlineGlyph};
+use hayro_interpret::util::TransformExt;
+use hayro_interpret::{Context, InterpreterCache, interpret_page};
+use kurbo::{Affine, Rect};
+
+// At this scale, the glyphs are too large for the glyph cache of the renderer,
+// so it fills their outlines directly.
+const SCALE: f64 = 16.0;
+
+// The page contains eight lines of 30 times the same character in 10pt Helvetica.
+fn collect_glyphs() -> Vec<(OutlineGlyph, Affine)> {
+    let pdf = load_pdf("pdfs/custom/glyph_cache_text.pdf");
+    let page = &pdf.pages()[0];
+    let (width, height) = page.render_dimensions();
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::scale(SCALE) * page.initial_transform(true).to_kurbo(),
+        Rect::new(0.0, 0.0, width as f64 * SCALE, height as f64 * SCALE),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let mut recorder = Recorder::default();
+    interpret_page(page, &mut context, &mut recorder);
+
+    recorder
+        .glyphs
+        .into_iter()
+        .filter_map(|(glyph, glyph_transform, props, _)| match glyph {
+            Glyph::Outline(o) => Some((o, props.transform * glyph_transform)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn glyph_outline_metadata() {
+    let glyphs = collect_glyphs();
+    assert_eq!(glyphs.len(), 240);
+
+    let (first, _) = &glyphs[0];
+    assert_eq!(first.units_per_em(), 1000);
+
+    for (glyph, _) in &glyphs {
+        assert_eq!(glyph.font_cache_key(), first.font_cache_key());
+        assert_eq!(glyph.glyph_id(), first.glyph_id());
+    }
+}
+
+#[test]
+fn glyph_outline_matches_renderer() {
+    let pdf = load_pdf("pdfs/custom/glyph_cache_text.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        x_scale: SCALE as f32,
+        y_scale: SCALE as f32,
+        ..Default::default()
+    };
+    let rendered = render(
+        page,
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings,
+    );
+
+    let (width, height) = (rendered.width(), rendered.height());
+    let mut ctx = RenderContext::new(width, height);
+    ctx.set_paint(BLACK);
+
+    for (glyph, transform) in collect_glyphs() {
+        ctx.set_transform(transform);
+        ctx.fill_path(&glyph.outline());
+    }
+
+    ctx.flush();
+    let mut filled = Pixmap::new(width, height);
+    ctx.render(&mut filled, &mut Resources::default());
+
+    let max_diff = rendered
+        .data()
+        .iter()
+        .zip(filled.data())
+        .map(|(a, b)| a.a.abs_diff(b.a))
+        .max()
+        .unwrap();
+    assert!(max_diff <= 2, "{max_diff}");
+}
@@ -0,0 +1,171 @@
+// This test lives in its own test target, since it replaces the global allocator.
+
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::{RenderCache, RenderSettings, render};
+use hayro_syntax::Pdf;
+use pdf_writer::{Name, Rect, Ref};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+// An allocator that records the allocations of the current thread while
+// tracking is enabled.
+struct TrackingAllocator;
+
+#[derive(Clone, Copy, Default)]
+struct Stats {
+    tracking: bool,
+    // The number of bytes that were allocated minus the number of bytes that
+    // were freed since tracking was enabled.
+    live: isize,
+    peak: isize,
+    // The sizes of all allocations.
+    allocations: [usize; 16],
+    num_allocations: usize,
+}
+
+thread_local! {
+    static STATS: Cell<Stats> = const {
+        Cell::new(Stats {
+            tracking: false,
+            live: 0,
+            peak: 0,
+            allocations: [0; 16],
+            num_allocations: 0,
+        })
+    };
+}
+
+// Only allocations of at least this size are stored.
+const LARGE_ALLOCATION: usize = 1 << 20;
+
+fn record(allocated: usize, freed: usize) {
+    STATS.with(|s| {
+        let mut stats = s.get();
+
+        if stats.tracking {
+            // For reallocations, the old and the new allocation might exist at
+            // the same time.
+            stats.live += allocated as isize;
+            stats.peak = stats.peak.max(stats.live);
+            stats.live -= freed as isize;
+
+            if allocated >= LARGE_ALLOCATION {
+                if let Some(slot) = stats.allocations.get_mut(stats.num_allocations) {
+                    *slot = allocated;
+                }
+
+                stats.num_allocations += 1;
+            }
+
+            s.set(stats);
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size(), 0);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(layout.size(), 0);
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        record(0, layout.size());
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size, layout.size());
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+
+fn track<T>(f: impl FnOnce() -> T) -> (T, Stats) {
+    STATS.with(|s| {
+        s.set(Stats {
+            tracking: true,
+            ..Default::default()
+        });
+    });
+
+    let result = f();
+    let stats = STATS.with(|s| s.replace(Stats::default()));
+
+    (result, stats)
+}
+
+// A page that draws an RGB image with a soft mask at its original size, so
+// that the image is not resized before drawing it.
+fn image_pdf(width: i32, height: i32) -> Vec<u8> {
+    let num_pixels = width as usize * height as usize;
+    let rgb = (0..num_pixels * 3)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<_>>();
+    let alpha = (0..num_pixels).map(|i| (i % 256) as u8).collect::<Vec<_>>();
+    let content = format!("q {width} 0 0 {height} 0 {} cm /Im0 Do Q", 64 - height);
+
+    let mut pdf = pdf_writer::Pdf::new();
+    let (catalog_id, pages_id, page_id) = (Ref::new(1), Ref::new(2), Ref::new(3));
+    let (content_id, image_id, mask_id) = (Ref::new(4), Ref::new(5), Ref::new(6));
+
+    pdf.catalog(catalog_id).pages(pages_id);
+    pdf.pages(pages_id).kids([page_id]).count(1);
+
+    let mut page = pdf.page(page_id);
+    page.parent(pages_id)
+        .media_box(Rect::new(0.0, 0.0, 64.0, 64.0))
+        .contents(content_id);
+    page.resources().x_objects().pair(Name(b"Im0"), image_id);
+    page.finish();
+
+    pdf.stream(content_id, content.as_bytes());
+
+    let mut image = pdf.image_xobject(image_id, &rgb);
+    image.width(width).height(height).bits_per_component(8);
+    image.color_space().device_rgb();
+    image.s_mask(mask_id);
+    image.finish();
+
+    let mut mask = pdf.image_xobject(mask_id, &alpha);
+    mask.width(width).height(height).bits_per_component(8);
+    mask.color_space().device_gray();
+    mask.finish();
+
+    pdf.finish()
+}
+
+#[test]
+fn image_memory_interleave() {
+    let (width, height) = (2048, 2048);
+    let num_pixels = width as usize * height as usize;
+    let pdf = Pdf::new(image_pdf(width, height)).unwrap();
+    let page = &pdf.pages()[0];
+    let cache = RenderCache::new();
+    let interpreter_settings = InterpreterSettings::default();
+    let render_settings = RenderSettings::default();
+
+    let (pixmap, stats) = track(|| render(page, &cache, &interpreter_settings, &render_settings));
+    assert_eq!((pixmap.width(), pixmap.height()), (64, 64));
+
+    // The decoded RGB data and the alpha data need to be interleaved into a
+    // single RGBA buffer, which is allocated exactly once. Apart from those
+    // three buffers, no other large allocations should be alive at the same
+    // time.
+    let rgba_allocations = stats.allocations[..stats.num_allocations.min(16)]
+        .iter()
+        .filter(|size| **size >= num_pixels * 4)
+        .count();
+    assert_eq!(rgba_allocations, 1, "{:?}", &stats.allocations);
+    assert!(
+        stats.peak < (num_pixels * 9) as isize,
+        "peak memory usage of {} bytes",
+        stats.peak
+    );
+}
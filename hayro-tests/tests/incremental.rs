@@ -0,0 +1,146 @@
+use crate::load_pdf;
+use hayro_syntax::Pdf;
+use hayro_syntax::object::dict::keys::{KIDS, PAGES};
+use hayro_syntax::object::{Array, Dict, ObjRef, Object};
+use hayro_write::{IncrementalError, IncrementalWriter};
+use pdf_writer::{Chunk, Name, Rect, TextStr};
+
+fn set_title(path: &str) {
+    set_title_impl(&load_pdf(path));
+}
+
+fn set_title_impl(pdf: &Pdf) -> Vec<u8> {
+    let mut writer = IncrementalWriter::new(pdf).unwrap();
+
+    let info_ref = writer.new_ref();
+    let mut chunk = Chunk::new();
+    chunk
+        .indirect(info_ref)
+        .dict()
+        .pair(Name(b"Title"), TextStr("An incremental update"));
+    writer.write(&chunk).unwrap();
+    writer.set_info(info_ref);
+
+    let updated = writer.finish();
+    assert!(updated.starts_with(pdf.data().as_ref()));
+
+    let updated_pdf = Pdf::new(updated.clone()).unwrap();
+    assert_eq!(
+        updated_pdf
+            .metadata()
+            .title
+            .as_ref()
+            .map(|t| t.to_string_lossy()),
+        Some("An incremental update".to_string())
+    );
+    assert_eq!(updated_pdf.pages().len(), pdf.pages().len());
+
+    updated
+}
+
+fn set_title_prefixed(path: &str) {
+    let prefix = b"\xEF\xBB\xBFFrom: someone@example.com\nSubject: A document\n\n";
+    let mut data = prefix.to_vec();
+    data.extend_from_slice(load_pdf(path).data().as_ref());
+
+    let pdf = Pdf::new(data).unwrap();
+    assert_eq!(pdf.header_offset(), prefix.len());
+
+    let updated = set_title_impl(&pdf);
+
+    // The offsets of the update must be relative to the header, just like the ones
+    // of the original file.
+    let startxref = updated
+        .windows(b"startxref".len())
+        .rposition(|w| w == b"startxref")
+        .unwrap();
+    let xref_pos = std::str::from_utf8(&updated[startxref + b"startxref".len()..])
+        .unwrap()
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .parse::<usize>()
+        .unwrap();
+    let xref_pos = prefix.len() + xref_pos;
+
+    let updated = Pdf::new(updated).unwrap();
+    assert!(
+        updated.data().as_ref()[xref_pos..].starts_with(b"xref")
+            || updated.object_at_offset(xref_pos).is_some()
+    );
+
+    for id in updated.xref().object_ids() {
+        assert!(updated.xref().get::<Object<'_>>(id).is_some());
+    }
+}
+
+#[test]
+fn incremental_set_title_xref_table() {
+    set_title("pdfs/custom/clip_path_evenodd.pdf");
+}
+
+#[test]
+fn incremental_set_title_xref_stream() {
+    set_title("pdfs/custom/font_cid_1.pdf");
+}
+
+#[test]
+fn incremental_set_title_prefixed_xref_table() {
+    set_title_prefixed("pdfs/custom/clip_path_evenodd.pdf");
+}
+
+#[test]
+fn incremental_set_title_prefixed_xref_stream() {
+    set_title_prefixed("pdfs/custom/font_cid_1.pdf");
+}
+
+#[test]
+fn incremental_add_page() {
+    let pdf = load_pdf("pdfs/custom/clip_path_evenodd.pdf");
+    let mut writer = IncrementalWriter::new(&pdf).unwrap();
+
+    let catalog = pdf
+        .xref()
+        .get::<Dict<'_>>(writer.root_ref().into())
+        .unwrap();
+    let pages_ref = catalog.get_ref(PAGES).unwrap();
+    let pages = pdf.xref().get::<Dict<'_>>(pages_ref.into()).unwrap();
+    let mut kids = pages
+        .get::<Array<'_>>(KIDS)
+        .unwrap()
+        .raw_iter()
+        .filter_map(|kid| ObjRef::try_from(kid).ok())
+        .map(|kid| writer.existing_ref(kid))
+        .collect::<Vec<_>>();
+
+    let pages_ref = writer.existing_ref(pages_ref);
+    let page_ref = writer.new_ref();
+    kids.push(page_ref);
+
+    let mut chunk = Chunk::new();
+    chunk
+        .page(page_ref)
+        .parent(pages_ref)
+        .media_box(Rect::new(0.0, 0.0, 200.0, 100.0));
+    chunk
+        .pages(pages_ref)
+        .kids(kids.iter().copied())
+        .count(kids.len() as i32);
+    writer.write(&chunk).unwrap();
+
+    let updated = Pdf::new(writer.finish()).unwrap();
+    assert_eq!(updated.pages().len(), pdf.pages().len() + 1);
+
+    let media_box = updated.pages().last().unwrap().media_box();
+    assert_eq!((media_box.x1, media_box.y1), (200.0, 100.0));
+}
+
+#[test]
+fn incremental_encrypted() {
+    let pdf = load_pdf("pdfs/custom/catalog-in-objstm-aes.pdf");
+
+    assert_eq!(
+        IncrementalWriter::new(&pdf).err(),
+        Some(IncrementalError::Encrypted)
+    );
+}
@@ -0,0 +1,69 @@
+use crate::load_pdf;
+use hayro::hayro_syntax::content::ops::TypedInstruction;
+use hayro::hayro_syntax::object::dict::keys::{BITS_PER_COMPONENT, FILTER, WIDTH};
+use hayro::hayro_syntax::object::{Name, Object};
+
+// The page contains four inline images using abbreviated keys and values:
+// a 4x2 RGB image with `[/AHx /Fl]`, a 27x21 RGB image with `[/AHx /DCT]`
+// and a 138x130 gray image with `[/AHx /CCF]`. The last one is repeated with
+// `/CS /Idx`, which refers to an indexed color space in the page resources.
+#[test]
+fn inline_image_data() {
+    let pdf = load_pdf("pdfs/custom/inline_image_data.pdf");
+    let page = &pdf.pages()[0];
+    let mut ops = page.typed_operations();
+    let mut images = vec![];
+
+    while let Some(op) = ops.next() {
+        if let TypedInstruction::InlineImage(i) = op {
+            let image = i.image();
+            let cs = image.color_space(page.resources());
+            images.push((image, cs));
+        }
+    }
+    assert_eq!(images.len(), 4);
+
+    let device_rgb = Some(Object::Name(Name::new_unescaped(b"DeviceRGB")));
+    let device_gray = Some(Object::Name(Name::new_unescaped(b"DeviceGray")));
+
+    let (flate, cs) = &images[0];
+    assert_eq!(flate.dict().get::<u32>(WIDTH), Some(4));
+    assert_eq!(flate.dict().get::<u8>(BITS_PER_COMPONENT), Some(8));
+    assert!(flate.dict().contains_key(FILTER));
+    assert_eq!(cs, &device_rgb);
+    assert_eq!(flate.raw_data().first(), Some(&b'7'));
+    let decoded = flate.decoded(page.resources()).unwrap();
+    assert_eq!(decoded.data.len(), 4 * 2 * 3);
+    assert_eq!(&decoded.data[..6], &[255, 0, 0, 0, 255, 0]);
+
+    let (dct, cs) = &images[1];
+    assert_eq!((dct.width(), dct.height()), (Some(27), Some(21)));
+    assert_eq!(cs, &device_rgb);
+    let decoded = dct.decoded(page.resources()).unwrap();
+    assert_eq!(decoded.data.len(), 27 * 21 * 3);
+    assert_eq!(&decoded.data[..3], &[31, 29, 30]);
+
+    let (ccitt, cs) = &images[2];
+    assert_eq!((ccitt.width(), ccitt.height()), (Some(138), Some(130)));
+    assert_eq!(ccitt.bits_per_component(), Some(1));
+    assert!(!ccitt.is_image_mask());
+    assert_eq!(cs, &device_gray);
+    let decoded = ccitt.decoded(page.resources()).unwrap();
+    // The CCITT decoder emits one byte per pixel.
+    assert_eq!(decoded.data.len(), 138 * 130);
+    assert_eq!(decoded.data[0], 255);
+
+    let (indexed, cs) = &images[3];
+    let Some(Object::Array(cs)) = cs else {
+        panic!("expected a color space array, got {cs:?}");
+    };
+    assert_eq!(
+        cs.iter::<Name<'_>>().next(),
+        Some(Name::new_unescaped(b"Indexed"))
+    );
+    let decoded = indexed.decoded(page.resources()).unwrap();
+    // For indexed color spaces, the CCITT decoder emits one bit per pixel,
+    // with rows padded to whole bytes.
+    assert_eq!(decoded.data.len(), 138usize.div_ceil(8) * 130);
+    assert_eq!(decoded.data[0], 0xff);
+}
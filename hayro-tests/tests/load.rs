@@ -1,10 +1,11 @@
 use hayro::hayro_interpret::InterpreterSettings;
-use hayro::render_pdf;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderSettings, render_pdf};
 use hayro_jbig2::DecoderContext;
 use hayro_jpeg2000::{DecodeSettings, Image};
 use hayro_syntax::Pdf;
 use hayro_syntax::metadata::Metadata;
-use hayro_syntax::object::DateTime;
+use hayro_syntax::object::{DateTime, TextEncoding, TextString};
 
 fn load_pdf(file: &[u8]) {
     let pdf = Pdf::new(file.to_vec());
@@ -38,6 +39,35 @@ fn load_jpeg2000(file: &[u8]) {
     }
 }
 
+// The page contains a red rectangle in the middle, the first pixel is white.
+fn check_header_offset(path: &str, expected_offset: usize) {
+    let pdf = crate::load_pdf(path);
+    assert_eq!(pdf.header_offset(), expected_offset);
+
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = crate::render_image(
+        &pdf.pages()[0],
+        &InterpreterSettings::default(),
+        &render_settings,
+    );
+
+    assert_eq!(image.get_pixel(5, 5).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(100, 50).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn header_offset_html() {
+    check_header_offset("pdfs/custom/header_offset_html.pdf", 300);
+}
+
+#[test]
+fn header_offset_bom() {
+    check_header_offset("pdfs/custom/header_offset_bom.pdf", 3);
+}
+
 #[test]
 fn issue50() {
     let file = include_bytes!("../pdfs/load/issue50.pdf");
@@ -667,17 +697,39 @@ fn metadata_in_object_stream() {
             utc_offset_hour: 1,
             utc_offset_minute: 0,
         }),
-        title: Some("Encrypted Metadata".as_bytes().to_vec()),
-        author: Some("Max Mustermann".as_bytes().to_vec()),
+        title: Some(TextString::new("Encrypted Metadata")),
+        author: Some(TextString::new("Max Mustermann")),
         subject: None,
         keywords: None,
-        creator: Some("Typst 0.14.0".as_bytes().to_vec()),
+        creator: Some(TextString::new("Typst 0.14.0")),
         producer: None,
     };
 
     assert_eq!(pdf.metadata(), &expected);
 }
 
+#[test]
+fn metadata_text_encodings() {
+    // The title is encoded in UTF-16BE, the author in UTF-8 and the subject in
+    // `PDFDocEncoding`.
+    let file = include_bytes!("../pdfs/custom/metadata_japanese_title.pdf");
+    let pdf = Pdf::new(file.to_vec()).unwrap();
+    let metadata = pdf.metadata();
+
+    let title = metadata.title.as_ref().unwrap();
+    assert_eq!(title.encoding(), TextEncoding::Utf16Be);
+    assert_eq!(title.to_string_lossy(), "日本語のタイトル");
+
+    let author = metadata.author.as_ref().unwrap();
+    assert_eq!(author.encoding(), TextEncoding::Utf8);
+    assert_eq!(author.to_string_lossy(), "山田太郎");
+
+    let subject = metadata.subject.as_ref().unwrap();
+    assert_eq!(subject.encoding(), TextEncoding::PdfDocEncoding);
+    assert_eq!(subject.raw_bytes(), b"Caf\xe9");
+    assert_eq!(subject.to_string_lossy(), "Café");
+}
+
 #[test]
 fn generic_refinement_decoding_overflow() {
     let file = include_bytes!("../pdfs/load/generic_refinement_decoding_overflow.jb2");
@@ -1,10 +1,17 @@
-use hayro::hayro_interpret::InterpreterSettings;
+use hayro::hayro_interpret::font::Glyph;
 use hayro::hayro_interpret::font::{FontData, FontQuery, StandardFont};
 use hayro::hayro_interpret::hayro_cmap::CidFamily;
-use hayro_svg::SvgRenderSettings;
+use hayro::hayro_interpret::{
+    BlendMode, ClipPath, Device, DrawMode, DrawProps, Image, ImageDrawProps, InterpreterSettings,
+    SoftMask,
+};
+use hayro::hayro_syntax::page::Page;
+use hayro::{RenderCache, RenderSettings};
+use hayro_svg::{MaskReuse, SvgRenderSettings};
 use hayro_syntax::Pdf;
 use hayro_syntax::{DecryptionError, LoadPdfError};
 use image::{Rgba, RgbaImage, load_from_memory};
+use kurbo::{Affine, BezPath};
 use resvg::tiny_skia::{Color, Pixmap, PixmapPaint};
 use resvg::usvg::{Options, Transform, Tree};
 use sitro::{RenderOptions, Renderer};
@@ -16,8 +23,44 @@ use std::sync::{Arc, LazyLock};
 #[rustfmt::skip]
 #[allow(non_snake_case)]
 mod render;
+mod annotation;
+mod antialias;
+mod budget;
+mod clip;
+mod color;
+mod content_bbox;
+mod cull;
+mod deadline;
+mod display_list;
+mod font;
+mod forms;
+mod glyph_cache;
+mod glyph_outline;
+mod incremental;
+mod inline_image;
 mod load;
+mod nesting;
+mod op_map;
+mod overprint;
+mod page_images;
+mod path;
+mod pattern;
+mod pixmap;
+mod predictor;
+mod render_settings;
+mod resample;
+mod rotation;
+mod soft_mask;
+mod stem_darkening;
+mod stencil;
+mod stroke;
+mod summary;
 mod svg;
+mod text;
+mod thumbnail;
+mod unsupported;
+mod user_unit;
+mod validate;
 mod write;
 
 const REPLACE: Option<&str> = option_env!("REPLACE");
@@ -189,6 +232,58 @@ fn interpreter_settings() -> InterpreterSettings {
     }
 }
 
+// Render the page into an image, so that tests can check the color of individual pixels.
+fn render_image(
+    page: &Page<'_>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+) -> RgbaImage {
+    pixmap_to_image(hayro::render(
+        page,
+        &RenderCache::new(),
+        interpreter_settings,
+        render_settings,
+    ))
+}
+
+fn pixmap_to_image(pixmap: hayro::vello_cpu::Pixmap) -> RgbaImage {
+    load_from_memory(&pixmap.into_png().unwrap())
+        .unwrap()
+        .into_rgba8()
+}
+
+/// A device that records all drawn paths and glyphs and counts the drawn images,
+/// so that tests can check what the interpreter passes to a device.
+#[derive(Default)]
+struct Recorder<'a> {
+    paths: Vec<(BezPath, DrawProps<'a>, DrawMode)>,
+    glyphs: Vec<(Glyph<'a>, Affine, DrawProps<'a>, DrawMode)>,
+    images: usize,
+}
+
+impl<'a> Device<'a> for Recorder<'a> {
+    fn draw_path(&mut self, path: &BezPath, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        self.paths.push((path.clone(), props, draw_mode.clone()));
+    }
+    fn push_clip_path(&mut self, _: &ClipPath) {}
+    fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'a>>, _: BlendMode) {}
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'a>,
+        glyph_transform: Affine,
+        props: DrawProps<'a>,
+        draw_mode: &DrawMode,
+    ) {
+        self.glyphs
+            .push((glyph.clone(), glyph_transform, props, draw_mode.clone()));
+    }
+    fn draw_image(&mut self, _: Image<'a, '_>, _: ImageDrawProps<'a>) {
+        self.images += 1;
+    }
+    fn pop_clip(&mut self) {}
+    fn pop_transparency_group(&mut self) {}
+}
+
 fn read_font(name: &str) -> Option<FontData> {
     let path = WORKSPACE_PATH.join("assets").join(name);
     Some(Arc::new(std::fs::read(&path).ok()?))
@@ -226,9 +321,10 @@ fn get_noto_fallback(query: &hayro::hayro_interpret::font::FallbackFontQuery) ->
 }
 
 fn svg_render_settings() -> SvgRenderSettings {
-    SvgRenderSettings {
-        bg_color: [0, 0, 0, 0],
-    }
+    let mut settings = SvgRenderSettings::default();
+    settings.bg_color = [0, 0, 0, 0];
+    settings.mask_reuse = MaskReuse::Shared;
+    settings
 }
 
 pub fn run_render_test(name: &str, file_path: &str, range_str: Option<&str>) {
@@ -0,0 +1,71 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::hayro_interpret::text::extract_text;
+use hayro::hayro_interpret::{InterpreterCache, InterpreterSettings};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use image::RgbaImage;
+
+fn render_page(path: &str, settings: &InterpreterSettings) -> RgbaImage {
+    let pdf = load_pdf(path);
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    render_image(page, settings, &render_settings)
+}
+
+// The page contains a black rectangle, a tiling pattern that uses itself as its
+// fill and a form XObject that draws itself.
+#[test]
+fn nesting_recursive() {
+    let image = render_page("pdfs/custom/nesting_recursive.pdf", &interpreter_settings());
+
+    assert_eq!(image.get_pixel(20, 100 - 20).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(52, 100 - 52).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(65, 100 - 25).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn nesting_max_depth_zero() {
+    let settings = InterpreterSettings {
+        max_nesting_depth: 0,
+        ..interpreter_settings()
+    };
+    let image = render_page("pdfs/custom/nesting_recursive.pdf", &settings);
+
+    assert_eq!(image.get_pixel(20, 100 - 20).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(52, 100 - 52).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(65, 100 - 25).0, [255, 255, 255, 255]);
+}
+
+// Each of the cycles below draws itself twice per level, so they only finish in
+// reasonable time if the cycle is detected. The page contains a red rectangle
+// drawn by a form XObject that invokes itself via a second form XObject, a blue
+// rectangle masked by a soft mask whose group uses the same soft mask, and a
+// green tiling pattern that uses itself as its fill.
+#[test]
+fn nesting_cyclic() {
+    let image = render_page("pdfs/custom/nesting_cyclic.pdf", &interpreter_settings());
+
+    assert_eq!(image.get_pixel(25, 100 - 25).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(25, 100 - 75).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(72, 100 - 72).0, [0, 255, 0, 255]);
+}
+
+// The text is shown by a form XObject without resources, using a font from the
+// resources of the form XObject invoking it. It then invokes its parent form
+// XObject again, which in turn is only found in the resources of the page.
+#[test]
+fn nesting_inherited_resources() {
+    let pdf = load_pdf("pdfs/custom/nesting_inherited_resources.pdf");
+    let page = &pdf.pages()[0];
+    let extractor = extract_text(page, &InterpreterCache::new(), interpreter_settings());
+
+    let text = extractor
+        .lines()
+        .iter()
+        .map(|l| l.visual_order())
+        .collect::<Vec<_>>();
+    assert_eq!(text, ["Hello"]);
+}
@@ -0,0 +1,64 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro::hayro_interpret::InterpreterSettings;
+use hayro::hayro_syntax::content::SourceLocation;
+use hayro::{RenderCache, RenderSettings, render_with_op_map};
+
+// The page contains a black rectangle, a tiling pattern and a form XObject that
+// draws a red rectangle and itself:
+// `0 0 0 rg 10 10 30 30 re f /Pattern cs /P0 scn 50 50 40 40 re f /Fm0 Do`
+#[test]
+fn op_map_nested_xobjects() {
+    let pdf = load_pdf("pdfs/custom/nesting_recursive.pdf");
+    let page = &pdf.pages()[0];
+    let settings = InterpreterSettings {
+        max_nesting_depth: 3,
+        ..interpreter_settings()
+    };
+    let (_, map) = render_with_op_map(
+        page,
+        &RenderCache::new(),
+        &settings,
+        &RenderSettings::default(),
+    );
+
+    let black = map.ops_at(20.0, 100.0 - 20.0).collect::<Vec<_>>();
+    assert_eq!(black.len(), 1);
+    assert_eq!(black[0].index, 2);
+    assert_eq!(
+        black[0].location,
+        SourceLocation {
+            stream: 0,
+            offset: 24
+        }
+    );
+    assert!(black[0].xobject_path.is_empty());
+
+    let pattern = map.ops_at(70.0, 100.0 - 70.0).next().unwrap();
+    assert_eq!(pattern.index, 6);
+    assert_eq!(pattern.location.offset, 61);
+
+    // The form XObject draws the red rectangle once per nesting level, with
+    // the innermost one being painted last.
+    let red = map
+        .ops_at(65.0, 100.0 - 25.0)
+        .map(|r| (r.index, r.xobject_path.as_slice()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        red,
+        vec![(2, &[7, 3, 3][..]), (2, &[7, 3][..]), (2, &[7][..])]
+    );
+}
+
+#[test]
+fn op_map_empty_area() {
+    let pdf = load_pdf("pdfs/custom/nesting_recursive.pdf");
+    let page = &pdf.pages()[0];
+    let (_, map) = render_with_op_map(
+        page,
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &RenderSettings::default(),
+    );
+
+    assert!(map.ops_at(5.0, 5.0).next().is_none());
+}
@@ -0,0 +1,113 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use image::RgbaImage;
+
+fn render_pdf(path: &str, simulate_overprint: bool) -> RgbaImage {
+    let pdf = load_pdf(path);
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        simulate_overprint,
+        ..Default::default()
+    };
+    render_image(page, &interpreter_settings(), &render_settings)
+}
+
+// The page is filled with cyan. On the left, a 50% black rectangle is painted
+// with overprinting enabled, while the one on the right knocks out the backdrop.
+fn render_page(simulate_overprint: bool) -> RgbaImage {
+    render_pdf("pdfs/custom/overprint_cmyk.pdf", simulate_overprint)
+}
+
+#[test]
+fn overprint_not_simulated_by_default() {
+    let image = render_page(false);
+    let overprinted = image.get_pixel(50, 50).0;
+    let knocked_out = image.get_pixel(150, 50).0;
+
+    assert_eq!(overprinted, knocked_out);
+    assert!(overprinted[0] > 100, "{overprinted:?}");
+}
+
+#[test]
+fn overprint_black_over_cyan() {
+    let image = render_page(true);
+    let cyan = image.get_pixel(10, 10).0;
+    let overprinted = image.get_pixel(50, 50).0;
+    let knocked_out = image.get_pixel(150, 50).0;
+
+    // The cyan colorant of the backdrop is kept, so the result is a darker cyan.
+    assert!(overprinted[0] < 30, "{overprinted:?}");
+    assert!(overprinted[1] < cyan[1] && overprinted[2] < cyan[2]);
+    assert!(overprinted[2] > overprinted[0] + 50, "{overprinted:?}");
+
+    // Non-stroking overprinting is disabled for the second rectangle.
+    assert_eq!(knocked_out, render_page(false).get_pixel(150, 50).0);
+}
+
+// The page is filled with cyan, followed by a transparency group with two
+// `0 0 0 0 k` rectangles. The left one is painted with an overprint mode of 1,
+// so it doesn't paint any colorant, while the right one uses an overprint mode
+// of 0 and thus knocks out the backdrop.
+#[test]
+fn overprint_mode_zero_components() {
+    let image = render_pdf("pdfs/custom/overprint_opm.pdf", true);
+    let cyan = image.get_pixel(10, 10).0;
+
+    // Measure the difference to the backdrop across the whole left rectangle,
+    // which used to be painted white since multiplying with the transparent
+    // backdrop of the group just results in the source color.
+    let error = (25..75)
+        .flat_map(|x| (25..75).map(move |y| (x, y)))
+        .map(|(x, y)| {
+            let pixel = image.get_pixel(x, y).0;
+            pixel
+                .iter()
+                .zip(cyan)
+                .map(|(a, b)| a.abs_diff(b) as u32)
+                .sum::<u32>()
+        })
+        .max()
+        .unwrap();
+    assert_eq!(error, 0);
+
+    let knocked_out = image.get_pixel(150, 50).0;
+    assert!(knocked_out[0] > 200, "{knocked_out:?}");
+
+    // Without simulation, both rectangles knock out the backdrop.
+    let image = render_pdf("pdfs/custom/overprint_opm.pdf", false);
+    assert_eq!(image.get_pixel(50, 50).0, knocked_out);
+    assert_eq!(image.get_pixel(150, 50).0, knocked_out);
+}
+
+// Similar to the overprint patches of the Ghent Output Suite, the page is filled
+// with cyan and overlaid with a strip of patches that overprint it:
+// 1. `0 1 0 0 k` with an overprint mode of 1, which only paints magenta.
+// 2. `0 0 0 0 k` with an overprint mode of 1, which paints no colorant.
+// 3. A 50% tint of the `All` Separation colorant, which paints every colorant.
+// 4. A full tint of a `Magenta` Separation colorant.
+// 5. `0 1 0 0 k` with an overprint mode of 0, which paints all colorants.
+#[test]
+fn overprint_strip() {
+    let image = render_pdf("pdfs/custom/overprint_strip.pdf", true);
+    let cyan = image.get_pixel(5, 5).0;
+    let patch = |i: u32| image.get_pixel(25 + i * 50, 25).0;
+
+    // The cyan of the backdrop is kept, so cyan and magenta result in a blue.
+    let magenta = patch(0);
+    assert!(magenta[0] < 60 && magenta[1] < 60, "{magenta:?}");
+    assert!(magenta[2] > magenta[0] + 50, "{magenta:?}");
+
+    assert_eq!(patch(1), cyan);
+
+    // The `All` colorant knocks out the backdrop, resulting in a neutral gray.
+    let all = patch(2);
+    assert!(all[0].abs_diff(all[2]) < 20, "{all:?}");
+    assert!(all[0].abs_diff(cyan[0]) > 50, "{all:?}");
+
+    assert_eq!(patch(3), magenta);
+
+    let knocked_out = patch(4);
+    assert!(knocked_out[0] > 200, "{knocked_out:?}");
+}
@@ -0,0 +1,74 @@
+use crate::load_pdf;
+use hayro::hayro_syntax::Filter;
+use hayro::hayro_syntax::image::{ImageRef, ImageSource};
+use hayro::hayro_syntax::object::{Name, Object};
+
+fn summary(images: &[ImageRef<'_>]) -> Vec<(String, Option<u32>, Option<u32>)> {
+    images
+        .iter()
+        .map(|i| {
+            let source = match i.source() {
+                ImageSource::XObject(name) => name.as_str().to_string(),
+                ImageSource::Inline => "inline".to_string(),
+            };
+
+            (source, i.width(), i.height())
+        })
+        .collect()
+}
+
+// The page draws the 2x1 RGB image `/Im0`, the form `/Fm0` and a 1x1 inline
+// image. The form draws the 1x2 gray image `/Im1`, and its resources also
+// contain `/Im0` and the form itself.
+#[test]
+fn page_images() {
+    let pdf = load_pdf("pdfs/custom/page_images.pdf");
+    let page = &pdf.pages()[0];
+
+    let images = page.images();
+    assert_eq!(
+        summary(&images),
+        vec![
+            ("Im0".to_string(), Some(2), Some(1)),
+            ("inline".to_string(), Some(1), Some(1)),
+        ]
+    );
+
+    let rgb = &images[0];
+    assert_eq!(
+        rgb.color_space(),
+        Some(Object::Name(Name::new_unescaped(b"DeviceRGB")))
+    );
+    assert_eq!(rgb.filters().as_slice(), &[Filter::FlateDecode]);
+    assert_ne!(rgb.raw().as_ref(), &[255, 0, 0, 0, 0, 255]);
+    assert_eq!(
+        rgb.decoded().unwrap().data.as_ref(),
+        &[255, 0, 0, 0, 0, 255]
+    );
+
+    let inline = &images[1];
+    assert_eq!(
+        inline.color_space(),
+        Some(Object::Name(Name::new_unescaped(b"DeviceGray")))
+    );
+    assert!(inline.filters().is_empty());
+    // The data of inline images includes the whitespace in front of `EI`.
+    assert_eq!(inline.decoded().unwrap().data[0], 0x80);
+}
+
+#[test]
+fn page_images_with_forms() {
+    let pdf = load_pdf("pdfs/custom/page_images.pdf");
+    let page = &pdf.pages()[0];
+
+    let images = page.images_with_forms();
+    assert_eq!(
+        summary(&images),
+        vec![
+            ("Im0".to_string(), Some(2), Some(1)),
+            ("Im1".to_string(), Some(1), Some(2)),
+            ("inline".to_string(), Some(1), Some(1)),
+        ]
+    );
+    assert_eq!(images[1].decoded().unwrap().data.as_ref(), &[0, 255]);
+}
@@ -0,0 +1,57 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro_interpret::path::extract_paths;
+use hayro_interpret::{DrawMode, FillRule, InterpreterCache};
+use kurbo::{Rect, Shape};
+use std::rc::Rc;
+
+// The page contains an even-odd filled triangle, a stroked line and a
+// filled and stroked rectangle that are clipped by a rectangle, as well as
+// an unclipped triangle. The rectangle is reported once for the fill and
+// once for the stroke.
+#[test]
+fn extract_paths_clip() {
+    let pdf = load_pdf("pdfs/custom/extract_paths.pdf");
+    let page = &pdf.pages()[0];
+    let paths = extract_paths(page, &InterpreterCache::new(), interpreter_settings());
+
+    assert_eq!(paths.len(), 5);
+
+    let colors = paths
+        .iter()
+        .map(|p| p.color.unwrap().to_rgba8())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        colors,
+        [
+            [255, 0, 0, 255],
+            [0, 0, 255, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [0, 0, 0, 255]
+        ]
+    );
+
+    assert_eq!(paths[0].fill_rule(), Some(FillRule::EvenOdd));
+    assert_eq!(paths[1].fill_rule(), None);
+    assert_eq!(paths[2].fill_rule(), Some(FillRule::NonZero));
+    assert_eq!(paths[3].fill_rule(), None);
+    assert_eq!(paths[4].fill_rule(), Some(FillRule::NonZero));
+
+    let DrawMode::Stroke(stroke) = &paths[1].draw_mode else {
+        panic!("expected a stroked path");
+    };
+    assert_eq!(stroke.line_width, 3.0);
+
+    // The y-axis is flipped in device space.
+    assert_eq!(paths[0].device_path().to_svg(), "M10,90 L90,90 L50,10 Z");
+
+    assert!(paths[0].clip_paths.is_empty());
+    assert!(paths[4].clip_paths.is_empty());
+    assert_eq!(paths[1].clip_paths.len(), 1);
+    assert!(Rc::ptr_eq(&paths[1].clip_paths, &paths[2].clip_paths));
+    assert!(Rc::ptr_eq(&paths[1].clip_paths, &paths[3].clip_paths));
+
+    let clip = &paths[1].clip_paths[0];
+    assert_eq!(clip.fill, FillRule::NonZero);
+    assert_eq!(clip.path.bounding_box(), Rect::new(0.0, 0.0, 100.0, 100.0));
+}
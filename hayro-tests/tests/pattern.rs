@@ -0,0 +1,73 @@
+use crate::{Recorder, interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro_interpret::pattern::{MAX_TILE_COUNT, Pattern, TilingPattern};
+use hayro_interpret::{Context, InterpreterCache, Paint, interpret_page};
+use kurbo::{Affine, Rect};
+
+// The page is filled with a pattern whose cells are 0.1pt wide, with the left
+// half of each cell being red.
+const PATH: &str = "pdfs/custom/pattern_tiny_step.pdf";
+
+/// Interpret the page and call `f` with the pattern it is filled with.
+fn with_tiny_step_pattern(f: impl FnOnce(TilingPattern<'_>)) {
+    let pdf = load_pdf(PATH);
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 612.0, 792.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let mut recorder = Recorder::default();
+    interpret_page(page, &mut context, &mut recorder);
+
+    match &recorder.paths[0].1.paint {
+        Paint::Pattern(pattern) => match pattern.as_ref() {
+            Pattern::Tiling(tiling) => f((**tiling).clone()),
+            Pattern::Shading(_) => panic!("expected a tiling pattern"),
+        },
+        Paint::Color(_) => panic!("expected a pattern"),
+    }
+}
+
+#[test]
+fn pattern_tiny_step() {
+    let page_bounds = Rect::new(0.0, 0.0, 612.0, 792.0);
+
+    with_tiny_step_pattern(|mut pattern| {
+        assert!(pattern.tile_count(Affine::IDENTITY, page_bounds) > MAX_TILE_COUNT);
+
+        pattern.clamp_tile_count(Affine::IDENTITY, page_bounds);
+        assert!(pattern.tile_count(Affine::IDENTITY, page_bounds) <= MAX_TILE_COUNT * 1.001);
+    });
+
+    let pdf = load_pdf(PATH);
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(&pdf.pages()[0], &interpreter_settings(), &render_settings);
+    let pixel = image.get_pixel(306, 396);
+
+    assert_eq!(pixel[0], 255);
+    assert!(pixel[1] < 200 && pixel[2] < 200);
+}
+
+#[test]
+fn pattern_tiny_step_high_resolution() {
+    // At a high resolution, the cells are larger than a pixel, so the pattern
+    // shouldn't be made coarser, even though a lot of cells are needed.
+    let transform = Affine::scale(20.0);
+    let bounds = transform.transform_rect_bbox(Rect::new(0.0, 0.0, 612.0, 792.0));
+
+    with_tiny_step_pattern(|mut pattern| {
+        let count = pattern.tile_count(transform, bounds);
+        assert!(count > MAX_TILE_COUNT);
+
+        pattern.clamp_tile_count(transform, bounds);
+        assert_eq!(pattern.tile_count(transform, bounds), count);
+    });
+}
@@ -0,0 +1,117 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro::vello_cpu::Pixmap;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{
+    AlphaMode, PixmapExt, RenderCache, RenderSettings, RenderStatus, RenderedPixmap, render,
+    render_into, render_with_format,
+};
+use std::sync::mpsc;
+
+fn render_settings() -> RenderSettings<'static> {
+    RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn render_into_reuses_buffers() {
+    // The pages are 100x100, 200x200 and 200x100 pt in size.
+    let pdfs = [
+        load_pdf("pdfs/custom/image_mask_strips.pdf"),
+        load_pdf("pdfs/custom/clip_path_evenodd.pdf"),
+        load_pdf("pdfs/custom/font_cid_to_gid_map.pdf"),
+    ];
+    let cache = RenderCache::new();
+    let settings = interpreter_settings();
+
+    let expected = pdfs
+        .iter()
+        .map(|pdf| render(&pdf.pages()[0], &cache, &settings, &render_settings()))
+        .collect::<Vec<_>>();
+
+    // Ping-pong between two buffers, so that each one is reused for a page of a
+    // different size (both smaller and larger) than the one it previously held.
+    let mut buffers = [Pixmap::new(0, 0), Pixmap::new(0, 0)];
+
+    for (i, idx) in [0, 1, 2, 0, 1, 2, 1, 0].into_iter().enumerate() {
+        let pixmap = &mut buffers[i % 2];
+        let status = render_into(
+            &pdfs[idx].pages()[0],
+            &cache,
+            &settings,
+            &render_settings(),
+            pixmap,
+        );
+
+        assert_eq!(status, RenderStatus::Complete);
+        assert_eq!(pixmap.width(), expected[idx].width());
+        assert_eq!(pixmap.height(), expected[idx].height());
+        assert_eq!(pixmap.clone().into_vec(), expected[idx].clone().into_vec());
+    }
+}
+
+#[test]
+fn pixmap_vec_round_trip() {
+    let pdf = load_pdf("pdfs/custom/font_cid_to_gid_map.pdf");
+    let pixmap = render(
+        &pdf.pages()[0],
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings(),
+    );
+    let (width, height) = (pixmap.width(), pixmap.height());
+    assert_eq!(pixmap.stride(), width as usize * 4);
+
+    // Hand the buffer over to another thread, as a texture upload would do.
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || sender.send(pixmap.into_vec()).unwrap());
+    let data = receiver.recv().unwrap();
+    assert_eq!(data.len(), width as usize * height as usize * 4);
+
+    assert!(Pixmap::from_vec(data.clone(), width, height + 1).is_none());
+    assert!(Pixmap::from_vec(data[1..].to_vec(), width, height).is_none());
+
+    let pixmap = Pixmap::from_vec(data.clone(), width, height).unwrap();
+    assert_eq!(pixmap.into_vec(), data);
+}
+
+// The page is filled with red at an opacity of 50%.
+fn render_alpha_mode(alpha_mode: AlphaMode) -> RenderedPixmap {
+    let pdf = load_pdf("pdfs/custom/alpha_mode.pdf");
+    let render_settings = RenderSettings {
+        alpha_mode,
+        ..Default::default()
+    };
+
+    render_with_format(
+        &pdf.pages()[0],
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings,
+    )
+    .0
+}
+
+#[test]
+fn alpha_mode() {
+    let RenderedPixmap::Rgba8(premultiplied) = render_alpha_mode(AlphaMode::Premultiplied) else {
+        panic!("expected a premultiplied pixmap");
+    };
+    let RenderedPixmap::StraightRgba8(straight) = render_alpha_mode(AlphaMode::Straight) else {
+        panic!("expected a straight pixmap");
+    };
+
+    assert_eq!(
+        (straight.width(), straight.height()),
+        (premultiplied.width(), premultiplied.height())
+    );
+    assert_eq!(straight.data().len(), premultiplied.stride() * 100);
+
+    let premultiplied = premultiplied.into_vec();
+    let alpha = premultiplied[3];
+    assert!((127..=128).contains(&alpha));
+
+    assert_eq!(premultiplied[..4], [alpha, 0, 0, alpha]);
+    assert_eq!(straight.data()[..4], [255, 0, 0, alpha]);
+}
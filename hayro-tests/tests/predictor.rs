@@ -0,0 +1,24 @@
+use crate::load_pdf;
+
+// The page draws two RGB images that are compressed with the TIFF predictor. The
+// rows of the 4x2 image with 8 bits per component go from red to green to blue to
+// white, the ones of the 3x2 image with 4 bits per component from red to green to
+// blue and don't end on a byte boundary.
+#[test]
+fn tiff_predictor_rgb() {
+    let pdf = load_pdf("pdfs/custom/filter_tiff_predictor_rgb.pdf");
+    let page = &pdf.pages()[0];
+    let images = page.images();
+
+    let row_8 = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+    assert_eq!(
+        images[0].decoded().unwrap().data.as_ref(),
+        [row_8, row_8].concat()
+    );
+
+    let row_4 = [0xF0, 0x00, 0xF0, 0x00, 0xF0];
+    assert_eq!(
+        images[1].decoded().unwrap().data.as_ref(),
+        [row_4, row_4].concat()
+    );
+}
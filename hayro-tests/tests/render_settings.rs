@@ -0,0 +1,153 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::hayro_syntax::page::{Page, Rotation};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use image::RgbaImage;
+
+// The first four pages are 100x150 pages with a rotation of 0, 90, 180 and 270
+// degrees, the last one is an unrotated page with an empty crop box. All pages have
+// a red 50x50 square in the bottom-left corner of the unrotated page.
+const PATH: &str = "pdfs/custom/render_settings_dimensions.pdf";
+
+fn render_page(
+    index: usize,
+    settings: impl FnOnce(&Page<'_>) -> RenderSettings<'static>,
+) -> RgbaImage {
+    let pdf = load_pdf(PATH);
+    let page = &pdf.pages()[index];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..settings(page)
+    };
+    render_image(page, &interpreter_settings(), &render_settings)
+}
+
+// Return the bounding box of the red pixels in the image.
+fn red_bbox(image: &RgbaImage) -> (u32, u32, u32, u32) {
+    let mut bbox = (u32::MAX, u32::MAX, 0, 0);
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel.0 == [255, 0, 0, 255] {
+            bbox = (
+                bbox.0.min(x),
+                bbox.1.min(y),
+                bbox.2.max(x + 1),
+                bbox.3.max(y + 1),
+            );
+        }
+    }
+
+    bbox
+}
+
+// Check that the image contains an undistorted square with the given size in the
+// expected corner, allowing for one pixel of anti-aliasing at each edge.
+fn check_square(image: &RgbaImage, size: u32, rotation: usize) {
+    let (width, height) = image.dimensions();
+    let (x0, y0, x1, y1) = red_bbox(image);
+    assert!((x1 - x0).abs_diff(size) <= 2, "{:?}", (x0, y0, x1, y1));
+    assert!((y1 - y0).abs_diff(size) <= 2, "{:?}", (x0, y0, x1, y1));
+
+    // Starting in the bottom-left corner, the square moves clockwise for each
+    // rotation by 90 degrees.
+    let (left, top) = match rotation {
+        0 => (true, false),
+        1 => (true, true),
+        2 => (false, true),
+        _ => (false, false),
+    };
+    assert_eq!(x0 <= 1, left);
+    assert_eq!(x1 >= width - 1, !left);
+    assert_eq!(y0 <= 1, top);
+    assert_eq!(y1 >= height - 1, !top);
+}
+
+#[test]
+fn render_settings_with_dpi() {
+    for rotation in 0..4 {
+        let image = render_page(rotation, |page| {
+            RenderSettings::with_dpi(page, 100.0, Rotation::None)
+        });
+        // 100 x 150 points at 100 DPI are 138.9 x 208.3 pixels.
+        let expected = if rotation % 2 == 0 {
+            (139, 208)
+        } else {
+            (208, 139)
+        };
+        assert_eq!(image.dimensions(), expected, "rotation {rotation}");
+        check_square(&image, 69, rotation);
+    }
+}
+
+#[test]
+fn render_settings_fit_within() {
+    for rotation in 0..4 {
+        let image = render_page(rotation, |page| {
+            RenderSettings::fit_within(page, 300, 300, Rotation::None)
+        });
+        let expected = if rotation % 2 == 0 {
+            (200, 300)
+        } else {
+            (300, 200)
+        };
+        assert_eq!(image.dimensions(), expected, "rotation {rotation}");
+        check_square(&image, 100, rotation);
+
+        // The height of the unrotated page is the limiting factor, and its width
+        // of 66.7 pixels is rounded up.
+        let image = render_page(rotation, |page| {
+            RenderSettings::fit_within(page, 100, 100, Rotation::None)
+        });
+        let expected = if rotation % 2 == 0 {
+            (67, 100)
+        } else {
+            (100, 67)
+        };
+        assert_eq!(image.dimensions(), expected, "rotation {rotation}");
+        check_square(&image, 33, rotation);
+    }
+}
+
+#[test]
+fn render_settings_extra_rotation() {
+    for rotation in 0..4 {
+        // The additional rotation by 90 degrees swaps the dimensions once more.
+        let image = render_page(rotation, |page| {
+            RenderSettings::with_dpi(page, 100.0, Rotation::Horizontal)
+        });
+        let expected = if rotation % 2 == 0 {
+            (208, 139)
+        } else {
+            (139, 208)
+        };
+        assert_eq!(image.dimensions(), expected, "rotation {rotation}");
+        check_square(&image, 69, (rotation + 1) % 4);
+
+        let image = render_page(rotation, |page| {
+            RenderSettings::fit_within(page, 300, 300, Rotation::Horizontal)
+        });
+        let expected = if rotation % 2 == 0 {
+            (300, 200)
+        } else {
+            (200, 300)
+        };
+        assert_eq!(image.dimensions(), expected, "rotation {rotation}");
+        check_square(&image, 100, (rotation + 1) % 4);
+    }
+}
+
+#[test]
+fn render_settings_empty_crop_box() {
+    // The media box is used instead of the empty crop box.
+    let image = render_page(4, |page| {
+        RenderSettings::with_dpi(page, 144.0, Rotation::None)
+    });
+    assert_eq!(image.dimensions(), (200, 300));
+    check_square(&image, 100, 0);
+
+    let image = render_page(4, |page| {
+        RenderSettings::fit_within(page, 1000, 300, Rotation::None)
+    });
+    assert_eq!(image.dimensions(), (200, 300));
+    check_square(&image, 100, 0);
+}
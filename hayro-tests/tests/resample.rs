@@ -0,0 +1,42 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{ImageResampling, RenderSettings, ResampleFilter};
+
+// The page contains a 400x400 checkerboard with cells of one pixel, which
+// is drawn at 40x40 from (30, 30) to (70, 70).
+fn check_downscale(image_resampling: ImageResampling) {
+    let pdf = load_pdf("pdfs/custom/image_downscale_checkerboard.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        image_resampling,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    // Without aliasing, the checkerboard should be downscaled to a uniform gray.
+    for y in 33..67 {
+        for x in 33..67 {
+            let [r, g, b, _] = image.get_pixel(x, y).0;
+
+            assert!(
+                (104..=152).contains(&r) && r == g && g == b,
+                "pixel at ({x}, {y}) has color {r}, {g}, {b}"
+            );
+        }
+    }
+}
+
+#[test]
+fn image_downscale_checkerboard() {
+    check_downscale(ImageResampling::default());
+}
+
+#[test]
+fn image_downscale_checkerboard_box() {
+    check_downscale(ImageResampling {
+        filter: ResampleFilter::Box,
+        large_downscale_threshold: f32::INFINITY,
+        ..Default::default()
+    });
+}
@@ -0,0 +1,63 @@
+use crate::{interpreter_settings, load_pdf, pixmap_to_image, render_image};
+use hayro::hayro_syntax::page::Rotation;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings, record, render_display_list};
+use image::RgbaImage;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+
+fn render_rotated(extra_rotation: Rotation) -> RgbaImage {
+    // A 100x200 page with a red 50x50 square in its bottom-left corner.
+    let pdf = load_pdf("pdfs/custom/rotation_portrait.pdf");
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        extra_rotation,
+        ..Default::default()
+    };
+
+    render_image(&pdf.pages()[0], &interpreter_settings(), &render_settings)
+}
+
+#[test]
+fn rotation_extra_90() {
+    let image = render_rotated(Rotation::None);
+    assert_eq!(image.dimensions(), (100, 200));
+    assert_eq!(image.get_pixel(25, 175).0, RED);
+
+    // Rotated clockwise, the bottom-left corner ends up in the top-left corner.
+    let image = render_rotated(Rotation::Horizontal);
+    assert_eq!(image.dimensions(), (200, 100));
+    assert_eq!(image.get_pixel(25, 25).0, RED);
+    assert_ne!(image.get_pixel(25, 75).0, RED);
+    assert_ne!(image.get_pixel(175, 25).0, RED);
+}
+
+#[test]
+fn rotation_extra_180_and_270() {
+    let image = render_rotated(Rotation::Flipped);
+    assert_eq!(image.dimensions(), (100, 200));
+    assert_eq!(image.get_pixel(75, 25).0, RED);
+    assert_ne!(image.get_pixel(25, 175).0, RED);
+
+    let image = render_rotated(Rotation::FlippedHorizontal);
+    assert_eq!(image.dimensions(), (200, 100));
+    assert_eq!(image.get_pixel(175, 75).0, RED);
+    assert_ne!(image.get_pixel(25, 25).0, RED);
+}
+
+#[test]
+fn rotation_extra_display_list() {
+    let pdf = load_pdf("pdfs/custom/rotation_portrait.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        extra_rotation: Rotation::Horizontal,
+        ..Default::default()
+    };
+
+    let cache = RenderCache::new();
+    let list = record(page, &cache, &interpreter_settings());
+    let replayed = pixmap_to_image(render_display_list(page, &list, &cache, &render_settings));
+
+    assert_eq!(replayed, render_rotated(Rotation::Horizontal));
+}
@@ -0,0 +1,24 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+
+// Both halves of the page are filled with red, using the same luminosity mask that
+// is white on the left side of each half. The mask of the right half additionally
+// has an inverting transfer function.
+#[test]
+fn mask_luminosity_transfer_function() {
+    let pdf = load_pdf("pdfs/custom/mask_luminosity_transfer_function.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    let red = [255, 0, 0, 255];
+    let white = [255, 255, 255, 255];
+    assert_eq!(image.get_pixel(25, 50).0, red);
+    assert_eq!(image.get_pixel(75, 50).0, white);
+    assert_eq!(image.get_pixel(125, 50).0, white);
+    assert_eq!(image.get_pixel(175, 50).0, red);
+}
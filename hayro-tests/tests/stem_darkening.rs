@@ -0,0 +1,45 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+
+// The page contains a line of black 9pt text in Helvetica. Returns the total
+// amount of ink on the rendered page, which grows when the glyphs are emboldened.
+fn coverage(scale: f32, stem_darkening: bool) -> u64 {
+    let pdf = load_pdf("pdfs/custom/stem_darkening_text.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        x_scale: scale,
+        y_scale: scale,
+        bg_color: WHITE,
+        stem_darkening,
+        ..Default::default()
+    };
+    render_image(page, &interpreter_settings(), &render_settings)
+        .pixels()
+        .map(|p| 255 - p.0[0] as u64)
+        .sum()
+}
+
+#[test]
+fn stem_darkening_small_text() {
+    let plain = coverage(1.0, false);
+    let darkened = coverage(1.0, true);
+
+    assert!(
+        darkened as f64 > plain as f64 * 1.1,
+        "{darkened} vs {plain}"
+    );
+}
+
+#[test]
+fn stem_darkening_smaller_text_is_darkened_more() {
+    let increase = |scale| coverage(scale, true) as f64 / coverage(scale, false) as f64;
+
+    assert!(increase(1.0) > increase(2.0));
+}
+
+#[test]
+fn stem_darkening_large_text() {
+    // At this scale, the text has a size of 72 pixels per em.
+    assert_eq!(coverage(8.0, true), coverage(8.0, false));
+}
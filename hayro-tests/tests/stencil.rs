@@ -0,0 +1,51 @@
+use crate::{interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+
+// The page consists of 4 abutting stencil strips with fractional heights, spanning
+// the rectangle from (10, 10) to (90, 91) in PDF coordinates.
+fn check_no_seams(scale: f32) {
+    let pdf = load_pdf("pdfs/custom/image_mask_strips.pdf");
+    let page = &pdf.pages()[0];
+    let render_settings = RenderSettings {
+        x_scale: scale,
+        y_scale: scale,
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+
+    // Only check the interior of the strips, so that we don't have to care about
+    // how the outer edges are rasterized.
+    let scale = scale as f64;
+    let (x0, x1) = ((10.0 * scale).ceil() as u32, (90.0 * scale).floor() as u32);
+    let (y0, y1) = (
+        ((100.0 - 91.0) * scale).ceil() as u32,
+        ((100.0 - 10.0) * scale).floor() as u32,
+    );
+
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let pixel = image.get_pixel(x, y);
+            assert!(
+                pixel[0] < 128,
+                "background shows through at ({x}, {y}) with scale {scale}"
+            );
+        }
+    }
+}
+
+#[test]
+fn stencil_strips_no_seams_scale_1() {
+    check_no_seams(1.0);
+}
+
+#[test]
+fn stencil_strips_no_seams_scale_1_37() {
+    check_no_seams(1.37);
+}
+
+#[test]
+fn stencil_strips_no_seams_scale_2_63() {
+    check_no_seams(2.63);
+}
@@ -0,0 +1,164 @@
+use crate::{Recorder, WORKSPACE_PATH, interpreter_settings, load_pdf, render_image};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro_interpret::{Context, DrawMode, InterpreterCache, interpret_page};
+use image::{Rgba, RgbaImage, load_from_memory};
+use kurbo::{Affine, PathEl, Point, Rect};
+use sitro::{RenderOptions, Renderer};
+
+/// Return the start and end points of the dashes of each stroked path, as
+/// produced by kurbo.
+fn dashes(recorder: &Recorder<'_>) -> Vec<Vec<(Point, Point)>> {
+    recorder
+        .paths
+        .iter()
+        .filter_map(|(path, _, mode)| {
+            let DrawMode::Stroke(props) = mode else {
+                return None;
+            };
+
+            let dashes = props
+                .dash_array
+                .iter()
+                .map(|d| *d as f64)
+                .collect::<Vec<_>>();
+            let mut segments = vec![];
+            let mut start = Point::ORIGIN;
+
+            for el in kurbo::dash(path.iter(), props.dash_offset as f64, &dashes) {
+                match el {
+                    PathEl::MoveTo(p) => start = p,
+                    PathEl::LineTo(p) => segments.push((start, p)),
+                    _ => unreachable!(),
+                }
+            }
+
+            // kurbo emits the first dash of a subpath last, so that it can be joined
+            // with the last dash of closed subpaths.
+            segments.sort_by(|a, b| (a.0.y, a.0.x).partial_cmp(&(b.0.y, b.0.x)).unwrap());
+            Some(segments)
+        })
+        .collect()
+}
+
+fn assert_dashes(actual: &[(Point, Point)], expected: &[(f64, f64, f64)]) {
+    assert_eq!(actual.len(), expected.len());
+
+    for ((start, end), (x0, x1, y)) in actual.iter().zip(expected) {
+        assert!((start.x - x0).abs() < 0.02, "{start:?} != ({x0}, {y})");
+        assert!((end.x - x1).abs() < 0.02, "{end:?} != ({x1}, {y})");
+        assert_eq!((start.y, end.y), (*y, *y));
+    }
+}
+
+// The page strokes two paths with two subpaths each. The first one uses a dotted
+// `[0 4] 0` pattern with round caps, the second one a `[6 4] 3` pattern. In both
+// cases, the dash phase needs to be applied anew at the start of each subpath,
+// and the dots need to stay exactly 4 units apart over the whole subpath.
+#[test]
+fn stroke_dash_subpaths() {
+    let pdf = load_pdf("pdfs/custom/stroke_dash_subpaths.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 420.0, 80.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let mut recorder = Recorder::default();
+    interpret_page(page, &mut context, &mut recorder);
+
+    let recorded = dashes(&recorder);
+    assert_eq!(recorded.len(), 2);
+
+    let dots = [10.0, 30.0]
+        .into_iter()
+        .flat_map(|y| (0..100).map(move |i| (10.0 + 4.0 * i as f64, y)))
+        .map(|(x, y)| (x, x, y))
+        .collect::<Vec<_>>();
+    assert_dashes(&recorded[0], &dots);
+
+    let dashes = [50.0, 70.0]
+        .into_iter()
+        .flat_map(|y| {
+            let first = (10.0, 13.0, y);
+            let middle = (0..9).map(move |i| (17.0 + 10.0 * i as f64, 23.0 + 10.0 * i as f64, y));
+            let last = (107.0, 110.0, y);
+
+            std::iter::once(first)
+                .chain(middle)
+                .chain(std::iter::once(last))
+        })
+        .collect::<Vec<_>>();
+    assert_dashes(&recorded[1], &dashes);
+}
+
+fn render_page(path: &str) -> RgbaImage {
+    let pdf = load_pdf(path);
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    render_image(&pdf.pages()[0], &interpreter_settings(), &render_settings)
+}
+
+/// Whether the pixel lies fully inside one of the cells of the red and blue
+/// checkerboard pattern.
+fn is_pattern_cell(pixel: &Rgba<u8>) -> bool {
+    pixel.0 == [255, 0, 0, 255] || pixel.0 == [0, 0, 255, 255]
+}
+
+// Both pages stroke the same translated rectangle with a checkerboard tiling pattern,
+// once with a `[12 6] 5` dash pattern and once without. Since patterns are anchored
+// to the page, the dashes should simply cut out parts of the solid stroke.
+#[test]
+fn stroke_dash_pattern_phase() {
+    let dashed = render_page("pdfs/custom/pattern_stroke_dashed.pdf");
+    let solid = render_page("pdfs/custom/pattern_stroke_solid.pdf");
+
+    let mut cells = 0;
+
+    for (x, y, pixel) in dashed.enumerate_pixels() {
+        if is_pattern_cell(pixel) {
+            cells += 1;
+            assert_eq!(pixel, solid.get_pixel(x, y), "pixel ({x}, {y}) differs");
+        }
+    }
+
+    assert!(cells > 1000);
+}
+
+#[test]
+fn stroke_dash_pattern_pdfium() {
+    let path = "pdfs/custom/pattern_stroke_dashed.pdf";
+    let actual = render_page(path);
+    let data = std::fs::read(WORKSPACE_PATH.join(path)).unwrap();
+    let expected = Renderer::Pdfium
+        .render_as_png(&data, &RenderOptions::default())
+        .unwrap();
+    let expected = load_from_memory(&expected[0]).unwrap().into_rgba8();
+
+    assert_eq!(actual.dimensions(), expected.dimensions());
+
+    // Only compare the interior of the pattern cells, since the anti-aliasing
+    // of the cell and dash edges differs slightly between the renderers.
+    let (mut cells, mut mismatches) = (0, 0);
+
+    for (x, y, pixel) in expected.enumerate_pixels() {
+        if is_pattern_cell(pixel) {
+            cells += 1;
+
+            if actual.get_pixel(x, y) != pixel {
+                mismatches += 1;
+            }
+        }
+    }
+
+    assert!(cells > 1000);
+    assert!(
+        mismatches * 50 < cells,
+        "{mismatches} of {cells} pattern pixels differ from pdfium"
+    );
+}
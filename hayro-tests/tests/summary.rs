@@ -0,0 +1,65 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro_interpret::{ContentSummary, InterpreterCache, content_summary};
+
+fn summarize(path: &str) -> ContentSummary {
+    let pdf = load_pdf(path);
+    let page = &pdf.pages()[0];
+
+    content_summary(page, &InterpreterCache::new(), interpreter_settings())
+}
+
+// The page consists of a single image covering the whole page, overlaid with
+// invisible text.
+#[test]
+fn content_summary_scanned() {
+    let summary = summarize("pdfs/custom/content_summary_scanned.pdf");
+
+    assert_eq!(
+        summary,
+        ContentSummary {
+            text_shows: 1,
+            glyphs: 0,
+            invisible_glyphs: 5,
+            images: 1,
+            paths: 0,
+            image_area: 20000.0,
+            largest_image_area: 20000.0,
+            page_area: 20000.0,
+        }
+    );
+    assert!(summary.is_likely_scanned());
+}
+
+// The page contains two paths, a small image that is partially outside of the page
+// and text in the fill, fill-and-stroke and invisible rendering modes, as well as
+// a text-showing operator with an empty string.
+#[test]
+fn content_summary_mixed() {
+    let summary = summarize("pdfs/custom/content_summary_mixed.pdf");
+
+    assert_eq!(
+        summary,
+        ContentSummary {
+            text_shows: 4,
+            glyphs: 12,
+            invisible_glyphs: 1,
+            images: 1,
+            paths: 2,
+            image_area: 1250.0,
+            largest_image_area: 1250.0,
+            page_area: 20000.0,
+        }
+    );
+    assert_eq!(summary.image_coverage(), 0.0625);
+    assert!(!summary.is_likely_scanned());
+}
+
+// Two different glyphs are stroked at the same position, which must not be
+// mistaken for a glyph that is filled and stroked.
+#[test]
+fn content_summary_stroke_overlap() {
+    let summary = summarize("pdfs/custom/content_summary_stroke_overlap.pdf");
+
+    assert_eq!(summary.text_shows, 2);
+    assert_eq!(summary.glyphs, 2);
+}
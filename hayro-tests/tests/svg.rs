@@ -1,4 +1,8 @@
-use crate::run_svg_test;
+use crate::{interpreter_settings, load_pdf, render_svg, run_svg_test};
+use hayro_interpret::pattern::MAX_TILE_COUNT;
+use hayro_svg::hayro_syntax::page::Rotation;
+use hayro_svg::{MaskReuse, SvgRenderSettings};
+use image::load_from_memory;
 
 // TODO: Ideally those tests are also generated from the manifest files so they stay in sync.
 
@@ -268,3 +272,202 @@ fn issue_typst_7269() {
 fn issue_986() {
     run_svg_test("issue968", "pdfs/custom/issue968.pdf", None);
 }
+
+#[test]
+fn shading_extreme_scale() {
+    run_svg_test(
+        "shading_extreme_scale",
+        "pdfs/custom/shading_extreme_scale.pdf",
+        None,
+    );
+    check_svg_geometry("pdfs/custom/shading_extreme_scale.pdf");
+}
+
+#[test]
+fn pattern_extreme_scale() {
+    run_svg_test(
+        "pattern_extreme_scale",
+        "pdfs/custom/pattern_extreme_scale.pdf",
+        None,
+    );
+    check_svg_geometry("pdfs/custom/pattern_extreme_scale.pdf");
+}
+
+#[test]
+fn pattern_tiny_step() {
+    let pdf = load_pdf("pdfs/custom/pattern_tiny_step.pdf");
+    let svg = hayro_svg::convert(
+        &pdf.pages()[0],
+        &hayro_svg::RenderCache::new(),
+        &interpreter_settings(),
+        &SvgRenderSettings::default(),
+    );
+
+    let pattern = &svg[svg.find("<pattern").unwrap()..];
+    let parse = |name| attribute_values(pattern, name)[0].parse::<f64>().unwrap();
+    let (width, height) = (parse("width"), parse("height"));
+    let transform = attribute_values(pattern, "patternTransform")[0]
+        .trim_start_matches("matrix(")
+        .trim_end_matches(')')
+        .split(' ')
+        .map(|c| c.parse::<f64>().unwrap())
+        .collect::<Vec<_>>();
+    let cell_area =
+        width * height * (transform[0] * transform[3] - transform[1] * transform[2]).abs();
+
+    assert!(612.0 * 792.0 / cell_area <= MAX_TILE_COUNT * 1.001);
+}
+
+#[test]
+fn svg_mask_reuse() {
+    // The same soft mask is used in the content of a tiling pattern that is
+    // painted with two different transforms.
+    let pdf = load_pdf("pdfs/custom/svg_mask_reuse.pdf");
+    let settings = |mask_reuse| {
+        let mut settings = SvgRenderSettings::default();
+        settings.mask_reuse = mask_reuse;
+        settings
+    };
+
+    let num_masks = |mask_reuse| {
+        hayro_svg::convert(
+            &pdf.pages()[0],
+            &hayro_svg::RenderCache::new(),
+            &interpreter_settings(),
+            &settings(mask_reuse),
+        )
+        .matches("<mask ")
+        .count()
+    };
+    assert_eq!(num_masks(MaskReuse::Shared), 1);
+    assert_eq!(num_masks(MaskReuse::PerUse), 2);
+
+    let render = |mask_reuse| {
+        render_svg(
+            &pdf,
+            "svg_mask_reuse",
+            interpreter_settings(),
+            settings(mask_reuse),
+            None,
+        )
+        .remove(0)
+    };
+    let shared = render(MaskReuse::Shared);
+    assert_eq!(shared, render(MaskReuse::PerUse));
+
+    // Only the left half of each tile is visible.
+    let image = load_from_memory(&shared).unwrap().into_rgba8();
+
+    for x in [25, 125] {
+        assert_eq!(image.get_pixel(x, 50).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(x + 10, 50).0, [255, 255, 255, 255]);
+    }
+}
+
+#[test]
+fn text_clip_image() {
+    // An image is drawn through the outline of a square glyph of a Type3 font and
+    // the glyph "H" of Helvetica, using text rendering mode 7.
+    let pdf = load_pdf("pdfs/custom/text_clip_image.pdf");
+    let svg = hayro_svg::convert(
+        &pdf.pages()[0],
+        &hayro_svg::RenderCache::new(),
+        &interpreter_settings(),
+        &SvgRenderSettings::default(),
+    );
+
+    // The text is used as a vector clip path instead of a mask.
+    let clip_path = &svg[svg.find("<clipPath").unwrap()..svg.find("</clipPath>").unwrap()];
+    assert!(attribute_values(clip_path, "d")[0].starts_with("M10,80 L50,80"));
+    assert!(!svg.contains("<mask"));
+
+    let rendered = render_svg(
+        &pdf,
+        "text_clip_image",
+        interpreter_settings(),
+        SvgRenderSettings::default(),
+        None,
+    )
+    .remove(0);
+    let image = load_from_memory(&rendered).unwrap().into_rgba8();
+
+    let red = [255, 0, 0, 255];
+    let white = [255, 255, 255, 255];
+    // The square glyph.
+    assert_eq!(image.get_pixel(30, 60).0, red);
+    assert_eq!(image.get_pixel(70, 50).0, white);
+    // The left stem and the gap between the stems of the "H".
+    assert_eq!(image.get_pixel(107, 60).0, red);
+    assert_eq!(image.get_pixel(120, 45).0, white);
+}
+
+/// Check that the pattern and gradient transforms of the converted pages stay in a
+/// range that all SVG renderers can handle, and that the gradient stops are strictly
+/// increasing.
+fn check_svg_geometry(path: &str) {
+    let pdf = load_pdf(path);
+    let cache = hayro_svg::RenderCache::new();
+
+    for page in pdf.pages().iter() {
+        let svg = hayro_svg::convert(
+            page,
+            &cache,
+            &interpreter_settings(),
+            &SvgRenderSettings::default(),
+        );
+
+        for transform in ["patternTransform", "gradientTransform"] {
+            for value in attribute_values(&svg, transform) {
+                let coeffs = value
+                    .trim_start_matches("matrix(")
+                    .trim_end_matches(')')
+                    .split(' ')
+                    .map(|c| c.parse::<f64>().unwrap())
+                    .collect::<Vec<_>>();
+                let scale = (coeffs[0] * coeffs[3] - coeffs[1] * coeffs[2]).abs().sqrt();
+
+                assert!(
+                    (1.0e-2..=1.0e3).contains(&scale),
+                    "{transform} {value} has an extreme scale"
+                );
+            }
+        }
+
+        for gradient in svg.split("Gradient id=").skip(1) {
+            let offsets = attribute_values(gradient, "offset")
+                .iter()
+                .map(|o| o.parse::<f32>().unwrap())
+                .collect::<Vec<_>>();
+
+            assert!(offsets.iter().all(|o| (0.0..=1.0).contains(o)));
+            assert!(offsets.windows(2).all(|w| w[0] < w[1]));
+        }
+    }
+}
+
+fn attribute_values<'a>(svg: &'a str, name: &str) -> Vec<&'a str> {
+    let needle = format!(" {name}=\"");
+
+    svg.match_indices(&needle)
+        .map(|(idx, _)| {
+            let value = &svg[idx + needle.len()..];
+            &value[..value.find('"').unwrap()]
+        })
+        .collect()
+}
+
+#[test]
+fn svg_extra_rotation() {
+    let pdf = load_pdf("pdfs/custom/pattern_tiny_step.pdf");
+    let mut settings = SvgRenderSettings::default();
+    settings.extra_rotation = Rotation::Horizontal;
+
+    let svg = hayro_svg::convert(
+        &pdf.pages()[0],
+        &hayro_svg::RenderCache::new(),
+        &interpreter_settings(),
+        &settings,
+    );
+
+    assert!(svg.contains(r#"viewBox="0 0 792 612""#));
+}
@@ -0,0 +1,90 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro_interpret::text::{TextExtractor, TextItem, extract_text};
+use hayro_interpret::{Context, InterpreterCache, interpret_page};
+use kurbo::{Affine, Point, Rect};
+
+// The page contains Hebrew, Arabic, Latin and numbers, stored in visual order.
+// The first line is split into two text objects, which are shown out of order.
+fn extract_lines() -> Vec<TextItem> {
+    let pdf = load_pdf("pdfs/custom/text_bidi.pdf");
+    let page = &pdf.pages()[0];
+    let cache = InterpreterCache::new();
+    let mut context = Context::new(
+        Affine::IDENTITY,
+        Rect::new(0.0, 0.0, 300.0, 200.0),
+        &cache,
+        pdf.xref(),
+        interpreter_settings(),
+    );
+    let mut extractor = TextExtractor::new();
+    interpret_page(page, &mut context, &mut extractor);
+
+    extractor.lines()
+}
+
+#[test]
+fn text_content_order() {
+    let lines = extract_lines();
+    let text = lines.iter().map(|l| l.content_order()).collect::<Vec<_>>();
+
+    assert_eq!(
+        text,
+        [
+            "123 םולשםלוע ",
+            "דג (xy) בא",
+            "2024 مالس",
+            "hello גבא world"
+        ]
+    );
+}
+
+#[test]
+fn text_visual_order() {
+    let lines = extract_lines();
+    let text = lines.iter().map(|l| l.visual_order()).collect::<Vec<_>>();
+
+    assert_eq!(
+        text,
+        [
+            "םלוע 123 םולש",
+            "דג (xy) בא",
+            "2024 مالس",
+            "hello גבא world"
+        ]
+    );
+}
+
+#[test]
+fn text_logical_order() {
+    let lines = extract_lines();
+    let text = lines.iter().map(|l| l.logical_order()).collect::<Vec<_>>();
+
+    assert_eq!(
+        text,
+        ["שלום 123 עולם", "אב (xy) גד", "سلام 2024", "hello אבג world"]
+    );
+}
+
+// The page has a `/Rotate` of 90 degrees and the two lines are drawn rotated in
+// the opposite direction, so that they appear upright on the rendered page, with
+// "Hello" at (20, 30) and "World" at (20, 60).
+#[test]
+fn text_rotated_page() {
+    let pdf = load_pdf("pdfs/custom/text_rotated_page.pdf");
+    let page = &pdf.pages()[0];
+    let extractor = extract_text(page, &InterpreterCache::new(), interpreter_settings());
+    let lines = extractor.lines();
+
+    let text = lines.iter().map(|l| l.visual_order()).collect::<Vec<_>>();
+    assert_eq!(text, ["Hello", "World"]);
+
+    for (line, y) in lines.iter().zip([30.0, 60.0]) {
+        let glyphs = line.glyphs();
+        assert!((glyphs[0].origin - Point::new(20.0, y)).hypot() < 0.01);
+
+        for pair in glyphs.windows(2) {
+            assert!((pair[1].origin.y - y).abs() < 0.01);
+            assert!((pair[1].origin.x - (pair[0].origin.x + pair[0].advance)).abs() < 0.01);
+        }
+    }
+}
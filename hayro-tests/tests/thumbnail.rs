@@ -0,0 +1,63 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro::hayro_interpret::{ImageData, decode_thumbnail};
+use hayro::{PixmapExt, thumbnail_or_render};
+
+fn rgb(image: ImageData) -> (u32, u32, Vec<u8>) {
+    match image {
+        ImageData::Rgb(rgb) => (rgb.width, rgb.height, rgb.data),
+        ImageData::Luma(_) => panic!("expected an RGB image"),
+    }
+}
+
+// The first page has an indexed thumbnail whose palette contains more entries
+// than indicated by `hival`: `[/Indexed /DeviceRGB 1 <FF0000 00FF00 0000FF>]`.
+#[test]
+fn thumbnail_indexed() {
+    let pdf = load_pdf("pdfs/custom/thumbnail.pdf");
+    let page = &pdf.pages()[0];
+
+    let (width, height, data) = rgb(decode_thumbnail(page, &interpreter_settings()).unwrap());
+    assert_eq!((width, height), (2, 2));
+    assert_eq!(data, vec![255, 0, 0, 0, 255, 0, 0, 255, 0, 255, 0, 0]);
+}
+
+// The second page has a red and a blue pixel in a CalRGB color space.
+#[test]
+fn thumbnail_cal_rgb() {
+    let pdf = load_pdf("pdfs/custom/thumbnail.pdf");
+    let page = &pdf.pages()[1];
+
+    let (width, height, data) = rgb(decode_thumbnail(page, &interpreter_settings()).unwrap());
+    assert_eq!((width, height), (2, 1));
+    assert!(data[0] > 200 && data[1] < 100 && data[2] < 100);
+    assert!(data[3] < 100 && data[4] < 100 && data[5] > 200);
+}
+
+// The third page has a grayscale thumbnail with an inverted decode array.
+#[test]
+fn thumbnail_gray_decode() {
+    let pdf = load_pdf("pdfs/custom/thumbnail.pdf");
+    let page = &pdf.pages()[2];
+
+    let ImageData::Luma(luma) = decode_thumbnail(page, &interpreter_settings()).unwrap() else {
+        panic!("expected a grayscale image");
+    };
+    assert_eq!((luma.width, luma.height), (2, 1));
+    assert_eq!(luma.data, vec![255, 0]);
+}
+
+// The last page (200x100 points, with a red square on the left) has no
+// thumbnail, so it is rendered instead.
+#[test]
+fn thumbnail_or_render_fallback() {
+    let pdf = load_pdf("pdfs/custom/thumbnail.pdf");
+    let pages = pdf.pages();
+
+    assert!(decode_thumbnail(&pages[3], &interpreter_settings()).is_none());
+    let pixmap = thumbnail_or_render(&pages[3], 50, &interpreter_settings());
+    assert_eq!((pixmap.width(), pixmap.height()), (50, 25));
+
+    let pixmap = thumbnail_or_render(&pages[2], 50, &interpreter_settings());
+    assert_eq!((pixmap.width(), pixmap.height()), (2, 1));
+    assert_eq!(pixmap.into_vec(), vec![255, 255, 255, 255, 0, 0, 0, 255]);
+}
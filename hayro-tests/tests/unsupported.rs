@@ -0,0 +1,86 @@
+use crate::{interpreter_settings, load_pdf};
+use hayro::hayro_interpret::UnsupportedFeature;
+use hayro::hayro_interpret::hayro_syntax::Filter;
+use hayro::{RenderCache, RenderSettings, UnsupportedFeatureFn, render};
+use std::sync::{Arc, Mutex};
+
+// The first page draws a knockout group, a PostScript XObject and a shading
+// pattern with its own graphics state parameters. The second page draws a JPEG
+// 2000 image with an unknown enumerated color space and a shading with an
+// unknown type.
+#[test]
+fn on_unsupported() {
+    let pdf = load_pdf("pdfs/custom/unsupported_features.pdf");
+    let features = Arc::new(Mutex::new(vec![]));
+    let on_unsupported: UnsupportedFeatureFn = Arc::new({
+        let features = features.clone();
+        move |page_index: usize, f: &UnsupportedFeature| {
+            features.lock().unwrap().push((page_index, *f))
+        }
+    });
+    let render_settings = RenderSettings {
+        on_unsupported: Some(&on_unsupported),
+        ..Default::default()
+    };
+
+    for page in pdf.pages().iter() {
+        render(
+            page,
+            &RenderCache::new(),
+            &interpreter_settings(),
+            &render_settings,
+        );
+    }
+
+    // Without the `images` feature, the image can't be decoded at all.
+    let image = if cfg!(feature = "images") {
+        UnsupportedFeature::UnsupportedImage(Filter::JpxDecode)
+    } else {
+        UnsupportedFeature::MissingImageDecoder(Filter::JpxDecode)
+    };
+
+    assert_eq!(
+        *features.lock().unwrap(),
+        [
+            (0, UnsupportedFeature::KnockoutGroup),
+            (0, UnsupportedFeature::PostScriptXObject),
+            (0, UnsupportedFeature::ShadingPatternExtGState),
+            (1, image),
+            (1, UnsupportedFeature::UnknownShadingType(8)),
+        ]
+    );
+}
+
+// Run with `cargo test -p hayro-tests --no-default-features -- unsupported::`.
+#[cfg(not(feature = "images"))]
+#[test]
+fn missing_image_decoder() {
+    use crate::render_image;
+    use hayro::vello_cpu::color::palette::css::WHITE;
+
+    let pdf = load_pdf("pdfs/custom/unsupported_features.pdf");
+    let features = Arc::new(Mutex::new(vec![]));
+    let on_unsupported: UnsupportedFeatureFn = Arc::new({
+        let features = features.clone();
+        move |page_index: usize, f: &UnsupportedFeature| {
+            features.lock().unwrap().push((page_index, *f))
+        }
+    });
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        on_unsupported: Some(&on_unsupported),
+        ..Default::default()
+    };
+
+    let image = render_image(&pdf.pages()[1], &interpreter_settings(), &render_settings);
+
+    assert!(features.lock().unwrap().contains(&(
+        1,
+        UnsupportedFeature::MissingImageDecoder(Filter::JpxDecode)
+    )));
+    // The image covers the area from (0, 0) to (18, 100) and is replaced by a
+    // light gray box with a darker cross.
+    let [r, g, b, a] = image.get_pixel(9, 10).0;
+    assert!(r == g && g == b && (200..230).contains(&r) && a == 255);
+    assert_eq!(image.get_pixel(50, 50).0, [255, 255, 255, 255]);
+}
@@ -0,0 +1,103 @@
+use crate::{interpreter_settings, load_pdf, pixmap_to_image, render_image};
+use hayro::hayro_syntax::Pdf;
+use hayro::vello_cpu::color::palette::css::WHITE;
+use hayro::{RenderCache, RenderSettings, RenderStatus, render_into, render_with_status};
+use kurbo::Rect;
+
+// The media box of the page is 2000x1000 units with a user unit of 10, so the
+// page is 20000x10000 points large. The left half is filled with red.
+#[test]
+fn user_unit_small_scale() {
+    let pdf = load_pdf("pdfs/custom/user_unit.pdf");
+    let page = &pdf.pages()[0];
+    assert_eq!(page.render_dimensions(), (20000.0, 10000.0));
+
+    let render_settings = RenderSettings {
+        x_scale: 0.01,
+        y_scale: 0.01,
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let (pixmap, status) = render_with_status(
+        page,
+        &RenderCache::new(),
+        &interpreter_settings(),
+        &render_settings,
+    );
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!((pixmap.width(), pixmap.height()), (200, 100));
+
+    let image = pixmap_to_image(pixmap);
+    assert_eq!(image.get_pixel(50, 50).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(150, 50).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn user_unit_too_large() {
+    let pdf = load_pdf("pdfs/custom/user_unit.pdf");
+    let page = &pdf.pages()[0];
+    let cache = RenderCache::new();
+
+    // At a scale of 4, the page would be 80000 pixels wide.
+    let render_settings = RenderSettings {
+        x_scale: 4.0,
+        y_scale: 4.0,
+        ..Default::default()
+    };
+    let (pixmap, status) =
+        render_with_status(page, &cache, &interpreter_settings(), &render_settings);
+    assert_eq!(status, RenderStatus::TooLarge);
+    assert_eq!((pixmap.width(), pixmap.height()), (0, 0));
+
+    // The buffer of an existing pixmap is kept, so that it can be reused later on.
+    let small_settings = RenderSettings {
+        x_scale: 0.01,
+        y_scale: 0.01,
+        ..Default::default()
+    };
+    let (mut pixmap, _) =
+        render_with_status(page, &cache, &interpreter_settings(), &small_settings);
+    let status = render_into(
+        page,
+        &cache,
+        &interpreter_settings(),
+        &render_settings,
+        &mut pixmap,
+    );
+    assert_eq!(status, RenderStatus::TooLarge);
+    assert_eq!((pixmap.width(), pixmap.height()), (0, 0));
+    assert!(pixmap.take().capacity() >= 200 * 100);
+
+    // With an explicit viewport, only the part of the page inside of it is rendered.
+    let render_settings = RenderSettings {
+        width: Some(100),
+        height: Some(100),
+        ..render_settings
+    };
+    let (pixmap, status) =
+        render_with_status(page, &cache, &interpreter_settings(), &render_settings);
+    assert_eq!(status, RenderStatus::Complete);
+    assert_eq!((pixmap.width(), pixmap.height()), (100, 100));
+}
+
+#[test]
+fn user_unit_extract() {
+    let pdf = load_pdf("pdfs/custom/user_unit.pdf");
+    let extracted = Pdf::new(hayro_write::extract_pages_to_pdf(&pdf, &[0])).unwrap();
+    let page = &extracted.pages()[0];
+
+    // The extracted page keeps the user unit instead of scaling up the media box.
+    assert_eq!(page.user_unit(), 10.0);
+    assert_eq!(page.media_box(), Rect::new(0.0, 0.0, 2000.0, 1000.0));
+    assert_eq!(page.render_dimensions(), (20000.0, 10000.0));
+
+    let render_settings = RenderSettings {
+        x_scale: 0.01,
+        y_scale: 0.01,
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    let image = render_image(page, &interpreter_settings(), &render_settings);
+    assert_eq!(image.get_pixel(50, 50).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(150, 50).0, [255, 255, 255, 255]);
+}
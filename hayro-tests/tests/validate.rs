@@ -0,0 +1,93 @@
+use crate::{WORKSPACE_PATH, load_pdf};
+use hayro_interpret::{FindingCode, ValidationDepth, validate};
+use hayro_syntax::Pdf;
+
+fn codes(path: &str, depth: ValidationDepth) -> Vec<(FindingCode, Option<i32>)> {
+    let pdf = load_pdf(path);
+
+    validate(&pdf, depth)
+        .findings()
+        .iter()
+        .map(|f| (f.code, f.object.map(|o| o.obj_number)))
+        .collect()
+}
+
+#[test]
+fn validate_structural() {
+    let findings = codes(
+        "pdfs/custom/validate_broken.pdf",
+        ValidationDepth::Structural,
+    );
+
+    assert_eq!(
+        findings,
+        [
+            (FindingCode::DanglingXObjectRef, Some(99)),
+            (FindingCode::MissingFontDescriptor, Some(7)),
+            (FindingCode::MissingDescendantFont, Some(9)),
+            // Referenced from the resources of a form XObject.
+            (FindingCode::DanglingFontRef, Some(98)),
+            (FindingCode::StreamLengthMismatch, Some(5)),
+        ]
+    );
+}
+
+#[test]
+fn validate_full() {
+    let findings = codes("pdfs/custom/validate_broken.pdf", ValidationDepth::Full);
+
+    assert_eq!(
+        findings,
+        [
+            (FindingCode::DanglingXObjectRef, Some(99)),
+            (FindingCode::MissingFontDescriptor, Some(7)),
+            (FindingCode::MissingDescendantFont, Some(9)),
+            (FindingCode::UnparseableFont, Some(12)),
+            (FindingCode::DanglingFontRef, Some(98)),
+            (FindingCode::StreamLengthMismatch, Some(5)),
+            (FindingCode::StreamDecodeFailure, Some(8)),
+        ]
+    );
+}
+
+#[test]
+fn validate_report() {
+    let pdf = load_pdf("pdfs/custom/validate_broken.pdf");
+    let report = validate(&pdf, ValidationDepth::Structural);
+
+    assert!(!report.is_valid());
+    assert_eq!(
+        report.findings()[0].to_string(),
+        "error[dangling-xobject-ref] 99 0 R (page 0): resource /Missing in /XObject references a missing object"
+    );
+
+    let pdf = load_pdf("pdfs/custom/content_summary_scanned.pdf");
+    assert!(validate(&pdf, ValidationDepth::Full).findings().is_empty());
+}
+
+// Validation should never panic and yield the same findings when run again on
+// a freshly loaded file, also for the (partially fuzzed) files of the load tests.
+#[test]
+fn validate_corpus() {
+    for dir in ["pdfs/load", "pdfs/custom"] {
+        let mut paths = std::fs::read_dir(WORKSPACE_PATH.join(dir))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "pdf"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            let data = std::fs::read(&path).unwrap();
+
+            let Ok(pdf) = Pdf::new(data.clone()) else {
+                continue;
+            };
+
+            let first = validate(&pdf, ValidationDepth::Full);
+            let second = validate(&Pdf::new(data).unwrap(), ValidationDepth::Full);
+
+            assert_eq!(first, second, "findings for {path:?} differ");
+        }
+    }
+}
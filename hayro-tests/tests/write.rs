@@ -1,8 +1,14 @@
-use crate::{load_pdf, run_write_test};
+use crate::{interpreter_settings, load_pdf, render_image, run_write_test};
+use hayro::RenderSettings;
+use hayro::vello_cpu::color::palette::css::WHITE;
 use hayro_syntax::Pdf;
-use hayro_syntax::object::Stream;
 use hayro_syntax::object::dict::keys::GROUP;
-use hayro_write::ExtractionQuery;
+use hayro_syntax::object::{ObjRef, Stream};
+use hayro_write::{
+    ContentFilter, ExtractionError, ExtractionOptions, ExtractionQuery, ExtractionResult,
+    ExtractionWarning, ExtractionWarningKind,
+};
+use image::{Rgba, RgbaImage};
 use pdf_writer::Ref;
 use sitro::Renderer;
 
@@ -373,3 +379,222 @@ fn write_null_objects() {
 
     assert_eq!(data, b"<<\n      /F1 5 0 R\n      /F2 null\n    >>");
 }
+
+// The page contains a red rectangle, a green image `XObject`, a blue inline image,
+// a form `XObject` with a black rectangle and another green image as well as
+// a black "H".
+fn render_filtered(filter: ContentFilter) -> RgbaImage {
+    let hayro_pdf = load_pdf("pdfs/custom/content_filter.pdf");
+    let options = ExtractionOptions {
+        content_filter: Some(filter),
+        ..Default::default()
+    };
+    let extracted = hayro_write::extract_pages_to_pdf_with_options(&hayro_pdf, &[0], &options);
+
+    let reread = Pdf::new(extracted).unwrap();
+    let page = &reread.pages()[0];
+    let render_settings = RenderSettings {
+        bg_color: WHITE,
+        ..Default::default()
+    };
+    render_image(page, &interpreter_settings(), &render_settings)
+}
+
+const RED: [u8; 3] = [255, 0, 0];
+const GREEN: [u8; 3] = [0, 255, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+const BLANK: [u8; 3] = [255, 255, 255];
+
+fn check_filtered(
+    filter: ContentFilter,
+    paths: ([u8; 3], [u8; 3]),
+    images: ([u8; 3], [u8; 3], [u8; 3]),
+    text: [u8; 3],
+) {
+    let image = render_filtered(filter);
+    let check = |x: u32, y: u32, expected: [u8; 3]| {
+        let Rgba([r, g, b, _]) = *image.get_pixel(x, 100 - y);
+        assert_eq!([r, g, b], expected, "unexpected color at ({x}, {y})");
+    };
+
+    check(30, 30, paths.0);
+    check(175, 25, paths.1);
+    check(80, 30, images.0);
+    check(130, 30, images.1);
+    check(175, 70, images.2);
+    check(15, 75, text);
+}
+
+#[test]
+fn write_page_filter_none() {
+    check_filtered(
+        ContentFilter::default(),
+        (RED, BLACK),
+        (GREEN, BLUE, GREEN),
+        BLACK,
+    );
+}
+
+#[test]
+fn write_page_filter_text() {
+    check_filtered(
+        ContentFilter {
+            strip_text: true,
+            ..Default::default()
+        },
+        (RED, BLACK),
+        (GREEN, BLUE, GREEN),
+        BLANK,
+    );
+}
+
+#[test]
+fn write_page_filter_images() {
+    check_filtered(
+        ContentFilter {
+            strip_images: true,
+            ..Default::default()
+        },
+        (RED, BLACK),
+        (BLANK, BLANK, BLANK),
+        BLACK,
+    );
+}
+
+#[test]
+fn write_page_filter_paths() {
+    check_filtered(
+        ContentFilter {
+            strip_paths: true,
+            ..Default::default()
+        },
+        (BLANK, BLANK),
+        (GREEN, BLUE, GREEN),
+        BLACK,
+    );
+}
+
+// Return the names of the entries in each resource category of the first extracted page.
+fn pruned_resources(path: &str) -> Vec<(String, Vec<String>)> {
+    let hayro_pdf = load_pdf(path);
+    let options = ExtractionOptions {
+        prune_resources: true,
+        ..Default::default()
+    };
+    let extracted = hayro_write::extract_pages_to_pdf_with_options(&hayro_pdf, &[0], &options);
+
+    let reread = Pdf::new(extracted).unwrap();
+    let resources = reread.pages()[0]
+        .raw()
+        .get::<hayro_syntax::object::Dict>("Resources")
+        .unwrap();
+
+    resources
+        .keys()
+        .map(|category| {
+            let names = resources
+                .get::<hayro_syntax::object::Dict>(&category)
+                .unwrap()
+                .keys()
+                .map(|n| n.as_str().to_string())
+                .collect();
+
+            (category.as_str().to_string(), names)
+        })
+        .collect()
+}
+
+#[test]
+fn write_page_prune_resources() {
+    let mut resources = pruned_resources("pdfs/custom/prune_resources.pdf");
+    resources.sort();
+
+    assert_eq!(
+        resources,
+        [
+            ("ColorSpace", vec!["CS1"]),
+            ("ExtGState", vec!["GS1"]),
+            ("Font", vec!["F1"]),
+            ("Properties", vec!["MC0"]),
+            ("XObject", vec!["Im1"]),
+        ]
+        .map(|(c, n)| (
+            c.to_string(),
+            n.into_iter().map(String::from).collect::<Vec<_>>()
+        ))
+    );
+}
+
+#[test]
+fn write_page_prune_resources_inherited() {
+    // The form `XObject` uses the font resources of the page, so nothing can be pruned.
+    let mut resources = pruned_resources("pdfs/custom/prune_resources_inherited.pdf");
+    resources.sort();
+
+    assert_eq!(
+        resources,
+        [("Font", vec!["F1", "F2"]), ("XObject", vec!["Fm0"])].map(|(c, n)| (
+            c.to_string(),
+            n.into_iter().map(String::from).collect::<Vec<_>>()
+        ))
+    );
+}
+
+#[test]
+fn write_page_prune_resources_indexed_inline_image() {
+    // The inline image uses an indexed color space whose base is a named resource.
+    let resources = pruned_resources("pdfs/custom/prune_resources_indexed.pdf");
+
+    assert_eq!(
+        resources,
+        [("ColorSpace".to_string(), vec!["CS0".to_string()])]
+    );
+}
+
+fn extract_missing_object(strict: bool) -> ExtractionResult {
+    let hayro_pdf = load_pdf("pdfs/custom/write_missing_object.pdf");
+    let mut next_ref = Ref::new(1);
+
+    hayro_write::extract_with_options(
+        &hayro_pdf,
+        Box::new(|| next_ref.bump()),
+        hayro_write::ChunkSettings::default(),
+        |_| {},
+        &[ExtractionQuery::new_page(0), ExtractionQuery::new_page(0)],
+        &ExtractionOptions {
+            strict,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+// The soft mask of the graphics state used by the page references an object that
+// doesn't exist.
+#[test]
+fn write_missing_object() {
+    let missing = ExtractionWarning {
+        obj_ref: ObjRef::new(99, 0),
+        kind: ExtractionWarningKind::MissingObject,
+    };
+
+    let extracted = extract_missing_object(false);
+    assert!(extracted.root_refs.iter().all(Result::is_ok));
+    assert_eq!(extracted.warnings, [missing]);
+    assert!(!extracted.is_complete());
+
+    // The graphics state is shared by both queries, so both of them must fail,
+    // even though it is only written once.
+    let extracted = extract_missing_object(true);
+    assert_eq!(extracted.root_refs.len(), 2);
+    assert!(
+        extracted
+            .root_refs
+            .iter()
+            .all(|r| *r == Err(ExtractionError::Incomplete(missing)))
+    );
+    assert_eq!(extracted.warnings, [missing]);
+    // Nothing is written for failed queries.
+    assert!(extracted.chunk.as_bytes().is_empty());
+}
@@ -0,0 +1,174 @@
+//! Removing categories of content from content streams.
+
+use crate::primitive::write_reencoded_stream_dict;
+use crate::{ExtractionContext, ExtractionWarningKind, deflate_encode};
+use hayro_syntax::content::{Instruction, UntypedIter};
+use hayro_syntax::object::dict::keys::{FORM, IMAGE, RESOURCES, SUBTYPE, XOBJECT};
+use hayro_syntax::object::{Dict, MaybeRef, Name, ObjRef, Object, Stream};
+use pdf_writer::{Chunk, Filter, Ref};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::{Deref, DerefMut};
+
+/// Categories of content that should be removed from extracted pages.
+///
+/// Graphics state operators (including `q`/`Q`, `BT`/`ET` and `gs`) are always
+/// kept, so the resulting content streams stay balanced. Form `XObjects` used by
+/// a page are filtered as well.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ContentFilter {
+    /// Whether text-showing operators should be removed.
+    pub strip_text: bool,
+    /// Whether image `XObjects` and inline images should be removed.
+    pub strip_images: bool,
+    /// Whether vector paths and shadings should be removed.
+    ///
+    /// Paths that are used as clip paths still take effect, they are
+    /// just not painted anymore.
+    pub strip_paths: bool,
+}
+
+enum Action {
+    Keep,
+    Remove,
+    /// Replace the painting operator by `n`, so that a pending clip path is
+    /// still applied.
+    EndPath,
+}
+
+impl ContentFilter {
+    fn action(&self, instruction: &Instruction<'_, '_>, images: &HashSet<Vec<u8>>) -> Action {
+        match instruction.operator.deref() {
+            // We also remove the text rendering mode, because text that is used as a
+            // clip path would otherwise clip away everything else.
+            b"Tj" | b"TJ" | b"'" | b"\"" | b"Tr" if self.strip_text => Action::Remove,
+            b"BI" if self.strip_images => Action::Remove,
+            b"Do" if self.strip_images => {
+                let is_image = match instruction.operands().next() {
+                    Some(Object::Name(name)) => images.contains(name.deref()),
+                    _ => false,
+                };
+
+                if is_image {
+                    Action::Remove
+                } else {
+                    Action::Keep
+                }
+            }
+            b"S" | b"s" | b"f" | b"F" | b"f*" | b"B" | b"B*" | b"b" | b"b*" if self.strip_paths => {
+                Action::EndPath
+            }
+            b"sh" if self.strip_paths => Action::Remove,
+            _ => Action::Keep,
+        }
+    }
+}
+
+/// Filter the given content stream. Form `XObjects` in the resources are registered
+/// so that they are filtered when they are written.
+pub(crate) fn filter_content<'a>(
+    content: &[u8],
+    x_objects: &BTreeMap<Name<'a>, MaybeRef<Object<'a>>>,
+    filter: &ContentFilter,
+    ctx: &mut ExtractionContext<'_>,
+) -> Vec<u8> {
+    let images = register_x_objects(x_objects, ctx);
+    let mut iter = UntypedIter::new(content);
+    let mut filtered = Vec::with_capacity(content.len());
+
+    loop {
+        let start = iter.offset();
+        let Some(instruction) = iter.next() else {
+            break;
+        };
+        let action = filter.action(&instruction, &images);
+        let end = iter.offset();
+
+        let data: &[u8] = match action {
+            Action::Keep => &content[start..end],
+            Action::Remove => continue,
+            Action::EndPath => b"n",
+        };
+
+        // Make sure that two instructions are never merged into one.
+        if !filtered.is_empty() && !data.first().is_some_and(u8::is_ascii_whitespace) {
+            filtered.push(b'\n');
+        }
+
+        filtered.extend_from_slice(data);
+    }
+
+    filtered
+}
+
+/// Write a filtered version of the given form `XObject`.
+pub(crate) fn write_filtered_form(
+    stream: &Stream<'_>,
+    obj_ref: ObjRef,
+    id: Ref,
+    filter: &ContentFilter,
+    chunk: &mut Chunk,
+    ctx: &mut ExtractionContext<'_>,
+) {
+    let Ok(decoded) = stream.decoded() else {
+        warn!("failed to decode form XObject, removing its content");
+        ctx.report(obj_ref, ExtractionWarningKind::UndecodableStream);
+        write_form(stream, id, &[], chunk, ctx);
+
+        return;
+    };
+
+    // Note that we don't handle forms that inherit their resources from the page,
+    // in this case, all of their `XObjects` are kept.
+    let x_objects = stream
+        .dict()
+        .get::<Dict<'_>>(RESOURCES)
+        .and_then(|r| r.get::<Dict<'_>>(XOBJECT))
+        .map(|d| d.entries().collect())
+        .unwrap_or_default();
+    let filtered = filter_content(&decoded, &x_objects, filter, ctx);
+
+    write_form(stream, id, &filtered, chunk, ctx);
+}
+
+fn write_form(
+    stream: &Stream<'_>,
+    id: Ref,
+    content: &[u8],
+    chunk: &mut Chunk,
+    ctx: &mut ExtractionContext<'_>,
+) {
+    let mut obj = chunk.stream(id, &deflate_encode(content));
+    write_reencoded_stream_dict(stream.dict(), obj.deref_mut(), ctx);
+    obj.filter(Filter::FlateDecode);
+}
+
+/// Return the names of all image `XObjects` and register all form `XObjects` for filtering.
+fn register_x_objects<'a>(
+    x_objects: &BTreeMap<Name<'a>, MaybeRef<Object<'a>>>,
+    ctx: &mut ExtractionContext<'_>,
+) -> HashSet<Vec<u8>> {
+    let mut images = HashSet::new();
+
+    for (name, object) in x_objects {
+        // Streams are always indirect objects.
+        let MaybeRef::Ref(ref_) = object else {
+            continue;
+        };
+
+        let Some(stream) = ctx.pdf.xref().get::<Stream<'_>>((*ref_).into()) else {
+            continue;
+        };
+
+        match stream.dict().get::<Name<'_>>(SUBTYPE).as_deref() {
+            Some(IMAGE) => {
+                images.insert(name.to_vec());
+            }
+            Some(FORM) => {
+                ctx.filtered_forms.insert(*ref_);
+            }
+            _ => {}
+        }
+    }
+
+    images
+}
@@ -0,0 +1,349 @@
+//! Appending incremental updates to existing PDF files.
+
+use hayro_syntax::Pdf;
+use hayro_syntax::object::dict::keys::{ENCRYPT, ID, INFO, ROOT, SIZE};
+use hayro_syntax::object::{Array, Dict, ObjRef, Object, ObjectIdentifier};
+use hayro_syntax::reader::{Reader, ReaderContext, ReaderExt};
+use pdf_writer::{Chunk, Ref};
+use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// An error that occurred while writing an incremental update.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IncrementalError {
+    /// The last xref section or the trailer of the original file couldn't be read.
+    InvalidXRef,
+    /// The original file is encrypted, which isn't supported.
+    Encrypted,
+    /// A chunk contained data that couldn't be parsed as indirect objects.
+    InvalidChunk,
+}
+
+/// A writer for appending an incremental update to an existing PDF file.
+///
+/// New and replacement objects are supplied as `pdf-writer` chunks. To replace an
+/// existing object, write it using the reference returned by
+/// [`IncrementalWriter::existing_ref`]. References for new objects must be allocated
+/// with [`IncrementalWriter::new_ref`], so that they don't collide with existing ones.
+///
+/// Since the bytes of the original file are left untouched, signatures of earlier
+/// revisions stay valid.
+pub struct IncrementalWriter<'a> {
+    pdf: &'a Pdf,
+    trailer: Trailer,
+    generations: FxHashMap<i32, i32>,
+    next_ref: Ref,
+    info: Option<ObjRef>,
+    objects: BTreeMap<i32, Vec<u8>>,
+}
+
+impl<'a> IncrementalWriter<'a> {
+    /// Create a new incremental writer for the given PDF file.
+    pub fn new(pdf: &'a Pdf) -> Result<Self, IncrementalError> {
+        let trailer = Trailer::read(pdf)?;
+
+        let mut generations = FxHashMap::default();
+
+        for id in pdf.xref().object_ids() {
+            let generation = generations.entry(id.obj_number).or_insert(id.gen_number);
+            *generation = (*generation).max(id.gen_number);
+        }
+
+        let next_number = generations
+            .keys()
+            .map(|n| n + 1)
+            .chain([trailer.size, 1])
+            .max()
+            .unwrap();
+
+        Ok(Self {
+            pdf,
+            info: trailer.info,
+            trailer,
+            generations,
+            next_ref: Ref::new(next_number),
+            objects: BTreeMap::new(),
+        })
+    }
+
+    /// Allocate a reference for a new object.
+    pub fn new_ref(&mut self) -> Ref {
+        self.next_ref.bump()
+    }
+
+    /// Return the reference under which a replacement for the given existing
+    /// object should be written.
+    pub fn existing_ref(&self, obj_ref: ObjRef) -> Ref {
+        // `pdf-writer` always writes a generation number of zero, the actual one
+        // is restored when writing the update.
+        Ref::new(obj_ref.obj_number)
+    }
+
+    /// Return the reference of the document catalog.
+    pub fn root_ref(&self) -> ObjRef {
+        self.trailer.root
+    }
+
+    /// Return the reference of the document information dictionary, if any.
+    pub fn info_ref(&self) -> Option<ObjRef> {
+        self.info
+    }
+
+    /// Set the document information dictionary that should be referenced by the
+    /// trailer of the update.
+    pub fn set_info(&mut self, info: Ref) {
+        let generation = self.generations.get(&info.get()).copied().unwrap_or(0);
+        self.info = Some(ObjRef::new(info.get(), generation));
+    }
+
+    /// Add all objects of the given chunk to the update.
+    ///
+    /// If an object with the same number was added before, it is replaced.
+    pub fn write(&mut self, chunk: &Chunk) -> Result<(), IncrementalError> {
+        let data = chunk.as_bytes();
+        let mut r = Reader::new(data);
+        let ctx = ReaderContext::dummy();
+
+        loop {
+            r.skip_white_spaces_and_comments();
+
+            if r.at_end() {
+                break;
+            }
+
+            let id = r
+                .read_without_context::<ObjectIdentifier>()
+                .filter(|id| id.obj_number > 0)
+                .ok_or(IncrementalError::InvalidChunk)?;
+            let start = r.offset();
+
+            r.skip_white_spaces_and_comments();
+            r.read_with_context::<Object<'_>>(&ctx)
+                .ok_or(IncrementalError::InvalidChunk)?;
+            r.skip_white_spaces_and_comments();
+            r.forward_tag(b"endobj")
+                .ok_or(IncrementalError::InvalidChunk)?;
+
+            // Make sure that objects whose reference wasn't allocated by us don't
+            // collide with objects that are allocated later on.
+            if id.obj_number >= self.next_ref.get() {
+                self.next_ref = Ref::new(id.obj_number + 1);
+            }
+
+            self.objects
+                .insert(id.obj_number, data[start..r.offset()].to_vec());
+        }
+
+        Ok(())
+    }
+
+    /// Return the original file with the incremental update appended to it.
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut out = self.pdf.data().as_ref().to_vec();
+
+        if !out.ends_with(b"\n") && !out.ends_with(b"\r") {
+            out.push(b'\n');
+        }
+
+        let mut entries = BTreeMap::new();
+
+        // Like the ones of the original file, all offsets are relative to the header.
+        let base = self.trailer.base;
+
+        for (number, body) in &self.objects {
+            let generation = self.generations.get(number).copied().unwrap_or(0);
+            entries.insert(*number, (out.len() - base, generation));
+
+            write!(out, "{number} {generation} obj").unwrap();
+            out.extend_from_slice(body);
+            out.push(b'\n');
+        }
+
+        let xref_pos = out.len() - base;
+
+        if self.trailer.is_stream {
+            let xref_ref = self.new_ref();
+            entries.insert(xref_ref.get(), (xref_pos, 0));
+            let trailer = self.trailer_entries();
+
+            // Choose the smallest width that can hold all offsets.
+            let offset_width = (usize::BITS - xref_pos.leading_zeros()).div_ceil(8).max(1);
+            let mut data = vec![];
+
+            for (offset, generation) in entries.values() {
+                data.push(1);
+                data.extend_from_slice(&offset.to_be_bytes()[(8 - offset_width as usize)..]);
+                data.extend_from_slice(&(*generation as u16).to_be_bytes());
+            }
+
+            let index = subsections(&entries)
+                .iter()
+                .map(|(start, count)| format!("{start} {count}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            write!(
+                out,
+                "{} 0 obj\n<< /Type /XRef{trailer} /W [1 {offset_width} 2] /Index [{index}] /Length {} >>\nstream\n",
+                xref_ref.get(),
+                data.len()
+            )
+            .unwrap();
+            out.extend_from_slice(&data);
+            out.extend_from_slice(b"\nendstream\nendobj\n");
+        } else {
+            out.extend_from_slice(b"xref\n");
+
+            let subsections = subsections(&entries);
+            let mut entries = entries.values();
+
+            for (start, count) in subsections {
+                writeln!(out, "{start} {count}").unwrap();
+
+                for (offset, generation) in entries.by_ref().take(count) {
+                    write!(out, "{offset:010} {generation:05} n\r\n").unwrap();
+                }
+            }
+
+            write!(out, "trailer\n<<{} >>\n", self.trailer_entries()).unwrap();
+        }
+
+        write!(out, "startxref\n{xref_pos}\n%%EOF\n").unwrap();
+
+        out
+    }
+
+    /// Return the entries of the trailer dictionary of the update.
+    fn trailer_entries(&self) -> String {
+        let size = self.next_ref.get().max(self.trailer.size);
+        let mut entries = format!(" /Size {size} /Root {}", format_ref(self.trailer.root));
+
+        if let Some(info) = self.info {
+            entries.push_str(&format!(" /Info {}", format_ref(info)));
+        }
+
+        entries.push_str(&format!(" /Prev {}", self.trailer.xref_pos));
+
+        if let Some((first, second)) = &self.trailer.id {
+            entries.push_str(&format!(" /ID [<{}> <{}>]", hex(first), hex(second)));
+        }
+
+        entries
+    }
+}
+
+/// The relevant information from the last trailer of the original file.
+struct Trailer {
+    /// The position of the last xref section, relative to `base`.
+    xref_pos: usize,
+    /// The position in the file that the offsets in the xref are relative to.
+    base: usize,
+    is_stream: bool,
+    size: i32,
+    root: ObjRef,
+    info: Option<ObjRef>,
+    id: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Trailer {
+    fn read(pdf: &Pdf) -> Result<Self, IncrementalError> {
+        let data = pdf.data().as_ref();
+        let xref_pos = find_last_xref_pos(data).ok_or(IncrementalError::InvalidXRef)?;
+
+        // Offsets are usually relative to the `%PDF-` header, but some writers treat
+        // them as absolute, so we fall back to that in the same way as when loading.
+        let (base, dict, is_stream) = [pdf.header_offset(), 0]
+            .into_iter()
+            .find_map(|base| {
+                let (dict, is_stream) = read_xref_dict(pdf, base + xref_pos)?;
+
+                Some((base, dict, is_stream))
+            })
+            .ok_or(IncrementalError::InvalidXRef)?;
+
+        if dict.contains_key(ENCRYPT) {
+            return Err(IncrementalError::Encrypted);
+        }
+
+        let id = dict.get::<Array<'_>>(ID).and_then(|id| {
+            let mut iter = id.iter::<hayro_syntax::object::String<'_>>();
+
+            Some((iter.next()?.to_vec(), iter.next()?.to_vec()))
+        });
+
+        Ok(Self {
+            xref_pos,
+            base,
+            is_stream,
+            size: dict.get::<i32>(SIZE).unwrap_or(0),
+            root: dict
+                .get_ref(ROOT)
+                .unwrap_or_else(|| pdf.xref().root_id().into()),
+            info: dict.get_ref(INFO),
+            id,
+        })
+    }
+}
+
+/// Read the trailer dictionary of the xref section at the given absolute position,
+/// and whether the section is an xref stream.
+fn read_xref_dict(pdf: &Pdf, pos: usize) -> Option<(Dict<'_>, bool)> {
+    let mut r = Reader::new(pdf.data().as_ref());
+    r.jump(pos);
+    r.skip_white_spaces_and_comments();
+
+    if r.forward_tag(b"xref").is_some() {
+        let trailer_pos = find_needle(r.tail()?, b"trailer")?;
+        r.jump(r.offset() + trailer_pos + b"trailer".len());
+        r.skip_white_spaces_and_comments();
+
+        Some((
+            r.read_with_context::<Dict<'_>>(&ReaderContext::dummy())?,
+            false,
+        ))
+    } else {
+        match pdf.object_at_offset(pos)? {
+            (_, Object::Stream(stream)) => Some((stream.dict().clone(), true)),
+            _ => None,
+        }
+    }
+}
+
+fn find_last_xref_pos(data: &[u8]) -> Option<usize> {
+    let needle = b"startxref";
+    let pos = data.windows(needle.len()).rposition(|w| w == needle)?;
+
+    let mut r = Reader::new(data);
+    r.jump(pos + needle.len());
+    r.skip_white_spaces_and_comments();
+
+    r.read_without_context::<i32>()?.try_into().ok()
+}
+
+fn find_needle(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Group the object numbers into subsections of consecutive numbers, returning
+/// the first number and the length of each subsection.
+fn subsections<T>(entries: &BTreeMap<i32, T>) -> Vec<(i32, usize)> {
+    let mut subsections: Vec<(i32, usize)> = vec![];
+
+    for number in entries.keys() {
+        match subsections.last_mut() {
+            Some((start, count)) if *start + *count as i32 == *number => *count += 1,
+            _ => subsections.push((*number, 1)),
+        }
+    }
+
+    subsections
+}
+
+fn format_ref(obj_ref: ObjRef) -> String {
+    format!("{} {} R", obj_ref.obj_number, obj_ref.gen_number)
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02X}")).collect()
+}
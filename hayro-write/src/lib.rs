@@ -1,5 +1,6 @@
 /*!
-A crate for converting PDF pages into either `XObjects` or a new page via [`pdf-writer`](https://docs.rs/pdf-writer/).
+A crate for converting PDF pages into either `XObjects` or a new page via [`pdf-writer`](https://docs.rs/pdf-writer/),
+as well as for appending incremental updates to existing PDF files.
 
 This is an internal crate and not meant for external use. Therefore, it's not very
 well-documented.
@@ -11,9 +12,14 @@ well-documented.
 #[macro_use]
 mod log;
 
+mod filter;
+mod incremental;
 mod primitive;
+mod prune;
 
+use crate::filter::{filter_content, write_filtered_form};
 use crate::primitive::{WriteDirect, WriteIndirect};
+use crate::prune::{UsedResources, inherits_resources};
 use flate2::Compression;
 use flate2::write::ZlibEncoder;
 use hayro_syntax::object::Dict;
@@ -29,23 +35,48 @@ use std::collections::{BTreeMap, HashSet};
 use std::ops::Deref;
 use std::ops::DerefMut;
 
+pub use filter::ContentFilter;
 pub use hayro_syntax;
 use hayro_syntax::Pdf;
+pub use incremental::{IncrementalError, IncrementalWriter};
 pub use pdf_writer::Settings as ChunkSettings;
 
 /// Apply the extraction queries to the given PDF and return the results.
 pub fn extract<'a, G>(
+    pdf: &Pdf,
+    new_ref: Box<dyn FnMut() -> Ref + 'a>,
+    chunk_settings: ChunkSettings,
+    write_xobject_group_cs: G,
+    queries: &[ExtractionQuery],
+) -> Result<ExtractionResult, ExtractionError>
+where
+    G: for<'b> FnMut(&mut pdf_writer::writers::Group<'b>),
+{
+    extract_with_options(
+        pdf,
+        new_ref,
+        chunk_settings,
+        write_xobject_group_cs,
+        queries,
+        &ExtractionOptions::default(),
+    )
+}
+
+/// Apply the extraction queries to the given PDF with the given options and
+/// return the results.
+pub fn extract_with_options<'a, G>(
     pdf: &Pdf,
     new_ref: Box<dyn FnMut() -> Ref + 'a>,
     chunk_settings: ChunkSettings,
     mut write_xobject_group_cs: G,
     queries: &[ExtractionQuery],
+    options: &ExtractionOptions,
 ) -> Result<ExtractionResult, ExtractionError>
 where
     G: for<'b> FnMut(&mut pdf_writer::writers::Group<'b>),
 {
     let pages = pdf.pages();
-    let mut ctx = ExtractionContext::new(new_ref, pdf, chunk_settings);
+    let mut ctx = ExtractionContext::new(new_ref, pdf, chunk_settings, options);
 
     for query in queries {
         let page = pages
@@ -53,6 +84,7 @@ where
             .ok_or(ExtractionError::InvalidPageIndex(query.page_index))?;
 
         let root_ref = ctx.new_ref();
+        let checkpoint = ctx.checkpoint(query.page_index);
 
         let res = match query.query_type {
             ExtractionQueryType::XObject => {
@@ -61,13 +93,23 @@ where
             ExtractionQueryType::Page => write_page(page, root_ref, query.page_index, &mut ctx),
         };
 
+        // Now we have shallowly extracted the page, now go through all dependencies until there
+        // aren't any anymore.
+        let warnings = write_dependencies(pdf, &mut ctx);
+
+        let res = match (res, warnings.first()) {
+            (Ok(()), Some(warning)) if options.strict => Err(ExtractionError::Incomplete(*warning)),
+            (res, _) => res,
+        };
+
+        // Don't write anything for a query that failed.
+        if res.is_err() {
+            ctx.rollback(checkpoint);
+        }
+
         ctx.root_refs.push(res.map(|_| root_ref));
     }
 
-    // Now we have shallowly extracted all pages, now go through all dependencies until there aren't
-    // any anymore.
-    write_dependencies(pdf, &mut ctx);
-
     let mut global_chunk = Chunk::with_settings(chunk_settings);
 
     for chunk in &ctx.chunks {
@@ -78,6 +120,7 @@ where
         chunk: global_chunk,
         root_refs: ctx.root_refs,
         page_tree_parent_ref: ctx.page_tree_parent_ref,
+        warnings: ctx.warnings,
     })
 }
 
@@ -116,11 +159,62 @@ impl ExtractionQuery {
     }
 }
 
+/// Options for an extraction.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ExtractionOptions {
+    /// The categories of content that should be removed from the extracted pages.
+    pub content_filter: Option<ContentFilter>,
+    /// Whether resources that aren't referenced by the content stream of the page
+    /// should be left out.
+    ///
+    /// By default, all resources of a page are copied, even if only a few of them
+    /// are actually used. Enabling this can considerably reduce the size of the
+    /// output, for example if all pages share one big resource dictionary. If a
+    /// used form `XObject`, tiling pattern or Type3 font inherits the resources of
+    /// the page, everything is still copied.
+    pub prune_resources: bool,
+    /// Whether queries should fail if any of the objects they depend on had to be
+    /// dropped or altered.
+    ///
+    /// Nothing is written for a query that fails, except for objects that were
+    /// already written for an earlier query.
+    ///
+    /// By default, such problems are only reported via [`ExtractionResult::warnings`],
+    /// which means that the extracted pages might be silently incomplete.
+    pub strict: bool,
+}
+
 /// An error that occurred during page extraction.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ExtractionError {
     /// An invalid page index was given.
     InvalidPageIndex(usize),
+    /// An object that the query depends on had to be dropped or altered. This is only
+    /// reported in strict mode (see [`ExtractionOptions::strict`]) and contains the
+    /// first problem that was encountered.
+    Incomplete(ExtractionWarning),
+}
+
+/// A problem that occurred while writing an object, causing the output to be incomplete.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExtractionWarning {
+    /// The reference of the affected object in the original PDF.
+    pub obj_ref: ObjRef,
+    /// What went wrong.
+    pub kind: ExtractionWarningKind,
+}
+
+/// The kind of an [`ExtractionWarning`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExtractionWarningKind {
+    /// The object doesn't exist or couldn't be read, so it was dropped and all
+    /// references to it were replaced with `null`.
+    MissingObject,
+    /// The stream couldn't be decoded for applying the content filter, so its
+    /// content was removed.
+    UndecodableStream,
 }
 
 /// The result of an extraction.
@@ -131,11 +225,40 @@ pub struct ExtractionResult {
     pub root_refs: Vec<Result<Ref, ExtractionError>>,
     /// The reference to the page tree parent that was generated.
     pub page_tree_parent_ref: Ref,
+    /// The problems that occurred while writing the objects, in the order in which
+    /// they were encountered. Each problem is only reported once, even if multiple
+    /// queries are affected by it.
+    pub warnings: Vec<ExtractionWarning>,
+}
+
+impl ExtractionResult {
+    /// Whether all queries succeeded and no object had to be dropped or altered.
+    pub fn is_complete(&self) -> bool {
+        self.warnings.is_empty() && self.root_refs.iter().all(Result::is_ok)
+    }
+}
+
+/// An object that has already been written, along with the objects it references
+/// and the problems that occurred while writing it.
+struct WrittenObject {
+    refs: Vec<ObjRef>,
+    warnings: Vec<ExtractionWarning>,
+}
+
+/// The state of an extraction context before a query, so that everything written for
+/// the query can be discarded again if it fails.
+struct Checkpoint {
+    page_index: usize,
+    chunks: usize,
+    written_objects: usize,
+    has_content_stream: bool,
 }
 
 struct ExtractionContext<'a> {
     chunks: Vec<Chunk>,
-    visited_objects: HashSet<ObjRef>,
+    written_objects: FxHashMap<ObjRef, WrittenObject>,
+    // The keys of `written_objects` in the order in which they were written.
+    written_order: Vec<ObjRef>,
     to_visit_refs: Vec<ObjRef>,
     valid_ref_cache: FxHashMap<ObjRef, bool>,
     root_refs: Vec<Result<Ref, ExtractionError>>,
@@ -145,6 +268,12 @@ struct ExtractionContext<'a> {
     cached_content_streams: FxHashMap<usize, Ref>,
     page_tree_parent_ref: Ref,
     chunk_settings: ChunkSettings,
+    content_filter: Option<ContentFilter>,
+    filtered_forms: HashSet<ObjRef>,
+    prune_resources: bool,
+    warnings: Vec<ExtractionWarning>,
+    // The problems that occurred since they were last collected by `write_dependencies`.
+    pending_warnings: Vec<ExtractionWarning>,
 }
 
 impl<'a> ExtractionContext<'a> {
@@ -152,11 +281,13 @@ impl<'a> ExtractionContext<'a> {
         mut new_ref: Box<dyn FnMut() -> Ref + 'a>,
         pdf: &'a Pdf,
         chunk_settings: ChunkSettings,
+        options: &ExtractionOptions,
     ) -> Self {
         let page_tree_parent_ref = new_ref();
         Self {
             chunks: vec![],
-            visited_objects: HashSet::new(),
+            written_objects: FxHashMap::default(),
+            written_order: Vec::new(),
             to_visit_refs: Vec::new(),
             valid_ref_cache: FxHashMap::default(),
             pdf,
@@ -166,7 +297,44 @@ impl<'a> ExtractionContext<'a> {
             root_refs: Vec::new(),
             page_tree_parent_ref,
             chunk_settings,
+            content_filter: options.content_filter,
+            filtered_forms: HashSet::new(),
+            prune_resources: options.prune_resources,
+            warnings: Vec::new(),
+            pending_warnings: Vec::new(),
+        }
+    }
+
+    fn checkpoint(&self, page_index: usize) -> Checkpoint {
+        Checkpoint {
+            page_index,
+            chunks: self.chunks.len(),
+            written_objects: self.written_order.len(),
+            has_content_stream: self.cached_content_streams.contains_key(&page_index),
+        }
+    }
+
+    /// Discard all objects that were written since the checkpoint was created.
+    fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.chunks.truncate(checkpoint.chunks);
+
+        for ref_ in self.written_order.drain(checkpoint.written_objects..) {
+            self.written_objects.remove(&ref_);
+        }
+
+        if !checkpoint.has_content_stream {
+            self.cached_content_streams.remove(&checkpoint.page_index);
+        }
+    }
+
+    pub(crate) fn report(&mut self, obj_ref: ObjRef, kind: ExtractionWarningKind) {
+        let warning = ExtractionWarning { obj_ref, kind };
+
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
         }
+
+        self.pending_warnings.push(warning);
     }
 
     pub(crate) fn map_ref(&mut self, ref_: ObjRef) -> Ref {
@@ -183,32 +351,87 @@ impl<'a> ExtractionContext<'a> {
     pub(crate) fn new_ref(&mut self) -> Ref {
         (self.new_ref)()
     }
+
+    /// Return the content stream of the page, with the content filter applied.
+    fn page_content(&mut self, page: &Page<'_>) -> Vec<u8> {
+        let content = page.page_stream().unwrap_or(b"");
+
+        if let Some(filter) = self.content_filter {
+            let x_objects = collect_resources(page.resources(), |r| r.x_objects.clone());
+
+            filter_content(content, &x_objects, &filter, self)
+        } else {
+            content.to_vec()
+        }
+    }
 }
 
-fn write_dependencies(pdf: &Pdf, ctx: &mut ExtractionContext<'_>) {
+/// Write all objects that are still to be visited as well as their dependencies, and
+/// return the problems that occurred for any of them, including the ones for objects
+/// that were already written for an earlier query.
+fn write_dependencies(pdf: &Pdf, ctx: &mut ExtractionContext<'_>) -> Vec<ExtractionWarning> {
+    let mut warnings = std::mem::take(&mut ctx.pending_warnings);
+    let mut visited = HashSet::new();
+
     while let Some(ref_) = ctx.to_visit_refs.pop() {
         // Don't visit objects twice!
-        if ctx.visited_objects.contains(&ref_) {
+        if !visited.insert(ref_) {
             continue;
         }
 
+        // Don't write objects twice either, but still collect the problems of their dependencies.
+        if let Some(written) = ctx.written_objects.get(&ref_) {
+            ctx.to_visit_refs.extend_from_slice(&written.refs);
+            warnings.extend_from_slice(&written.warnings);
+
+            continue;
+        }
+
+        let start = ctx.to_visit_refs.len();
         let mut chunk = Chunk::with_settings(ctx.chunk_settings);
         if let Some(object) = pdf.xref().get::<Object<'_>>(ref_.into()) {
             let new_ref = ctx.map_ref(ref_);
-            object.write_indirect(&mut chunk, new_ref, ctx);
-            ctx.chunks.push(chunk);
 
-            ctx.visited_objects.insert(ref_);
+            match (&object, ctx.content_filter) {
+                (Object::Stream(stream), Some(filter)) if ctx.filtered_forms.contains(&ref_) => {
+                    write_filtered_form(stream, ref_, new_ref, &filter, &mut chunk, ctx);
+                }
+                _ => object.write_indirect(&mut chunk, new_ref, ctx),
+            }
+
+            ctx.chunks.push(chunk);
         } else {
             warn!("failed to extract object with ref: {ref_:?}");
+            ctx.report(ref_, ExtractionWarningKind::MissingObject);
         }
+
+        let written = WrittenObject {
+            refs: ctx.to_visit_refs[start..].to_vec(),
+            warnings: std::mem::take(&mut ctx.pending_warnings),
+        };
+        warnings.extend_from_slice(&written.warnings);
+        ctx.written_objects.insert(ref_, written);
+        ctx.written_order.push(ref_);
     }
+
+    warnings
 }
 
 /// Extract the given pages from the PDF and resave them as a new PDF. This function shouldn't be
 /// used directly and only exists for test purposes.
 #[doc(hidden)]
 pub fn extract_pages_to_pdf(hayro_pdf: &Pdf, page_indices: &[usize]) -> Vec<u8> {
+    extract_pages_to_pdf_with_options(hayro_pdf, page_indices, &ExtractionOptions::default())
+}
+
+/// Extract the given pages from the PDF with the given options and resave them as a
+/// new PDF. This function shouldn't be used directly and only exists for test purposes.
+#[doc(hidden)]
+pub fn extract_pages_to_pdf_with_options(
+    hayro_pdf: &Pdf,
+    page_indices: &[usize],
+    options: &ExtractionOptions,
+) -> Vec<u8> {
     let mut pdf = pdf_writer::Pdf::new();
     let mut next_ref = Ref::new(1);
     let requests = page_indices
@@ -221,12 +444,13 @@ pub fn extract_pages_to_pdf(hayro_pdf: &Pdf, page_indices: &[usize]) -> Vec<u8>
 
     let catalog_id = next_ref.bump();
 
-    let extracted = extract(
+    let extracted = extract_with_options(
         hayro_pdf,
         Box::new(|| next_ref.bump()),
         ChunkSettings::default(),
         /* Unused when writing as page instead of XObject */ |_| unreachable!(),
         &requests,
+        options,
     )
     .unwrap();
     pdf.catalog(catalog_id)
@@ -277,8 +501,15 @@ pub fn extract_pages_as_xobject_to_pdf(hayro_pdf: &Pdf, page_indices: &[usize])
     for (x_object_ref, page_idx) in extracted.root_refs.iter().zip(page_indices) {
         let page = &page_list[*page_idx];
         let render_dimensions = page.render_dimensions();
+        let user_unit = page.user_unit();
 
         let mut content = Content::new();
+        // The XObject is scaled by the user unit already. Undo that, since the
+        // new page has the same user unit, so that its media box doesn't exceed
+        // the size limit of PDF pages.
+        if user_unit != 1.0 {
+            content.transform([1.0 / user_unit, 0.0, 0.0, 1.0 / user_unit, 0.0, 0.0]);
+        }
         content.x_object(Name(b"O1"));
 
         let finished = content.finish();
@@ -294,9 +525,12 @@ pub fn extract_pages_as_xobject_to_pdf(hayro_pdf: &Pdf, page_indices: &[usize])
         page.media_box(Rect::new(
             0.0,
             0.0,
-            render_dimensions.0,
-            render_dimensions.1,
+            render_dimensions.0 / user_unit,
+            render_dimensions.1 / user_unit,
         ));
+        if user_unit != 1.0 {
+            page.user_unit(user_unit);
+        }
         page.parent(extracted.page_tree_parent_ref);
         page.contents(stream_id);
         page.finish();
@@ -320,6 +554,7 @@ fn write_page(
     ctx: &mut ExtractionContext<'_>,
 ) -> Result<(), ExtractionError> {
     let mut chunk = Chunk::with_settings(ctx.chunk_settings);
+    let content = ctx.page_content(page);
     // Note: We can cache content stream references, but _not_ the page references themselves.
     // Acrobat for some reason doesn't like duplicate page references in the page tree.
     let stream_ref = if let Some(cached) = ctx.cached_content_streams.get(&page_idx) {
@@ -328,10 +563,7 @@ fn write_page(
         let stream_ref = ctx.new_ref();
 
         chunk
-            .stream(
-                stream_ref,
-                &deflate_encode(page.page_stream().unwrap_or(b"")),
-            )
+            .stream(stream_ref, &deflate_encode(&content))
             .filter(Filter::FlateDecode);
         ctx.cached_content_streams.insert(page_idx, stream_ref);
 
@@ -358,7 +590,7 @@ fn write_page(
         group.write_direct(pdf_page.insert(Name(GROUP)), ctx);
     }
 
-    serialize_resources(page.resources(), ctx, &mut pdf_page);
+    serialize_resources(page.resources(), &content, ctx, &mut pdf_page);
 
     pdf_page.finish();
 
@@ -377,7 +609,8 @@ where
     G: for<'b> FnMut(&mut pdf_writer::writers::Group<'b>),
 {
     let mut chunk = Chunk::with_settings(ctx.chunk_settings);
-    let encoded_stream = deflate_encode(page.page_stream().unwrap_or(b""));
+    let content = ctx.page_content(page);
+    let encoded_stream = deflate_encode(&content);
     let mut x_object = chunk.form_xobject(xobj_ref, &encoded_stream);
     x_object.deref_mut().filter(Filter::FlateDecode);
 
@@ -401,7 +634,7 @@ where
         i[5] as f32,
     ]);
 
-    serialize_resources(page.resources(), ctx, &mut x_object);
+    serialize_resources(page.resources(), &content, ctx, &mut x_object);
 
     // Latex seems to isolate all embedded PDFs which makes sense, so we also
     // do the same. See also https://github.com/typst/typst/issues/7269.
@@ -418,16 +651,38 @@ where
 
 fn serialize_resources(
     resources: &Resources<'_>,
+    content: &[u8],
     ctx: &mut ExtractionContext<'_>,
     writer: &mut impl ResourcesExt,
 ) {
-    let ext_g_states = collect_resources(resources, |r| r.ext_g_states.clone());
-    let shadings = collect_resources(resources, |r| r.shadings.clone());
-    let patterns = collect_resources(resources, |r| r.patterns.clone());
-    let x_objects = collect_resources(resources, |r| r.x_objects.clone());
-    let color_spaces = collect_resources(resources, |r| r.color_spaces.clone());
-    let fonts = collect_resources(resources, |r| r.fonts.clone());
-    let properties = collect_resources(resources, |r| r.properties.clone());
+    let mut ext_g_states = collect_resources(resources, |r| r.ext_g_states.clone());
+    let mut shadings = collect_resources(resources, |r| r.shadings.clone());
+    let mut patterns = collect_resources(resources, |r| r.patterns.clone());
+    let mut x_objects = collect_resources(resources, |r| r.x_objects.clone());
+    let mut color_spaces = collect_resources(resources, |r| r.color_spaces.clone());
+    let mut fonts = collect_resources(resources, |r| r.fonts.clone());
+    let mut properties = collect_resources(resources, |r| r.properties.clone());
+
+    if ctx.prune_resources {
+        let used = UsedResources::new(content);
+        let all = [
+            (EXT_G_STATE, &mut ext_g_states),
+            (SHADING, &mut shadings),
+            (PATTERN, &mut patterns),
+            (XOBJECT, &mut x_objects),
+            (COLORSPACE, &mut color_spaces),
+            (FONT, &mut fonts),
+            (PROPERTIES, &mut properties),
+        ];
+
+        let shared = all.each_ref().map(|(key, map)| (*key, &**map));
+
+        if !inherits_resources(&used, &shared, ctx) {
+            for (key, map) in all {
+                map.retain(|name, _| used.contains(key, name));
+            }
+        }
+    }
 
     // Resource dictionary is always required (unless it can be inherited), so
     // let's just be safe and always write it.
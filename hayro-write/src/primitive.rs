@@ -1,8 +1,8 @@
-use crate::ExtractionContext;
+use crate::{ExtractionContext, ExtractionWarningKind};
 use hayro_syntax::object;
 use hayro_syntax::object::dict::keys::{
-    AF, LAST_MODIFIED, LENGTH, METADATA, OC, OPI, PIECE_INFO, PT_DATA, REF, STRUCT_PARENT,
-    STRUCT_PARENTS,
+    AF, DECODE_PARMS, FILTER, LAST_MODIFIED, LENGTH, METADATA, OC, OPI, PIECE_INFO, PT_DATA, REF,
+    STRUCT_PARENT, STRUCT_PARENTS,
 };
 use hayro_syntax::object::{MaybeRef, Null, Number, ObjectIdentifier, Stream};
 use hayro_syntax::object::{Object, array, dict};
@@ -48,6 +48,7 @@ impl WriteDirect for object::ObjRef {
             let mapped_ref = ctx.map_ref(*self);
             obj.primitive(mapped_ref);
         } else {
+            ctx.report(*self, ExtractionWarningKind::MissingObject);
             obj.primitive(pdf_writer::Null);
         }
     }
@@ -112,10 +113,10 @@ fn write_dict(
     hayro_dict: &dict::Dict<'_>,
     pdf_dict: &mut Dict<'_>,
     ctx: &mut ExtractionContext<'_>,
-    is_stream: bool,
+    skipped_keys: &[&[u8]],
 ) {
     for (name, val) in hayro_dict.entries() {
-        if is_stream && name.deref() == LENGTH {
+        if skipped_keys.contains(&name.deref()) {
             continue;
         }
 
@@ -125,11 +126,21 @@ fn write_dict(
     }
 }
 
+/// Write the dictionary of a stream whose data was re-encoded, i.e. without
+/// the information about the original filters.
+pub(crate) fn write_reencoded_stream_dict(
+    hayro_dict: &dict::Dict<'_>,
+    pdf_dict: &mut Dict<'_>,
+    ctx: &mut ExtractionContext<'_>,
+) {
+    write_dict(hayro_dict, pdf_dict, ctx, &[LENGTH, FILTER, DECODE_PARMS]);
+}
+
 impl WriteDirect for object::Dict<'_> {
     fn write_direct(&self, obj: Obj<'_>, ctx: &mut ExtractionContext<'_>) {
         let mut dict = obj.dict();
 
-        write_dict(self, &mut dict, ctx, false);
+        write_dict(self, &mut dict, ctx, &[]);
     }
 }
 
@@ -175,7 +186,7 @@ impl WriteIndirect for Stream<'_> {
         // TODO: Handle `Crypt` filter
         let data = self.raw_data();
         let mut obj = chunk.stream(id, &data);
-        write_dict(self.dict(), obj.deref_mut(), ctx, true);
+        write_dict(self.dict(), obj.deref_mut(), ctx, &[LENGTH]);
     }
 }
 
@@ -0,0 +1,120 @@
+//! Determining which resources are actually used by a content stream.
+
+use crate::ExtractionContext;
+use hayro_syntax::content::UntypedIter;
+use hayro_syntax::object::dict::keys::{
+    COLORSPACE, CS, EXT_G_STATE, FONT, FORM, I, INDEXED, PATTERN, PATTERN_TYPE, PROPERTIES,
+    RESOURCES, SHADING, SUBTYPE, TYPE3, XOBJECT,
+};
+use hayro_syntax::object::{Dict, MaybeRef, Name, Object};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Deref;
+
+/// The names of all resources that are referenced by a content stream, together
+/// with the resource category they belong to.
+#[derive(Debug, Default)]
+pub(crate) struct UsedResources {
+    names: HashSet<(&'static [u8], Vec<u8>)>,
+}
+
+impl UsedResources {
+    /// Scan the operators of the given content stream for resource references.
+    pub(crate) fn new(content: &[u8]) -> Self {
+        let mut used = Self::default();
+        let mut iter = UntypedIter::new(content);
+
+        while let Some(instruction) = iter.next() {
+            let mut operands = instruction.operands();
+
+            match instruction.operator.deref() {
+                b"Do" => used.insert(XOBJECT, operands.next()),
+                b"Tf" => used.insert(FONT, operands.next()),
+                b"gs" => used.insert(EXT_G_STATE, operands.next()),
+                b"sh" => used.insert(SHADING, operands.next()),
+                b"cs" | b"CS" => used.insert(COLORSPACE, operands.next()),
+                // The pattern name is always the last operand.
+                b"scn" | b"SCN" => used.insert(PATTERN, operands.last()),
+                b"BDC" | b"DP" => used.insert(PROPERTIES, operands.nth(1)),
+                b"BI" => {
+                    if let Some(Object::Stream(stream)) = operands.next() {
+                        let dict = stream.dict();
+                        let color_space = dict
+                            .get::<Object<'_>>(CS)
+                            .or_else(|| dict.get::<Object<'_>>(COLORSPACE));
+                        used.insert(COLORSPACE, color_space.as_ref());
+
+                        // The base color space of an indexed color space can be
+                        // the name of a resource as well.
+                        if let Some(Object::Array(array)) = &color_space {
+                            let mut iter = array.iter::<Object<'_>>();
+
+                            if let Some(Object::Name(name)) = iter.next()
+                                && matches!(name.deref(), I | INDEXED)
+                            {
+                                used.insert(COLORSPACE, iter.next().as_ref());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        used
+    }
+
+    fn insert(&mut self, category: &'static [u8], operand: Option<&Object<'_>>) {
+        if let Some(Object::Name(name)) = operand {
+            self.names.insert((category, name.to_vec()));
+        }
+    }
+
+    /// Whether the resource with the given name is referenced.
+    pub(crate) fn contains(&self, category: &'static [u8], name: &[u8]) -> bool {
+        self.names.contains(&(category, name.to_vec()))
+    }
+}
+
+/// Whether any of the used form `XObjects`, tiling patterns or Type3 fonts lacks
+/// its own resource dictionary. Such objects inherit the resources of the page,
+/// so they might reference resources that don't appear in the page content stream
+/// itself. In this case, no resources can be pruned.
+pub(crate) fn inherits_resources<'a>(
+    used: &UsedResources,
+    resources: &[(&'static [u8], &BTreeMap<Name<'a>, MaybeRef<Object<'a>>>)],
+    ctx: &ExtractionContext<'_>,
+) -> bool {
+    resources
+        .iter()
+        .filter(|(category, _)| [XOBJECT, PATTERN, FONT].contains(category))
+        .flat_map(|(category, map)| map.iter().map(move |(name, obj)| (*category, name, obj)))
+        .filter(|&(category, name, _)| used.contains(category, name))
+        .any(|(category, _, obj)| {
+            let dict = match obj {
+                MaybeRef::Ref(ref_) => ctx.pdf.xref().get::<Object<'_>>((*ref_).into()),
+                MaybeRef::NotRef(obj) => Some(obj.clone()),
+            }
+            .and_then(|obj| match obj {
+                Object::Stream(stream) => Some(stream.dict().clone()),
+                Object::Dict(dict) => Some(dict),
+                _ => None,
+            });
+
+            let Some(dict) = dict else {
+                return false;
+            };
+
+            has_content(category, &dict) && !dict.contains_key(RESOURCES)
+        })
+}
+
+/// Whether the dictionary belongs to a Type3 font, a form `XObject` or a tiling pattern.
+fn has_content(category: &[u8], dict: &Dict<'_>) -> bool {
+    let subtype = dict.get::<Name<'_>>(SUBTYPE);
+
+    if category == FONT {
+        subtype.as_deref() == Some(TYPE3)
+    } else {
+        subtype.as_deref() == Some(FORM) || dict.get::<u8>(PATTERN_TYPE) == Some(1)
+    }
+}
@@ -0,0 +1,50 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Keeps track of the memory used by the pixmaps that are allocated while
+/// rendering a page, see [`RenderSettings::max_pixmap_bytes`].
+///
+/// The budget is shared between the main renderer and all nested renderers
+/// (for example for soft masks and pattern tiles). Since the contents are only
+/// rasterized once the whole page has been processed, memory is never given back.
+///
+/// [`RenderSettings::max_pixmap_bytes`]: crate::RenderSettings::max_pixmap_bytes
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PixmapBudget {
+    max_bytes: Option<usize>,
+    used: Rc<Cell<usize>>,
+}
+
+impl PixmapBudget {
+    pub(crate) fn new(max_bytes: Option<usize>) -> Self {
+        Self {
+            max_bytes,
+            used: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Return the number of pixels of an RGBA pixmap that can still be allocated.
+    pub(crate) fn remaining_pixels(&self) -> usize {
+        self.max_bytes
+            .map(|max| max.saturating_sub(self.used.get()) / BYTES_PER_PIXEL)
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Try to reserve the memory for an RGBA pixmap with the given dimensions.
+    ///
+    /// Returns `false` if this would exceed the budget, in which case nothing is reserved.
+    pub(crate) fn reserve(&self, width: u16, height: u16) -> bool {
+        let pixels = width as usize * height as usize;
+
+        if pixels > self.remaining_pixels() {
+            return false;
+        }
+
+        self.used
+            .set(self.used.get().saturating_add(pixels * BYTES_PER_PIXEL));
+
+        true
+    }
+}
+
+const BYTES_PER_PIXEL: usize = 4;
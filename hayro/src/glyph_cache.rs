@@ -0,0 +1,221 @@
+//! A cache for the rasterized coverage of glyphs that are drawn at small sizes.
+//!
+//! Body text usually consists of the same few glyphs that are drawn over and over
+//! again with the same size, only at different positions. Instead of filling the
+//! outline of each of them, we rasterize each glyph once per sub-pixel offset and
+//! then simply blit the stored coverage at the integer part of its position.
+//!
+//! Since the sub-pixel offset is quantized, glyphs can be placed up to an eighth
+//! of a pixel away from their exact position, which is why the cache needs to be
+//! enabled explicitly via `RenderSettings::glyph_cache`.
+
+use crate::AntialiasMode;
+use kurbo::{Affine, Rect};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use vello_cpu::Pixmap;
+use vello_cpu::color::PremulRgba8;
+
+/// The number of sub-pixel positions per pixel in each direction.
+const SUBPIXEL_STEPS: f64 = 4.0;
+
+/// Glyphs whose bitmap is larger than this number of pixels in either direction
+/// are filled as paths instead.
+const MAX_GLYPH_SIZE: f64 = 64.0;
+
+/// The total number of bytes of coverage and colorized pixmaps after which the
+/// cache is cleared.
+const MAX_CACHE_SIZE: usize = 8 * 1024 * 1024;
+
+/// The factor by which the scale and skew of the glyph transform are multiplied
+/// before being rounded for the cache key.
+const TRANSFORM_PRECISION: f64 = (1 << 20) as f64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct GlyphKey {
+    glyph: u128,
+    coeffs: [i64; 4],
+    offset: (u8, u8),
+    antialias: AntialiasMode,
+    stem_darkening: bool,
+}
+
+/// The position of a glyph in device space, split into the integer pixel the
+/// glyph origin lies in and a quantized sub-pixel offset.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GlyphPlacement {
+    pub(crate) key: GlyphKey,
+    /// The transform the glyph is rasterized with, i.e. the glyph transform
+    /// without its integer translation.
+    pub(crate) transform: Affine,
+    /// The bounds of the glyph bitmap relative to the integer origin.
+    pub(crate) bounds: Rect,
+    /// The integer part of the glyph origin.
+    pub(crate) origin: (f64, f64),
+}
+
+impl GlyphPlacement {
+    /// Determine the placement of a glyph with the given control box that is
+    /// drawn with the given transform, or `None` if the glyph is too large to be
+    /// cached.
+    pub(crate) fn new(
+        glyph: u128,
+        transform: Affine,
+        control_box: Rect,
+        antialias: AntialiasMode,
+        stem_darkening: bool,
+    ) -> Option<Self> {
+        let [a, b, c, d, e, f] = transform.as_coeffs();
+        let (x, x_offset) = split_position(e);
+        let (y, y_offset) = split_position(f);
+
+        let transform = Affine::new([
+            a,
+            b,
+            c,
+            d,
+            x_offset as f64 / SUBPIXEL_STEPS,
+            y_offset as f64 / SUBPIXEL_STEPS,
+        ]);
+
+        // Stem darkening can make the outline slightly larger, so leave a pixel of
+        // room on each side.
+        let bounds = transform.transform_rect_bbox(control_box);
+        let bounds = Rect::new(
+            bounds.x0.floor() - 1.0,
+            bounds.y0.floor() - 1.0,
+            bounds.x1.ceil() + 1.0,
+            bounds.y1.ceil() + 1.0,
+        );
+
+        if !(bounds.width() <= MAX_GLYPH_SIZE && bounds.height() <= MAX_GLYPH_SIZE) {
+            return None;
+        }
+
+        let quantize = |v: f64| (v * TRANSFORM_PRECISION).round() as i64;
+
+        Some(Self {
+            key: GlyphKey {
+                glyph,
+                coeffs: [quantize(a), quantize(b), quantize(c), quantize(d)],
+                offset: (x_offset, y_offset),
+                antialias,
+                stem_darkening,
+            },
+            transform,
+            bounds,
+            origin: (x, y),
+        })
+    }
+}
+
+/// Split a coordinate into its integer part and the sub-pixel offset, in
+/// multiples of `1 / SUBPIXEL_STEPS`.
+fn split_position(v: f64) -> (f64, u8) {
+    let floor = v.floor();
+    let offset = ((v - floor) * SUBPIXEL_STEPS).round();
+
+    if offset >= SUBPIXEL_STEPS {
+        (floor + 1.0, 0)
+    } else {
+        (floor, offset as u8)
+    }
+}
+
+/// The rasterized coverage of a glyph.
+#[derive(Debug)]
+pub(crate) struct GlyphBitmap {
+    pub(crate) coverage: Vec<u8>,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+}
+
+impl GlyphBitmap {
+    /// Create a pixmap that is filled with the given color, using the coverage
+    /// as its alpha channel.
+    fn colorize(&self, color: [u8; 4]) -> Pixmap {
+        let data = self
+            .coverage
+            .iter()
+            .map(|&coverage| {
+                let alpha = mul_u8(coverage, color[3]);
+
+                PremulRgba8 {
+                    r: mul_u8(color[0], alpha),
+                    g: mul_u8(color[1], alpha),
+                    b: mul_u8(color[2], alpha),
+                    a: alpha,
+                }
+            })
+            .collect();
+
+        Pixmap::from_parts_with_opacity(data, self.width, self.height, true)
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct GlyphCache {
+    bitmaps: FxHashMap<GlyphKey, Rc<GlyphBitmap>>,
+    // Text is usually drawn in only a few colors, so we also keep the colorized
+    // pixmaps around instead of rebuilding them for each glyph that is drawn.
+    pixmaps: FxHashMap<(GlyphKey, [u8; 4]), Arc<Pixmap>>,
+    size: usize,
+    rasterizations: usize,
+}
+
+impl GlyphCache {
+    /// Return the pixmap for the given key filled with the given color,
+    /// rasterizing the glyph if it isn't cached yet.
+    pub(crate) fn get_or_rasterize(
+        &mut self,
+        key: GlyphKey,
+        color: [u8; 4],
+        rasterize: impl FnOnce() -> GlyphBitmap,
+    ) -> Arc<Pixmap> {
+        if let Some(pixmap) = self.pixmaps.get(&(key, color)) {
+            return pixmap.clone();
+        }
+
+        let bitmap = if let Some(bitmap) = self.bitmaps.get(&key) {
+            bitmap.clone()
+        } else {
+            let bitmap = Rc::new(rasterize());
+            self.rasterizations += 1;
+            self.reserve(bitmap.coverage.len());
+            self.bitmaps.insert(key, bitmap.clone());
+
+            bitmap
+        };
+
+        let pixmap = Arc::new(bitmap.colorize(color));
+        self.reserve(bitmap.coverage.len() * 4);
+        self.pixmaps.insert((key, color), pixmap.clone());
+
+        pixmap
+    }
+
+    /// Account for an entry of the given size, clearing the cache if it gets
+    /// too large.
+    fn reserve(&mut self, size: usize) {
+        self.size += size;
+
+        if self.size > MAX_CACHE_SIZE {
+            self.bitmaps.clear();
+            self.pixmaps.clear();
+            self.size = size;
+        }
+    }
+
+    /// The number of glyphs that have been rasterized so far.
+    pub(crate) fn rasterizations(&self) -> usize {
+        self.rasterizations
+    }
+}
+
+/// Multiply two values in the range 0-255 with each other.
+fn mul_u8(a: u8, b: u8) -> u8 {
+    let product = a as u32 * b as u32 + 128;
+
+    ((product + (product >> 8)) >> 8) as u8
+}
@@ -0,0 +1,55 @@
+use vello_cpu::Pixmap;
+
+/// An 8-bit grayscale pixmap.
+#[derive(Debug, Clone)]
+pub struct GrayPixmap {
+    width: u16,
+    height: u16,
+    data: Vec<u8>,
+}
+
+impl GrayPixmap {
+    /// Convert an RGBA pixmap into a grayscale pixmap.
+    ///
+    /// The gray value of each pixel is the luminance of its color, using the
+    /// weights from ITU-R BT.601. Since the pixmap has no alpha channel, pixels
+    /// that aren't fully opaque are treated as if they were drawn on black.
+    pub(crate) fn from_rgba(pixmap: &Pixmap) -> Self {
+        let data = pixmap
+            .data()
+            .iter()
+            .map(|p| {
+                // The weights are 0.299, 0.587 and 0.114, scaled by 2^16.
+                let luminance = 19595 * p.r as u32 + 38470 * p.g as u32 + 7471 * p.b as u32;
+
+                ((luminance + (1 << 15)) >> 16) as u8
+            })
+            .collect();
+
+        Self {
+            width: pixmap.width(),
+            height: pixmap.height(),
+            data,
+        }
+    }
+
+    /// The width of the pixmap.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The height of the pixmap.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The gray values of the pixmap, in row-major order.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consume the pixmap and return the gray values, in row-major order.
+    pub fn take(self) -> Vec<u8> {
+        self.data
+    }
+}
@@ -26,40 +26,67 @@ For usage examples, see the [example](https://github.com/LaurenzV/hayro/tree/mas
 the GitHub repository.
 
 ## Cargo features
-This crate has one optional feature:
+This crate has the following optional features:
 - `embed-fonts`: See the description of [`hayro-interpret`](https://docs.rs/hayro-interpret/latest/hayro_interpret/#cargo-features) for more information.
+- `images` (enabled by default): See the description of [`hayro-interpret`](https://docs.rs/hayro-interpret/latest/hayro_interpret/#cargo-features) for more information.
+- `op-map`: Enables `render_with_op_map`, which additionally records which regions of the
+  rendered page were painted by which content stream operator. This is useful for debugging
+  overlays that highlight the operators under the cursor.
 */
 
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
 
-use crate::renderer::Renderer;
+use crate::budget::PixmapBudget;
+use crate::glyph_cache::GlyphCache;
+use crate::renderer::{Renderer, RendererConfig};
+use hayro_interpret::Deadline;
 use hayro_interpret::Device;
+use hayro_interpret::DisplayList;
 use hayro_interpret::FillRule;
+use hayro_interpret::ImageData;
 use hayro_interpret::InterpreterCache;
 use hayro_interpret::InterpreterSettings;
 use hayro_interpret::hayro_syntax::Pdf;
-use hayro_interpret::hayro_syntax::page::Page;
+use hayro_interpret::hayro_syntax::page::{Page, Rotation};
 use hayro_interpret::util::{RectExt, TransformExt};
 use hayro_interpret::{BlendMode, Context};
-use hayro_interpret::{ClipPath, interpret_page};
-use kurbo::{Affine, Rect, Shape};
+use hayro_interpret::{ClipPath, decode_thumbnail, interpret_page};
+use hayro_interpret::{InterpreterWarning, UnsupportedFeature};
+use kurbo::{Affine, BezPath, Rect, Shape};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 use std::ops::RangeInclusive;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
 
 pub use hayro_interpret;
 pub use hayro_interpret::hayro_syntax;
 pub use vello_cpu;
 
 use vello_cpu::color::AlphaColor;
+use vello_cpu::color::PremulRgba8;
 use vello_cpu::color::Srgb;
 use vello_cpu::color::palette::css::TRANSPARENT;
 use vello_cpu::color::palette::css::WHITE;
 use vello_cpu::{Level, Pixmap};
 
+mod budget;
+mod glyph_cache;
+mod gray;
+#[cfg(feature = "op-map")]
+mod op_map;
+mod pixmap;
 mod renderer;
+mod stem_darkening;
+
+pub use gray::GrayPixmap;
+pub use pixmap::{PixmapExt, StraightPixmap};
+
+#[cfg(feature = "op-map")]
+pub use op_map::{OpMap, OpRegion, render_with_op_map};
 
 /// A cache used by the renderer.
 ///
@@ -68,7 +95,8 @@ mod renderer;
 #[derive(Clone, Default)]
 pub struct RenderCache<'a> {
     pub(crate) interpreter_cache: InterpreterCache<'a>,
-    pub(crate) outline_cache: Rc<RefCell<FxHashMap<u128, Rc<kurbo::BezPath>>>>,
+    pub(crate) outline_cache: Rc<RefCell<FxHashMap<u128, Rc<BezPath>>>>,
+    pub(crate) glyph_cache: Rc<RefCell<GlyphCache>>,
 }
 
 impl<'a> RenderCache<'a> {
@@ -77,13 +105,30 @@ impl<'a> RenderCache<'a> {
         Self {
             interpreter_cache: InterpreterCache::new(),
             outline_cache: Rc::new(RefCell::new(FxHashMap::default())),
+            glyph_cache: Rc::new(RefCell::new(GlyphCache::default())),
         }
     }
+
+    // Return the number of glyphs that have been rasterized into the glyph cache
+    // so far. Only used for testing the glyph cache.
+    #[doc(hidden)]
+    pub fn rasterized_glyphs(&self) -> usize {
+        self.glyph_cache.borrow().rasterizations()
+    }
 }
 
+/// A callback for unsupported features that are encountered during rendering.
+///
+/// The callback receives the index of the page that is being rendered (see
+/// [`Page::index`]) and the feature.
+pub type UnsupportedFeatureFn = Arc<dyn Fn(usize, &UnsupportedFeature) + Send + Sync>;
+
 /// Settings to apply during rendering.
+///
+/// The clip path, the cancellation flag and the unsupported feature callback are
+/// borrowed, so that the settings stay `Copy`.
 #[derive(Clone, Copy)]
-pub struct RenderSettings {
+pub struct RenderSettings<'a> {
     /// How much the contents should be scaled into the x direction.
     pub x_scale: f32,
     /// How much the contents should be scaled into the y direction.
@@ -97,9 +142,98 @@ pub struct RenderSettings {
     /// The background color. Determines the color of the base
     /// rectangle during rendering to a pixmap.
     pub bg_color: AlphaColor<Srgb>,
+    /// An additional clip path in page coordinates (i.e. in the same coordinate system
+    /// as the crop box), which is applied in addition to the crop box of the page.
+    ///
+    /// This allows you to only render the contents of a specific region of the page.
+    pub extra_clip: Option<&'a BezPath>,
+    /// A point in time after which rendering should be cancelled.
+    ///
+    /// Once the deadline has passed, interpreting the page is stopped and the
+    /// contents that have been processed until then are rasterized, meaning that
+    /// the returned pixmap may be incomplete. Use [`render_with_status`] to find out
+    /// whether this happened. Note that the deadline is checked cooperatively, so
+    /// rendering can take a bit longer than the deadline allows for.
+    pub deadline: Option<Instant>,
+    /// A flag that cancels rendering once it is set.
+    ///
+    /// This behaves like [`RenderSettings::deadline`], but allows callers that manage
+    /// their own timers (or want to cancel rendering for other reasons) to do so from
+    /// another thread. Both can be combined, in which case rendering is cancelled as
+    /// soon as either of them triggers.
+    pub cancel: Option<&'a Arc<AtomicBool>>,
+    /// How images should be downsampled in case they are drawn at a smaller
+    /// size than their native resolution.
+    pub image_resampling: ImageResampling,
+    /// The anti-aliasing mode.
+    pub antialias: AntialiasMode,
+    /// Whether stem darkening should be applied to text.
+    ///
+    /// Since glyphs are rendered without hinting, text at small sizes can look
+    /// noticeably lighter than in other viewers. Stem darkening compensates for
+    /// this by slightly emboldening glyphs depending on the width of their stems
+    /// in pixels, which improves legibility at small sizes. Glyphs at larger sizes
+    /// are not affected.
+    pub stem_darkening: bool,
+    /// Whether small glyphs that are filled with a solid color should be drawn
+    /// from a cache of rasterized bitmaps.
+    ///
+    /// This speeds up rendering text-heavy pages considerably, since each glyph
+    /// only needs to be rasterized once per size and sub-pixel position. However,
+    /// glyphs are then positioned with a precision of a quarter of a pixel, so the
+    /// output can differ slightly from filling the glyph outlines directly. The
+    /// cache is stored in the [`RenderCache`] and shared between render calls.
+    pub glyph_cache: bool,
+    /// A callback that is invoked whenever a feature that isn't supported is
+    /// encountered and skipped while rendering the page.
+    ///
+    /// The callback can be invoked multiple times during a single render call,
+    /// including multiple times for the same feature. It is also invoked in addition
+    /// to the warning sink of the interpreter settings.
+    pub on_unsupported: Option<&'a UnsupportedFeatureFn>,
+    /// The pixel format of the rendered page.
+    ///
+    /// This is only taken into account by [`render_with_format`], the other
+    /// render functions always return an RGBA pixmap.
+    pub output_format: OutputFormat,
+    /// Whether the color channels of RGBA output should be premultiplied with
+    /// the alpha channel.
+    ///
+    /// Like [`RenderSettings::output_format`], this is only taken into account by
+    /// [`render_with_format`], since a [`Pixmap`] always stores premultiplied alpha.
+    /// It has no effect for grayscale output.
+    pub alpha_mode: AlphaMode,
+    /// Whether overprinting should be simulated.
+    ///
+    /// If enabled, `DeviceCMYK`, Separation and `DeviceN` colors that are painted
+    /// with overprinting enabled (see the `OP`, `op` and `OPM` entries of graphics
+    /// state parameter dictionaries) don't knock out colorants of the backdrop that
+    /// they don't paint. Since the page is rendered in RGB, this is only an
+    /// approximation of the result on a printing device.
+    pub simulate_overprint: bool,
+    /// An additional rotation that is applied on top of the rotation of the page.
+    ///
+    /// This is useful for correcting pages whose `/Rotate` entry is wrong, for
+    /// example scans that were tagged with the wrong orientation. For rotations by
+    /// 90 or 270 degrees, the width and height of the rendered page are swapped.
+    pub extra_rotation: Rotation,
+    /// The maximum number of bytes that may be used for the pixmaps allocated while
+    /// rendering the page.
+    ///
+    /// This is a soft limit that is meant for rendering untrusted PDFs in constrained
+    /// environments. It takes into account the pixmap of the page itself as well as
+    /// the intermediate pixmaps that are needed for soft masks, image masks and tiling
+    /// patterns, but not the memory used for decoding images or fonts.
+    ///
+    /// If the page itself doesn't fit into the budget, nothing is rendered and
+    /// [`RenderStatus::OverBudget`] is returned. If an intermediate pixmap doesn't
+    /// fit, rendering is degraded instead: Tiling patterns are rendered at a lower
+    /// resolution, and contents that need a mask are left out. If this is set to
+    /// `None`, no limit is applied.
+    pub max_pixmap_bytes: Option<usize>,
 }
 
-impl Default for RenderSettings {
+impl Default for RenderSettings<'_> {
     fn default() -> Self {
         Self {
             x_scale: 1.0,
@@ -107,48 +241,695 @@ impl Default for RenderSettings {
             width: None,
             height: None,
             bg_color: TRANSPARENT,
+            extra_clip: None,
+            deadline: None,
+            cancel: None,
+            image_resampling: ImageResampling::default(),
+            antialias: AntialiasMode::default(),
+            stem_darkening: false,
+            glyph_cache: false,
+            on_unsupported: None,
+            output_format: OutputFormat::default(),
+            alpha_mode: AlphaMode::default(),
+            simulate_overprint: false,
+            extra_rotation: Rotation::None,
+            max_pixmap_bytes: None,
         }
     }
 }
 
+impl RenderSettings<'_> {
+    /// Create render settings for rendering the page at the given resolution in
+    /// dots per inch, where a resolution of 72 DPI corresponds to a scale of 1.
+    ///
+    /// The page is rendered with the given additional rotation (see
+    /// [`RenderSettings::extra_rotation`]). The dimensions of the pixmap are the
+    /// render dimensions of the rotated page (see [`Page::render_dimensions_with_rotation`])
+    /// at that resolution, rounded to the nearest pixel, and the page is scaled so
+    /// that it covers the whole pixmap.
+    ///
+    /// If the dimensions don't fit into a `u16`, they are left unset, in which case
+    /// rendering fails with [`RenderStatus::TooLarge`].
+    pub fn with_dpi(page: &Page<'_>, dpi: f32, extra_rotation: Rotation) -> Self {
+        let scale = dpi / 72.0;
+        let (width, height) = page.render_dimensions_with_rotation(extra_rotation);
+
+        match (
+            pixel_dimension(width * scale),
+            pixel_dimension(height * scale),
+        ) {
+            (Some(pix_width), Some(pix_height)) => {
+                Self::with_pixel_dimensions(page, pix_width, pix_height, extra_rotation)
+            }
+            _ => Self {
+                x_scale: scale,
+                y_scale: scale,
+                extra_rotation,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Create render settings for rendering the page as large as possible while
+    /// fitting within the given dimensions and preserving its aspect ratio.
+    ///
+    /// One side of the pixmap has exactly the given size, while the other one is
+    /// rounded to the nearest pixel. Like for [`RenderSettings::with_dpi`], the page
+    /// is rendered with the given additional rotation.
+    pub fn fit_within(
+        page: &Page<'_>,
+        max_width: u16,
+        max_height: u16,
+        extra_rotation: Rotation,
+    ) -> Self {
+        let (width, height) = page.render_dimensions_with_rotation(extra_rotation);
+        let scale = (max_width as f32 / width).min(max_height as f32 / height);
+        let fit = |dimension: f32, max: u16| {
+            // The dimension can't exceed the maximum, so it always fits into a `u16`.
+            pixel_dimension(dimension * scale).map_or(max, |d| d.min(max))
+        };
+
+        Self::with_pixel_dimensions(
+            page,
+            fit(width, max_width),
+            fit(height, max_height),
+            extra_rotation,
+        )
+    }
+
+    fn with_pixel_dimensions(
+        page: &Page<'_>,
+        pix_width: u16,
+        pix_height: u16,
+        extra_rotation: Rotation,
+    ) -> Self {
+        let (width, height) = page.render_dimensions_with_rotation(extra_rotation);
+
+        Self {
+            x_scale: pix_width as f32 / width,
+            y_scale: pix_height as f32 / height,
+            width: Some(pix_width),
+            height: Some(pix_height),
+            extra_rotation,
+            ..Default::default()
+        }
+    }
+}
+
+/// Round the given dimension to the nearest number of pixels, which is at least 1.
+fn pixel_dimension(dimension: f32) -> Option<u16> {
+    let dimension = dimension.round().max(1.0);
+
+    (dimension <= u16::MAX as f32).then_some(dimension as u16)
+}
+
+/// A filter for downsampling images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResampleFilter {
+    /// A bilinear filter.
+    Bilinear,
+    /// A Catmull-Rom (bicubic) filter.
+    CatmullRom,
+    /// A Lanczos filter with a window of 3.
+    Lanczos3,
+    /// A box filter, i.e. averaging over the area that is covered by each
+    /// output pixel.
+    Box,
+}
+
+/// Settings for downsampling images that are drawn at a smaller size than their
+/// native resolution.
+///
+/// Such images are downsampled once before being drawn, which is much faster
+/// and leads to better results than sampling the full image while drawing.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageResampling {
+    /// The filter that is used for downsampling images.
+    pub filter: ResampleFilter,
+    /// The filter that is used for downsampling images by a factor larger than
+    /// [`ImageResampling::large_downscale_threshold`].
+    ///
+    /// Filters with a fixed support like [`ResampleFilter::CatmullRom`] are prone
+    /// to aliasing in this case, which is why this defaults to [`ResampleFilter::Box`].
+    pub large_downscale_filter: ResampleFilter,
+    /// The downscale factor from which on [`ImageResampling::large_downscale_filter`]
+    /// is used.
+    pub large_downscale_threshold: f32,
+    /// Images that are scaled by a factor between `1.0 - skip_threshold` and 1.0
+    /// are not downsampled beforehand, since the sampling while drawing yields
+    /// almost the same result in this case.
+    pub skip_threshold: f32,
+}
+
+impl Default for ImageResampling {
+    fn default() -> Self {
+        Self {
+            filter: ResampleFilter::CatmullRom,
+            large_downscale_filter: ResampleFilter::Box,
+            large_downscale_threshold: 4.0,
+            skip_threshold: 0.05,
+        }
+    }
+}
+
+/// The anti-aliasing mode used for rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AntialiasMode {
+    /// Don't apply any anti-aliasing.
+    ///
+    /// A pixel is painted if at least half of it is covered, so all edges are
+    /// either fully black or fully white when drawing black on white. This can,
+    /// for example, be useful when preprocessing pages for OCR.
+    None,
+    /// Anti-alias the edges of paths and text.
+    ///
+    /// Images are always drawn with hard edges, since anti-aliasing them leads
+    /// to faint seams between abutting images that aren't aligned to the pixel
+    /// grid.
+    #[default]
+    Fast,
+    /// Anti-alias the edges of paths and text, and additionally render the page
+    /// at twice the resolution in each direction and average each block of 2x2
+    /// pixels (supersampling).
+    ///
+    /// This reduces artifacts that the anti-aliasing of single edges can't avoid,
+    /// such as faint seams between abutting shapes, but takes roughly four times
+    /// as much time and memory as [`AntialiasMode::Fast`].
+    High,
+}
+
+impl AntialiasMode {
+    pub(crate) fn aliasing_threshold(self) -> Option<u8> {
+        match self {
+            Self::None => Some(128),
+            Self::Fast | Self::High => None,
+        }
+    }
+
+    /// The factor by which the page is rendered at a higher resolution in each direction.
+    pub(crate) fn supersampling_factor(self) -> u16 {
+        match self {
+            Self::None | Self::Fast => 1,
+            Self::High => 2,
+        }
+    }
+}
+
+/// The pixel format of a rendered page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// 8-bit RGBA with premultiplied alpha.
+    #[default]
+    Rgba8,
+    /// 8-bit grayscale, based on the luminance of the colors.
+    ///
+    /// This is for example useful for OCR. Since there is no alpha channel, you
+    /// usually want to set an opaque [`RenderSettings::bg_color`] as well, as
+    /// transparent areas become black otherwise.
+    ///
+    /// Note that the page is still rasterized in RGBA internally and converted
+    /// afterwards, so this does not reduce the peak memory usage while rendering.
+    Gray8,
+}
+
+/// How the alpha channel of a rendered page is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+    /// The color channels are premultiplied with the alpha channel.
+    #[default]
+    Premultiplied,
+    /// The color channels are stored independently of the alpha channel.
+    ///
+    /// Since the page is rendered with premultiplied alpha, the colors of pixels
+    /// with a low opacity lose some precision during the conversion.
+    Straight,
+}
+
+/// A rendered page in the pixel format requested via [`RenderSettings::output_format`]
+/// and [`RenderSettings::alpha_mode`].
+#[derive(Debug, Clone)]
+pub enum RenderedPixmap {
+    /// An RGBA pixmap with premultiplied alpha.
+    Rgba8(Pixmap),
+    /// An RGBA pixmap with straight alpha.
+    StraightRgba8(StraightPixmap),
+    /// A grayscale pixmap.
+    Gray8(GrayPixmap),
+}
+
+/// Whether a page was rendered completely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStatus {
+    /// All contents of the page were rendered.
+    Complete,
+    /// Rendering was cancelled because the deadline was exceeded,
+    /// so the pixmap only contains part of the contents of the page.
+    TimedOut,
+    /// Nothing was rendered because the page is too large to fit into a pixmap
+    /// at the requested scale, which is limited to 65535 pixels in each direction.
+    ///
+    /// This can for example happen for pages that use a large `UserUnit`. In this
+    /// case, a smaller scale or an explicit width and height need to be set in the
+    /// [`RenderSettings`].
+    TooLarge,
+    /// Nothing was rendered because the pixmap of the page would exceed the
+    /// budget set via [`RenderSettings::max_pixmap_bytes`] at the requested scale.
+    OverBudget,
+}
+
+/// The result of rendering a page with [`render_with_outcome`].
+#[derive(Debug, Clone)]
+pub struct RenderOutcome {
+    /// The rendered page.
+    ///
+    /// If rendering was cancelled, this contains the contents that were processed
+    /// until then. Nested transparency groups and clip paths are still closed
+    /// properly, so the result is what the page would look like if its content
+    /// streams ended at that point.
+    pub pixmap: Pixmap,
+    /// Whether all contents of the page were rendered.
+    ///
+    /// This is `false` if the deadline was exceeded or the cancellation flag was set,
+    /// as well as if the page is too large to be rendered (see [`RenderStatus::TooLarge`]
+    /// and [`RenderStatus::OverBudget`]).
+    pub completed: bool,
+    /// The number of content stream operators of the page (including those of form
+    /// `XObjects` and annotations) that were executed.
+    pub ops_executed: u64,
+}
+
 /// Render the page with the given settings to a pixmap.
+///
+/// If a deadline was set (either via [`RenderSettings::deadline`] or
+/// [`InterpreterSettings::deadline`]) and has been exceeded, the returned pixmap
+/// may be incomplete. Use [`render_with_status`] if you need to know whether
+/// this happened.
+///
+/// The pixmap always contains RGBA data with premultiplied alpha, so
+/// [`RenderSettings::output_format`] and [`RenderSettings::alpha_mode`] are
+/// ignored. Use [`render_with_format`] to take them into account.
 pub fn render<'a>(
     page: &'a Page<'a>,
     cache: &RenderCache<'a>,
     interpreter_settings: &InterpreterSettings,
-    render_settings: &RenderSettings,
+    render_settings: &RenderSettings<'_>,
 ) -> Pixmap {
-    let (x_scale, y_scale) = (render_settings.x_scale, render_settings.y_scale);
-    let (width, height) = page.render_dimensions();
-    let (scaled_width, scaled_height) = ((width * x_scale) as f64, (height * y_scale) as f64);
-    let initial_transform = Affine::scale_non_uniform(x_scale as f64, y_scale as f64)
-        * page.initial_transform(true).to_kurbo();
+    render_with_status(page, cache, interpreter_settings, render_settings).0
+}
+
+/// Render the page with the given settings to a pixmap, and report whether
+/// the page was rendered completely.
+///
+/// Like [`render`], this ignores [`RenderSettings::output_format`] and
+/// [`RenderSettings::alpha_mode`].
+pub fn render_with_status<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+) -> (Pixmap, RenderStatus) {
+    let mut pixmap = Pixmap::new(0, 0);
+    let status = render_into(
+        page,
+        cache,
+        interpreter_settings,
+        render_settings,
+        &mut pixmap,
+    );
+
+    (pixmap, status)
+}
+
+/// Render the page with the given settings into an existing pixmap, and report
+/// whether the page was rendered completely.
+///
+/// The pixmap is resized to the dimensions of the rendered page, reusing its
+/// buffer if it is large enough. This avoids allocating a new buffer for each
+/// render, for example when alternating between two pixmaps, one of which is
+/// rendered into on a worker thread while the other one is uploaded to a texture.
+///
+/// If the page isn't rendered at all (see [`RenderStatus::TooLarge`] and
+/// [`RenderStatus::OverBudget`]), the pixmap is left empty, but keeps its buffer.
+pub fn render_into<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+    pixmap: &mut Pixmap,
+) -> RenderStatus {
+    render_into_impl(page, cache, interpreter_settings, render_settings, pixmap).0
+}
+
+/// Render the page with the given settings to a pixmap, and report whether
+/// the page was rendered completely and how many operators were executed.
+///
+/// This is mostly useful in combination with [`RenderSettings::deadline`] or
+/// [`RenderSettings::cancel`], for example to bound the time spent on each page
+/// when generating thumbnails on a server, while still getting a best-effort result.
+pub fn render_with_outcome<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+) -> RenderOutcome {
+    let mut pixmap = Pixmap::new(0, 0);
+    let (status, ops_executed) = render_into_impl(
+        page,
+        cache,
+        interpreter_settings,
+        render_settings,
+        &mut pixmap,
+    );
 
-    let (pix_width, pix_height) = (
-        render_settings.width.unwrap_or(scaled_width.floor() as u16),
-        render_settings
-            .height
-            .unwrap_or(scaled_height.floor() as u16),
+    RenderOutcome {
+        pixmap,
+        completed: status == RenderStatus::Complete,
+        ops_executed,
+    }
+}
+
+fn render_into_impl<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+    pixmap: &mut Pixmap,
+) -> (RenderStatus, u64) {
+    let interpreter_settings =
+        derive_interpreter_settings(page, interpreter_settings, render_settings);
+    let deadline = interpreter_settings.deadline.clone();
+    let mut ops_executed = 0;
+
+    let status = render_impl_into(
+        page,
+        cache,
+        render_settings,
+        |device, initial_transform, bbox| {
+            let mut state = Context::new(
+                initial_transform,
+                bbox,
+                &cache.interpreter_cache,
+                page.xref(),
+                interpreter_settings,
+            );
+
+            device.config.deadline = deadline.clone();
+            interpret_page(page, &mut state, device);
+            ops_executed = state.ops_executed();
+        },
+        pixmap,
     );
+
+    let status = if status == RenderStatus::Complete && deadline.is_some_and(|d| d.exceeded()) {
+        RenderStatus::TimedOut
+    } else {
+        status
+    };
+
+    (status, ops_executed)
+}
+
+/// Render the page with the given settings to a pixmap in the format specified by
+/// [`RenderSettings::output_format`] and [`RenderSettings::alpha_mode`], and report
+/// whether the page was rendered completely.
+pub fn render_with_format<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+) -> (RenderedPixmap, RenderStatus) {
+    let (pixmap, status) = render_with_status(page, cache, interpreter_settings, render_settings);
+
+    let pixmap = match (render_settings.output_format, render_settings.alpha_mode) {
+        (OutputFormat::Rgba8, AlphaMode::Premultiplied) => RenderedPixmap::Rgba8(pixmap),
+        (OutputFormat::Rgba8, AlphaMode::Straight) => {
+            RenderedPixmap::StraightRgba8(StraightPixmap::from_premultiplied(pixmap))
+        }
+        (OutputFormat::Gray8, _) => RenderedPixmap::Gray8(GrayPixmap::from_rgba(&pixmap)),
+    };
+
+    (pixmap, status)
+}
+
+/// Return a thumbnail of the page.
+///
+/// If the page has an embedded thumbnail image, it is decoded and returned at
+/// its own size. Otherwise, the page is rendered on a white background at a
+/// scale such that its larger side is `max_dimension` pixels long.
+pub fn thumbnail_or_render<'a>(
+    page: &'a Page<'a>,
+    max_dimension: u16,
+    interpreter_settings: &InterpreterSettings,
+) -> Pixmap {
+    if let Some(pixmap) = decode_thumbnail(page, interpreter_settings).and_then(thumbnail_pixmap) {
+        return pixmap;
+    }
+
+    let (width, height) = page.render_dimensions();
+    let scale = max_dimension as f32 / width.max(height).max(1.0);
+    let render_settings = RenderSettings {
+        x_scale: scale,
+        y_scale: scale,
+        bg_color: WHITE,
+        ..Default::default()
+    };
+
+    render(
+        page,
+        &RenderCache::new(),
+        interpreter_settings,
+        &render_settings,
+    )
+}
+
+fn thumbnail_pixmap(image: ImageData) -> Option<Pixmap> {
+    let width = u16::try_from(image.width()).ok()?;
+    let height = u16::try_from(image.height()).ok()?;
+
+    let data = match image {
+        ImageData::Rgb(rgb) => rgb
+            .data
+            .chunks_exact(3)
+            .flat_map(|c| [c[0], c[1], c[2], 255])
+            .collect(),
+        ImageData::Luma(luma) => luma.data.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+    };
+
+    Pixmap::from_vec(data, width, height)
+}
+
+/// Interpret the page once and record all drawing operations into a display list.
+///
+/// The display list can then be rendered many times (for example at different
+/// zoom levels) via [`render_display_list`], without interpreting the page again.
+pub fn record<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    interpreter_settings: &InterpreterSettings,
+) -> DisplayList<'a> {
+    let (width, height) = page.render_dimensions();
     let mut state = Context::new(
-        initial_transform,
-        Rect::new(0.0, 0.0, pix_width as f64, pix_height as f64),
+        page.initial_transform(true).to_kurbo(),
+        Rect::new(0.0, 0.0, width.floor() as f64, height.floor() as f64),
         &cache.interpreter_cache,
         page.xref(),
         interpreter_settings.clone(),
     );
 
+    let mut list = DisplayList::new();
+    interpret_page(page, &mut state, &mut list);
+
+    list
+}
+
+/// Render a display list that was previously recorded from the page via [`record`]
+/// with the given settings to a pixmap.
+///
+/// Just like for [`render`], nothing is drawn anymore once the deadline of the
+/// render settings has passed or its cancellation flag has been set.
+///
+/// If the page is too large to be rendered at the given scale (see
+/// [`RenderStatus::TooLarge`] and [`RenderStatus::OverBudget`]), an empty
+/// pixmap is returned.
+pub fn render_display_list<'a>(
+    page: &'a Page<'a>,
+    list: &DisplayList<'a>,
+    cache: &RenderCache<'a>,
+    render_settings: &RenderSettings<'_>,
+) -> Pixmap {
+    // The display list was recorded without the extra rotation, so undo the
+    // initial transform it was recorded with and apply the rotated one instead.
+    let transform = Affine::scale_non_uniform(
+        render_settings.x_scale as f64,
+        render_settings.y_scale as f64,
+    ) * page
+        .initial_transform_with_rotation(true, render_settings.extra_rotation)
+        .to_kurbo()
+        * page.initial_transform(true).to_kurbo().inverse();
+
+    let deadline = render_deadline(render_settings, None);
+
+    render_impl(page, cache, render_settings, |device, _, _| {
+        if let Some(deadline) = &deadline {
+            device.config.deadline = Some(deadline.clone());
+            list.replay_with_deadline(transform, deadline, device);
+        } else {
+            list.replay_with_transform(transform, device);
+        }
+    })
+}
+
+/// Apply the deadline, the cancellation flag and the unsupported feature callback of the render
+/// settings (if any) to the interpreter settings, and reset the deadline of the interpreter
+/// settings for the new render.
+pub(crate) fn derive_interpreter_settings(
+    page: &Page<'_>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+) -> InterpreterSettings {
+    let mut interpreter_settings = interpreter_settings.clone();
+
+    // The deadline might have been exceeded during a previous render.
+    if let Some(deadline) = &interpreter_settings.deadline {
+        deadline.reset();
+    }
+
+    interpreter_settings.deadline =
+        render_deadline(render_settings, interpreter_settings.deadline.take());
+
+    if let Some(on_unsupported) = render_settings.on_unsupported.cloned() {
+        let warning_sink = interpreter_settings.warning_sink.clone();
+        let page_index = page.index();
+
+        interpreter_settings.warning_sink = Arc::new(move |warning| {
+            if let InterpreterWarning::UnsupportedFeature(feature) = warning {
+                on_unsupported(page_index, &feature);
+            }
+
+            warning_sink(warning);
+        });
+    }
+
+    interpreter_settings
+}
+
+/// Combine the deadline and the cancellation flag of the render settings (if any) with
+/// the given deadline.
+fn render_deadline(
+    render_settings: &RenderSettings<'_>,
+    mut deadline: Option<Deadline>,
+) -> Option<Deadline> {
+    if let Some(instant) = render_settings.deadline {
+        deadline = Some(Deadline::new(instant));
+    }
+
+    if let Some(cancel) = render_settings.cancel.cloned() {
+        deadline = Some(match deadline {
+            Some(deadline) => deadline.with_flag(cancel),
+            None => Deadline::from_flag(cancel),
+        });
+    }
+
+    deadline
+}
+
+pub(crate) fn render_impl<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    render_settings: &RenderSettings<'_>,
+    draw: impl FnOnce(&mut Renderer, Affine, Rect),
+) -> Pixmap {
+    let mut pixmap = Pixmap::new(0, 0);
+    render_impl_into(page, cache, render_settings, draw, &mut pixmap);
+
+    pixmap
+}
+
+pub(crate) fn render_impl_into<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    render_settings: &RenderSettings<'_>,
+    draw: impl FnOnce(&mut Renderer, Affine, Rect),
+    target: &mut Pixmap,
+) -> RenderStatus {
+    let (x_scale, y_scale) = (render_settings.x_scale, render_settings.y_scale);
+    let extra_rotation = render_settings.extra_rotation;
+    let (width, height) = page.render_dimensions_with_rotation(extra_rotation);
+    let (scaled_width, scaled_height) = ((width * x_scale) as f64, (height * y_scale) as f64);
+    let initial_transform = Affine::scale_non_uniform(x_scale as f64, y_scale as f64)
+        * page
+            .initial_transform_with_rotation(true, extra_rotation)
+            .to_kurbo();
+
+    // Don't silently clamp the dimensions, since the result would be cut off.
+    let pixmap_dimension = |dimension: f64| {
+        let dimension = dimension.floor();
+        (dimension <= u16::MAX as f64).then_some(dimension as u16)
+    };
+
+    let Some((pix_width, pix_height)) = render_settings
+        .width
+        .or_else(|| pixmap_dimension(scaled_width))
+        .zip(
+            render_settings
+                .height
+                .or_else(|| pixmap_dimension(scaled_height)),
+        )
+    else {
+        clear_pixmap(target);
+
+        return RenderStatus::TooLarge;
+    };
+
+    // When supersampling, the page is rendered into a larger pixmap that is
+    // downsampled to the target afterwards.
+    let factor = render_settings.antialias.supersampling_factor();
+    let (Some(device_width), Some(device_height)) = (
+        pix_width.checked_mul(factor),
+        pix_height.checked_mul(factor),
+    ) else {
+        clear_pixmap(target);
+
+        return RenderStatus::TooLarge;
+    };
+    let initial_transform = Affine::scale(factor as f64) * initial_transform;
+
+    let budget = PixmapBudget::new(render_settings.max_pixmap_bytes);
+
+    if !budget.reserve(pix_width, pix_height)
+        || (factor > 1 && !budget.reserve(device_width, device_height))
+    {
+        clear_pixmap(target);
+
+        return RenderStatus::OverBudget;
+    }
+
     let vc_settings = vello_cpu::RenderSettings {
         level: Level::new(),
         num_threads: 0,
     };
 
-    let mut device = Renderer::new(pix_width, pix_height, vc_settings, cache);
+    let config = RendererConfig {
+        outline_cache: cache.outline_cache.clone(),
+        glyph_cache: render_settings
+            .glyph_cache
+            .then(|| cache.glyph_cache.clone()),
+        image_resampling: render_settings.image_resampling,
+        antialias: render_settings.antialias,
+        stem_darkening: render_settings.stem_darkening,
+        simulate_overprint: render_settings.simulate_overprint,
+        deadline: None,
+        budget,
+    };
+    let mut device = Renderer::new(device_width, device_height, vc_settings, config);
 
     device.ctx.set_paint(render_settings.bg_color);
-    device
-        .ctx
-        .fill_rect(&Rect::new(0.0, 0.0, pix_width as f64, pix_height as f64));
+    device.ctx.fill_rect(&Rect::new(
+        0.0,
+        0.0,
+        device_width as f64,
+        device_height as f64,
+    ));
     let mut clip_path = page.intersected_crop_box().to_kurbo().to_path(0.1);
     clip_path.apply_affine(initial_transform);
     device.push_clip_path(&ClipPath {
@@ -156,18 +937,91 @@ pub fn render<'a>(
         fill: FillRule::NonZero,
     });
 
+    if let Some(extra_clip) = render_settings.extra_clip {
+        device.push_clip_path(&ClipPath {
+            path: initial_transform * extra_clip.clone(),
+            fill: FillRule::NonZero,
+        });
+    }
+
     device.push_transparency_group(1.0, None, BlendMode::Normal);
-    interpret_page(page, &mut state, &mut device);
+    draw(
+        &mut device,
+        initial_transform,
+        Rect::new(0.0, 0.0, device_width as f64, device_height as f64),
+    );
 
     device.pop_transparency_group();
 
+    if render_settings.extra_clip.is_some() {
+        device.pop_clip();
+    }
+
     device.pop_clip();
 
-    let mut pixmap = Pixmap::new(pix_width, pix_height);
+    // Reuse the buffer of the target, but make sure that no stale contents remain.
+    let mut buffer = std::mem::replace(target, Pixmap::new(0, 0)).take();
+    buffer.clear();
+    buffer.resize(
+        pix_width as usize * pix_height as usize,
+        PremulRgba8::from_u32(0),
+    );
+
+    let mut pixmap = Pixmap::from_parts_with_opacity(buffer, pix_width, pix_height, true);
     let mut resources = vello_cpu::Resources::default();
-    device.ctx.render(&mut pixmap, &mut resources);
 
-    pixmap
+    if factor > 1 {
+        let mut supersampled = Pixmap::new(device_width, device_height);
+        device.ctx.render(&mut supersampled, &mut resources);
+        downsample(&supersampled, &mut pixmap, factor);
+    } else {
+        device.ctx.render(&mut pixmap, &mut resources);
+    }
+
+    *target = pixmap;
+
+    RenderStatus::Complete
+}
+
+/// Downsample a pixmap that was rendered at `factor` times the resolution of the
+/// target in each direction by averaging each block of `factor`x`factor` pixels.
+fn downsample(source: &Pixmap, target: &mut Pixmap, factor: u16) {
+    let factor = factor as usize;
+    let source_width = source.width() as usize;
+    let target_width = target.width() as usize;
+    let num_samples = (factor * factor) as u32;
+    let source_data = source.data();
+
+    if target_width == 0 {
+        return;
+    }
+
+    for (y, row) in target.data_mut().chunks_exact_mut(target_width).enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let mut sum = [0_u32; 4];
+
+            for sy in y * factor..(y + 1) * factor {
+                for sample in &source_data[sy * source_width + x * factor..][..factor] {
+                    sum[0] += sample.r as u32;
+                    sum[1] += sample.g as u32;
+                    sum[2] += sample.b as u32;
+                    sum[3] += sample.a as u32;
+                }
+            }
+
+            let [r, g, b, a] = sum.map(|c| ((c + num_samples / 2) / num_samples) as u8);
+            *pixel = PremulRgba8 { r, g, b, a };
+        }
+    }
+}
+
+/// Turn the pixmap into an empty one, while keeping its buffer around so that it
+/// can be reused for the next page.
+fn clear_pixmap(pixmap: &mut Pixmap) {
+    let mut buffer = std::mem::replace(pixmap, Pixmap::new(0, 0)).take();
+    buffer.clear();
+
+    *pixmap = Pixmap::from_parts_with_opacity(buffer, 0, 0, true);
 }
 
 // Just a convenience method for testing.
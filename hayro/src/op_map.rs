@@ -0,0 +1,331 @@
+//! Mapping the regions of a rendered page back to the operators that painted them.
+
+use crate::renderer::{Renderer, max_factor};
+use crate::{RenderCache, RenderSettings, derive_interpreter_settings, render_impl};
+use hayro_interpret::font::Glyph;
+use hayro_interpret::hayro_syntax::content::SourceLocation;
+use hayro_interpret::hayro_syntax::page::Page;
+use hayro_interpret::{
+    BlendMode, ClipPath, Context, Device, DrawMode, DrawProps, Image, ImageDrawProps,
+    InterpreterSettings, OpContext, SoftMask, interpret_page,
+};
+use kurbo::{Affine, BezPath, Point, Rect, Shape};
+use vello_cpu::Pixmap;
+
+/// A region of a rendered page that was painted by a single operator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpRegion {
+    /// The index of the operator, see [`OpContext::index`].
+    pub index: usize,
+    /// The location of the operator in its content stream.
+    pub location: SourceLocation,
+    /// The indices of the `Do` operators through which the form `XObject` containing
+    /// the operator was invoked, starting with the one in the page content stream.
+    pub xobject_path: Vec<usize>,
+    /// The index of the annotation the operator belongs to, if any.
+    pub annotation: Option<usize>,
+    /// The bounding box of the painted region, in pixels.
+    pub bounds: Rect,
+}
+
+/// A map from the regions of a rendered page to the operators that painted them.
+#[derive(Debug, Clone, Default)]
+pub struct OpMap {
+    regions: Vec<OpRegion>,
+}
+
+impl OpMap {
+    /// Return all regions, in the order in which they were painted.
+    pub fn regions(&self) -> &[OpRegion] {
+        &self.regions
+    }
+
+    /// Return the regions that contain the given pixel, starting with the one
+    /// that was painted last (i.e. the topmost one).
+    pub fn ops_at(&self, x: f64, y: f64) -> impl Iterator<Item = &OpRegion> {
+        let point = Point::new(x, y);
+
+        self.regions
+            .iter()
+            .rev()
+            .filter(move |r| r.bounds.contains(point))
+    }
+}
+
+/// Render the page with the given settings to a pixmap, and record which
+/// regions of the pixmap were painted by which operator.
+///
+/// Note that the regions are based on the bounding boxes of the painted
+/// shapes, so they can be larger than the area that was actually painted.
+pub fn render_with_op_map<'a>(
+    page: &'a Page<'a>,
+    cache: &RenderCache<'a>,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings<'_>,
+) -> (Pixmap, OpMap) {
+    let interpreter_settings =
+        derive_interpreter_settings(page, interpreter_settings, render_settings);
+    let mut regions = vec![];
+
+    let pixmap = render_impl(
+        page,
+        cache,
+        render_settings,
+        |renderer, initial_transform, bbox| {
+            let mut state = Context::new(
+                initial_transform,
+                bbox,
+                &cache.interpreter_cache,
+                page.xref(),
+                interpreter_settings,
+            );
+
+            renderer.config.deadline = interpreter_settings.deadline.clone();
+            let mut device = OpMapDevice::new(renderer, bbox);
+            interpret_page(page, &mut state, &mut device);
+            regions = device.regions;
+        },
+    );
+
+    // The regions were recorded in the coordinates of the supersampled pixmap.
+    let factor = render_settings.antialias.supersampling_factor();
+
+    if factor > 1 {
+        for region in &mut regions {
+            region.bounds = Affine::scale(1.0 / factor as f64).transform_rect_bbox(region.bounds);
+        }
+    }
+
+    (pixmap, OpMap { regions })
+}
+
+/// A device that forwards all drawing calls to the renderer and records the
+/// bounding box of everything that is drawn.
+struct OpMapDevice<'r> {
+    renderer: &'r mut Renderer,
+    current: OpRegion,
+    // Whether something has been drawn since the current operator has been set.
+    has_drawn: bool,
+    clips: Vec<Rect>,
+    regions: Vec<OpRegion>,
+}
+
+impl<'r> OpMapDevice<'r> {
+    fn new(renderer: &'r mut Renderer, bbox: Rect) -> Self {
+        Self {
+            renderer,
+            current: OpRegion {
+                index: 0,
+                location: SourceLocation::default(),
+                xobject_path: vec![],
+                annotation: None,
+                bounds: Rect::ZERO,
+            },
+            has_drawn: false,
+            clips: vec![bbox],
+            regions: vec![],
+        }
+    }
+
+    fn record(&mut self, bounds: Option<Rect>) {
+        let Some(bounds) = bounds else {
+            return;
+        };
+
+        let bounds = bounds.intersect(*self.clips.last().unwrap());
+
+        if bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+            return;
+        }
+
+        // Operators like `Tj` usually result in multiple drawing calls, which
+        // are merged into a single region.
+        if self.has_drawn
+            && let Some(last) = self.regions.last_mut()
+        {
+            last.bounds = last.bounds.union(bounds);
+        } else {
+            self.regions.push(OpRegion {
+                bounds,
+                ..self.current.clone()
+            });
+            self.has_drawn = true;
+        }
+    }
+}
+
+impl<'a> Device<'a> for OpMapDevice<'_> {
+    fn draw_path(&mut self, path: &BezPath, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        self.record(path_bounds(path, props.transform, draw_mode));
+        self.renderer.draw_path(path, props, draw_mode);
+    }
+
+    fn push_clip_path(&mut self, clip_path: &ClipPath) {
+        let bounds = clip_path
+            .path
+            .bounding_box()
+            .intersect(*self.clips.last().unwrap());
+        self.clips.push(bounds);
+        self.renderer.push_clip_path(clip_path);
+    }
+
+    fn push_clip_rect(&mut self, rect: &Rect) {
+        let bounds = rect.intersect(*self.clips.last().unwrap());
+        self.clips.push(bounds);
+        self.renderer.push_clip_rect(rect);
+    }
+
+    fn push_transparency_group(
+        &mut self,
+        opacity: f32,
+        mask: Option<SoftMask<'a>>,
+        blend_mode: BlendMode,
+    ) {
+        self.renderer
+            .push_transparency_group(opacity, mask, blend_mode);
+    }
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'a>,
+        glyph_transform: Affine,
+        props: DrawProps<'a>,
+        draw_mode: &DrawMode,
+    ) {
+        self.record(glyph_bounds(glyph, glyph_transform, &props, draw_mode));
+        self.renderer
+            .draw_glyph(glyph, glyph_transform, props, draw_mode);
+    }
+
+    fn draw_image(&mut self, image: Image<'a, '_>, props: ImageDrawProps<'a>) {
+        self.record(Some(image_bounds(&image, props.transform)));
+        self.renderer.draw_image(image, props);
+    }
+
+    fn pop_clip(&mut self) {
+        // The bounding box of the pixmap always stays at the bottom.
+        if self.clips.len() > 1 {
+            self.clips.pop();
+        }
+
+        self.renderer.pop_clip();
+    }
+
+    fn pop_transparency_group(&mut self) {
+        self.renderer.pop_transparency_group();
+    }
+
+    fn draw_rect(&mut self, rect: &Rect, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        self.record(path_bounds(&rect.to_path(0.1), props.transform, draw_mode));
+        self.renderer.draw_rect(rect, props, draw_mode);
+    }
+
+    fn set_op_context(&mut self, context: &OpContext<'_>) {
+        self.current.index = context.index;
+        self.current.location = context.location;
+        self.current.xobject_path.clear();
+        self.current
+            .xobject_path
+            .extend_from_slice(context.xobject_path);
+        self.current.annotation = context.annotation;
+        self.has_drawn = false;
+    }
+}
+
+/// A device that only computes the bounding box of everything that is drawn.
+///
+/// Used for Type3 glyphs, which are drawn by interpreting a content stream.
+#[derive(Default)]
+struct BoundsDevice {
+    bounds: Option<Rect>,
+}
+
+impl BoundsDevice {
+    fn add(&mut self, bounds: Option<Rect>) {
+        self.bounds = match (self.bounds, bounds) {
+            (Some(a), Some(b)) => Some(a.union(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+impl<'a> Device<'a> for BoundsDevice {
+    fn draw_path(&mut self, path: &BezPath, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        self.add(path_bounds(path, props.transform, draw_mode));
+    }
+
+    fn push_clip_path(&mut self, _: &ClipPath) {}
+
+    fn push_transparency_group(&mut self, _: f32, _: Option<SoftMask<'a>>, _: BlendMode) {}
+
+    fn draw_glyph(
+        &mut self,
+        glyph: &Glyph<'a>,
+        glyph_transform: Affine,
+        props: DrawProps<'a>,
+        draw_mode: &DrawMode,
+    ) {
+        self.add(glyph_bounds(glyph, glyph_transform, &props, draw_mode));
+    }
+
+    fn draw_image(&mut self, image: Image<'a, '_>, props: ImageDrawProps<'a>) {
+        self.add(Some(image_bounds(&image, props.transform)));
+    }
+
+    fn pop_clip(&mut self) {}
+
+    fn pop_transparency_group(&mut self) {}
+}
+
+fn path_bounds(path: &BezPath, transform: Affine, draw_mode: &DrawMode) -> Option<Rect> {
+    let stroke_props = match draw_mode {
+        DrawMode::Fill(_) => None,
+        DrawMode::Stroke(s) | DrawMode::FillAndStroke(_, s) => Some(s),
+        DrawMode::Invisible => return None,
+    };
+
+    let bounds = (transform * path.clone()).bounding_box();
+
+    Some(match stroke_props {
+        Some(s) => {
+            // Line joins can extend further than half the line width, but this
+            // is good enough for our purposes.
+            let half_width = (s.line_width * max_factor(&transform)) as f64 / 2.0;
+            // A line width of zero denotes the thinnest line that can be rendered.
+            bounds.inflate(half_width.max(0.5), half_width.max(0.5))
+        }
+        None => bounds,
+    })
+}
+
+fn glyph_bounds<'a>(
+    glyph: &Glyph<'a>,
+    glyph_transform: Affine,
+    props: &DrawProps<'a>,
+    draw_mode: &DrawMode,
+) -> Option<Rect> {
+    match glyph {
+        Glyph::Outline(o) => {
+            path_bounds(&(glyph_transform * o.outline()), props.transform, draw_mode)
+        }
+        Glyph::Type3(s) => {
+            if matches!(draw_mode, DrawMode::Invisible) {
+                return None;
+            }
+
+            let mut device = BoundsDevice::default();
+            s.interpret(&mut device, props.transform, glyph_transform, &props.paint);
+
+            device.bounds
+        }
+    }
+}
+
+fn image_bounds(image: &Image<'_, '_>, transform: Affine) -> Rect {
+    transform.transform_rect_bbox(Rect::new(
+        0.0,
+        0.0,
+        image.width() as f64,
+        image.height() as f64,
+    ))
+}
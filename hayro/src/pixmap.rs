@@ -0,0 +1,103 @@
+use vello_cpu::Pixmap;
+use vello_cpu::color::PremulRgba8;
+
+use crate::GrayPixmap;
+
+// Pixmaps are typically rendered on a worker thread and then handed over to
+// another thread, for example for uploading them to a texture.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Pixmap>();
+    assert_send_sync::<GrayPixmap>();
+    assert_send_sync::<StraightPixmap>();
+};
+
+/// Extension methods for handing off the buffer of a [`Pixmap`] without copying it.
+///
+/// The data of a pixmap is stored in row-major order, starting with the top-left
+/// pixel. Each pixel consists of 4 bytes of RGBA data with premultiplied alpha,
+/// and rows are not padded, so [`PixmapExt::stride`] is always `width * 4`. This
+/// matches the layout expected by most GPU texture uploads (e.g. `Rgba8Unorm` in
+/// wgpu with a `bytes_per_row` of `width * 4`), so the buffer can be uploaded as is.
+pub trait PixmapExt: Sized {
+    /// Create a pixmap from a buffer of premultiplied RGBA data in the layout
+    /// described above, for example to reuse a buffer from a pool.
+    ///
+    /// Returns `None` if the length of the buffer isn't `width * height * 4`.
+    fn from_vec(data: Vec<u8>, width: u16, height: u16) -> Option<Self>;
+
+    /// Consume the pixmap and return its buffer of premultiplied RGBA data.
+    fn into_vec(self) -> Vec<u8>;
+
+    /// The number of bytes between the start of two consecutive rows.
+    fn stride(&self) -> usize;
+}
+
+impl PixmapExt for Pixmap {
+    fn from_vec(data: Vec<u8>, width: u16, height: u16) -> Option<Self> {
+        if data.len() != width as usize * height as usize * 4 {
+            return None;
+        }
+
+        // This only fails if the capacity of the buffer isn't a multiple of 4,
+        // in which case we need to copy.
+        let data = bytemuck::try_cast_vec::<u8, PremulRgba8>(data)
+            .unwrap_or_else(|(_, data)| bytemuck::pod_collect_to_vec(&data));
+
+        Some(Self::from_parts_with_opacity(data, width, height, true))
+    }
+
+    fn into_vec(self) -> Vec<u8> {
+        bytemuck::cast_vec(self.take())
+    }
+
+    fn stride(&self) -> usize {
+        self.width() as usize * 4
+    }
+}
+
+/// An 8-bit RGBA pixmap with straight alpha, i.e. whose color channels are not
+/// premultiplied with the alpha channel.
+///
+/// The data is stored in the same layout as the one of [`Pixmap`] (see
+/// [`PixmapExt`]), which is what most image libraries and compositors that
+/// don't work with premultiplied alpha expect.
+#[derive(Debug, Clone)]
+pub struct StraightPixmap {
+    width: u16,
+    height: u16,
+    data: Vec<u8>,
+}
+
+impl StraightPixmap {
+    /// Convert a pixmap with premultiplied alpha into one with straight alpha.
+    pub(crate) fn from_premultiplied(pixmap: Pixmap) -> Self {
+        let (width, height) = (pixmap.width(), pixmap.height());
+
+        Self {
+            width,
+            height,
+            data: bytemuck::cast_vec(pixmap.take_unpremultiplied()),
+        }
+    }
+
+    /// The width of the pixmap.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// The height of the pixmap.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The RGBA data of the pixmap, in row-major order.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consume the pixmap and return its RGBA data, in row-major order.
+    pub fn take(self) -> Vec<u8> {
+        self.data
+    }
+}
@@ -1,33 +1,57 @@
-use crate::{RenderCache, derive_settings};
+use crate::budget::PixmapBudget;
+use crate::glyph_cache::{GlyphBitmap, GlyphCache, GlyphPlacement};
+use crate::stem_darkening::darken;
+use crate::{AntialiasMode, ImageResampling, ResampleFilter, derive_settings};
 use hayro_interpret::encode::{EncodedShadingPattern, EncodedShadingType};
 use hayro_interpret::font::Glyph;
 use hayro_interpret::gradient::SvgGradientKind;
 use hayro_interpret::pattern::Pattern;
 use hayro_interpret::{
-    BlendMode, CacheKey, ClipPath, Device, DrawMode, DrawProps, FillRule, ImageData,
+    BlendMode, CacheKey, ClipPath, Deadline, Device, DrawMode, DrawProps, FillRule, ImageData,
     ImageDrawProps, LumaData, MaskType, Paint, RgbData, SoftMask, StrokeProps,
 };
-use kurbo::{Affine, BezPath, Point, Rect, Shape, Vec2};
+use kurbo::{Affine, BezPath, Join, Point, Rect, Shape, Vec2};
 use pic_scale::{
     ImageSize, ImageStore, ImageStoreMut, PicScaleError, Resampling, ResamplingFunction, Scaler,
 };
 use rustc_hash::FxHashMap;
+use std::borrow::Cow;
+use std::f64::consts::SQRT_2;
+use std::iter;
 use std::rc::Rc;
 use std::sync::Arc;
-use vello_cpu::color::palette::css::BLACK;
+use vello_cpu::color::palette::css::{BLACK, TRANSPARENT};
 use vello_cpu::color::{AlphaColor, DynamicColor, PremulRgba8, Srgb};
 use vello_cpu::peniko::{ColorStop, Compose, Fill, Gradient, ImageQuality, ImageSampler, Mix};
 use vello_cpu::{
     Image, ImageSource, Mask, PaintType, Pixmap, RenderContext, RenderSettings, peniko,
 };
 
+/// The settings and shared state of a renderer, which are passed on to the
+/// renderers of soft masks, pattern tiles and other intermediate pixmaps.
+#[derive(Clone)]
+pub(crate) struct RendererConfig {
+    pub(crate) outline_cache: Rc<std::cell::RefCell<FxHashMap<u128, Rc<BezPath>>>>,
+    // The cache for the bitmaps of small glyphs, or `None` if glyphs should
+    // always be filled as paths.
+    pub(crate) glyph_cache: Option<Rc<std::cell::RefCell<GlyphCache>>>,
+    pub(crate) image_resampling: ImageResampling,
+    pub(crate) antialias: AntialiasMode,
+    pub(crate) stem_darkening: bool,
+    pub(crate) simulate_overprint: bool,
+    pub(crate) deadline: Option<Deadline>,
+    pub(crate) budget: PixmapBudget,
+}
+
 pub(crate) struct Renderer {
     pub(crate) ctx: RenderContext,
+    pub(crate) config: RendererConfig,
     pub(crate) inside_pattern: bool,
     pub(crate) soft_mask_cache: FxHashMap<u128, Mask>,
-    pub(crate) outline_cache: Rc<std::cell::RefCell<FxHashMap<u128, Rc<BezPath>>>>,
     pub(crate) in_type3_glyph: bool,
-    pub(crate) scaler: Scaler,
+    // The bounding boxes of the active clip paths in device space, each one
+    // intersected with the previous one.
+    pub(crate) clip_bboxes: Vec<Rect>,
 }
 
 #[derive(Clone, Copy)]
@@ -44,14 +68,14 @@ struct SolidColorImage {
     interpolate: bool,
 }
 
-enum RenderImageData {
-    Rgb(RgbData),
-    Luma(LumaData),
+enum RenderImageData<'a> {
+    Rgb(&'a RgbData),
+    Luma(&'a LumaData),
     Solid(SolidColorImage),
 }
 
-impl From<ImageData> for RenderImageData {
-    fn from(value: ImageData) -> Self {
+impl<'a> From<&'a ImageData> for RenderImageData<'a> {
+    fn from(value: &'a ImageData) -> Self {
         match value {
             ImageData::Rgb(rgb) => Self::Rgb(rgb),
             ImageData::Luma(luma) => Self::Luma(luma),
@@ -59,13 +83,7 @@ impl From<ImageData> for RenderImageData {
     }
 }
 
-impl From<RgbData> for RenderImageData {
-    fn from(value: RgbData) -> Self {
-        Self::Rgb(value)
-    }
-}
-
-impl RenderImageData {
+impl RenderImageData<'_> {
     fn width(&self) -> u32 {
         match self {
             Self::Rgb(d) => d.width,
@@ -96,16 +114,51 @@ impl Renderer {
         width: u16,
         height: u16,
         settings: RenderSettings,
-        cache: &RenderCache<'_>,
+        config: RendererConfig,
     ) -> Self {
         Self {
-            ctx: RenderContext::new_with(width, height, settings),
+            ctx: new_render_context(width, height, settings, config.antialias),
+            config,
             inside_pattern: false,
             soft_mask_cache: FxHashMap::default(),
-            outline_cache: cache.outline_cache.clone(),
             in_type3_glyph: false,
-            scaler: Scaler::new(ResamplingFunction::CatmullRom),
+            clip_bboxes: Vec::new(),
+        }
+    }
+
+    /// Create a renderer for an intermediate pixmap of the given size, with the
+    /// same configuration as this one.
+    fn sub_renderer(&self, width: u16, height: u16) -> Self {
+        Self::new(
+            width,
+            height,
+            derive_settings(self.ctx.render_settings()),
+            self.config.clone(),
+        )
+    }
+
+    /// Return whether a drawing operation with the given bounding box in device
+    /// space is guaranteed to be invisible because it lies outside of the
+    /// current clip.
+    ///
+    /// Pages often contain many objects outside of the crop box, so checking this
+    /// first allows us to avoid flattening and rasterizing them.
+    fn is_culled(&self, bbox: Rect) -> bool {
+        // Pattern tiles are rendered into a separate pixmap that extends beyond
+        // the visible area, so we can't cull anything in there.
+        if self.inside_pattern {
+            return false;
         }
+
+        let clip_bbox = self.clip_bboxes.last().copied().unwrap_or(Rect::new(
+            0.0,
+            0.0,
+            self.ctx.width() as f64,
+            self.ctx.height() as f64,
+        ));
+
+        // Account for anti-aliasing and pixel snapping.
+        !bbox.inflate(1.0, 1.0).overlaps(clip_bbox)
     }
 
     fn set_stroke_properties(&mut self, stroke_props: &StrokeProps, is_text: bool) {
@@ -137,25 +190,27 @@ impl Renderer {
         self.ctx.set_stroke(stroke);
     }
 
-    fn draw_image_with_alpha_mask(&mut self, image_data: RenderImageData, alpha_data: LumaData) {
+    fn draw_image_with_alpha_mask(
+        &mut self,
+        image_data: RenderImageData<'_>,
+        alpha_data: &LumaData,
+    ) {
+        // Leave out the image if there is no room for rendering its mask.
+        if !self
+            .config
+            .budget
+            .reserve(self.ctx.width(), self.ctx.height())
+        {
+            return;
+        }
+
         let mask = {
             let transform = *self.ctx.transform()
                 * Affine::scale_non_uniform(
                     image_data.width() as f64 / alpha_data.width as f64,
                     image_data.height() as f64 / alpha_data.height as f64,
                 );
-            let mut renderer = Self {
-                ctx: RenderContext::new_with(
-                    self.ctx.width(),
-                    self.ctx.height(),
-                    derive_settings(self.ctx.render_settings()),
-                ),
-                inside_pattern: false,
-                soft_mask_cache: FxHashMap::default(),
-                outline_cache: self.outline_cache.clone(),
-                in_type3_glyph: false,
-                scaler: self.scaler,
-            };
+            let mut renderer = self.sub_renderer(self.ctx.width(), self.ctx.height());
             let mut mask_pix = Pixmap::new(self.ctx.width(), self.ctx.height());
             let rgb_data = ImageData::Rgb(RgbData {
                 data: vec![0; alpha_data.width as usize * alpha_data.height as usize * 3],
@@ -168,7 +223,7 @@ impl Renderer {
             // Note that there is a circle between `draw_image` and `draw_image_with_alpha_mask`,
             // but `draw_image_with_alpha_mask` is only called if the dimensions or interpolate
             // values between alpha_data and rgb_data don't match, which they do here.
-            renderer.draw_image(rgb_data, Some(alpha_data));
+            renderer.draw_image(&rgb_data, Some(alpha_data));
             renderer.ctx.flush();
             let mut resources = vello_cpu::Resources::default();
             renderer.ctx.render(&mut mask_pix, &mut resources);
@@ -182,15 +237,27 @@ impl Renderer {
 
     fn resize_image_data(
         &self,
-        data: Vec<u8>,
+        data: &[u8],
         src_width: u32,
         src_height: u32,
         new_width: u32,
         new_height: u32,
         pixel_format: ImagePixelFormat,
     ) -> Vec<u8> {
+        // Use a dedicated filter for large downscales, where the default filter is prone
+        // to aliasing.
+        let factor =
+            (src_width as f32 / new_width as f32).max(src_height as f32 / new_height as f32);
+        let filter = if factor > self.config.image_resampling.large_downscale_threshold {
+            self.config.image_resampling.large_downscale_filter
+        } else {
+            self.config.image_resampling.filter
+        };
+        let scaler = Scaler::new(convert_resample_filter(filter));
+
         match pixel_format {
-            ImagePixelFormat::Luma => self.resize_image_data_impl::<1>(
+            ImagePixelFormat::Luma => resize_image_data_impl::<1>(
+                &scaler,
                 data,
                 src_width,
                 src_height,
@@ -200,7 +267,8 @@ impl Renderer {
                     scaler.plan_planar_resampling(source_size, target_size)
                 },
             ),
-            ImagePixelFormat::Rgb => self.resize_image_data_impl::<3>(
+            ImagePixelFormat::Rgb => resize_image_data_impl::<3>(
+                &scaler,
                 data,
                 src_width,
                 src_height,
@@ -210,7 +278,8 @@ impl Renderer {
                     scaler.plan_rgb_resampling(source_size, target_size)
                 },
             ),
-            ImagePixelFormat::Rgba => self.resize_image_data_impl::<4>(
+            ImagePixelFormat::Rgba => resize_image_data_impl::<4>(
+                &scaler,
                 data,
                 src_width,
                 src_height,
@@ -223,33 +292,11 @@ impl Renderer {
         }
     }
 
-    fn resize_image_data_impl<const N: usize>(
-        &self,
-        data: Vec<u8>,
-        src_width: u32,
-        src_height: u32,
-        new_width: u32,
-        new_height: u32,
-        plan: impl FnOnce(
-            &Scaler,
-            ImageSize,
-            ImageSize,
-        ) -> Result<Arc<Resampling<u8, N>>, PicScaleError>,
-    ) -> Vec<u8> {
-        let source_size = ImageSize::new(src_width as usize, src_height as usize);
-        let target_size = ImageSize::new(new_width as usize, new_height as usize);
-        let src = ImageStore::<u8, N>::from_slice(&data, src_width as usize, src_height as usize)
-            .unwrap();
-        let mut out = vec![0; new_width as usize * new_height as usize * N];
-        let mut dst =
-            ImageStoreMut::<u8, N>::from_slice(&mut out, new_width as usize, new_height as usize)
-                .unwrap();
-        let plan = plan(&self.scaler, source_size, target_size).unwrap();
-        plan.resample(&src, &mut dst).unwrap();
-        out
-    }
-
-    fn draw_image(&mut self, image_data: impl Into<RenderImageData>, alpha_data: Option<LumaData>) {
+    fn draw_image<'b>(
+        &mut self,
+        image_data: impl Into<RenderImageData<'b>>,
+        alpha_data: Option<&LumaData>,
+    ) {
         let image_data = image_data.into();
         let cur_transform = *self.ctx.transform();
         let mut additional_transform = Affine::IDENTITY;
@@ -262,10 +309,10 @@ impl Renderer {
         let mut img_height = image_data.height();
         let interpolate = image_data.interpolate();
 
-        if let Some(a) = &alpha_data
+        if let Some(a) = alpha_data
             && (a.width != img_width || a.height != img_height || a.interpolate != interpolate)
         {
-            return self.draw_image_with_alpha_mask(image_data, alpha_data.unwrap());
+            return self.draw_image_with_alpha_mask(image_data, a);
         }
 
         let mut quality = if interpolate {
@@ -276,7 +323,10 @@ impl Renderer {
 
         let has_alpha = alpha_data.is_some();
         let mut may_have_transparency = has_alpha;
-        let needs_resize = x_scale < 1.0 || y_scale < 1.0;
+        // Downscales that are close to 1.0 are left to the sampling while drawing,
+        // since resampling the whole image beforehand is costly.
+        let max_scale = 1.0 - self.config.image_resampling.skip_threshold;
+        let needs_resize = x_scale < max_scale || y_scale < max_scale;
         let (new_width, new_height) = if needs_resize {
             let w = (img_width as f32 * x_scale)
                 .ceil()
@@ -294,8 +344,10 @@ impl Renderer {
             (img_width, img_height)
         };
 
-        // For luma images without alpha, we can resize as single-channel and
-        // expand to RGBA afterwards, which is ~4x faster.
+        // The RGBA data is allocated once and filled in a single pass directly from the
+        // color and alpha data. Luma images, solid colors and alpha values are resized
+        // as single-channel images beforehand, which is ~4x faster than resizing them
+        // as RGBA.
         let mut rgba_data = if matches!(&image_data, RenderImageData::Solid(_)) && has_alpha {
             let RenderImageData::Solid(solid) = image_data else {
                 unreachable!()
@@ -303,10 +355,10 @@ impl Renderer {
             let alpha = alpha_data.unwrap();
 
             let alpha_data = if !needs_resize {
-                alpha.data
+                Cow::Borrowed(alpha.data.as_slice())
             } else {
                 let resized_alpha = self.resize_image_data(
-                    alpha.data,
+                    &alpha.data,
                     img_width,
                     img_height,
                     new_width,
@@ -319,90 +371,55 @@ impl Renderer {
                 );
                 img_width = new_width;
                 img_height = new_height;
-                resized_alpha
+                Cow::Owned(resized_alpha)
             };
 
-            let mut out = Vec::with_capacity(img_width as usize * img_height as usize * 4);
-            for a in alpha_data {
-                out.extend_from_slice(&[solid.color[0], solid.color[1], solid.color[2], a]);
-            }
-            out
-        } else if matches!(&image_data, RenderImageData::Luma(_)) && !has_alpha {
+            interleave_rgba(iter::repeat(solid.color), Some(alpha_data.as_ref()), true)
+        } else if matches!(&image_data, RenderImageData::Luma(_)) {
             // We cannot lift this up due to borrowing issues.
             let RenderImageData::Luma(luma) = image_data else {
                 unreachable!()
             };
-
-            let luma_data = if !needs_resize {
-                luma.data
-            } else {
-                let resized = self.resize_image_data(
-                    luma.data,
-                    img_width,
-                    img_height,
-                    new_width,
-                    new_height,
-                    ImagePixelFormat::Luma,
-                );
-                additional_transform = Affine::scale_non_uniform(
-                    img_width as f64 / new_width as f64,
-                    img_height as f64 / new_height as f64,
-                );
-                img_width = new_width;
-                img_height = new_height;
-                resized
-            };
-
-            luma_data
-                .iter()
-                .flat_map(|g| [*g, *g, *g, 255])
-                .collect::<Vec<_>>()
-        } else if matches!(&image_data, RenderImageData::Luma(_)) && has_alpha {
-            let RenderImageData::Luma(luma) = image_data else {
-                unreachable!()
-            };
-            let alpha = alpha_data.unwrap();
+            let alpha = alpha_data.map(|a| a.data.as_slice());
 
             let (luma_data, alpha_data) = if !needs_resize {
-                (luma.data, alpha.data)
+                (
+                    Cow::Borrowed(luma.data.as_slice()),
+                    alpha.map(Cow::Borrowed),
+                )
             } else {
-                let resized_luma = self.resize_image_data(
-                    luma.data,
-                    img_width,
-                    img_height,
-                    new_width,
-                    new_height,
-                    ImagePixelFormat::Luma,
-                );
-                let resized_alpha = self.resize_image_data(
-                    alpha.data,
-                    img_width,
-                    img_height,
-                    new_width,
-                    new_height,
-                    ImagePixelFormat::Luma,
-                );
+                let resize = |data: &[u8]| {
+                    Cow::Owned(self.resize_image_data(
+                        data,
+                        img_width,
+                        img_height,
+                        new_width,
+                        new_height,
+                        ImagePixelFormat::Luma,
+                    ))
+                };
+                let resized = (resize(&luma.data), alpha.map(resize));
                 additional_transform = Affine::scale_non_uniform(
                     img_width as f64 / new_width as f64,
                     img_height as f64 / new_height as f64,
                 );
                 img_width = new_width;
                 img_height = new_height;
-                (resized_luma, resized_alpha)
+                resized
             };
 
-            let mut out = Vec::with_capacity(img_width as usize * img_height as usize * 4);
-            for (g, a) in luma_data.iter().zip(alpha_data) {
-                out.extend_from_slice(&[*g, *g, *g, a]);
-            }
-            out
+            interleave_rgba(
+                luma_data.iter().map(|g| [*g, *g, *g]),
+                alpha_data.as_deref(),
+                true,
+            )
         } else if matches!(&image_data, RenderImageData::Rgb(_)) && !has_alpha && needs_resize {
             let RenderImageData::Rgb(rgb) = image_data else {
                 unreachable!()
             };
 
             let resized = self.resize_image_data(
-                rgb.data,
+                &rgb.data,
                 img_width,
                 img_height,
                 new_width,
@@ -416,75 +433,58 @@ impl Renderer {
             img_width = new_width;
             img_height = new_height;
 
-            let mut out = Vec::with_capacity((img_width * img_height) as usize * 4);
-            for px in resized.chunks_exact(3) {
-                out.extend_from_slice(&[px[0], px[1], px[2], 255]);
-            }
-            out
+            interleave_rgba(resized.as_chunks::<3>().0.iter().copied(), None, false)
         } else {
-            let (rgb_data, alpha_data) = match image_data {
-                RenderImageData::Rgb(rgb) => (rgb.data, alpha_data.map(|a| a.data)),
-                RenderImageData::Luma(luma) => {
-                    let rgb = luma
-                        .data
-                        .iter()
-                        .flat_map(|g| [*g, *g, *g])
-                        .collect::<Vec<_>>();
-                    (rgb, alpha_data.map(|a| a.data))
+            // If the image is resized, it needs to be premultiplied afterwards.
+            let premultiply = !needs_resize;
+
+            let rgba_data = {
+                let alpha_data = alpha_data.map(|a| a.data.as_slice());
+
+                match image_data {
+                    RenderImageData::Rgb(rgb) => interleave_rgba(
+                        rgb.data.as_chunks::<3>().0.iter().copied(),
+                        alpha_data,
+                        premultiply,
+                    ),
+                    RenderImageData::Luma(_) => unreachable!(),
+                    RenderImageData::Solid(solid) => interleave_rgba(
+                        iter::repeat_n(solid.color, solid.width as usize * solid.height as usize),
+                        alpha_data,
+                        premultiply,
+                    ),
                 }
-                RenderImageData::Solid(solid) => {
-                    let mut rgb =
-                        Vec::with_capacity(solid.width as usize * solid.height as usize * 3);
-                    for _ in 0..solid.width as usize * solid.height as usize {
-                        rgb.extend_from_slice(&solid.color);
-                    }
-                    (rgb, alpha_data.map(|a| a.data))
-                }
-            };
-
-            let rgba_data = match alpha_data {
-                None => rgb_data
-                    .chunks_exact(3)
-                    .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
-                    .collect::<Vec<_>>(),
-                Some(alpha) => rgb_data
-                    .chunks_exact(3)
-                    .zip(alpha)
-                    .flat_map(|(rgb, a)| [rgb[0], rgb[1], rgb[2], a])
-                    .collect::<Vec<_>>(),
             };
 
             if !needs_resize {
                 rgba_data
             } else {
-                let resized = self.resize_image_data(
-                    rgba_data,
+                let mut resized = self.resize_image_data(
+                    &rgba_data,
                     img_width,
                     img_height,
                     new_width,
                     new_height,
                     ImagePixelFormat::Rgba,
                 );
+                drop(rgba_data);
                 additional_transform = Affine::scale_non_uniform(
                     img_width as f64 / new_width as f64,
                     img_height as f64 / new_height as f64,
                 );
                 img_width = new_width;
                 img_height = new_height;
+
+                if has_alpha {
+                    for pixel in resized.as_chunks_mut::<4>().0 {
+                        *pixel = premultiply_rgba8(*pixel);
+                    }
+                }
+
                 resized
             }
         };
 
-        if has_alpha {
-            let (chunks, _) = rgba_data.as_chunks_mut::<4>();
-            for chunk in chunks {
-                *chunk = AlphaColor::from_rgba8(chunk[0], chunk[1], chunk[2], chunk[3])
-                    .premultiply()
-                    .to_rgba8()
-                    .to_u8_array();
-            }
-        }
-
         // The problem is that by default, when applying a bilinear or bicubic scaling, we will
         // sample pixels using an extend (pad/reflect/repeat). For glyphs, this is undesirable
         // as the glyphs will look very bold. Therefore, for glyphs it is more desirable to sample
@@ -523,6 +523,15 @@ impl Renderer {
         );
     }
 
+    fn push_clip_bbox(&mut self, bbox: Rect) {
+        let bbox = match self.clip_bboxes.last() {
+            Some(last) => bbox.intersect(*last),
+            None => bbox,
+        };
+
+        self.clip_bboxes.push(bbox);
+    }
+
     fn push_clip_path_inner(&mut self, clip_path: &BezPath, fill: FillRule) {
         let old_transform = *self.ctx.transform();
 
@@ -551,37 +560,140 @@ impl Renderer {
             .fill_rect(&Rect::new(0.0, 0.0, width as f64, height as f64));
     }
 
-    fn apply_soft_mask(&mut self, mask: Option<&SoftMask<'_>>) {
+    /// Return the rasterized soft mask, rendering it if it isn't cached yet.
+    ///
+    /// The soft mask needs to be rendered into a separate pixmap, so `None` is
+    /// returned if there is no room for it in the pixmap budget.
+    fn soft_mask(&mut self, mask: &SoftMask<'_>) -> Option<Mask> {
+        let key = mask.cache_key();
+
+        if let Some(mask) = self.soft_mask_cache.get(&key) {
+            return Some(mask.clone());
+        }
+
+        let (width, height) = (self.ctx.width(), self.ctx.height());
+
+        if !self.config.budget.reserve(width, height) {
+            return None;
+        }
+
         let settings = *self.ctx.render_settings();
-        let mask = mask.map(|m| {
-            let width = self.ctx.width();
-            let height = self.ctx.height();
-
-            self.soft_mask_cache
-                .entry(m.cache_key())
-                .or_insert_with(|| draw_soft_mask(m, settings, width, height))
-                .clone()
-        });
+        let mask = draw_soft_mask(mask, settings, &self.config, width, height);
+        self.soft_mask_cache.insert(key, mask.clone());
+
+        Some(mask)
+    }
 
+    /// Apply the given soft mask, or reset it if there is none.
+    ///
+    /// Returns `false` if there is no room for the mask in the pixmap budget, in
+    /// which case the masked contents should be left out.
+    #[must_use]
+    fn apply_soft_mask(&mut self, mask: Option<&SoftMask<'_>>) -> bool {
         if let Some(mask) = mask {
+            let Some(mask) = self.soft_mask(mask) else {
+                return false;
+            };
+
             self.ctx.set_mask(mask);
         } else {
             self.ctx.reset_mask();
         }
+
+        true
     }
 
-    fn apply_draw_props(&mut self, props: &DrawProps<'_>) {
+    /// Apply the draw properties, returning `false` if the contents should be
+    /// left out (see `apply_soft_mask`).
+    #[must_use]
+    fn apply_draw_props(&mut self, props: &DrawProps<'_>) -> bool {
         self.ctx.set_transform(props.transform);
-        self.apply_soft_mask(props.soft_mask.as_ref());
-        self.ctx
-            .set_blend_mode(convert_blend_mode(props.blend_mode));
+
+        if !self.apply_soft_mask(props.soft_mask.as_ref()) {
+            return false;
+        }
+
+        // Since we composite in RGB, we can't keep track of the individual colorants
+        // of the backdrop. However, each colorant only ever darkens the result, so
+        // overprinting can be approximated by multiplying the colors, where the
+        // colorants that aren't painted map to white and thus leave the backdrop
+        // unchanged. The painted colorants can't replace those of the backdrop
+        // though, so for example `0.2 0 0 0 k` over full cyan stays full cyan
+        // instead of getting lighter.
+        let blend_mode = if self.config.simulate_overprint
+            && props.overprint
+            && props.blend_mode == BlendMode::Normal
+        {
+            BlendMode::Multiply
+        } else {
+            props.blend_mode
+        };
+
+        self.ctx.set_blend_mode(convert_blend_mode(blend_mode));
+
+        true
     }
 
-    fn apply_image_props(&mut self, props: &ImageDrawProps<'_>) {
+    /// Return whether the paint is a color that overprints without painting
+    /// any colorant, for example `0 0 0 0 k` with an overprint mode of 1.
+    ///
+    /// Such paints leave the backdrop unchanged, so instead of relying on the
+    /// multiplication in `apply_draw_props`, which would knock out the backdrop
+    /// of transparency groups, they are skipped entirely.
+    fn paints_no_inks(&self, props: &DrawProps<'_>) -> bool {
+        self.config.simulate_overprint
+            && props.overprint
+            && matches!(
+                &props.paint,
+                Paint::Color(c) if c.overprint_inks().is_some_and(|inks| inks.is_empty())
+            )
+    }
+
+    /// Return whether rendering has been cancelled, in which case nothing is drawn anymore.
+    ///
+    /// Transparency groups and clip paths are still pushed and popped as usual, so the
+    /// layer stack is unwound cleanly and everything drawn until then is composited.
+    /// This only performs a relaxed atomic load, the clock is checked by the interpreter
+    /// and before expensive operations.
+    fn is_cancelled(&self) -> bool {
+        self.config.deadline.as_ref().is_some_and(|d| d.exceeded())
+    }
+
+    /// Apply the image draw properties, returning `false` if the image should be
+    /// left out (see `apply_soft_mask`).
+    #[must_use]
+    fn apply_image_props(&mut self, props: &ImageDrawProps<'_>) -> bool {
         self.ctx.set_transform(props.transform);
-        self.apply_soft_mask(props.soft_mask.as_ref());
+
+        if !self.apply_soft_mask(props.soft_mask.as_ref()) {
+            return false;
+        }
+
         self.ctx
             .set_blend_mode(convert_blend_mode(props.blend_mode));
+
+        true
+    }
+
+    /// Return the bounding box in device space of the area that is painted when
+    /// filling or stroking a path with the given bounding box, clipped to the pixmap.
+    fn paint_bbox(&self, path_bbox: Rect, is_stroke: bool) -> Rect {
+        let path_transform = self.ctx.transform();
+        let mut bbox = (*path_transform * path_bbox.to_path(0.0)).bounding_box();
+
+        if is_stroke {
+            // Try to account for stroke in bbox.
+            let (a1, a2) = x_y_advances(path_transform);
+            let factor = a1.length().max(a2.length()) * self.ctx.stroke().width;
+            bbox = bbox.inflate(factor, factor);
+        }
+
+        bbox.intersect(Rect::new(
+            0.0,
+            0.0,
+            self.ctx.width() as f64,
+            self.ctx.height() as f64,
+        ))
     }
 
     #[must_use]
@@ -594,7 +706,7 @@ impl Renderer {
         let mut paint_transform = Affine::IDENTITY;
         let mut clip_path = None;
 
-        let paint: PaintType = match paint.clone() {
+        let paint: PaintType = match paint {
             Paint::Color(c) => {
                 let c = c.to_rgba().to_rgba8();
                 AlphaColor::from_rgba8(c[0], c[1], c[2], c[3]).into()
@@ -602,27 +714,13 @@ impl Renderer {
             Paint::Pattern(p) => {
                 let path_transform = self.ctx.transform();
 
-                match *p {
+                match p.as_ref() {
                     Pattern::Shading(s) => {
                         const NATIVE_GRADIENT_TOLERANCE: f32 = 0.01;
 
                         clip_path = s.shading.clip_path.clone();
                         let encoded = s.encode();
-                        let mut bbox = (*path_transform * path_bbox().to_path(0.0)).bounding_box();
-
-                        if is_stroke {
-                            // Try to account for stroke in bbox.
-                            let (a1, a2) = x_y_advances(path_transform);
-                            let factor = a1.length().max(a2.length()) * self.ctx.stroke().width;
-                            bbox = bbox.inflate(factor, factor);
-                        }
-
-                        bbox = bbox.intersect(Rect::new(
-                            0.0,
-                            0.0,
-                            self.ctx.width() as f64,
-                            self.ctx.height() as f64,
-                        ));
+                        let bbox = self.paint_bbox(path_bbox(), is_stroke);
 
                         if let EncodedShadingType::RadialAxial(gradient) = &encoded.shading_type
                             && let Some(native) =
@@ -688,12 +786,19 @@ impl Renderer {
                             PaintType::Image(image)
                         }
                     }
-                    Pattern::Tiling(t) => {
+                    Pattern::Tiling(t) => 'tile: {
                         const MAX_PIXMAP_SIZE: f32 = 3000.0;
                         // TODO: Raise this limit and perform downsampling if reached
                         // (see pdftc_100k_0138.pdf).
                         const MIN_PIXMAP_SIZE: f32 = 1.0;
 
+                        // The pattern matrix already maps to device space.
+                        let mut t = t.clone();
+                        t.clamp_tile_count(
+                            Affine::IDENTITY,
+                            self.paint_bbox(path_bbox(), is_stroke),
+                        );
+
                         let bbox = t.bbox;
                         let max_x_scale = MAX_PIXMAP_SIZE / bbox.width() as f32;
                         let min_x_scale = MIN_PIXMAP_SIZE / bbox.width() as f32;
@@ -707,6 +812,18 @@ impl Renderer {
                         xs = xs.max(min_x_scale).min(max_x_scale);
                         ys = ys.max(min_y_scale).min(max_y_scale);
 
+                        // Render the tile at a lower resolution if it doesn't fit into the
+                        // pixmap budget. Account for rounding up the dimensions.
+                        let pixels = ((xs * t.x_step).abs() as f64 + 1.0)
+                            * ((ys * t.y_step).abs() as f64 + 1.0);
+                        let remaining_pixels = self.config.budget.remaining_pixels() as f64;
+
+                        if pixels > remaining_pixels {
+                            let factor = (remaining_pixels / pixels).sqrt() as f32;
+                            xs *= factor;
+                            ys *= factor;
+                        }
+
                         let x_step = xs * t.x_step;
                         let y_step = ys * t.y_step;
 
@@ -715,21 +832,27 @@ impl Renderer {
                         let pix_width = x_step.abs().round() as u16;
                         let pix_height = y_step.abs().round() as u16;
 
-                        let mut renderer = Self {
-                            ctx: RenderContext::new_with(
-                                pix_width,
-                                pix_height,
-                                derive_settings(self.ctx.render_settings()),
-                            ),
-                            inside_pattern: true,
-                            soft_mask_cache: FxHashMap::default(),
-                            outline_cache: self.outline_cache.clone(),
-                            in_type3_glyph: false,
-                            scaler: self.scaler,
-                        };
+                        // Rendering a tile can be expensive, so check the clock as well.
+                        if pix_width == 0
+                            || pix_height == 0
+                            || self.config.deadline.as_ref().is_some_and(|d| d.check())
+                            || !self.config.budget.reserve(pix_width, pix_height)
+                        {
+                            break 'tile TRANSPARENT.into();
+                        }
+
+                        let mut renderer = self.sub_renderer(pix_width, pix_height);
+                        renderer.inside_pattern = true;
                         let mut initial_transform = Affine::scale_non_uniform(xs as f64, ys as f64)
                             * Affine::translate((-bbox.x0, -bbox.y0));
                         t.interpret(&mut renderer, initial_transform, is_stroke);
+
+                        // Don't rasterize and paint with a tile that was only partially
+                        // rendered.
+                        if renderer.is_cancelled() {
+                            break 'tile TRANSPARENT.into();
+                        }
+
                         let mut pix = Pixmap::new(pix_width, pix_height);
                         renderer.ctx.flush();
                         let mut resources = vello_cpu::Resources::default();
@@ -778,7 +901,29 @@ impl Renderer {
         stroke_props: &StrokeProps,
         is_text: bool,
     ) {
-        self.apply_draw_props(&props);
+        // The stroke can extend beyond the path by half of the line width, or by
+        // more in case of miter joins and square caps. Since line widths are
+        // enforced to be at least one pixel wide, the same factor is also applied
+        // in device space.
+        let factor = if stroke_props.line_join == Join::Miter {
+            (stroke_props.miter_limit as f64).max(SQRT_2)
+        } else {
+            SQRT_2
+        };
+        let outset = stroke_props.line_width as f64 / 2.0 * factor;
+        let bbox = props
+            .transform
+            .transform_rect_bbox(path.control_box().inflate(outset, outset))
+            .inflate(factor, factor);
+
+        if self.is_culled(bbox) {
+            return;
+        }
+
+        if !self.apply_draw_props(&props) {
+            return;
+        }
+
         self.set_stroke_properties(stroke_props, is_text);
 
         let clip_path = self.set_paint(&props.paint, || path.bounding_box(), true);
@@ -792,8 +937,15 @@ impl Renderer {
     }
 
     fn fill_path(&mut self, path: &BezPath, props: DrawProps<'_>, fill_rule: FillRule) {
+        if self.is_culled(props.transform.transform_rect_bbox(path.control_box())) {
+            return;
+        }
+
         self.ctx.set_fill_rule(convert_fill_rule(fill_rule));
-        self.apply_draw_props(&props);
+
+        if !self.apply_draw_props(&props) {
+            return;
+        }
 
         let clip_path = self.set_paint(&props.paint, || path.bounding_box(), false);
         if let Some(clip_path) = clip_path.as_ref() {
@@ -816,7 +968,33 @@ impl Renderer {
                     ..props
                 };
 
-                self.fill_path(base_outline.as_ref(), props, FillRule::NonZero);
+                if let Paint::Color(c) = &props.paint
+                    && let Some(cache) = self.config.glyph_cache.clone()
+                    && let Some(placement) = GlyphPlacement::new(
+                        o.identifier().cache_key(),
+                        props.transform,
+                        base_outline.control_box(),
+                        self.config.antialias,
+                        self.config.stem_darkening,
+                    )
+                {
+                    let color = c.to_rgba().to_rgba8();
+                    self.fill_cached_glyph(&cache, o, &base_outline, &placement, color, &props);
+
+                    return;
+                }
+
+                let darkened = if self.config.stem_darkening {
+                    darken(&base_outline, o.stem_width(), &props.transform)
+                } else {
+                    None
+                };
+
+                self.fill_path(
+                    darkened.as_ref().unwrap_or(base_outline.as_ref()),
+                    props,
+                    FillRule::NonZero,
+                );
             }
             Glyph::Type3(s) => {
                 self.in_type3_glyph = true;
@@ -850,22 +1028,89 @@ impl Renderer {
         }
     }
 
+    /// Draw a glyph by blitting its cached coverage instead of filling its outline,
+    /// rasterizing it first if necessary.
+    fn fill_cached_glyph(
+        &mut self,
+        cache: &std::cell::RefCell<GlyphCache>,
+        glyph: &hayro_interpret::font::OutlineGlyph,
+        outline: &BezPath,
+        placement: &GlyphPlacement,
+        color: [u8; 4],
+        props: &DrawProps<'_>,
+    ) {
+        let (x, y) = placement.origin;
+        let bounds = placement.bounds;
+
+        if self.is_culled(bounds + Vec2::new(x, y)) {
+            return;
+        }
+
+        let pixmap = cache
+            .borrow_mut()
+            .get_or_rasterize(placement.key, color, || {
+                let darkened = if self.config.stem_darkening {
+                    darken(outline, glyph.stem_width(), &placement.transform)
+                } else {
+                    None
+                };
+
+                rasterize_glyph(
+                    darkened.as_ref().unwrap_or(outline),
+                    placement,
+                    derive_settings(self.ctx.render_settings()),
+                    self.config.antialias,
+                )
+            });
+
+        if !self.apply_draw_props(props) {
+            return;
+        }
+
+        self.ctx.set_fill_rule(Fill::NonZero);
+        self.ctx.set_paint_transform(Affine::IDENTITY);
+        // Since the bitmap is placed at an integer position without any scaling,
+        // each pixel maps exactly to one pixel of the bitmap.
+        self.draw_pixmap(
+            pixmap,
+            ImageQuality::Low,
+            Affine::translate((x + bounds.x0, y + bounds.y0)),
+        );
+    }
+
     fn cached_outline(&self, glyph: &hayro_interpret::font::OutlineGlyph) -> Rc<BezPath> {
         let id = glyph.identifier().cache_key();
 
-        if let Some(path) = self.outline_cache.borrow().get(&id) {
+        if let Some(path) = self.config.outline_cache.borrow().get(&id) {
             return path.clone();
         }
 
         let path = Rc::new(glyph.outline());
-        self.outline_cache.borrow_mut().insert(id, path.clone());
+        self.config
+            .outline_cache
+            .borrow_mut()
+            .insert(id, path.clone());
         path
     }
 }
 
 impl<'a> Device<'a> for Renderer {
     fn draw_image(&mut self, image: hayro_interpret::Image<'a, '_>, props: ImageDrawProps<'a>) {
-        self.apply_image_props(&props);
+        let image_rect = Rect::new(0.0, 0.0, image.width() as f64, image.height() as f64);
+
+        if self.is_culled(props.transform.transform_rect_bbox(image_rect)) {
+            return;
+        }
+
+        // Decoding and resizing large images is expensive, so check the clock as well.
+        if self.config.deadline.as_ref().is_some_and(|d| d.check()) {
+            return;
+        }
+
+        if !self.apply_image_props(&props) {
+            return;
+        }
+
         let mut transform = props.transform;
         self.ctx.set_paint_transform(Affine::IDENTITY);
         self.ctx.set_aliasing_threshold(Some(1));
@@ -887,6 +1132,11 @@ impl<'a> Device<'a> for Renderer {
                             stencil.scale_factors.0 as f64,
                             stencil.scale_factors.1 as f64,
                         );
+                        // Type3 glyphs are usually tiny, snapping them would distort their
+                        // position and size too much.
+                        if !self.in_type3_glyph {
+                            transform = snap_to_pixels(transform, stencil.width, stencil.height);
+                        }
 
                         match paint {
                             Paint::Color(c) => {
@@ -927,6 +1177,13 @@ impl<'a> Device<'a> for Renderer {
                             }
                             Paint::Pattern(_) => {
                                 let (width, height) = (self.ctx.width(), self.ctx.height());
+
+                                // Leave out the stencil if there is no room for rendering
+                                // its mask.
+                                if !self.config.budget.reserve(width, height) {
+                                    return;
+                                }
+
                                 let stencil_rect = Rect::new(
                                     0.0,
                                     0.0,
@@ -946,21 +1203,10 @@ impl<'a> Device<'a> for Renderer {
                                         interpolate: stencil.interpolate,
                                         scale_factors: stencil.scale_factors,
                                     });
-                                    let mut sub_renderer = Self {
-                                        ctx: RenderContext::new_with(
-                                            width,
-                                            height,
-                                            derive_settings(self.ctx.render_settings()),
-                                        ),
-                                        inside_pattern: false,
-                                        soft_mask_cache: FxHashMap::default(),
-                                        outline_cache: self.outline_cache.clone(),
-                                        in_type3_glyph: false,
-                                        scaler: self.scaler,
-                                    };
+                                    let mut sub_renderer = self.sub_renderer(width, height);
                                     let mut sub_pix = Pixmap::new(width, height);
                                     sub_renderer.ctx.set_transform(transform);
-                                    sub_renderer.draw_image(rgb_bytes, Some(stencil));
+                                    sub_renderer.draw_image(&rgb_bytes, Some(stencil));
                                     sub_renderer.ctx.flush();
                                     let mut resources = vello_cpu::Resources::default();
                                     sub_renderer.ctx.render(&mut sub_pix, &mut resources);
@@ -1005,14 +1251,17 @@ impl<'a> Device<'a> for Renderer {
             }
         }
 
-        self.ctx.set_aliasing_threshold(None);
+        self.ctx
+            .set_aliasing_threshold(self.config.antialias.aliasing_threshold());
     }
 
     fn push_clip_path(&mut self, clip_path: &ClipPath) {
+        self.push_clip_bbox(clip_path.path.control_box());
         self.push_clip_path_inner(&clip_path.path, clip_path.fill);
     }
 
     fn push_clip_rect(&mut self, rect: &Rect) {
+        self.push_clip_bbox(*rect);
         self.push_clip_path_inner(&rect.to_path(0.1), FillRule::NonZero);
     }
 
@@ -1022,26 +1271,28 @@ impl<'a> Device<'a> for Renderer {
         mask: Option<SoftMask<'_>>,
         blend_mode: BlendMode,
     ) {
-        let settings = *self.ctx.render_settings();
+        let mut fits_budget = true;
+
+        // If there is no room for rendering the soft mask, leave out the contents of
+        // the group instead.
+        let mask = mask.and_then(|m| {
+            let mask = self.soft_mask(&m);
+            fits_budget = mask.is_some();
+
+            mask
+        });
+
         self.ctx.push_layer(
             None,
             Some(convert_blend_mode(blend_mode)),
-            Some(opacity),
-            // TODO: Deduplicate
-            mask.map(|m| {
-                let width = self.ctx.width();
-                let height = self.ctx.height();
-
-                self.soft_mask_cache
-                    .entry(m.cache_key())
-                    .or_insert_with(|| draw_soft_mask(&m, settings, width, height))
-                    .clone()
-            }),
+            Some(if fits_budget { opacity } else { 0.0 }),
+            mask,
             None,
         );
     }
 
     fn pop_clip(&mut self) {
+        self.clip_bboxes.pop();
         self.ctx.pop_clip_path();
     }
 
@@ -1050,6 +1301,10 @@ impl<'a> Device<'a> for Renderer {
     }
 
     fn draw_path(&mut self, path: &BezPath, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        if self.is_cancelled() || self.paints_no_inks(&props) {
+            return;
+        }
+
         match draw_mode {
             DrawMode::Fill(f) => {
                 Self::fill_path(self, path, props, *f);
@@ -1066,10 +1321,21 @@ impl<'a> Device<'a> for Renderer {
     }
 
     fn draw_rect(&mut self, rect: &Rect, props: DrawProps<'a>, draw_mode: &DrawMode) {
+        if self.is_cancelled() || self.paints_no_inks(&props) {
+            return;
+        }
+
         match draw_mode {
             DrawMode::Fill(fill_rule) => {
+                if self.is_culled(props.transform.transform_rect_bbox(*rect)) {
+                    return;
+                }
+
                 self.ctx.set_fill_rule(convert_fill_rule(*fill_rule));
-                self.apply_draw_props(&props);
+
+                if !self.apply_draw_props(&props) {
+                    return;
+                }
 
                 let clip_path = self.set_paint(&props.paint, || *rect, false);
                 if let Some(clip_path) = clip_path.as_ref() {
@@ -1102,6 +1368,10 @@ impl<'a> Device<'a> for Renderer {
         props: DrawProps<'a>,
         draw_mode: &DrawMode,
     ) {
+        if self.is_cancelled() || self.paints_no_inks(&props) {
+            return;
+        }
+
         match draw_mode {
             DrawMode::Fill(_) => {
                 Self::fill_glyph(self, glyph, props, glyph_transform);
@@ -1119,6 +1389,28 @@ impl<'a> Device<'a> for Renderer {
 }
 
 // TODO: Deduplicate with hayro-svg?
+fn resize_image_data_impl<const N: usize>(
+    scaler: &Scaler,
+    data: &[u8],
+    src_width: u32,
+    src_height: u32,
+    new_width: u32,
+    new_height: u32,
+    plan: impl FnOnce(&Scaler, ImageSize, ImageSize) -> Result<Arc<Resampling<u8, N>>, PicScaleError>,
+) -> Vec<u8> {
+    let source_size = ImageSize::new(src_width as usize, src_height as usize);
+    let target_size = ImageSize::new(new_width as usize, new_height as usize);
+    let src =
+        ImageStore::<u8, N>::from_slice(data, src_width as usize, src_height as usize).unwrap();
+    let mut out = vec![0; new_width as usize * new_height as usize * N];
+    let mut dst =
+        ImageStoreMut::<u8, N>::from_slice(&mut out, new_width as usize, new_height as usize)
+            .unwrap();
+    let plan = plan(scaler, source_size, target_size).unwrap();
+    plan.resample(&src, &mut dst).unwrap();
+    out
+}
+
 fn render_shading_texture(
     path_bbox: Rect,
     shading_pattern: &EncodedShadingPattern,
@@ -1160,15 +1452,104 @@ fn render_shading_texture(
     )
 }
 
-fn draw_soft_mask(mask: &SoftMask<'_>, settings: RenderSettings, width: u16, height: u16) -> Mask {
-    let mut renderer = Renderer {
-        ctx: RenderContext::new_with(width, height, derive_settings(&settings)),
-        inside_pattern: false,
-        soft_mask_cache: FxHashMap::default(),
-        outline_cache: Rc::new(std::cell::RefCell::new(FxHashMap::default())),
-        in_type3_glyph: false,
-        scaler: Scaler::new(ResamplingFunction::CatmullRom),
-    };
+/// Rasterize the coverage of a glyph outline into a bitmap with the bounds of the
+/// given placement.
+fn rasterize_glyph(
+    outline: &BezPath,
+    placement: &GlyphPlacement,
+    settings: RenderSettings,
+    antialias: AntialiasMode,
+) -> GlyphBitmap {
+    let bounds = placement.bounds;
+    let (width, height) = (bounds.width() as u16, bounds.height() as u16);
+
+    let mut ctx = new_render_context(width, height, settings, antialias);
+    ctx.set_transform(Affine::translate((-bounds.x0, -bounds.y0)) * placement.transform);
+    ctx.set_fill_rule(Fill::NonZero);
+    ctx.set_paint(BLACK);
+    ctx.fill_path(outline);
+    ctx.flush();
+
+    let mut pixmap = Pixmap::new(width, height);
+    let mut resources = vello_cpu::Resources::default();
+    ctx.render(&mut pixmap, &mut resources);
+
+    GlyphBitmap {
+        coverage: pixmap.data().iter().map(|p| p.a).collect(),
+        width,
+        height,
+    }
+}
+
+/// Build RGBA8 data from the given colors and alpha values in a single pass.
+///
+/// The buffer is allocated exactly once. If `alpha` is `None`, all pixels are
+/// opaque. Otherwise, the colors are premultiplied with the alpha values if
+/// `premultiply` is `true`.
+fn interleave_rgba(
+    colors: impl Iterator<Item = [u8; 3]>,
+    alpha: Option<&[u8]>,
+    premultiply: bool,
+) -> Vec<u8> {
+    match alpha {
+        None => {
+            let (len, _) = colors.size_hint();
+            let mut out = Vec::with_capacity(len * 4);
+
+            for [r, g, b] in colors {
+                out.extend_from_slice(&[r, g, b, 255]);
+            }
+
+            out
+        }
+        Some(alpha) => {
+            let mut out = Vec::with_capacity(alpha.len() * 4);
+
+            for ([r, g, b], a) in colors.zip(alpha) {
+                let pixel = [r, g, b, *a];
+                out.extend_from_slice(&if premultiply {
+                    premultiply_rgba8(pixel)
+                } else {
+                    pixel
+                });
+            }
+
+            out
+        }
+    }
+}
+
+fn premultiply_rgba8([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    match a {
+        255 => [r, g, b, a],
+        0 => [0; 4],
+        _ => AlphaColor::<Srgb>::from_rgba8(r, g, b, a)
+            .premultiply()
+            .to_rgba8()
+            .to_u8_array(),
+    }
+}
+
+fn new_render_context(
+    width: u16,
+    height: u16,
+    settings: RenderSettings,
+    antialias: AntialiasMode,
+) -> RenderContext {
+    let mut ctx = RenderContext::new_with(width, height, settings);
+    ctx.set_aliasing_threshold(antialias.aliasing_threshold());
+
+    ctx
+}
+
+fn draw_soft_mask(
+    mask: &SoftMask<'_>,
+    settings: RenderSettings,
+    config: &RendererConfig,
+    width: u16,
+    height: u16,
+) -> Mask {
+    let mut renderer = Renderer::new(width, height, derive_settings(&settings), config.clone());
 
     let bg_color = mask.background_color().to_rgba();
     let apply_bg = bg_color.to_rgba8() != BLACK.to_rgba8().to_u8_array();
@@ -1185,6 +1566,12 @@ fn draw_soft_mask(mask: &SoftMask<'_>, settings: RenderSettings, width: u16, hei
 
     mask.interpret(&mut renderer);
 
+    // Don't apply a mask that was only partially rasterized, but hide the masked
+    // contents instead.
+    if renderer.is_cancelled() {
+        return Mask::from_parts(vec![0; width as usize * height as usize], width, height);
+    }
+
     if apply_bg {
         renderer.ctx.pop_layer();
     }
@@ -1247,6 +1634,15 @@ pub(crate) fn x_y_advances(transform: &Affine) -> (Vec2, Vec2) {
     )
 }
 
+fn convert_resample_filter(filter: ResampleFilter) -> ResamplingFunction {
+    match filter {
+        ResampleFilter::Bilinear => ResamplingFunction::Bilinear,
+        ResampleFilter::CatmullRom => ResamplingFunction::CatmullRom,
+        ResampleFilter::Lanczos3 => ResamplingFunction::Lanczos3,
+        ResampleFilter::Box => ResamplingFunction::Box,
+    }
+}
+
 fn convert_fill_rule(fill_rule: FillRule) -> Fill {
     match fill_rule {
         FillRule::NonZero => Fill::NonZero,
@@ -1276,3 +1672,45 @@ fn convert_blend_mode(blend_mode: BlendMode) -> peniko::BlendMode {
 
     peniko::BlendMode::new(mix, Compose::SrcOver)
 }
+
+/// Snap the edges of an axis-aligned image to pixel boundaries.
+///
+/// Stencil images are drawn without anti-aliasing, so if the edge of an image lands on a
+/// fractional device coordinate, two abutting images (for example a scanned page that was
+/// split into horizontal strips) do not necessarily agree on which of them covers the pixel
+/// on the shared edge, leaving a visible seam. By rounding the edges to the nearest pixel
+/// boundary, abutting images end up sharing the same boundary, so that each pixel is covered
+/// exactly once.
+fn snap_to_pixels(transform: Affine, width: u32, height: u32) -> Affine {
+    let [a, b, c, d, _, _] = transform.as_coeffs();
+
+    if b != 0.0 || c != 0.0 {
+        return transform;
+    }
+
+    let rect = transform.transform_rect_bbox(Rect::new(0.0, 0.0, width as f64, height as f64));
+    let snapped = Rect::new(
+        rect.x0.round(),
+        rect.y0.round(),
+        rect.x1.round(),
+        rect.y1.round(),
+    );
+
+    if snapped.width() == 0.0 || snapped.height() == 0.0 {
+        return transform;
+    }
+
+    // Make sure that we preserve any flips of the image.
+    let (sx, tx) = if a < 0.0 {
+        (-snapped.width(), snapped.x1)
+    } else {
+        (snapped.width(), snapped.x0)
+    };
+    let (sy, ty) = if d < 0.0 {
+        (-snapped.height(), snapped.y1)
+    } else {
+        (snapped.height(), snapped.y0)
+    };
+
+    Affine::new([sx / width as f64, 0.0, 0.0, sy / height as f64, tx, ty])
+}
@@ -0,0 +1,200 @@
+//! Stem darkening for glyphs that are rendered at small sizes.
+//!
+//! Since glyphs are rasterized without hinting, their stems cover fewer pixels
+//! at small sizes than they would with hinting, causing text to look lighter
+//! than it should. Similarly to what `FreeType` does for CFF fonts, we compensate
+//! for that by emboldening the outlines depending on the width of the stems in pixels.
+
+use crate::renderer::max_factor;
+use kurbo::{Affine, BezPath, PathEl, Point, Vec2};
+
+/// The stem width that is assumed in case the font doesn't specify one,
+/// assuming an upem value of 1000.
+const DEFAULT_STEM_WIDTH: f64 = 75.0;
+
+/// Glyphs with a size above this number of pixels per em are never darkened.
+const MAX_PPEM: f64 = 48.0;
+
+/// Pairs of stem widths and the amount by which stems of that width are
+/// widened, both in pixels. The amount for stem widths in between is
+/// interpolated linearly. These are the default darkening parameters of
+/// `FreeType`'s CFF driver.
+const DARKENING_PARAMS: [(f64, f64); 4] = [(0.5, 0.4), (1.0, 0.275), (1.667, 0.275), (2.333, 0.0)];
+
+/// Return the darkened outline of a glyph with the given stem width (assuming an
+/// upem value of 1000) that is drawn with the given transform, or `None` if the
+/// glyph doesn't need to be darkened.
+pub(crate) fn darken(
+    outline: &BezPath,
+    stem_width: Option<f32>,
+    transform: &Affine,
+) -> Option<BezPath> {
+    // The number of pixels per glyph unit.
+    let scale = max_factor(transform) as f64;
+    let amount = darkening_amount(stem_width.map(|w| w as f64), scale * 1000.0);
+
+    // Half of the amount is applied to each side of a stem.
+    (amount > 0.0).then(|| embolden(outline, amount / 2.0 / scale))
+}
+
+/// Return the amount by which stems of the given width (in glyph units) should
+/// be widened at the given size in pixels per em, in pixels.
+fn darkening_amount(stem_width: Option<f64>, ppem: f64) -> f64 {
+    if !(ppem > 0.0 && ppem <= MAX_PPEM) {
+        return 0.0;
+    }
+
+    let stem_width = stem_width.unwrap_or(DEFAULT_STEM_WIDTH) * ppem / 1000.0;
+    let (first_width, first_amount) = DARKENING_PARAMS[0];
+
+    if stem_width < first_width {
+        return first_amount;
+    }
+
+    for window in DARKENING_PARAMS.windows(2) {
+        let [(x0, y0), (x1, y1)] = [window[0], window[1]];
+
+        if stem_width < x1 {
+            return y0 + (y1 - y0) * (stem_width - x0) / (x1 - x0);
+        }
+    }
+
+    DARKENING_PARAMS[DARKENING_PARAMS.len() - 1].1
+}
+
+/// Embolden an outline by shifting all of its points outwards by the given
+/// strength, in the same way as `FT_Outline_EmboldenXY` does.
+fn embolden(outline: &BezPath, strength: f64) -> BezPath {
+    let elements = outline.elements();
+    // Whether the outer contours of the outline are oriented counter-clockwise,
+    // in which case the outward normal of a segment is on its right side.
+    let counter_clockwise = signed_area(elements) >= 0.0;
+    let mut emboldened = BezPath::new();
+    let mut start = 0;
+
+    while start < elements.len() {
+        let end = elements[start + 1..]
+            .iter()
+            .position(|e| matches!(e, PathEl::MoveTo(_)))
+            .map_or(elements.len(), |pos| start + 1 + pos);
+        let contour = &elements[start..end];
+        let mut points = contour_points(contour);
+        shift_points(&mut points, strength, counter_clockwise);
+
+        let mut points = points.into_iter();
+        let mut next = || points.next().unwrap();
+
+        for el in contour {
+            emboldened.push(match el {
+                PathEl::MoveTo(_) => PathEl::MoveTo(next()),
+                PathEl::LineTo(_) => PathEl::LineTo(next()),
+                PathEl::QuadTo(..) => PathEl::QuadTo(next(), next()),
+                PathEl::CurveTo(..) => PathEl::CurveTo(next(), next(), next()),
+                PathEl::ClosePath => PathEl::ClosePath,
+            });
+        }
+
+        start = end;
+    }
+
+    emboldened
+}
+
+/// Return all on- and off-curve points of a contour.
+fn contour_points(contour: &[PathEl]) -> Vec<Point> {
+    let mut points = vec![];
+
+    for el in contour {
+        match el {
+            PathEl::MoveTo(p) | PathEl::LineTo(p) => points.push(*p),
+            PathEl::QuadTo(p1, p2) => points.extend([*p1, *p2]),
+            PathEl::CurveTo(p1, p2, p3) => points.extend([*p1, *p2, *p3]),
+            PathEl::ClosePath => {}
+        }
+    }
+
+    points
+}
+
+/// Shift the points of a closed contour along the bisectors of the normals of
+/// their adjacent segments.
+fn shift_points(points: &mut [Point], strength: f64, counter_clockwise: bool) {
+    let normal = |v: Vec2| {
+        if counter_clockwise {
+            Vec2::new(v.y, -v.x)
+        } else {
+            Vec2::new(-v.y, v.x)
+        }
+    };
+
+    let len = points.len();
+    let original = points.to_vec();
+
+    for (i, point) in points.iter_mut().enumerate() {
+        // Find the closest distinct points before and after the current one.
+        let prev = (1..len)
+            .map(|j| original[(i + len - j) % len])
+            .find(|p| *p != original[i]);
+        let next = (1..len)
+            .map(|j| original[(i + j) % len])
+            .find(|p| *p != original[i]);
+        let (Some(prev), Some(next)) = (prev, next) else {
+            continue;
+        };
+
+        let (d_in, d_out) = (original[i] - prev, next - original[i]);
+        let (l_in, l_out) = (d_in.length(), d_out.length());
+        let (n_in, n_out) = (normal(d_in / l_in), normal(d_out / l_out));
+        let d = n_in.dot(n_out);
+
+        // Don't shift points at very sharp turns, since they would be shifted
+        // way too far.
+        if d <= -0.9375 {
+            continue;
+        }
+
+        let d = d + 1.0;
+        let mut q = n_out.cross(n_in);
+
+        if !counter_clockwise {
+            q = -q;
+        }
+
+        // Restrict the shift in case the adjacent segments are short.
+        let l = l_in.min(l_out);
+        let factor = if strength * q <= l * d {
+            strength / d
+        } else {
+            l / q
+        };
+
+        *point += (n_in + n_out) * factor;
+    }
+}
+
+/// Return the signed area of a path, treating all curves as polygons.
+fn signed_area(elements: &[PathEl]) -> f64 {
+    let mut area = 0.0;
+    let mut points = vec![];
+
+    let mut add_contour = |points: &mut Vec<Point>| {
+        for (i, p) in points.iter().enumerate() {
+            let next = points[(i + 1) % points.len()];
+            area += p.x * next.y - next.x * p.y;
+        }
+
+        points.clear();
+    };
+
+    for el in elements {
+        if matches!(el, PathEl::MoveTo(_)) {
+            add_contour(&mut points);
+        }
+
+        points.extend(contour_points(core::slice::from_ref(el)));
+    }
+
+    add_contour(&mut points);
+
+    area / 2.0
+}